@@ -14,13 +14,13 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::fmt;
 use std::sync::Arc;
 use rustc_serialize::hex::ToHex;
 use mime_guess;
 
 use ethabi::{Interface, Contract, Token};
-use util::{Address, Bytes, Hashable};
+use ethcore::client::Registrar;
+use util::{Address, Bytes};
 
 const COMMIT_LEN: usize = 20;
 
@@ -86,36 +86,26 @@ pub trait URLHint {
 
 pub struct URLHintContract {
 	urlhint: Contract,
-	registrar: Contract,
+	registrar: Registrar,
 	client: Arc<ContractClient>,
 }
 
 impl URLHintContract {
 	pub fn new(client: Arc<ContractClient>) -> Self {
 		let urlhint = Interface::load(include_bytes!("./urlhint.json")).expect("urlhint.json is valid ABI");
-		let registrar = Interface::load(include_bytes!("./registrar.json")).expect("registrar.json is valid ABI");
 
 		URLHintContract {
 			urlhint: Contract::new(urlhint),
-			registrar: Contract::new(registrar),
+			registrar: Registrar::new(),
 			client: client,
 		}
 	}
 
 	fn urlhint_address(&self) -> Option<Address> {
 		let res = || {
-			let get_address = try!(self.registrar.function("getAddress".into()).map_err(as_string));
-			let params = try!(get_address.encode_call(
-					vec![Token::FixedBytes((*"githubhint".sha3()).to_vec()), Token::String("A".into())]
-			).map_err(as_string));
-			let output = try!(self.client.call(try!(self.client.registrar()), params));
-			let result = try!(get_address.decode_output(output).map_err(as_string));
-
-			match result.get(0) {
-				Some(&Token::Address(address)) if address != *Address::default() => Ok(address.into()),
-				Some(&Token::Address(_)) => Err(format!("Contract not found.")),
-				e => Err(format!("Invalid result: {:?}", e)),
-			}
+			let registrar = try!(self.client.registrar());
+			let address = try!(self.registrar.get_address(registrar, "githubhint", |address, data| self.client.call(address, data)));
+			address.ok_or_else(|| "Contract not found.".to_owned())
 		};
 
 		match res() {
@@ -249,10 +239,6 @@ pub fn test_guess_mime_type(url: &str) -> Option<String> {
 	guess_mime_type(url)
 }
 
-fn as_string<T: fmt::Debug>(e: T) -> String {
-	format!("{:?}", e)
-}
-
 #[cfg(test)]
 mod tests {
 	use std::sync::Arc;
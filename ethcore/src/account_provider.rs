@@ -17,14 +17,18 @@
 //! Account management.
 
 use std::{fs, fmt};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::time::{Instant, Duration};
 use util::{Mutex, RwLock};
 use ethstore::{SecretStore, Error as SSError, SafeAccount, EthStore};
 use ethstore::dir::{KeyDirectory};
-use ethstore::ethkey::{Address, Message, Secret, Random, Generator};
+use ethstore::ethkey::{Address, Message, Secret, Random, Generator, Derivation};
 use ethjson::misc::AccountMeta;
+use rustc_serialize::json::Json;
+use hardware_wallet::{HardwareWalletManager, Error as HardwareError};
+use rlp::RlpStream;
+use transaction::Transaction;
 pub use ethstore::ethkey::Signature;
 
 /// Type of unlock.
@@ -46,6 +50,16 @@ struct AccountData {
 	password: String,
 }
 
+impl AccountData {
+	/// Returns `true` if timed unlock has run out.
+	fn is_expired(&self) -> bool {
+		match self.unlock {
+			Unlock::Timed((ref start, ref duration)) => start.elapsed() > Duration::from_millis(*duration as u64),
+			Unlock::Temp | Unlock::Perm => false,
+		}
+	}
+}
+
 /// `AccountProvider` errors.
 #[derive(Debug)]
 pub enum Error {
@@ -53,6 +67,8 @@ pub enum Error {
 	NotUnlocked,
 	/// Returned when signing fails.
 	SStore(SSError),
+	/// Returned when hardware wallet signing fails.
+	Hardware(HardwareError),
 }
 
 impl fmt::Display for Error {
@@ -60,6 +76,7 @@ impl fmt::Display for Error {
 		match *self {
 			Error::NotUnlocked => write!(f, "Account is locked"),
 			Error::SStore(ref e) => write!(f, "{}", e),
+			Error::Hardware(ref e) => write!(f, "{}", e),
 		}
 	}
 }
@@ -153,21 +170,42 @@ impl AddressBook {
 	}
 }
 
+/// Account management settings.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AccountProviderSettings {
+	/// Enable support for hardware wallets attached over USB.
+	pub enable_hardware_wallets: bool,
+}
+
 /// Account management.
 /// Responsible for unlocking accounts.
 pub struct AccountProvider {
 	unlocked: Mutex<HashMap<Address, AccountData>>,
 	sstore: Box<SecretStore>,
 	address_book: Mutex<AddressBook>,
+	hardware_store: Option<HardwareWalletManager>,
 }
 
 impl AccountProvider {
 	/// Creates new account provider.
 	pub fn new(sstore: Box<SecretStore>) -> Self {
+		Self::new_with_settings(sstore, AccountProviderSettings::default())
+	}
+
+	/// Creates new account provider with given settings.
+	pub fn new_with_settings(sstore: Box<SecretStore>, settings: AccountProviderSettings) -> Self {
+		let hardware_store = match settings.enable_hardware_wallets {
+			true => HardwareWalletManager::new()
+				.map_err(|e| warn!("Error initializing hardware wallets: {}", e))
+				.ok(),
+			false => None,
+		};
+
 		AccountProvider {
 			unlocked: Mutex::new(HashMap::new()),
 			address_book: Mutex::new(AddressBook::new(sstore.local_path().into())),
 			sstore: sstore,
+			hardware_store: hardware_store,
 		}
 	}
 
@@ -176,7 +214,8 @@ impl AccountProvider {
 		AccountProvider {
 			unlocked: Mutex::new(HashMap::new()),
 			address_book: Mutex::new(AddressBook::new(Default::default())),
-			sstore: Box::new(EthStore::open(Box::new(NullDir::default())).unwrap())
+			sstore: Box::new(EthStore::open(Box::new(NullDir::default())).unwrap()),
+			hardware_store: None,
 		}
 	}
 
@@ -194,6 +233,20 @@ impl AccountProvider {
 		Ok(address)
 	}
 
+	/// Inserts new account with the key derived along `path` from BIP-32 master key of `seed`.
+	/// Does not unlock account!
+	pub fn insert_account_from_seed(&self, seed: &[u8], path: &[Derivation], password: &str) -> Result<Address, Error> {
+		let address = try!(self.sstore.insert_account_from_seed(seed, path, password));
+		Ok(address)
+	}
+
+	/// Derives new account from existing one along `path`. The new account is stored
+	/// with the same password only if `save` is set.
+	pub fn derive_account(&self, account: &Address, password: String, path: &[Derivation], save: bool) -> Result<Address, Error> {
+		let address = try!(self.sstore.derive_account(account, &password, path, save));
+		Ok(address)
+	}
+
 	/// Import a new presale wallet.
 	pub fn import_presale(&self, presale_json: &[u8], password: &str) -> Result<Address, Error> {
 		let address = try!(self.sstore.import_presale(presale_json, password));
@@ -208,10 +261,31 @@ impl AccountProvider {
 
 	/// Returns addresses of all accounts.
 	pub fn accounts(&self) -> Result<Vec<Address>, Error> {
-		let accounts = try!(self.sstore.accounts());
+		let mut accounts = try!(self.sstore.accounts());
+		accounts.extend(self.hardware_accounts());
 		Ok(accounts)
 	}
 
+	/// Returns addresses of attached hardware wallets.
+	pub fn hardware_accounts(&self) -> Vec<Address> {
+		self.hardware_store.as_ref()
+			.map_or_else(Vec::new, |h| h.list_wallets().into_iter().map(|w| w.address).collect())
+	}
+
+	/// Returns `true` if the address belongs to an attached hardware wallet.
+	pub fn is_hardware_address(&self, address: Address) -> bool {
+		self.hardware_store.as_ref().map_or(false, |h| h.wallet_info(&address).is_some())
+	}
+
+	/// Signs transaction on the hardware wallet holding `address`.
+	/// Blocks until the transaction is confirmed or rejected on the device.
+	pub fn sign_with_hardware(&self, address: Address, transaction: &Transaction) -> Result<Signature, Error> {
+		let manager = try!(self.hardware_store.as_ref().ok_or(Error::Hardware(HardwareError::KeyNotFound)));
+		let mut stream = RlpStream::new();
+		transaction.rlp_append_unsigned_transaction(&mut stream);
+		manager.sign_transaction(&address, &stream.out()).map_err(Error::Hardware)
+	}
+
 	/// Returns each address along with metadata.
 	pub fn addresses_info(&self) -> Result<HashMap<Address, AccountMeta>, Error> {
 		Ok(self.address_book.lock().get())
@@ -229,10 +303,17 @@ impl AccountProvider {
 
 	/// Returns each account along with name and meta.
 	pub fn accounts_info(&self) -> Result<HashMap<Address, AccountMeta>, Error> {
-		let r: HashMap<Address, AccountMeta> = try!(self.sstore.accounts())
+		let mut r: HashMap<Address, AccountMeta> = try!(self.sstore.accounts())
 			.into_iter()
 			.map(|a| (a.clone(), self.account_meta(a).ok().unwrap_or_default()))
 			.collect();
+		if let Some(ref hardware_store) = self.hardware_store {
+			r.extend(hardware_store.list_wallets().into_iter().map(|w| (w.address, AccountMeta {
+				name: w.name,
+				meta: hardware_meta(&w.manufacturer),
+				uuid: None,
+			})));
+		}
 		Ok(r)
 	}
 
@@ -295,10 +376,17 @@ impl AccountProvider {
 		self.unlock_account(account, password, Unlock::Timed((Instant::now(), duration_ms)))
 	}
 
-	/// Checks if given account is unlocked
+	/// Checks if given account is unlocked. Locks the account again if its unlock has expired.
 	pub fn is_unlocked(&self, account: Address) -> bool {
-		let unlocked = self.unlocked.lock();
-		unlocked.get(&account).is_some()
+		let mut unlocked = self.unlocked.lock();
+		let expired = match unlocked.get(&account) {
+			Some(data) => data.is_expired(),
+			None => return false,
+		};
+		if expired {
+			unlocked.remove(&account);
+		}
+		!expired
 	}
 
 	/// Signs the message. Account must be unlocked.
@@ -309,11 +397,9 @@ impl AccountProvider {
 			if let Unlock::Temp = data.unlock {
 				unlocked.remove(&account).expect("data exists: so key must exist: qed");
 			}
-			if let Unlock::Timed((ref start, ref duration)) = data.unlock {
-				if start.elapsed() > Duration::from_millis(*duration as u64) {
-					unlocked.remove(&account).expect("data exists: so key must exist: qed");
-					return Err(Error::NotUnlocked);
-				}
+			if data.is_expired() {
+				unlocked.remove(&account).expect("data exists: so key must exist: qed");
+				return Err(Error::NotUnlocked);
 			}
 			data
 		};
@@ -330,11 +416,9 @@ impl AccountProvider {
 			if let Unlock::Temp = data.unlock {
 				unlocked.remove(&account).expect("data exists: so key must exist: qed");
 			}
-			if let Unlock::Timed((ref start, ref duration)) = data.unlock {
-				if start.elapsed() > Duration::from_millis(*duration as u64) {
-					unlocked.remove(&account).expect("data exists: so key must exist: qed");
-					return Err(Error::NotUnlocked);
-				}
+			if data.is_expired() {
+				unlocked.remove(&account).expect("data exists: so key must exist: qed");
+				return Err(Error::NotUnlocked);
 			}
 			data
 		};
@@ -348,6 +432,11 @@ impl AccountProvider {
 		Ok(signature)
 	}
 
+	/// Decrypts a message with given password. Account does not need to be unlocked.
+	pub fn decrypt_with_password(&self, account: Address, password: String, shared_mac: &[u8], message: &[u8]) -> Result<Vec<u8>, Error> {
+		Ok(try!(self.sstore.decrypt(&account, &password, shared_mac, message)))
+	}
+
 	/// Returns the underlying `SecretStore` reference if one exists.
 	pub fn list_geth_accounts(&self, testnet: bool) -> Vec<Address> {
 		self.sstore.list_geth_accounts(testnet).into_iter().map(|a| Address::from(a).into()).collect()
@@ -357,14 +446,73 @@ impl AccountProvider {
 	pub fn import_geth_accounts(&self, desired: Vec<Address>, testnet: bool) -> Result<Vec<Address>, Error> {
 		self.sstore.import_geth_accounts(desired, testnet).map_err(Into::into)
 	}
+
+	/// Creates new vault protected with `password` and opens it.
+	pub fn create_vault(&self, name: &str, password: &str) -> Result<(), Error> {
+		self.sstore.create_vault(name, password).map_err(Into::into)
+	}
+
+	/// Opens existing vault.
+	pub fn open_vault(&self, name: &str, password: &str) -> Result<(), Error> {
+		self.sstore.open_vault(name, password).map_err(Into::into)
+	}
+
+	/// Closes opened vault. Accounts stored in the vault are locked.
+	pub fn close_vault(&self, name: &str) -> Result<(), Error> {
+		try!(self.sstore.close_vault(name));
+		let available = try!(self.sstore.accounts());
+		let mut unlocked = self.unlocked.lock();
+		let closed: Vec<Address> = unlocked.keys().filter(|address| !available.contains(address)).cloned().collect();
+		for address in closed {
+			unlocked.remove(&address);
+		}
+		Ok(())
+	}
+
+	/// Lists all vaults.
+	pub fn list_vaults(&self) -> Result<Vec<String>, Error> {
+		self.sstore.list_vaults().map_err(Into::into)
+	}
+
+	/// Lists opened vaults.
+	pub fn list_opened_vaults(&self) -> Result<Vec<String>, Error> {
+		self.sstore.list_opened_vaults().map_err(Into::into)
+	}
+
+	/// Moves account to an opened vault, or back to the main keys directory if `vault` is `None`.
+	/// The account is locked, as it is encrypted with the vault password afterwards.
+	pub fn change_vault(&self, account: Address, password: &str, vault: Option<&str>) -> Result<(), Error> {
+		try!(self.sstore.change_account_vault(&account, password, vault));
+		self.unlocked.lock().remove(&account);
+		Ok(())
+	}
+
+	/// Returns vault metadata.
+	pub fn get_vault_meta(&self, name: &str) -> Result<String, Error> {
+		self.sstore.get_vault_meta(name).map_err(Into::into)
+	}
+
+	/// Sets metadata of an opened vault.
+	pub fn set_vault_meta(&self, name: &str, meta: &str) -> Result<(), Error> {
+		self.sstore.set_vault_meta(name, meta).map_err(Into::into)
+	}
+}
+
+/// Metadata of hardware wallet accounts.
+fn hardware_meta(manufacturer: &str) -> String {
+	let mut meta = BTreeMap::new();
+	meta.insert("hardware".to_owned(), Json::String(manufacturer.to_owned()));
+	Json::Object(meta).to_string()
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{AccountProvider, AddressBook};
+	use super::{AccountProvider, AccountProviderSettings, AddressBook, Error, hardware_meta};
+	use ethstore::EthStore;
+	use ethstore::dir::DiskDirectory;
 	use std::collections::HashMap;
 	use ethjson::misc::AccountMeta;
-	use ethstore::ethkey::{Generator, Random};
+	use ethstore::ethkey::{Generator, Random, Derivation};
 	use std::time::Duration;
 	use devtools::RandomTempPath;
 
@@ -379,6 +527,37 @@ mod tests {
 		assert_eq!(b.get(), hash_map![1.into() => AccountMeta{name: "One".to_owned(), meta: "{1:1}".to_owned(), uuid: None}]);
 	}
 
+	#[test]
+	fn should_lock_vault_accounts_when_vault_is_closed() {
+		let temp = RandomTempPath::create_dir();
+		let dir = DiskDirectory::create(temp.as_path()).unwrap();
+		let ap = AccountProvider::new(Box::new(EthStore::open(Box::new(dir)).unwrap()));
+		let kp = Random.generate().unwrap();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.create_vault("vault", "vault_pass").is_ok());
+		assert!(ap.change_vault(kp.address(), "test", Some("vault")).is_ok());
+		assert!(ap.unlock_account_permanently(kp.address(), "vault_pass".into()).is_ok());
+		assert!(ap.sign(kp.address(), Default::default()).is_ok());
+
+		assert!(ap.close_vault("vault").is_ok());
+		assert!(ap.sign(kp.address(), Default::default()).is_err());
+		assert_eq!(ap.accounts().unwrap(), vec![]);
+		assert_eq!(ap.list_vaults().unwrap(), vec!["vault".to_owned()]);
+		assert_eq!(ap.list_opened_vaults().unwrap(), Vec::<String>::new());
+	}
+
+	#[test]
+	fn should_derive_accounts() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		let path = [Derivation::Soft(0), Derivation::Hard(1)];
+		let derived = ap.derive_account(&kp.address(), "test".into(), &path, false).unwrap();
+		assert!(ap.accounts().unwrap() == vec![kp.address()]);
+		assert_eq!(ap.derive_account(&kp.address(), "test".into(), &path, true).unwrap(), derived);
+		assert!(ap.sign_with_password(derived, "test".into(), Default::default()).is_ok());
+	}
+
 	#[test]
 	fn unlock_account_temp() {
 		let kp = Random.generate().unwrap();
@@ -415,4 +594,40 @@ mod tests {
 		::std::thread::sleep(Duration::from_millis(2000));
 		assert!(ap.sign(kp.address(), Default::default()).is_err());
 	}
+
+	#[test]
+	fn timed_unlock_should_expire() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_timed(kp.address(), "test".into(), 100).is_ok());
+		assert!(ap.is_unlocked(kp.address()));
+		::std::thread::sleep(Duration::from_millis(200));
+		assert!(!ap.is_unlocked(kp.address()));
+	}
+
+	#[test]
+	fn should_not_enable_hardware_wallets_by_default() {
+		assert_eq!(AccountProviderSettings::default().enable_hardware_wallets, false);
+
+		let temp = RandomTempPath::create_dir();
+		let dir = DiskDirectory::create(temp.as_path()).unwrap();
+		let ap = AccountProvider::new(Box::new(EthStore::open(Box::new(dir)).unwrap()));
+		let kp = Random.generate().unwrap();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+
+		assert!(ap.hardware_accounts().is_empty());
+		assert!(!ap.is_hardware_address(kp.address()));
+		assert_eq!(ap.accounts().unwrap(), vec![kp.address()]);
+		match ap.sign_with_hardware(kp.address(), &Default::default()) {
+			Err(Error::Hardware(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_escape_hardware_meta() {
+		assert_eq!(hardware_meta("Ledger"), r#"{"hardware":"Ledger"}"#);
+		assert_eq!(hardware_meta("\"Quoted\" \\ Ltd."), r#"{"hardware":"\"Quoted\" \\ Ltd."}"#);
+	}
 }
@@ -0,0 +1,177 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Replays a synthetic state access trace against the shared state cache of `StateDB`
+//! in several configurations and reports hit rates and the number of database reads
+//! each of them causes. The trace has a skewed access distribution, so that a small set
+//! of accounts gets most of the accesses.
+
+use std::time::Instant;
+use util::{Address, H256, Arc, Database, DBTransaction};
+use util::journaldb::{self, Algorithm};
+use state::Account;
+use state_db::{StateDB, StateDbConfig, STATE_CACHE_ITEMS};
+use db::{COL_STATE, NUM_COLUMNS};
+use super::test::Bencher;
+
+/// Single entry of an access trace.
+enum Access {
+	Account(Address),
+	Storage(Address, H256),
+}
+
+/// Accesses made by each block of the trace.
+fn synthetic_trace() -> Vec<Vec<Access>> {
+	const ACCOUNTS: u64 = 50_000;
+	const BLOCKS: u64 = 500;
+	const ACCESSES_PER_BLOCK: u64 = 400;
+
+	let mut seed = 0x2545f4914f6cdd1du64;
+	let mut next = move || {
+		seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+		seed >> 33
+	};
+
+	(0..BLOCKS).map(|_| (0..ACCESSES_PER_BLOCK).map(|_| {
+		// squaring a uniform sample skews it towards low account indices.
+		let r = next() % 65536;
+		let account = Address::from(r * r * ACCOUNTS / (65536 * 65536));
+		match next() % 10 {
+			0...2 => Access::Storage(account, H256::from(next() % 32)),
+			_ => Access::Account(account),
+		}
+	}).collect()).collect()
+}
+
+/// Cache configuration to evaluate.
+struct CachePolicy {
+	name: &'static str,
+	/// Number of cached accounts.
+	items: usize,
+	/// Number of most accessed contracts pinned in the cache.
+	hot_contracts: usize,
+}
+
+impl CachePolicy {
+	fn state_db(&self) -> StateDB {
+		let db = Arc::new(Database::in_memory(NUM_COLUMNS));
+		let config = StateDbConfig { hot_contracts: self.hot_contracts, ..Default::default() };
+		let cache_size = self.items * StateDB::default_cache_size() / STATE_CACHE_ITEMS;
+		StateDB::with_config(journaldb::new(db, Algorithm::Archive, COL_STATE), cache_size, &config)
+	}
+}
+
+#[derive(Default)]
+struct Stats {
+	blocks: u64,
+	account_hits: u64,
+	account_misses: u64,
+	storage_hits: u64,
+	storage_misses: u64,
+}
+
+impl Stats {
+	fn hit_rate(hits: u64, misses: u64) -> f64 {
+		match hits + misses {
+			0 => 0.0,
+			total => hits as f64 * 100.0 / total as f64,
+		}
+	}
+}
+
+fn replay(trace: &[Vec<Access>], policy: &CachePolicy) -> Stats {
+	let state_db = policy.state_db();
+	let mut batch = DBTransaction::new(state_db.journal_db().backing());
+	let mut parent = H256::new();
+	let mut stats = Stats::default();
+
+	for (number, accesses) in trace.iter().enumerate() {
+		let mut db = state_db.boxed_clone_canon(&parent);
+		for access in accesses {
+			let (address, key) = match *access {
+				Access::Account(ref address) => (address, None),
+				Access::Storage(ref address, ref key) => (address, Some(key)),
+			};
+
+			if db.get_cached_account(address).is_some() {
+				stats.account_hits += 1;
+			} else {
+				stats.account_misses += 1;
+				let account = Account::new_basic(0.into(), 0.into());
+				if let Some(key) = key {
+					account.storage_at(db.as_hashdb(), key);
+					stats.storage_misses += 1;
+				}
+				db.add_to_account_cache(address.clone(), Some(account), false);
+				continue;
+			}
+
+			if let Some(key) = key {
+				let hashdb = db.as_hashdb();
+				let hit = db.get_cached(address, |account| account.map_or(true, |account| {
+					account.cached_storage_at(key).is_some() || {
+						account.storage_at(hashdb, key);
+						false
+					}
+				}));
+				if hit == Some(true) {
+					stats.storage_hits += 1;
+				} else {
+					stats.storage_misses += 1;
+				}
+			}
+		}
+
+		let hash = H256::from(number as u64 + 1);
+		db.commit(&mut batch, number as u64, &hash, None).expect("in-memory commit does not fail; qed");
+		db.sync_cache(&[], &[], true);
+		parent = hash;
+		stats.blocks += 1;
+	}
+	stats
+}
+
+fn policies() -> Vec<CachePolicy> {
+	vec![
+		CachePolicy { name: "default", items: STATE_CACHE_ITEMS, hot_contracts: StateDbConfig::default().hot_contracts },
+		CachePolicy { name: "small", items: STATE_CACHE_ITEMS / 8, hot_contracts: StateDbConfig::default().hot_contracts },
+		CachePolicy { name: "large", items: STATE_CACHE_ITEMS * 4, hot_contracts: StateDbConfig::default().hot_contracts },
+		CachePolicy { name: "no hot", items: STATE_CACHE_ITEMS, hot_contracts: 0 },
+	]
+}
+
+#[bench]
+fn state_cache_policies(b: &mut Bencher) {
+	let trace = synthetic_trace();
+
+	println!("{:<12}{:>10}{:>12}{:>12}{:>12}{:>10}", "policy", "blocks", "account %", "storage %", "db reads", "ms");
+	for policy in &policies() {
+		let start = Instant::now();
+		let stats = replay(&trace, policy);
+		let elapsed = start.elapsed();
+		println!("{:<12}{:>10}{:>12.2}{:>12.2}{:>12}{:>10}",
+			policy.name,
+			stats.blocks,
+			Stats::hit_rate(stats.account_hits, stats.account_misses),
+			Stats::hit_rate(stats.storage_hits, stats.storage_misses),
+			stats.account_misses + stats.storage_misses,
+			elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000
+		);
+	}
+
+	let default = &policies()[0];
+	b.iter(|| replay(&trace, default));
+}
@@ -218,12 +218,11 @@ pub struct ClosedBlock {
 	block: ExecutedBlock,
 	uncle_bytes: Bytes,
 	last_hashes: Arc<LastHashes>,
-	unclosed_state: State,
 }
 
 /// Just like `ClosedBlock` except that we can't reopen it and it's faster.
 ///
-/// We actually store the post-`Engine::on_close_block` state, unlike in `ClosedBlock` where it's the pre.
+/// We actually store the post-`Engine::on_close_block` state, unlike in `ClosedBlock` where it can still be reverted to the pre.
 #[derive(Clone)]
 pub struct LockedBlock {
 	block: ExecutedBlock,
@@ -357,10 +356,11 @@ impl<'x> OpenBlock<'x> {
 	}
 
 	/// Turn this into a `ClosedBlock`.
-	pub fn close(self) -> ClosedBlock {
+	pub fn close(self) -> Result<ClosedBlock, Error> {
 		let mut s = self;
 
-		let unclosed_state = s.block.state.clone();
+		// rewards are applied on top of a checkpoint, so they can be reverted on `reopen`.
+		try!(s.block.state.checkpoint());
 
 		s.engine.on_close_block(&mut s.block);
 		s.block.base.header.set_transactions_root(ordered_trie_root(s.block.base.transactions.iter().map(|e| e.rlp_bytes().to_vec())));
@@ -371,12 +371,11 @@ impl<'x> OpenBlock<'x> {
 		s.block.base.header.set_log_bloom(s.block.receipts.iter().fold(LogBloom::zero(), |mut b, r| {b = &b | &r.log_bloom; b})); //TODO: use |= operator
 		s.block.base.header.set_gas_used(s.block.receipts.last().map_or(U256::zero(), |r| r.gas_used));
 
-		ClosedBlock {
+		Ok(ClosedBlock {
 			block: s.block,
 			uncle_bytes: uncle_bytes,
 			last_hashes: s.last_hashes,
-			unclosed_state: unclosed_state,
-		}
+		})
 	}
 
 	/// Turn this into a `LockedBlock`.
@@ -424,8 +423,10 @@ impl ClosedBlock {
 
 	/// Turn this into a `LockedBlock`, unable to be reopened again.
 	pub fn lock(self) -> LockedBlock {
+		let mut block = self.block;
+		block.state.discard_checkpoint();
 		LockedBlock {
-			block: self.block,
+			block: block,
 			uncle_bytes: self.uncle_bytes,
 		}
 	}
@@ -434,13 +435,22 @@ impl ClosedBlock {
 	pub fn reopen(self, engine: &Engine) -> OpenBlock {
 		// revert rewards (i.e. set state back at last transaction's state).
 		let mut block = self.block;
-		block.state = self.unclosed_state;
+		block.state.revert_to_checkpoint();
 		OpenBlock {
 			block: block,
 			engine: engine,
 			last_hashes: self.last_hashes,
 		}
 	}
+
+	/// Run `f` on top of the block's state and revert all changes it made afterwards,
+	/// so transactions can be executed speculatively without cloning the state.
+	pub fn speculate<F, T>(&mut self, f: F) -> Result<T, Error> where F: FnOnce(&mut State) -> T {
+		try!(self.block.state.checkpoint());
+		let result = f(&mut self.block.state);
+		self.block.state.revert_to_checkpoint();
+		Ok(result)
+	}
 }
 
 impl LockedBlock {
@@ -31,12 +31,23 @@ use blockchain::best_block::BestBlock;
 use types::tree_route::TreeRoute;
 use blockchain::update::ExtrasUpdate;
 use blockchain::{CacheSize, ImportRoute, Config};
-use db::{self, Writable, Readable, CacheUpdatePolicy};
+use db::{self, Writable, Readable, Key, CacheUpdatePolicy};
 use cache_manager::CacheManager;
 
 const LOG_BLOOMS_LEVELS: usize = 3;
 const LOG_BLOOMS_ELEMENTS_PER_INDEX: usize = 16;
 
+/// Key of the number of the last block moved to the ancient database.
+const ANCIENT_KEY: &'static [u8] = b"ancient";
+/// Key marking databases which only store headers, see `Config::store_bodies`.
+const HEADERS_ONLY_KEY: &'static [u8] = b"headers_only";
+/// Key of the number of Canonical Hash Tries generated so far.
+const CHT_COUNT_KEY: &'static [u8] = b"cht_count";
+/// Number of blocks covered by a single Canonical Hash Trie.
+pub const CHT_SIZE: u64 = 2048;
+/// Number of blocks a CHT section must be behind the best block before its trie is generated.
+const CHT_CONFIRMATIONS: u64 = 256;
+
 /// Interface for querying blocks by hash and by number.
 pub trait BlockProvider {
 	/// Returns true if the given block is known
@@ -138,6 +149,7 @@ pub trait BlockProvider {
 enum CacheID {
 	BlockHeader(H256),
 	BlockBody(H256),
+	BlockUncles(H256),
 	BlockDetails(H256),
 	BlockHashes(BlockNumber),
 	TransactionAddresses(H256),
@@ -167,6 +179,7 @@ pub struct BlockChain {
 	// block cache
 	block_headers: RwLock<HashMap<H256, Bytes>>,
 	block_bodies: RwLock<HashMap<H256, Bytes>>,
+	block_uncles: RwLock<HashMap<H256, Vec<Bytes>>>,
 
 	// extra caches
 	block_details: RwLock<HashMap<H256, BlockDetails>>,
@@ -176,6 +189,10 @@ pub struct BlockChain {
 	block_receipts: RwLock<HashMap<H256, BlockReceipts>>,
 
 	db: Arc<Database>,
+	/// Secondary database holding bodies and receipts of old blocks.
+	ancient: Option<Arc<Database>>,
+	ancient_horizon: u64,
+	store_bodies: bool,
 
 	cache_man: Mutex<CacheManager<CacheID>>,
 
@@ -265,18 +282,10 @@ impl BlockProvider for BlockChain {
 		}
 
 		// Read from DB and populate cache
-		let opt = self.db.get(db::COL_BODIES, hash)
-			.expect("Low level database error. Some issue with disk?");
-
-		let result = match opt {
-			Some(b) => {
-				let bytes: Bytes = UntrustedRlp::new(&b).decompress(RlpType::Blocks).to_vec();
-				let mut write = self.block_bodies.write();
-				write.insert(hash.clone(), bytes.clone());
-				Some(bytes)
-			},
-			None => None
-		};
+		let result = self.read_block_body(hash);
+		if let Some(ref bytes) = result {
+			self.block_bodies.write().insert(hash.clone(), bytes.clone());
+		}
 
 		self.cache_man.lock().note_used(CacheID::BlockBody(hash.clone()));
 
@@ -306,7 +315,8 @@ impl BlockProvider for BlockChain {
 
 	/// Get receipts of block with given hash.
 	fn block_receipts(&self, hash: &H256) -> Option<BlockReceipts> {
-		let result = self.db.read_with_cache(db::COL_EXTRA, &self.block_receipts, hash);
+		let result = self.db.read_with_cache(db::COL_EXTRA, &self.block_receipts, hash)
+			.or_else(|| self.ancient.as_ref().and_then(|ancient| ancient.read(db::COL_EXTRA, hash)));
 		self.cache_man.lock().note_used(CacheID::BlockReceipts(hash.clone()));
 		result
 	}
@@ -389,8 +399,13 @@ impl<'a> Iterator for AncestryIter<'a> {
 impl BlockChain {
 	/// Create new instance of blockchain from given Genesis
 	pub fn new(config: Config, genesis: &[u8], db: Arc<Database>) -> BlockChain {
-		// 400 is the avarage size of the key
-		let cache_man = CacheManager::new(config.pref_cache_size, config.max_cache_size, 400);
+		Self::with_ancient(config, genesis, db, None)
+	}
+
+	/// Create new instance of blockchain from given Genesis, keeping bodies and receipts
+	/// of blocks older than the configured horizon in the `ancient` database.
+	pub fn with_ancient(config: Config, genesis: &[u8], db: Arc<Database>, ancient: Option<Arc<Database>>) -> BlockChain {
+		let cache_man = CacheManager::new(config.pref_cache_size, config.max_cache_size);
 
 		let mut bc = BlockChain {
 			blooms_config: bc::Config {
@@ -401,12 +416,16 @@ impl BlockChain {
 			best_block: RwLock::new(BestBlock::default()),
 			block_headers: RwLock::new(HashMap::new()),
 			block_bodies: RwLock::new(HashMap::new()),
+			block_uncles: RwLock::new(HashMap::new()),
 			block_details: RwLock::new(HashMap::new()),
 			block_hashes: RwLock::new(HashMap::new()),
 			transaction_addresses: RwLock::new(HashMap::new()),
 			blocks_blooms: RwLock::new(HashMap::new()),
 			block_receipts: RwLock::new(HashMap::new()),
 			db: db.clone(),
+			ancient: ancient,
+			ancient_horizon: config.ancient_horizon,
+			store_bodies: config.store_bodies,
 			cache_man: Mutex::new(cache_man),
 			pending_best_block: RwLock::new(None),
 			pending_block_hashes: RwLock::new(HashMap::new()),
@@ -441,6 +460,9 @@ impl BlockChain {
 
 				batch.put(db::COL_EXTRA, b"best", &hash);
 				batch.put(db::COL_EXTRA, b"first", &hash);
+				if !config.store_bodies {
+					batch.put(db::COL_EXTRA, HEADERS_ONLY_KEY, &[1]);
+				}
 				bc.db.write(batch).expect("Low level database error. Some issue with disk?");
 				hash
 			}
@@ -450,7 +472,10 @@ impl BlockChain {
 			// Fetch best block details
 			let best_block_number = bc.block_number(&best_block_hash).unwrap();
 			let best_block_total_difficulty = bc.block_details(&best_block_hash).unwrap().total_difficulty;
-			let best_block_rlp = bc.block(&best_block_hash).unwrap();
+			// without stored bodies the best block is kept with an empty body.
+			let best_block_rlp = bc.block(&best_block_hash)
+				.or_else(|| bc.block_header_data(&best_block_hash).map(|header| Self::header_only_block(&header)))
+				.unwrap();
 
 			let raw_first = bc.db.get(db::COL_EXTRA, b"first").unwrap().map_or(Vec::new(), |v| v.to_vec());
 
@@ -542,6 +567,7 @@ impl BlockChain {
 				self.block_hashes.write().clear();
 				self.block_headers.write().clear();
 				self.block_bodies.write().clear();
+				self.block_uncles.write().clear();
 				self.block_receipts.write().clear();
 				return Some(hash);
 			}
@@ -767,7 +793,9 @@ impl BlockChain {
 
 		// store block in db
 		batch.put_compressed(db::COL_HEADERS, &hash, block.header_rlp().as_raw().to_vec());
-		batch.put_compressed(db::COL_BODIES, &hash, Self::block_to_body(bytes));
+		if self.store_bodies {
+			batch.put_compressed(db::COL_BODIES, &hash, Self::block_to_body(bytes));
+		}
 
 		let info = self.block_info(&header);
 
@@ -780,12 +808,18 @@ impl BlockChain {
 			);
 		}
 
+		let (block_receipts, transactions_addresses) = if self.store_bodies {
+			(self.prepare_block_receipts_update(receipts, &info), self.prepare_transaction_addresses_update(bytes, &info))
+		} else {
+			(HashMap::new(), HashMap::new())
+		};
+
 		self.prepare_update(batch, ExtrasUpdate {
 			block_hashes: self.prepare_block_hashes_update(bytes, &info),
 			block_details: self.prepare_block_details_update(bytes, &info),
-			block_receipts: self.prepare_block_receipts_update(receipts, &info),
+			block_receipts: block_receipts,
 			blocks_blooms: self.prepare_block_blooms_update(bytes, &info),
-			transactions_addresses: self.prepare_transaction_addresses_update(bytes, &info),
+			transactions_addresses: transactions_addresses,
 			info: info.clone(),
 			block: bytes,
 		}, true);
@@ -931,6 +965,47 @@ impl BlockChain {
 		}
 	}
 
+	/// Read the body of the block with given hash from the database, bypassing the caches.
+	fn read_block_body(&self, hash: &H256) -> Option<Bytes> {
+		let opt = match self.db.get(db::COL_BODIES, hash).expect("Low level database error. Some issue with disk?") {
+			Some(b) => Some(b),
+			None => self.ancient.as_ref().and_then(|ancient| ancient.get(db::COL_BODIES, hash)
+				.expect("Low level database error. Some issue with disk?")),
+		};
+		opt.map(|b| UntrustedRlp::new(&b).decompress(RlpType::Blocks).to_vec())
+	}
+
+	/// Get the RLP of the uncle at `index` in the block with given hash.
+	/// Uncles of a block are decoded once and cached, since a block's body never changes.
+	/// The body they are decoded from is not cached on its own.
+	pub fn uncle(&self, hash: &H256, index: usize) -> Option<Bytes> {
+		// Check cache first
+		{
+			let read = self.block_uncles.read();
+			if let Some(uncles) = read.get(hash) {
+				return uncles.get(index).cloned();
+			}
+		}
+
+		let cached = self.block_bodies.read().get(hash).cloned();
+		let body = cached.or_else(|| {
+			let best_block = self.best_block.read();
+			if &best_block.hash == hash {
+				Some(Self::block_to_body(&best_block.block))
+			} else {
+				None
+			}
+		}).or_else(|| self.read_block_body(hash));
+		let uncles: Vec<Bytes> = match body {
+			Some(body) => BodyView::new(&body).rlp().at(1).iter().map(|uncle| uncle.as_raw().to_vec()).collect(),
+			None => return None,
+		};
+		let result = uncles.get(index).cloned();
+		self.block_uncles.write().insert(hash.clone(), uncles);
+		self.cache_man.lock().note_used(CacheID::BlockUncles(hash.clone()));
+		result
+	}
+
 	/// Given a block's `parent`, find every block header which represents a valid possible uncle.
 	pub fn find_uncle_headers(&self, parent: &H256, uncle_generations: usize) -> Option<Vec<Header>> {
 		self.find_uncle_hashes(parent, uncle_generations).map(|v| v.into_iter().filter_map(|h| self.block_header(&h)).collect())
@@ -1147,20 +1222,165 @@ impl BlockChain {
 	/// Get current cache size.
 	pub fn cache_size(&self) -> CacheSize {
 		CacheSize {
-			blocks: self.block_headers.read().heap_size_of_children() + self.block_bodies.read().heap_size_of_children(),
+			blocks: self.block_headers.read().heap_size_of_children() + self.block_bodies.read().heap_size_of_children() +
+				self.block_uncles.read().heap_size_of_children(),
 			block_details: self.block_details.read().heap_size_of_children(),
+			block_hashes: self.block_hashes.read().heap_size_of_children(),
 			transaction_addresses: self.transaction_addresses.read().heap_size_of_children(),
 			blocks_blooms: self.blocks_blooms.read().heap_size_of_children(),
 			block_receipts: self.block_receipts.read().heap_size_of_children(),
 		}
 	}
 
+	/// Returns true if bodies and receipts of some blocks in `db` were moved to an ancient database.
+	pub fn has_ancient_blocks(db: &Database) -> bool {
+		db.get(db::COL_EXTRA, ANCIENT_KEY).expect("Low level database error. Some issue with disk?").is_some()
+	}
+
+	/// Whether the chain stored in `db` keeps block bodies, `None` if there is no chain yet.
+	/// The setting of a database can't be changed once it's created.
+	pub fn stores_bodies(db: &Database) -> Option<bool> {
+		let get = |key: &[u8]| db.get(db::COL_EXTRA, key).expect("Low level database error. Some issue with disk?");
+		get(b"best").map(|_| get(HEADERS_ONLY_KEY).is_none())
+	}
+
+	/// Moves bodies and receipts of canonical blocks older than the ancient horizon
+	/// to the ancient database, at most `max_blocks` at a time.
+	/// Returns the number of blocks moved.
+	pub fn move_ancient_blocks(&self, max_blocks: u64) -> usize {
+		let ancient = match self.ancient {
+			Some(ref ancient) => ancient,
+			None => return 0,
+		};
+
+		let best_block_number = self.best_block_number();
+		if max_blocks == 0 || best_block_number <= self.ancient_horizon {
+			return 0;
+		}
+
+		// the genesis block always stays in the main database.
+		let first = self.db.get(db::COL_EXTRA, ANCIENT_KEY).expect("Low level database error. Some issue with disk?")
+			.map_or(1, |n| decode::<BlockNumber>(&n) + 1);
+		let last = cmp::min(best_block_number - self.ancient_horizon, first + max_blocks - 1);
+		if first > last {
+			return 0;
+		}
+
+		let mut ancient_batch = ancient.transaction();
+		let mut batch = self.db.transaction();
+		let mut moved = 0;
+		for number in first..last + 1 {
+			let hash = match self.block_hash(number) {
+				Some(hash) => hash,
+				None => break,
+			};
+
+			let receipts_key = Key::<BlockReceipts>::key(&hash);
+			if let Some(body) = self.db.get(db::COL_BODIES, &hash).expect("Low level database error. Some issue with disk?") {
+				ancient_batch.put(db::COL_BODIES, &hash, &body);
+				batch.delete(db::COL_BODIES, &hash);
+			}
+			if let Some(receipts) = self.db.get(db::COL_EXTRA, &receipts_key).expect("Low level database error. Some issue with disk?") {
+				ancient_batch.put(db::COL_EXTRA, &receipts_key, &receipts);
+				batch.delete(db::COL_EXTRA, &receipts_key);
+			}
+			moved += 1;
+		}
+
+		// data is written to the ancient database first, so that nothing is lost if we are interrupted.
+		ancient.write(ancient_batch).expect("Low level database error. Some issue with disk?");
+		batch.put(db::COL_EXTRA, ANCIENT_KEY, &encode(&(first + moved as u64 - 1)));
+		self.db.write(batch).expect("Low level database error. Some issue with disk?");
+
+		trace!(target: "blockchain", "Moved {} blocks to the ancient database", moved);
+		moved
+	}
+
+	/// Generates the Canonical Hash Tries of sections which are at least `CHT_CONFIRMATIONS`
+	/// blocks behind the best block, at most `max_tries` at a time. Tries are only kept
+	/// when bodies are not stored. Returns the number of tries generated.
+	///
+	/// Each trie maps the numbers of the section's `CHT_SIZE` blocks to their canonical
+	/// hashes and total difficulties.
+	pub fn update_chts(&self, max_tries: u64) -> usize {
+		if self.store_bodies {
+			return 0;
+		}
+
+		let count = self.cht_count();
+		let available = (self.best_block_number() + 1).saturating_sub(CHT_CONFIRMATIONS) / CHT_SIZE;
+		let mut batch = self.db.transaction();
+		let mut generated = 0;
+		for index in count..cmp::min(available, count + max_tries) {
+			let root = match self.compute_cht_root(index) {
+				Some(root) => root,
+				None => break,
+			};
+			batch.put(db::COL_EXTRA, &Self::cht_key(index), &root);
+			generated += 1;
+		}
+
+		if generated > 0 {
+			batch.put(db::COL_EXTRA, CHT_COUNT_KEY, &encode(&(count + generated as u64)));
+			self.db.write(batch).expect("Low level database error. Some issue with disk?");
+			trace!(target: "blockchain", "Generated {} Canonical Hash Tries", generated);
+		}
+		generated
+	}
+
+	/// Number of Canonical Hash Tries generated so far.
+	pub fn cht_count(&self) -> u64 {
+		self.db.get(db::COL_EXTRA, CHT_COUNT_KEY).expect("Low level database error. Some issue with disk?")
+			.map_or(0, |n| decode::<u64>(&n))
+	}
+
+	/// Root of the Canonical Hash Trie covering blocks `index * CHT_SIZE .. (index + 1) * CHT_SIZE`.
+	pub fn cht_root(&self, index: u64) -> Option<H256> {
+		self.db.get(db::COL_EXTRA, &Self::cht_key(index)).expect("Low level database error. Some issue with disk?")
+			.map(|root| H256::from_slice(&root))
+	}
+
+	fn compute_cht_root(&self, index: u64) -> Option<H256> {
+		let mut items = Vec::with_capacity(CHT_SIZE as usize);
+		for number in index * CHT_SIZE..(index + 1) * CHT_SIZE {
+			let hash = match self.block_hash(number) {
+				Some(hash) => hash,
+				None => return None,
+			};
+			let details = match self.block_details(&hash) {
+				Some(details) => details,
+				None => return None,
+			};
+
+			let mut value = RlpStream::new_list(2);
+			value.append(&hash).append(&details.total_difficulty);
+			items.push((encode(&number).to_vec(), value.out()));
+		}
+		Some(trie_root(items))
+	}
+
+	fn cht_key(index: u64) -> Vec<u8> {
+		let mut key = b"cht".to_vec();
+		key.extend_from_slice(&encode(&index));
+		key
+	}
+
+	/// Block with the given header and no transactions or uncles.
+	fn header_only_block(header: &[u8]) -> Bytes {
+		let mut block = RlpStream::new_list(3);
+		block.append_raw(header, 1);
+		block.begin_list(0);
+		block.begin_list(0);
+		block.out()
+	}
+
 	/// Ticks our cache system and throws out any old data.
 	pub fn collect_garbage(&self) {
 		let current_size = self.cache_size().total();
 
 		let mut block_headers = self.block_headers.write();
 		let mut block_bodies = self.block_bodies.write();
+		let mut block_uncles = self.block_uncles.write();
 		let mut block_details = self.block_details.write();
 		let mut block_hashes = self.block_hashes.write();
 		let mut transaction_addresses = self.transaction_addresses.write();
@@ -1173,6 +1393,7 @@ impl BlockChain {
 				match *id {
 					CacheID::BlockHeader(ref h) => { block_headers.remove(h); },
 					CacheID::BlockBody(ref h) => { block_bodies.remove(h); },
+					CacheID::BlockUncles(ref h) => { block_uncles.remove(h); },
 					CacheID::BlockDetails(ref h) => { block_details.remove(h); }
 					CacheID::BlockHashes(ref h) => { block_hashes.remove(h); }
 					CacheID::TransactionAddresses(ref h) => { transaction_addresses.remove(h); }
@@ -1183,6 +1404,7 @@ impl BlockChain {
 
 			block_headers.shrink_to_fit();
 			block_bodies.shrink_to_fit();
+			block_uncles.shrink_to_fit();
 			block_details.shrink_to_fit();
 			block_hashes.shrink_to_fit();
 			transaction_addresses.shrink_to_fit();
@@ -1191,6 +1413,7 @@ impl BlockChain {
 
 			block_headers.heap_size_of_children() +
 			block_bodies.heap_size_of_children() +
+			block_uncles.heap_size_of_children() +
 			block_details.heap_size_of_children() +
 			block_hashes.heap_size_of_children() +
 			transaction_addresses.heap_size_of_children() +
@@ -1224,8 +1447,11 @@ mod tests {
 	use blockchain::generator::{ChainGenerator, ChainIterator, BlockFinalizer};
 	use blockchain::extras::TransactionAddress;
 	use views::BlockView;
+	use header::Header;
+	use rlp::encode;
 	use transaction::{Transaction, Action};
 	use log_entry::{LogEntry, LocalizedLogEntry};
+	use super::{CHT_SIZE, CHT_CONFIRMATIONS};
 
 	fn new_db(path: &str) -> Arc<Database> {
 		Arc::new(Database::open(&DatabaseConfig::with_columns(::db::NUM_COLUMNS), path).unwrap())
@@ -1679,6 +1905,88 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn moves_ancient_blocks() {
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+
+		let temp = RandomTempPath::new();
+		let ancient_temp = RandomTempPath::new();
+		let db = new_db(temp.as_str());
+		let ancient = new_db(ancient_temp.as_str());
+		let config = Config { ancient_horizon: 2, ..Config::default() };
+		let mut hashes = Vec::new();
+		{
+			let bc = BlockChain::with_ancient(config.clone(), &genesis, db.clone(), Some(ancient.clone()));
+			for _ in 0..5 {
+				let block = canon_chain.generate(&mut finalizer).unwrap();
+				hashes.push(BlockView::new(&block).header_view().sha3());
+				let mut batch = db.transaction();
+				bc.insert_block(&mut batch, &block, vec![]);
+				db.write(batch).unwrap();
+				bc.commit();
+			}
+
+			// best block is #5, so #1 to #3 are beyond the horizon.
+			assert_eq!(bc.move_ancient_blocks(2), 2);
+			assert_eq!(bc.move_ancient_blocks(10), 1);
+			assert_eq!(bc.move_ancient_blocks(10), 0);
+		}
+
+		assert!(db.get(::db::COL_BODIES, &hashes[2]).unwrap().is_none());
+		assert!(ancient.get(::db::COL_BODIES, &hashes[2]).unwrap().is_some());
+		assert!(db.get(::db::COL_BODIES, &hashes[3]).unwrap().is_some());
+
+		assert!(BlockChain::has_ancient_blocks(&db));
+		let bc = BlockChain::with_ancient(config, &genesis, db.clone(), Some(ancient.clone()));
+		assert!(bc.block(&hashes[0]).is_some());
+		assert!(bc.block_receipts(&hashes[0]).is_some());
+		assert!(bc.block_receipts(&hashes[4]).is_some());
+	}
+
+	#[test]
+	fn stores_only_headers_and_chts() {
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+
+		let temp = RandomTempPath::new();
+		let db = new_db(temp.as_str());
+		let config = Config { store_bodies: false, ..Config::default() };
+		let mut hashes = Vec::new();
+		{
+			let bc = BlockChain::new(config.clone(), &genesis, db.clone());
+			for _ in 0..CHT_SIZE + CHT_CONFIRMATIONS {
+				let block = canon_chain.generate(&mut finalizer).unwrap();
+				hashes.push(BlockView::new(&block).header_view().sha3());
+				let mut batch = db.transaction();
+				bc.insert_block(&mut batch, &block, vec![]);
+				db.write(batch).unwrap();
+				bc.commit();
+			}
+
+			assert_eq!(bc.cht_count(), 0);
+			assert_eq!(bc.update_chts(10), 1);
+			assert_eq!(bc.update_chts(10), 0);
+			assert_eq!(bc.cht_count(), 1);
+			assert!(bc.cht_root(0).is_some());
+			assert!(bc.cht_root(1).is_none());
+		}
+
+		assert_eq!(BlockChain::stores_bodies(&db), Some(false));
+		assert!(db.get(::db::COL_BODIES, &hashes[0]).unwrap().is_none());
+		assert!(db.get(::db::COL_BODIES, hashes.last().unwrap()).unwrap().is_none());
+
+		// the chain can be reopened without the best block's body.
+		let bc = BlockChain::new(config, &genesis, db.clone());
+		assert_eq!(bc.best_block_number(), CHT_SIZE + CHT_CONFIRMATIONS);
+		assert!(bc.block_header(&hashes[0]).is_some());
+		assert!(bc.block_body(&hashes[0]).is_none());
+		assert!(bc.block_receipts(&hashes[0]).is_none());
+		assert_eq!(bc.cht_count(), 1);
+	}
+
 	#[test]
 	fn can_contain_arbitrary_block_sequence() {
 		let bc_result = generate_dummy_blockchain(50);
@@ -2002,4 +2310,54 @@ mod tests {
 		assert_eq!(bc.rewind(), Some(genesis_hash.clone()));
 		assert_eq!(bc.rewind(), None);
 	}
+
+	#[test]
+	fn test_uncles_after_reorg() {
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+		let mut fork_finalizer = finalizer.fork();
+		let mut fork_chain = canon_chain.fork(1);
+
+		let mut uncle_a = Header::new();
+		uncle_a.set_extra_data(b"a".to_vec());
+		let mut uncle_b = Header::new();
+		uncle_b.set_extra_data(b"b".to_vec());
+
+		let b1a = canon_chain.with_uncle(uncle_a.clone()).generate(&mut finalizer).unwrap();
+		let b1b = fork_chain.with_uncle(uncle_b.clone()).generate(&mut fork_finalizer).unwrap();
+		let b2 = fork_chain.generate(&mut fork_finalizer).unwrap();
+
+		let b1a_hash = BlockView::new(&b1a).header_view().sha3();
+		let b1b_hash = BlockView::new(&b1b).header_view().sha3();
+
+		let temp = RandomTempPath::new();
+		let db = new_db(temp.as_str());
+		let bc = BlockChain::new(Config::default(), &genesis, db.clone());
+
+		let mut batch = db.transaction();
+		bc.insert_block(&mut batch, &b1a, vec![]);
+		bc.commit();
+		bc.insert_block(&mut batch, &b1b, vec![]);
+		bc.commit();
+		db.write(batch).unwrap();
+
+		assert_eq!(bc.block_hash(1), Some(b1a_hash.clone()));
+		assert_eq!(bc.uncle(&b1a_hash, 0), Some(encode(&uncle_a).to_vec()));
+		assert_eq!(bc.uncle(&b1a_hash, 1), None);
+
+		// make the forked chain canonical
+		let mut batch = db.transaction();
+		bc.insert_block(&mut batch, &b2, vec![]);
+		bc.commit();
+		db.write(batch).unwrap();
+
+		assert_eq!(bc.block_hash(1), Some(b1b_hash.clone()));
+		assert_eq!(bc.uncle(&b1b_hash, 0), Some(encode(&uncle_b).to_vec()));
+		// uncles of the retracted block are still available by hash
+		assert_eq!(bc.uncle(&b1a_hash, 0), Some(encode(&uncle_a).to_vec()));
+		assert_eq!(bc.uncle(&H256::from(1), 0), None);
+		// bodies read to get the uncles are not cached on their own
+		assert!(bc.block_bodies.read().is_empty());
+	}
 }
@@ -21,6 +21,8 @@ pub struct CacheSize {
 	pub blocks: usize,
 	/// BlockDetails cache size.
 	pub block_details: usize,
+	/// Block hashes cache size.
+	pub block_hashes: usize,
 	/// Transaction addresses cache size.
 	pub transaction_addresses: usize,
 	/// Blooms cache size.
@@ -32,6 +34,6 @@ pub struct CacheSize {
 impl CacheSize {
 	/// Total amount used by the cache.
 	pub fn total(&self) -> usize {
-		self.blocks + self.block_details + self.transaction_addresses + self.blocks_blooms + self.block_receipts
+		self.blocks + self.block_details + self.block_hashes + self.transaction_addresses + self.blocks_blooms + self.block_receipts
 	}
 }
@@ -25,6 +25,12 @@ pub struct Config {
 	pub max_cache_size: usize,
 	/// Backing db cache_size
 	pub db_cache_size: Option<usize>,
+	/// Bodies and receipts of blocks this many blocks behind the best block
+	/// are moved to the ancient database, if there is one.
+	pub ancient_horizon: u64,
+	/// Whether block bodies and receipts are stored. Without them only headers
+	/// and Canonical Hash Trie roots are kept.
+	pub store_bodies: bool,
 }
 
 impl Default for Config {
@@ -33,6 +39,8 @@ impl Default for Config {
 			pref_cache_size: 1 << 14,
 			max_cache_size: 1 << 20,
 			db_cache_size: None,
+			ancient_horizon: 90000,
+			store_bodies: true,
 		}
 	}
 }
@@ -24,6 +24,7 @@ use super::fork::Forkable;
 use super::bloom::WithBloom;
 use super::complete::CompleteBlock;
 use super::transaction::WithTransaction;
+use super::uncle::WithUncle;
 
 /// Helper structure, used for encoding blocks.
 #[derive(Default)]
@@ -64,6 +65,13 @@ impl WithTransaction for Block {
 	}
 }
 
+impl WithUncle for Block {
+	fn with_uncle(mut self, uncle: Header) -> Self where Self: Sized {
+		self.uncles.push(uncle);
+		self
+	}
+}
+
 impl CompleteBlock for Block {
 	fn complete(mut self, parent_hash: H256) -> Bytes {
 		self.header.set_parent_hash(parent_hash);
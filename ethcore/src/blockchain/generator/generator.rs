@@ -16,12 +16,14 @@
 
 use util::{U256, H2048, Bytes};
 use header::BlockNumber;
+use header::Header;
 use transaction::SignedTransaction;
 use super::fork::Fork;
 use super::bloom::Bloom;
 use super::complete::{BlockFinalizer, CompleteBlock, Complete};
 use super::block::Block;
 use super::transaction::Transaction;
+use super::uncle::Uncle;
 
 /// Chain iterator interface.
 pub trait ChainIterator: Iterator + Sized {
@@ -32,6 +34,8 @@ pub trait ChainIterator: Iterator + Sized {
 	fn with_bloom(&mut self, bloom: H2048) -> Bloom<Self>;
 	/// Should be called to make every consecutive block have given transaction.
 	fn with_transaction(&mut self, transaction: SignedTransaction) -> Transaction<Self>;
+	/// Should be called to make every consecutive block include given uncle.
+	fn with_uncle(&mut self, uncle: Header) -> Uncle<Self>;
 	/// Should be called to complete block. Without complete, block may have incorrect hash.
 	fn complete<'a>(&'a mut self, finalizer: &'a mut BlockFinalizer) -> Complete<'a, Self>;
 	/// Completes and generates block.
@@ -60,6 +64,13 @@ impl<I> ChainIterator for I where I: Iterator + Sized {
 		}
 	}
 
+	fn with_uncle(&mut self, uncle: Header) -> Uncle<Self> {
+		Uncle {
+			iter: self,
+			uncle: uncle,
+		}
+	}
+
 	fn complete<'a>(&'a mut self, finalizer: &'a mut BlockFinalizer) -> Complete<'a, Self> {
 		Complete {
 			iter: self,
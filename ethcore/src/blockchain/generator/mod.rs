@@ -22,6 +22,7 @@ mod complete;
 mod fork;
 pub mod generator;
 mod transaction;
+mod uncle;
 
 pub use self::complete::BlockFinalizer;
 pub use self::generator::{ChainIterator, ChainGenerator};
@@ -0,0 +1,35 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use header::Header;
+
+pub trait WithUncle {
+	fn with_uncle(self, uncle: Header) -> Self where Self: Sized;
+}
+
+pub struct Uncle<'a, I> where I: 'a {
+	pub iter: &'a mut I,
+	pub uncle: Header,
+}
+
+impl <'a, I> Iterator for Uncle<'a, I> where I: Iterator, <I as Iterator>::Item: WithUncle {
+	type Item = <I as Iterator>::Item;
+
+	#[inline]
+	fn next(&mut self) -> Option<Self::Item> {
+		self.iter.next().map(|item| item.with_uncle(self.uncle.clone()))
+	}
+}
@@ -19,19 +19,22 @@ use std::hash::Hash;
 
 const COLLECTION_QUEUE_SIZE: usize = 8;
 
+/// Tracks usage of cached items and decides which of them to evict.
+/// Cache limits are expressed in bytes, as measured by the owner of the cache.
 pub struct CacheManager<T> where T: Eq + Hash {
 	pref_cache_size: usize,
 	max_cache_size: usize,
-	bytes_per_cache_entry: usize,
+	/// Size of the cache when the most recent generation was started.
+	generation_start_size: usize,
 	cache_usage: VecDeque<HashSet<T>>
 }
 
 impl<T> CacheManager<T> where T: Eq + Hash {
-	pub fn new(pref_cache_size: usize, max_cache_size: usize, bytes_per_cache_entry: usize) -> Self {
+	pub fn new(pref_cache_size: usize, max_cache_size: usize) -> Self {
 		CacheManager {
 			pref_cache_size: pref_cache_size,
 			max_cache_size: max_cache_size,
-			bytes_per_cache_entry: bytes_per_cache_entry,
+			generation_start_size: 0,
 			cache_usage: (0..COLLECTION_QUEUE_SIZE).into_iter().map(|_| Default::default()).collect(),
 		}
 	}
@@ -50,23 +53,66 @@ impl<T> CacheManager<T> where T: Eq + Hash {
 	/// Second one is an with objects to remove. It should also return new size of the cache.
 	pub fn collect_garbage<F>(&mut self, current_size: usize, mut notify_unused: F) where F: FnMut(HashSet<T>) -> usize {
 		if current_size < self.pref_cache_size {
-			self.rotate_cache_if_needed();
+			self.rotate_cache_if_needed(current_size);
 			return;
 		}
 
 		for _ in 0..COLLECTION_QUEUE_SIZE {
 			let current_size = notify_unused(self.cache_usage.pop_back().unwrap());
 			self.cache_usage.push_front(Default::default());
+			self.generation_start_size = current_size;
 			if current_size < self.max_cache_size {
 				break;
 			}
 		}
 	}
 
-	fn rotate_cache_if_needed(&mut self) {
-		if self.cache_usage[0].len() * self.bytes_per_cache_entry > self.pref_cache_size / COLLECTION_QUEUE_SIZE {
+	/// Starts a new generation once the cache has grown by an eighth of the preferred size.
+	fn rotate_cache_if_needed(&mut self, current_size: usize) {
+		if current_size < self.generation_start_size {
+			self.generation_start_size = current_size;
+		}
+
+		if current_size - self.generation_start_size > self.pref_cache_size / COLLECTION_QUEUE_SIZE {
 			let cache = self.cache_usage.pop_back().unwrap();
 			self.cache_usage.push_front(cache);
+			self.generation_start_size = current_size;
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::CacheManager;
+
+	#[test]
+	fn rotates_generations_by_size() {
+		let mut manager = CacheManager::new(800, 1600);
+		manager.note_used(1);
+		manager.collect_garbage(50, |_| unreachable!());
+		assert!(manager.cache_usage[0].contains(&1));
+
+		manager.collect_garbage(150, |_| unreachable!());
+		manager.note_used(2);
+		assert!(manager.cache_usage[0].contains(&2));
+		assert!(manager.cache_usage[1].contains(&1));
+	}
+
+	#[test]
+	fn evicts_oldest_generations_first() {
+		let mut manager = CacheManager::new(800, 1600);
+		manager.note_used(1);
+		manager.collect_garbage(200, |_| unreachable!());
+		manager.note_used(2);
+
+		let mut evicted = Vec::new();
+		let mut size = 2000;
+		manager.collect_garbage(size, |ids| {
+			evicted.extend(ids.into_iter());
+			if !evicted.is_empty() { size = 1000; }
+			size
+		});
+		assert_eq!(evicted, vec![1]);
+		assert!(manager.cache_usage.iter().any(|g| g.contains(&2)));
+	}
+}
@@ -0,0 +1,147 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Store of blocks which failed verification, kept for consensus bug triage.
+
+use std::collections::VecDeque;
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json::Json;
+use time::get_time;
+use util::{H256, Hashable, Mutex, Database, DBTransaction};
+use rlp::{encode, UntrustedRlp, View};
+use db::COL_BAD_BLOCKS;
+use http_poster::JsonPoster;
+use types::bad_block::BadBlock;
+
+/// Maximal number of bad blocks kept in the database. The oldest ones are dropped first.
+const MAX_BAD_BLOCKS: usize = 128;
+
+/// Persists blocks which failed verification together with the failure reason,
+/// optionally reporting them to a URL.
+pub struct BadBlocks {
+	/// Hashes of the stored blocks, oldest first.
+	hashes: Mutex<VecDeque<H256>>,
+	reporter: Option<JsonPoster>,
+}
+
+impl BadBlocks {
+	/// Load the bad blocks stored in `db`. New blocks are posted to `report_url` if given.
+	pub fn new(db: &Database, report_url: Option<String>) -> Self {
+		let mut blocks: Vec<BadBlock> = db.iter(COL_BAD_BLOCKS).filter_map(|(key, value)| Self::decode(&key, &value)).collect();
+		blocks.sort_by_key(|block| block.timestamp);
+
+		BadBlocks {
+			hashes: Mutex::new(blocks.into_iter().map(|block| block.hash).collect()),
+			reporter: report_url.map(|url| JsonPoster::new(&[url])),
+		}
+	}
+
+	/// Store a block which failed verification.
+	pub fn note(&self, db: &Database, rlp: &[u8], reason: String) {
+		let hash = rlp.sha3();
+		let mut hashes = self.hashes.lock();
+		if hashes.contains(&hash) {
+			return;
+		}
+
+		let block = BadBlock {
+			hash: hash,
+			rlp: rlp.to_vec(),
+			reason: reason,
+			timestamp: get_time().sec as u64,
+		};
+
+		let mut batch = DBTransaction::new(db);
+		while hashes.len() >= MAX_BAD_BLOCKS {
+			let oldest = hashes.pop_front().expect("MAX_BAD_BLOCKS > 0; qed");
+			batch.delete(COL_BAD_BLOCKS, &oldest);
+		}
+		batch.put(COL_BAD_BLOCKS, &hash, &encode(&block));
+		if let Err(e) = db.write(batch) {
+			warn!(target: "client", "Error storing bad block {}: {}", hash, e);
+			return;
+		}
+		hashes.push_back(hash);
+
+		if let Some(ref reporter) = self.reporter {
+			reporter.post(&format!(r#"{{ "hash": "0x{}", "rlp": "0x{}", "reason": {}, "timestamp": {} }}"#,
+				block.hash.to_hex(), block.rlp.to_hex(), Json::String(block.reason.clone()), block.timestamp));
+		}
+	}
+
+	/// All stored bad blocks, oldest first.
+	pub fn blocks(&self, db: &Database) -> Vec<BadBlock> {
+		self.hashes.lock().iter()
+			.filter_map(|hash| db.get(COL_BAD_BLOCKS, hash).expect("Low level database error. Some issue with disk?")
+				.and_then(|value| Self::decode(hash, &value)))
+			.collect()
+	}
+
+	/// Decode a stored bad block, skipping corrupt entries instead of panicking.
+	fn decode(key: &[u8], value: &[u8]) -> Option<BadBlock> {
+		match UntrustedRlp::new(value).as_val() {
+			Ok(block) => Some(block),
+			Err(e) => {
+				warn!(target: "client", "Skipping corrupt bad block entry {}: {:?}", key.to_hex(), e);
+				None
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use devtools::RandomTempPath;
+	use util::{Database, DatabaseConfig, DBTransaction, Hashable};
+	use db::{NUM_COLUMNS, COL_BAD_BLOCKS};
+	use super::{BadBlocks, MAX_BAD_BLOCKS};
+
+	#[test]
+	fn keeps_the_most_recent_bad_blocks() {
+		let path = RandomTempPath::new();
+		let db = Arc::new(Database::open(&DatabaseConfig::with_columns(NUM_COLUMNS), path.as_str()).unwrap());
+		let bad_blocks = BadBlocks::new(&db, None);
+		for i in 0..MAX_BAD_BLOCKS + 2 {
+			bad_blocks.note(&db, &[i as u8], format!("Reason {}", i));
+		}
+		bad_blocks.note(&db, &[2], "Duplicate".into());
+
+		let blocks = bad_blocks.blocks(&db);
+		assert_eq!(blocks.len(), MAX_BAD_BLOCKS);
+		assert_eq!(blocks[0].hash, [2u8].sha3());
+		assert_eq!(blocks[0].reason, "Reason 2");
+		assert_eq!(blocks[0].rlp, vec![2]);
+
+		let reloaded = BadBlocks::new(&db, None);
+		assert_eq!(reloaded.blocks(&db).len(), MAX_BAD_BLOCKS);
+	}
+
+	#[test]
+	fn skips_corrupt_entries() {
+		let path = RandomTempPath::new();
+		let db = Arc::new(Database::open(&DatabaseConfig::with_columns(NUM_COLUMNS), path.as_str()).unwrap());
+		BadBlocks::new(&db, None).note(&db, &[1], "Reason".into());
+		let mut batch = DBTransaction::new(&db);
+		batch.put(COL_BAD_BLOCKS, &[2u8].sha3(), &[0xff, 0x01]);
+		db.write(batch).unwrap();
+
+		let reloaded = BadBlocks::new(&db, None);
+		let blocks = reloaded.blocks(&db);
+		assert_eq!(blocks.len(), 1);
+		assert_eq!(blocks[0].reason, "Reason");
+	}
+}
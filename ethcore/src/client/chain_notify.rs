@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use ipc::IpcConfig;
-use util::H256;
+use util::{H256, Bytes};
 
 /// Represents what has to be handled by actor listening to chain events
 #[ipc]
@@ -40,6 +40,16 @@ pub trait ChainNotify : Send + Sync {
 	fn stop(&self) {
 		// does nothing by default
 	}
+
+	/// fires when a private transaction should be sent to the peers
+	fn broadcast_private_transaction(&self, _packet: Bytes) {
+		// does nothing by default
+	}
+
+	/// fires when a validator's signature of a private transaction should be sent to the peers
+	fn broadcast_signed_private_transaction(&self, _packet: Bytes) {
+		// does nothing by default
+	}
 }
 
 impl IpcConfig for ChainNotify { }
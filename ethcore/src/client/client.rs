@@ -17,22 +17,24 @@ use std::collections::{HashSet, HashMap, BTreeMap, VecDeque};
 use std::sync::{Arc, Weak};
 use std::path::{Path};
 use std::fmt;
+use std::thread;
 use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering as AtomicOrdering};
 use std::time::{Instant};
 use time::precise_time_ns;
 
 // util
-use util::{Bytes, PerfTimer, Itertools, Mutex, RwLock};
+use util::{Bytes, PerfTimer, Itertools, Mutex, RwLock, Hashable};
 use util::{journaldb, TrieFactory, Trie};
-use util::trie::TrieSpec;
-use util::{U256, H256, Address, H2048, Uint, FixedHash};
+use util::trie::{TrieSpec, FatDB};
+use util::{U256, H256, H512, Address, H2048, Uint, FixedHash};
 use util::kvdb::*;
+use util::metrics::{self, Counter, Histogram};
 
 // other
 use io::*;
-use views::{HeaderView, BodyView};
-use error::{ImportError, ExecutionError, CallError, BlockError, ImportResult, Error as EthcoreError};
-use header::BlockNumber;
+use views::{HeaderView, BodyView, BlockView};
+use error::{ImportError, ExecutionError, CallError, StateDiffError, BlockError, ImportResult, Error as EthcoreError};
+use header::{BlockNumber, Header};
 use state::State;
 use spec::Spec;
 use basic_types::Seal;
@@ -42,7 +44,7 @@ use env_info::LastHashes;
 use verification;
 use verification::{PreverifiedBlock, Verifier};
 use block::*;
-use transaction::{LocalizedTransaction, SignedTransaction, Action};
+use transaction::{LocalizedTransaction, SignedTransaction, Transaction, Action};
 use blockchain::extras::TransactionAddress;
 use types::filter::Filter;
 use log_entry::LocalizedLogEntry;
@@ -51,21 +53,34 @@ use blockchain::{BlockChain, BlockProvider, TreeRoute, ImportRoute};
 use client::{
 	BlockID, TransactionID, UncleID, TraceId, ClientConfig, BlockChainClient,
 	MiningBlockChainClient, TraceFilter, CallAnalytics, BlockImportError, Mode,
-	ChainNotify,
+	ChainNotify, Registrar, IpcMode,
 };
 use client::Error as ClientError;
+use client::relay::RelayedTransactions;
+use client::root_check::RootCheck;
+use client::bad_blocks::BadBlocks;
+use tx_permission::TransactionPermission;
+use node_permission::NodePermission;
+use private_transactions::Provider as PrivateTransactions;
 use env_info::EnvInfo;
 use executive::{Executive, Executed, TransactOptions, contract_address};
-use receipt::LocalizedReceipt;
+use receipt::{Receipt, LocalizedReceipt};
+use pod_account::PodAccount;
+use pod_state::{self, PodState};
+use types::state_diff::StateDiff;
 use trace::{TraceDB, ImportRequest as TraceImportRequest, LocalizedTrace, Database as TraceDatabase};
 use trace;
 use trace::FlatTransactionTraces;
+use trace::trace::{Action as TraceAction, Res as TraceResult};
 use evm::Factory as EvmFactory;
 use miner::{Miner, MinerService};
 use snapshot::{self, io as snapshot_io};
 use factory::Factories;
 use rlp::{View, UntrustedRlp};
 use state_db::StateDB;
+use types::builtin_info::BuiltinInfo;
+use types::bad_block::BadBlock;
+use types::executed::CallType;
 
 // re-export
 pub use types::blockchain_info::BlockChainInfo;
@@ -74,6 +89,17 @@ pub use blockchain::CacheSize as BlockChainCacheSize;
 
 const MAX_TX_QUEUE_SIZE: usize = 4096;
 const MAX_QUEUE_SIZE_TO_SLEEP_ON: usize = 2;
+const ANCIENT_BLOCKS_PER_TICK: u64 = 2048;
+/// Maximal number of Canonical Hash Tries generated in one tick.
+const CHTS_PER_TICK: u64 = 4;
+/// Maximal number of transactions kept for gossiping in relay mode.
+const MAX_RELAYED_TRANSACTIONS: usize = 8192;
+/// Maximal number of blocks kept after being refused for reorganizing too deep.
+const MAX_REFUSED_REORGS: usize = 1024;
+/// Gas available to calls of contracts configured in the chain spec.
+const CONTRACT_CALL_GAS: u64 = 1_000_000;
+/// Maximal number of blocks replayed to rebuild a pruned state for a state diff.
+const MAX_STATE_DIFF_REPLAY: u64 = 256;
 
 impl fmt::Display for BlockChainInfo {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -120,7 +146,7 @@ impl SleepState {
 /// Blockchain database client backed by a persistent database. Owns and manages a blockchain and a block queue.
 /// Call `import_block()` to import a block asynchronously; `flush_queue()` flushes the queue.
 pub struct Client {
-	mode: Mode,
+	mode: Mutex<Mode>,
 	chain: RwLock<Arc<BlockChain>>,
 	tracedb: RwLock<TraceDB<BlockChain>>,
 	engine: Arc<Engine>,
@@ -128,6 +154,8 @@ pub struct Client {
 	pruning: journaldb::Algorithm,
 	db: RwLock<Arc<Database>>,
 	state_db: Mutex<StateDB>,
+	cold_state_db: Option<Mutex<StateDB>>,
+	ancient_db: Option<Arc<Database>>,
 	block_queue: BlockQueue,
 	report: RwLock<ClientReport>,
 	import_lock: Mutex<()>,
@@ -136,17 +164,51 @@ pub struct Client {
 	miner: Arc<Miner>,
 	sleep_state: Mutex<SleepState>,
 	liveness: AtomicBool,
+	shut_down: AtomicBool,
 	io_channel: IoChannel<ClientIoMessage>,
 	notify: RwLock<Vec<Weak<ChainNotify>>>,
 	queue_transactions: AtomicUsize,
 	last_hashes: RwLock<VecDeque<H256>>,
 	factories: Factories,
+	registrar: Registrar,
+	tx_permission: TransactionPermission,
+	node_permission: NodePermission,
+	relayed_transactions: Mutex<RelayedTransactions>,
+	root_check: Option<RootCheck>,
+	/// Set when a state root check failed. No more blocks are imported.
+	import_stopped: AtomicBool,
+	/// Blocks refused for reorganizing deeper than `max_reorg_depth`, by hash.
+	refused_reorgs: Mutex<HashMap<H256, Bytes>>,
+	/// Blocks allowed to reorganize past `max_reorg_depth` once.
+	forced_reorgs: Mutex<HashSet<H256>>,
+	bad_blocks: BadBlocks,
+	private_transactions: RwLock<Option<Weak<PrivateTransactions>>>,
+	/// Number of the block whose state the last account bloom rebuild walks, and the rebuild thread.
+	/// The state must not be pruned before the thread is done with it.
+	account_bloom_rebuild: Mutex<Option<(BlockNumber, thread::JoinHandle<()>)>>,
 }
 
 /// The pruning constant -- how old blocks must be before we
 /// assume finality of a given candidate.
 pub const HISTORY: u64 = 1200;
 
+/// Key of the extras entry written on clean shutdown and removed on start.
+const CLEAN_SHUTDOWN_KEY: &'static [u8] = b"clean_shutdown";
+
+lazy_static! {
+	static ref BLOCK_IMPORT_TIME: Arc<Histogram> = metrics::registry().histogram("parity_block_import_seconds", "Time taken to execute and commit a verified block.");
+	static ref BLOCKS_IMPORTED: Arc<Counter> = metrics::registry().counter("parity_blocks_imported_total", "Number of blocks imported.");
+	static ref TRANSACTIONS_APPLIED: Arc<Counter> = metrics::registry().counter("parity_transactions_applied_total", "Number of transactions applied by imported blocks.");
+}
+
+/// Describe the builtin contracts of `engine`.
+pub fn builtin_infos(engine: &Engine) -> BTreeMap<Address, BuiltinInfo> {
+	engine.builtins().iter().map(|(address, builtin)| (address.clone(), BuiltinInfo {
+		name: builtin.name().to_owned(),
+		activate_at: builtin.activate_at(),
+	})).collect()
+}
+
 /// Append a path element to the given path and return the string.
 pub fn append_path<P>(path: P, item: &str) -> String where P: AsRef<Path> {
 	let mut p = path.as_ref().to_path_buf();
@@ -154,6 +216,51 @@ pub fn append_path<P>(path: P, item: &str) -> String where P: AsRef<Path> {
 	p.to_str().unwrap().to_owned()
 }
 
+/// Read an account with the given storage entries from `state`. Returns `None` if the account doesn't exist.
+fn pod_account(state: &State, address: &Address, keys: &HashSet<H256>) -> Option<PodAccount> {
+	if !state.exists(address) {
+		return None;
+	}
+	Some(PodAccount {
+		balance: state.balance(address),
+		nonce: state.nonce(address),
+		code: Some(state.code(address).map_or_else(Bytes::new, |code| (*code).clone())),
+		storage: keys.iter()
+			.map(|key| (key.clone(), state.storage_at(address, key)))
+			.filter(|&(_, ref value)| !value.is_zero())
+			.collect(),
+	})
+}
+
+/// Attach the transaction location to its receipt. `prior_gas_used` is the cumulative
+/// gas used by the preceding transactions in the block.
+fn localized_receipt(tx: LocalizedTransaction, receipt: Receipt, prior_gas_used: U256) -> LocalizedReceipt {
+	let block_hash = tx.block_hash.clone();
+	let block_number = tx.block_number;
+	let transaction_hash = tx.hash();
+	let transaction_index = tx.transaction_index;
+	LocalizedReceipt {
+		transaction_hash: transaction_hash.clone(),
+		transaction_index: transaction_index,
+		block_hash: block_hash.clone(),
+		block_number: block_number,
+		cumulative_gas_used: receipt.gas_used,
+		gas_used: receipt.gas_used - prior_gas_used,
+		contract_address: match tx.action {
+			Action::Call(_) => None,
+			Action::Create => Some(contract_address(&tx.sender().unwrap(), &tx.nonce))
+		},
+		logs: receipt.logs.into_iter().enumerate().map(|(i, log)| LocalizedLogEntry {
+			entry: log,
+			block_hash: block_hash.clone(),
+			block_number: block_number,
+			transaction_hash: transaction_hash.clone(),
+			transaction_index: transaction_index,
+			log_index: i
+		}).collect()
+	}
+}
+
 impl Client {
 	/// Create a new client with given spec and DB path and custom verifier.
 	pub fn new(
@@ -164,11 +271,36 @@ impl Client {
 		message_channel: IoChannel<ClientIoMessage>,
 		db_config: &DatabaseConfig,
 	) -> Result<Arc<Client>, ClientError> {
-		let path = path.to_path_buf();
+		let db = Arc::new(try!(Database::open(&db_config, &path.to_str().unwrap()).map_err(ClientError::Database)));
+		Client::with_database(config, spec, db, miner, message_channel, db_config)
+	}
+
+	/// Create a new client on top of an already opened database, e.g. `Database::in_memory`.
+	/// `db_config` is still used for the ancient and cold databases.
+	pub fn with_database(
+		config: ClientConfig,
+		spec: &Spec,
+		db: Arc<Database>,
+		miner: Arc<Miner>,
+		message_channel: IoChannel<ClientIoMessage>,
+		db_config: &DatabaseConfig,
+	) -> Result<Arc<Client>, ClientError> {
 		let gb = spec.genesis_block();
 
-		let db = Arc::new(try!(Database::open(&db_config, &path.to_str().unwrap()).map_err(ClientError::Database)));
-		let chain = Arc::new(BlockChain::new(config.blockchain.clone(), &gb, db.clone()));
+		let ancient_db = match config.ancient_path {
+			Some(ref ancient_path) => {
+				info!("Keeping bodies and receipts of old blocks in {}", ancient_path);
+				Some(Arc::new(try!(Database::open(&db_config, ancient_path).map_err(ClientError::Database))))
+			},
+			None => None,
+		};
+		if ancient_db.is_none() && BlockChain::has_ancient_blocks(&db) {
+			return Err(ClientError::MissingAncientDatabase);
+		}
+		if BlockChain::stores_bodies(&db).map_or(false, |stored| stored != config.blockchain.store_bodies) {
+			return Err(ClientError::NoBodiesMismatch(config.blockchain.store_bodies));
+		}
+		let chain = Arc::new(BlockChain::with_ancient(config.blockchain.clone(), &gb, db.clone(), ancient_db.clone()));
 		let tracedb = RwLock::new(TraceDB::new(config.tracing.clone(), db.clone(), chain.clone()));
 
 		let trie_spec = match config.fat_db {
@@ -176,8 +308,21 @@ impl Client {
 			false => TrieSpec::Secure,
 		};
 
+		// the marker is missing if the node was not shut down cleanly
+		let clean_shutdown = try!(db.get(::db::COL_EXTRA, CLEAN_SHUTDOWN_KEY).map_err(ClientError::Database)).is_some();
+		if !clean_shutdown && chain.best_block_number() > 0 {
+			warn!("The node was not shut down cleanly. Checking the state journal...");
+			let records = try!(journaldb::check_journal(&db, config.pruning, ::db::COL_STATE)
+				.map_err(|e| ClientError::Database(format!("{}. Repair the database with `parity db check-journal <snapshot file>` or remove it with `parity db kill` and sync again.", e))));
+			info!("State journal is consistent ({} records)", records);
+		}
+		let mut batch = DBTransaction::new(&db);
+		batch.delete(::db::COL_EXTRA, CLEAN_SHUTDOWN_KEY);
+		try!(db.write(batch).map_err(ClientError::Database));
+
 		let journal_db = journaldb::new(db.clone(), config.pruning, ::db::COL_STATE);
-		let mut state_db = StateDB::new(journal_db);
+		let state_cache_size = config.state_cache_size.unwrap_or_else(StateDB::default_cache_size);
+		let mut state_db = StateDB::with_config(journal_db, state_cache_size, &config.state_db);
 		if state_db.journal_db().is_empty() && try!(spec.ensure_db_good(&mut state_db)) {
 			let mut batch = DBTransaction::new(&db);
 			try!(state_db.commit(&mut batch, 0, &spec.genesis_header().hash(), None));
@@ -188,24 +333,41 @@ impl Client {
 			warn!("State root not found for block #{} ({})", chain.best_block_number(), chain.best_block_hash().hex());
 		}
 
+		// the secondary database is opened in place in read-only mode, so pruning does not matter.
+		// it may be in use by another node; its later writes are not seen until restart.
+		let cold_state_db = match config.cold_db_path {
+			Some(ref cold_path) => {
+				let cold_db = Arc::new(try!(Database::open_read_only(&db_config, cold_path).map_err(ClientError::Database)));
+				info!("Using {} as cold storage for historical state", cold_path);
+				Some(Mutex::new(StateDB::new(journaldb::new(cold_db, journaldb::Algorithm::Archive, ::db::COL_STATE))))
+			},
+			None => None,
+		};
+
 		let engine = spec.engine.clone();
 
 		let block_queue = BlockQueue::new(config.queue.clone(), engine.clone(), message_channel.clone());
 		let panic_handler = PanicHandler::new_in_arc();
 		panic_handler.forward_from(&block_queue);
 
-		let awake = match config.mode { Mode::Dark(..) => false, _ => true };
+		let awake = match config.mode { Mode::Dark(..) | Mode::Off => false, _ => true };
 
 		let factories = Factories {
 			vm: EvmFactory::new(config.vm_type.clone()),
 			trie: TrieFactory::new(trie_spec),
 			accountdb: Default::default(),
 		};
+		let bad_blocks = BadBlocks::new(&db, config.bad_block_report_url.clone());
+		let root_check = match config.check_roots {
+			true => Some(RootCheck::new(factories.accountdb.clone())),
+			false => None,
+		};
 
 		let client = Client {
 			sleep_state: Mutex::new(SleepState::new(awake)),
 			liveness: AtomicBool::new(awake),
-			mode: config.mode.clone(),
+			shut_down: AtomicBool::new(false),
+			mode: Mutex::new(config.mode.clone()),
 			chain: RwLock::new(chain),
 			tracedb: tracedb,
 			engine: engine,
@@ -214,6 +376,8 @@ impl Client {
 			config: config,
 			db: RwLock::new(db),
 			state_db: Mutex::new(state_db),
+			cold_state_db: cold_state_db,
+			ancient_db: ancient_db,
 			block_queue: block_queue,
 			report: RwLock::new(Default::default()),
 			import_lock: Mutex::new(()),
@@ -224,10 +388,27 @@ impl Client {
 			queue_transactions: AtomicUsize::new(0),
 			last_hashes: RwLock::new(VecDeque::new()),
 			factories: factories,
+			registrar: Registrar::new(),
+			tx_permission: TransactionPermission::new(),
+			node_permission: NodePermission::new(),
+			relayed_transactions: Mutex::new(RelayedTransactions::new(MAX_RELAYED_TRANSACTIONS)),
+			root_check: root_check,
+			import_stopped: AtomicBool::new(false),
+			refused_reorgs: Mutex::new(HashMap::new()),
+			forced_reorgs: Mutex::new(HashSet::new()),
+			bad_blocks: bad_blocks,
+			private_transactions: RwLock::new(None),
+			account_bloom_rebuild: Mutex::new(None),
 		};
+		client.check_account_bloom();
 		Ok(Arc::new(client))
 	}
 
+	/// Set the provider handling private transactions received from the network.
+	pub fn set_private_transactions(&self, provider: Weak<PrivateTransactions>) {
+		*self.private_transactions.write() = Some(provider);
+	}
+
 	/// Adds an actor to be notified on certain events
 	pub fn add_notify(&self, target: Arc<ChainNotify>) {
 		self.notify.write().push(Arc::downgrade(&target));
@@ -241,6 +422,21 @@ impl Client {
 		}
 	}
 
+	/// Stop importing blocks, wait for the import in progress and write all buffered
+	/// database changes along with the clean shutdown marker.
+	pub fn shutdown(&self) -> Result<(), String> {
+		self.shut_down.store(true, AtomicOrdering::SeqCst);
+		self.block_queue.clear();
+
+		let _import_lock = self.import_lock.lock();
+		self.collect_root_check();
+		let db = self.db.read();
+		let mut batch = DBTransaction::new(&db);
+		batch.put(::db::COL_EXTRA, CLEAN_SHUTDOWN_KEY, &[1]);
+		try!(db.write(batch));
+		db.flush()
+	}
+
 	/// Flush the block import queue.
 	pub fn flush_queue(&self) {
 		self.block_queue.flush();
@@ -275,7 +471,8 @@ impl Client {
 		Arc::new(last_hashes)
 	}
 
-	fn check_and_close_block(&self, block: &PreverifiedBlock) -> Result<LockedBlock, ()> {
+	/// Verify the block against its parent. Returns the parent header.
+	fn check_block_family(&self, block: &PreverifiedBlock) -> Result<Bytes, ()> {
 		let engine = &*self.engine;
 		let header = &block.header;
 
@@ -291,24 +488,37 @@ impl Client {
 		let verify_family_result = self.verifier.verify_block_family(header, &block.bytes, engine, &**chain);
 		if let Err(e) = verify_family_result {
 			warn!(target: "client", "Stage 3 block verification failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
+			self.bad_blocks.note(&self.db.read(), &block.bytes, format!("Stage 3 verification failed: {:?}", e));
 			return Err(());
 		};
 
 		// Check if Parent is in chain
-		let chain_has_parent = chain.block_header(header.parent_hash());
-		if let None = chain_has_parent {
+		chain.block_header(header.parent_hash()).ok_or_else(|| {
 			warn!(target: "client", "Block import failed for #{} ({}): Parent not found ({}) ", header.number(), header.hash(), header.parent_hash());
-			return Err(());
-		};
+		})
+	}
+
+	fn check_and_close_block(&self, block: &PreverifiedBlock) -> Result<LockedBlock, ()> {
+		let engine = &*self.engine;
+		let header = &block.header;
+		let parent = try!(self.check_block_family(block));
 
 		// Enact Verified Block
-		let parent = chain_has_parent.unwrap();
 		let last_hashes = self.build_last_hashes(header.parent_hash().clone());
-		let db = self.state_db.lock().boxed_clone_canon(&header.parent_hash());
+		let mut db = self.state_db.lock().boxed_clone_canon(&header.parent_hash());
+		Self::restrict_account_bloom(&self.chain.read(), &mut db, header.parent_hash(), header.number() - 1);
+
+		// transactions are checked against the permission contract on top of the parent state.
+		if let Some(t) = block.transactions.iter().find(|t| !self.transaction_allowed_at(t, header.parent_hash())) {
+			warn!(target: "client", "Block import failed for #{} ({})\nTransaction {} is not allowed.", header.number(), header.hash(), t.hash());
+			self.bad_blocks.note(&self.db.read(), &block.bytes, format!("Transaction {} is not allowed", t.hash()));
+			return Err(());
+		}
 
 		let enact_result = enact_verified(block, engine, self.tracedb.read().tracing_enabled(), db, &parent, last_hashes, self.factories.clone());
 		if let Err(e) = enact_result {
 			warn!(target: "client", "Block import failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
+			self.bad_blocks.note(&self.db.read(), &block.bytes, format!("Execution failed: {:?}", e));
 			return Err(());
 		};
 
@@ -316,6 +526,7 @@ impl Client {
 		let locked_block = enact_result.unwrap();
 		if let Err(e) = self.verifier.verify_block_final(header, locked_block.block().header()) {
 			warn!(target: "client", "Stage 4 block verification failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
+			self.bad_blocks.note(&self.db.read(), &block.bytes, format!("Stage 4 verification failed: {:?}", e));
 			return Err(());
 		}
 
@@ -351,22 +562,51 @@ impl Client {
 	/// This is triggered by a message coming from a block queue when the block is ready for insertion
 	pub fn import_verified_blocks(&self) -> usize {
 		let max_blocks_to_import = 64;
+		self.note_failed_blocks();
 		let (imported_blocks, import_results, invalid_blocks, imported, duration) = {
 			let mut imported_blocks = Vec::with_capacity(max_blocks_to_import);
 			let mut invalid_blocks = HashSet::new();
 			let mut import_results = Vec::with_capacity(max_blocks_to_import);
+			let mut refused_blocks = Vec::new();
 
 			let _import_lock = self.import_lock.lock();
+			if self.shut_down.load(AtomicOrdering::SeqCst) {
+				return 0;
+			}
 			let _timer = PerfTimer::new("import_verified_blocks");
 			let start = precise_time_ns();
 			let blocks = self.block_queue.drain(max_blocks_to_import);
 
 			for block in blocks {
+				if !self.collect_root_check() {
+					break;
+				}
 				let header = &block.header;
 				if invalid_blocks.contains(header.parent_hash()) {
 					invalid_blocks.insert(header.hash());
 					continue;
 				}
+				if self.refuses_reorg(header) {
+					self.store_refused_reorg(header.hash(), header.number(), block.bytes.clone());
+					refused_blocks.push(header.hash());
+					continue;
+				}
+
+				let block_start = Instant::now();
+				if !self.keeps_state() {
+					if let Err(_) = self.check_block_family(&block) {
+						invalid_blocks.insert(header.hash());
+						continue;
+					}
+
+					imported_blocks.push(header.hash());
+					import_results.push(self.commit_relayed_block(&block));
+					BLOCK_IMPORT_TIME.observe_since(block_start);
+					BLOCKS_IMPORTED.inc();
+					self.report.write().accrue_block(&block);
+					continue;
+				}
+
 				let closed_block = self.check_and_close_block(&block);
 				if let Err(_) = closed_block {
 					invalid_blocks.insert(header.hash());
@@ -379,6 +619,9 @@ impl Client {
 				let route = self.commit_block(closed_block, &header.hash(), &block.bytes);
 				import_results.push(route);
 
+				BLOCK_IMPORT_TIME.observe_since(block_start);
+				BLOCKS_IMPORTED.inc();
+				TRANSACTIONS_APPLIED.inc_by(block.transactions.len());
 				self.report.write().accrue_block(&block);
 			}
 
@@ -392,6 +635,8 @@ impl Client {
 				if !imported_blocks.is_empty() {
 					self.block_queue.mark_as_good(&imported_blocks);
 				}
+				// refused blocks leave the queue so that they can be imported again when forced.
+				self.block_queue.mark_as_good(&refused_blocks);
 			}
 			let duration_ns = precise_time_ns() - start;
 			(imported_blocks, import_results, invalid_blocks, imported, duration_ns)
@@ -400,8 +645,13 @@ impl Client {
 		{
 			if !imported_blocks.is_empty() && self.block_queue.queue_info().is_empty() {
 				let (enacted, retracted) = self.calculate_enacted_retracted(&import_results);
+				if !retracted.is_empty() {
+					self.check_account_bloom();
+				}
 
-				if self.queue_info().is_empty() {
+				if self.relaying() {
+					self.remove_relayed_transactions(&enacted);
+				} else if self.keeps_state() && self.queue_info().is_empty() {
 					self.miner.chain_new_blocks(self, &imported_blocks, &invalid_blocks, &enacted, &retracted);
 				}
 
@@ -422,9 +672,107 @@ impl Client {
 		imported
 	}
 
+	/// Rebuild the account bloom in the background if it's saturated or does not cover the best block,
+	/// which happens after reorganizations to a branch forked off before the bloom was last rebuilt.
+	fn check_account_bloom(&self) {
+		let state_db = self.state_db.lock().boxed_clone();
+		let chain = self.chain.read();
+		let (hash, number) = (chain.best_block_hash(), chain.best_block_number());
+		let saturation = state_db.account_bloom_saturation();
+		let saturated = saturation > self.config.state_db.max_bloom_saturation;
+		let retracted = state_db.account_bloom_base().map_or(false, |base| !Self::account_bloom_covers(&chain, base, hash.clone(), number));
+		if !(saturated || retracted) {
+			return;
+		}
+
+		// the bloom is left as it is if the best state is missing; there is a warning for that on startup.
+		let root = match chain.block_header(&hash).map(|header| header.state_root().clone()) {
+			Some(ref root) if state_db.journal_db().contains(root) => root.clone(),
+			_ => return,
+		};
+		let rebuild = match state_db.begin_account_bloom_rebuild(&root, (number, hash), &self.config.state_db) {
+			Some(rebuild) => rebuild,
+			None => return,
+		};
+		match saturated {
+			true => info!("Account bloom is {:.0}% full, rebuilding it in the background", saturation * 100.0),
+			false => info!("Account bloom does not cover block #{}, rebuilding it in the background", number),
+		}
+		let spawned = thread::Builder::new().name("AccountBloom".into()).spawn(move || {
+			match rebuild.run() {
+				Ok(accounts) => info!("Account bloom rebuilt with {} accounts", accounts),
+				Err(e) => warn!("Error rebuilding the account bloom: {}. It will be rebuilt on the next restart.", e),
+			}
+		});
+		match spawned {
+			Ok(handle) => *self.account_bloom_rebuild.lock() = Some((number, handle)),
+			Err(e) => warn!("Error spawning the account bloom thread: {}", e),
+		}
+	}
+
+	/// Waits for the account bloom rebuild if pruning `era` would remove nodes of the state it walks.
+	/// Nodes of a block's state are only removed by pruning later eras.
+	fn unpin_account_bloom_state(&self, era: BlockNumber) {
+		let mut rebuild = self.account_bloom_rebuild.lock();
+		if !rebuild.as_ref().map_or(false, |&(base, _)| era > base) {
+			return;
+		}
+		let (_, handle) = rebuild.take().expect("checked to be some above; qed");
+		debug!(target: "client", "Waiting for the account bloom rebuild before pruning block #{}", era);
+		let _ = handle.join();
+	}
+
+	/// Whether the account bloom with the given base covers the state of block `hash` with the given number,
+	/// which is the case if the block is the base block or one of its descendants.
+	fn account_bloom_covers(chain: &BlockChain, base: (BlockNumber, H256), mut hash: H256, mut number: BlockNumber) -> bool {
+		let (base_number, base_hash) = base;
+		let best_number = chain.best_block_number();
+		loop {
+			if number <= base_number {
+				return number == base_number && hash == base_hash;
+			}
+			// hashes of retracted blocks above the best block are not removed on reorgs.
+			if number <= best_number && chain.block_hash(number).as_ref() == Some(&hash) {
+				return base_number <= best_number && chain.block_hash(base_number) == Some(base_hash);
+			}
+			match chain.block_details(&hash) {
+				Some(details) => {
+					hash = details.parent;
+					number -= 1;
+				},
+				None => return false,
+			}
+		}
+	}
+
+	/// Collect the result of the running state root check, which must be done before the next block
+	/// is committed. On a mismatch the checked block is reported as bad and block import stops, since
+	/// the states of all following blocks would be wrong as well. Returns whether blocks may be imported.
+	fn collect_root_check(&self) -> bool {
+		if let Some(ref root_check) = self.root_check {
+			if let Err((hash, e)) = root_check.wait() {
+				error!(target: "client", "{}. Block import is stopped, the database needs to be repaired.", e);
+				if let Some(bytes) = self.chain.read().block(&hash) {
+					self.bad_blocks.note(&self.db.read(), &bytes, e);
+				}
+				self.import_stopped.store(true, AtomicOrdering::SeqCst);
+			}
+		}
+		!self.import_stopped.load(AtomicOrdering::SeqCst)
+	}
+
+	/// Look up missing accounts in the trie if the account bloom does not cover the state of the given block.
+	fn restrict_account_bloom(chain: &BlockChain, db: &mut StateDB, hash: &H256, number: BlockNumber) {
+		let covered = db.account_bloom_base().map_or(true, |base| Self::account_bloom_covers(chain, base, hash.clone(), number));
+		if !covered {
+			db.disable_account_bloom();
+		}
+	}
+
 	fn commit_block<B>(&self, block: B, hash: &H256, block_data: &[u8]) -> ImportRoute where B: IsBlock + Drain {
 		let number = block.header().number();
 		let parent = block.header().parent_hash().clone();
+		let state_root = block.header().state_root().clone();
 		let chain = self.chain.read();
 		// Are we committing an era?
 		let ancient = if number >= HISTORY {
@@ -448,6 +796,9 @@ impl Client {
 		// already-imported block of the same number.
 		// TODO: Prove it with a test.
 		let mut state = block.drain();
+		if let Some(&(era, _)) = ancient.as_ref() {
+			self.unpin_account_bloom_state(era);
+		}
 		state.commit(&mut batch, number, hash, ancient).expect("DB commit failed.");
 
 		let route = chain.insert_block(&mut batch, block_data, receipts);
@@ -463,10 +814,98 @@ impl Client {
 		// Final commit to the DB
 		self.db.read().write_buffered(batch);
 		chain.commit();
+		if let Some(ref root_check) = self.root_check {
+			root_check.start(state.journal_db().boxed_clone(), number, hash.clone(), state_root);
+		}
 		self.update_last_hashes(&parent, hash);
 		route
 	}
 
+	/// Whether importing the block with `header` would reorganize the chain deeper than
+	/// `max_reorg_depth`. Descendants of refused blocks are refused as well, while blocks
+	/// passed to `force_reorg` are allowed once.
+	fn refuses_reorg(&self, header: &Header) -> bool {
+		let max_depth = match self.config.max_reorg_depth {
+			Some(depth) => depth,
+			None => return false,
+		};
+		if self.refused_reorgs.lock().contains_key(header.parent_hash()) {
+			return true;
+		}
+		if self.forced_reorgs.lock().remove(&header.hash()) {
+			return false;
+		}
+
+		let chain = self.chain.read();
+		let parent_td = match chain.block_details(header.parent_hash()) {
+			Some(details) => details.total_difficulty,
+			None => return false,
+		};
+		if parent_td + *header.difficulty() <= chain.best_block_total_difficulty() {
+			return false;
+		}
+
+		let depth = chain.tree_route(chain.best_block_hash(), header.parent_hash().clone()).index as u64;
+		if depth <= max_depth {
+			return false;
+		}
+		warn!(target: "client", "Refusing to reorganize {} blocks deep to #{} ({}). Use parity_forceReorg to import it.", depth, header.number(), header.hash());
+		true
+	}
+
+	/// Whether the chain's transaction permission contract, if any, allows `t` on top of the block `parent`.
+	fn transaction_allowed_at(&self, t: &SignedTransaction, parent: &H256) -> bool {
+		let contract = match self.engine.params().transaction_permission_contract {
+			Some(contract) => contract,
+			None => return true,
+		};
+		let block_id = BlockID::Hash(parent.clone());
+		let has_code = match t.action {
+			Action::Call(ref address) => self.code(address, block_id).map_or(false, |code| code.is_some()),
+			Action::Create => false,
+		};
+		self.tx_permission.is_allowed(contract, parent, t, has_code, |address, data| self.call_contract(block_id, address, data))
+	}
+
+	/// Store the blocks which failed verification in the queue as bad blocks.
+	fn note_failed_blocks(&self) {
+		for (bytes, reason) in self.block_queue.take_failed() {
+			self.bad_blocks.note(&self.db.read(), &bytes, reason);
+		}
+	}
+
+	fn store_refused_reorg(&self, hash: H256, number: BlockNumber, bytes: Bytes) {
+		let mut refused = self.refused_reorgs.lock();
+		if refused.len() >= MAX_REFUSED_REORGS {
+			warn!(target: "client", "Too many refused blocks, dropping #{} ({})", number, hash);
+			return;
+		}
+		refused.insert(hash, bytes);
+	}
+
+	/// Store a block in relay or headers-only mode. The block is not executed, so no state, receipts or traces are written.
+	fn commit_relayed_block(&self, block: &PreverifiedBlock) -> ImportRoute {
+		let hash = block.header.hash();
+		let chain = self.chain.read();
+
+		let mut batch = DBTransaction::new(&self.db.read());
+		let route = chain.insert_block(&mut batch, &block.bytes, Vec::new());
+		self.db.read().write_buffered(batch);
+		chain.commit();
+		self.update_last_hashes(block.header.parent_hash(), &hash);
+		route
+	}
+
+	/// Drop relayed transactions included in the given blocks.
+	fn remove_relayed_transactions(&self, blocks: &[H256]) {
+		let chain = self.chain.read();
+		let hashes: Vec<H256> = blocks.iter()
+			.filter_map(|hash| chain.block_body(hash))
+			.flat_map(|body| BodyView::new(&body).transaction_hashes())
+			.collect();
+		self.relayed_transactions.lock().remove_all(&hashes);
+	}
+
 	fn update_last_hashes(&self, parent: &H256, hash: &H256) {
 		let mut hashes = self.last_hashes.write();
 		if hashes.front().map_or(false, |h| h == parent) {
@@ -477,24 +916,102 @@ impl Client {
 		}
 	}
 
+	/// The provider handling private transactions, if any.
+	fn private_transactions(&self) -> Option<Arc<PrivateTransactions>> {
+		self.private_transactions.read().as_ref().and_then(|provider| provider.upgrade())
+	}
+
+	/// Send a private transaction to the peers.
+	pub fn broadcast_private_transaction(&self, packet: Bytes) {
+		self.notify(|notify| notify.broadcast_private_transaction(packet.clone()));
+	}
+
+	/// Send a validator's signature of a private transaction to the peers.
+	pub fn broadcast_signed_private_transaction(&self, packet: Bytes) {
+		self.notify(|notify| notify.broadcast_signed_private_transaction(packet.clone()));
+	}
+
+	/// Execute `t` on top of the state at `block` with the account at `contract` replaced by a
+	/// contract with the given code and storage. Nothing is committed. Returns the result of
+	/// the execution and the contract storage afterwards.
+	pub fn call_private_contract(&self, t: &SignedTransaction, block: BlockID, contract: &Address, code: Bytes, storage: BTreeMap<H256, H256>) -> Result<(Executed, BTreeMap<H256, H256>), CallError> {
+		let env_info = try!(self.call_env_info(block).ok_or(CallError::StatePruned));
+		let mut state = try!(self.state_at(block).ok_or(CallError::StatePruned));
+		let balance = state.balance(contract);
+		state.new_contract(contract, balance);
+		state.init_code(contract, code);
+		for (key, value) in storage {
+			state.set_storage(contract, key, value);
+		}
+
+		try!(Self::fund_sender(&mut state, t));
+		let options = TransactOptions { tracing: false, vm_tracing: false, check_nonce: false };
+		let executed = try!(Executive::new(&mut state, &env_info, &*self.engine, &self.factories.vm).transact(t, options));
+		let storage = state.to_pod().get().get(contract).map_or_else(BTreeMap::new, |account| account.storage.iter()
+			.filter(|&(_, value)| !value.is_zero())
+			.map(|(key, value)| (key.clone(), value.clone()))
+			.collect());
+		Ok((executed, storage))
+	}
+
+	/// Environment of calls made on top of the state at `block`.
+	fn call_env_info(&self, block: BlockID) -> Option<EnvInfo> {
+		self.block_header(block).map(|header| {
+			let view = HeaderView::new(&header);
+			EnvInfo {
+				number: view.number(),
+				author: view.author(),
+				timestamp: view.timestamp(),
+				difficulty: view.difficulty(),
+				last_hashes: self.build_last_hashes(view.hash()),
+				gas_used: U256::zero(),
+				gas_limit: U256::max_value(),
+			}
+		})
+	}
+
+	/// Give the sender of a call a sufficient balance to execute it.
+	fn fund_sender(state: &mut State, t: &SignedTransaction) -> Result<(), CallError> {
+		let sender = try!(t.sender().map_err(|e| {
+			let message = format!("Transaction malformed: {:?}", e);
+			ExecutionError::TransactionMalformed(message)
+		}));
+		let balance = state.balance(&sender);
+		let needed_balance = t.value + t.gas * t.gas_price;
+		if balance < needed_balance {
+			state.add_balance(&sender, &(needed_balance - balance));
+		}
+		Ok(())
+	}
+
 	/// Import transactions from the IO queue
 	pub fn import_queued_transactions(&self, transactions: &[Bytes]) -> usize {
 		let _timer = PerfTimer::new("import_queued_transactions");
 		self.queue_transactions.fetch_sub(transactions.len(), AtomicOrdering::SeqCst);
-		let txs = transactions.iter().filter_map(|bytes| UntrustedRlp::new(bytes).as_val().ok()).collect();
+		let txs: Vec<SignedTransaction> = transactions.iter().filter_map(|bytes| UntrustedRlp::new(bytes).as_val().ok()).collect();
+		if self.relaying() {
+			return self.relay_transactions(txs);
+		}
+		if !self.keeps_state() {
+			// transactions can't be verified without state.
+			return 0;
+		}
 		let results = self.miner.import_external_transactions(self, txs);
 		results.len()
 	}
 
 	/// Attempt to get a copy of a specific block's final state.
 	///
-	/// This will not fail if given BlockID::Latest.
+	/// This will not fail if given BlockID::Latest, unless the client runs in relay mode.
 	/// Otherwise, this can fail (but may not) if the DB prunes state.
 	pub fn state_at(&self, id: BlockID) -> Option<State> {
 		// fast path for latest state.
 		match id.clone() {
-			BlockID::Pending => return self.miner.pending_state().or_else(|| Some(self.state())),
-			BlockID::Latest => return Some(self.state()),
+			BlockID::Pending => {
+				let best_block_number = self.chain.read().best_block_number();
+				return self.miner.pending_state(best_block_number).or_else(|| self.state());
+			},
+			BlockID::Latest => return self.state(),
 			_ => {},
 		}
 
@@ -504,16 +1021,26 @@ impl Client {
 		};
 
 		self.block_header(id).and_then(|header| {
-			let db = self.state_db.lock().boxed_clone();
-
-			// early exit for pruned blocks
-			if db.is_pruned() && self.chain.read().best_block_number() >= block_number + HISTORY {
-				return None;
+			let header = HeaderView::new(&header);
+			let mut db = self.state_db.lock().boxed_clone();
+			let root = header.state_root();
+			Self::restrict_account_bloom(&self.chain.read(), &mut db, &header.hash(), block_number);
+
+			// pruned blocks are only available from the cold storage.
+			let pruned = db.is_pruned() && self.chain.read().best_block_number() >= block_number + HISTORY;
+			if !pruned && db.journal_db().contains(&root) {
+				return State::from_existing(db, root, self.engine.account_start_nonce(), self.factories.clone()).ok();
 			}
 
-			let root = HeaderView::new(&header).state_root();
-
-			State::from_existing(db, root, self.engine.account_start_nonce(), self.factories.clone()).ok()
+			self.cold_state_db.as_ref()
+				.map(|cold| cold.lock().boxed_clone())
+				.and_then(|mut cold| {
+					Self::restrict_account_bloom(&self.chain.read(), &mut cold, &header.hash(), block_number);
+					match cold.journal_db().contains(&root) {
+						true => State::from_existing(cold, root, self.engine.account_start_nonce(), self.factories.clone()).ok(),
+						false => None,
+					}
+				})
 		})
 	}
 
@@ -532,16 +1059,117 @@ impl Client {
 		}
 	}
 
+	/// Storage keys of the account in the given state, if fat DB is in operation.
+	fn storage_keys(&self, state: State, account: &Address) -> Option<Vec<H256>> {
+		let root = match state.storage_root(account) {
+			Some(root) => root,
+			_ => return None,
+		};
+
+		let (_, db) = state.drop();
+		let account_db = self.factories.accountdb.readonly(db.as_hashdb(), account.sha3());
+		let trie = match self.factories.trie.readonly(account_db.as_hashdb(), &root) {
+			Ok(trie) => trie,
+			_ => {
+				trace!(target: "fatdb", "list_storage: Couldn't open the DB");
+				return None;
+			}
+		};
+
+		let iter = match trie.iter() {
+			Ok(iter) => iter,
+			_ => return None,
+		};
+
+		let keys = iter.filter_map(|item| {
+			item.ok().map(|(key, _)| H256::from_slice(&key))
+		}).collect();
+
+		Some(keys)
+	}
+
+	/// State of the canonical block `number`. A pruned state is rebuilt by replaying blocks
+	/// on top of `base`, a state of an earlier block, or else of the closest ancestor whose
+	/// state is available, at most `MAX_STATE_DIFF_REPLAY` blocks back.
+	fn replayed_state_at(&self, number: BlockNumber, base: Option<(BlockNumber, State)>) -> Option<State> {
+		if let Some(state) = self.state_at(BlockID::Number(number)) {
+			return Some(state);
+		}
+
+		let (mut base_number, mut state) = match base {
+			Some(base) => base,
+			None => {
+				let earliest = number.saturating_sub(MAX_STATE_DIFF_REPLAY);
+				let found = (earliest..number).rev()
+					.filter_map(|n| self.state_at(BlockID::Number(n)).map(|state| (n, state)))
+					.next();
+				match found {
+					Some(found) => found,
+					None => return None,
+				}
+			},
+		};
+		if number - base_number > MAX_STATE_DIFF_REPLAY {
+			return None;
+		}
+
+		while base_number < number {
+			base_number += 1;
+			state = match self.replay_block(base_number, state) {
+				Some(state) => state,
+				None => return None,
+			};
+		}
+		Some(state)
+	}
+
+	/// Execute the canonical block `number` on top of its parent's state.
+	fn replay_block(&self, number: BlockNumber, parent_state: State) -> Option<State> {
+		let (block, parent) = {
+			let chain = self.chain.read();
+			let block = match chain.block_hash(number).and_then(|hash| chain.block(&hash)) {
+				Some(block) => block,
+				None => return None,
+			};
+			let parent = match chain.block_header(&BlockView::new(&block).header_view().parent_hash()) {
+				Some(parent) => parent,
+				None => return None,
+			};
+			(block, parent)
+		};
+
+		let view = BlockView::new(&block);
+		let header = view.header();
+		let (_, db) = parent_state.drop();
+		let last_hashes = self.build_last_hashes(header.parent_hash().clone());
+		let locked = match enact(&header, &view.transactions(), &view.uncles(), &*self.engine, false, db, &parent, last_hashes, self.factories.clone()) {
+			Ok(locked) => locked,
+			Err(e) => {
+				warn!(target: "client", "Replaying block #{} failed: {:?}", number, e);
+				return None;
+			}
+		};
+		if locked.block().header().state_root() != header.state_root() {
+			warn!(target: "client", "Replaying block #{} resulted in a different state root", number);
+			return None;
+		}
+
+		State::from_existing(locked.drain(), header.state_root().clone(), self.engine.account_start_nonce(), self.factories.clone()).ok()
+	}
+
 	/// Get a copy of the best block's state.
-	pub fn state(&self) -> State {
+	/// None in relay mode, where blocks are imported without building their state.
+	pub fn state(&self) -> Option<State> {
 		let header = self.best_block_header();
 		let header = HeaderView::new(&header);
+		let mut db = self.state_db.lock().boxed_clone_canon(&header.hash());
+		Self::restrict_account_bloom(&self.chain.read(), &mut db, &header.hash(), header.number());
 		State::from_existing(
-			self.state_db.lock().boxed_clone_canon(&header.hash()),
+			db,
 			header.state_root(),
 			self.engine.account_start_nonce(),
 			self.factories.clone())
-		.expect("State root of best block header always valid.")
+		.ok()
 	}
 
 	/// Get info on the cache.
@@ -549,6 +1177,19 @@ impl Client {
 		self.chain.read().cache_size()
 	}
 
+	/// Lend half of the memory which the blockchain cache and the verification queue
+	/// are currently not using to the state cache. The loan is returned as soon as
+	/// their usage grows again, so the total stays within the configured budget.
+	fn rebalance_caches(&self) {
+		let queue_info = self.block_queue.queue_info();
+		let chain_cache = self.chain.read().cache_size().total();
+		let idle = queue_info.max_mem_use.saturating_sub(queue_info.mem_used)
+			+ self.config.blockchain.max_cache_size.saturating_sub(chain_cache);
+
+		let state_cache_size = self.config.state_cache_size.unwrap_or_else(StateDB::default_cache_size);
+		self.state_db.lock().set_cache_size(state_cache_size + idle / 2);
+	}
+
 	/// Get the report.
 	pub fn report(&self) -> ClientReport {
 		let mut report = self.report.read().clone();
@@ -562,8 +1203,12 @@ impl Client {
 		self.chain.read().collect_garbage();
 		self.block_queue.collect_garbage();
 		self.tracedb.read().collect_garbage();
+		self.chain.read().move_ancient_blocks(ANCIENT_BLOCKS_PER_TICK);
+		self.chain.read().update_chts(CHTS_PER_TICK);
+		self.rebalance_caches();
 
-		match self.mode {
+		let mode = self.mode.lock().clone();
+		match mode {
 			Mode::Dark(timeout) => {
 				let mut ss = self.sleep_state.lock();
 				if let Some(t) = ss.last_activity {
@@ -591,6 +1236,8 @@ impl Client {
 					}
 				}
 			}
+			// sleeping is refused while blocks are being imported, so keep trying.
+			Mode::Off => self.sleep(),
 			_ => {}
 		}
 	}
@@ -641,6 +1288,8 @@ impl Client {
 	fn block_hash(chain: &BlockChain, id: BlockID) -> Option<H256> {
 		match id {
 			BlockID::Hash(hash) => Some(hash),
+			// hashes of retracted blocks above the best block are not removed on reorgs.
+			BlockID::Number(number) if number > chain.best_block_number() => None,
 			BlockID::Number(number) => chain.block_hash(number),
 			BlockID::Earliest => chain.block_hash(0),
 			BlockID::Latest | BlockID::Pending => Some(chain.best_block_hash()),
@@ -657,6 +1306,11 @@ impl Client {
 		}
 	}
 
+	/// Whether the client runs in relay mode, without keeping any state.
+	fn relaying(&self) -> bool {
+		*self.mode.lock() == Mode::Relay
+	}
+
 	fn wake_up(&self) {
 		if !self.liveness.load(AtomicOrdering::Relaxed) {
 			self.liveness.store(true, AtomicOrdering::Relaxed);
@@ -694,8 +1348,9 @@ impl snapshot::DatabaseRestore for Client {
 		let db = self.db.write();
 		try!(db.restore(new_db));
 
-		*state_db = StateDB::new(journaldb::new(db.clone(), self.pruning, ::db::COL_STATE));
-		*chain = Arc::new(BlockChain::new(self.config.blockchain.clone(), &[], db.clone()));
+		let state_cache_size = self.config.state_cache_size.unwrap_or_else(StateDB::default_cache_size);
+		*state_db = StateDB::with_config(journaldb::new(db.clone(), self.pruning, ::db::COL_STATE), state_cache_size, &self.config.state_db);
+		*chain = Arc::new(BlockChain::with_ancient(self.config.blockchain.clone(), &[], db.clone(), self.ancient_db.clone()));
 		*tracedb = TraceDB::new(self.config.tracing.clone(), db.clone(), chain.clone());
 		Ok(())
 	}
@@ -704,32 +1359,12 @@ impl snapshot::DatabaseRestore for Client {
 
 impl BlockChainClient for Client {
 	fn call(&self, t: &SignedTransaction, block: BlockID, analytics: CallAnalytics) -> Result<Executed, CallError> {
-		let header = try!(self.block_header(block).ok_or(CallError::StatePruned));
-		let view = HeaderView::new(&header);
-		let last_hashes = self.build_last_hashes(view.hash());
-		let env_info = EnvInfo {
-			number: view.number(),
-			author: view.author(),
-			timestamp: view.timestamp(),
-			difficulty: view.difficulty(),
-			last_hashes: last_hashes,
-			gas_used: U256::zero(),
-			gas_limit: U256::max_value(),
-		};
+		let env_info = try!(self.call_env_info(block).ok_or(CallError::StatePruned));
 		// that's just a copy of the state.
 		let mut state = try!(self.state_at(block).ok_or(CallError::StatePruned));
 		let original_state = if analytics.state_diffing { Some(state.clone()) } else { None };
 
-		let sender = try!(t.sender().map_err(|e| {
-			let message = format!("Transaction malformed: {:?}", e);
-			ExecutionError::TransactionMalformed(message)
-		}));
-		let balance = state.balance(&sender);
-		let needed_balance = t.value + t.gas * t.gas_price;
-		if balance < needed_balance {
-			// give the sender a sufficient balance
-			state.add_balance(&sender, &(needed_balance - balance));
-		}
+		try!(Self::fund_sender(&mut state, t));
 		let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false };
 		let mut ret = try!(Executive::new(&mut state, &env_info, &*self.engine, &self.factories.vm).transact(t, options));
 
@@ -777,8 +1412,48 @@ impl BlockChainClient for Client {
 		Ok(ret)
 	}
 
+	fn state_diff(&self, from: BlockID, to: BlockID, addresses: Vec<Address>) -> Result<StateDiff, StateDiffError> {
+		// storage keys can only be enumerated with fat DB
+		if !self.factories.trie.is_fat() {
+			return Err(StateDiffError::FatDbRequired);
+		}
+
+		let from = try!(self.block_number(from).ok_or(StateDiffError::BlockNotFound));
+		let to = try!(self.block_number(to).ok_or(StateDiffError::BlockNotFound));
+		if self.block_hash(BlockID::Number(from)).is_none() || self.block_hash(BlockID::Number(to)).is_none() {
+			return Err(StateDiffError::BlockNotFound);
+		}
+		if from > to {
+			return Err(StateDiffError::InvalidRange);
+		}
+
+		let pre = try!(self.replayed_state_at(from, None).ok_or(StateDiffError::StatePruned));
+		let post = try!(self.replayed_state_at(to, Some((from, pre.clone()))).ok_or(StateDiffError::StatePruned));
+
+		let addresses: HashSet<Address> = addresses.into_iter().collect();
+		let mut pre_pod = BTreeMap::new();
+		let mut post_pod = BTreeMap::new();
+		for address in addresses {
+			let keys: HashSet<H256> = self.storage_keys(pre.clone(), &address).unwrap_or_default().into_iter()
+				.chain(self.storage_keys(post.clone(), &address).unwrap_or_default().into_iter())
+				.collect();
+			if let Some(account) = pod_account(&pre, &address, &keys) {
+				pre_pod.insert(address.clone(), account);
+			}
+			if let Some(account) = pod_account(&post, &address, &keys) {
+				post_pod.insert(address, account);
+			}
+		}
+
+		Ok(pod_state::diff_pod(&PodState::from(pre_pod), &PodState::from(post_pod)))
+	}
+
 	fn keep_alive(&self) {
-		if self.mode != Mode::Active {
+		let should_wake = match *self.mode.lock() {
+			Mode::Dark(..) | Mode::Passive(..) => true,
+			_ => false,
+		};
+		if should_wake {
 			self.wake_up();
 			(*self.sleep_state.lock()).last_activity = Some(Instant::now());
 		}
@@ -789,18 +1464,32 @@ impl BlockChainClient for Client {
 	}
 
 	fn block_header(&self, id: BlockID) -> Option<Bytes> {
+		if let BlockID::Pending = id {
+			return self.block(id).map(|bytes| ::rlp::Rlp::new(&bytes).at(0).as_raw().to_vec());
+		}
 		let chain = self.chain.read();
 		Self::block_hash(&chain, id).and_then(|hash| chain.block_header_data(&hash))
 	}
 
 	fn block_body(&self, id: BlockID) -> Option<Bytes> {
+		if let BlockID::Pending = id {
+			use rlp::{Rlp, RlpStream, Stream};
+			return self.block(id).map(|bytes| {
+				let rlp = Rlp::new(&bytes);
+				let mut body = RlpStream::new_list(2);
+				body.append_raw(rlp.at(1).as_raw(), 1);
+				body.append_raw(rlp.at(2).as_raw(), 1);
+				body.out()
+			});
+		}
 		let chain = self.chain.read();
 		Self::block_hash(&chain, id).and_then(|hash| chain.block_body(&hash))
 	}
 
 	fn block(&self, id: BlockID) -> Option<Bytes> {
 		if let BlockID::Pending = id {
-			if let Some(block) = self.miner.pending_block() {
+			let best_block_number = self.chain.read().best_block_number();
+			if let Some(block) = self.miner.pending_block(best_block_number) {
 				return Some(block.rlp_bytes(Seal::Without));
 			}
 		}
@@ -821,7 +1510,8 @@ impl BlockChainClient for Client {
 
 	fn block_total_difficulty(&self, id: BlockID) -> Option<U256> {
 		if let BlockID::Pending = id {
-			if let Some(block) = self.miner.pending_block() {
+			let best_block_number = self.chain.read().best_block_number();
+			if let Some(block) = self.miner.pending_block(best_block_number) {
 				return Some(*block.header.difficulty() + self.block_total_difficulty(BlockID::Latest).expect("blocks in chain have details; qed"));
 			}
 		}
@@ -850,7 +1540,7 @@ impl BlockChainClient for Client {
 		self.state_at(id).map(|s| s.storage_at(address, position))
 	}
 
-	fn list_accounts(&self, id: BlockID) -> Option<Vec<Address>> {
+	fn list_accounts(&self, id: BlockID, after: Option<&Address>, count: u64) -> Option<Vec<Address>> {
 		if !self.factories.trie.is_fat() {
 			trace!(target: "fatdb", "list_accounts: Not a fat DB");
 			return None;
@@ -862,7 +1552,7 @@ impl BlockChainClient for Client {
 		};
 
 		let (root, db) = state.drop();
-		let trie = match self.factories.trie.readonly(db.as_hashdb(), &root) {
+		let trie = match FatDB::new(db.as_hashdb(), &root) {
 			Ok(trie) => trie,
 			_ => {
 				trace!(target: "fatdb", "list_accounts: Couldn't open the DB");
@@ -870,16 +1560,37 @@ impl BlockChainClient for Client {
 			}
 		};
 
-		let iter = match trie.iter() {
+		let mut iter = match trie.iter_preimages() {
 			Ok(iter) => iter,
 			_ => return None,
 		};
 
+		// accounts are ordered by address hash, so paging seeks to the hash of `after`
+		if let Some(after) = after {
+			if iter.seek(after).is_err() {
+				return None;
+			}
+		}
+
 		let accounts = iter.filter_map(|item| {
 			item.ok().map(|(addr, _)| Address::from_slice(&addr))
-		}).collect();
+		});
+		Some(accounts
+			.skip_while(|address| Some(address) == after)
+			.take(count as usize)
+			.collect())
+	}
+
+	fn list_storage(&self, id: BlockID, account: &Address) -> Option<Vec<H256>> {
+		if !self.factories.trie.is_fat() {
+			trace!(target: "fatdb", "list_storage: Not a fat DB");
+			return None;
+		}
 
-		Some(accounts)
+		match self.state_at(id) {
+			Some(state) => self.storage_keys(state, account),
+			_ => None,
+		}
 	}
 
 	fn transaction(&self, id: TransactionID) -> Option<LocalizedTransaction> {
@@ -887,8 +1598,8 @@ impl BlockChainClient for Client {
 	}
 
 	fn uncle(&self, id: UncleID) -> Option<Bytes> {
-		let index = id.position;
-		self.block_body(id.block).and_then(|body| BodyView::new(&body).uncle_rlp_at(index))
+		let chain = self.chain.read();
+		Self::block_hash(&chain, id.block).and_then(|hash| chain.uncle(&hash, id.position))
 	}
 
 	fn transaction_receipt(&self, id: TransactionID) -> Option<LocalizedReceipt> {
@@ -902,10 +1613,6 @@ impl BlockChainClient for Client {
 
 			match (t, chain.transaction_receipt(&address)) {
 				(Some(tx), Some(receipt)) => {
-					let block_hash = tx.block_hash.clone();
-					let block_number = tx.block_number.clone();
-					let transaction_hash = tx.hash();
-					let transaction_index = tx.transaction_index;
 					let prior_gas_used = match tx.transaction_index {
 						0 => U256::zero(),
 						i => {
@@ -914,32 +1621,36 @@ impl BlockChainClient for Client {
 							prior_receipt.gas_used
 						}
 					};
-					Some(LocalizedReceipt {
-						transaction_hash: tx.hash(),
-						transaction_index: tx.transaction_index,
-						block_hash: tx.block_hash,
-						block_number: tx.block_number,
-						cumulative_gas_used: receipt.gas_used,
-						gas_used: receipt.gas_used - prior_gas_used,
-						contract_address: match tx.action {
-							Action::Call(_) => None,
-							Action::Create => Some(contract_address(&tx.sender().unwrap(), &tx.nonce))
-						},
-						logs: receipt.logs.into_iter().enumerate().map(|(i, log)| LocalizedLogEntry {
-							entry: log,
-							block_hash: block_hash.clone(),
-							block_number: block_number,
-							transaction_hash: transaction_hash.clone(),
-							transaction_index: transaction_index,
-							log_index: i
-						}).collect()
-					})
+					Some(localized_receipt(tx, receipt, prior_gas_used))
 				},
 				_ => None
 			}
 		}))
 	}
 
+	fn block_receipts_localized(&self, id: BlockID) -> Option<Vec<LocalizedReceipt>> {
+		let chain = self.chain.read();
+		let hash = match Self::block_hash(&chain, id) {
+			Some(hash) => hash,
+			None => return None,
+		};
+		let (number, body, receipts) = match (chain.block_number(&hash), chain.block_body(&hash), chain.block_receipts(&hash)) {
+			(Some(number), Some(body), Some(receipts)) => (number, body, receipts),
+			_ => return None,
+		};
+
+		let mut prior_gas_used = U256::zero();
+		Some(BodyView::new(&body).localized_transactions(&hash, number).into_iter()
+			.zip(receipts.receipts.into_iter())
+			.map(|(tx, receipt)| {
+				let gas_used = receipt.gas_used;
+				let localized = localized_receipt(tx, receipt, prior_gas_used);
+				prior_gas_used = gas_used;
+				localized
+			})
+			.collect())
+	}
+
 	fn tree_route(&self, from: &H256, to: &H256) -> Option<TreeRoute> {
 		let chain = self.chain.read();
 		match chain.is_known(from) && chain.is_known(to) {
@@ -957,6 +1668,10 @@ impl BlockChainClient for Client {
 	}
 
 	fn block_receipts(&self, hash: &H256) -> Option<Bytes> {
+		// blocks are not executed in relay mode, so there are no receipts to serve
+		if self.relaying() {
+			return None;
+		}
 		self.chain.read().block_receipts(hash).map(|receipts| ::rlp::encode(&receipts).to_vec())
 	}
 
@@ -964,6 +1679,17 @@ impl BlockChainClient for Client {
 		use verification::queue::kind::HasHash;
 		use verification::queue::kind::blocks::Unverified;
 
+		// children of refused blocks would be unknown to the chain, keep them until the reorganization is forced.
+		{
+			let header = BlockView::new(&bytes).header_view();
+			let refused_parent = self.refused_reorgs.lock().contains_key(&header.parent_hash());
+			if refused_parent {
+				let hash = header.sha3();
+				self.store_refused_reorg(hash.clone(), header.number(), bytes.clone());
+				return Ok(hash);
+			}
+		}
+
 		// create unverified block here so the `sha3` calculation can be cached.
 		let unverified = Unverified::new(bytes);
 
@@ -975,7 +1701,11 @@ impl BlockChainClient for Client {
 				return Err(BlockImportError::Block(BlockError::UnknownParent(unverified.parent_hash())));
 			}
 		}
-		Ok(try!(self.block_queue.import(unverified)))
+		let result = self.block_queue.import(unverified);
+		if result.is_err() {
+			self.note_failed_blocks();
+		}
+		Ok(try!(result))
 	}
 
 	fn queue_info(&self) -> BlockQueueInfo {
@@ -993,7 +1723,8 @@ impl BlockChainClient for Client {
 			pending_total_difficulty: chain.best_block_total_difficulty(),
 			genesis_hash: chain.genesis_hash(),
 			best_block_hash: chain.best_block_hash(),
-			best_block_number: From::from(chain.best_block_number())
+			best_block_number: From::from(chain.best_block_number()),
+			first_block_number: chain.first_block_number(),
 		}
 	}
 
@@ -1001,6 +1732,146 @@ impl BlockChainClient for Client {
 		self.engine.additional_params().into_iter().collect()
 	}
 
+	fn engine_name(&self) -> String {
+		self.engine.name().to_owned()
+	}
+
+	fn mode(&self) -> IpcMode {
+		self.mode.lock().clone().into()
+	}
+
+	fn set_mode(&self, new_mode: IpcMode) {
+		let new_mode: Mode = new_mode.into();
+		{
+			let mut mode = self.mode.lock();
+			if *mode == Mode::Relay || new_mode == Mode::Relay {
+				warn!(target: "mode", "Can't switch from {:?} to {:?}: relay mode can only be set on startup.", *mode, new_mode);
+				return;
+			}
+			trace!(target: "mode", "set_mode: {:?} -> {:?}", *mode, new_mode);
+			*mode = new_mode.clone();
+		}
+
+		match new_mode {
+			Mode::Active => self.wake_up(),
+			Mode::Off => self.sleep(),
+			_ => (*self.sleep_state.lock()).last_activity = Some(Instant::now()),
+		}
+	}
+
+	fn bad_blocks(&self) -> Vec<BadBlock> {
+		self.bad_blocks.blocks(&self.db.read())
+	}
+
+	fn keeps_state(&self) -> bool {
+		!self.relaying() && self.config.blockchain.store_bodies
+	}
+
+	fn stores_bodies(&self) -> bool {
+		self.config.blockchain.store_bodies
+	}
+
+	fn cht_root(&self, index: u64) -> Option<H256> {
+		self.chain.read().cht_root(index)
+	}
+
+	fn force_reorg(&self, hash: H256) -> bool {
+		let blocks = {
+			let mut refused = self.refused_reorgs.lock();
+			let block = match refused.remove(&hash) {
+				Some(block) => block,
+				None => return false,
+			};
+
+			// refused descendants are imported after their parents.
+			let mut blocks = vec![block];
+			let mut parents = HashSet::new();
+			parents.insert(hash.clone());
+			loop {
+				let children: Vec<H256> = refused.iter()
+					.filter(|&(_, bytes)| parents.contains(&BlockView::new(bytes).header_view().parent_hash()))
+					.map(|(hash, _)| hash.clone())
+					.collect();
+				if children.is_empty() {
+					break;
+				}
+				for child in children {
+					blocks.push(refused.remove(&child).expect("child was found in refused blocks; qed"));
+					parents.insert(child);
+				}
+			}
+			blocks
+		};
+
+		info!(target: "client", "Forcing reorganization to {} ({} blocks)", hash, blocks.len());
+		self.forced_reorgs.lock().insert(hash);
+		for block in blocks {
+			if let Err(e) = self.import_block(block) {
+				warn!(target: "client", "Error importing forced block: {:?}", e);
+			}
+		}
+		true
+	}
+
+	fn registry_address(&self, name: String) -> Option<Address> {
+		let registrar = match self.additional_params().get("registrar").and_then(|a| a.parse().ok()) {
+			Some(registrar) => registrar,
+			None => return None,
+		};
+
+		match self.registrar.get_address(registrar, &name, |address, data| self.call_contract(BlockID::Latest, address, data)) {
+			Ok(address) => address,
+			Err(e) => {
+				warn!(target: "client", "Error resolving {} in the registrar: {}", name, e);
+				None
+			}
+		}
+	}
+
+	fn call_contract(&self, block_id: BlockID, address: Address, data: Bytes) -> Result<Bytes, String> {
+		let from = Address::default();
+		let nonce = try!(self.nonce(&from, block_id).ok_or_else(|| "State is not available.".to_owned()));
+		let transaction = Transaction {
+			nonce: nonce,
+			action: Action::Call(address),
+			gas: U256::from(CONTRACT_CALL_GAS),
+			gas_price: U256::default(),
+			value: U256::default(),
+			data: data,
+		}.fake_sign(from);
+
+		self.call(&transaction, block_id, Default::default())
+			.map(|executed| executed.output)
+			.map_err(|e| format!("{:?}", e))
+	}
+
+	fn node_allowed(&self, own_id: &H512, node_id: &H512) -> bool {
+		let params = self.engine.params();
+		if params.allowed_nodes.as_ref().map_or(false, |nodes| !nodes.contains(node_id)) {
+			return false;
+		}
+		match params.node_permission_contract {
+			Some(contract) => {
+				let best = self.chain.read().best_block_hash();
+				let block_id = BlockID::Hash(best.clone());
+				self.node_permission.is_allowed(contract, &best, own_id, node_id, |address, data| self.call_contract(block_id, address, data))
+			},
+			None => true,
+		}
+	}
+
+	fn network_id(&self) -> U256 {
+		self.engine.params().network_id
+	}
+
+	fn fork_transitions(&self) -> BTreeMap<String, BlockNumber> {
+		self.engine.fork_transitions().into_iter().collect()
+	}
+
+	fn builtins(&self) -> BTreeMap<Address, BuiltinInfo> {
+		builtin_infos(&*self.engine)
+	}
+
 	fn blocks_with_bloom(&self, bloom: &H2048, from_block: BlockID, to_block: BlockID) -> Option<Vec<BlockNumber>> {
 		match (self.block_number(from_block), self.block_number(to_block)) {
 			(Some(from), Some(to)) => Some(self.chain.read().blocks_with_bloom(bloom, from, to)),
@@ -1060,10 +1931,82 @@ impl BlockChainClient for Client {
 			.and_then(|number| self.tracedb.read().block_traces(number))
 	}
 
+	fn balance_change(&self, address: &Address, id: BlockID) -> Option<(U256, U256)> {
+		let traces = match self.block_traces(id.clone()) {
+			Some(traces) => traces,
+			None => return None,
+		};
+		let (block, receipts) = match (self.block(id.clone()), self.block_receipts_localized(id)) {
+			(Some(block), Some(receipts)) => (block, receipts),
+			_ => return None,
+		};
+		let block = BlockView::new(&block);
+		if block.header_view().author() == *address || block.uncles().iter().any(|uncle| uncle.author() == address) {
+			return None;
+		}
+
+		let mut gained = U256::zero();
+		let mut lost = U256::zero();
+		for (transaction, receipt) in block.transactions().iter().zip(receipts.iter()) {
+			if transaction.sender().ok().as_ref() == Some(address) {
+				lost = lost + receipt.gas_used * transaction.gas_price;
+			}
+		}
+
+		// transfers made by a failed call or create and everything beneath it are reverted
+		let failed: HashSet<_> = traces.iter()
+			.filter(|t| match t.result {
+				TraceResult::FailedCall(_) | TraceResult::FailedCreate(_) => true,
+				_ => false,
+			})
+			.map(|t| (t.transaction_number, t.trace_address.clone()))
+			.collect();
+		let reverted = |t: &LocalizedTrace| (0..t.trace_address.len() + 1)
+			.any(|len| failed.contains(&(t.transaction_number, t.trace_address[..len].to_vec())));
+
+		for t in traces.iter().filter(|t| !reverted(t)) {
+			match t.action {
+				// delegate calls move no value, `from` is the original caller
+				TraceAction::Call(ref call) if call.call_type != CallType::DelegateCall => {
+					if &call.from == address { lost = lost + call.value; }
+					if &call.to == address { gained = gained + call.value; }
+				},
+				TraceAction::Create(ref create) => {
+					if &create.from == address { lost = lost + create.value; }
+					if let TraceResult::Create(ref result) = t.result {
+						if &result.address == address { gained = gained + create.value; }
+					}
+				},
+				TraceAction::Suicide(ref suicide) => {
+					if &suicide.address == address { lost = lost + suicide.balance; }
+					if &suicide.refund_address == address { gained = gained + suicide.balance; }
+				},
+				_ => {},
+			}
+		}
+		Some((gained, lost))
+	}
+
 	fn last_hashes(&self) -> LastHashes {
 		(*self.build_last_hashes(self.chain.read().best_block_hash())).clone()
 	}
 
+	fn relay_transactions(&self, transactions: Vec<SignedTransaction>) -> usize {
+		let header: Header = ::rlp::decode(&self.best_block_header());
+		let mut relayed = self.relayed_transactions.lock();
+		transactions.into_iter()
+			.filter(|t| {
+				let verified = self.engine.verify_transaction_basic(t, &header)
+					.and_then(|_| self.engine.verify_transaction(t, &header));
+				if let Err(ref e) = verified {
+					debug!(target: "client", "Not relaying transaction {}: {:?}", t.hash(), e);
+				}
+				verified.is_ok()
+			})
+			.filter(|t| relayed.insert(t.clone()))
+			.count()
+	}
+
 	fn queue_transactions(&self, transactions: Vec<Bytes>) {
 		if self.queue_transactions.load(AtomicOrdering::Relaxed) > MAX_TX_QUEUE_SIZE {
 			debug!("Ignoring {} transactions: queue is full", transactions.len());
@@ -1080,7 +2023,22 @@ impl BlockChainClient for Client {
 		}
 	}
 
+	fn queue_private_transaction(&self, packet: Bytes) {
+		if let Some(provider) = self.private_transactions() {
+			provider.queue_private_transaction(packet);
+		}
+	}
+
+	fn queue_signed_private_transaction(&self, packet: Bytes) {
+		if let Some(provider) = self.private_transactions() {
+			provider.queue_signed_private_transaction(packet);
+		}
+	}
+
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
+		if self.relaying() {
+			return self.relayed_transactions.lock().transactions();
+		}
 		self.miner.pending_transactions(self.chain.read().best_block_number())
 	}
 }
@@ -1090,18 +2048,21 @@ impl MiningBlockChainClient for Client {
 		let engine = &*self.engine;
 		let chain = self.chain.read();
 		let h = chain.best_block_hash();
+		let mut db = self.state_db.lock().boxed_clone();
+		Self::restrict_account_bloom(&chain, &mut db, &h, chain.best_block_number());
 
 		let mut open_block = OpenBlock::new(
 			engine,
 			self.factories.clone(),
 			false,	// TODO: this will need to be parameterised once we want to do immediate mining insertion.
-			self.state_db.lock().boxed_clone(),
+			db,
 			&chain.block_header(&h).expect("h is best block hash: so its header must exist: qed"),
 			self.build_last_hashes(h.clone()),
 			author,
 			gas_range_target,
 			extra_data,
-		).expect("OpenBlock::new only fails if parent state root invalid; state root of best block's header is never invalid; qed");
+		).expect("OpenBlock::new only fails if parent state root invalid; state root of best block's header is never invalid \
+			outside of relay mode; the miner never prepares blocks in relay mode; qed");
 
 		// Add uncles
 		chain
@@ -1122,6 +2083,12 @@ impl MiningBlockChainClient for Client {
 
 	fn import_sealed_block(&self, block: SealedBlock) -> ImportResult {
 		let _import_lock = self.import_lock.lock();
+		if self.shut_down.load(AtomicOrdering::SeqCst) {
+			return Err(ImportError::ShuttingDown.into());
+		}
+		if !self.collect_root_check() {
+			return Err(ImportError::Stopped.into());
+		}
 		let _timer = PerfTimer::new("import_sealed_block");
 		let start = precise_time_ns();
 
@@ -1149,6 +2116,11 @@ impl MiningBlockChainClient for Client {
 		self.db.read().flush().expect("DB flush failed.");
 		Ok(h)
 	}
+
+	fn transaction_allowed(&self, t: &SignedTransaction) -> bool {
+		let best_block_hash = self.chain.read().best_block_hash();
+		self.transaction_allowed_at(t, &best_block_hash)
+	}
 }
 
 impl MayPanic for Client {
@@ -15,10 +15,12 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::str::FromStr;
+use std::path::Path;
 pub use std::time::Duration;
 pub use blockchain::Config as BlockChainConfig;
 pub use trace::Config as TraceConfig;
 pub use evm::VMType;
+pub use state_db::StateDbConfig;
 
 use verification::{VerifierType, QueueConfig};
 use util::{journaldb, CompactionProfile};
@@ -26,23 +28,26 @@ use util::{journaldb, CompactionProfile};
 /// Client state db compaction profile
 #[derive(Debug, PartialEq)]
 pub enum DatabaseCompactionProfile {
-	/// Default compaction profile
-	Default,
+	/// Try to determine compaction profile automatically
+	Auto,
+	/// SSD compaction profile
+	SSD,
 	/// HDD or other slow storage io compaction profile
 	HDD,
 }
 
 impl Default for DatabaseCompactionProfile {
 	fn default() -> Self {
-		DatabaseCompactionProfile::Default
+		DatabaseCompactionProfile::Auto
 	}
 }
 
 impl DatabaseCompactionProfile {
-	/// Returns corresponding compaction profile.
-	pub fn compaction_profile(&self) -> CompactionProfile {
+	/// Returns corresponding compaction profile for the database at given path.
+	pub fn compaction_profile(&self, db_path: &Path) -> CompactionProfile {
 		match *self {
-			DatabaseCompactionProfile::Default => Default::default(),
+			DatabaseCompactionProfile::Auto => CompactionProfile::auto(db_path),
+			DatabaseCompactionProfile::SSD => Default::default(),
 			DatabaseCompactionProfile::HDD => CompactionProfile::hdd(),
 		}
 	}
@@ -53,9 +58,10 @@ impl FromStr for DatabaseCompactionProfile {
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
 		match s {
-			"ssd" | "default" => Ok(DatabaseCompactionProfile::Default),
+			"auto" => Ok(DatabaseCompactionProfile::Auto),
+			"ssd" | "default" => Ok(DatabaseCompactionProfile::SSD),
 			"hdd" => Ok(DatabaseCompactionProfile::HDD),
-			_ => Err("Invalid compaction profile given. Expected hdd/ssd (default).".into()),
+			_ => Err("Invalid compaction profile given. Expected auto (default)/hdd/ssd.".into()),
 		}
 	}
 }
@@ -71,6 +77,11 @@ pub enum Mode {
 	/// Goes offline after RLP is inactive for some (given) time and
 	/// stays inactive.
 	Dark(Duration),
+	/// Never connects to the network.
+	Off,
+	/// Always on, but blocks are only verified and stored, never executed,
+	/// and transactions are relayed without being checked against the state.
+	Relay,
 }
 
 impl Default for Mode {
@@ -98,14 +109,39 @@ pub struct ClientConfig {
 	pub name: String,
 	/// State db cache-size if not default
 	pub db_cache_size: Option<usize>,
+	/// State cache size (in bytes) if not default. Memory left unused by the
+	/// blockchain cache and the verification queue is lent to it at runtime.
+	pub state_cache_size: Option<usize>,
 	/// State db compaction profile
 	pub db_compaction: DatabaseCompactionProfile,
 	/// Should db have WAL enabled?
 	pub db_wal: bool,
+	/// Maximum number of files the db keeps open if not default
+	pub db_max_open_files: Option<i32>,
+	/// Total size of db write-ahead logs (in bytes) if not default
+	pub db_wal_size: Option<u64>,
+	/// Size of db memtables (in bytes) if not default
+	pub db_write_buffer_size: Option<u64>,
 	/// Operating mode
 	pub mode: Mode,
 	/// Type of block verifier used by client.
 	pub verifier_type: VerifierType,
+	/// Path to a read-only secondary database consulted for historical state
+	/// which is no longer present in the main database.
+	pub cold_db_path: Option<String>,
+	/// Path to a secondary database which receives bodies and receipts of blocks
+	/// older than `blockchain.ancient_horizon`.
+	pub ancient_path: Option<String>,
+	/// Account bloom configuration.
+	pub state_db: StateDbConfig,
+	/// Recompute the state root of every imported block in the background
+	/// and stop on mismatch.
+	pub check_roots: bool,
+	/// Maximal number of blocks behind the best block the chain may be reorganized to,
+	/// unless forced. `None` for no limit.
+	pub max_reorg_depth: Option<u64>,
+	/// URL the blocks which fail verification are posted to.
+	pub bad_block_report_url: Option<String>,
 }
 
 #[cfg(test)]
@@ -114,13 +150,14 @@ mod test {
 
 	#[test]
 	fn test_default_compaction_profile() {
-		assert_eq!(DatabaseCompactionProfile::default(), DatabaseCompactionProfile::Default);
+		assert_eq!(DatabaseCompactionProfile::default(), DatabaseCompactionProfile::Auto);
 	}
 
 	#[test]
 	fn test_parsing_compaction_profile() {
-		assert_eq!(DatabaseCompactionProfile::Default, "ssd".parse().unwrap());
-		assert_eq!(DatabaseCompactionProfile::Default, "default".parse().unwrap());
+		assert_eq!(DatabaseCompactionProfile::Auto, "auto".parse().unwrap());
+		assert_eq!(DatabaseCompactionProfile::SSD, "ssd".parse().unwrap());
+		assert_eq!(DatabaseCompactionProfile::SSD, "default".parse().unwrap());
 		assert_eq!(DatabaseCompactionProfile::HDD, "hdd".parse().unwrap());
 	}
 
@@ -15,6 +15,10 @@ pub enum Error {
 	Database(String),
 	/// Util error
 	Util(UtilError),
+	/// Old blocks were moved to an ancient database which is not configured.
+	MissingAncientDatabase,
+	/// The database was created with a different `--no-bodies` setting. Holds whether it only stores headers.
+	NoBodiesMismatch(bool),
 }
 
 impl From<TraceError> for Error {
@@ -48,6 +52,9 @@ impl Display for Error {
 			Error::Trie(ref err) => write!(f, "{}", err),
 			Error::Util(ref err) => write!(f, "{}", err),
 			Error::Database(ref s) => write!(f, "Database error: {}", s),
+			Error::MissingAncientDatabase => write!(f, "Bodies and receipts of old blocks were moved to an ancient database. Run with --ancient-path to attach it."),
+			Error::NoBodiesMismatch(true) => write!(f, "The database only stores block headers. Run with --no-bodies or use a different database."),
+			Error::NoBodiesMismatch(false) => write!(f, "The database stores block bodies and can't be used with --no-bodies. Use a different database or remove it with `parity db kill`."),
 		}
 	}
 }
@@ -0,0 +1,110 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Execution of EVM code against a fresh, empty state.
+
+use std::sync::Arc;
+use util::{U256, Bytes, BytesRef, Database, journaldb};
+use action_params::ActionParams;
+use client::{Executive, EnvInfo};
+use db::{NUM_COLUMNS, COL_STATE};
+use evm::{self, Factory, VMType};
+use log_entry::LogEntry;
+use spec::Spec;
+use state::{State, Substate};
+use state_db::StateDB;
+use trace::{Tracer, VMTracer};
+
+/// Result of a call executed by `EvmTestClient`.
+#[derive(Debug, PartialEq)]
+pub struct EvmTestResult {
+	/// Gas left after the execution.
+	pub gas_left: U256,
+	/// Returned data.
+	pub output: Bytes,
+	/// Logs created by the call.
+	pub logs: Vec<LogEntry>,
+}
+
+/// Runs EVM code following the rules of a chain spec at its genesis block, without a blockchain.
+/// The state is only kept in memory.
+pub struct EvmTestClient {
+	state: State,
+	spec: Spec,
+	vm_factory: Factory,
+}
+
+impl EvmTestClient {
+	/// Create a client with an empty state.
+	pub fn new(spec: Spec, vm_type: VMType) -> Self {
+		let journal_db = journaldb::new(Arc::new(Database::in_memory(NUM_COLUMNS)), journaldb::Algorithm::EarlyMerge, COL_STATE);
+		let state = State::new(StateDB::new(journal_db), spec.params.account_start_nonce, Default::default());
+
+		EvmTestClient {
+			state: state,
+			spec: spec,
+			vm_factory: Factory::new(vm_type),
+		}
+	}
+
+	/// Execute `params.code` as a message call. Calls and contracts created by the code
+	/// modify the state seen by later calls.
+	pub fn call<T, V>(&mut self, params: ActionParams, tracer: &mut T, vm_tracer: &mut V) -> evm::Result<EvmTestResult>
+		where T: Tracer, V: VMTracer
+	{
+		let env_info = EnvInfo {
+			gas_limit: params.gas,
+			..Default::default()
+		};
+		let mut substate = Substate::new();
+		let mut output = Vec::new();
+		let gas_left = {
+			let mut executive = Executive::new(&mut self.state, &env_info, &*self.spec.engine, &self.vm_factory);
+			try!(executive.call(params, &mut substate, BytesRef::Flexible(&mut output), tracer, vm_tracer))
+		};
+
+		Ok(EvmTestResult {
+			gas_left: gas_left,
+			output: output,
+			logs: substate.logs,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use action_params::ActionParams;
+	use ethereum;
+	use evm::VMType;
+	use trace::{NoopTracer, NoopVMTracer};
+	use super::EvmTestClient;
+
+	#[test]
+	fn should_execute_code_against_fresh_state() {
+		let mut client = EvmTestClient::new(ethereum::new_homestead_test(), VMType::Interpreter);
+		let mut params = ActionParams::default();
+		params.gas = 100_000.into();
+		// PUSH1 0x2a PUSH1 0 SSTORE PUSH1 0x2a PUSH1 0 MSTORE8 PUSH1 1 PUSH1 0 RETURN
+		// costs 6 pushes, a new storage value, MSTORE8 and one word of memory.
+		params.code = Some(Arc::new(vec![0x60, 0x2a, 0x60, 0x00, 0x55, 0x60, 0x2a, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3]));
+
+		let result = client.call(params, &mut NoopTracer, &mut NoopVMTracer).unwrap();
+		assert_eq!(result.output, vec![0x2a]);
+		assert_eq!(result.gas_left, (100_000 - 6 * 3 - 20_000 - 3 - 3).into());
+		assert!(result.logs.is_empty());
+	}
+}
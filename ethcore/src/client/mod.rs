@@ -21,10 +21,17 @@ mod error;
 mod test_client;
 mod trace;
 mod client;
+mod registry;
+mod relay;
+mod root_check;
+mod bad_blocks;
+mod evm_test_client;
 
 pub use self::client::*;
-pub use self::config::{Mode, ClientConfig, DatabaseCompactionProfile, BlockChainConfig, VMType};
+pub use self::config::{Mode, ClientConfig, DatabaseCompactionProfile, BlockChainConfig, VMType, StateDbConfig};
 pub use self::error::Error;
+pub use self::registry::Registrar;
+pub use self::evm_test_client::{EvmTestClient, EvmTestResult};
 pub use types::ids::*;
 pub use self::test_client::{TestBlockChainClient, EachBlockWith};
 pub use types::trace_filter::Filter as TraceFilter;
@@ -33,6 +40,7 @@ pub use env_info::{LastHashes, EnvInfo};
 pub use self::chain_notify::ChainNotify;
 
 pub use types::call_analytics::CallAnalytics;
+pub use types::mode::Mode as IpcMode;
 pub use block_import_error::BlockImportError;
 pub use transaction_import::TransactionImportResult;
 pub use transaction_import::TransactionImportError;
@@ -0,0 +1,99 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Name resolution through the on-chain registrar contract.
+
+use std::fmt;
+use ethabi::{Interface, Contract, Token};
+use util::{Address, Bytes, Hashable};
+
+/// Registrar key under which the address of a name is stored.
+const ADDRESS_KEY: &'static str = "A";
+
+/// Client-side interface of the registrar contract.
+/// The contract is called through a closure, so it can be used with any way of executing calls.
+pub struct Registrar {
+	contract: Contract,
+}
+
+impl Registrar {
+	/// Create a new registrar interface.
+	pub fn new() -> Self {
+		let interface = Interface::load(include_bytes!("../../res/contracts/registrar.json")).expect("registrar.json is valid ABI");
+		Registrar {
+			contract: Contract::new(interface),
+		}
+	}
+
+	/// Resolve `name` to the address registered for it in the registrar at `registrar`.
+	/// Returns `None` if the name is not registered.
+	pub fn get_address<F>(&self, registrar: Address, name: &str, call: F) -> Result<Option<Address>, String>
+		where F: FnOnce(Address, Bytes) -> Result<Bytes, String>
+	{
+		let get_address = try!(self.contract.function("getAddress".into()).map_err(as_string));
+		let params = try!(get_address.encode_call(
+			vec![Token::FixedBytes((*name.sha3()).to_vec()), Token::String(ADDRESS_KEY.into())]
+		).map_err(as_string));
+		let output = try!(call(registrar, params));
+		let result = try!(get_address.decode_output(output).map_err(as_string));
+
+		match result.get(0) {
+			Some(&Token::Address(address)) if address != *Address::default() => Ok(Some(address.into())),
+			Some(&Token::Address(_)) => Ok(None),
+			e => Err(format!("Invalid registrar result: {:?}", e)),
+		}
+	}
+}
+
+impl Default for Registrar {
+	fn default() -> Self {
+		Registrar::new()
+	}
+}
+
+fn as_string<T: fmt::Debug>(e: T) -> String {
+	format!("{:?}", e)
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::{ToHex, FromHex};
+	use util::{Address, Hashable};
+	use super::Registrar;
+
+	#[test]
+	fn should_resolve_registered_name() {
+		let registrar = Registrar::new();
+		let registrar_address: Address = "8e4e9b13d4b45cb0befc93c3061b1408f67316b2".parse().unwrap();
+
+		let result = registrar.get_address(registrar_address, "githubhint", |address, data| {
+			assert_eq!(address, registrar_address);
+			// getAddress(bytes32,string) selector followed by the hashed name
+			assert_eq!(data[0..4].to_hex(), "6795dbcd");
+			assert_eq!(data[4..36].to_hex(), "githubhint".sha3().to_hex());
+			Ok("000000000000000000000000deadbeefcafe0000000000000000000000000001".from_hex().unwrap())
+		});
+
+		assert_eq!(result, Ok(Some("deadbeefcafe0000000000000000000000000001".parse().unwrap())));
+	}
+
+	#[test]
+	fn should_return_none_for_unregistered_name() {
+		let registrar = Registrar::new();
+		let result = registrar.get_address(Address::default(), "unknown", |_, _| Ok(vec![0; 32]));
+		assert_eq!(result, Ok(None));
+	}
+}
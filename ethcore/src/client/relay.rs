@@ -0,0 +1,124 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transactions kept for gossiping by a client in relay mode.
+
+use std::collections::{HashMap, VecDeque};
+use util::H256;
+use transaction::SignedTransaction;
+
+/// Transactions waiting to be relayed to peers, in arrival order.
+/// Without state they can't be checked against nonces or balances, so they are only dropped
+/// when included in a block or when the limit is reached.
+pub struct RelayedTransactions {
+	transactions: HashMap<H256, SignedTransaction>,
+	order: VecDeque<H256>,
+	limit: usize,
+}
+
+impl RelayedTransactions {
+	/// Create an empty set holding at most `limit` transactions.
+	pub fn new(limit: usize) -> Self {
+		RelayedTransactions {
+			transactions: HashMap::new(),
+			order: VecDeque::new(),
+			limit: limit,
+		}
+	}
+
+	/// Add a transaction, evicting the oldest one if the limit is reached.
+	/// Returns false if the transaction is already known.
+	pub fn insert(&mut self, transaction: SignedTransaction) -> bool {
+		let hash = transaction.hash();
+		if self.transactions.contains_key(&hash) {
+			return false;
+		}
+
+		if self.order.len() >= self.limit {
+			if let Some(oldest) = self.order.pop_front() {
+				self.transactions.remove(&oldest);
+			}
+		}
+		self.order.push_back(hash);
+		self.transactions.insert(hash, transaction);
+		true
+	}
+
+	/// Drop transactions which have been included in a block.
+	pub fn remove_all(&mut self, hashes: &[H256]) {
+		for hash in hashes {
+			self.transactions.remove(hash);
+		}
+		let transactions = &self.transactions;
+		self.order.retain(|hash| transactions.contains_key(hash));
+	}
+
+	/// All transactions, oldest first.
+	pub fn transactions(&self) -> Vec<SignedTransaction> {
+		self.order.iter().filter_map(|hash| self.transactions.get(hash)).cloned().collect()
+	}
+
+	/// Number of transactions held.
+	pub fn len(&self) -> usize {
+		self.order.len()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::U256;
+	use ethkey::{Generator, Random};
+	use transaction::{Transaction, Action, SignedTransaction};
+	use super::RelayedTransactions;
+
+	fn transaction(nonce: u64) -> SignedTransaction {
+		let keypair = Random.generate().unwrap();
+		Transaction {
+			nonce: U256::from(nonce),
+			action: Action::Create,
+			gas: U256::from(21_000),
+			gas_price: U256::default(),
+			value: U256::default(),
+			data: vec![],
+		}.sign(keypair.secret())
+	}
+
+	#[test]
+	fn should_evict_oldest_transaction() {
+		let mut relayed = RelayedTransactions::new(2);
+		let (t1, t2, t3) = (transaction(1), transaction(2), transaction(3));
+
+		assert!(relayed.insert(t1.clone()));
+		assert!(relayed.insert(t2.clone()));
+		assert!(!relayed.insert(t2.clone()));
+		assert!(relayed.insert(t3.clone()));
+
+		assert_eq!(relayed.transactions(), vec![t2, t3]);
+	}
+
+	#[test]
+	fn should_remove_included_transactions() {
+		let mut relayed = RelayedTransactions::new(10);
+		let (t1, t2) = (transaction(1), transaction(2));
+		relayed.insert(t1.clone());
+		relayed.insert(t2.clone());
+
+		relayed.remove_all(&[t1.hash()]);
+
+		assert_eq!(relayed.len(), 1);
+		assert_eq!(relayed.transactions(), vec![t2]);
+	}
+}
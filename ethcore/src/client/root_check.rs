@@ -0,0 +1,336 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Background verification of the state roots of imported blocks.
+//!
+//! Tries are checked node by node: every node must hash to the key it is stored under and
+//! the trie must have the canonical shape, so that its root commits to the accounts in it.
+//! Nodes are addressed by their hash, so sub-tries verified by previous checks are skipped
+//! and each check only walks the nodes written since.
+
+use std::mem;
+use std::collections::HashSet;
+use std::thread::{self, JoinHandle};
+use util::{H256, Mutex, FixedHash, Hashable};
+use util::hashdb::HashDB;
+use util::journaldb::JournalDB;
+use util::sha3::SHA3_NULL_RLP;
+use util::trie::node::Node;
+use rlp::{UntrustedRlp, View, Prototype};
+use account_db::Factory as AccountFactory;
+use header::BlockNumber;
+
+/// Number of verified trie nodes remembered between checks. The next check walks
+/// the whole state again once there are more.
+const MAX_VERIFIED_NODES: usize = 4000000;
+
+/// Verified sub-tries by the address hash of the account whose storage they belong to
+/// (zero for the account trie), the hash of their root node and its depth in nibbles.
+pub type Verified = HashSet<(H256, H256, usize)>;
+
+/// Check the state trie at `root` and the storage tries of all accounts in it, skipping
+/// the sub-tries in `verified` and adding the ones verified on the way.
+pub fn verify_state(db: &HashDB, accountdb: &AccountFactory, root: &H256, verified: &mut Verified) -> Result<(), String> {
+	let on_account = |address_hash: &H256, account: &[u8], verified: &mut Verified| {
+		let storage_root: H256 = try!(UntrustedRlp::new(account).val_at(2)
+			.map_err(|e| format!("Invalid account {}: {:?}", address_hash, e)));
+		let account_db = accountdb.readonly(db, address_hash.clone());
+		let mut storage = TrieCheck { db: &*account_db, owner: address_hash.clone(), on_leaf: ignore_value };
+		storage.check(&storage_root, verified)
+			.map_err(|e| format!("Storage of account {}: {}", address_hash, e))
+	};
+	TrieCheck { db: db, owner: H256::zero(), on_leaf: on_account }.check(root, verified)
+}
+
+/// Structural check of a trie with 32-byte keys.
+struct TrieCheck<'a, F> {
+	db: &'a HashDB,
+	owner: H256,
+	/// Called with the key and value of every leaf.
+	on_leaf: F,
+}
+
+impl<'a, F> TrieCheck<'a, F> where F: FnMut(&H256, &[u8], &mut Verified) -> Result<(), String> {
+	fn check(&mut self, root: &H256, verified: &mut Verified) -> Result<(), String> {
+		match *root == SHA3_NULL_RLP {
+			true => Ok(()),
+			false => self.check_hashed(root, &mut Vec::new(), true, false, verified),
+		}
+	}
+
+	/// Check the node stored under `hash` at the given path. Only the root may be shorter
+	/// than a hash, and children of extensions must be branches.
+	fn check_hashed(&mut self, hash: &H256, path: &mut Vec<u8>, is_root: bool, branch: bool, verified: &mut Verified) -> Result<(), String> {
+		let key = (self.owner.clone(), hash.clone(), path.len());
+		let known = verified.contains(&key);
+		if known && !branch {
+			return Ok(());
+		}
+		let db = self.db;
+		let node = try!(db.get(hash).ok_or_else(|| format!("Missing trie node {}", hash)));
+		if known {
+			return match is_branch(node) {
+				true => Ok(()),
+				false => Err(format!("Extension child {} is not a branch", hash)),
+			};
+		}
+		if node.sha3() != *hash {
+			return Err(format!("Trie node stored under {} has hash {}", hash, node.sha3()));
+		}
+		if node.len() < 32 && !is_root {
+			return Err(format!("Trie node {} should be inline", hash));
+		}
+		try!(self.check_node(node, path, branch, verified));
+		verified.insert(key);
+		Ok(())
+	}
+
+	/// Check a child reference, which is either the hash of a node or an inline node.
+	fn check_child(&mut self, child: &[u8], path: &mut Vec<u8>, branch: bool, verified: &mut Verified) -> Result<(), String> {
+		let rlp = UntrustedRlp::new(child);
+		match rlp.prototype() {
+			Ok(Prototype::Data(32)) => {
+				let hash = H256::from_slice(try!(rlp.data().map_err(|e| format!("{:?}", e))));
+				self.check_hashed(&hash, path, false, branch, verified)
+			},
+			Ok(Prototype::List(_)) if child.len() < 32 => self.check_node(child, path, branch, verified),
+			_ => Err(format!("Invalid child reference at path {:?}", path)),
+		}
+	}
+
+	fn check_node(&mut self, data: &[u8], path: &mut Vec<u8>, branch: bool, verified: &mut Verified) -> Result<(), String> {
+		if !is_valid_node(data) {
+			return Err(format!("Invalid trie node at path {:?}", path));
+		}
+		let depth = path.len();
+		let result = match Node::decoded(data) {
+			Node::Branch(children, value) => {
+				if value.is_some() {
+					return Err(format!("Branch with a value at path {:?}", path));
+				}
+				if children.iter().filter(|child| !is_empty(child)).count() < 2 {
+					return Err(format!("Branch with less than two children at path {:?}", path));
+				}
+				for (nibble, child) in children.iter().enumerate() {
+					if is_empty(child) {
+						continue;
+					}
+					path.push(nibble as u8);
+					try!(self.check_child(child, path, false, verified));
+					path.pop();
+				}
+				Ok(())
+			},
+			_ if branch => Err(format!("Extension child at path {:?} is not a branch", path)),
+			Node::Extension(slice, child) => {
+				if slice.is_empty() {
+					return Err(format!("Empty extension at path {:?}", path));
+				}
+				path.extend((0..slice.len()).map(|i| slice.at(i)));
+				self.check_child(child, path, true, verified)
+			},
+			Node::Leaf(slice, value) => {
+				path.extend((0..slice.len()).map(|i| slice.at(i)));
+				match path.len() == 64 && !value.is_empty() {
+					true => (self.on_leaf)(&nibbles_to_key(path), value, verified),
+					false => Err(format!("Invalid leaf at path {:?}", path)),
+				}
+			},
+			Node::Empty => Err(format!("Empty node at path {:?}", path)),
+		};
+		path.truncate(depth);
+		result
+	}
+}
+
+/// Whether `data` can be decoded as a trie node without panicking.
+fn is_valid_node(data: &[u8]) -> bool {
+	let rlp = UntrustedRlp::new(data);
+	match rlp.prototype() {
+		Ok(Prototype::List(2)) => rlp.at(0).map_or(false, |path| path.is_data() && path.data().map_or(false, |path| !path.is_empty()))
+			&& rlp.at(1).is_ok(),
+		Ok(Prototype::List(17)) => (0..17).all(|i| rlp.at(i).is_ok()),
+		_ => false,
+	}
+}
+
+fn ignore_value(_: &H256, _: &[u8], _: &mut Verified) -> Result<(), String> {
+	Ok(())
+}
+
+fn is_branch(data: &[u8]) -> bool {
+	match UntrustedRlp::new(data).prototype() {
+		Ok(Prototype::List(17)) => true,
+		_ => false,
+	}
+}
+
+fn is_empty(child: &[u8]) -> bool {
+	child == &[0x80]
+}
+
+fn nibbles_to_key(path: &[u8]) -> H256 {
+	let mut key = [0u8; 32];
+	for (i, pair) in path.chunks(2).enumerate() {
+		key[i] = pair[0] << 4 | pair[1];
+	}
+	H256::from(key)
+}
+
+/// Verifies the state of each committed block in a background thread.
+/// Only one block is checked at a time; the result must be collected with `wait`
+/// before the next block is committed.
+pub struct RootCheck {
+	accountdb: AccountFactory,
+	pending: Mutex<Option<(H256, JoinHandle<(Verified, Result<(), String>)>)>>,
+	/// Sub-tries verified by previous checks. Moved to the running check.
+	verified: Mutex<Verified>,
+}
+
+impl RootCheck {
+	/// Create a new checker for states using the given account database factory.
+	pub fn new(accountdb: AccountFactory) -> Self {
+		RootCheck {
+			accountdb: accountdb,
+			pending: Mutex::new(None),
+			verified: Mutex::new(HashSet::new()),
+		}
+	}
+
+	/// Start checking the state of block `number` with hash `hash` in `db`, which should have the given root.
+	/// Any previous check must have been collected with `wait`.
+	pub fn start(&self, db: Box<JournalDB>, number: BlockNumber, hash: H256, root: H256) {
+		let accountdb = self.accountdb.clone();
+		let mut verified = mem::replace(&mut *self.verified.lock(), HashSet::new());
+		if verified.len() > MAX_VERIFIED_NODES {
+			verified.clear();
+		}
+		let check = move || {
+			let result = verify_state(db.as_hashdb(), &accountdb, &root, &mut verified)
+				.map_err(|e| format!("State root mismatch in block #{}: {}", number, e));
+			if result.is_ok() {
+				trace!(target: "client", "State root of block #{} verified", number);
+			}
+			(verified, result)
+		};
+
+		let handle = thread::Builder::new().name("root-check".into()).spawn(check)
+			.expect("Error starting the state root check thread");
+		let previous = mem::replace(&mut *self.pending.lock(), Some((hash, handle)));
+		assert!(previous.is_none(), "The previous state root check was not collected");
+	}
+
+	/// Wait for the running check, if any, and return its result along with the hash of the checked block.
+	pub fn wait(&self) -> Result<(), (H256, String)> {
+		let (hash, handle) = match self.pending.lock().take() {
+			Some(pending) => pending,
+			None => return Ok(()),
+		};
+		match handle.join() {
+			Ok((verified, result)) => {
+				*self.verified.lock() = verified;
+				result.map_err(|e| (hash, e))
+			},
+			Err(_) => Err((hash, "State root check panicked".into())),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+	use util::{U256, H256, Address, FixedHash, Hashable};
+	use util::hashdb::HashDB;
+	use util::memorydb::MemoryDB;
+	use rlp::{RlpStream, Stream};
+	use tests::helpers::get_temp_state;
+	use account_db::Factory as AccountFactory;
+	use super::{verify_state, RootCheck};
+
+	fn leaf(path: &[u8], value: &[u8]) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(2);
+		stream.append(&path.to_vec());
+		stream.append(&value.to_vec());
+		stream.out()
+	}
+
+	#[test]
+	fn should_verify_state() {
+		let mut state_result = get_temp_state();
+		let mut state = state_result.take();
+		let address = Address::random();
+		state.add_balance(&Address::random(), &U256::from(100u64));
+		state.init_code(&address, vec![0x60, 0x00]);
+		state.set_storage(&address, 1.into(), 2.into());
+		state.commit().unwrap();
+
+		let (root, db) = state.drop();
+		let mut verified = HashSet::new();
+		assert_eq!(verify_state(db.as_hashdb(), &AccountFactory::default(), &root, &mut verified), Ok(()));
+		assert!(!verified.is_empty());
+		// verified sub-tries are not walked again.
+		let known = verified.len();
+		assert_eq!(verify_state(db.as_hashdb(), &AccountFactory::default(), &root, &mut verified), Ok(()));
+		assert_eq!(verified.len(), known);
+		assert!(verify_state(db.as_hashdb(), &AccountFactory::default(), &1.into(), &mut verified).is_err());
+	}
+
+	#[test]
+	fn should_reject_non_canonical_trie() {
+		let mut db = MemoryDB::new();
+		// a branch with a single child should have been an extension.
+		let mut path = vec![0x30];
+		path.extend_from_slice(&[0x11; 31]);
+		let child = db.insert(&leaf(&path, b"value"));
+		let mut stream = RlpStream::new_list(17);
+		stream.append(&child);
+		for _ in 0..16 {
+			stream.append_empty_data();
+		}
+		let root = db.insert(&stream.out());
+
+		let result = verify_state(&db, &AccountFactory::default(), &root, &mut HashSet::new());
+		assert!(result.unwrap_err().contains("less than two children"));
+	}
+
+	#[test]
+	fn should_report_mismatching_state() {
+		let mut db = MemoryDB::new();
+		let mut path = vec![0x20];
+		path.extend_from_slice(&[0x11; 32]);
+		let node = leaf(&path, b"value");
+		let root = H256::random();
+		db.emplace(root.clone(), node.clone());
+
+		let result = verify_state(&db, &AccountFactory::default(), &root, &mut HashSet::new());
+		assert_eq!(result, Err(format!("Trie node stored under {} has hash {}", root, node.sha3())));
+	}
+
+	#[test]
+	fn should_return_the_failed_block() {
+		let mut state_result = get_temp_state();
+		let state = state_result.take();
+		let (_, db) = state.drop();
+		let check = RootCheck::new(AccountFactory::default());
+		let hash = H256::random();
+
+		check.start(db.journal_db().boxed_clone(), 1, hash.clone(), H256::random());
+		let (failed, error) = check.wait().unwrap_err();
+		assert_eq!(failed, hash);
+		assert!(error.starts_with("State root mismatch in block #1: Missing trie node"));
+		assert_eq!(check.wait(), Ok(()));
+	}
+}
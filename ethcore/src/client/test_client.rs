@@ -25,7 +25,8 @@ use transaction::{Transaction, LocalizedTransaction, SignedTransaction, Action};
 use blockchain::TreeRoute;
 use client::{
 	BlockChainClient, MiningBlockChainClient, BlockChainInfo, BlockStatus, BlockID,
-	TransactionID, UncleID, TraceId, TraceFilter, LastHashes, CallAnalytics, BlockImportError,
+	TransactionID, UncleID, TraceId, TraceFilter, LastHashes, CallAnalytics, BlockImportError, IpcMode,
+	builtin_infos,
 };
 use db::{NUM_COLUMNS, COL_STATE};
 use header::{Header as BlockHeader, BlockNumber};
@@ -41,9 +42,12 @@ use spec::Spec;
 use verification::queue::QueueInfo;
 use block::{OpenBlock, SealedBlock};
 use executive::Executed;
-use error::CallError;
+use error::{CallError, StateDiffError};
+use types::state_diff::StateDiff;
 use trace::LocalizedTrace;
 use state_db::StateDB;
+use types::builtin_info::BuiltinInfo;
+use types::bad_block::BadBlock;
 
 /// Test client.
 pub struct TestBlockChainClient {
@@ -81,6 +85,20 @@ pub struct TestBlockChainClient {
 	pub vm_factory: EvmFactory,
 	/// Timestamp assigned to latest sealed block
 	pub latest_block_timestamp: RwLock<u64>,
+	/// Addresses registered in the name registry.
+	pub registry: RwLock<HashMap<String, Address>>,
+	/// Outputs of contract calls, by contract address.
+	pub contract_results: RwLock<HashMap<Address, Bytes>>,
+	/// Operating mode.
+	pub mode: RwLock<IpcMode>,
+	/// Blocks which failed verification.
+	pub bad_blocks: RwLock<Vec<BadBlock>>,
+	/// Canonical Hash Trie roots by index.
+	pub cht_roots: RwLock<HashMap<u64, H256>>,
+	/// Blocks refused for reorganizing too deep.
+	pub refused_reorgs: RwLock<HashSet<H256>>,
+	/// Whether block bodies and receipts are stored.
+	pub stores_bodies: RwLock<bool>,
 }
 
 #[derive(Clone)]
@@ -124,6 +142,13 @@ impl TestBlockChainClient {
 			spec: spec,
 			vm_factory: EvmFactory::new(VMType::Interpreter),
 			latest_block_timestamp: RwLock::new(10_000_000),
+			registry: RwLock::new(HashMap::new()),
+			contract_results: RwLock::new(HashMap::new()),
+			mode: RwLock::new(IpcMode::Active),
+			bad_blocks: RwLock::new(Vec::new()),
+			cht_roots: RwLock::new(HashMap::new()),
+			refused_reorgs: RwLock::new(HashSet::new()),
+			stores_bodies: RwLock::new(true),
 		};
 		client.add_blocks(1, EachBlockWith::Nothing); // add genesis block
 		client.genesis_hash = client.last_hash.read().clone();
@@ -328,6 +353,10 @@ impl MiningBlockChainClient for TestBlockChainClient {
 	fn import_sealed_block(&self, _block: SealedBlock) -> ImportResult {
 		Ok(H256::default())
 	}
+
+	fn transaction_allowed(&self, _t: &SignedTransaction) -> bool {
+		true
+	}
 }
 
 impl BlockChainClient for TestBlockChainClient {
@@ -385,7 +414,11 @@ impl BlockChainClient for TestBlockChainClient {
 		}
 	}
 
-	fn list_accounts(&self, _id: BlockID) -> Option<Vec<Address>> {
+	fn list_accounts(&self, _id: BlockID, _after: Option<&Address>, _count: u64) -> Option<Vec<Address>> {
+		None
+	}
+
+	fn list_storage(&self, _id: BlockID, _account: &Address) -> Option<Vec<H256>> {
 		None
 	}
 
@@ -401,6 +434,18 @@ impl BlockChainClient for TestBlockChainClient {
 		self.receipts.read().get(&id).cloned()
 	}
 
+	fn state_diff(&self, _from: BlockID, _to: BlockID, _addresses: Vec<Address>) -> Result<StateDiff, StateDiffError> {
+		Ok(StateDiff { raw: BTreeMap::new() })	// Simple default.
+	}
+
+	fn block_receipts_localized(&self, id: BlockID) -> Option<Vec<LocalizedReceipt>> {
+		self.block_hash(id).map(|hash| {
+			let mut receipts: Vec<_> = self.receipts.read().values().filter(|r| r.block_hash == hash).cloned().collect();
+			receipts.sort_by_key(|r| r.transaction_index);
+			receipts
+		})
+	}
+
 	fn blocks_with_bloom(&self, _bloom: &H2048, _from_block: BlockID, _to_block: BlockID) -> Option<Vec<BlockNumber>> {
 		unimplemented!();
 	}
@@ -569,6 +614,62 @@ impl BlockChainClient for TestBlockChainClient {
 		Default::default()
 	}
 
+	fn engine_name(&self) -> String {
+		self.spec.engine.name().to_owned()
+	}
+
+	fn registry_address(&self, name: String) -> Option<Address> {
+		self.registry.read().get(&name).cloned()
+	}
+
+	fn call_contract(&self, _block_id: BlockID, address: Address, _data: Bytes) -> Result<Bytes, String> {
+		self.contract_results.read().get(&address).cloned().ok_or_else(|| format!("No contract at {}", address.hex()))
+	}
+
+	fn node_allowed(&self, _own_id: &H512, _node_id: &H512) -> bool {
+		true
+	}
+
+	fn mode(&self) -> IpcMode {
+		*self.mode.read()
+	}
+
+	fn set_mode(&self, mode: IpcMode) {
+		*self.mode.write() = mode;
+	}
+
+	fn force_reorg(&self, hash: H256) -> bool {
+		self.refused_reorgs.write().remove(&hash)
+	}
+
+	fn bad_blocks(&self) -> Vec<BadBlock> {
+		self.bad_blocks.read().clone()
+	}
+
+	fn keeps_state(&self) -> bool {
+		*self.mode.read() != IpcMode::Relay
+	}
+
+	fn stores_bodies(&self) -> bool {
+		*self.stores_bodies.read()
+	}
+
+	fn cht_root(&self, index: u64) -> Option<H256> {
+		self.cht_roots.read().get(&index).cloned()
+	}
+
+	fn network_id(&self) -> U256 {
+		self.spec.network_id()
+	}
+
+	fn fork_transitions(&self) -> BTreeMap<String, BlockNumber> {
+		self.spec.engine.fork_transitions().into_iter().collect()
+	}
+
+	fn builtins(&self) -> BTreeMap<Address, BuiltinInfo> {
+		builtin_infos(&*self.spec.engine)
+	}
+
 	fn chain_info(&self) -> BlockChainInfo {
 		BlockChainInfo {
 			total_difficulty: *self.difficulty.read(),
@@ -576,6 +677,7 @@ impl BlockChainClient for TestBlockChainClient {
 			genesis_hash: self.genesis_hash.clone(),
 			best_block_hash: self.last_hash.read().clone(),
 			best_block_number: self.blocks.read().len() as BlockNumber - 1,
+			first_block_number: 0,
 		}
 	}
 
@@ -591,16 +693,30 @@ impl BlockChainClient for TestBlockChainClient {
 		unimplemented!();
 	}
 
+	fn balance_change(&self, _address: &Address, _id: BlockID) -> Option<(U256, U256)> {
+		None
+	}
+
 	fn block_traces(&self, _trace: BlockID) -> Option<Vec<LocalizedTrace>> {
 		unimplemented!();
 	}
 
+	fn relay_transactions(&self, transactions: Vec<SignedTransaction>) -> usize {
+		transactions.len()
+	}
+
 	fn queue_transactions(&self, transactions: Vec<Bytes>) {
 		// import right here
 		let txs = transactions.into_iter().filter_map(|bytes| UntrustedRlp::new(&bytes).as_val().ok()).collect();
 		self.miner.import_external_transactions(self, txs);
 	}
 
+	fn queue_private_transaction(&self, _packet: Bytes) {
+	}
+
+	fn queue_signed_private_transaction(&self, _packet: Bytes) {
+	}
+
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
 		self.miner.pending_transactions(self.chain_info().best_block_number)
 	}
@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::BTreeMap;
-use util::{U256, Address, H256, H2048, Bytes, Itertools};
+use util::{U256, Address, H256, H512, H2048, Bytes, Itertools};
 use blockchain::TreeRoute;
 use verification::queue::QueueInfo as BlockQueueInfo;
 use block::{OpenBlock, SealedBlock};
@@ -24,7 +24,8 @@ use transaction::{LocalizedTransaction, SignedTransaction};
 use log_entry::LocalizedLogEntry;
 use filter::Filter;
 use views::{BlockView};
-use error::{ImportResult, CallError};
+use error::{ImportResult, CallError, StateDiffError};
+use types::state_diff::StateDiff;
 use receipt::LocalizedReceipt;
 use trace::LocalizedTrace;
 use evm::Factory as EvmFactory;
@@ -37,6 +38,9 @@ use block_import_error::BlockImportError;
 use ipc::IpcConfig;
 use types::blockchain_info::BlockChainInfo;
 use types::block_status::BlockStatus;
+use types::mode::Mode as IpcMode;
+use types::builtin_info::BuiltinInfo;
+use types::bad_block::BadBlock;
 
 #[ipc(client_ident="RemoteClient")]
 /// Blockchain database client. Owns and manages a blockchain and a block queue.
@@ -87,7 +91,7 @@ pub trait BlockChainClient : Sync + Send {
 
 	/// Get address balance at the given block's state.
 	///
-	/// May not return None if given BlockID::Latest.
+	/// May not return None if given BlockID::Latest, unless the client runs in relay mode.
 	/// Returns None if and only if the block's root hash has been pruned from the DB.
 	fn balance(&self, address: &Address, id: BlockID) -> Option<U256>;
 
@@ -111,8 +115,12 @@ pub trait BlockChainClient : Sync + Send {
 			Therefore storage_at has returned Some; qed")
 	}
 
-	/// Get a list of all accounts in the block `id`, if fat DB is in operation, otherwise `None`.
-	fn list_accounts(&self, id: BlockID) -> Option<Vec<Address>>;
+	/// Get a list of up to `count` accounts in the block `id` following `after`, if fat DB is in operation, otherwise `None`.
+	/// Accounts are ordered by the hash of their address.
+	fn list_accounts(&self, id: BlockID, after: Option<&Address>, count: u64) -> Option<Vec<Address>>;
+
+	/// Get a list of all storage keys of given account in the block `id`, if fat DB is in operation, otherwise `None`.
+	fn list_storage(&self, id: BlockID, account: &Address) -> Option<Vec<H256>>;
 
 	/// Get transaction with given hash.
 	fn transaction(&self, id: TransactionID) -> Option<LocalizedTransaction>;
@@ -123,6 +131,9 @@ pub trait BlockChainClient : Sync + Send {
 	/// Get transaction receipt with given hash.
 	fn transaction_receipt(&self, id: TransactionID) -> Option<LocalizedReceipt>;
 
+	/// Get receipts of all transactions in the given block.
+	fn block_receipts_localized(&self, id: BlockID) -> Option<Vec<LocalizedReceipt>>;
+
 	/// Get a tree route between `from` and `to`.
 	/// See `BlockChain::tree_route`.
 	fn tree_route(&self, from: &H256, to: &H256) -> Option<TreeRoute>;
@@ -151,6 +162,53 @@ pub trait BlockChainClient : Sync + Send {
 	/// Get the registrar address, if it exists.
 	fn additional_params(&self) -> BTreeMap<String, String>;
 
+	/// Get the name of the consensus engine.
+	fn engine_name(&self) -> String;
+
+	/// Resolve a name to an address using the registrar contract configured in the chain spec.
+	fn registry_address(&self, name: String) -> Option<Address>;
+
+	/// Call `address` with `data` on top of the state of the given block and return the output.
+	/// The call has a limited amount of gas.
+	fn call_contract(&self, block_id: BlockID, address: Address, data: Bytes) -> Result<Bytes, String>;
+
+	/// Whether the node `own_id` may connect to the node `node_id` according to the
+	/// node whitelist and permission contract configured in the chain spec.
+	fn node_allowed(&self, own_id: &H512, node_id: &H512) -> bool;
+
+	/// Get the current operating mode.
+	fn mode(&self) -> IpcMode;
+
+	/// Change the operating mode. Switching to or from relay mode is not possible at runtime.
+	fn set_mode(&self, mode: IpcMode);
+
+	/// Import a block which was refused for reorganizing the chain deeper than the configured
+	/// maximum, along with its refused descendants. Returns false if no such block was refused.
+	fn force_reorg(&self, hash: H256) -> bool;
+
+	/// Get the blocks which failed verification, oldest first.
+	fn bad_blocks(&self) -> Vec<BadBlock>;
+
+	/// Whether imported blocks are executed and their state kept.
+	/// Not the case in relay mode or when only headers are stored.
+	fn keeps_state(&self) -> bool;
+
+	/// Whether block bodies and receipts are stored. Only headers are kept with `--no-bodies`.
+	fn stores_bodies(&self) -> bool;
+
+	/// Get the root of the Canonical Hash Trie covering blocks `index * CHT_SIZE .. (index + 1) * CHT_SIZE`.
+	/// Tries are only generated when bodies are not stored.
+	fn cht_root(&self, index: u64) -> Option<H256>;
+
+	/// Get the network id of the chain.
+	fn network_id(&self) -> U256;
+
+	/// Get the block numbers at which the engine's hard forks activate, by name.
+	fn fork_transitions(&self) -> BTreeMap<String, BlockNumber>;
+
+	/// Get the builtin contracts of the chain by address.
+	fn builtins(&self) -> BTreeMap<Address, BuiltinInfo>;
+
 	/// Get the best block header.
 	fn best_block_header(&self) -> Bytes;
 
@@ -166,6 +224,10 @@ pub trait BlockChainClient : Sync + Send {
 	/// Replays a given transaction for inspection.
 	fn replay(&self, t: TransactionID, analytics: CallAnalytics) -> Result<Executed, CallError>;
 
+	/// Returns the difference between states of two blocks for the given accounts.
+	/// Requires fat DB to enumerate storage. Pruned states of recent blocks are rebuilt by replaying blocks.
+	fn state_diff(&self, from: BlockID, to: BlockID, addresses: Vec<Address>) -> Result<StateDiff, StateDiffError>;
+
 	/// Returns traces matching given filter.
 	fn filter_traces(&self, filter: TraceFilter) -> Option<Vec<LocalizedTrace>>;
 
@@ -178,12 +240,26 @@ pub trait BlockChainClient : Sync + Send {
 	/// Returns traces created by transaction from block.
 	fn block_traces(&self, trace: BlockID) -> Option<Vec<LocalizedTrace>>;
 
+	/// Returns the value `address` gained and lost in the given block, reconstructed from the block's traces and receipts.
+	/// None if the block has no traces or the account authored the block or one of its uncles, since rewards are not traced.
+	fn balance_change(&self, address: &Address, id: BlockID) -> Option<(U256, U256)>;
+
 	/// Get last hashes starting from best block.
 	fn last_hashes(&self) -> LastHashes;
 
+	/// Keep transactions with a valid signature for gossiping in relay mode. Nothing else can be checked without state.
+	/// Returns the number of transactions which weren't relayed before.
+	fn relay_transactions(&self, transactions: Vec<SignedTransaction>) -> usize;
+
 	/// Queue transactions for importing.
 	fn queue_transactions(&self, transactions: Vec<Bytes>);
 
+	/// Queue a private transaction received from the network for importing.
+	fn queue_private_transaction(&self, packet: Bytes);
+
+	/// Queue a validator's signature of a private transaction for importing.
+	fn queue_signed_private_transaction(&self, packet: Bytes);
+
 	/// list all transactions
 	fn pending_transactions(&self) -> Vec<SignedTransaction>;
 
@@ -228,6 +304,10 @@ pub trait MiningBlockChainClient : BlockChainClient {
 
 	/// Import sealed block. Skips all verifications.
 	fn import_sealed_block(&self, block: SealedBlock) -> ImportResult;
+
+	/// Whether the chain's transaction permission contract, if any, allows the transaction
+	/// on top of the latest state. Results are cached per block and sender.
+	fn transaction_allowed(&self, t: &SignedTransaction) -> bool;
 }
 
 impl IpcConfig for BlockChainClient { }
@@ -19,7 +19,7 @@ pub use basic_types::*;
 pub use error::*;
 pub use env_info::*;
 pub use views::*;
-pub use builtin::*;
+pub use builtin::{Builtin, Impl, Pricer, Error as BuiltinError};
 pub use header::*;
 pub use transaction::*;
 pub use log_entry::*;
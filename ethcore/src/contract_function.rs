@@ -0,0 +1,57 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Read-only calls to a single function of a contract with a known ABI.
+
+use std::fmt;
+use ethabi::{Interface, Contract, Token};
+use util::{Address, Bytes};
+
+/// A function of a contract interface, called through a caller-provided executor.
+pub struct ContractFunction {
+	contract: Contract,
+	name: &'static str,
+}
+
+impl ContractFunction {
+	/// Load the function `name` of the JSON `abi`. The ABI is expected to be a valid constant.
+	pub fn new(abi: &[u8], name: &'static str) -> Self {
+		let interface = Interface::load(abi).expect("contract ABI is a valid constant");
+		ContractFunction {
+			contract: Contract::new(interface),
+			name: name,
+		}
+	}
+
+	/// Encode a call with `params`, execute it at `address` with `call` and return the first output.
+	pub fn call<F>(&self, address: Address, params: Vec<Token>, call: F) -> Result<Token, String>
+		where F: FnOnce(Address, Bytes) -> Result<Bytes, String>
+	{
+		let function = try!(self.contract.function(self.name.into()).map_err(as_string));
+		let data = try!(function.encode_call(params).map_err(as_string));
+		let output = try!(call(address, data));
+		let mut result = try!(function.decode_output(output).map_err(as_string));
+		if result.is_empty() {
+			return Err(format!("No output from {}", self.name));
+		}
+		Ok(result.remove(0))
+	}
+}
+
+/// Format an error for logging.
+pub fn as_string<T: fmt::Debug>(e: T) -> String {
+	format!("{:?}", e)
+}
@@ -36,8 +36,10 @@ pub const COL_EXTRA: Option<u32> = Some(3);
 pub const COL_TRACE: Option<u32> = Some(4);
 /// Column for Traces
 pub const COL_ACCOUNT_BLOOM: Option<u32> = Some(5);
+/// Column for blocks which failed verification
+pub const COL_BAD_BLOCKS: Option<u32> = Some(6);
 /// Number of columns in DB
-pub const NUM_COLUMNS: Option<u32> = Some(6);
+pub const NUM_COLUMNS: Option<u32> = Some(7);
 
 /// Modes for updating caches.
 #[derive(Clone, Copy)]
@@ -47,6 +47,9 @@ pub trait Engine : Sync + Send {
 	/// Additional information.
 	fn additional_params(&self) -> HashMap<String, String> { HashMap::new() }
 
+	/// Block numbers at which the hard forks known to this engine activate, by name.
+	fn fork_transitions(&self) -> HashMap<String, BlockNumber> { HashMap::new() }
+
 	/// Get the general parameters of the chain.
 	fn params(&self) -> &CommonParams;
 
@@ -119,14 +122,16 @@ pub trait Engine : Sync + Send {
 
 	// TODO: builtin contract routing - to do this properly, it will require removing the built-in configuration-reading logic
 	// from Spec into here and removing the Spec::builtins field.
-	/// Determine whether a particular address is a builtin contract.
-	fn is_builtin(&self, a: &Address) -> bool { self.builtins().contains_key(a) }
+	/// Determine whether a particular address is a builtin contract active at block number `at`.
+	fn is_builtin(&self, a: &Address, at: u64) -> bool { self.builtins().get(a).map_or(false, |b| b.is_active(at)) }
 	/// Determine the code execution cost of the builtin contract with address `a`.
 	/// Panics if `is_builtin(a)` is not true.
-	fn cost_of_builtin(&self, a: &Address, input: &[u8]) -> U256 { self.builtins().get(a).unwrap().cost(input.len()) }
+	fn cost_of_builtin(&self, a: &Address, input: &[u8]) -> U256 { self.builtins().get(a).unwrap().cost(input) }
 	/// Execution the builtin contract `a` on `input` and return `output`.
 	/// Panics if `is_builtin(a)` is not true.
-	fn execute_builtin(&self, a: &Address, input: &[u8], output: &mut BytesRef) { self.builtins().get(a).unwrap().execute(input, output); }
+	fn execute_builtin(&self, a: &Address, input: &[u8], output: &mut BytesRef) -> Result<(), BuiltinError> {
+		self.builtins().get(a).unwrap().execute(input, output)
+	}
 
 	// TODO: sealing stuff - though might want to leave this for later.
 }
@@ -26,7 +26,7 @@ use types::block_import_error::BlockImportError;
 use snapshot::Error as SnapshotError;
 use ethkey::Error as EthkeyError;
 
-pub use types::executed::{ExecutionError, CallError};
+pub use types::executed::{ExecutionError, CallError, StateDiffError};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 /// Errors concerning transaction processing.
@@ -40,6 +40,8 @@ pub enum TransactionError {
 	TooCheapToReplace,
 	/// Transaction was not imported to the queue because limit has been reached.
 	LimitReached,
+	/// Transactions from the network are not accepted at the moment.
+	QueueThrottled,
 	/// Transaction's gas price is below threshold.
 	InsufficientGasPrice {
 		/// Minimal expected gas price
@@ -63,6 +65,8 @@ pub enum TransactionError {
 	},
 	/// Transaction's gas limit (aka gas) is invalid.
 	InvalidGasLimit(OutOfBounds<U256>),
+	/// Transaction is not allowed by the chain's transaction permission contract.
+	NotAllowed,
 }
 
 impl fmt::Display for TransactionError {
@@ -73,6 +77,7 @@ impl fmt::Display for TransactionError {
 			Old => "No longer valid".into(),
 			TooCheapToReplace => "Gas price too low to replace".into(),
 			LimitReached => "Transaction limit reached".into(),
+			QueueThrottled => "Transaction queue throttled".into(),
 			InsufficientGasPrice { minimal, got } =>
 				format!("Insufficient gas price. Min={}, Given={}", minimal, got),
 			InsufficientBalance { balance, cost } =>
@@ -81,6 +86,7 @@ impl fmt::Display for TransactionError {
 			GasLimitExceeded { limit, got } =>
 				format!("Gas limit exceeded. Limit={}, Given={}", limit, got),
 			InvalidGasLimit(ref err) => format!("Invalid gas limit. {}", err),
+			NotAllowed => "Sender is not permitted to make this transaction".into(),
 		};
 
 		f.write_fmt(format_args!("Transaction error ({})", msg))
@@ -194,6 +200,10 @@ pub enum ImportError {
 	AlreadyQueued,
 	/// Already marked as bad from a previous import (could mean parent is bad).
 	KnownBad,
+	/// The client is shutting down and no longer imports blocks.
+	ShuttingDown,
+	/// Block import was stopped after a state root mismatch.
+	Stopped,
 }
 
 impl fmt::Display for ImportError {
@@ -202,6 +212,8 @@ impl fmt::Display for ImportError {
 			ImportError::AlreadyInChain => "block already in chain",
 			ImportError::AlreadyQueued => "block already in the block queue",
 			ImportError::KnownBad => "block known to be bad",
+			ImportError::ShuttingDown => "client is shutting down",
+			ImportError::Stopped => "import stopped after a state root mismatch",
 		};
 
 		f.write_fmt(format_args!("Block import error ({})", msg))
@@ -105,7 +105,31 @@ impl Engine for Ethash {
 	fn seal_fields(&self) -> usize { 2 }
 
 	fn params(&self) -> &CommonParams { &self.params }
-	fn additional_params(&self) -> HashMap<String, String> { hash_map!["registrar".to_owned() => self.ethash_params.registrar.hex()] }
+	fn additional_params(&self) -> HashMap<String, String> {
+		hash_map![
+			"registrar".to_owned() => self.ethash_params.registrar.hex(),
+			"blockReward".to_owned() => format!("{}", self.ethash_params.block_reward),
+			"durationLimit".to_owned() => format!("{}", self.ethash_params.duration_limit),
+			"minimumDifficulty".to_owned() => format!("{}", self.ethash_params.minimum_difficulty),
+			"difficultyBoundDivisor".to_owned() => format!("{}", self.ethash_params.difficulty_bound_divisor),
+			"gasLimitBoundDivisor".to_owned() => format!("{}", self.ethash_params.gas_limit_bound_divisor)
+		]
+	}
+
+	fn fork_transitions(&self) -> HashMap<String, BlockNumber> {
+		let transitions = vec![
+			("homestead", self.ethash_params.frontier_compatibility_mode_limit),
+			("daoHardfork", self.ethash_params.dao_hardfork_transition),
+			("difficultyHardfork", self.ethash_params.difficulty_hardfork_transition),
+			("bombDefuse", self.ethash_params.bomb_defuse_transition),
+		];
+
+		// transitions which never happen are left out.
+		transitions.into_iter()
+			.filter(|&(_, block)| block != 0x7fffffffffffffff)
+			.map(|(name, block)| (name.to_owned(), block))
+			.collect()
+	}
 
 	fn builtins(&self) -> &BTreeMap<Address, Builtin> {
 		&self.builtins
@@ -383,7 +407,7 @@ mod tests {
 		spec.ensure_db_good(&mut db).unwrap();
 		let last_hashes = Arc::new(vec![genesis_header.hash()]);
 		let b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, Address::zero(), (3141562.into(), 31415620.into()), vec![]).unwrap();
-		let b = b.close();
+		let b = b.close().unwrap();
 		assert_eq!(b.state().balance(&Address::zero()), U256::from_str("4563918244f40000").unwrap());
 	}
 
@@ -402,7 +426,7 @@ mod tests {
 		uncle.set_author(uncle_author);
 		b.push_uncle(uncle).unwrap();
 
-		let b = b.close();
+		let b = b.close().unwrap();
 		assert_eq!(b.state().balance(&Address::zero()), "478eae0e571ba000".into());
 		assert_eq!(b.state().balance(&uncle_author), "3cb71f51fc558000".into());
 	}
@@ -60,6 +60,32 @@ pub fn new_mainnet_like() -> Spec { load(include_bytes!("../../res/ethereum/fron
 /// Create a new Morden chain spec.
 pub fn new_morden() -> Spec { load(include_bytes!("../../res/ethereum/morden.json")) }
 
+/// Create a new Ropsten chain spec.
+pub fn new_ropsten() -> Spec { load(include_bytes!("../../res/ethereum/ropsten.json")) }
+
+/// Names of all bundled chain presets, including aliases.
+pub const PRESETS: &'static [&'static str] = &[
+	"frontier", "homestead", "mainnet",
+	"frontier-dogmatic", "homestead-dogmatic", "classic",
+	"morden", "testnet",
+	"ropsten",
+	"olympic",
+	"expanse",
+];
+
+/// Create the chain spec of a bundled preset by name. Returns `None` if there is no such preset.
+pub fn preset(name: &str) -> Option<Spec> {
+	match name {
+		"frontier" | "homestead" | "mainnet" => Some(new_frontier()),
+		"frontier-dogmatic" | "homestead-dogmatic" | "classic" => Some(new_classic()),
+		"morden" | "testnet" => Some(new_morden()),
+		"ropsten" => Some(new_ropsten()),
+		"olympic" => Some(new_olympic()),
+		"expanse" => Some(new_expanse()),
+		_ => None,
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use common::*;
@@ -95,6 +121,23 @@ mod tests {
 		let _ = morden.engine;
 	}
 
+	#[test]
+	fn all_presets_load() {
+		for name in PRESETS {
+			assert!(preset(name).is_some(), "preset {} is missing", name);
+		}
+		assert!(preset("unknown").is_none());
+	}
+
+	#[test]
+	fn ropsten() {
+		let ropsten = new_ropsten();
+
+		assert_eq!(ropsten.network_id(), 3.into());
+		let genesis = ropsten.genesis_block();
+		assert_eq!(BlockView::new(&genesis).header_view().sha3(), "41941023680923e0fe4d74a34bdac8141f2540e3ae90623718e47d66d1ca4a2d".into());
+	}
+
 	#[test]
 	fn frontier() {
 		let frontier = new_frontier();
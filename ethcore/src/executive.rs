@@ -258,7 +258,7 @@ impl<'a> Executive<'a> {
 		}
 		trace!("Executive::call(params={:?}) self.env_info={:?}", params, self.info);
 
-		if self.engine.is_builtin(&params.code_address) {
+		if self.engine.is_builtin(&params.code_address, self.info.number) {
 			// if destination is builtin, try to execute it
 
 			let default = [];
@@ -268,7 +268,15 @@ impl<'a> Executive<'a> {
 
 			let cost = self.engine.cost_of_builtin(&params.code_address, data);
 			if cost <= params.gas {
-				self.engine.execute_builtin(&params.code_address, data, &mut output);
+				if let Err(e) = self.engine.execute_builtin(&params.code_address, data, &mut output) {
+					// failing builtins consume all gas, just like an exceptional halt.
+					debug!(target: "executive", "Builtin {} failed: {}", params.code_address, e.0);
+					self.state.revert_to_snapshot();
+
+					tracer.trace_failed_call(trace_info, vec![], evm::Error::OutOfGas.into());
+
+					return Err(evm::Error::OutOfGas);
+				}
 				self.state.discard_snapshot();
 
 				// trace only top level calls to builtins to avoid DDoS attacks
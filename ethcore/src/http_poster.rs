@@ -0,0 +1,106 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fire-and-forget JSON notifications over HTTP.
+
+use std::io::Write;
+use hyper::{self, Next, Url};
+use hyper::header::ContentType;
+use hyper::method::Method;
+use hyper::client::{Request, Response, Client};
+use hyper::net::HttpStream;
+use util::Mutex;
+
+/// Posts JSON bodies to a set of URLs, ignoring the responses.
+pub struct JsonPoster {
+	urls: Vec<Url>,
+	client: Mutex<Client<PostHandler>>,
+}
+
+impl JsonPoster {
+	/// Create a poster for the given URLs. Invalid URLs are skipped with a warning.
+	pub fn new(urls: &[String]) -> Self {
+		let urls = urls.into_iter().filter_map(|u| {
+			match Url::parse(u) {
+				Ok(url) => Some(url),
+				Err(e) => {
+					warn!("Error parsing URL {} : {}", u, e);
+					None
+				}
+			}
+		}).collect();
+		JsonPoster {
+			urls: urls,
+			client: Mutex::new(Self::create_client()),
+		}
+	}
+
+	fn create_client() -> Client<PostHandler> {
+		Client::<PostHandler>::configure()
+			.keep_alive(true)
+			.build()
+			.expect("Error creating HTTP client")
+	}
+
+	/// Post `body` to every URL.
+	pub fn post(&self, body: &str) {
+		let mut client = self.client.lock();
+		for u in &self.urls {
+			if let Err(e) = client.request(u.clone(), PostHandler { body: body.to_owned() }) {
+				warn!("Error sending HTTP notification to {} : {}, retrying", u, e);
+				// TODO: remove this once https://github.com/hyperium/hyper/issues/848 is fixed
+				*client = Self::create_client();
+				if let Err(e) = client.request(u.clone(), PostHandler { body: body.to_owned() }) {
+					warn!("Error sending HTTP notification to {} : {}", u, e);
+				}
+			}
+		}
+	}
+}
+
+struct PostHandler {
+	body: String,
+}
+
+impl hyper::client::Handler<HttpStream> for PostHandler {
+	fn on_request(&mut self, request: &mut Request) -> Next {
+		request.set_method(Method::Post);
+		request.headers_mut().set(ContentType::json());
+		Next::write()
+	}
+
+	fn on_request_writable(&mut self, encoder: &mut hyper::Encoder<HttpStream>) -> Next {
+		if let Err(e) = encoder.write_all(self.body.as_bytes()) {
+			trace!("Error posting notification: {}", e);
+		}
+		encoder.close();
+		Next::read()
+
+	}
+
+	fn on_response(&mut self, _response: Response) -> Next {
+		Next::end()
+	}
+
+	fn on_response_readable(&mut self, _decoder: &mut hyper::Decoder<HttpStream>) -> Next {
+		Next::end()
+	}
+
+	fn on_error(&mut self, err: hyper::Error) -> Next {
+		trace!("Error posting notification: {}", err);
+		Next::end()
+	}
+}
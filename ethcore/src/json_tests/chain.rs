@@ -15,71 +15,23 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::test_common::*;
-use client::{BlockChainClient, Client, ClientConfig};
-use block::Block;
-use ethereum;
+use super::run_blockchain_tests;
 use tests::helpers::*;
-use devtools::*;
-use spec::Genesis;
-use ethjson;
-use miner::Miner;
-use io::IoChannel;
 
 pub fn json_chain_test(json_data: &[u8], era: ChainEra) -> Vec<String> {
 	init_log();
-	let tests = ethjson::blockchain::Test::load(json_data).unwrap();
+	let outcomes = run_blockchain_tests(json_data, era, &|_| true).expect("Test vectors are valid JSON");
 	let mut failed = Vec::new();
 
-	for (name, blockchain) in tests.into_iter() {
-		let mut fail = false;
-		{
-			let mut fail_unless = |cond: bool| if !cond && !fail {
-				failed.push(name.clone());
+	for outcome in outcomes {
+		flush!("   - {}...", outcome.name);
+		match outcome.failure {
+			None => flushln!("ok"),
+			Some(failure) => {
 				flushln!("FAIL");
-				fail = true;
-				true
-			} else {false};
-
-			flush!("   - {}...", name);
-
-			let spec = {
-				let genesis = Genesis::from(blockchain.genesis());
-				let state = From::from(blockchain.pre_state.clone());
-				let mut spec = match era {
-					ChainEra::Frontier => ethereum::new_frontier_test(),
-					ChainEra::Homestead => ethereum::new_homestead_test(),
-					ChainEra::DaoHardfork => ethereum::new_daohardfork_test(),
-				};
-				spec.set_genesis_state(state);
-				spec.overwrite_genesis_params(genesis);
-				assert!(spec.is_state_root_valid());
-				spec
-			};
-
-			let temp = RandomTempPath::new();
-			{
-				let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
-				let client = Client::new(
-					ClientConfig::default(),
-					&spec,
-					temp.as_path(),
-					Arc::new(Miner::with_spec(&spec)),
-					IoChannel::disconnected(),
-					&db_config,
-				).unwrap();
-				for b in &blockchain.blocks_rlp() {
-					if Block::is_good(&b) {
-						let _ = client.import_block(b.clone());
-						client.flush_queue();
-						client.import_verified_blocks();
-					}
-				}
-				fail_unless(client.chain_info().best_block_hash == blockchain.best_block.into());
-			}
-		}
-
-		if !fail {
-			flushln!("ok");
+				println!("!!! {}: {}", outcome.name, failure);
+				failed.push(outcome.name);
+			},
 		}
 	}
 
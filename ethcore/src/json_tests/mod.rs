@@ -14,13 +14,27 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+//! Consensus JSON tests (`StateTests` and `BlockchainTests`) execution.
+
+mod runner;
+
+pub use self::runner::{ChainEra, TestKind, TestOutcome, run_file, run_state_tests, run_general_state_tests, run_blockchain_tests};
+
+#[cfg(all(test, feature = "json-tests"))]
 #[macro_use]
 mod test_common;
 
+#[cfg(all(test, feature = "json-tests"))]
 mod transaction;
+#[cfg(all(test, feature = "json-tests"))]
 mod executive;
+#[cfg(all(test, feature = "json-tests"))]
 mod state;
+#[cfg(all(test, feature = "json-tests"))]
 mod chain;
+#[cfg(all(test, feature = "json-tests"))]
 mod homestead_state;
+#[cfg(all(test, feature = "json-tests"))]
 mod homestead_chain;
+#[cfg(all(test, feature = "json-tests"))]
 mod trie;
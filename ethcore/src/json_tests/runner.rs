@@ -0,0 +1,296 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use common::*;
+use client::{BlockChainClient, Client, ClientConfig};
+use block::Block;
+use engines::Engine;
+use spec::{Spec, Genesis};
+use state::State;
+use state_db::StateDB;
+use pod_state::{self, PodState};
+use miner::Miner;
+use io::IoChannel;
+use db::{NUM_COLUMNS, COL_STATE};
+use ethereum;
+use ethjson;
+
+/// Chain rules the tests are executed with.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ChainEra {
+	/// Frontier rules.
+	Frontier,
+	/// Homestead rules from the genesis.
+	Homestead,
+	/// Frontier, Homestead from block #5 and the DAO hard fork at block #8.
+	DaoHardfork,
+}
+
+impl FromStr for ChainEra {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"frontier" => Ok(ChainEra::Frontier),
+			"homestead" => Ok(ChainEra::Homestead),
+			"dao" | "daohardfork" => Ok(ChainEra::DaoHardfork),
+			other => Err(format!("Invalid chain era: {}. Expected frontier, homestead or dao.", other)),
+		}
+	}
+}
+
+impl ChainEra {
+	/// Chain rules of a fork named in the post states of a `GeneralStateTests` file.
+	/// `None` for forks this client does not implement.
+	pub fn from_fork_name(name: &str) -> Option<Self> {
+		match name {
+			"Frontier" => Some(ChainEra::Frontier),
+			"Homestead" => Some(ChainEra::Homestead),
+			_ => None,
+		}
+	}
+
+	fn spec(&self) -> Spec {
+		match *self {
+			ChainEra::Frontier => ethereum::new_frontier_test(),
+			ChainEra::Homestead => ethereum::new_homestead_test(),
+			ChainEra::DaoHardfork => ethereum::new_daohardfork_test(),
+		}
+	}
+
+	fn engine(&self) -> Arc<Engine> {
+		match *self {
+			ChainEra::Frontier => ethereum::new_mainnet_like().engine,
+			_ => self.spec().engine,
+		}
+	}
+}
+
+/// Format of a test file.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TestKind {
+	/// `StateTests`: a single transaction applied to a state.
+	State,
+	/// `GeneralStateTests`: transaction variants applied to a state, with expected roots per fork.
+	GeneralState,
+	/// `BlockchainTests`: blocks imported on top of a genesis.
+	Blockchain,
+}
+
+impl TestKind {
+	/// Guess the format from the directory the file is in, e.g. `tests/BlockchainTests/bcStateTest.json`.
+	pub fn from_path(path: &Path) -> Option<Self> {
+		path.components().rev()
+			.filter_map(|component| component.as_os_str().to_str())
+			.filter_map(|component| match component {
+				"StateTests" => Some(TestKind::State),
+				"GeneralStateTests" => Some(TestKind::GeneralState),
+				"BlockchainTests" => Some(TestKind::Blockchain),
+				_ => None,
+			})
+			.next()
+	}
+}
+
+/// Result of a single test.
+#[derive(Debug, PartialEq)]
+pub struct TestOutcome {
+	/// Name of the test.
+	pub name: String,
+	/// Why the test failed, `None` if it passed.
+	pub failure: Option<String>,
+}
+
+/// Run the tests of a JSON file.
+pub fn run_file(path: &Path, kind: TestKind, era: ChainEra, filter: &Fn(&str) -> bool) -> Result<Vec<TestOutcome>, String> {
+	let mut data = Vec::new();
+	try!(File::open(path).and_then(|mut file| file.read_to_end(&mut data))
+		.map_err(|e| format!("Error reading {}: {}", path.display(), e)));
+	match kind {
+		TestKind::State => run_state_tests(&data, era, filter),
+		TestKind::GeneralState => run_general_state_tests(&data, filter),
+		TestKind::Blockchain => run_blockchain_tests(&data, era, filter),
+	}
+}
+
+/// Run the state tests whose names pass `filter`.
+pub fn run_state_tests(json_data: &[u8], era: ChainEra, filter: &Fn(&str) -> bool) -> Result<Vec<TestOutcome>, String> {
+	let tests = try!(ethjson::state::Test::load(json_data).map_err(|e| format!("Invalid state tests: {}", e)));
+	let engine = era.engine();
+	Ok(tests.into_iter()
+		.filter(|&(ref name, _)| filter(name))
+		.map(|(name, test)| TestOutcome {
+			failure: state_test(test, &*engine).err(),
+			name: name,
+		})
+		.collect())
+}
+
+/// Run the general state tests whose names pass `filter`. The chain rules are taken from
+/// the fork names of the post states; forks this client does not implement are skipped.
+/// Every post state is reported as `name/fork/index`.
+pub fn run_general_state_tests(json_data: &[u8], filter: &Fn(&str) -> bool) -> Result<Vec<TestOutcome>, String> {
+	let tests = try!(ethjson::state::GeneralTest::load(json_data).map_err(|e| format!("Invalid general state tests: {}", e)));
+	let mut outcomes = Vec::new();
+	for (name, test) in tests.into_iter().filter(|&(ref name, _)| filter(name)) {
+		let env: EnvInfo = test.env.into();
+		let pre: PodState = test.pre_state.into();
+		for (fork, posts) in &test.post_states {
+			let engine = match ChainEra::from_fork_name(fork) {
+				Some(era) => era.engine(),
+				None => continue,
+			};
+			for (index, post) in posts.iter().enumerate() {
+				outcomes.push(TestOutcome {
+					name: format!("{}/{}/{}", name, fork, index),
+					failure: general_state_test(&env, &pre, &test.transaction, post, &*engine).err(),
+				});
+			}
+		}
+	}
+	Ok(outcomes)
+}
+
+/// Run the blockchain tests whose names pass `filter`.
+pub fn run_blockchain_tests(json_data: &[u8], era: ChainEra, filter: &Fn(&str) -> bool) -> Result<Vec<TestOutcome>, String> {
+	let tests = try!(ethjson::blockchain::Test::load(json_data).map_err(|e| format!("Invalid blockchain tests: {}", e)));
+	Ok(tests.into_iter()
+		.filter(|&(ref name, _)| filter(name))
+		.map(|(name, test)| TestOutcome {
+			failure: blockchain_test(test, era).err(),
+			name: name,
+		})
+		.collect())
+}
+
+fn state_test(test: ethjson::state::State, engine: &Engine) -> Result<(), String> {
+	let transaction = test.transaction.into();
+	let post_state_root: H256 = test.post_state_root.into();
+	let env = test.env.into();
+	let pre: PodState = test.pre_state.into();
+	let post: PodState = test.post_state.into();
+	let logs: Vec<LogEntry> = test.logs.into_iter().map(Into::into).collect();
+
+	let calc_post = sec_trie_root(post.get().iter().map(|(k, v)| (k.to_vec(), v.rlp())).collect());
+	if post_state_root != calc_post {
+		return Err(format!("Trie root mismatch (got: {}, expect: {})", calc_post, post_state_root));
+	}
+
+	let db = Arc::new(Database::in_memory(NUM_COLUMNS));
+	let mut state = State::new(StateDB::new(journaldb::new(db, journaldb::Algorithm::EarlyMerge, COL_STATE)), U256::zero(), Default::default());
+	state.populate_from(pre);
+	try!(state.commit().map_err(|e| format!("Internal error: {}", e)));
+	let res = state.apply(&env, engine, &transaction, false);
+
+	if state.root() != &post_state_root {
+		let our_post = state.to_pod();
+		return Err(format!("State mismatch (got: {}, expect: {})\nDiff ---expect -> +++got:\n{}",
+			state.root(), post_state_root, pod_state::diff_pod(&post, &our_post)));
+	}
+
+	if let Ok(r) = res {
+		if logs != r.receipt.logs {
+			return Err(format!("Logs mismatch (got: {:?}, expect: {:?})", r.receipt.logs, logs));
+		}
+	}
+	Ok(())
+}
+
+fn general_state_test(
+	env: &EnvInfo,
+	pre: &PodState,
+	transaction: &ethjson::state::MultiTransaction,
+	post: &ethjson::state::PostStateResult,
+	engine: &Engine,
+) -> Result<(), String> {
+	let transaction: SignedTransaction = match transaction.select(&post.indexes) {
+		Some(transaction) => transaction.into(),
+		None => return Err(format!("Invalid transaction indexes: {:?}", post.indexes)),
+	};
+	let post_state_root: H256 = post.hash.clone().into();
+
+	let db = Arc::new(Database::in_memory(NUM_COLUMNS));
+	let mut state = State::new(StateDB::new(journaldb::new(db, journaldb::Algorithm::EarlyMerge, COL_STATE)), U256::zero(), Default::default());
+	state.populate_from(pre.clone());
+	try!(state.commit().map_err(|e| format!("Internal error: {}", e)));
+	let _ = state.apply(env, engine, &transaction, false);
+
+	match state.root() == &post_state_root {
+		true => Ok(()),
+		false => Err(format!("State root mismatch (got: {}, expect: {})", state.root(), post_state_root)),
+	}
+}
+
+fn blockchain_test(test: ethjson::blockchain::BlockChain, era: ChainEra) -> Result<(), String> {
+	let mut spec = era.spec();
+	spec.set_genesis_state(From::from(test.pre_state.clone()));
+	spec.overwrite_genesis_params(Genesis::from(test.genesis()));
+	if !spec.is_state_root_valid() {
+		return Err("Genesis state root mismatch".into());
+	}
+
+	let db_config = DatabaseConfig::with_columns(NUM_COLUMNS);
+	let client = try!(Client::with_database(
+		ClientConfig::default(),
+		&spec,
+		Arc::new(Database::in_memory(NUM_COLUMNS)),
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+		&db_config,
+	).map_err(|e| format!("Error creating the client: {}", e)));
+
+	for block in &test.blocks_rlp() {
+		if Block::is_good(block) {
+			let _ = client.import_block(block.clone());
+			client.flush_queue();
+			client.import_verified_blocks();
+		}
+	}
+
+	let best_block = client.chain_info().best_block_hash;
+	let expected: H256 = test.best_block.into();
+	match best_block == expected {
+		true => Ok(()),
+		false => Err(format!("Best block mismatch (got: {}, expect: {})", best_block, expected)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+	use super::{ChainEra, TestKind};
+
+	#[test]
+	fn should_guess_test_kind_from_path() {
+		assert_eq!(TestKind::from_path(Path::new("tests/StateTests/stExample.json")), Some(TestKind::State));
+		assert_eq!(TestKind::from_path(Path::new("tests/BlockchainTests/RandomTests/bl10251623GO.json")), Some(TestKind::Blockchain));
+		assert_eq!(TestKind::from_path(Path::new("tests/VMTests/vmArithmeticTest.json")), None);
+		assert_eq!(TestKind::from_path(Path::new("tests/GeneralStateTests/stExample/add11.json")), Some(TestKind::GeneralState));
+	}
+
+	#[test]
+	fn should_parse_chain_era() {
+		assert_eq!("homestead".parse(), Ok(ChainEra::Homestead));
+		assert_eq!("dao".parse(), Ok(ChainEra::DaoHardfork));
+		assert!("metropolis".parse::<ChainEra>().is_err());
+		assert_eq!(ChainEra::from_fork_name("Homestead"), Some(ChainEra::Homestead));
+		assert_eq!(ChainEra::from_fork_name("EIP158"), None);
+	}
+}
@@ -15,10 +15,8 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::test_common::*;
+use super::run_state_tests;
 use tests::helpers::*;
-use pod_state::{self, PodState};
-use ethereum;
-use ethjson;
 
 fn do_json_test(json_data: &[u8]) -> Vec<String> {
 	json_chain_test(json_data, ChainEra::Frontier)
@@ -26,66 +24,18 @@ fn do_json_test(json_data: &[u8]) -> Vec<String> {
 
 pub fn json_chain_test(json_data: &[u8], era: ChainEra) -> Vec<String> {
 	init_log();
-	let tests = ethjson::state::Test::load(json_data).unwrap();
+	let outcomes = run_state_tests(json_data, era, &|_| true).expect("Test vectors are valid JSON");
 	let mut failed = Vec::new();
-	let engine = match era {
-		ChainEra::Frontier => ethereum::new_mainnet_like().engine,
-		ChainEra::Homestead => ethereum::new_homestead_test().engine,
-		ChainEra::DaoHardfork => ethereum::new_daohardfork_test().engine,
-};
 
-	for (name, test) in tests.into_iter() {
-		let mut fail = false;
-		{
-			let mut fail_unless = |cond: bool| if !cond && !fail {
-				failed.push(name.clone());
+	for outcome in outcomes {
+		flush!("   - {}...", outcome.name);
+		match outcome.failure {
+			None => flushln!("ok"),
+			Some(failure) => {
 				flushln!("FAIL");
-				fail = true;
-				true
-			} else {false};
-
-			flush!("   - {}...", name);
-
-			let transaction = test.transaction.into();
-			let post_state_root = test.post_state_root.into();
-			let env = test.env.into();
-			let pre: PodState = test.pre_state.into();
-			let post: PodState = test.post_state.into();
-			let logs: Vec<LogEntry> = test.logs.into_iter().map(Into::into).collect();
-
-			let calc_post = sec_trie_root(post.get().iter().map(|(k, v)| (k.to_vec(), v.rlp())).collect());
-
-			if fail_unless(post_state_root == calc_post) {
-				println!("!!! {}: Trie root mismatch (got: {}, expect: {}):", name, calc_post, post_state_root);
-				println!("!!! Post:\n{}", post);
-			} else {
-				let mut state_result = get_temp_state();
-				let mut state = state_result.reference_mut();
-				state.populate_from(pre);
-				state.commit()
-					.expect(&format!("State test {} failed due to internal error.", name));
-				let res = state.apply(&env, &*engine, &transaction, false);
-
-				if fail_unless(state.root() == &post_state_root) {
-					println!("!!! {}: State mismatch (got: {}, expect: {}):", name, state.root(), post_state_root);
-					let our_post = state.to_pod();
-					println!("Got:\n{}", our_post);
-					println!("Expect:\n{}", post);
-					println!("Diff ---expect -> +++got:\n{}", pod_state::diff_pod(&post, &our_post));
-				}
-
-				if let Ok(r) = res {
-					if fail_unless(logs == r.receipt.logs) {
-						println!("!!! {}: Logs mismatch:", name);
-						println!("Got:\n{:?}", r.receipt.logs);
-						println!("Expect:\n{:?}", logs);
-					}
-				}
-			}
-		}
-
-		if !fail {
-			flushln!("ok");
+				println!("!!! {}: {}", outcome.name, failure);
+				failed.push(outcome.name);
+			},
 		}
 	}
 
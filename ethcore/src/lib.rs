@@ -101,6 +101,10 @@ extern crate bit_set;
 extern crate rlp;
 extern crate ethcore_bloom_journal as bloom_journal;
 extern crate byteorder;
+extern crate num;
+extern crate bn;
+extern crate hardware_wallet;
+extern crate ethabi;
 
 #[macro_use]
 extern crate log;
@@ -136,6 +140,8 @@ pub mod miner;
 pub mod snapshot;
 pub mod action_params;
 pub mod db;
+pub mod private_transactions;
+pub mod json_tests;
 #[macro_use] pub mod evm;
 
 mod cache_manager;
@@ -154,12 +160,15 @@ mod verification;
 mod blockchain;
 mod types;
 mod factory;
+mod http_poster;
+mod contract_function;
+mod tx_permission;
+mod node_permission;
 
 #[cfg(test)]
 mod tests;
-#[cfg(test)]
-#[cfg(feature="json-tests")]
-mod json_tests;
+#[cfg(all(feature="benches", test))]
+mod benches;
 
 pub use types::*;
 pub use executive::contract_address;
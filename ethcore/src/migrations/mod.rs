@@ -16,6 +16,8 @@
 
 //! Database migrations.
 
+use util::migration::ChangeColumns;
+
 pub mod state;
 pub mod blocks;
 pub mod extras;
@@ -26,3 +28,10 @@ pub use self::v9::Extract;
 
 mod v10;
 pub use self::v10::ToV10;
+
+/// Adds the column storing blocks which failed verification.
+pub const TO_V11: ChangeColumns = ChangeColumns {
+	pre_columns: Some(6),
+	post_columns: Some(7),
+	version: 11,
+};
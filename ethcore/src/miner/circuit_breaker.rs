@@ -0,0 +1,141 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction queue admission circuit breaker.
+//!
+//! Trips when deep reorganisations happen repeatedly in a short period of time,
+//! which usually means the node is under attack. While tripped, the miner stops accepting
+//! transactions from the network and requires a higher gas price for local ones,
+//! leaving the CPU to chain processing.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Options for the reorg circuit breaker.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CircuitBreakerOptions {
+	/// Minimal number of retracted blocks for a reorganisation to be considered deep.
+	pub min_depth: usize,
+	/// Number of deep reorganisations within `window` which trips the breaker. Zero disables it.
+	pub threshold: usize,
+	/// Period over which deep reorganisations are counted. The breaker stays tripped for this long after the last one.
+	pub window: Duration,
+	/// Factor by which the minimal gas price of local transactions is raised while tripped.
+	pub gas_price_multiplier: u32,
+}
+
+impl Default for CircuitBreakerOptions {
+	fn default() -> Self {
+		CircuitBreakerOptions {
+			min_depth: 6,
+			threshold: 3,
+			window: Duration::from_secs(600),
+			gas_price_multiplier: 4,
+		}
+	}
+}
+
+/// Keeps track of recent deep reorganisations.
+pub struct CircuitBreaker {
+	options: CircuitBreakerOptions,
+	deep_reorgs: VecDeque<Instant>,
+	tripped_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+	/// Creates new circuit breaker.
+	pub fn new(options: CircuitBreakerOptions) -> Self {
+		CircuitBreaker {
+			options: options,
+			deep_reorgs: VecDeque::new(),
+			tripped_until: None,
+		}
+	}
+
+	/// Factor by which the minimal gas price is raised while tripped.
+	pub fn gas_price_multiplier(&self) -> u32 {
+		self.options.gas_price_multiplier
+	}
+
+	/// Note a reorganisation retracting `depth` blocks.
+	/// Returns `true` if this trips the breaker.
+	pub fn note_reorg(&mut self, depth: usize, now: Instant) -> bool {
+		if self.options.threshold == 0 || depth < self.options.min_depth {
+			return false;
+		}
+
+		let window = self.options.window;
+		while self.deep_reorgs.front().map_or(false, |t| now.duration_since(*t) > window) {
+			self.deep_reorgs.pop_front();
+		}
+		self.deep_reorgs.push_back(now);
+
+		if self.deep_reorgs.len() < self.options.threshold {
+			return false;
+		}
+
+		let was_tripped = self.is_tripped(now);
+		self.tripped_until = Some(now + window);
+		!was_tripped
+	}
+
+	/// Returns `true` if admission should be tightened.
+	pub fn is_tripped(&self, now: Instant) -> bool {
+		self.tripped_until.map_or(false, |until| now < until)
+	}
+
+	/// Number of deep reorganisations within the last window.
+	pub fn recent_deep_reorgs(&self, now: Instant) -> usize {
+		let window = self.options.window;
+		self.deep_reorgs.iter().filter(|t| now.duration_since(**t) <= window).count()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use super::{CircuitBreaker, CircuitBreakerOptions};
+
+	#[test]
+	fn trips_on_repeated_deep_reorgs() {
+		let mut breaker = CircuitBreaker::new(CircuitBreakerOptions::default());
+		let start = Instant::now();
+
+		// shallow reorgs don't count.
+		assert!(!breaker.note_reorg(1, start));
+		assert!(!breaker.note_reorg(6, start));
+		assert!(!breaker.note_reorg(10, start + Duration::from_secs(10)));
+		assert!(!breaker.is_tripped(start + Duration::from_secs(10)));
+
+		assert!(breaker.note_reorg(7, start + Duration::from_secs(20)));
+		assert!(breaker.is_tripped(start + Duration::from_secs(30)));
+		assert_eq!(breaker.recent_deep_reorgs(start + Duration::from_secs(30)), 3);
+
+		// recovers once the window has passed.
+		assert!(!breaker.is_tripped(start + Duration::from_secs(621)));
+	}
+
+	#[test]
+	fn forgets_old_reorgs() {
+		let mut breaker = CircuitBreaker::new(CircuitBreakerOptions::default());
+		let start = Instant::now();
+
+		assert!(!breaker.note_reorg(6, start));
+		assert!(!breaker.note_reorg(6, start + Duration::from_secs(400)));
+		assert!(!breaker.note_reorg(6, start + Duration::from_secs(700)));
+		assert!(!breaker.is_tripped(start + Duration::from_secs(700)));
+	}
+}
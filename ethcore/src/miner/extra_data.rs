@@ -0,0 +1,134 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Extra data templates of sealed blocks.
+//!
+//! The extra data set on the miner may contain placeholders which are substituted
+//! when a block is prepared: `{version}` with the short client version and
+//! `{timestamp}` with the UNIX timestamp of the block.
+
+use time::get_time;
+use util::Bytes;
+use util::misc::short_version;
+use rlp::{UntrustedRlp, View};
+
+const VERSION: &'static [u8] = b"{version}";
+const TIMESTAMP: &'static [u8] = b"{timestamp}";
+
+/// Substitute the placeholders of the extra data template.
+/// The result is truncated to `max_size` bytes.
+pub fn render_extra_data(template: &[u8], timestamp: u64, max_size: usize) -> Bytes {
+	let version = short_version().into_bytes();
+	let timestamp = timestamp.to_string().into_bytes();
+
+	let mut rendered = Vec::with_capacity(template.len());
+	let mut rest = template;
+	while !rest.is_empty() {
+		if rest.starts_with(VERSION) {
+			rendered.extend_from_slice(&version);
+			rest = &rest[VERSION.len()..];
+		} else if rest.starts_with(TIMESTAMP) {
+			rendered.extend_from_slice(&timestamp);
+			rest = &rest[TIMESTAMP.len()..];
+		} else {
+			rendered.push(rest[0]);
+			rest = &rest[1..];
+		}
+	}
+
+	if rendered.len() > max_size {
+		warn!(target: "miner", "Extra data is {} bytes long, truncating to {} bytes.", rendered.len(), max_size);
+		rendered.truncate(max_size);
+	}
+	rendered
+}
+
+/// Check that the extra data template fits in `max_size` bytes once rendered.
+pub fn check_extra_data(template: &[u8], max_size: usize) -> Result<(), String> {
+	let size = render_extra_data(template, get_time().sec as u64, usize::max_value()).len();
+	if size > max_size {
+		Err(format!("Extra data is {} bytes long, the maximum for this chain is {} bytes.", size, max_size))
+	} else {
+		Ok(())
+	}
+}
+
+/// Human-readable form of the extra data of a block.
+/// The version data sealed by default is shown as e.g. `Parity/v1.4.0/rustc1.12.0/li`,
+/// anything else as its printable ASCII characters.
+pub fn decode_extra_data(extra_data: &[u8]) -> String {
+	decode_version_data(extra_data).unwrap_or_else(|| {
+		extra_data.iter()
+			.filter(|b| **b >= 0x20 && **b < 0x7f)
+			.map(|b| *b as char)
+			.collect::<String>()
+			.trim()
+			.to_owned()
+	})
+}
+
+fn decode_version_data(extra_data: &[u8]) -> Option<String> {
+	let rlp = UntrustedRlp::new(extra_data);
+	if !rlp.is_list() || rlp.item_count() != 4 {
+		return None;
+	}
+
+	let version: u32 = match rlp.val_at(0) {
+		Ok(version) => version,
+		Err(_) => return None,
+	};
+	let fields = (rlp.val_at::<String>(1), rlp.val_at::<String>(2), rlp.val_at::<String>(3));
+	match fields {
+		(Ok(client), Ok(rustc), Ok(os)) => Some(format!("{}/v{}.{}.{}/rustc{}/{}", client, version >> 16, (version >> 8) & 0xff, version & 0xff, rustc, os)),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rlp::{RlpStream, Stream};
+	use util::misc::short_version;
+	use super::{render_extra_data, check_extra_data, decode_extra_data};
+
+	#[test]
+	fn should_render_extra_data_template() {
+		assert_eq!(render_extra_data(b"pool {timestamp}", 1476000000, 32), b"pool 1476000000".to_vec());
+		assert_eq!(render_extra_data(b"{version}", 0, 32), short_version().into_bytes());
+		assert_eq!(render_extra_data(b"{unknown}", 0, 32), b"{unknown}".to_vec());
+		assert_eq!(render_extra_data(b"0123456789", 0, 4), b"0123".to_vec());
+	}
+
+	#[test]
+	fn should_check_rendered_extra_data_size() {
+		assert!(check_extra_data(b"0123", 4).is_ok());
+		assert!(check_extra_data(b"01234", 4).is_err());
+		assert!(check_extra_data(b"{timestamp}", 10).is_ok());
+		assert!(check_extra_data(b"{timestamp}", 9).is_err());
+	}
+
+	#[test]
+	fn should_decode_extra_data() {
+		let mut s = RlpStream::new_list(4);
+		s.append(&((1u32 << 16) + (4 << 8) + 1));
+		s.append(&"Parity");
+		s.append(&"1.12.0");
+		s.append(&"li");
+
+		assert_eq!(decode_extra_data(&s.out()), "Parity/v1.4.1/rustc1.12.0/li");
+		assert_eq!(decode_extra_data(b"Geth\x01\x02 pool "), "Geth pool");
+		assert_eq!(decode_extra_data(&[]), "");
+	}
+}
@@ -24,14 +24,17 @@ use views::{BlockView, HeaderView};
 use state::State;
 use client::{MiningBlockChainClient, Executive, Executed, EnvInfo, TransactOptions, BlockID, CallAnalytics};
 use executive::contract_address;
-use block::{ClosedBlock, SealedBlock, IsBlock, Block};
+use block::{OpenBlock, ClosedBlock, SealedBlock, IsBlock, Block};
 use error::*;
 use transaction::{Action, SignedTransaction};
 use receipt::{Receipt, RichReceipt};
 use spec::Spec;
 use engines::Engine;
-use miner::{MinerService, MinerStatus, TransactionQueue, AccountDetails, TransactionOrigin};
+use miner::{MinerService, MinerStatus, TransactionQueue, AccountDetails, TransactionOrigin, EvictionStrategy};
 use miner::work_notify::WorkPoster;
+use miner::extra_data::render_extra_data;
+use miner::circuit_breaker::{CircuitBreaker, CircuitBreakerOptions};
+use miner::service_transaction_checker::ServiceTransactionChecker;
 use client::TransactionImportResult;
 use miner::price_info::PriceInfo;
 use header::BlockNumber;
@@ -65,12 +68,22 @@ pub struct MinerOptions {
 	pub tx_gas_limit: U256,
 	/// Maximum size of the transaction queue.
 	pub tx_queue_size: usize,
+	/// Maximum number of transactions from a single sender in the transaction queue.
+	pub tx_queue_per_sender: usize,
+	/// Maximum amount of memory (in bytes) used by the transaction queue.
+	pub tx_queue_memory_limit: Option<usize>,
+	/// Which transactions to drop when the transaction queue is full.
+	pub tx_queue_eviction: EvictionStrategy,
 	/// Whether we should fallback to providing all the queue's transactions or just pending.
 	pub pending_set: PendingSet,
 	/// How many historical work packages can we store before running out?
 	pub work_queue_size: usize,
 	/// Can we submit two different solutions for the same block and expect both to result in an import?
 	pub enable_resubmission: bool,
+	/// When to tighten transaction admission because of repeated deep reorganisations.
+	pub reorg_circuit_breaker: CircuitBreakerOptions,
+	/// Refuse zero gas price transactions, even from senders certified by the service transaction checker.
+	pub refuse_service_transactions: bool,
 }
 
 impl Default for MinerOptions {
@@ -82,10 +95,15 @@ impl Default for MinerOptions {
 			reseal_on_own_tx: true,
 			tx_gas_limit: !U256::zero(),
 			tx_queue_size: 2048,
+			tx_queue_per_sender: usize::max_value(),
+			tx_queue_memory_limit: None,
+			tx_queue_eviction: EvictionStrategy::GasPrice,
 			pending_set: PendingSet::AlwaysQueue,
 			reseal_min_period: Duration::from_secs(2),
 			work_queue_size: 20,
 			enable_resubmission: true,
+			reorg_circuit_breaker: Default::default(),
+			refuse_service_transactions: false,
 		}
 	}
 }
@@ -165,12 +183,35 @@ struct SealingWork {
 	enabled: bool,
 }
 
+/// Pending block for RPC queries, it is never sealed.
+struct PendingView {
+	block: Option<ClosedBlock>,
+	/// External transactions were imported since the block was built.
+	outdated: bool,
+	/// Earliest time an outdated block is rebuilt.
+	next_rebuild: Instant,
+}
+
+impl PendingView {
+	fn empty() -> Self {
+		PendingView {
+			block: None,
+			outdated: false,
+			next_rebuild: Instant::now(),
+		}
+	}
+}
+
 /// Keeps track of transactions using priority queue and holds currently mined block.
 /// Handles preparing work for "work sealing" or seals "internally" if Engine does not require work.
 pub struct Miner {
 	// NOTE [ToDr]  When locking always lock in this order!
+	// held while the pending view is rebuilt, so that concurrent queries wait for a single rebuild.
+	pending_view_rebuild: Mutex<()>,
 	transaction_queue: Arc<Mutex<TransactionQueue>>,
+	circuit_breaker: Mutex<CircuitBreaker>,
 	sealing_work: Mutex<SealingWork>,
+	pending_view: Mutex<PendingView>,
 	next_allowed_reseal: Mutex<Instant>,
 	sealing_block_last_request: Mutex<u64>,
 	// for sealing...
@@ -185,6 +226,7 @@ pub struct Miner {
 	accounts: Option<Arc<AccountProvider>>,
 	work_poster: Option<WorkPoster>,
 	gas_pricer: Mutex<GasPricer>,
+	service_transaction_checker: Option<ServiceTransactionChecker>,
 }
 
 impl Miner {
@@ -194,9 +236,18 @@ impl Miner {
 			true => None,
 			false => Some(WorkPoster::new(&options.new_work_notify))
 		};
-		let txq = Arc::new(Mutex::new(TransactionQueue::with_limits(options.tx_queue_size, options.tx_gas_limit)));
+		let mut txq = TransactionQueue::with_limits(options.tx_queue_size, options.tx_gas_limit);
+		txq.set_per_sender_limit(options.tx_queue_per_sender);
+		txq.set_memory_limit(options.tx_queue_memory_limit.unwrap_or(usize::max_value()), options.tx_queue_eviction);
+		let txq = Arc::new(Mutex::new(txq));
+		let service_transaction_checker = match options.refuse_service_transactions {
+			true => None,
+			false => Some(ServiceTransactionChecker::new()),
+		};
 		Miner {
+			pending_view_rebuild: Mutex::new(()),
 			transaction_queue: txq,
+			circuit_breaker: Mutex::new(CircuitBreaker::new(options.reorg_circuit_breaker.clone())),
 			next_allowed_reseal: Mutex::new(Instant::now()),
 			sealing_block_last_request: Mutex::new(0),
 			sealing_work: Mutex::new(SealingWork{
@@ -205,6 +256,7 @@ impl Miner {
 					|| !options.new_work_notify.is_empty()
 					|| spec.engine.is_default_sealer().unwrap_or(false)
 			}),
+			pending_view: Mutex::new(PendingView::empty()),
 			seals_internally: spec.engine.is_default_sealer().is_some(),
 			gas_range_target: RwLock::new((U256::zero(), U256::zero())),
 			author: RwLock::new(Address::default()),
@@ -214,6 +266,7 @@ impl Miner {
 			engine: spec.engine.clone(),
 			work_poster: work_poster,
 			gas_pricer: Mutex::new(gas_pricer),
+			service_transaction_checker: service_transaction_checker,
 		}
 	}
 
@@ -236,19 +289,56 @@ impl Miner {
 		self.sealing_work.lock().queue.reset();
 	}
 
-	/// Get `Some` `clone()` of the current pending block's state or `None` if we're not sealing.
-	pub fn pending_state(&self) -> Option<State> {
-		self.sealing_work.lock().queue.peek_last_ref().map(|b| b.block().fields().state.clone())
+	/// Get `Some` `clone()` of the current pending block's state or `None` if we're not sealing
+	/// on top of `latest_block_number`.
+	pub fn pending_state(&self, latest_block_number: BlockNumber) -> Option<State> {
+		self.from_pending_block(latest_block_number, || None, |b| Some(b.block().fields().state.clone()))
+	}
+
+	/// Get `Some` `clone()` of the current pending block or `None` if we're not sealing
+	/// on top of `latest_block_number`.
+	pub fn pending_block(&self, latest_block_number: BlockNumber) -> Option<Block> {
+		self.from_pending_block(latest_block_number, || None, |b| Some(b.base().clone()))
 	}
 
-	/// Get `Some` `clone()` of the current pending block's state or `None` if we're not sealing.
-	pub fn pending_block(&self) -> Option<Block> {
-		self.sealing_work.lock().queue.peek_last_ref().map(|b| b.base().clone())
+	/// Import transactions kept from a previous run. They are treated as local ones,
+	/// but sealing is left to the next block import instead of resealing for each of them.
+	pub fn import_restored_transactions(&self, chain: &MiningBlockChainClient, transactions: Vec<SignedTransaction>) -> Vec<Result<TransactionImportResult, Error>> {
+		let results = {
+			let transactions = self.check_transactions(chain, transactions);
+			let mut transaction_queue = self.transaction_queue.lock();
+			self.add_transactions_to_queue(
+				chain, transactions, TransactionOrigin::Local, &mut transaction_queue
+			)
+		};
+		if results.iter().any(|r| r.is_ok()) {
+			self.invalidate_pending_view();
+		}
+		results
+	}
+
+	/// Seal and import a new block even if there are no pending transactions.
+	/// Returns `false` if the engine does not seal internally or the block could not be imported.
+	pub fn seal_empty_block(&self, chain: &MiningBlockChainClient) -> bool {
+		if !self.seals_internally || Self::is_disabled(chain) {
+			return false;
+		}
+		let block = match self.prepare_block(chain) {
+			Ok((block, _)) => block,
+			Err(e) => {
+				warn!(target: "miner", "Unable to prepare block: {}", e);
+				return false;
+			},
+		};
+		match self.seal_block_internally(block) {
+			Ok(sealed) => chain.import_block(sealed.rlp_bytes()).is_ok(),
+			Err(_) => false,
+		}
 	}
 
 	#[cfg_attr(feature="dev", allow(match_same_arms))]
 	/// Prepares new block for sealing including top transactions from queue.
-	fn prepare_block(&self, chain: &MiningBlockChainClient) -> (ClosedBlock, Option<H256>) {
+	fn prepare_block(&self, chain: &MiningBlockChainClient) -> Result<(ClosedBlock, Option<H256>), Error> {
 		{
 			trace!(target: "miner", "prepare_block: recalibrating...");
 			let txq = self.transaction_queue.clone();
@@ -259,7 +349,7 @@ impl Miner {
 			trace!(target: "miner", "prepare_block: done recalibration.");
 		}
 
-		let (transactions, mut open_block, original_work_hash) = {
+		let (transactions, open_block, original_work_hash) = {
 			let transactions = {self.transaction_queue.lock().top_transactions()};
 			let mut sealing_work = self.sealing_work.lock();
 			let last_work_hash = sealing_work.queue.peek_last_ref().map(|pb| pb.block().fields().header.hash());
@@ -281,22 +371,47 @@ impl Miner {
 				None => {
 					// block not found - create it.
 					trace!(target: "miner", "prepare_block: No existing work - making new block");
-					chain.prepare_open_block(
-						self.author(),
-						(self.gas_floor_target(), self.gas_ceil_target()),
-						self.extra_data()
-					)
+					self.open_block(chain)
 				}
 			};
 			(transactions, open_block, last_work_hash)
 		};
 
+		self.push_transactions(chain, open_block, transactions).map(|block| (block, original_work_hash))
+	}
+
+	/// Opens a new block on top of the best block.
+	fn open_block<'a>(&'a self, chain: &'a MiningBlockChainClient) -> OpenBlock<'a> {
+		let template = self.extra_data();
+		let mut open_block = chain.prepare_open_block(
+			self.author(),
+			(self.gas_floor_target(), self.gas_ceil_target()),
+			template.clone()
+		);
+		// render the template with the timestamp of the block, unless the engine replaced the extra data.
+		if open_block.block().fields().header.extra_data() == &template {
+			let timestamp = open_block.block().fields().header.timestamp();
+			let extra_data = render_extra_data(&template, timestamp, self.engine.maximum_extra_data_size());
+			open_block.set_extra_data(extra_data).expect("rendered extra data is truncated to the maximum size; qed");
+		}
+		open_block
+	}
+
+	/// Pushes `transactions` to the block and closes it.
+	/// Transactions which can't be included are removed from the queue or penalized.
+	fn push_transactions(&self, chain: &MiningBlockChainClient, mut open_block: OpenBlock, transactions: Vec<SignedTransaction>) -> Result<ClosedBlock, Error> {
 		let mut invalid_transactions = HashSet::new();
 		let mut transactions_to_penalize = HashSet::new();
 		let block_number = open_block.block().fields().header.number();
 		// TODO: push new uncles, too.
 		for tx in transactions {
 			let hash = tx.hash();
+			// the permission is cached for the parent of the block, so this is cheap.
+			if !chain.transaction_allowed(&tx) {
+				debug!(target: "miner", "Skipping transaction not allowed by the permission contract: {:?}", hash);
+				invalid_transactions.insert(hash);
+				continue;
+			}
 			match open_block.push_transaction(tx, None) {
 				Err(Error::Execution(ExecutionError::BlockGasLimitReached { gas_limit, gas_used, gas })) => {
 					debug!(target: "miner", "Skipping adding transaction to block because of gas limit: {:?} (limit: {:?}, used: {:?}, gas: {:?})", hash, gas_limit, gas_used, gas);
@@ -345,7 +460,7 @@ impl Miner {
 				queue.penalize(&hash);
 			}
 		}
-		(block, original_work_hash)
+		block
 	}
 
 	/// Check is reseal is allowed and necessary.
@@ -445,9 +560,17 @@ impl Miner {
 		queue.set_gas_limit(gas_limit);
 	}
 
+	/// The client keeps no state in relay or headers-only mode, so no blocks can be prepared on top of it.
+	fn is_disabled(chain: &MiningBlockChainClient) -> bool {
+		!chain.keeps_state()
+	}
+
 	/// Returns true if we had to prepare new pending block.
 	fn prepare_work_sealing(&self, chain: &MiningBlockChainClient) -> bool {
 		trace!(target: "miner", "prepare_work_sealing: entering");
+		if Self::is_disabled(chain) {
+			return false;
+		}
 		let prepare_new = {
 			let mut sealing_work = self.sealing_work.lock();
 			let have_work = sealing_work.queue.peek_last_ref().is_some();
@@ -464,8 +587,10 @@ impl Miner {
 			// | NOTE Code below requires transaction_queue and sealing_work locks.     |
 			// | Make sure to release the locks before calling that method.             |
 			// --------------------------------------------------------------------------
-			let (block, original_work_hash) = self.prepare_block(chain);
-			self.prepare_work(block, original_work_hash);
+			match self.prepare_block(chain) {
+				Ok((block, original_work_hash)) => self.prepare_work(block, original_work_hash),
+				Err(e) => warn!(target: "miner", "Unable to prepare block: {}", e),
+			}
 		}
 		let mut sealing_block_last_request = self.sealing_block_last_request.lock();
 		let best_number = chain.chain_info().best_block_number;
@@ -478,7 +603,21 @@ impl Miner {
 		prepare_new
 	}
 
-	fn add_transactions_to_queue(&self, chain: &MiningBlockChainClient, transactions: Vec<SignedTransaction>, origin: TransactionOrigin, transaction_queue: &mut TransactionQueue) ->
+	/// Check `transactions` against the chain's transaction permission contract and whether zero gas price
+	/// ones are certified service transactions. Contract calls are made before the transaction queue is locked.
+	fn check_transactions(&self, chain: &MiningBlockChainClient, transactions: Vec<SignedTransaction>) -> Vec<Result<(SignedTransaction, bool), Error>> {
+		transactions.into_iter()
+			.map(|tx| match chain.transaction_allowed(&tx) {
+				true => {
+					let is_service = tx.gas_price.is_zero() && self.is_certified_service_transaction(chain, &tx);
+					Ok((tx, is_service))
+				},
+				false => Err(Error::Transaction(TransactionError::NotAllowed)),
+			})
+			.collect()
+	}
+
+	fn add_transactions_to_queue(&self, chain: &MiningBlockChainClient, transactions: Vec<Result<(SignedTransaction, bool), Error>>, origin: TransactionOrigin, transaction_queue: &mut TransactionQueue) ->
 		Vec<Result<TransactionImportResult, Error>> {
 
 		let fetch_account = |a: &Address| AccountDetails {
@@ -486,27 +625,73 @@ impl Miner {
 			balance: chain.latest_balance(a),
 		};
 
+		// while the circuit breaker is tripped remote transactions are refused
+		// and local ones have to pay more.
+		let throttled_gas_price = {
+			let breaker = self.circuit_breaker.lock();
+			match breaker.is_tripped(Instant::now()) {
+				true => Some(*transaction_queue.minimal_gas_price() * U256::from(breaker.gas_price_multiplier())),
+				false => None,
+			}
+		};
+
 		transactions.into_iter()
-			.map(|tx| transaction_queue.add(tx, &fetch_account, origin))
+			.map(|tx| tx.and_then(|(tx, is_service)| match (origin, throttled_gas_price) {
+				(TransactionOrigin::External, Some(_)) => Err(Error::Transaction(TransactionError::QueueThrottled)),
+				(TransactionOrigin::Local, Some(minimal)) if tx.gas_price < minimal =>
+					Err(Error::Transaction(TransactionError::InsufficientGasPrice { minimal: minimal, got: tx.gas_price })),
+				_ if is_service =>
+					transaction_queue.add_service_transaction(tx, &fetch_account, origin),
+				_ => transaction_queue.add(tx, &fetch_account, origin),
+			}))
 			.collect()
 	}
 
+	/// Is `tx` a service transaction from a sender certified by the service transaction checker?
+	fn is_certified_service_transaction(&self, chain: &MiningBlockChainClient, tx: &SignedTransaction) -> bool {
+		self.service_transaction_checker.as_ref().map_or(false, |checker| checker.is_certified(chain, tx))
+	}
+
 	/// Are we allowed to do a non-mandatory reseal?
 	fn tx_reseal_allowed(&self) -> bool { Instant::now() > *self.next_allowed_reseal.lock() }
 
 	fn from_pending_block<H, F, G>(&self, latest_block_number: BlockNumber, from_chain: F, map_block: G) -> H
 		where F: Fn() -> H, G: Fn(&ClosedBlock) -> H {
 		let sealing_work = self.sealing_work.lock();
-		sealing_work.queue.peek_last_ref().map_or_else(
-			|| from_chain(),
-			|b| {
-				if b.block().header().number() > latest_block_number {
-					map_block(b)
-				} else {
-					from_chain()
-				}
-			}
-		)
+		let pending_view = self.pending_view.lock();
+		// sealing work takes precedence over the view, it is what gets mined.
+		let pending = sealing_work.queue.peek_last_ref().into_iter()
+			.chain(pending_view.block.as_ref())
+			.find(|b| b.block().header().number() > latest_block_number);
+		match pending {
+			Some(b) => map_block(b),
+			None => from_chain(),
+		}
+	}
+
+	/// Drop the pending view after the transaction queue changed, it is rebuilt on the next query.
+	fn invalidate_pending_view(&self) {
+		*self.pending_view.lock() = PendingView::empty();
+	}
+
+	/// Note that external transactions were imported. The pending view is still served,
+	/// and rebuilt on a query at most once per `reseal_min_period`.
+	fn note_pending_view_outdated(&self) {
+		self.pending_view.lock().outdated = true;
+	}
+
+	/// Does the pending view have to be built for a query on top of `best_block`?
+	fn pending_view_needs_rebuild(&self, best_block: BlockNumber) -> bool {
+		let sealing_work = self.sealing_work.lock();
+		let pending_view = self.pending_view.lock();
+		if sealing_work.queue.peek_last_ref().map_or(false, |b| b.block().header().number() > best_block) {
+			return false;
+		}
+		match pending_view.block {
+			Some(ref b) if b.block().header().number() > best_block =>
+				pending_view.outdated && Instant::now() >= pending_view.next_rebuild,
+			_ => true,
+		}
 	}
 }
 
@@ -516,6 +701,7 @@ impl MinerService for Miner {
 
 	fn clear_and_reset(&self, chain: &MiningBlockChainClient) {
 		self.transaction_queue.lock().clear();
+		self.invalidate_pending_view();
 		// --------------------------------------------------------------------------
 		// | NOTE Code below requires transaction_queue and sealing_work locks.     |
 		// | Make sure to release the locks before calling that method.             |
@@ -534,44 +720,54 @@ impl MinerService for Miner {
 	}
 
 	fn call(&self, chain: &MiningBlockChainClient, t: &SignedTransaction, analytics: CallAnalytics) -> Result<Executed, CallError> {
-		let sealing_work = self.sealing_work.lock();
-		match sealing_work.queue.peek_last_ref() {
+		let best_block = chain.chain_info().best_block_number;
+		let mut sealing_work = self.sealing_work.lock();
+		let mut pending_view = self.pending_view.lock();
+		let pending = sealing_work.queue.peek_last_mut().into_iter()
+			.chain(pending_view.block.as_mut())
+			.find(|work| work.block().header().number() > best_block);
+		match pending {
 			Some(work) => {
-				let block = work.block();
-
 				// TODO: merge this code with client.rs's fn call somwhow.
-				let header = block.header();
-				let last_hashes = Arc::new(chain.last_hashes());
-				let env_info = EnvInfo {
-					number: header.number(),
-					author: *header.author(),
-					timestamp: header.timestamp(),
-					difficulty: *header.difficulty(),
-					last_hashes: last_hashes,
-					gas_used: U256::zero(),
-					gas_limit: U256::max_value(),
+				let env_info = {
+					let header = work.block().header();
+					EnvInfo {
+						number: header.number(),
+						author: *header.author(),
+						timestamp: header.timestamp(),
+						difficulty: *header.difficulty(),
+						last_hashes: Arc::new(chain.last_hashes()),
+						gas_used: U256::zero(),
+						gas_limit: U256::max_value(),
+					}
 				};
-				// that's just a copy of the state.
-				let mut state = block.state().clone();
-				let original_state = if analytics.state_diffing { Some(state.clone()) } else { None };
 
 				let sender = try!(t.sender().map_err(|e| {
 					let message = format!("Transaction malformed: {:?}", e);
 					ExecutionError::TransactionMalformed(message)
 				}));
-				let balance = state.balance(&sender);
-				let needed_balance = t.value + t.gas * t.gas_price;
-				if balance < needed_balance {
-					// give the sender a sufficient balance
-					state.add_balance(&sender, &(needed_balance - balance));
-				}
-				let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false };
-				let mut ret = try!(Executive::new(&mut state, &env_info, &*self.engine, chain.vm_factory()).transact(t, options));
+				let engine = &*self.engine;
+				// execute on top of the pending state and revert it afterwards instead of copying it.
+				let result = work.speculate(|state| -> Result<Executed, CallError> {
+					let original_state = if analytics.state_diffing { Some(state.clone()) } else { None };
+					let balance = state.balance(&sender);
+					let needed_balance = t.value + t.gas * t.gas_price;
+					if balance < needed_balance {
+						// give the sender a sufficient balance
+						state.add_balance(&sender, &(needed_balance - balance));
+					}
+					let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false };
+					let mut ret = try!(Executive::new(state, &env_info, engine, chain.vm_factory()).transact(t, options));
 
-				// TODO gav move this into Executive.
-				ret.state_diff = original_state.map(|original| state.diff_from(original));
+					// TODO gav move this into Executive.
+					ret.state_diff = original_state.map(|original| state.diff_from(original));
 
-				Ok(ret)
+					Ok(ret)
+				});
+				result.unwrap_or_else(|e| {
+					warn!(target: "miner", "Unable to execute call on the pending block: {}", e);
+					Err(ExecutionError::Internal.into())
+				})
 			},
 			None => {
 				chain.call(t, BlockID::Latest, analytics)
@@ -623,6 +819,10 @@ impl MinerService for Miner {
 		*self.extra_data.write() = extra_data;
 	}
 
+	fn maximum_extra_data_size(&self) -> usize {
+		self.engine.maximum_extra_data_size()
+	}
+
 	/// Set the gas limit we wish to target when sealing a new block.
 	fn set_gas_floor_target(&self, target: U256) {
 		self.gas_range_target.write().0 = target;
@@ -688,11 +888,15 @@ impl MinerService for Miner {
 	) -> Vec<Result<TransactionImportResult, Error>> {
 
 		let results = {
+			let transactions = self.check_transactions(chain, transactions);
 			let mut transaction_queue = self.transaction_queue.lock();
 			self.add_transactions_to_queue(
 				chain, transactions, TransactionOrigin::External, &mut transaction_queue
 			)
 		};
+		if results.iter().any(|r| r.is_ok()) {
+			self.note_pending_view_outdated();
+		}
 
 		if !results.is_empty() && self.options.reseal_on_external_tx &&	self.tx_reseal_allowed() {
 			// --------------------------------------------------------------------------
@@ -716,9 +920,10 @@ impl MinerService for Miner {
 
 		let imported = {
 			// Be sure to release the lock before we call prepare_work_sealing
+			let transactions = self.check_transactions(chain, vec![transaction]);
 			let mut transaction_queue = self.transaction_queue.lock();
 			let import = self.add_transactions_to_queue(
-				chain, vec![transaction], TransactionOrigin::Local, &mut transaction_queue
+				chain, transactions, TransactionOrigin::Local, &mut transaction_queue
 			).pop().unwrap();
 
 			match import {
@@ -734,6 +939,9 @@ impl MinerService for Miner {
 			}
 			import
 		};
+		if imported.is_ok() {
+			self.invalidate_pending_view();
+		}
 
 		// --------------------------------------------------------------------------
 		// | NOTE Code below requires transaction_queue and sealing_work locks.     |
@@ -758,6 +966,41 @@ impl MinerService for Miner {
 		queue.top_transactions()
 	}
 
+	fn future_transactions(&self) -> Vec<SignedTransaction> {
+		self.transaction_queue.lock().future_transactions()
+	}
+
+	fn remove_pending_transaction(&self, chain: &MiningBlockChainClient, hash: &H256) -> Option<SignedTransaction> {
+		let removed = {
+			let mut queue = self.transaction_queue.lock();
+			let transaction = queue.find(hash);
+			if transaction.is_some() {
+				let fetch_account = |a: &Address| AccountDetails {
+					nonce: chain.latest_nonce(a),
+					balance: chain.latest_balance(a),
+				};
+				queue.remove_invalid(hash, &fetch_account);
+			}
+			transaction
+		};
+
+		if removed.is_some() {
+			self.invalidate_pending_view();
+			// resealing reopens the pending block, so it has to be dropped to get rid of the transaction.
+			let in_pending_block = self.sealing_work.lock().queue.peek_last_ref()
+				.map_or(false, |b| b.transactions().iter().any(|t| &t.hash() == hash));
+			if in_pending_block {
+				self.sealing_work.lock().queue.reset();
+				// --------------------------------------------------------------------------
+				// | NOTE Code below requires transaction_queue and sealing_work locks.     |
+				// | Make sure to release the locks before calling that method.             |
+				// --------------------------------------------------------------------------
+				self.update_sealing(chain);
+			}
+		}
+		removed
+	}
+
 	fn pending_transactions(&self, best_block: BlockNumber) -> Vec<SignedTransaction> {
 		let queue = self.transaction_queue.lock();
 		match self.options.pending_set {
@@ -876,13 +1119,19 @@ impl MinerService for Miner {
 	fn update_sealing(&self, chain: &MiningBlockChainClient) {
 		trace!(target: "miner", "update_sealing");
 
-		if self.requires_reseal(chain.chain_info().best_block_number) {
+		if !Self::is_disabled(chain) && self.requires_reseal(chain.chain_info().best_block_number) {
 			// --------------------------------------------------------------------------
 			// | NOTE Code below requires transaction_queue and sealing_work locks.     |
 			// | Make sure to release the locks before calling that method.             |
 			// --------------------------------------------------------------------------
 			trace!(target: "miner", "update_sealing: preparing a block");
-			let (block, original_work_hash) = self.prepare_block(chain);
+			let (block, original_work_hash) = match self.prepare_block(chain) {
+				Ok(prepared) => prepared,
+				Err(e) => {
+					warn!(target: "miner", "Unable to prepare block: {}", e);
+					return;
+				},
+			};
 			if self.seals_internally {
 				trace!(target: "miner", "update_sealing: engine indicates internal sealing");
 				self.seal_and_import_block_internally(chain, block);
@@ -893,10 +1142,41 @@ impl MinerService for Miner {
 		}
 	}
 
+	fn is_admission_throttled(&self) -> bool {
+		self.circuit_breaker.lock().is_tripped(Instant::now())
+	}
+
 	fn is_sealing(&self) -> bool {
 		self.sealing_work.lock().queue.is_in_use()
 	}
 
+	fn prepare_pending_block(&self, chain: &MiningBlockChainClient) {
+		trace!(target: "miner", "prepare_pending_block: entering");
+		if Self::is_disabled(chain) {
+			return;
+		}
+		let best_block = chain.chain_info().best_block_number;
+		let _rebuild = self.pending_view_rebuild.lock();
+		if !self.pending_view_needs_rebuild(best_block) {
+			trace!(target: "miner", "prepare_pending_block: pending block is up to date");
+			return;
+		}
+		// the view is not handed out as work, so sealing stays as it is.
+		let transactions = self.transaction_queue.lock().top_transactions();
+		let block = match self.push_transactions(chain, self.open_block(chain), transactions) {
+			Ok(block) => block,
+			Err(e) => {
+				warn!(target: "miner", "Unable to prepare pending block: {}", e);
+				return;
+			},
+		};
+		*self.pending_view.lock() = PendingView {
+			block: Some(block),
+			outdated: false,
+			next_rebuild: Instant::now() + self.options.reseal_min_period,
+		};
+	}
+
 	fn map_sealing_work<F, T>(&self, chain: &MiningBlockChainClient, f: F) -> Option<T> where F: FnOnce(&ClosedBlock) -> T {
 		trace!(target: "miner", "map_sealing_work: entering");
 		self.prepare_work_sealing(chain);
@@ -957,8 +1237,14 @@ impl MinerService for Miner {
 		// 2. We ignore blocks that are `invalid` because it doesn't have any meaning in terms of the transactions that
 		//    are in those blocks
 
+		if !retracted.is_empty() && self.circuit_breaker.lock().note_reorg(retracted.len(), Instant::now()) {
+			warn!(target: "miner", "Repeated deep chain reorganisations. Refusing transactions from the network for a while.");
+		}
+
 		// First update gas limit in transaction queue
 		self.update_gas_limit(chain);
+		// the view might have the same number as a block on the new best chain
+		self.invalidate_pending_view();
 
 		// Then import all transactions...
 		{
@@ -966,6 +1252,7 @@ impl MinerService for Miner {
 				.par_iter()
 				.map(|h| fetch_transactions(chain, h));
 			out_of_chain.for_each(|txs| {
+				let txs = self.check_transactions(chain, txs);
 				let mut transaction_queue = self.transaction_queue.lock();
 				let _ = self.add_transactions_to_queue(
 					chain, txs, TransactionOrigin::RetractedBlock, &mut transaction_queue
@@ -1013,6 +1300,7 @@ mod tests {
 	use ethkey::{Generator, Random};
 	use client::{BlockChainClient, TestBlockChainClient, EachBlockWith, TransactionImportResult};
 	use header::BlockNumber;
+	use error::{Error, TransactionError};
 	use types::transaction::{Transaction, SignedTransaction, Action};
 	use block::*;
 	use spec::Spec;
@@ -1029,6 +1317,20 @@ mod tests {
 		assert!(sealing_work.is_some(), "Expected closed block");
 	}
 
+	#[test]
+	fn should_render_extra_data_with_block_timestamp() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = Miner::with_spec(&Spec::new_test());
+		miner.set_extra_data(b"pool {timestamp}".to_vec());
+
+		// when
+		let header = miner.map_sealing_work(&client, |b| b.block().fields().header.clone()).unwrap();
+
+		// then
+		assert_eq!(header.extra_data(), &format!("pool {}", header.timestamp()).into_bytes());
+	}
+
 	#[test]
 	fn should_still_work_after_a_couple_of_blocks() {
 		// given
@@ -1060,9 +1362,14 @@ mod tests {
 				reseal_min_period: Duration::from_secs(5),
 				tx_gas_limit: !U256::zero(),
 				tx_queue_size: 1024,
+				tx_queue_per_sender: usize::max_value(),
+				tx_queue_memory_limit: None,
+				tx_queue_eviction: EvictionStrategy::GasPrice,
 				pending_set: PendingSet::AlwaysSealing,
 				work_queue_size: 5,
 				enable_resubmission: true,
+				reorg_circuit_breaker: Default::default(),
+				refuse_service_transactions: false,
 			},
 			GasPricer::new_fixed(0u64.into()),
 			&Spec::new_test(),
@@ -1102,6 +1409,35 @@ mod tests {
 		assert!(!miner.prepare_work_sealing(&client));
 	}
 
+	#[test]
+	fn should_call_on_pending_block_without_changing_it() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+		miner.import_own_transaction(&client, transaction()).unwrap();
+		let pending_hash = |miner: &Miner| miner.sealing_work.lock().queue.peek_last_ref().map(|b| b.hash());
+		let hash = pending_hash(&miner);
+		assert!(hash.is_some());
+		let keypair = Random.generate().unwrap();
+		let call = Transaction {
+			action: Action::Call(Address::default()),
+			value: U256::from(5),
+			data: vec![],
+			gas: U256::from(100_000),
+			gas_price: U256::zero(),
+			nonce: U256::zero(),
+		}.sign(keypair.secret());
+
+		// when
+		let res = miner.call(&client, &call, Default::default());
+
+		// then
+		assert!(res.is_ok());
+		assert_eq!(pending_hash(&miner), hash);
+		assert_eq!(miner.balance(&client, &keypair.address()), U256::zero());
+		assert_eq!(miner.balance(&client, &Address::default()), U256::zero());
+	}
+
 	#[test]
 	fn should_not_use_pending_block_if_best_block_is_higher() {
 		// given
@@ -1120,6 +1456,32 @@ mod tests {
 		assert_eq!(miner.pending_receipts(best_block).len(), 0);
 	}
 
+	#[test]
+	fn should_import_zero_gas_price_transactions_from_certified_senders() {
+		// given
+		let client = TestBlockChainClient::default();
+		let certifier: Address = 0x05.into();
+		client.registry.write().insert("service_transaction_checker".into(), certifier);
+		let result = |certified: bool| format!("{:0>64}", certified as u8).from_hex().unwrap();
+		let miner = miner();
+		miner.set_minimal_gas_price(U256::from(20_000_000_000u64));
+
+		// when
+		client.contract_results.write().insert(certifier, result(false));
+		let refused = miner.import_external_transactions(&client, vec![transaction()]).pop().unwrap();
+		client.add_blocks(1, EachBlockWith::Nothing);
+		client.contract_results.write().insert(certifier, result(true));
+		let imported = miner.import_external_transactions(&client, vec![transaction()]).pop().unwrap();
+
+		// then
+		match refused {
+			Err(Error::Transaction(TransactionError::InsufficientGasPrice { .. })) => {},
+			e => panic!("Expected insufficient gas price error, got {:?}", e),
+		}
+		assert_eq!(imported.unwrap(), TransactionImportResult::Current);
+		assert_eq!(miner.all_transactions().len(), 1);
+	}
+
 	#[test]
 	fn should_import_external_transaction() {
 		// given
@@ -1140,6 +1502,76 @@ mod tests {
 		assert!(miner.prepare_work_sealing(&client));
 	}
 
+	#[test]
+	fn should_import_restored_transactions_as_local() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+
+		// when
+		let res = miner.import_restored_transactions(&client, vec![transaction()]).pop().unwrap();
+
+		// then
+		assert_eq!(res.unwrap(), TransactionImportResult::Current);
+		assert!(miner.transaction_queue.lock().has_local_pending_transactions());
+	}
+
+	#[test]
+	fn should_remove_transaction_from_queue_and_pending_block() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+		let transaction = transaction();
+		let hash = transaction.hash();
+		let best_block = 0;
+		miner.import_own_transaction(&client, transaction).unwrap();
+		assert_eq!(miner.pending_transactions(best_block).len(), 1);
+
+		// when
+		let removed = miner.remove_pending_transaction(&client, &hash);
+
+		// then
+		assert_eq!(removed.map(|t| t.hash()), Some(hash));
+		assert_eq!(miner.all_transactions().len(), 0);
+		assert_eq!(miner.pending_transactions(best_block).len(), 0);
+		assert!(miner.remove_pending_transaction(&client, &hash).is_none());
+	}
+
+	#[test]
+	fn should_prepare_pending_block_on_request() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+		let (first, second) = (transaction(), transaction());
+		let (first_hash, second_hash) = (first.hash(), second.hash());
+		let best_block = 0;
+		miner.import_external_transactions(&client, vec![first]).pop().unwrap().unwrap();
+		assert!(miner.pending_block(best_block).is_none());
+		assert!(miner.pending_receipt(best_block, &first_hash).is_none());
+
+		// when
+		miner.prepare_pending_block(&client);
+
+		// then
+		assert_eq!(miner.pending_block(best_block).unwrap().transactions.len(), 1);
+		assert!(miner.pending_receipt(best_block, &first_hash).is_some());
+		assert!(miner.pending_state(best_block).is_some());
+		assert!(miner.pending_block(best_block + 1).is_none());
+		// the view is not work to be sealed
+		assert!(!miner.sealing_work.lock().enabled);
+		assert!(miner.sealing_work.lock().queue.peek_last_ref().is_none());
+		assert!(!miner.is_sealing());
+
+		// and is rebuilt after the queue changed, but not within the reseal period
+		miner.import_external_transactions(&client, vec![second]).pop().unwrap().unwrap();
+		miner.prepare_pending_block(&client);
+		assert_eq!(miner.pending_block(best_block).unwrap().transactions.len(), 1);
+		miner.pending_view.lock().next_rebuild = Instant::now();
+		miner.prepare_pending_block(&client);
+		assert_eq!(miner.pending_block(best_block).unwrap().transactions.len(), 2);
+		assert!(miner.pending_receipt(best_block, &second_hash).is_some());
+	}
+
 	#[test]
 	fn should_not_seal_unless_enabled() {
 		let miner = miner();
@@ -1164,14 +1596,29 @@ mod tests {
 
 		miner.update_sealing(client);
 		client.flush_queue();
-		assert!(miner.pending_block().is_none());
+		assert!(miner.pending_block(client.chain_info().best_block_number).is_none());
 		assert_eq!(client.chain_info().best_block_number, 3 as BlockNumber);
 
 		assert_eq!(miner.import_own_transaction(client, transaction()).unwrap(), TransactionImportResult::Current);
 
 		miner.update_sealing(client);
 		client.flush_queue();
-		assert!(miner.pending_block().is_none());
+		assert!(miner.pending_block(client.chain_info().best_block_number).is_none());
 		assert_eq!(client.chain_info().best_block_number, 4 as BlockNumber);
 	}
+
+	#[test]
+	fn internal_seals_empty_blocks_on_request() {
+		let miner = Miner::with_spec(&Spec::new_test_instant());
+
+		let c = generate_dummy_client(2);
+		let client = c.reference().as_ref();
+
+		assert!(miner.seal_empty_block(client));
+		client.flush_queue();
+		assert_eq!(client.chain_info().best_block_number, 3 as BlockNumber);
+
+		// engines sealing with work can't seal on request.
+		assert!(!Miner::with_spec(&Spec::new_test()).seal_empty_block(client));
+	}
 }
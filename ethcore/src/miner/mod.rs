@@ -43,13 +43,18 @@
 
 mod miner;
 mod external;
+mod extra_data;
 mod transaction_queue;
 mod work_notify;
 mod price_info;
+mod circuit_breaker;
+mod service_transaction_checker;
 
-pub use self::transaction_queue::{TransactionQueue, AccountDetails, TransactionOrigin};
+pub use self::transaction_queue::{TransactionQueue, AccountDetails, TransactionOrigin, EvictionStrategy};
 pub use self::miner::{Miner, MinerOptions, PendingSet, GasPricer, GasPriceCalibratorOptions};
 pub use self::external::{ExternalMiner, ExternalMinerService};
+pub use self::circuit_breaker::CircuitBreakerOptions;
+pub use self::extra_data::{render_extra_data, check_extra_data, decode_extra_data};
 pub use client::TransactionImportResult;
 
 use std::collections::BTreeMap;
@@ -79,6 +84,9 @@ pub trait MinerService : Send + Sync {
 	/// Set the extra_data that we will seal blocks with.
 	fn set_extra_data(&self, extra_data: Bytes);
 
+	/// Get the maximum size of the extra_data of sealed blocks.
+	fn maximum_extra_data_size(&self) -> usize;
+
 	/// Get current minimal gas price for transactions accepted to queue.
 	fn minimal_gas_price(&self) -> U256;
 
@@ -131,6 +139,12 @@ pub trait MinerService : Send + Sync {
 	/// Will check the seal, but not actually insert the block into the chain.
 	fn submit_seal(&self, chain: &MiningBlockChainClient, pow_hash: H256, seal: Vec<Bytes>) -> Result<(), Error>;
 
+	/// Make sure there is a pending block on top of the best block to answer `pending` queries.
+	/// Unlike a work request it neither enables sealing nor notifies about new work.
+	/// The block is reused until the best block or the transaction queue changes. After imports
+	/// of external transactions it is rebuilt at most once per reseal period.
+	fn prepare_pending_block(&self, chain: &MiningBlockChainClient);
+
 	/// Get the sealing work package and if `Some`, apply some transform.
 	fn map_sealing_work<F, T>(&self, chain: &MiningBlockChainClient, f: F) -> Option<T>
 		where F: FnOnce(&ClosedBlock) -> T, Self: Sized;
@@ -144,6 +158,13 @@ pub trait MinerService : Send + Sync {
 	/// Get a list of all pending transactions.
 	fn pending_transactions(&self, best_block: BlockNumber) -> Vec<SignedTransaction>;
 
+	/// Get a list of transactions which can't be included yet because of a nonce gap.
+	fn future_transactions(&self) -> Vec<SignedTransaction>;
+
+	/// Remove a transaction from the queue and from the pending block.
+	/// Returns the removed transaction or `None` if it was not in the queue.
+	fn remove_pending_transaction(&self, chain: &MiningBlockChainClient, hash: &H256) -> Option<SignedTransaction>;
+
 	/// Get a list of all pending receipts.
 	fn pending_receipts(&self, best_block: BlockNumber) -> BTreeMap<H256, Receipt>;
 
@@ -156,6 +177,9 @@ pub trait MinerService : Send + Sync {
 	/// Is it currently sealing?
 	fn is_sealing(&self) -> bool;
 
+	/// Is transaction admission tightened because of repeated deep chain reorganisations?
+	fn is_admission_throttled(&self) -> bool;
+
 	/// Suggested gas price.
 	fn sensible_gas_price(&self) -> U256 { 20000000000u64.into() }
 
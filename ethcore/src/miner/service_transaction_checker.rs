@@ -0,0 +1,135 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks whether senders of zero gas price "service" transactions are certified.
+//!
+//! The certifier contract is looked up in the registrar under `service_transaction_checker`.
+
+use ethabi::Token;
+use lru_cache::LruCache;
+use util::{Address, Bytes, H256, Mutex};
+use client::{BlockChainClient, BlockID};
+use transaction::SignedTransaction;
+use contract_function::{ContractFunction, as_string};
+
+const SERVICE_TRANSACTION_CHECKER: &'static str = "service_transaction_checker";
+const CERTIFIER_ABI: &'static [u8] = br#"[{"constant":true,"inputs":[{"name":"_who","type":"address"}],"name":"certified","outputs":[{"name":"","type":"bool"}],"type":"function"}]"#;
+
+/// Number of (block, sender) pairs whose certification is cached.
+const MAX_CACHE_SIZE: usize = 4096;
+
+/// Client-side interface of the service transaction certifier contract.
+///
+/// Certification is cached per best block and sender, so it's checked once for all transactions
+/// of a sender until the next block is imported.
+pub struct ServiceTransactionChecker {
+	certified: ContractFunction,
+	cache: Mutex<LruCache<(H256, Address), bool>>,
+}
+
+impl ServiceTransactionChecker {
+	/// Create a new checker.
+	pub fn new() -> Self {
+		ServiceTransactionChecker {
+			certified: ContractFunction::new(CERTIFIER_ABI, "certified"),
+			cache: Mutex::new(LruCache::new(MAX_CACHE_SIZE)),
+		}
+	}
+
+	/// Whether the sender of the service transaction `t` is certified at the best block.
+	/// Transactions are refused if there is no certifier or it can't be called.
+	pub fn is_certified<C: BlockChainClient + ?Sized>(&self, client: &C, t: &SignedTransaction) -> bool {
+		match self.certified(client, t) {
+			Ok(certified) => certified,
+			Err(e) => {
+				debug!(target: "miner", "Error checking service transaction {}: {}", t.hash(), e);
+				false
+			}
+		}
+	}
+
+	fn certified<C: BlockChainClient + ?Sized>(&self, client: &C, t: &SignedTransaction) -> Result<bool, String> {
+		let sender = try!(t.sender().map_err(as_string));
+		let block = client.chain_info().best_block_hash;
+		let key = (block.clone(), sender.clone());
+		if let Some(certified) = self.cache.lock().get_mut(&key) {
+			return Ok(*certified);
+		}
+
+		let contract = try!(client.registry_address(SERVICE_TRANSACTION_CHECKER.into())
+			.ok_or_else(|| "No service transaction certifier registered".to_owned()));
+		let call = |address: Address, data: Bytes| client.call_contract(BlockID::Hash(block.clone()), address, data);
+		let certified = match try!(self.certified.call(contract, vec![Token::Address(sender.0)], call)) {
+			Token::Bool(certified) => certified,
+			e => return Err(format!("Invalid certifier result: {:?}", e)),
+		};
+		self.cache.lock().insert(key, certified);
+		Ok(certified)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::hex::FromHex;
+	use util::{Address, U256};
+	use client::{TestBlockChainClient, EachBlockWith};
+	use transaction::{Transaction, Action};
+	use super::{ServiceTransactionChecker, SERVICE_TRANSACTION_CHECKER};
+
+	fn service_transaction(sender: Address) -> ::transaction::SignedTransaction {
+		Transaction {
+			nonce: 0.into(),
+			action: Action::Call(0x20.into()),
+			gas: 21000.into(),
+			gas_price: U256::zero(),
+			value: U256::zero(),
+			data: vec![],
+		}.fake_sign(sender)
+	}
+
+	fn certifier_client(certified: bool) -> TestBlockChainClient {
+		let client = TestBlockChainClient::default();
+		let certifier: Address = 0x05.into();
+		client.registry.write().insert(SERVICE_TRANSACTION_CHECKER.into(), certifier);
+		client.contract_results.write().insert(certifier, format!("{:0>64}", certified as u8).from_hex().unwrap());
+		client
+	}
+
+	#[test]
+	fn should_check_certifier() {
+		let checker = ServiceTransactionChecker::new();
+		let t = service_transaction(0x10.into());
+
+		assert!(checker.is_certified(&certifier_client(true), &t));
+		assert!(!ServiceTransactionChecker::new().is_certified(&certifier_client(false), &t));
+		assert!(!ServiceTransactionChecker::new().is_certified(&TestBlockChainClient::default(), &t));
+	}
+
+	#[test]
+	fn should_cache_per_block_and_sender() {
+		let checker = ServiceTransactionChecker::new();
+		let client = certifier_client(true);
+		let t = service_transaction(0x10.into());
+
+		assert!(checker.is_certified(&client, &t));
+		client.contract_results.write().clear();
+		assert!(checker.is_certified(&client, &t));
+		assert!(!checker.is_certified(&client, &service_transaction(0x11.into())));
+
+		client.add_blocks(1, EachBlockWith::Nothing);
+		assert!(!checker.is_certified(&client, &t));
+	}
+}
@@ -83,14 +83,39 @@
 
 use std::ops::Deref;
 use std::cmp::Ordering;
-use std::cmp;
+use std::{cmp, mem};
 use std::collections::{HashSet, HashMap, BTreeSet, BTreeMap};
+use std::sync::Arc;
+use heapsize::HeapSizeOf;
 use util::{Address, H256, Uint, U256};
 use util::table::Table;
+use util::metrics::{self, Counter};
 use transaction::*;
 use error::{Error, TransactionError};
 use client::TransactionImportResult;
 
+lazy_static! {
+	static ref EVICTED: Arc<Counter> = metrics::registry().counter("parity_transaction_queue_evicted_total", "Transactions dropped because the transaction queue was full.");
+	static ref SENDER_LIMIT_REJECTED: Arc<Counter> = metrics::registry().counter("parity_transaction_queue_sender_limit_rejected_total", "Transactions rejected because their sender reached the per sender limit.");
+}
+
+/// Which transactions are dropped first when the queue is full.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EvictionStrategy {
+	/// Drop transactions with the lowest priority first, i.e. the lowest gas price
+	/// among transactions at the same distance from their sender's state nonce.
+	GasPrice,
+	/// Drop the transactions which have been waiting in the queue for the longest time first.
+	/// The last transaction of the sender is dropped to avoid a nonce gap.
+	Oldest,
+}
+
+impl Default for EvictionStrategy {
+	fn default() -> Self {
+		EvictionStrategy::GasPrice
+	}
+}
+
 /// Transaction origin
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TransactionOrigin {
@@ -139,6 +164,10 @@ struct TransactionOrder {
 	origin: TransactionOrigin,
 	/// Penalties
 	penalties: usize,
+	/// Order in which transactions were imported, used to find the oldest transaction.
+	insertion_id: u64,
+	/// Memory used by the transaction.
+	mem_usage: usize,
 }
 
 
@@ -146,10 +175,12 @@ impl TransactionOrder {
 	fn for_transaction(tx: &VerifiedTransaction, base_nonce: U256) -> Self {
 		TransactionOrder {
 			nonce_height: tx.nonce() - base_nonce,
-			gas_price: tx.transaction.gas_price,
+			gas_price: tx.priority_gas_price,
 			hash: tx.hash(),
 			origin: tx.origin,
 			penalties: 0,
+			insertion_id: tx.insertion_id,
+			mem_usage: tx.mem_usage(),
 		}
 	}
 
@@ -212,17 +243,27 @@ struct VerifiedTransaction {
 	transaction: SignedTransaction,
 	/// transaction origin
 	origin: TransactionOrigin,
+	/// Gas price used to prioritize the transaction. Differs from the actual gas price for service transactions.
+	priority_gas_price: U256,
+	/// Order in which transactions were imported.
+	insertion_id: u64,
 }
 
 impl VerifiedTransaction {
 	fn new(transaction: SignedTransaction, origin: TransactionOrigin) -> Result<Self, Error> {
 		try!(transaction.sender());
 		Ok(VerifiedTransaction {
+			priority_gas_price: transaction.gas_price,
 			transaction: transaction,
 			origin: origin,
+			insertion_id: 0,
 		})
 	}
 
+	fn mem_usage(&self) -> usize {
+		mem::size_of::<Self>() + self.transaction.heap_size_of_children()
+	}
+
 	fn hash(&self) -> H256 {
 		self.transaction.hash()
 	}
@@ -286,7 +327,11 @@ struct TransactionSet {
 	by_priority: BTreeSet<TransactionOrder>,
 	by_address: Table<Address, U256, TransactionOrder>,
 	by_gas_price: GasPriceQueue,
+	/// Sender and nonce of transactions ordered by insertion id, i.e. the oldest first.
+	by_insertion: BTreeMap<u64, (Address, U256)>,
 	limit: usize,
+	mem_usage: usize,
+	strategy: EvictionStrategy,
 }
 
 impl TransactionSet {
@@ -299,51 +344,79 @@ impl TransactionSet {
 		}
 		let order_hash = order.hash.clone();
 		let order_gas_price = order.gas_price.clone();
+		let order_insertion_id = order.insertion_id;
+		self.mem_usage += order.mem_usage;
 		let by_address_replaced = self.by_address.insert(sender, nonce, order);
 		// If transaction was replaced remove it from priority queue
 		if let Some(ref old_order) = by_address_replaced {
 			assert!(self.by_priority.remove(old_order), "hash is in `by_address`; all transactions in `by_address` must be in `by_priority`; qed");
 			assert!(self.by_gas_price.remove(&old_order.gas_price, &old_order.hash),
 				"hash is in `by_address`; all transactions' gas_prices in `by_address` must be in `by_gas_limit`; qed");
+			self.by_insertion.remove(&old_order.insertion_id);
+			self.mem_usage -= old_order.mem_usage;
 		}
+		self.by_insertion.insert(order_insertion_id, (sender, nonce));
 		self.by_gas_price.insert(order_gas_price, order_hash);
 		assert_eq!(self.by_priority.len(), self.by_address.len());
 		assert_eq!(self.by_gas_price.values().map(|v| v.len()).fold(0, |a, b| a + b), self.by_address.len());
 		by_address_replaced
 	}
 
-	/// Remove low priority transactions if there is more than specified by given `limit`.
+	/// Is the number of transactions above the limit or do they use more than `memory_limit` bytes?
+	fn is_over_limit(&self, memory_limit: usize) -> bool {
+		self.by_priority.len() > self.limit || self.mem_usage > memory_limit
+	}
+
+	/// Remove transactions, as chosen by the eviction strategy, while there are more than specified
+	/// by given `limit` or they use more than `memory_limit` bytes.
 	///
 	/// It drops transactions from this set but also removes associated `VerifiedTransaction`.
 	/// Returns addresses and lowest nonces of transactions removed because of limit.
-	fn enforce_limit(&mut self, by_hash: &mut HashMap<H256, VerifiedTransaction>) -> Option<HashMap<Address, U256>> {
-		let len = self.by_priority.len();
-		if len <= self.limit {
+	fn enforce_limit(&mut self, by_hash: &mut HashMap<H256, VerifiedTransaction>, memory_limit: usize) -> Option<HashMap<Address, U256>> {
+		if !self.is_over_limit(memory_limit) {
 			return None;
 		}
 
-		let to_drop : Vec<(Address, U256)> = {
-			self.by_priority
-				.iter()
-				.skip(self.limit)
+		let mut removed = HashMap::new();
+		while self.is_over_limit(memory_limit) {
+			let (sender, nonce) = match self.eviction_candidate(by_hash) {
+				Some(candidate) => candidate,
+				None => break,
+			};
+			let order = self.drop(&sender, &nonce)
+				.expect("Transaction has just been found in `by_priority`; so it is in `by_address` also.");
+
+			by_hash.remove(&order.hash)
+				.expect("hash is in `by_priorty`; all hashes in `by_priority` must be in `by_hash`; qed");
+
+			trace!(target: "txqueue", "Evicting transaction from a full queue: {:?}", order.hash);
+			EVICTED.inc();
+			let min = removed.get(&sender).map_or(nonce, |val| cmp::min(*val, nonce));
+			removed.insert(sender, min);
+		}
+		Some(removed)
+	}
+
+	/// Sender and nonce of the transaction to drop next.
+	///
+	/// Only the transaction with the highest nonce of a sender is dropped,
+	/// so evicting a transaction never leaves a nonce gap behind.
+	fn eviction_candidate(&self, by_hash: &HashMap<H256, VerifiedTransaction>) -> Option<(Address, U256)> {
+		match self.strategy {
+			// the lowest priority transaction of a sender is always the one with the highest nonce.
+			EvictionStrategy::GasPrice => self.by_priority.iter().next_back()
 				.map(|order| by_hash.get(&order.hash)
 					.expect("All transactions in `self.by_priority` and `self.by_address` are kept in sync with `by_hash`."))
-				.map(|tx| (tx.sender(), tx.nonce()))
-				.collect()
-		};
-
-		Some(to_drop.into_iter()
-			.fold(HashMap::new(), |mut removed, (sender, nonce)| {
-				let order = self.drop(&sender, &nonce)
-					.expect("Transaction has just been found in `by_priority`; so it is in `by_address` also.");
-
-				by_hash.remove(&order.hash)
-					.expect("hash is in `by_priorty`; all hashes in `by_priority` must be in `by_hash`; qed");
-
-				let min = removed.get(&sender).map_or(nonce, |val| cmp::min(*val, nonce));
-				removed.insert(sender, min);
-				removed
-			}))
+				.map(|tx| (tx.sender(), tx.nonce())),
+			// the sender of the oldest transaction drops its last one.
+			EvictionStrategy::Oldest => self.by_insertion.values().next()
+				.map(|&(sender, _)| {
+					let nonce = self.by_address.row(&sender)
+						.and_then(|row| row.keys().max().cloned())
+						.expect("All transactions in `self.by_insertion` are also in `self.by_address`.");
+					(sender, nonce)
+				}),
+		}
 	}
 
 	/// Drop transaction from this set (remove from `by_priority` and `by_address`)
@@ -353,6 +426,8 @@ impl TransactionSet {
 				"hash is in `by_address`; all transactions' gas_prices in `by_address` must be in `by_gas_limit`; qed");
 			assert!(self.by_priority.remove(&tx_order),
 				"hash is in `by_address`; all transactions' gas_prices in `by_address` must be in `by_priority`; qed");
+			self.by_insertion.remove(&tx_order.insertion_id);
+			self.mem_usage -= tx_order.mem_usage;
 			assert_eq!(self.by_priority.len(), self.by_address.len());
 			assert_eq!(self.by_gas_price.values().map(|v| v.len()).fold(0, |a, b| a + b), self.by_address.len());
 			return Some(tx_order);
@@ -367,6 +442,8 @@ impl TransactionSet {
 		self.by_priority.clear();
 		self.by_address.clear();
 		self.by_gas_price.backing.clear();
+		self.by_insertion.clear();
+		self.mem_usage = 0;
 	}
 
 	/// Sets new limit for number of transactions in this `TransactionSet`.
@@ -376,10 +453,14 @@ impl TransactionSet {
 	}
 
 	/// Get the minimum gas price that we can accept into this queue that wouldn't cause the transaction to
-	/// immediately be dropped. 0 if the queue isn't at capacity; 1 plus the lowest if it is.
-	fn gas_price_entry_limit(&self) -> U256 {
+	/// immediately be dropped. 0 if the queue isn't at capacity or evicts the oldest transactions;
+	/// 1 plus the lowest if it is. The queue is also at capacity when it uses `memory_limit` bytes.
+	fn gas_price_entry_limit(&self, memory_limit: usize) -> U256 {
+		if self.strategy == EvictionStrategy::Oldest {
+			return U256::default();
+		}
 		match self.by_gas_price.keys().next() {
-			Some(k) if self.by_priority.len() >= self.limit => *k + 1.into(),
+			Some(k) if self.by_priority.len() >= self.limit || self.mem_usage >= memory_limit => *k + 1.into(),
 			_ => U256::default(),
 		}
 	}
@@ -421,6 +502,12 @@ pub struct TransactionQueue {
 	by_hash: HashMap<H256, VerifiedTransaction>,
 	/// Last nonce of transaction in current (to quickly check next expected transaction)
 	last_nonces: HashMap<Address, U256>,
+	/// Maximal amount of memory (in bytes) used by transactions in `current` and `future` together
+	memory_limit: usize,
+	/// Maximal number of transactions from a single sender (local and retracted transactions are exempt)
+	per_sender_limit: usize,
+	/// Insertion id given to the next imported transaction
+	next_insertion_id: u64,
 }
 
 impl Default for TransactionQueue {
@@ -441,14 +528,20 @@ impl TransactionQueue {
 			by_priority: BTreeSet::new(),
 			by_address: Table::new(),
 			by_gas_price: Default::default(),
+			by_insertion: BTreeMap::new(),
 			limit: limit,
+			mem_usage: 0,
+			strategy: EvictionStrategy::default(),
 		};
 
 		let future = TransactionSet {
 			by_priority: BTreeSet::new(),
 			by_address: Table::new(),
 			by_gas_price: Default::default(),
+			by_insertion: BTreeMap::new(),
 			limit: limit,
+			mem_usage: 0,
+			strategy: EvictionStrategy::default(),
 		};
 
 		TransactionQueue {
@@ -459,6 +552,9 @@ impl TransactionQueue {
 			future: future,
 			by_hash: HashMap::new(),
 			last_nonces: HashMap::new(),
+			memory_limit: usize::max_value(),
+			per_sender_limit: usize::max_value(),
+			next_insertion_id: 0,
 		}
 	}
 
@@ -467,8 +563,7 @@ impl TransactionQueue {
 		self.current.set_limit(limit);
 		self.future.set_limit(limit);
 		// And ensure the limits
-		self.current.enforce_limit(&mut self.by_hash);
-		self.future.enforce_limit(&mut self.by_hash);
+		self.enforce_current_limit();
 	}
 
 	/// Returns current limit of transactions in the queue.
@@ -476,6 +571,28 @@ impl TransactionQueue {
 		self.current.limit
 	}
 
+	/// Set the maximal number of transactions a single sender may have in the queue.
+	/// Local transactions and transactions from retracted blocks are not limited.
+	/// Any transaction already imported to the queue is not affected.
+	pub fn set_per_sender_limit(&mut self, limit: usize) {
+		self.per_sender_limit = limit;
+	}
+
+	/// Set the maximal amount of memory (in bytes) transactions in `current` and `future` queue may use together
+	/// and the strategy used to choose transactions to drop when the queue is full.
+	pub fn set_memory_limit(&mut self, memory_limit: usize, strategy: EvictionStrategy) {
+		self.memory_limit = memory_limit;
+		self.current.strategy = strategy;
+		self.future.strategy = strategy;
+		// And ensure the limits
+		self.enforce_current_limit();
+	}
+
+	/// Returns memory used by transactions in the queue.
+	pub fn mem_usage(&self) -> usize {
+		self.current.mem_usage + self.future.mem_usage
+	}
+
 	/// Get the minimal gas price.
 	pub fn minimal_gas_price(&self) -> &U256 {
 		&self.minimal_gas_price
@@ -490,7 +607,7 @@ impl TransactionQueue {
 	/// Get one more than the lowest gas price in the queue iff the pool is
 	/// full, otherwise 0.
 	pub fn effective_minimum_gas_price(&self) -> U256 {
-		self.current.gas_price_entry_limit()
+		self.current.gas_price_entry_limit(self.memory_limit.saturating_sub(self.future.mem_usage))
 	}
 
 	/// Sets new gas limit. Transactions with gas slightly (`GAS_LIMIT_HYSTERESIS`) above the limit won't be imported.
@@ -551,6 +668,22 @@ impl TransactionQueue {
 			}));
 		}
 
+		let priority_gas_price = tx.gas_price;
+		self.add_internal(tx, priority_gas_price, fetch_account, origin)
+	}
+
+	/// Add a zero gas price service transaction from a certified sender to the queue.
+	/// Gas price thresholds don't apply to it and it is prioritized as if it paid the lowest
+	/// gas price the queue accepts, so paid transactions can't starve it.
+	pub fn add_service_transaction<T>(&mut self, tx: SignedTransaction, fetch_account: &T, origin: TransactionOrigin) -> Result<TransactionImportResult, Error>
+	where T: Fn(&Address) -> AccountDetails {
+		let priority_gas_price = cmp::max(self.minimal_gas_price, self.effective_minimum_gas_price());
+		self.add_internal(tx, priority_gas_price, fetch_account, origin)
+	}
+
+	fn add_internal<T>(&mut self, tx: SignedTransaction, priority_gas_price: U256, fetch_account: &T, origin: TransactionOrigin) -> Result<TransactionImportResult, Error>
+	where T: Fn(&Address) -> AccountDetails {
+
 		try!(tx.check_low_s());
 
 		if tx.gas > self.gas_limit || tx.gas > self.tx_gas_limit {
@@ -568,7 +701,10 @@ impl TransactionQueue {
 			}));
 		}
 
-		let vtx = try!(VerifiedTransaction::new(tx, origin));
+		let mut vtx = try!(VerifiedTransaction::new(tx, origin));
+		vtx.priority_gas_price = priority_gas_price;
+		vtx.insertion_id = self.next_insertion_id;
+		self.next_insertion_id += 1;
 		let client_account = fetch_account(&vtx.sender());
 
 		let cost = vtx.transaction.value + vtx.transaction.gas_price * vtx.transaction.gas;
@@ -723,7 +859,7 @@ impl TransactionQueue {
 				self.by_hash.remove(&order.hash).expect("All transactions in `future` are also in `by_hash`");
 			}
 		}
-		self.future.enforce_limit(&mut self.by_hash);
+		self.enforce_future_limit();
 	}
 
 	/// Returns top transactions from the queue ordered by priority.
@@ -735,8 +871,8 @@ impl TransactionQueue {
 			.collect()
 	}
 
-	#[cfg(test)]
-	fn future_transactions(&self) -> Vec<SignedTransaction> {
+	/// Returns transactions waiting for a nonce gap to be filled, ordered by priority.
+	pub fn future_transactions(&self) -> Vec<SignedTransaction> {
 		self.future.by_priority
 			.iter()
 			.map(|t| self.by_hash.get(&t.hash).expect("All transactions in `current` and `future` are always included in `by_hash`"))
@@ -789,6 +925,8 @@ impl TransactionQueue {
 				// remove also from priority and gas_price
 				self.future.by_priority.remove(&order);
 				self.future.by_gas_price.remove(&order.gas_price, &order.hash);
+				self.future.by_insertion.remove(&order.insertion_id);
+				self.future.mem_usage -= order.mem_usage;
 				// Put to current
 				let order = order.update_height(current_nonce, first_nonce);
 				if let Some(old) = self.current.insert(address, current_nonce, order.clone()) {
@@ -838,6 +976,17 @@ impl TransactionQueue {
 
 		// Update nonces of transactions in future (remove old transactions)
 		self.update_future(&address, state_nonce);
+
+		// Limit the number of transactions a single sender may have, unless it replaces one of them.
+		// Transactions from retracted blocks were in the queue already, so they are not limited.
+		if tx.origin == TransactionOrigin::External && self.sender_count(&address) >= self.per_sender_limit
+			&& self.current.by_address.get(&address, &nonce).is_none()
+			&& self.future.by_address.get(&address, &nonce).is_none() {
+			trace!(target: "txqueue", "Dropping transaction above the per sender limit: {:?} (limit: {})", hash, self.per_sender_limit);
+			SENDER_LIMIT_REJECTED.inc();
+			return Err(TransactionError::LimitReached);
+		}
+
 		// State nonce could be updated. Maybe there are some more items waiting in future?
 		self.move_matching_future_to_current(address, state_nonce, state_nonce);
 		// Check the next expected nonce (might be updated by move above)
@@ -852,7 +1001,7 @@ impl TransactionQueue {
 			// Insert transaction (or replace old one with lower gas price)
 			try!(check_too_cheap(Self::replace_transaction(tx, state_nonce, &mut self.future, &mut self.by_hash)));
 			// Enforce limit in Future
-			let removed = self.future.enforce_limit(&mut self.by_hash);
+			let removed = self.enforce_future_limit();
 			// Return an error if this transaction was not imported because of limit.
 			try!(check_if_removed(&address, &nonce, removed));
 
@@ -871,10 +1020,8 @@ impl TransactionQueue {
 		let new_max = self.last_nonces.get(&address).map_or(nonce, |n| cmp::max(nonce, *n));
 		self.last_nonces.insert(address, new_max);
 
-		// Also enforce the limit
-		let removed = self.current.enforce_limit(&mut self.by_hash);
-		// If some transaction were removed because of limit we need to update last_nonces also.
-		self.update_last_nonces(&removed);
+		// Also enforce the limit, it updates last_nonces if some transactions were removed.
+		let removed = self.enforce_current_limit();
 		// Trigger error if the transaction we are importing was removed.
 		try!(check_if_removed(&address, &nonce, removed));
 
@@ -883,6 +1030,30 @@ impl TransactionQueue {
 		Ok(TransactionImportResult::Current)
 	}
 
+	/// Enforce the limits of `future`. The memory limit is shared with `current`.
+	fn enforce_future_limit(&mut self) -> Option<HashMap<Address, U256>> {
+		let memory_limit = self.memory_limit.saturating_sub(self.current.mem_usage);
+		self.future.enforce_limit(&mut self.by_hash, memory_limit)
+	}
+
+	/// Enforce the limits of `current` and `future`.
+	/// Transactions in `future` are dropped first when both together use more memory than allowed.
+	/// Returns addresses and lowest nonces of transactions removed from `current`.
+	fn enforce_current_limit(&mut self) -> Option<HashMap<Address, U256>> {
+		self.enforce_future_limit();
+		let memory_limit = self.memory_limit.saturating_sub(self.future.mem_usage);
+		let removed = self.current.enforce_limit(&mut self.by_hash, memory_limit);
+		// If some transaction were removed because of limit we need to update last_nonces also.
+		self.update_last_nonces(&removed);
+		removed
+	}
+
+	/// Number of transactions from given sender in `current` and `future` queue.
+	fn sender_count(&self, sender: &Address) -> usize {
+		let count = |set: &TransactionSet| set.by_address.row(sender).map_or(0, |row| row.len());
+		count(&self.current) + count(&self.future)
+	}
+
 	/// Updates
 	fn update_last_nonces(&mut self, removed_min_nonces: &Option<HashMap<Address, U256>>) {
 		if let Some(ref min_nonces) = *removed_min_nonces {
@@ -1089,7 +1260,10 @@ mod test {
 			by_priority: BTreeSet::new(),
 			by_address: Table::new(),
 			by_gas_price: Default::default(),
-			limit: 1
+			by_insertion: BTreeMap::new(),
+			limit: 1,
+			mem_usage: 0,
+			strategy: EvictionStrategy::GasPrice,
 		};
 		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
 		let tx1 = VerifiedTransaction::new(tx1, TransactionOrigin::External).unwrap();
@@ -1111,7 +1285,7 @@ mod test {
 		assert_eq!(set.by_address.len(), 2);
 
 		// when
-		set.enforce_limit(&mut by_hash);
+		set.enforce_limit(&mut by_hash, usize::max_value());
 
 		// then
 		assert_eq!(by_hash.len(), 1);
@@ -1129,7 +1303,10 @@ mod test {
 			by_priority: BTreeSet::new(),
 			by_address: Table::new(),
 			by_gas_price: Default::default(),
-			limit: 1
+			by_insertion: BTreeMap::new(),
+			limit: 1,
+			mem_usage: 0,
+			strategy: EvictionStrategy::GasPrice,
 		};
 		// Create two transactions with same nonce
 		// (same hash)
@@ -1177,7 +1354,10 @@ mod test {
 			by_priority: BTreeSet::new(),
 			by_address: Table::new(),
 			by_gas_price: Default::default(),
-			limit: 2
+			by_insertion: BTreeMap::new(),
+			limit: 2,
+			mem_usage: 0,
+			strategy: EvictionStrategy::GasPrice,
 		};
 		let tx = new_tx_default();
 		let tx1 = VerifiedTransaction::new(tx.clone(), TransactionOrigin::External).unwrap();
@@ -1194,15 +1374,18 @@ mod test {
 			by_priority: BTreeSet::new(),
 			by_address: Table::new(),
 			by_gas_price: Default::default(),
-			limit: 1
+			by_insertion: BTreeMap::new(),
+			limit: 1,
+			mem_usage: 0,
+			strategy: EvictionStrategy::GasPrice,
 		};
 
-		assert_eq!(set.gas_price_entry_limit(), 0.into());
+		assert_eq!(set.gas_price_entry_limit(usize::max_value()), 0.into());
 		let tx = new_tx_default();
 		let tx1 = VerifiedTransaction::new(tx.clone(), TransactionOrigin::External).unwrap();
 		let order1 = TransactionOrder::for_transaction(&tx1, U256::zero());
 		assert!(set.insert(tx1.sender(), tx1.nonce(), order1.clone()).is_none());
-		assert_eq!(set.gas_price_entry_limit(), 2.into());
+		assert_eq!(set.gas_price_entry_limit(usize::max_value()), 2.into());
 	}
 
 	#[test]
@@ -1368,6 +1551,28 @@ mod test {
 		assert_eq!(stats.future, 0);
 	}
 
+	#[test]
+	fn should_import_service_transaction_below_min_gas_price_threshold() {
+		// given
+		let mut txq = TransactionQueue::new();
+		txq.set_minimal_gas_price(U256::from(2));
+		let service_tx = new_tx(default_nonce(), U256::zero());
+		let paid_tx = new_tx(default_nonce(), U256::from(3));
+
+		// when
+		let rejected = txq.add(service_tx.clone(), &default_account_details, TransactionOrigin::External);
+		let res = txq.add_service_transaction(service_tx.clone(), &default_account_details, TransactionOrigin::External);
+		txq.add(paid_tx.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// then
+		assert_eq!(unwrap_tx_err(rejected), TransactionError::InsufficientGasPrice {
+			minimal: U256::from(2),
+			got: U256::zero(),
+		});
+		assert_eq!(res.unwrap(), TransactionImportResult::Current);
+		assert_eq!(txq.top_transactions(), vec![paid_tx, service_tx]);
+	}
+
 	#[test]
 	fn should_reject_incorectly_signed_transaction() {
 		use rlp::{self, RlpStream, Stream};
@@ -1728,6 +1933,138 @@ mod test {
 		assert_eq!(txq.status().future, 1);
 	}
 
+	#[test]
+	fn should_limit_transactions_per_sender() {
+		// given
+		let mut txq = TransactionQueue::new();
+		txq.set_per_sender_limit(1);
+		let keypair = Random.generate().unwrap();
+		let tx1 = new_unsigned_tx(default_nonce(), default_gas_price()).sign(keypair.secret());
+		let tx2 = new_unsigned_tx(default_nonce() + 1.into(), default_gas_price()).sign(keypair.secret());
+		let tx1_replacement = new_unsigned_tx(default_nonce(), default_gas_price() + 1.into()).sign(keypair.secret());
+		txq.add(tx1.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// when
+		let res = txq.add(tx2.clone(), &default_account_details, TransactionOrigin::External);
+		txq.add(tx1_replacement.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// then
+		assert_eq!(unwrap_tx_err(res), TransactionError::LimitReached);
+		assert_eq!(txq.top_transactions(), vec![tx1_replacement]);
+	}
+
+	#[test]
+	fn should_not_limit_local_transactions_per_sender() {
+		// given
+		let mut txq = TransactionQueue::new();
+		txq.set_per_sender_limit(1);
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		txq.add(tx1.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+
+		// when
+		txq.add(tx2.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+
+		// then
+		assert_eq!(txq.status().pending, 2);
+	}
+
+	#[test]
+	fn should_not_limit_retracted_transactions_per_sender() {
+		// given
+		let mut txq = TransactionQueue::new();
+		txq.set_per_sender_limit(1);
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		txq.add(tx1.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// when
+		txq.add(tx2.clone(), &default_account_details, TransactionOrigin::RetractedBlock).unwrap();
+
+		// then
+		assert_eq!(txq.status().pending, 2);
+	}
+
+	#[test]
+	fn should_not_count_old_transactions_against_the_per_sender_limit() {
+		// given
+		let mut txq = TransactionQueue::new();
+		txq.set_per_sender_limit(1);
+		let keypair = Random.generate().unwrap();
+		let old = new_unsigned_tx(default_nonce() + 1.into(), default_gas_price()).sign(keypair.secret());
+		let tx = new_unsigned_tx(default_nonce() + 2.into(), default_gas_price()).sign(keypair.secret());
+		assert_eq!(txq.add(old, &default_account_details, TransactionOrigin::External).unwrap(), TransactionImportResult::Future);
+
+		// when
+		let account_details = |_: &Address| AccountDetails {
+			nonce: default_nonce() + 2.into(),
+			balance: !U256::zero(),
+		};
+		let res = txq.add(tx.clone(), &account_details, TransactionOrigin::External);
+
+		// then
+		assert_eq!(res.unwrap(), TransactionImportResult::Current);
+		assert_eq!(txq.top_transactions(), vec![tx]);
+		assert_eq!(txq.status().future, 0);
+	}
+
+	#[test]
+	fn should_share_the_memory_limit_between_current_and_future() {
+		// given
+		let mut txq = TransactionQueue::new();
+		let current = new_tx_default();
+		let future = new_tx(default_nonce() + 1.into(), default_gas_price());
+		txq.add(current.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+		txq.add(future, &default_account_details, TransactionOrigin::External).unwrap();
+		let mem_usage = txq.mem_usage();
+
+		// when
+		txq.set_memory_limit(mem_usage - 1, EvictionStrategy::GasPrice);
+
+		// then
+		assert_eq!(txq.top_transactions(), vec![current]);
+		assert_eq!(txq.status().future, 0);
+		assert!(txq.mem_usage() < mem_usage);
+	}
+
+	#[test]
+	fn should_drop_lowest_gas_price_transactions_when_hitting_the_memory_limit() {
+		// given
+		let mut txq = TransactionQueue::new();
+		let tx1 = new_tx(default_nonce(), 2.into());
+		let tx2 = new_tx(default_nonce(), 3.into());
+		txq.add(tx1.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+		let mem_usage = txq.mem_usage();
+		txq.set_memory_limit(mem_usage, EvictionStrategy::GasPrice);
+
+		// when
+		txq.add(tx2.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// then
+		assert_eq!(txq.top_transactions(), vec![tx2]);
+		assert_eq!(txq.mem_usage(), mem_usage);
+	}
+
+	#[test]
+	fn should_drop_oldest_transactions_when_hitting_the_limit() {
+		// given
+		let mut txq = TransactionQueue::with_limits(2, !U256::zero());
+		txq.set_memory_limit(usize::max_value(), EvictionStrategy::Oldest);
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		let tx3 = new_tx(default_nonce(), 1.into());
+		txq.add(tx1.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+		txq.add(tx2.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// when
+		txq.add(tx3.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// then
+		// tx1 is the oldest, but dropping it would leave a nonce gap, so tx2 goes instead.
+		let t = txq.top_transactions();
+		assert_eq!(t.len(), 2);
+		assert!(t.contains(&tx1));
+		assert!(t.contains(&tx3));
+		assert_eq!(txq.last_nonce(&tx1.sender().unwrap()), Some(tx1.nonce));
+	}
+
 	#[test]
 	fn should_drop_transactions_with_old_nonces() {
 		let mut txq = TransactionQueue::new();
@@ -14,50 +14,24 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-extern crate hyper;
-
-use hyper::header::ContentType;
-use hyper::method::Method;
-use hyper::client::{Request, Response, Client};
-use hyper::{Next};
-use hyper::net::HttpStream;
 use ethash::SeedHashCompute;
-use hyper::Url;
+use http_poster::JsonPoster;
 use util::*;
 use ethereum::ethash::Ethash;
 
 pub struct WorkPoster {
-	urls: Vec<Url>,
-	client: Mutex<Client<PostHandler>>,
+	poster: JsonPoster,
 	seed_compute: Mutex<SeedHashCompute>,
 }
 
 impl WorkPoster {
 	pub fn new(urls: &[String]) -> Self {
-		let urls = urls.into_iter().filter_map(|u| {
-			match Url::parse(u) {
-				Ok(url) => Some(url),
-				Err(e) => {
-					warn!("Error parsing URL {} : {}", u, e);
-					None
-				}
-			}
-		}).collect();
-		let client = WorkPoster::create_client();
 		WorkPoster {
-			client: Mutex::new(client),
-			urls: urls,
+			poster: JsonPoster::new(urls),
 			seed_compute: Mutex::new(SeedHashCompute::new()),
 		}
 	}
 
-	fn create_client() -> Client<PostHandler> {
-		Client::<PostHandler>::configure()
-			.keep_alive(true)
-			.build()
-			.expect("Error creating HTTP client")
-	}
-
 	pub fn notify(&self, pow_hash: H256, difficulty: U256, number: u64) {
 		// TODO: move this to engine
 		let target = Ethash::difficulty_to_boundary(&difficulty);
@@ -67,51 +41,6 @@ impl WorkPoster {
 			r#"{{ "result": ["0x{}","0x{}","0x{}","0x{:x}"] }}"#,
 			pow_hash.hex(), seed_hash.hex(), target.hex(), number
 		);
-		let mut client = self.client.lock();
-		for u in &self.urls {
-			if let Err(e) = client.request(u.clone(), PostHandler { body: body.clone() }) {
-				warn!("Error sending HTTP notification to {} : {}, retrying", u, e);
-				// TODO: remove this once https://github.com/hyperium/hyper/issues/848 is fixed
-				*client = WorkPoster::create_client();
-				if let Err(e) = client.request(u.clone(), PostHandler { body: body.clone() }) {
-					warn!("Error sending HTTP notification to {} : {}", u, e);
-				}
-			}
-		}
-	}
-}
-
-struct PostHandler {
-	body: String,
-}
-
-impl hyper::client::Handler<HttpStream> for PostHandler {
-	fn on_request(&mut self, request: &mut Request) -> Next {
-		request.set_method(Method::Post);
-		request.headers_mut().set(ContentType::json());
-		Next::write()
-	}
-
-	fn on_request_writable(&mut self, encoder: &mut hyper::Encoder<HttpStream>) -> Next {
-		if let Err(e) = encoder.write_all(self.body.as_bytes()) {
-			trace!("Error posting work data: {}", e);
-		}
-		encoder.close();
-		Next::read()
-
-	}
-
-	fn on_response(&mut self, _response: Response) -> Next {
-		Next::end()
-	}
-
-	fn on_response_readable(&mut self, _decoder: &mut hyper::Decoder<HttpStream>) -> Next {
-		Next::end()
-	}
-
-	fn on_error(&mut self, err: hyper::Error) -> Next {
-		trace!("Error posting work data: {}", err);
-		Next::end()
+		self.poster.post(&body);
 	}
 }
-
@@ -0,0 +1,138 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node permissioning through a contract configured in the chain spec.
+
+use ethabi::Token;
+use lru_cache::LruCache;
+use util::{Address, Bytes, H256, H512, Mutex};
+use contract_function::ContractFunction;
+
+const PERMISSION_ABI: &'static [u8] = br#"[{"constant":true,"inputs":[{"name":"sl","type":"bytes32"},{"name":"sh","type":"bytes32"},{"name":"pl","type":"bytes32"},{"name":"ph","type":"bytes32"}],"name":"connectionAllowed","outputs":[{"name":"","type":"bool"}],"type":"function"}]"#;
+
+/// Number of (block, node) pairs whose permission is cached.
+const MAX_CACHE_SIZE: usize = 1024;
+
+/// Client-side interface of the node permission contract.
+/// Node ids are passed as their low and high 32 bytes.
+///
+/// Peers are checked on connection and again periodically, so results are cached per block and node
+/// to keep the contract from being executed on every check.
+pub struct NodePermission {
+	connection_allowed: ContractFunction,
+	cache: Mutex<LruCache<(H256, H512), bool>>,
+}
+
+impl NodePermission {
+	/// Create a new permission checker.
+	pub fn new() -> Self {
+		NodePermission {
+			connection_allowed: ContractFunction::new(PERMISSION_ABI, "connectionAllowed"),
+			cache: Mutex::new(LruCache::new(MAX_CACHE_SIZE)),
+		}
+	}
+
+	/// Ask the permission contract at `contract` whether the node `own_id` may connect to `node_id`.
+	/// `call` executes a read-only call to the contract on top of the state of `block` and returns its output.
+	/// Connections are refused if the contract can't be called.
+	pub fn is_allowed<F>(&self, contract: Address, block: &H256, own_id: &H512, node_id: &H512, call: F) -> bool
+		where F: FnOnce(Address, Bytes) -> Result<Bytes, String>
+	{
+		let key = (block.clone(), node_id.clone());
+		if let Some(allowed) = self.cache.lock().get_mut(&key) {
+			return *allowed;
+		}
+
+		match self.allowed(contract, own_id, node_id, call) {
+			Ok(allowed) => {
+				self.cache.lock().insert(key, allowed);
+				allowed
+			},
+			Err(e) => {
+				warn!(target: "node_permission", "Error checking node {} permission: {}", node_id, e);
+				false
+			}
+		}
+	}
+
+	fn allowed<F>(&self, contract: Address, own_id: &H512, node_id: &H512, call: F) -> Result<bool, String>
+		where F: FnOnce(Address, Bytes) -> Result<Bytes, String>
+	{
+		let params = vec![
+			Token::FixedBytes(own_id[0..32].to_vec()),
+			Token::FixedBytes(own_id[32..64].to_vec()),
+			Token::FixedBytes(node_id[0..32].to_vec()),
+			Token::FixedBytes(node_id[32..64].to_vec()),
+		];
+		match try!(self.connection_allowed.call(contract, params, call)) {
+			Token::Bool(allowed) => Ok(allowed),
+			e => Err(format!("Invalid node permission result: {:?}", e)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::Cell;
+	use rustc_serialize::hex::{ToHex, FromHex};
+	use util::{Address, H256, H512};
+	use super::NodePermission;
+
+	fn result(allowed: bool) -> Result<Vec<u8>, String> {
+		Ok(format!("{:0>64}", allowed as u8).from_hex().unwrap())
+	}
+
+	#[test]
+	fn should_encode_permission_call() {
+		let permission = NodePermission::new();
+		let own_id = H512::from(1);
+		let node_id = H512::from(2);
+		let contract: Address = 0x05.into();
+
+		let allowed = permission.is_allowed(contract, &H256::from(1), &own_id, &node_id, |address, data| {
+			assert_eq!(address, contract);
+			assert_eq!(data[4..].to_hex(), format!("{}{}{}{}",
+				format!("{:0>64}", ""),
+				format!("{:0>64}", "1"),
+				format!("{:0>64}", ""),
+				format!("{:0>64}", "2")));
+			result(false)
+		});
+		assert!(!allowed);
+
+		assert!(!permission.is_allowed(contract, &H256::from(2), &own_id, &node_id, |_, _| Err("Call failed".into())));
+	}
+
+	#[test]
+	fn should_cache_per_block_and_node() {
+		let permission = NodePermission::new();
+		let own_id = H512::from(1);
+		let contract: Address = 0x05.into();
+		let calls = Cell::new(0);
+		let call = |_: Address, _: Vec<u8>| { calls.set(calls.get() + 1); result(true) };
+
+		assert!(permission.is_allowed(contract, &H256::from(1), &own_id, &H512::from(2), &call));
+		assert!(permission.is_allowed(contract, &H256::from(1), &own_id, &H512::from(2), &call));
+		assert_eq!(calls.get(), 1);
+		assert!(permission.is_allowed(contract, &H256::from(1), &own_id, &H512::from(3), &call));
+		assert!(permission.is_allowed(contract, &H256::from(2), &own_id, &H512::from(2), &call));
+		assert_eq!(calls.get(), 3);
+
+		// failed calls are not cached
+		assert!(!permission.is_allowed(contract, &H256::from(3), &own_id, &H512::from(2), |_, _| Err("Call failed".into())));
+		assert!(permission.is_allowed(contract, &H256::from(3), &own_id, &H512::from(2), &call));
+	}
+}
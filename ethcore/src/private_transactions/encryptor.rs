@@ -0,0 +1,161 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encryption of private transactions and contract states.
+
+use std::collections::HashMap;
+use crypto::aes::{self, KeySize};
+use crypto::symmetriccipher::SynchronousStreamCipher;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use crypto::util::fixed_time_eq;
+use util::{H128, Address, Bytes, Mutex, Hashable};
+use super::error::Error;
+
+/// Length of the initialisation vector appended to the encrypted data.
+const IV_LENGTH: usize = 16;
+/// Length of the authentication code appended after the initialisation vector.
+const MAC_LENGTH: usize = 32;
+
+/// Encrypts and decrypts data with the key shared by the validators of a private contract.
+pub trait Encryptor: Send + Sync {
+	/// Encrypt `plain` for the validators of `contract`, using the given initialisation vector.
+	fn encrypt(&self, contract: &Address, iv: &H128, plain: &[u8]) -> Result<Bytes, Error>;
+
+	/// Decrypt data encrypted with `encrypt` for the same contract.
+	/// Fails if the data was not encrypted with the key of `contract` or was altered.
+	fn decrypt(&self, contract: &Address, cypher: &[u8]) -> Result<Bytes, Error>;
+}
+
+/// Keys of a private contract, derived from its key material.
+#[derive(Clone)]
+struct Keys {
+	encryption: Bytes,
+	mac: Bytes,
+}
+
+/// AES-128-CTR encryptor authenticated with HMAC-SHA256. The key material of each contract is
+/// fetched once, usually from the Secret Store, and the derived keys are kept in memory.
+pub struct AesEncryptor<F> {
+	fetch_key: F,
+	keys: Mutex<HashMap<Address, Keys>>,
+}
+
+impl<F> AesEncryptor<F> where F: Fn(&Address) -> Result<Bytes, String> + Send + Sync {
+	/// Create a new encryptor fetching the key material of a contract with `fetch_key`.
+	pub fn new(fetch_key: F) -> Self {
+		AesEncryptor {
+			fetch_key: fetch_key,
+			keys: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Keys of `contract`. The key material is fetched without holding the lock, so a slow
+	/// request doesn't block the other contracts.
+	fn keys(&self, contract: &Address) -> Result<Keys, Error> {
+		if let Some(keys) = self.keys.lock().get(contract) {
+			return Ok(keys.clone());
+		}
+		let material = try!((self.fetch_key)(contract).map_err(Error::Encrypt));
+		let hash = material.sha3();
+		let keys = Keys {
+			encryption: hash[..16].to_vec(),
+			mac: hash[16..].to_vec(),
+		};
+		self.keys.lock().insert(contract.clone(), keys.clone());
+		Ok(keys)
+	}
+}
+
+fn mac(key: &[u8], data: &[u8]) -> [u8; MAC_LENGTH] {
+	let mut hmac = Hmac::new(Sha256::new(), key);
+	hmac.input(data);
+	let mut mac = [0u8; MAC_LENGTH];
+	hmac.raw_result(&mut mac);
+	mac
+}
+
+impl<F> Encryptor for AesEncryptor<F> where F: Fn(&Address) -> Result<Bytes, String> + Send + Sync {
+	fn encrypt(&self, contract: &Address, iv: &H128, plain: &[u8]) -> Result<Bytes, Error> {
+		let keys = try!(self.keys(contract));
+		let mut cypher = vec![0u8; plain.len()];
+		aes::ctr(KeySize::KeySize128, &keys.encryption, iv).process(plain, &mut cypher);
+		cypher.extend_from_slice(iv);
+		let mac = mac(&keys.mac, &cypher);
+		cypher.extend_from_slice(&mac);
+		Ok(cypher)
+	}
+
+	fn decrypt(&self, contract: &Address, cypher: &[u8]) -> Result<Bytes, Error> {
+		if cypher.len() < IV_LENGTH + MAC_LENGTH {
+			return Err(Error::Encrypt("Encrypted data is too short".into()));
+		}
+		let keys = try!(self.keys(contract));
+		let (authenticated, tag) = cypher.split_at(cypher.len() - MAC_LENGTH);
+		if !fixed_time_eq(&mac(&keys.mac, authenticated), tag) {
+			return Err(Error::Encrypt("Encrypted data is not authentic".into()));
+		}
+		let (data, iv) = authenticated.split_at(authenticated.len() - IV_LENGTH);
+		let mut plain = vec![0u8; data.len()];
+		aes::ctr(KeySize::KeySize128, &keys.encryption, iv).process(data, &mut plain);
+		Ok(plain)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use util::{H128, Address};
+	use super::{Encryptor, AesEncryptor};
+
+	#[test]
+	fn should_encrypt_and_decrypt() {
+		let encryptor = AesEncryptor::new(|contract: &Address| Ok(contract.to_vec()));
+		let contract: Address = 5.into();
+		let iv: H128 = 7.into();
+
+		let cypher = encryptor.encrypt(&contract, &iv, b"private data").unwrap();
+		assert!(&cypher[..12] != b"private data");
+		assert_eq!(encryptor.decrypt(&contract, &cypher).unwrap(), b"private data".to_vec());
+		assert!(encryptor.decrypt(&6.into(), &cypher).is_err());
+		assert!(encryptor.decrypt(&contract, &[0u8; 4]).is_err());
+	}
+
+	#[test]
+	fn should_refuse_altered_data() {
+		let encryptor = AesEncryptor::new(|contract: &Address| Ok(contract.to_vec()));
+		let contract: Address = 5.into();
+
+		let mut cypher = encryptor.encrypt(&contract, &7.into(), b"private data").unwrap();
+		cypher[0] ^= 1;
+		assert!(encryptor.decrypt(&contract, &cypher).is_err());
+	}
+
+	#[test]
+	fn should_fetch_key_once() {
+		let fetched = AtomicUsize::new(0);
+		let encryptor = AesEncryptor::new(|contract: &Address| {
+			fetched.fetch_add(1, Ordering::SeqCst);
+			Ok(contract.to_vec())
+		});
+		let contract: Address = 5.into();
+
+		let cypher = encryptor.encrypt(&contract, &7.into(), b"private data").unwrap();
+		encryptor.decrypt(&contract, &cypher).unwrap();
+		assert_eq!(fetched.load(Ordering::SeqCst), 1);
+	}
+}
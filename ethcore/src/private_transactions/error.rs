@@ -0,0 +1,78 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Private transactions errors.
+
+use std::fmt;
+use rlp::DecoderError;
+use util::Address;
+
+/// Private transactions error.
+#[derive(Debug)]
+pub enum Error {
+	/// The transaction is not a call to a contract.
+	BadTransactionType,
+	/// The private contract has no validators.
+	NoValidators,
+	/// None of the local accounts validates the contract.
+	NotValidator,
+	/// The message was signed by an account which doesn't validate the contract.
+	NotFromValidator(Address),
+	/// No account is configured to sign the public state transactions.
+	SignerAccountNotSet,
+	/// The signed message refers to an unknown private transaction.
+	PrivateTransactionNotFound,
+	/// A validator signed a different state than the pending one.
+	StateMismatch,
+	/// Error encrypting or decrypting a payload.
+	Encrypt(String),
+	/// Error calling the private contract.
+	Contract(String),
+	/// Error executing the private transaction.
+	Call(String),
+	/// Error signing a message.
+	Sign(String),
+	/// Error decoding a message.
+	Decoder(DecoderError),
+	/// Error importing the public transaction.
+	Transaction(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::BadTransactionType => write!(f, "Private transactions must call a contract"),
+			Error::NoValidators => write!(f, "The private contract has no validators"),
+			Error::NotValidator => write!(f, "No local account validates the private contract"),
+			Error::NotFromValidator(ref address) => write!(f, "Message signed by {} which is not a validator", address),
+			Error::SignerAccountNotSet => write!(f, "No account configured to sign public transactions"),
+			Error::PrivateTransactionNotFound => write!(f, "Unknown private transaction"),
+			Error::StateMismatch => write!(f, "Validator signed a different state"),
+			Error::Encrypt(ref e) => write!(f, "Encryption error: {}", e),
+			Error::Contract(ref e) => write!(f, "Private contract error: {}", e),
+			Error::Call(ref e) => write!(f, "Private execution error: {}", e),
+			Error::Sign(ref e) => write!(f, "Signing error: {}", e),
+			Error::Decoder(ref e) => write!(f, "Invalid message: {:?}", e),
+			Error::Transaction(ref e) => write!(f, "Error importing public transaction: {}", e),
+		}
+	}
+}
+
+impl From<DecoderError> for Error {
+	fn from(err: DecoderError) -> Self {
+		Error::Decoder(err)
+	}
+}
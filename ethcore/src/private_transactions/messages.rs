@@ -0,0 +1,144 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Messages exchanged between the nodes taking part in private transactions.
+
+use util::{H256, H520, Address, Bytes, Hashable};
+use ethkey::{Signature, recover, public_to_address};
+use rlp::*;
+
+/// Private transaction encrypted with the key of the contract it calls.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PrivateTransaction {
+	/// Encrypted rlp of the signed transaction.
+	pub encrypted: Bytes,
+	/// Address of the private contract.
+	pub contract: Address,
+	/// Block the transaction was executed on by its creator. Validators execute it on the same
+	/// state so that they all sign the same new state.
+	pub block_hash: H256,
+}
+
+impl PrivateTransaction {
+	/// Hash identifying the private transaction.
+	pub fn hash(&self) -> H256 {
+		(&*self.rlp_bytes()).sha3()
+	}
+}
+
+impl Encodable for PrivateTransaction {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(3);
+		s.append(&self.encrypted);
+		s.append(&self.contract);
+		s.append(&self.block_hash);
+	}
+}
+
+impl Decodable for PrivateTransaction {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		Ok(PrivateTransaction {
+			encrypted: try!(d.val_at(0)),
+			contract: try!(d.val_at(1)),
+			block_hash: try!(d.val_at(2)),
+		})
+	}
+}
+
+/// A validator's signature of the new state resulting from a private transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedPrivateTransaction {
+	/// Hash of the private transaction.
+	pub private_transaction_hash: H256,
+	/// Address of the private contract.
+	pub contract: Address,
+	/// Commitment to the new state, see `Provider` for how it is computed.
+	pub state_hash: H256,
+	/// Signature of `state_hash`.
+	pub signature: Signature,
+}
+
+impl SignedPrivateTransaction {
+	/// Address of the validator who signed `state_hash`.
+	pub fn signer(&self) -> Result<Address, DecoderError> {
+		recover(&self.signature, &self.state_hash)
+			.map(|public| public_to_address(&public))
+			.map_err(|_| DecoderError::Custom("Invalid signature"))
+	}
+
+	/// Hash identifying the message.
+	pub fn hash(&self) -> H256 {
+		(&*self.rlp_bytes()).sha3()
+	}
+}
+
+impl Encodable for SignedPrivateTransaction {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.append(&self.private_transaction_hash);
+		s.append(&self.contract);
+		s.append(&self.state_hash);
+		s.append(&H520::from(self.signature.clone()));
+	}
+}
+
+impl Decodable for SignedPrivateTransaction {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let signature: H520 = try!(d.val_at(3));
+		Ok(SignedPrivateTransaction {
+			private_transaction_hash: try!(d.val_at(0)),
+			contract: try!(d.val_at(1)),
+			state_hash: try!(d.val_at(2)),
+			signature: signature.into(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ethkey::{sign, Generator, Random};
+	use rlp::{encode, decode};
+	use util::{H256, Hashable};
+	use super::{PrivateTransaction, SignedPrivateTransaction};
+
+	#[test]
+	fn should_encode_and_decode_messages() {
+		let private = PrivateTransaction {
+			encrypted: vec![1, 2, 3],
+			contract: 5.into(),
+			block_hash: H256::from(7),
+		};
+		let decoded: PrivateTransaction = decode(&encode(&private));
+		assert_eq!(decoded, private);
+
+		let keypair = Random.generate().unwrap();
+		let state_hash = b"state".sha3();
+		let mut signed = SignedPrivateTransaction {
+			private_transaction_hash: private.hash(),
+			contract: 5.into(),
+			state_hash: state_hash,
+			signature: sign(keypair.secret(), &state_hash).unwrap(),
+		};
+		let decoded: SignedPrivateTransaction = decode(&encode(&signed));
+		assert_eq!(decoded, signed);
+		assert_eq!(decoded.signer().unwrap(), keypair.address());
+
+		signed.state_hash = H256::default();
+		assert!(signed.signer().map(|a| a != keypair.address()).unwrap_or(true));
+	}
+}
@@ -0,0 +1,547 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Private transactions.
+//!
+//! A private contract is a public contract holding the encrypted code and state of a
+//! contract along with the accounts validating it. Private transactions are encrypted
+//! with the contract key, obtained from the secret store, and sent to the validators.
+//! Each validator executes the transaction against the decrypted state and signs the
+//! resulting encrypted state. Once all validators have signed, the new state is
+//! committed publicly with a call to `setState`, so that only state commitments
+//! are visible on chain.
+//!
+//! The private contract is expected to implement:
+//!
+//! ```text
+//! function getValidators() constant returns (address[]);
+//! function getCode() constant returns (bytes);
+//! function getState() constant returns (bytes);
+//! function nonce() constant returns (uint);
+//! function setState(bytes newState, uint8[] v, bytes32[] r, bytes32[] s);
+//! ```
+//!
+//! where `setState` checks that every validator signed `sha3(contract ++ sha3(newState) ++ nonce)`.
+//!
+//! Validators execute a private transaction on the state of the block its creator executed it on,
+//! so that they all sign the same new state.
+
+mod encryptor;
+mod error;
+mod messages;
+
+pub use self::encryptor::{Encryptor, AesEncryptor};
+pub use self::error::Error;
+pub use self::messages::{PrivateTransaction, SignedPrivateTransaction};
+
+use std::fmt;
+use std::thread;
+use std::sync::{Arc, Weak, mpsc};
+use std::collections::{HashMap, BTreeMap};
+use ethabi::{Interface, Contract, Token};
+use ethkey::Signature;
+use lru_cache::LruCache;
+use rlp::{UntrustedRlp, RlpStream, View, Stream, Encodable};
+use util::{H128, H256, U256, Address, Bytes, Mutex, Hashable, FixedHash};
+use account_provider::AccountProvider;
+use client::{Client, BlockChainClient, BlockID};
+use executive::Executed;
+use header::BlockNumber;
+use miner::{Miner, MinerService};
+use transaction::{SignedTransaction, Transaction, Action};
+
+const CONTRACT_ABI: &'static [u8] = br#"[{"constant":true,"inputs":[],"name":"getValidators","outputs":[{"name":"","type":"address[]"}],"type":"function"},{"constant":true,"inputs":[],"name":"getCode","outputs":[{"name":"","type":"bytes"}],"type":"function"},{"constant":true,"inputs":[],"name":"getState","outputs":[{"name":"","type":"bytes"}],"type":"function"},{"constant":true,"inputs":[],"name":"nonce","outputs":[{"name":"","type":"uint256"}],"type":"function"},{"constant":false,"inputs":[{"name":"newState","type":"bytes"},{"name":"v","type":"uint8[]"},{"name":"r","type":"bytes32[]"},{"name":"s","type":"bytes32[]"}],"name":"setState","outputs":[],"type":"function"}]"#;
+
+/// Number of message hashes remembered to avoid processing or relaying a message twice.
+const MAX_SEEN_MESSAGES: usize = 4096;
+/// Number of messages received from the network waiting to be imported.
+const MAX_QUEUED_MESSAGES: usize = 1024;
+/// Number of blocks after which a new state still missing validator signatures is dropped.
+const PENDING_STATE_BLOCKS: BlockNumber = 64;
+
+/// Private transactions configuration.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProviderConfig {
+	/// Local accounts validating private contracts.
+	pub validator_accounts: Vec<Address>,
+	/// Account signing the public transactions which commit new states.
+	pub signer_account: Option<Address>,
+}
+
+/// Result of sending a private transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Receipt {
+	/// Hash of the private transaction.
+	pub hash: H256,
+	/// Address of the private contract.
+	pub contract_address: Address,
+	/// Output of the private execution.
+	pub output: Bytes,
+	/// Whether the new state was committed immediately, i.e. all validators are local.
+	pub committed: bool,
+}
+
+/// New state of a private contract waiting for validator signatures.
+struct PendingState {
+	contract: Address,
+	state: Bytes,
+	state_hash: H256,
+	validators: Vec<Address>,
+	signatures: HashMap<Address, Signature>,
+	/// Number of the block the private transaction was executed on.
+	block_number: BlockNumber,
+}
+
+impl PendingState {
+	fn is_complete(&self) -> bool {
+		self.validators.iter().all(|validator| self.signatures.contains_key(validator))
+	}
+}
+
+/// Message received from the network.
+enum Message {
+	Private(Bytes),
+	Signed(Bytes),
+}
+
+/// Creates, validates and commits private transactions.
+pub struct Provider {
+	config: ProviderConfig,
+	encryptor: Box<Encryptor>,
+	contract: Contract,
+	client: Arc<Client>,
+	miner: Arc<Miner>,
+	accounts: Arc<AccountProvider>,
+	pending: Mutex<HashMap<H256, PendingState>>,
+	seen: Mutex<LruCache<H256, ()>>,
+	queue: Mutex<mpsc::SyncSender<Message>>,
+	queued: Mutex<Option<mpsc::Receiver<Message>>>,
+}
+
+impl Provider {
+	/// Create a new provider. Call `start` to receive the private transactions of other nodes.
+	pub fn new(config: ProviderConfig, encryptor: Box<Encryptor>, client: Arc<Client>, miner: Arc<Miner>, accounts: Arc<AccountProvider>) -> Self {
+		let interface = Interface::load(CONTRACT_ABI).expect("private contract ABI is valid");
+		let (queue, queued) = mpsc::sync_channel(MAX_QUEUED_MESSAGES);
+		Provider {
+			config: config,
+			encryptor: encryptor,
+			contract: Contract::new(interface),
+			client: client,
+			miner: miner,
+			accounts: accounts,
+			pending: Mutex::new(HashMap::new()),
+			seen: Mutex::new(LruCache::new(MAX_SEEN_MESSAGES)),
+			queue: Mutex::new(queue),
+			queued: Mutex::new(Some(queued)),
+		}
+	}
+
+	/// Register the provider with the client and import the messages it receives from the network
+	/// in a background thread until the provider is dropped. Importing decrypts messages with keys
+	/// from the Secret Store and executes transactions, so it's kept off the client's IO threads.
+	pub fn start(this: &Arc<Self>) -> Result<(), String> {
+		let queued = try!(this.queued.lock().take().ok_or_else(|| "Private transactions provider is already started".to_owned()));
+		let provider = Arc::downgrade(this);
+		try!(thread::Builder::new().name("private-tx".into()).spawn(move || Self::run(provider, queued))
+			.map_err(|e| format!("Error starting private transactions thread: {}", e)));
+		this.client.set_private_transactions(Arc::downgrade(this));
+		Ok(())
+	}
+
+	fn run(provider: Weak<Self>, queued: mpsc::Receiver<Message>) {
+		for message in queued.iter() {
+			let provider = match provider.upgrade() {
+				Some(provider) => provider,
+				None => return,
+			};
+			let result = match message {
+				Message::Private(ref packet) => provider.import_private_transaction(packet),
+				Message::Signed(ref packet) => provider.import_signed_private_transaction(packet),
+			};
+			if let Err(e) = result {
+				debug!(target: "privatetx", "Error importing private transaction message: {}", e);
+			}
+		}
+	}
+
+	/// Queue a private transaction received from the network for importing.
+	pub fn queue_private_transaction(&self, packet: Bytes) {
+		self.queue_message(Message::Private(packet));
+	}
+
+	/// Queue a validator's signature received from the network for importing.
+	pub fn queue_signed_private_transaction(&self, packet: Bytes) {
+		self.queue_message(Message::Signed(packet));
+	}
+
+	fn queue_message(&self, message: Message) {
+		if self.queue.lock().try_send(message).is_err() {
+			debug!(target: "privatetx", "Ignoring private transaction message: queue is full");
+		}
+	}
+
+	/// Execute `transaction` privately, encrypt it and send it to the validators of the
+	/// contract it calls. The new state is committed once all validators signed it.
+	pub fn create_private_transaction(&self, transaction: SignedTransaction) -> Result<Receipt, Error> {
+		if self.config.signer_account.is_none() {
+			return Err(Error::SignerAccountNotSet);
+		}
+		let contract = try!(private_contract(&transaction));
+		let chain_info = self.client.chain_info();
+		let block = BlockID::Hash(chain_info.best_block_hash.clone());
+		let validators = try!(self.validators(&contract, block));
+		let (executed, state) = try!(self.execute(&transaction, block));
+		let state_hash = try!(self.state_hash(&contract, &state, block));
+
+		let private = PrivateTransaction {
+			encrypted: try!(self.encryptor.encrypt(&contract, &H128::random(), &transaction.rlp_bytes())),
+			contract: contract.clone(),
+			block_hash: chain_info.best_block_hash,
+		};
+		let hash = private.hash();
+		let mut pending = PendingState {
+			contract: contract.clone(),
+			state: state,
+			state_hash: state_hash,
+			validators: validators,
+			signatures: HashMap::new(),
+			block_number: chain_info.best_block_number,
+		};
+		for account in self.local_validators(&pending.validators) {
+			let signature = try!(self.sign(account, &pending.state_hash));
+			pending.signatures.insert(account, signature);
+		}
+
+		let committed = pending.is_complete();
+		if committed {
+			try!(self.commit_state(pending));
+		} else {
+			{
+				let mut states = self.pending.lock();
+				prune_pending(&mut states, chain_info.best_block_number);
+				states.insert(hash, pending);
+			}
+			self.seen.lock().insert(hash, ());
+			self.client.broadcast_private_transaction(private.rlp_bytes().to_vec());
+		}
+		trace!(target: "privatetx", "Private transaction {} to {} created", hash, contract);
+
+		Ok(Receipt {
+			hash: hash,
+			contract_address: contract,
+			output: executed.output,
+			committed: committed,
+		})
+	}
+
+	/// Import a private transaction received from the network. Validators of the contract
+	/// execute it and broadcast their signature of the new state, other nodes relay it.
+	/// Messages are only remembered once processed, so those which failed can be retried.
+	pub fn import_private_transaction(&self, rlp: &[u8]) -> Result<(), Error> {
+		let private: PrivateTransaction = try!(UntrustedRlp::new(rlp).as_val());
+		let hash = private.hash();
+		if self.is_seen(&hash) {
+			return Ok(());
+		}
+
+		let validators = try!(self.validators(&private.contract, BlockID::Latest));
+		let accounts = self.local_validators(&validators);
+		if accounts.is_empty() {
+			self.seen.lock().insert(hash, ());
+			self.client.broadcast_private_transaction(rlp.to_vec());
+			return Ok(());
+		}
+
+		let block = BlockID::Hash(private.block_hash.clone());
+		let plain = try!(self.encryptor.decrypt(&private.contract, &private.encrypted));
+		let transaction: SignedTransaction = try!(UntrustedRlp::new(&plain).as_val());
+		if try!(private_contract(&transaction)) != private.contract {
+			return Err(Error::BadTransactionType);
+		}
+		let (_, state) = try!(self.execute(&transaction, block));
+		let state_hash = try!(self.state_hash(&private.contract, &state, block));
+		let mut signed = Vec::with_capacity(accounts.len());
+		for account in accounts {
+			signed.push(SignedPrivateTransaction {
+				private_transaction_hash: hash,
+				contract: private.contract.clone(),
+				state_hash: state_hash,
+				signature: try!(self.sign(account, &state_hash)),
+			});
+			trace!(target: "privatetx", "Private transaction {} validated by {}", hash, account);
+		}
+
+		self.seen.lock().insert(hash, ());
+		for signed in signed {
+			self.seen.lock().insert(signed.hash(), ());
+			self.client.broadcast_signed_private_transaction(signed.rlp_bytes().to_vec());
+		}
+		Ok(())
+	}
+
+	/// Import a validator's signature received from the network. The state is committed
+	/// once signed by all validators. Signatures of unknown transactions are relayed if they
+	/// were made by a validator of the contract.
+	pub fn import_signed_private_transaction(&self, rlp: &[u8]) -> Result<(), Error> {
+		let signed: SignedPrivateTransaction = try!(UntrustedRlp::new(rlp).as_val());
+		let message_hash = signed.hash();
+		if self.is_seen(&message_hash) {
+			return Ok(());
+		}
+
+		let signer = try!(signed.signer());
+		let hash = signed.private_transaction_hash;
+		let is_pending = {
+			let mut states = self.pending.lock();
+			prune_pending(&mut states, self.client.chain_info().best_block_number);
+			states.contains_key(&hash)
+		};
+		if !is_pending {
+			let validators = try!(self.validators(&signed.contract, BlockID::Latest));
+			if !validators.contains(&signer) {
+				return Err(Error::NotFromValidator(signer));
+			}
+			self.seen.lock().insert(message_hash, ());
+			self.client.broadcast_signed_private_transaction(rlp.to_vec());
+			return Ok(());
+		}
+
+		let complete = {
+			let mut states = self.pending.lock();
+			let complete = match states.get_mut(&hash) {
+				Some(state) => {
+					if signed.contract != state.contract || signed.state_hash != state.state_hash {
+						return Err(Error::StateMismatch);
+					}
+					if !state.validators.contains(&signer) {
+						return Err(Error::NotFromValidator(signer));
+					}
+					state.signatures.insert(signer, signed.signature);
+					state.is_complete()
+				},
+				None => return Err(Error::PrivateTransactionNotFound),
+			};
+			if complete { states.remove(&hash) } else { None }
+		};
+
+		self.seen.lock().insert(message_hash, ());
+		if let Some(state) = complete {
+			try!(self.commit_state(state));
+		}
+		Ok(())
+	}
+
+	/// Execute `transaction` privately on top of the state at `block` without committing anything.
+	pub fn private_call(&self, block: BlockID, transaction: &SignedTransaction) -> Result<Executed, Error> {
+		self.execute(transaction, block).map(|(executed, _)| executed)
+	}
+
+	/// Execute `transaction` against the decrypted state of the private contract it calls at `block`,
+	/// returning the result and the encrypted new state.
+	fn execute(&self, transaction: &SignedTransaction, block: BlockID) -> Result<(Executed, Bytes), Error> {
+		let contract = try!(private_contract(transaction));
+		let code = try!(self.decrypt_field(&contract, "getCode", block));
+		let state = try!(self.decrypt_field(&contract, "getState", block));
+		let storage = try!(decode_state(&state));
+
+		let (executed, storage) = try!(self.client.call_private_contract(transaction, block, &contract, code, storage)
+			.map_err(|e| Error::Call(format!("{:?}", e))));
+		// validators must all produce the same encrypted state, so the iv depends only on the transaction
+		// and the contract state it is executed on. Re-executing a transaction on a different state
+		// must not reuse the iv.
+		let block_hash = try!(self.client.block_hash(block.clone()).ok_or_else(|| Error::Call("Unknown block".into())));
+		let contract_nonce = try!(self.client.nonce(&contract, block).ok_or_else(|| Error::Call("State is not available".into())));
+		let mut seed = transaction.hash().to_vec();
+		seed.extend_from_slice(&block_hash);
+		seed.extend_from_slice(&H256::from(contract_nonce));
+		let iv = H128::from_slice(&seed.sha3()[..16]);
+		let state = try!(self.encryptor.encrypt(&contract, &iv, &encode_state(&storage)));
+		Ok((executed, state))
+	}
+
+	/// Send the public transaction committing a new state signed by all validators.
+	fn commit_state(&self, pending: PendingState) -> Result<(), Error> {
+		let signer = try!(self.config.signer_account.ok_or(Error::SignerAccountNotSet));
+		let (mut v, mut r, mut s) = (Vec::new(), Vec::new(), Vec::new());
+		for validator in &pending.validators {
+			let signature = pending.signatures.get(validator).expect("state is complete; qed");
+			let mut recovery = [0u8; 32];
+			recovery[31] = signature.v() + 27;
+			v.push(Token::Uint(recovery));
+			r.push(Token::FixedBytes(signature.r().to_vec()));
+			s.push(Token::FixedBytes(signature.s().to_vec()));
+		}
+
+		let set_state = try!(self.contract.function("setState".into()).map_err(as_contract_error));
+		let data = try!(set_state.encode_call(vec![Token::Bytes(pending.state), Token::Array(v), Token::Array(r), Token::Array(s)])
+			.map_err(as_contract_error));
+		let transaction = Transaction {
+			nonce: self.miner.last_nonce(&signer).map_or_else(|| self.client.latest_nonce(&signer), |nonce| nonce + U256::one()),
+			action: Action::Call(pending.contract.clone()),
+			gas: self.miner.sensible_gas_limit(),
+			gas_price: self.miner.sensible_gas_price(),
+			value: U256::zero(),
+			data: data,
+		};
+		let signature = try!(self.sign(signer, &transaction.hash()));
+		let transaction = transaction.with_signature(signature);
+		let hash = transaction.hash();
+		try!(self.miner.import_own_transaction(&*self.client, transaction).map_err(|e| Error::Transaction(format!("{:?}", e))));
+		trace!(target: "privatetx", "State of {} committed in transaction {}", pending.contract, hash);
+		Ok(())
+	}
+
+	/// Hash signed by the validators to approve `state` as the next state of `contract`:
+	/// `sha3(contract ++ sha3(state) ++ nonce)`, with the nonce of the contract at `block`.
+	fn state_hash(&self, contract: &Address, state: &[u8], block: BlockID) -> Result<H256, Error> {
+		let nonce = match try!(self.call(contract, "nonce", block)).into_iter().next() {
+			Some(Token::Uint(nonce)) => nonce,
+			other => return Err(Error::Contract(format!("Invalid nonce: {:?}", other))),
+		};
+		let mut message = contract.to_vec();
+		message.extend_from_slice(&state.sha3());
+		message.extend_from_slice(&nonce);
+		Ok(message.sha3())
+	}
+
+	fn validators(&self, contract: &Address, block: BlockID) -> Result<Vec<Address>, Error> {
+		let validators = match try!(self.call(contract, "getValidators", block)).into_iter().next() {
+			Some(Token::Array(validators)) => try!(validators.into_iter().map(|validator| match validator {
+				Token::Address(address) => Ok(Address::from(address)),
+				other => Err(Error::Contract(format!("Invalid validator: {:?}", other))),
+			}).collect::<Result<Vec<_>, _>>()),
+			other => return Err(Error::Contract(format!("Invalid validators: {:?}", other))),
+		};
+		if validators.is_empty() {
+			return Err(Error::NoValidators);
+		}
+		Ok(validators)
+	}
+
+	fn local_validators(&self, validators: &[Address]) -> Vec<Address> {
+		self.config.validator_accounts.iter().filter(|account| validators.contains(account)).cloned().collect()
+	}
+
+	fn is_seen(&self, hash: &H256) -> bool {
+		self.seen.lock().get_mut(hash).is_some()
+	}
+
+	/// Read and decrypt a `bytes` field of the private contract. Empty fields are left as they are.
+	fn decrypt_field(&self, contract: &Address, name: &str, block: BlockID) -> Result<Bytes, Error> {
+		match try!(self.call(contract, name, block)).into_iter().next() {
+			Some(Token::Bytes(ref encrypted)) if encrypted.is_empty() => Ok(Vec::new()),
+			Some(Token::Bytes(encrypted)) => self.encryptor.decrypt(contract, &encrypted),
+			other => Err(Error::Contract(format!("Invalid {} result: {:?}", name, other))),
+		}
+	}
+
+	fn call(&self, contract: &Address, name: &str, block: BlockID) -> Result<Vec<Token>, Error> {
+		let function = try!(self.contract.function(name.into()).map_err(as_contract_error));
+		let data = try!(function.encode_call(Vec::new()).map_err(as_contract_error));
+		let output = try!(self.client.call_contract(block, contract.clone(), data).map_err(Error::Contract));
+		function.decode_output(output).map_err(as_contract_error)
+	}
+
+	fn sign(&self, account: Address, hash: &H256) -> Result<Signature, Error> {
+		self.accounts.sign(account, hash.clone()).map_err(|e| Error::Sign(format!("{}", e)))
+	}
+}
+
+/// Drop the pending states created more than `PENDING_STATE_BLOCKS` blocks before `best_block`.
+fn prune_pending(states: &mut HashMap<H256, PendingState>, best_block: BlockNumber) {
+	let expired: Vec<H256> = states.iter()
+		.filter(|&(_, state)| state.block_number + PENDING_STATE_BLOCKS < best_block)
+		.map(|(hash, _)| hash.clone())
+		.collect();
+	for hash in expired {
+		trace!(target: "privatetx", "Dropping private transaction {} missing validator signatures", hash);
+		states.remove(&hash);
+	}
+}
+
+/// Address of the private contract called by `transaction`.
+fn private_contract(transaction: &SignedTransaction) -> Result<Address, Error> {
+	match transaction.action {
+		Action::Call(ref contract) => Ok(contract.clone()),
+		Action::Create => Err(Error::BadTransactionType),
+	}
+}
+
+/// Encode contract storage as a list of `[key, value]` pairs.
+fn encode_state(storage: &BTreeMap<H256, H256>) -> Bytes {
+	let mut stream = RlpStream::new_list(storage.len());
+	for (key, value) in storage {
+		stream.begin_list(2).append(key).append(value);
+	}
+	stream.out()
+}
+
+fn decode_state(state: &[u8]) -> Result<BTreeMap<H256, H256>, Error> {
+	let mut storage = BTreeMap::new();
+	if state.is_empty() {
+		return Ok(storage);
+	}
+	for item in UntrustedRlp::new(state).iter() {
+		storage.insert(try!(item.val_at(0)), try!(item.val_at(1)));
+	}
+	Ok(storage)
+}
+
+fn as_contract_error<T: fmt::Debug>(e: T) -> Error {
+	Error::Contract(format!("{:?}", e))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::{BTreeMap, HashMap};
+	use util::H256;
+	use super::{encode_state, decode_state, prune_pending, PendingState};
+
+	fn pending_state(block_number: u64) -> PendingState {
+		PendingState {
+			contract: 5.into(),
+			state: Vec::new(),
+			state_hash: H256::default(),
+			validators: vec![6.into()],
+			signatures: HashMap::new(),
+			block_number: block_number,
+		}
+	}
+
+	#[test]
+	fn should_encode_and_decode_state() {
+		let mut storage = BTreeMap::new();
+		storage.insert(1.into(), 2.into());
+		storage.insert(3.into(), 4.into());
+
+		assert_eq!(decode_state(&encode_state(&storage)).unwrap(), storage);
+		assert_eq!(decode_state(&[]).unwrap(), BTreeMap::new());
+	}
+
+	#[test]
+	fn should_prune_expired_pending_states() {
+		let mut states = HashMap::new();
+		states.insert(H256::from(1), pending_state(10));
+		states.insert(H256::from(2), pending_state(100));
+
+		prune_pending(&mut states, 74);
+		assert_eq!(states.len(), 2);
+		prune_pending(&mut states, 75);
+		assert_eq!(states.len(), 1);
+		assert!(states.contains_key(&H256::from(2)));
+	}
+}
@@ -22,7 +22,7 @@ use spec::Spec;
 use error::*;
 use client::{Client, ClientConfig, ChainNotify};
 use miner::Miner;
-use snapshot::ManifestData;
+use snapshot::{ManifestData, SnapshotService as SnapshotServiceTrait};
 use snapshot::service::{Service as SnapshotService, ServiceParams as SnapServiceParams};
 use std::sync::atomic::AtomicBool;
 
@@ -46,6 +46,10 @@ pub enum ClientIoMessage {
 	FeedStateChunk(H256, Bytes),
 	/// Feed a block chunk to the snapshot service
 	FeedBlockChunk(H256, Bytes),
+	/// Feed state trie nodes of the given account (`None` for the account trie) to the snapshot service
+	FeedTrieNodes(Option<H256>, Vec<Bytes>),
+	/// Finish a state trie restoration at the given manifest, feeding the block chunk of its target block
+	CompleteTrieRestoration(ManifestData, Bytes),
 	/// Take a snapshot for the block with given number.
 	TakeSnapshot(u64),
 }
@@ -87,8 +91,15 @@ impl ClientService {
 			db_config.set_cache(::db::COL_STATE, size);
 		}
 
-		db_config.compaction = config.db_compaction.compaction_profile();
+		db_config.compaction = config.db_compaction.compaction_profile(client_path);
 		db_config.wal = config.db_wal;
+		if let Some(max_open_files) = config.db_max_open_files {
+			db_config.max_open_files = max_open_files;
+		}
+		if let Some(wal_size) = config.db_wal_size {
+			db_config.wal_size_limit = wal_size;
+		}
+		db_config.write_buffer_size = config.db_write_buffer_size;
 
 		let pruning = config.pruning;
 		let client = try!(Client::new(config, &spec, client_path, miner, io_service.channel(), &db_config));
@@ -152,6 +163,13 @@ impl ClientService {
 	pub fn add_notify(&self, notify: Arc<ChainNotify>) {
 		self.client.add_notify(notify);
 	}
+
+	/// Stop block import and snapshot restoration and write all pending database changes.
+	/// The next start skips the consistency check only if this succeeded.
+	pub fn shutdown(&self) -> Result<(), String> {
+		self.snapshot.abort_restore();
+		self.client.shutdown()
+	}
 }
 
 impl MayPanic for ClientService {
@@ -198,6 +216,8 @@ impl IoHandler<ClientIoMessage> for ClientIoHandler {
 			}
 			ClientIoMessage::FeedStateChunk(ref hash, ref chunk) => self.snapshot.feed_state_chunk(*hash, chunk),
 			ClientIoMessage::FeedBlockChunk(ref hash, ref chunk) => self.snapshot.feed_block_chunk(*hash, chunk),
+			ClientIoMessage::FeedTrieNodes(ref address_hash, ref nodes) => self.snapshot.feed_queued_trie_nodes(address_hash.clone(), nodes),
+			ClientIoMessage::CompleteTrieRestoration(ref manifest, ref chunk) => self.snapshot.finish_trie_restore(manifest.clone(), chunk),
 			ClientIoMessage::TakeSnapshot(num) => {
 				if let Err(e) = self.snapshot.take_snapshot(&*self.client, num) {
 					warn!("Failed to take snapshot at block #{}: {}", num, e);
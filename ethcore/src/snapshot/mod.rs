@@ -27,15 +27,15 @@ use account_db::{AccountDB, AccountDBMut};
 use blockchain::{BlockChain, BlockProvider};
 use engines::Engine;
 use ids::BlockID;
-use views::BlockView;
+use views::{BlockView, HeaderView};
 
-use util::{Bytes, Hashable, HashDB, snappy};
+use util::{Bytes, Hashable, HashDB, U256, snappy};
 use util::memorydb::MemoryDB;
 use util::Mutex;
 use util::hash::{FixedHash, H256};
 use util::journaldb::{self, Algorithm, JournalDB};
 use util::kvdb::Database;
-use util::trie::{TrieDB, TrieDBMut, Trie, TrieMut};
+use util::trie::{TrieDB, TrieDBMut, Trie, TrieMut, TrieError};
 use util::sha3::SHA3_NULL_RLP;
 use rlp::{RlpStream, Stream, UntrustedRlp, View};
 
@@ -289,6 +289,25 @@ pub fn chunk_blocks<'a>(chain: &'a BlockChain, start_block_info: (u64, H256), wr
 	Ok(chunker.hashes)
 }
 
+/// Create a compressed block chunk holding a single block along with its receipts.
+/// `receipts` is the RLP list of the block's receipts.
+/// Returns the hash of the chunk and the chunk itself.
+pub fn single_block_chunk(block: &[u8], receipts: &[u8], parent_total_difficulty: U256) -> (H256, Bytes) {
+	let view = BlockView::new(block);
+	let header = view.header_view();
+	let abridged_rlp = AbridgedBlock::from_block_view(&view).into_inner();
+
+	let mut pair_stream = RlpStream::new_list(2);
+	pair_stream.append_raw(&abridged_rlp, 1).append_raw(receipts, 1);
+
+	let mut rlp_stream = RlpStream::new_list(4);
+	rlp_stream.append(&(header.number() - 1)).append(&header.parent_hash()).append(&parent_total_difficulty);
+	rlp_stream.append_raw(&pair_stream.out(), 1);
+
+	let compressed = snappy::compress(&rlp_stream.out());
+	(compressed.sha3(), compressed)
+}
+
 /// State trie chunker.
 struct StateChunker<'a> {
 	hashes: Vec<H256>,
@@ -490,6 +509,56 @@ impl StateRebuilder {
 		Ok(())
 	}
 
+	/// Feed raw trie nodes into the rebuilder. Storage trie nodes and code are
+	/// given with the hash of the account they belong to.
+	pub fn feed_trie_nodes(&mut self, address_hash: Option<H256>, nodes: &[Bytes]) -> Result<(), ::error::Error> {
+		match address_hash {
+			Some(address_hash) => {
+				let mut db = AccountDBMut::from_hash(self.db.as_hashdb_mut(), address_hash);
+				for node in nodes {
+					db.insert(node);
+				}
+			}
+			None => {
+				for node in nodes {
+					self.db.as_hashdb_mut().insert(node);
+				}
+			}
+		}
+
+		let backing = self.db.backing().clone();
+		let mut batch = backing.transaction();
+		try!(self.db.inject(&mut batch));
+		try!(backing.write(batch).map_err(::util::UtilError::SimpleString));
+		Ok(())
+	}
+
+	/// Set the state root after all trie nodes have been fed.
+	/// Walks the account trie, which checks that no node is missing and fills the account bloom.
+	pub fn finish_trie(&mut self, state_root: H256) -> Result<(), ::error::Error> {
+		let backing = self.db.backing().clone();
+		let mut bloom = StateDB::load_bloom(&backing);
+		let mut accounts = 0;
+
+		{
+			let account_trie = try!(TrieDB::new(self.db.as_hashdb(), &state_root));
+			for item in try!(account_trie.iter()) {
+				let (account_key, _) = try!(item);
+				bloom.set(&*account_key);
+				accounts += 1;
+			}
+		}
+
+		let bloom_journal = bloom.drain_journal();
+		let mut batch = backing.transaction();
+		try!(StateDB::commit_bloom(&mut batch, bloom_journal));
+		try!(backing.write(batch).map_err(::util::UtilError::SimpleString));
+
+		trace!(target: "snapshot", "rebuilt state from trie nodes: {} accounts, root {:?}", accounts, state_root);
+		self.state_root = state_root;
+		Ok(())
+	}
+
 	/// Check for accounts missing code. Once all chunks have been fed, there should
 	/// be none.
 	pub fn check_missing(self) -> Result<(), Error> {
@@ -504,6 +573,58 @@ impl StateRebuilder {
 	pub fn state_root(&self) -> H256 { self.state_root }
 }
 
+/// Feeds state chunks straight into a `StateRebuilder` instead of storing them.
+struct RebuildingWriter {
+	rebuilder: StateRebuilder,
+	buffer: Bytes,
+}
+
+impl SnapshotWriter for RebuildingWriter {
+	fn write_state_chunk(&mut self, _hash: H256, chunk: &[u8]) -> ::std::io::Result<()> {
+		use std::io::{Error as IoError, ErrorKind};
+
+		let len = try!(snappy::decompress_into(chunk, &mut self.buffer)
+			.map_err(|e| IoError::new(ErrorKind::InvalidData, format!("{:?}", e))));
+		self.rebuilder.feed(&self.buffer[..len]).map_err(|e| IoError::new(ErrorKind::Other, format!("{}", e)))
+	}
+
+	fn write_block_chunk(&mut self, _hash: H256, _chunk: &[u8]) -> ::std::io::Result<()> {
+		Ok(())
+	}
+
+	fn finish(self, _manifest: ManifestData) -> ::std::io::Result<()> {
+		Ok(())
+	}
+}
+
+/// Rebuild the state of the best block of an archive database into `dest`,
+/// journalled with the given pruning algorithm. The state is passed through
+/// snapshot chunks, so only the accounts and storage reachable from the best block's
+/// state root are carried over. Returns the number of the best block.
+pub fn rebuild_head_state(source: Arc<Database>, dest: Arc<Database>, pruning: Algorithm, progress: &Progress) -> Result<u64, ::error::Error> {
+	let chain = BlockChain::new(Default::default(), &[], source.clone());
+	let best_header = chain.best_block_header();
+	let header = HeaderView::new(&best_header);
+	let state_root = header.state_root();
+
+	info!(target: "snapshot", "Rebuilding state of block #{} ({:?})", header.number(), state_root);
+	let state_db = journaldb::new(source, Algorithm::Archive, ::db::COL_STATE);
+	let writer = Mutex::new(RebuildingWriter {
+		rebuilder: StateRebuilder::new(dest, pruning),
+		buffer: Vec::new(),
+	});
+
+	try!(chunk_state(state_db.as_hashdb(), &state_root, &writer, progress));
+
+	let rebuilder = writer.into_inner().rebuilder;
+	if rebuilder.state_root() != state_root {
+		return Err(TrieError::InvalidStateRoot(rebuilder.state_root()).into());
+	}
+	try!(rebuilder.check_missing());
+
+	Ok(header.number())
+}
+
 #[derive(Default)]
 struct RebuiltStatus {
 	new_code: Vec<(H256, Bytes)>, // new code that's become available.
@@ -580,11 +701,15 @@ impl BlockRebuilder {
 		})
 	}
 
+	/// Set the number of the block to be marked as best once fed.
+	pub fn set_best_number(&mut self, best_number: u64) {
+		self.best_number = best_number;
+	}
+
 	/// Feed the rebuilder an uncompressed block chunk.
 	/// Returns the number of blocks fed or any errors.
 	pub fn feed(&mut self, chunk: &[u8], engine: &Engine) -> Result<u64, ::error::Error> {
 		use basic_types::Seal::With;
-		use util::U256;
 		use util::triehash::ordered_trie_root;
 
 		let rlp = UntrustedRlp::new(chunk);
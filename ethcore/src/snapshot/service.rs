@@ -35,7 +35,8 @@ use service::ClientIoMessage;
 
 use io::IoChannel;
 
-use util::{Bytes, H256, Mutex, RwLock, RwLockReadGuard, UtilError};
+use util::{Bytes, H256, Hashable, Mutex, RwLock, RwLockReadGuard, UtilError};
+use util::sha3::SHA3_NULL_RLP;
 use util::journaldb::Algorithm;
 use util::kvdb::{Database, DatabaseConfig};
 use util::snappy;
@@ -74,6 +75,7 @@ struct Restoration {
 	snappy_buffer: Bytes,
 	final_state_root: H256,
 	guard: Guard,
+	trie_pending: bool,
 }
 
 struct RestorationParams<'a> {
@@ -84,6 +86,7 @@ struct RestorationParams<'a> {
 	writer: Option<LooseWriter>, // writer for recovered snapshot.
 	genesis: &'a [u8], // genesis block of the chain.
 	guard: Guard, // guard for the restoration directory.
+	trie: bool, // whether the state is fed as raw trie nodes.
 }
 
 impl Restoration {
@@ -111,6 +114,7 @@ impl Restoration {
 			snappy_buffer: Vec::new(),
 			final_state_root: root,
 			guard: params.guard,
+			trie_pending: params.trie,
 		})
 	}
 
@@ -143,6 +147,29 @@ impl Restoration {
 		Ok(())
 	}
 
+	// feeds raw trie nodes
+	fn feed_trie_nodes(&mut self, address_hash: Option<H256>, nodes: &[Bytes]) -> Result<(), Error> {
+		if self.trie_pending {
+			try!(self.state.feed_trie_nodes(address_hash, nodes));
+		}
+
+		Ok(())
+	}
+
+	// stop accepting trie nodes and move on to the block chunks of the given manifest.
+	fn complete_trie(&mut self, manifest: ManifestData) -> Result<(), Error> {
+		if !self.trie_pending { return Ok(()) }
+
+		try!(self.state.finish_trie(manifest.state_root));
+
+		self.block_chunks_left = manifest.block_hashes.iter().cloned().collect();
+		self.final_state_root = manifest.state_root;
+		self.blocks.set_best_number(manifest.block_number);
+		self.manifest = manifest;
+		self.trie_pending = false;
+		Ok(())
+	}
+
 	// finish up restoration.
 	fn finalize(self) -> Result<(), Error> {
 		use util::trie::TrieError;
@@ -172,7 +199,7 @@ impl Restoration {
 
 	// is everything done?
 	fn is_done(&self) -> bool {
-		self.block_chunks_left.is_empty() && self.state_chunks_left.is_empty()
+		!self.trie_pending && self.block_chunks_left.is_empty() && self.state_chunks_left.is_empty()
 	}
 }
 
@@ -212,6 +239,8 @@ pub struct Service {
 	genesis_block: Bytes,
 	state_chunks: AtomicUsize,
 	block_chunks: AtomicUsize,
+	/// Trie node batches sent to the io channel and not yet written.
+	trie_nodes_queued: AtomicUsize,
 	db_restore: Arc<DatabaseRestore>,
 	progress: super::Progress,
 	taking_snapshot: AtomicBool,
@@ -232,6 +261,7 @@ impl Service {
 			genesis_block: params.genesis_block,
 			state_chunks: AtomicUsize::new(0),
 			block_chunks: AtomicUsize::new(0),
+			trie_nodes_queued: AtomicUsize::new(0),
 			db_restore: params.db_restore,
 			progress: Default::default(),
 			taking_snapshot: AtomicBool::new(false),
@@ -379,6 +409,25 @@ impl Service {
 	/// Initialize the restoration synchronously.
 	/// The recover flag indicates whether to recover the restored snapshot.
 	pub fn init_restore(&self, manifest: ManifestData, recover: bool) -> Result<(), Error> {
+		self.init_restoration(manifest, recover, false)
+	}
+
+	/// Initialize a restoration from raw trie nodes synchronously.
+	/// The target block is only known once all nodes are fed, so an empty manifest
+	/// is used until then.
+	pub fn init_trie_restore(&self) -> Result<(), Error> {
+		let manifest = ManifestData {
+			state_hashes: Vec::new(),
+			block_hashes: Vec::new(),
+			state_root: SHA3_NULL_RLP,
+			block_number: 0,
+			block_hash: H256::default(),
+		};
+
+		self.init_restoration(manifest, false, true)
+	}
+
+	fn init_restoration(&self, manifest: ManifestData, recover: bool, trie: bool) -> Result<(), Error> {
 		let rest_dir = self.restoration_dir();
 
 		let mut res = self.restoration.lock();
@@ -413,6 +462,7 @@ impl Service {
 			writer: writer,
 			genesis: &self.genesis_block,
 			guard: Guard::new(rest_dir),
+			trie: trie,
 		};
 
 		*res = Some(try!(Restoration::new(params)));
@@ -522,6 +572,53 @@ impl Service {
 			}
 		}
 	}
+
+	/// Feed raw trie nodes to be processed synchronously. no-op if not restoring from trie nodes.
+	pub fn feed_trie_nodes(&self, address_hash: Option<H256>, nodes: &[Bytes]) {
+		let res = match *self.restoration.lock() {
+			Some(ref mut rest) => rest.feed_trie_nodes(address_hash, nodes),
+			None => Ok(()),
+		};
+
+		if let Err(e) = res {
+			warn!("Encountered error during state restoration: {}", e);
+			*self.restoration.lock() = None;
+			*self.status.lock() = RestorationStatus::Failed;
+			let _ = fs::remove_dir_all(self.restoration_dir());
+		}
+	}
+
+	/// Feed raw trie nodes queued by `restore_trie_nodes`.
+	pub fn feed_queued_trie_nodes(&self, address_hash: Option<H256>, nodes: &[Bytes]) {
+		self.feed_trie_nodes(address_hash, nodes);
+		self.trie_nodes_queued.fetch_sub(1, Ordering::SeqCst);
+	}
+
+	/// Finish a restoration from trie nodes at the block given by the manifest
+	/// and feed the block chunk holding that block synchronously.
+	/// Put back on the io channel while trie nodes queued before it are still being written.
+	pub fn finish_trie_restore(&self, manifest: ManifestData, block_chunk: &[u8]) {
+		if self.trie_nodes_queued.load(Ordering::SeqCst) != 0 {
+			self.io_channel.send(ClientIoMessage::CompleteTrieRestoration(manifest, block_chunk.to_vec()))
+				.expect("snapshot service and io service are kept alive by client service; qed");
+			return;
+		}
+
+		let res = match *self.restoration.lock() {
+			Some(ref mut rest) => rest.complete_trie(manifest),
+			None => Ok(()),
+		};
+
+		match res {
+			Ok(()) => self.feed_block_chunk(block_chunk.sha3(), block_chunk),
+			Err(e) => {
+				warn!("Encountered error during state restoration: {}", e);
+				*self.restoration.lock() = None;
+				*self.status.lock() = RestorationStatus::Failed;
+				let _ = fs::remove_dir_all(self.restoration_dir());
+			}
+		}
+	}
 }
 
 impl SnapshotService for Service {
@@ -562,6 +659,23 @@ impl SnapshotService for Service {
 		self.io_channel.send(ClientIoMessage::FeedBlockChunk(hash, chunk))
 			.expect("snapshot service and io service are kept alive by client service; qed");
 	}
+
+	fn begin_trie_restore(&self) {
+		if let Err(e) = self.init_trie_restore() {
+			warn!("Failed to initialize state trie restoration: {}", e);
+		}
+	}
+
+	fn restore_trie_nodes(&self, address_hash: Option<H256>, nodes: Vec<Bytes>) {
+		self.trie_nodes_queued.fetch_add(1, Ordering::SeqCst);
+		self.io_channel.send(ClientIoMessage::FeedTrieNodes(address_hash, nodes))
+			.expect("snapshot service and io service are kept alive by client service; qed");
+	}
+
+	fn complete_trie_restore(&self, manifest: ManifestData, block_chunk: Bytes) {
+		self.io_channel.send(ClientIoMessage::CompleteTrieRestoration(manifest, block_chunk))
+			.expect("snapshot service and io service are kept alive by client service; qed");
+	}
 }
 
 impl Drop for Service {
@@ -628,5 +742,8 @@ mod tests {
 		service.abort_restore();
 		service.restore_state_chunk(Default::default(), vec![]);
 		service.restore_block_chunk(Default::default(), vec![]);
+		service.begin_trie_restore();
+		service.restore_trie_nodes(None, vec![vec![0x80]]);
+		service.abort_restore();
 	}
 }
\ No newline at end of file
@@ -48,6 +48,22 @@ pub trait SnapshotService : Sync + Send {
 	/// Feed a raw block chunk to the service to be processed asynchronously.
 	/// no-op if currently restoring.
 	fn restore_block_chunk(&self, hash: H256, chunk: Bytes);
+
+	/// Begin restoration of the state from raw trie nodes instead of state chunks.
+	/// If restoration in-progress, this will reset it.
+	/// The target block is only supplied once all the nodes are in, see `complete_trie_restore`.
+	fn begin_trie_restore(&self);
+
+	/// Feed raw trie nodes to the service. `address_hash` is given for the storage
+	/// trie nodes and code of that account and is `None` for account trie nodes.
+	/// Nodes are written asynchronously, but before a later `complete_trie_restore` is processed.
+	/// no-op if not currently restoring from trie nodes.
+	fn restore_trie_nodes(&self, address_hash: Option<H256>, nodes: Vec<Bytes>);
+
+	/// Finish a trie node restoration at the block described by the manifest.
+	/// The manifest is expected to contain no state chunks and the single block
+	/// chunk given here, which holds the block itself.
+	fn complete_trie_restore(&self, manifest: ManifestData, block_chunk: Bytes);
 }
 
 impl IpcConfig for SnapshotService { }
@@ -19,6 +19,7 @@
 use common::*;
 use engines::{Engine, NullEngine, InstantSeal, BasicAuthority};
 use pod_state::*;
+use pod_account::PodAccount;
 use account_db::*;
 use state_db::StateDB;
 use super::genesis::Genesis;
@@ -43,6 +44,13 @@ pub struct CommonParams {
 	pub min_gas_limit: U256,
 	/// Fork block to check.
 	pub fork_block: Option<(BlockNumber, H256)>,
+	/// Contract deciding which transaction types each sender may use, if any.
+	/// It implements `allowedTxTypes(address) returns (uint32)`, see `tx_permission::types`.
+	pub transaction_permission_contract: Option<Address>,
+	/// Contract deciding which nodes may connect, if any.
+	pub node_permission_contract: Option<Address>,
+	/// Ids of the only nodes allowed to connect, if restricted.
+	pub allowed_nodes: Option<HashSet<H512>>,
 }
 
 impl From<ethjson::spec::Params> for CommonParams {
@@ -54,6 +62,9 @@ impl From<ethjson::spec::Params> for CommonParams {
 			subprotocol_name: p.subprotocol_name.unwrap_or_else(|| "eth".to_owned()),
 			min_gas_limit: p.min_gas_limit.into(),
 			fork_block: if let (Some(n), Some(h)) = (p.fork_block, p.fork_hash) { Some((n.into(), h.into())) } else { None },
+			transaction_permission_contract: p.transaction_permission_contract.map(Into::into),
+			node_permission_contract: p.node_permission_contract.map(Into::into),
+			allowed_nodes: p.allowed_nodes.map(|nodes| nodes.into_iter().map(Into::into).collect()),
 		}
 	}
 }
@@ -228,6 +239,21 @@ impl Spec {
 		*self.state_root_memo.write() = None;
 	}
 
+	/// Add the given accounts to the genesis state, each with `balance`.
+	/// Accounts which are already in the genesis state are overwritten.
+	pub fn prefund_accounts(&mut self, accounts: &[Address], balance: U256) {
+		let mut state = self.genesis_state.get().clone();
+		for address in accounts {
+			state.insert(address.clone(), PodAccount {
+				balance: balance,
+				nonce: self.params.account_start_nonce,
+				code: Some(Vec::new()),
+				storage: BTreeMap::new(),
+			});
+		}
+		self.set_genesis_state(PodState::from(state));
+	}
+
 	/// Returns `false` if the memoized state root is invalid. `true` otherwise.
 	pub fn is_state_root_valid(&self) -> bool {
 		self.state_root_memo.read().clone().map_or(true, |sr| sr == self.genesis_state.root())
@@ -263,6 +289,88 @@ impl Spec {
 		}
 	}
 
+	/// Loads spec from json file, checking builtins, engine parameters and the genesis
+	/// block for errors which would otherwise only surface once the node is running.
+	pub fn load_validated<R>(reader: R) -> Result<Self, String> where R: Read {
+		let spec = try!(ethjson::spec::Spec::load(reader).map_err(|e| format!("Spec json is invalid: {}", e)));
+		try!(Self::validate_json(&spec));
+		let spec: Spec = spec.into();
+		try!(spec.validate_genesis());
+		Ok(spec)
+	}
+
+	fn validate_json(spec: &ethjson::spec::Spec) -> Result<(), String> {
+		let non_zero = |value: &ethjson::uint::Uint, name: &str| match value.0.is_zero() {
+			true => Err(format!("Engine parameter {} must not be zero", name)),
+			false => Ok(()),
+		};
+
+		for (address, builtin) in spec.accounts.builtins() {
+			if !::builtin::is_builtin_name(&builtin.name) {
+				return Err(format!("Unknown builtin {} at {}", builtin.name, address.0));
+			}
+		}
+
+		match spec.engine {
+			ethjson::spec::Engine::Ethash(ref ethash) => {
+				let params = &ethash.params;
+				try!(non_zero(&params.gas_limit_bound_divisor, "gasLimitBoundDivisor"));
+				try!(non_zero(&params.minimum_difficulty, "minimumDifficulty"));
+				try!(non_zero(&params.difficulty_bound_divisor, "difficultyBoundDivisor"));
+				try!(non_zero(&params.duration_limit, "durationLimit"));
+				if let Some(ref divisor) = params.difficulty_increment_divisor {
+					try!(non_zero(divisor, "difficultyIncrementDivisor"));
+				}
+				if let Some(ref divisor) = params.difficulty_hardfork_bound_divisor {
+					try!(non_zero(divisor, "difficultyHardforkBoundDivisor"));
+				}
+			},
+			ethjson::spec::Engine::BasicAuthority(ref basic_authority) => {
+				let params = &basic_authority.params;
+				try!(non_zero(&params.gas_limit_bound_divisor, "gasLimitBoundDivisor"));
+				try!(non_zero(&params.duration_limit, "durationLimit"));
+				if params.authorities.is_empty() {
+					return Err("BasicAuthority engine has no authorities".into());
+				}
+			},
+			ethjson::spec::Engine::Null | ethjson::spec::Engine::InstantSeal => {},
+		}
+		Ok(())
+	}
+
+	fn validate_genesis(&self) -> Result<(), String> {
+		if self.gas_used > self.gas_limit {
+			return Err(format!("Genesis gas used {} exceeds the gas limit {}", self.gas_used, self.gas_limit));
+		}
+		if self.gas_limit < self.params.min_gas_limit {
+			return Err(format!("Genesis gas limit {} is below the minimum gas limit {}", self.gas_limit, self.params.min_gas_limit));
+		}
+		if self.extra_data.len() > self.params.maximum_extra_data_size {
+			return Err(format!("Genesis extra data is {} bytes long, the maximum is {}", self.extra_data.len(), self.params.maximum_extra_data_size));
+		}
+		if self.engine.seal_fields() != 0 && self.seal_fields != self.engine.seal_fields() {
+			return Err(format!("Genesis seal has {} fields, the {} engine expects {}", self.seal_fields, self.engine.name(), self.engine.seal_fields()));
+		}
+		if self.transactions_root != SHA3_NULL_RLP || self.receipts_root != SHA3_NULL_RLP {
+			return Err("Genesis transactions and receipts roots must be empty".into());
+		}
+
+		let state_root = self.genesis_state.root();
+		match *self.state_root_memo.read() {
+			Some(ref declared) if *declared != state_root => Err(format!("Genesis state root {} does not match the accounts, which have root {}", declared, state_root)),
+			_ => Ok(()),
+		}
+	}
+
+	/// Loads a bundled chain preset by name (e.g. `ropsten`), falling back to reading the given path as a JSON spec file.
+	pub fn load_named(name_or_path: &str) -> Result<Self, String> {
+		if let Some(spec) = ethereum::preset(name_or_path) {
+			return Ok(spec);
+		}
+		let file = try!(fs::File::open(name_or_path).map_err(|e| format!("Could not load specification file {}: {}", name_or_path, e)));
+		Spec::load(file)
+	}
+
 	/// Create a new Spec which conforms to the Frontier-era Morden chain except that it's a NullEngine consensus.
 	pub fn new_test() -> Self {
 		Spec::load(include_bytes!("../../res/null_morden.json") as &[u8]).expect("null_morden.json is invalid")
@@ -277,6 +385,11 @@ impl Spec {
 	pub fn new_test_instant() -> Self {
 		Spec::load(include_bytes!("../../res/instant_seal.json") as &[u8]).expect("instant_seal.json is invalid")
 	}
+
+	/// Create a new Spec of the local development chain, an InstantSeal chain without prefunded accounts.
+	pub fn new_dev() -> Self {
+		Spec::load(include_bytes!("../../res/dev.json") as &[u8]).expect("dev.json is invalid")
+	}
 }
 
 #[cfg(test)]
@@ -293,6 +406,16 @@ mod tests {
 		assert!(Spec::load(&[] as &[u8]).is_err());
 	}
 
+	#[test]
+	fn test_load_validated() {
+		let json = include_str!("../../res/null_morden.json");
+		let spec = Spec::load_validated(json.as_bytes()).unwrap();
+		assert_eq!(spec.genesis_header().hash(), Spec::new_test().genesis_header().hash());
+
+		let unknown_builtin = json.replace("\"ripemd160\"", "\"ripemd\"");
+		assert!(Spec::load_validated(unknown_builtin.as_bytes()).unwrap_err().contains("Unknown builtin ripemd"));
+	}
+
 	#[test]
 	fn test_chain() {
 		let test_spec = Spec::new_test();
@@ -301,4 +424,15 @@ mod tests {
 		let genesis = test_spec.genesis_block();
 		assert_eq!(BlockView::new(&genesis).header_view().sha3(), H256::from_str("0cd786a2425d16f152c658316c423e6ce1181e15c3295826d7c9904cba9ce303").unwrap());
 	}
+
+	#[test]
+	fn should_prefund_dev_accounts() {
+		let mut spec = Spec::new_dev();
+		let root = spec.state_root();
+		let address = Address::from(1000);
+
+		spec.prefund_accounts(&[address], 1000.into());
+		assert!(spec.state_root() != root);
+		assert_eq!(spec.genesis_state.get()[&address].balance, 1000.into());
+	}
 }
@@ -22,6 +22,7 @@ use rlp::*;
 use lru_cache::LruCache;
 
 use std::cell::{RefCell, Cell};
+use std::mem;
 
 const STORAGE_CACHE_ITEMS: usize = 8192;
 
@@ -228,6 +229,15 @@ impl Account {
 		self.code_size.clone()
 	}
 
+	/// Approximate memory used by the account, including cached storage and code.
+	pub fn mem_used(&self) -> usize {
+		// every storage entry is a key-value pair plus the hash table slot and, in the LRU, two links.
+		let storage_entry = 2 * mem::size_of::<H256>() + 3 * mem::size_of::<usize>();
+		mem::size_of::<Account>()
+			+ (self.storage_cache.borrow().len() + self.storage_changes.len()) * storage_entry
+			+ self.code_cache.len()
+	}
+
 	#[cfg(test)]
 	/// Provide a byte array which hashes to the `code_hash`. returns the hash as a result.
 	pub fn note_code(&mut self, code: Bytes) -> Result<(), H256> {
@@ -179,12 +179,24 @@ impl AccountEntry {
 /// Snapshot can be discateded with `discard_snapshot`. All of the orignal
 /// backed-up values are moved into a parent snapshot (if any).
 ///
+/// State checkpoints.
+///
+/// A checkpoint is a snapshot which survives `commit`, so whole transactions
+/// can be applied speculatively and reverted with `revert_to_checkpoint`.
+/// Creating a checkpoint commits the cache, so the snapshot only has to hold
+/// accounts modified afterwards together with the state root to go back to.
+/// Trie nodes written to the `StateDB` overlay since then are undone on revert.
+/// Snapshots created while a checkpoint is active must be reverted or
+/// discarded before the next commit.
+///
 pub struct State {
 	db: StateDB,
 	root: H256,
 	cache: RefCell<HashMap<Address, AccountEntry>>,
 	// The original account is preserved in
 	snapshots: RefCell<Vec<HashMap<Address, Option<AccountEntry>>>>,
+	// State roots of active checkpoints. Checkpoints occupy the bottom of `snapshots`.
+	checkpoints: Vec<H256>,
 	account_start_nonce: U256,
 	factories: Factories,
 }
@@ -214,6 +226,7 @@ impl State {
 			root: root,
 			cache: RefCell::new(HashMap::new()),
 			snapshots: RefCell::new(Vec::new()),
+			checkpoints: Vec::new(),
 			account_start_nonce: account_start_nonce,
 			factories: factories,
 		}
@@ -230,6 +243,7 @@ impl State {
 			root: root,
 			cache: RefCell::new(HashMap::new()),
 			snapshots: RefCell::new(Vec::new()),
+			checkpoints: Vec::new(),
 			account_start_nonce: account_start_nonce,
 			factories: factories
 		};
@@ -291,6 +305,47 @@ impl State {
 		}
 	}
 
+	/// Commit pending changes and create a checkpoint which can be reverted to
+	/// even after further transactions are applied and committed.
+	pub fn checkpoint(&mut self) -> Result<(), Error> {
+		try!(self.commit());
+		self.snapshots.borrow_mut().push(HashMap::new());
+		self.checkpoints.push(self.root.clone());
+		self.db.checkpoint();
+		Ok(())
+	}
+
+	/// Drop the last checkpoint, keeping all changes made since it was created.
+	pub fn discard_checkpoint(&mut self) {
+		assert_eq!(self.snapshots.borrow().len(), self.checkpoints.len());
+		if self.checkpoints.pop().is_some() {
+			self.discard_snapshot();
+			self.db.discard_checkpoint();
+		}
+	}
+
+	/// Revert all changes made since the last checkpoint, committed or not, and drop the checkpoint.
+	pub fn revert_to_checkpoint(&mut self) {
+		assert_eq!(self.snapshots.borrow().len(), self.checkpoints.len());
+		let root = match self.checkpoints.pop() {
+			Some(root) => root,
+			None => return,
+		};
+		if let Some(mut checkpoint) = self.snapshots.borrow_mut().pop() {
+			let mut cache = self.cache.borrow_mut();
+			for (k, v) in checkpoint.drain() {
+				// Storage cached since the checkpoint may hold committed values
+				// which are being reverted, so backed-up entries are restored as a whole.
+				match v {
+					Some(v) => { cache.insert(k, v); },
+					None => { cache.remove(&k); },
+				}
+			}
+		}
+		self.db.revert_to_checkpoint();
+		self.root = root;
+	}
+
 	fn insert_cache(&self, address: &Address, account: AccountEntry) {
 		// Dirty account which is not in the cache means this is a new account.
 		// It goes directly into the snapshot as there's nothing to rever to.
@@ -355,13 +410,18 @@ impl State {
 			|a| a.as_ref().map_or(self.account_start_nonce, |account| *account.nonce()))
 	}
 
+	/// Get the storage root of account `a`.
+	pub fn storage_root(&self, a: &Address) -> Option<H256> {
+		self.ensure_cached(a, RequireCache::None,
+			|a| a.as_ref().and_then(|account| account.storage_root().cloned()))
+	}
+
 	/// Mutate storage of account `address` so that it is `value` for `key`.
 	pub fn storage_at(&self, address: &Address, key: &H256) -> H256 {
 		// Storage key search and update works like this:
 		// 1. If there's an entry for the account in the local cache check for the key and return it if found.
 		// 2. If there's an entry for the account in the global cache check for the key or load it into that account.
 		// 3. If account is missing in the global cache load it into the local cache and cache the key there.
-
 		// check local cache first without updating
 		{
 			let local_cache = self.cache.borrow_mut();
@@ -544,7 +604,7 @@ impl State {
 
 	/// Commits our cached account changes into the trie.
 	pub fn commit(&mut self) -> Result<(), Error> {
-		assert!(self.snapshots.borrow().is_empty());
+		assert_eq!(self.snapshots.borrow().len(), self.checkpoints.len());
 		Self::commit_into(&self.factories, &mut self.db, &mut self.root, &mut *self.cache.borrow_mut())
 	}
 
@@ -553,8 +613,6 @@ impl State {
 		self.cache.borrow_mut().clear();
 	}
 
-	#[cfg(test)]
-	#[cfg(feature = "json-tests")]
 	/// Populate the state from `accounts`.
 	pub fn populate_from(&mut self, accounts: PodState) {
 		assert!(self.snapshots.borrow().is_empty());
@@ -566,7 +624,7 @@ impl State {
 
 	/// Populate a PodAccount map from this state.
 	pub fn to_pod(&self) -> PodState {
-		assert!(self.snapshots.borrow().is_empty());
+		assert_eq!(self.snapshots.borrow().len(), self.checkpoints.len());
 		// TODO: handle database rather than just the cache.
 		// will need fat db.
 		PodState::from(self.cache.borrow().iter().fold(BTreeMap::new(), |mut m, (add, opt)| {
@@ -730,12 +788,17 @@ impl Clone for State {
 			}
 			cache
 		};
+		// keep the checkpoints, so a clone can be reverted just like the original.
+		let snapshots = self.snapshots.borrow().iter().take(self.checkpoints.len())
+			.map(|checkpoint| checkpoint.iter().map(|(k, v)| (k.clone(), v.as_ref().map(AccountEntry::clone_dirty))).collect())
+			.collect();
 
 		State {
 			db: self.db.boxed_clone(),
 			root: self.root.clone(),
 			cache: RefCell::new(cache),
-			snapshots: RefCell::new(Vec::new()),
+			snapshots: RefCell::new(snapshots),
+			checkpoints: self.checkpoints.clone(),
 			account_start_nonce: self.account_start_nonce.clone(),
 			factories: self.factories.clone(),
 		}
@@ -747,6 +810,7 @@ mod tests {
 
 use std::sync::Arc;
 use std::str::FromStr;
+use std::collections::HashMap;
 use rustc_serialize::hex::FromHex;
 use super::*;
 use util::{U256, H256, FixedHash, Address, Hashable};
@@ -1789,6 +1853,75 @@ fn snapshot_nested() {
 	assert_eq!(state.balance(&a), U256::from(0));
 }
 
+#[test]
+fn checkpoint_reverts_committed_changes() {
+	let mut state_result = get_temp_state();
+	let mut state = state_result.reference_mut();
+	let a = Address::zero();
+	let b: Address = 1u64.into();
+	state.add_balance(&a, &U256::from(69u64));
+	state.set_storage(&a, H256::from(&U256::from(1u64)), H256::from(&U256::from(42u64)));
+	state.checkpoint().unwrap();
+	let root = state.root().clone();
+
+	state.add_balance(&a, &U256::from(1u64));
+	state.set_storage(&a, H256::from(&U256::from(1u64)), H256::from(&U256::from(43u64)));
+	state.add_balance(&b, &U256::from(5u64));
+	state.commit().unwrap();
+	assert!(state.root() != &root);
+	assert_eq!(state.storage_at(&a, &H256::from(&U256::from(1u64))), H256::from(&U256::from(43u64)));
+
+	let mut cloned = state.clone();
+	state.revert_to_checkpoint();
+	assert_eq!(state.root(), &root);
+	assert_eq!(state.balance(&a), U256::from(69u64));
+	assert_eq!(state.balance(&b), U256::zero());
+	assert_eq!(state.storage_at(&a, &H256::from(&U256::from(1u64))), H256::from(&U256::from(42u64)));
+
+	cloned.revert_to_checkpoint();
+	assert_eq!(cloned.root(), &root);
+	assert_eq!(cloned.balance(&a), U256::from(69u64));
+	assert_eq!(cloned.balance(&b), U256::zero());
+}
+
+#[test]
+fn checkpoint_reverts_overlay() {
+	let mut state_result = get_temp_state();
+	let mut state = state_result.reference_mut();
+	let a = Address::zero();
+	fn live_keys(db: &StateDB) -> HashMap<H256, i32> {
+		db.as_hashdb().keys().into_iter().filter(|&(_, rc)| rc != 0).collect()
+	}
+	state.add_balance(&a, &U256::from(69u64));
+	state.checkpoint().unwrap();
+	let keys = live_keys(&state.db);
+
+	state.add_balance(&a, &U256::from(1u64));
+	state.commit().unwrap();
+	let root = state.root().clone();
+	assert!(state.db.as_hashdb().contains(&root));
+
+	state.revert_to_checkpoint();
+	assert!(!state.db.as_hashdb().contains(&root));
+	assert_eq!(live_keys(&state.db), keys);
+}
+
+#[test]
+fn checkpoint_discard_keeps_changes() {
+	let mut state_result = get_temp_state();
+	let mut state = state_result.reference_mut();
+	let a = Address::zero();
+	state.checkpoint().unwrap();
+	state.add_balance(&a, &U256::from(69u64));
+	state.checkpoint().unwrap();
+	state.add_balance(&a, &U256::from(1u64));
+	state.commit().unwrap();
+	state.discard_checkpoint();
+	assert_eq!(state.balance(&a), U256::from(70u64));
+	state.revert_to_checkpoint();
+	assert_eq!(state.balance(&a), U256::zero());
+}
+
 #[test]
 fn create_empty() {
 	let mut state_result = get_temp_state();
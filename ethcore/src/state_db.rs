@@ -14,33 +14,148 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{VecDeque, HashSet};
+use std::collections::{VecDeque, HashSet, HashMap};
+use std::cmp::max;
+use std::mem;
 use lru_cache::LruCache;
 use util::journaldb::JournalDB;
-use util::hash::{H256};
+use util::hash::{H256, FixedHash};
+use util::sha3::SHA3_EMPTY;
 use util::hashdb::HashDB;
+use util::trie::{self, TrieDB, Trie};
 use state::Account;
 use header::BlockNumber;
-use util::{Arc, Address, Database, DBTransaction, UtilError, Mutex, Hashable};
+use util::{Arc, Address, Bytes, Database, DBTransaction, UtilError, Mutex, Hashable};
+use util::metrics::{self, Counter};
 use bloom_journal::{Bloom, BloomJournal};
 use db::COL_ACCOUNT_BLOOM;
 use byteorder::{LittleEndian, ByteOrder};
 
-const STATE_CACHE_ITEMS: usize = 256000;
+/// Number of accounts kept in the state cache unless configured otherwise.
+pub const STATE_CACHE_ITEMS: usize = 256000;
 const STATE_CACHE_BLOCKS: usize = 8;
+/// Number of most accessed contracts kept in the state cache regardless of its size.
+pub const HOT_CONTRACTS: usize = 64;
 
 pub const ACCOUNT_BLOOM_SPACE: usize = 1048576;
 pub const DEFAULT_ACCOUNT_PRESET: usize = 1000000;
+/// Saturation above which the account bloom is rebuilt with a larger size.
+pub const MAX_ACCOUNT_BLOOM_SATURATION: f64 = 0.5;
 
 pub const ACCOUNT_BLOOM_HASHCOUNT_KEY: &'static [u8] = b"account_hash_count";
+/// Key of the bloom size in bytes. Databases without it use `ACCOUNT_BLOOM_SPACE`.
+pub const ACCOUNT_BLOOM_SPACE_KEY: &'static [u8] = b"account_bloom_space";
+/// Key of the number and hash of the block whose state the bloom was last rebuilt from.
+pub const ACCOUNT_BLOOM_BASE_KEY: &'static [u8] = b"account_bloom_base";
+
+/// State database configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateDbConfig {
+	/// Size of the account bloom in bytes for new databases.
+	pub account_bloom_space: usize,
+	/// Number of accounts the account bloom of new databases is tuned for.
+	pub account_bloom_items: usize,
+	/// Saturation above which the account bloom is rebuilt with a larger size.
+	pub max_bloom_saturation: f64,
+	/// Number of most accessed contracts pinned in the state cache.
+	pub hot_contracts: usize,
+}
+
+impl Default for StateDbConfig {
+	fn default() -> Self {
+		StateDbConfig {
+			account_bloom_space: ACCOUNT_BLOOM_SPACE,
+			account_bloom_items: DEFAULT_ACCOUNT_PRESET,
+			max_bloom_saturation: MAX_ACCOUNT_BLOOM_SATURATION,
+			hot_contracts: HOT_CONTRACTS,
+		}
+	}
+}
+
+lazy_static! {
+	static ref CACHE_HITS: Arc<Counter> = metrics::registry().counter("parity_state_cache_hits_total", "Account lookups served by the state cache.");
+	static ref CACHE_MISSES: Arc<Counter> = metrics::registry().counter("parity_state_cache_misses_total", "Account lookups not found in the state cache.");
+}
 
 /// Shared canonical state cache.
 struct AccountCache {
 	/// DB Account cache. `None` indicates that account is known to be missing.
 	accounts: LruCache<Address, Option<Account>>,
+	/// Approximate memory used by the entries of `accounts`.
+	accounts_size: usize,
 	/// Information on the modifications in recently committed blocks; specifically which addresses
 	/// changed in which block. Ordered by block number.
 	modifications: VecDeque<BlockChanges>,
+	/// Most accessed contracts, kept out of the LRU so that contracts touched in every
+	/// block are not evicted by the accounts of a single big block.
+	hot: HashMap<Address, Account>,
+	/// Maximal number of hot contracts.
+	hot_limit: usize,
+	/// Number of cache lookups per address, halved on every canonical block.
+	accesses: HashMap<Address, usize>,
+}
+
+impl AccountCache {
+	/// Approximate memory used by a single entry of `accounts`.
+	fn entry_size(account: &Option<Account>) -> usize {
+		account.as_ref().map_or(mem::size_of::<Option<Account>>(), Account::mem_used)
+	}
+
+	/// Insert an account, evicting the least recently used one if the cache is full.
+	fn insert_account(&mut self, address: Address, account: Option<Account>) {
+		if !self.accounts.contains_key(&address) && self.accounts.len() >= self.accounts.capacity() {
+			self.evict_lru();
+		}
+		self.accounts_size += Self::entry_size(&account);
+		if let Some(old) = self.accounts.insert(address, account) {
+			self.accounts_size -= Self::entry_size(&old);
+		}
+	}
+
+	/// Drop the least recently used account. Returns `false` if the cache is empty.
+	fn evict_lru(&mut self) -> bool {
+		let address = match self.accounts.iter().next() {
+			Some((address, _)) => address.clone(),
+			None => return false,
+		};
+		if let Some(old) = self.accounts.remove(&address) {
+			self.accounts_size -= Self::entry_size(&old);
+		}
+		true
+	}
+
+	/// Note a lookup of `address`.
+	fn note_access(&mut self, address: &Address) {
+		if self.hot_limit > 0 {
+			*self.accesses.entry(address.clone()).or_insert(0) += 1;
+		}
+	}
+
+	/// Pin the most accessed contracts present in the cache and decay the access counts.
+	fn update_hot(&mut self) {
+		let mut candidates: Vec<_> = self.accesses.iter().map(|(a, n)| (*n, a.clone())).collect();
+		candidates.sort_by(|a, b| b.0.cmp(&a.0));
+
+		let mut hot = HashMap::with_capacity(self.hot_limit);
+		for (_, address) in candidates {
+			if hot.len() == self.hot_limit {
+				break;
+			}
+			let account = match self.hot.remove(&address) {
+				Some(account) => Some(account),
+				None => match self.accounts.get_mut(&address) {
+					Some(&mut Some(ref account)) => Some(account.clone_basic()),
+					_ => None,
+				},
+			};
+			if let Some(account) = account.into_iter().find(|a| a.code_hash() != SHA3_EMPTY) {
+				hot.insert(address, account);
+			}
+		}
+		self.hot = hot;
+
+		self.accesses = self.accesses.drain().filter(|&(_, count)| count > 1).map(|(a, count)| (a, count / 2)).collect();
+	}
 }
 
 /// Buffered account cache item.
@@ -90,8 +205,10 @@ pub struct StateDB {
 	account_cache: Arc<Mutex<AccountCache>>,
 	/// Local dirty cache.
 	local_cache: Vec<CacheQueueItem>,
-	/// Shared account bloom. Does not handle chain reorganizations.
-	account_bloom: Arc<Mutex<Bloom>>,
+	/// Shared account bloom. Only covers the descendants of its base block, see `account_bloom_base`.
+	account_bloom: Arc<Mutex<AccountBloom>>,
+	/// Whether missing accounts may be ruled out with the account bloom.
+	use_account_bloom: bool,
 	/// Hash of the block on top of which this instance was created or
 	/// `None` if cache is disabled
 	parent_hash: Option<H256>,
@@ -99,25 +216,98 @@ pub struct StateDB {
 	commit_hash: Option<H256>,
 	/// Number of the committing block or `None` if not committed yet.
 	commit_number: Option<BlockNumber>,
+	/// Overlay changes made since each active checkpoint, oldest checkpoint first.
+	checkpoints: Vec<Vec<OverlayChange>>,
+}
+
+/// Account bloom shared between all clones of a `StateDB`.
+struct AccountBloom {
+	bloom: Bloom,
+	/// Block whose state the bloom was last rebuilt from, `None` if it was never rebuilt.
+	/// Accounts which only exist on other branches may be missing from the bloom.
+	base: Option<(BlockNumber, H256)>,
+	/// Hashes of the accounts noted since a rebuild started, `None` if no rebuild is running.
+	noted: Option<Vec<H256>>,
+	/// Size in bytes of a replaced bloom whose parts are yet to be deleted from the database.
+	replaced_space: Option<usize>,
+}
+
+/// Account bloom rebuild, started by `StateDB::begin_account_bloom_rebuild`.
+pub struct AccountBloomRebuild {
+	db: StateDB,
+	root: H256,
+	base: (BlockNumber, H256),
+	space: usize,
+	items: usize,
+}
+
+impl AccountBloomRebuild {
+	/// Build the new bloom in a single walk of the state trie and replace the shared bloom with it.
+	/// Accounts noted during the walk are added as well. The new bloom is written to the database
+	/// by the next `StateDB::commit`. Returns the number of accounts in the state.
+	pub fn run(self) -> trie::Result<usize> {
+		let mut bloom = Bloom::new(self.space, self.items);
+		let filled = self.fill(&mut bloom);
+
+		let mut shared = self.db.account_bloom.lock();
+		let noted = shared.noted.take().expect("noted is set while a rebuild runs; qed");
+		let accounts = try!(filled);
+		for hash in noted {
+			bloom.set(&*hash);
+		}
+		let old_space = (shared.bloom.number_of_bits() / 8) as usize;
+		shared.replaced_space = Some(shared.replaced_space.map_or(old_space, |space| max(space, old_space)));
+		shared.bloom = bloom;
+		shared.base = Some(self.base);
+		Ok(accounts)
+	}
+
+	fn fill(&self, bloom: &mut Bloom) -> trie::Result<usize> {
+		let trie = try!(TrieDB::new(self.db.as_hashdb(), &self.root));
+		let mut accounts = 0;
+		for item in try!(trie.iter()) {
+			// trie keys are the hashes of account addresses.
+			let (key, _) = try!(item);
+			bloom.set(&key[..]);
+			accounts += 1;
+		}
+		Ok(accounts)
+	}
+}
+
+/// Journal overlay change which can be undone when reverting to a checkpoint.
+#[derive(Clone)]
+enum OverlayChange {
+	/// A node was inserted.
+	Inserted(H256),
+	/// A node with the given value was removed.
+	Removed(H256, Bytes),
 }
 
 impl StateDB {
 	/// Loads accounts bloom from the database
 	/// This bloom is used to handle request for the non-existant account fast
 	pub fn load_bloom(db: &Database) -> Bloom {
+		Self::load_bloom_with_config(db, &StateDbConfig::default())
+	}
+
+	/// Loads accounts bloom from the database, creating one as configured if there is none.
+	pub fn load_bloom_with_config(db: &Database, config: &StateDbConfig) -> Bloom {
 		let hash_count_entry = db.get(COL_ACCOUNT_BLOOM, ACCOUNT_BLOOM_HASHCOUNT_KEY)
 			.expect("Low-level database error");
 
 		if hash_count_entry.is_none() {
-			return Bloom::new(ACCOUNT_BLOOM_SPACE, DEFAULT_ACCOUNT_PRESET);
+			return Bloom::new(config.account_bloom_space, config.account_bloom_items);
 		}
 		let hash_count_bytes = hash_count_entry.unwrap();
 		assert_eq!(hash_count_bytes.len(), 1);
 		let hash_count = hash_count_bytes[0];
 
-		let mut bloom_parts = vec![0u64; ACCOUNT_BLOOM_SPACE / 8];
+		let space = db.get(COL_ACCOUNT_BLOOM, ACCOUNT_BLOOM_SPACE_KEY).expect("Low-level database error")
+			.map_or(ACCOUNT_BLOOM_SPACE, |val| LittleEndian::read_u64(&val[..]) as usize);
+		let mut bloom_parts = vec![0u64; space / 8];
 		let mut key = [0u8; 8];
-		for i in 0..ACCOUNT_BLOOM_SPACE / 8 {
+		for i in 0..space / 8 {
 			LittleEndian::write_u64(&mut key, i as u64);
 			bloom_parts[i] = db.get(COL_ACCOUNT_BLOOM, &key).expect("low-level database error")
 				.and_then(|val| Some(LittleEndian::read_u64(&val[..])))
@@ -129,33 +319,167 @@ impl StateDB {
 		bloom
 	}
 
-	/// Create a new instance wrapping `JournalDB`
+	fn load_bloom_base(db: &Database) -> Option<(BlockNumber, H256)> {
+		db.get(COL_ACCOUNT_BLOOM, ACCOUNT_BLOOM_BASE_KEY).expect("Low-level database error")
+			.and_then(|val| match val.len() {
+				40 => Some((LittleEndian::read_u64(&val[..8]), H256::from_slice(&val[8..]))),
+				_ => None,
+			})
+	}
+
+	/// Create a new instance wrapping `JournalDB` with the default cache size.
 	pub fn new(db: Box<JournalDB>) -> StateDB {
-		let bloom = Self::load_bloom(db.backing());
+		Self::with_cache_size(db, Self::default_cache_size())
+	}
+
+	/// Create a new instance wrapping `JournalDB` with an account cache of
+	/// approximately `cache_size` bytes.
+	pub fn with_cache_size(db: Box<JournalDB>, cache_size: usize) -> StateDB {
+		Self::with_config(db, cache_size, &StateDbConfig::default())
+	}
+
+	/// Create a new instance wrapping `JournalDB` with an account cache of
+	/// approximately `cache_size` bytes and the account bloom configured by `config`.
+	pub fn with_config(db: Box<JournalDB>, cache_size: usize, config: &StateDbConfig) -> StateDB {
+		let bloom = AccountBloom {
+			bloom: Self::load_bloom_with_config(db.backing(), config),
+			base: Self::load_bloom_base(db.backing()),
+			noted: None,
+			replaced_space: None,
+		};
 		StateDB {
 			db: db,
 			account_cache: Arc::new(Mutex::new(AccountCache {
-				accounts: LruCache::new(STATE_CACHE_ITEMS),
+				accounts: LruCache::new(Self::cache_items(cache_size)),
+				accounts_size: 0,
 				modifications: VecDeque::new(),
+				hot: HashMap::new(),
+				hot_limit: config.hot_contracts,
+				accesses: HashMap::new(),
 			})),
 			local_cache: Vec::new(),
 			account_bloom: Arc::new(Mutex::new(bloom)),
+			use_account_bloom: true,
 			parent_hash: None,
 			commit_hash: None,
 			commit_number: None,
+			checkpoints: Vec::new(),
+		}
+	}
+
+	/// Size of the account cache in bytes if not configured otherwise.
+	pub fn default_cache_size() -> usize {
+		STATE_CACHE_ITEMS * mem::size_of::<Option<Account>>()
+	}
+
+	fn cache_items(cache_size: usize) -> usize {
+		max(1, cache_size / mem::size_of::<Option<Account>>())
+	}
+
+	/// Memory used by the cached accounts. The LRU part is tracked as entries are
+	/// inserted, changed and evicted; the hot contracts are few and measured on demand.
+	fn cached_accounts_size(cache: &AccountCache) -> usize {
+		cache.accounts_size + cache.hot.values().map(Account::mem_used).sum::<usize>()
+	}
+
+	/// Resize the account cache, shared between all clones of this `StateDB`.
+	/// The number of cached accounts is derived from the average measured size of the
+	/// accounts currently in the cache. Least recently used accounts are dropped if the cache shrinks.
+	pub fn set_cache_size(&self, cache_size: usize) {
+		let mut cache = self.account_cache.lock();
+		let cached = cache.accounts.len() + cache.hot.len();
+		let items = match cached {
+			0 => Self::cache_items(cache_size),
+			n => max(1, cache_size * n / max(1, Self::cached_accounts_size(&cache))),
+		};
+		if cache.accounts.capacity() != items {
+			trace!(target: "state_db", "Resizing account cache to {} items", items);
+			while cache.accounts.len() > items && cache.evict_lru() {}
+			cache.accounts.set_capacity(items);
 		}
 	}
 
 	pub fn check_account_bloom(&self, address: &Address) -> bool {
 		trace!(target: "account_bloom", "Check account bloom: {:?}", address);
+		if !self.use_account_bloom {
+			return true;
+		}
 		let bloom = self.account_bloom.lock();
-		bloom.check(&*address.sha3())
+		bloom.bloom.check(&*address.sha3())
 	}
 
 	pub fn note_account_bloom(&self, address: &Address) {
 		trace!(target: "account_bloom", "Note account bloom: {:?}", address);
 		let mut bloom = self.account_bloom.lock();
-		bloom.set(&*address.sha3());
+		let hash = address.sha3();
+		bloom.bloom.set(&*hash);
+		if let Some(ref mut noted) = bloom.noted {
+			noted.push(hash);
+		}
+	}
+
+	/// Stop ruling out accounts with the account bloom in this instance, so that they are
+	/// always looked up in the trie. For states the bloom does not cover.
+	pub fn disable_account_bloom(&mut self) {
+		self.use_account_bloom = false;
+	}
+
+	/// Fraction of the account bloom's bits which are set.
+	pub fn account_bloom_saturation(&self) -> f64 {
+		self.account_bloom.lock().bloom.saturation()
+	}
+
+	/// Number and hash of the block whose state the account bloom was last rebuilt from.
+	/// The bloom covers the states of this block and its descendants, or all states if `None`.
+	pub fn account_bloom_base(&self) -> Option<(BlockNumber, H256)> {
+		self.account_bloom.lock().base.clone()
+	}
+
+	/// Whether the account bloom is being rebuilt.
+	pub fn account_bloom_rebuilding(&self) -> bool {
+		self.account_bloom.lock().noted.is_some()
+	}
+
+	/// Start rebuilding the account bloom from the state at `root` of block `base`. The size of the
+	/// bloom is doubled if it's saturated. Accounts noted from now on are added to the new bloom,
+	/// which is built by the returned job. Returns `None` if a rebuild is running already.
+	/// The caller must keep the state at `root` from being pruned until the job is done.
+	pub fn begin_account_bloom_rebuild(&self, root: &H256, base: (BlockNumber, H256), config: &StateDbConfig) -> Option<AccountBloomRebuild> {
+		let mut shared = self.account_bloom.lock();
+		if shared.noted.is_some() {
+			return None;
+		}
+		shared.noted = Some(Vec::new());
+
+		let bits = shared.bloom.number_of_bits() as f64;
+		let saturation = shared.bloom.saturation();
+		// estimate of the number of accounts from the fraction of set bits.
+		let accounts = -bits / shared.bloom.number_of_hash_functions() as f64 * (1.0 - saturation.min(0.99)).ln();
+		let old_space = (bits / 8.0) as usize;
+		let space = match saturation > config.max_bloom_saturation {
+			true => max(config.account_bloom_space, old_space * 2),
+			false => old_space,
+		};
+		Some(AccountBloomRebuild {
+			db: self.boxed_clone(),
+			root: root.clone(),
+			base: base,
+			space: space,
+			items: max(config.account_bloom_items, accounts as usize * 2),
+		})
+	}
+
+	fn commit_bloom_space(batch: &mut DBTransaction, space: usize) {
+		let mut val = [0u8; 8];
+		LittleEndian::write_u64(&mut val, space as u64);
+		batch.put(COL_ACCOUNT_BLOOM, ACCOUNT_BLOOM_SPACE_KEY, &val);
+	}
+
+	fn commit_bloom_base(batch: &mut DBTransaction, number: BlockNumber, hash: &H256) {
+		let mut val = [0u8; 40];
+		LittleEndian::write_u64(&mut val[..8], number);
+		val[8..].copy_from_slice(&hash[..]);
+		batch.put(COL_ACCOUNT_BLOOM, ACCOUNT_BLOOM_BASE_KEY, &val);
 	}
 
 	pub fn commit_bloom(batch: &mut DBTransaction, journal: BloomJournal) -> Result<(), UtilError> {
@@ -177,7 +501,19 @@ impl StateDB {
 	pub fn commit(&mut self, batch: &mut DBTransaction, now: u64, id: &H256, end: Option<(u64, H256)>) -> Result<u32, UtilError> {
 		{
  			let mut bloom_lock = self.account_bloom.lock();
- 			try!(Self::commit_bloom(batch, bloom_lock.drain_journal()));
+			if let Some(space) = bloom_lock.replaced_space.take() {
+				// parts of the replaced bloom which the new one does not overwrite must be cleared.
+				let mut key = [0u8; 8];
+				for i in 0..space / 8 {
+					LittleEndian::write_u64(&mut key, i as u64);
+					batch.delete(COL_ACCOUNT_BLOOM, &key);
+				}
+				if let Some((number, hash)) = bloom_lock.base.clone() {
+					Self::commit_bloom_base(batch, number, &hash);
+				}
+			}
+			Self::commit_bloom_space(batch, (bloom_lock.bloom.number_of_bits() / 8) as usize);
+ 			try!(Self::commit_bloom(batch, bloom_lock.bloom.drain_journal()));
  		}
 		let records = try!(self.db.commit(batch, now, id, end));
 		self.commit_hash = Some(id.clone());
@@ -206,7 +542,10 @@ impl StateDB {
 					m.is_canon = true;
 					for a in &m.accounts {
 						trace!("Reverting enacted address {:?}", a);
-						cache.accounts.remove(a);
+						if let Some(old) = cache.accounts.remove(a) {
+							cache.accounts_size -= AccountCache::entry_size(&old);
+						}
+						cache.hot.remove(a);
 					}
 					false
 				} else {
@@ -222,7 +561,10 @@ impl StateDB {
 					m.is_canon = false;
 					for a in &m.accounts {
 						trace!("Retracted address {:?}", a);
-						cache.accounts.remove(a);
+						if let Some(old) = cache.accounts.remove(a) {
+							cache.accounts_size -= AccountCache::entry_size(&old);
+						}
+						cache.hot.remove(a);
 					}
 					false
 				} else {
@@ -234,6 +576,8 @@ impl StateDB {
 			// We don't know anything about the block; clear everything
 			trace!("Wiping cache");
 			cache.accounts.clear();
+			cache.accounts_size = 0;
+			cache.hot.clear();
 			cache.modifications.clear();
 		}
 
@@ -244,22 +588,34 @@ impl StateDB {
 			if cache.modifications.len() == STATE_CACHE_BLOCKS {
 				cache.modifications.pop_back();
 			}
+			// pin the hot contracts before the accounts of this block can evict them.
+			if is_best {
+				cache.update_hot();
+			}
 			let mut modifications = HashSet::new();
 			trace!("committing {} cache entries", self.local_cache.len());
 			for account in self.local_cache.drain(..) {
 				if account.modified {
 					modifications.insert(account.address.clone());
 				}
+				if is_best && account.modified && cache.hot.contains_key(&account.address) {
+					match account.account {
+						Some(ref new) => { cache.hot.insert(account.address.clone(), new.clone_basic()); },
+						None => { cache.hot.remove(&account.address); },
+					}
+				}
 				if is_best {
 					if let Some(&mut Some(ref mut existing)) = cache.accounts.get_mut(&account.address) {
 						if let Some(new) = account.account {
 							if account.modified {
+								let before = existing.mem_used();
 								existing.overwrite_with(new);
+								cache.accounts_size = cache.accounts_size + existing.mem_used() - before;
 							}
 							continue;
 						}
 					}
-					cache.accounts.insert(account.address, account.account);
+					cache.insert_account(account.address, account.account);
 				}
 			}
 
@@ -287,8 +643,35 @@ impl StateDB {
 	}
 
 	/// Returns an interface to mutable HashDB.
+	/// Changes made through it are recorded while a checkpoint is active.
 	pub fn as_hashdb_mut(&mut self) -> &mut HashDB {
-		self.db.as_hashdb_mut()
+		self
+	}
+
+	/// Start recording overlay changes, so they can be undone with `revert_to_checkpoint`.
+	pub fn checkpoint(&mut self) {
+		self.checkpoints.push(Vec::new());
+	}
+
+	/// Drop the last checkpoint, keeping the overlay changes made since it was created.
+	pub fn discard_checkpoint(&mut self) {
+		if let Some(changes) = self.checkpoints.pop() {
+			if let Some(parent) = self.checkpoints.last_mut() {
+				parent.extend(changes);
+			}
+		}
+	}
+
+	/// Undo all overlay changes made since the last checkpoint and drop it.
+	pub fn revert_to_checkpoint(&mut self) {
+		if let Some(changes) = self.checkpoints.pop() {
+			for change in changes.into_iter().rev() {
+				match change {
+					OverlayChange::Inserted(key) => self.db.remove(&key),
+					OverlayChange::Removed(key, value) => self.db.emplace(key, value),
+				}
+			}
+		}
 	}
 
 	/// Clone the database.
@@ -298,9 +681,11 @@ impl StateDB {
 			account_cache: self.account_cache.clone(),
 			local_cache: Vec::new(),
 			account_bloom: self.account_bloom.clone(),
+			use_account_bloom: self.use_account_bloom,
 			parent_hash: None,
 			commit_hash: None,
 			commit_number: None,
+			checkpoints: self.checkpoints.clone(),
 		}
 	}
 
@@ -311,9 +696,11 @@ impl StateDB {
 			account_cache: self.account_cache.clone(),
 			local_cache: Vec::new(),
 			account_bloom: self.account_bloom.clone(),
+			use_account_bloom: self.use_account_bloom,
 			parent_hash: Some(parent.clone()),
 			commit_hash: None,
 			commit_number: None,
+			checkpoints: self.checkpoints.clone(),
 		}
 	}
 
@@ -322,9 +709,9 @@ impl StateDB {
 		self.db.is_pruned()
 	}
 
-	/// Heap size used.
+	/// Heap size used by the journal overlay and the account cache.
 	pub fn mem_used(&self) -> usize {
-		self.db.mem_used() //TODO: + self.account_cache.lock().heap_size_of_children()
+		self.db.mem_used() + Self::cached_accounts_size(&self.account_cache.lock())
 	}
 
 	/// Returns underlying `JournalDB`.
@@ -351,7 +738,14 @@ impl StateDB {
 		if !Self::is_allowed(addr, &self.parent_hash, &cache.modifications) {
 			return None;
 		}
-		cache.accounts.get_mut(&addr).map(|a| a.as_ref().map(|a| a.clone_basic()))
+		cache.note_access(addr);
+		let cache = &mut *cache;
+		let account = match cache.accounts.get_mut(&addr).map(|a| a.as_ref().map(|a| a.clone_basic())) {
+			Some(account) => Some(account),
+			None => cache.hot.get(addr).map(|a| Some(a.clone_basic())),
+		};
+		Self::count_lookup(account.is_some());
+		account
 	}
 
 	/// Get value from a cached account.
@@ -362,7 +756,24 @@ impl StateDB {
 		if !Self::is_allowed(a, &self.parent_hash, &cache.modifications) {
 			return None;
 		}
-		cache.accounts.get_mut(a).map(|c| f(c.as_mut()))
+		cache.note_access(a);
+		let cache = &mut *cache;
+		let result = match cache.accounts.get_mut(a) {
+			Some(c) => {
+				// `f` may cache storage or code in the account.
+				let before = AccountCache::entry_size(c);
+				let result = f(c.as_mut());
+				cache.accounts_size = cache.accounts_size + AccountCache::entry_size(c) - before;
+				Some(result)
+			},
+			None => cache.hot.get_mut(a).map(|c| f(Some(c))),
+		};
+		Self::count_lookup(result.is_some());
+		result
+	}
+
+	fn count_lookup(hit: bool) {
+		if hit { CACHE_HITS.inc() } else { CACHE_MISSES.inc() }
 	}
 
 	/// Check if the account can be returned from cache by matching current block parent hash against canonical
@@ -401,13 +812,68 @@ impl StateDB {
 	}
 }
 
+impl HashDB for StateDB {
+	fn keys(&self) -> HashMap<H256, i32> {
+		self.db.keys()
+	}
+
+	fn get(&self, key: &H256) -> Option<&[u8]> {
+		self.db.get(key)
+	}
+
+	fn contains(&self, key: &H256) -> bool {
+		self.db.contains(key)
+	}
+
+	fn insert(&mut self, value: &[u8]) -> H256 {
+		let key = self.db.insert(value);
+		if let Some(changes) = self.checkpoints.last_mut() {
+			changes.push(OverlayChange::Inserted(key.clone()));
+		}
+		key
+	}
+
+	fn emplace(&mut self, key: H256, value: Bytes) {
+		if let Some(changes) = self.checkpoints.last_mut() {
+			changes.push(OverlayChange::Inserted(key.clone()));
+		}
+		self.db.emplace(key, value);
+	}
+
+	fn remove(&mut self, key: &H256) {
+		if let Some(changes) = self.checkpoints.last_mut() {
+			// Nodes are only removed after being read, so the value is known.
+			if let Some(value) = self.db.get(key).map(|v| v.to_vec()) {
+				changes.push(OverlayChange::Removed(key.clone(), value));
+			}
+		}
+		self.db.remove(key);
+	}
+
+	fn insert_aux(&mut self, hash: Vec<u8>, value: Vec<u8>) {
+		self.db.insert_aux(hash, value);
+	}
+
+	fn get_aux(&self, hash: &[u8]) -> Option<Vec<u8>> {
+		self.db.get_aux(hash)
+	}
+
+	fn remove_aux(&mut self, hash: &[u8]) {
+		self.db.remove_aux(hash);
+	}
+}
+
 #[cfg(test)]
 mod tests {
 
-use util::{U256, H256, FixedHash, Address, DBTransaction};
+use std::mem;
+use std::collections::HashMap;
+use util::{U256, H256, FixedHash, Address, DBTransaction, TrieMut, SecTrieDBMut, Hashable};
 use tests::helpers::*;
 use state::Account;
 use util::log::init_log;
+use db::COL_ACCOUNT_BLOOM;
+use super::{StateDB, StateDbConfig, ACCOUNT_BLOOM_SPACE_KEY};
 
 #[test]
 fn state_db_smoke() {
@@ -476,5 +942,147 @@ fn state_db_smoke() {
 	let s = state_db.boxed_clone_canon(&h3a);
 	assert!(s.get_cached_account(&address).is_none());
 }
+
+#[test]
+fn account_bloom_is_rebuilt_larger() {
+	// the bloom of the temporary database is created as configured and saturated by the accounts.
+	let config = StateDbConfig {
+		account_bloom_space: 64,
+		account_bloom_items: 4,
+		max_bloom_saturation: 0.5,
+		..Default::default()
+	};
+	let mut state_db_result = get_temp_state_db();
+	let state_db = state_db_result.take();
+	let mut state_db = StateDB::with_config(state_db.journal_db().boxed_clone(), StateDB::default_cache_size(), &config);
+	let addresses: Vec<_> = (0..16).map(|_| Address::random()).collect();
+	let mut root = H256::new();
+	{
+		let mut trie = SecTrieDBMut::new(state_db.as_hashdb_mut(), &mut root);
+		for address in &addresses {
+			trie.insert(address, b"account").unwrap();
+		}
+	}
+	for address in &addresses {
+		state_db.note_account_bloom(address);
+	}
+	let base = (1, H256::random());
+	let mut batch = DBTransaction::new(state_db.journal_db().backing());
+	state_db.commit(&mut batch, 1, &base.1, None).unwrap();
+	state_db.journal_db().backing().write(batch).unwrap();
+	assert!(state_db.account_bloom_saturation() > config.max_bloom_saturation);
+
+	let rebuild = state_db.begin_account_bloom_rebuild(&root, base.clone(), &config).unwrap();
+	assert!(state_db.account_bloom_rebuilding());
+	assert!(state_db.begin_account_bloom_rebuild(&root, base.clone(), &config).is_none());
+	// accounts noted during the rebuild are kept.
+	let noted = Address::random();
+	state_db.note_account_bloom(&noted);
+	assert_eq!(rebuild.run().unwrap(), addresses.len());
+	assert!(!state_db.account_bloom_rebuilding());
+	assert_eq!(state_db.account_bloom_base(), Some(base.clone()));
+	assert!(addresses.iter().chain(Some(&noted)).all(|address| state_db.check_account_bloom(address)));
+
+	// the geometry and the base are persisted along with the bloom on the next commit.
+	let mut batch = DBTransaction::new(state_db.journal_db().backing());
+	state_db.commit(&mut batch, 2, &H256::random(), None).unwrap();
+	state_db.journal_db().backing().write(batch).unwrap();
+	let backing = state_db.journal_db().backing();
+	let space = backing.get(COL_ACCOUNT_BLOOM, ACCOUNT_BLOOM_SPACE_KEY).unwrap().unwrap();
+	assert_eq!(&*space, &[128, 0, 0, 0, 0, 0, 0, 0]);
+	let bloom = StateDB::load_bloom_with_config(backing, &config);
+	assert_eq!(bloom.number_of_bits(), 128 * 8);
+	assert!(addresses.iter().chain(Some(&noted)).all(|address| bloom.check(&*address.sha3())));
+	let reopened = StateDB::with_config(state_db.journal_db().boxed_clone(), StateDB::default_cache_size(), &config);
+	assert_eq!(reopened.account_bloom_base(), Some(base));
+}
+
+#[test]
+fn disabled_account_bloom_does_not_rule_out_accounts() {
+	let mut state_db_result = get_temp_state_db();
+	let state_db = state_db_result.take();
+	let address = Address::random();
+	assert!(!state_db.check_account_bloom(&address));
+
+	let mut disabled = state_db.boxed_clone();
+	disabled.disable_account_bloom();
+	assert!(disabled.check_account_bloom(&address));
+	assert!(disabled.boxed_clone_canon(&H256::random()).check_account_bloom(&address));
+	assert!(!state_db.check_account_bloom(&address));
+}
+
+#[test]
+fn hot_contracts_survive_cache_eviction() {
+	let mut state_db_result = get_temp_state_db();
+	let state_db = state_db_result.take();
+	// the account cache only has room for a single account.
+	let config = StateDbConfig { hot_contracts: 1, ..Default::default() };
+	let state_db = StateDB::with_config(state_db.journal_db().boxed_clone(), mem::size_of::<Option<Account>>(), &config);
+	let contract = Address::random();
+	let h0 = H256::random();
+	let h1 = H256::random();
+	let mut batch = DBTransaction::new(state_db.journal_db().backing());
+
+	let mut s = state_db.boxed_clone_canon(&H256::random());
+	s.add_to_account_cache(contract, Some(Account::new(1.into(), 0.into(), HashMap::new(), vec![0x60, 0x00])), true);
+	s.commit(&mut batch, 0, &h0, None).unwrap();
+	s.sync_cache(&[], &[], true);
+	assert!(state_db.boxed_clone_canon(&h0).get_cached_account(&contract).is_some());
+
+	let mut s = state_db.boxed_clone_canon(&h0);
+	s.add_to_account_cache(Address::random(), Some(Account::new_basic(2.into(), 0.into())), true);
+	s.add_to_account_cache(Address::random(), Some(Account::new_basic(3.into(), 0.into())), true);
+	s.commit(&mut batch, 1, &h1, None).unwrap();
+	s.sync_cache(&[], &[], true);
+
+	let account = state_db.boxed_clone_canon(&h1).get_cached_account(&contract).unwrap().unwrap();
+	assert_eq!(account.balance(), &U256::from(1));
+	assert_eq!(account.code_hash(), vec![0x60, 0x00].sha3());
+}
+
+#[test]
+fn account_cache_size_is_measured() {
+	let mut state_db_result = get_temp_state_db();
+	let state_db = state_db_result.take();
+	let h0 = H256::random();
+	let mut batch = DBTransaction::new(state_db.journal_db().backing());
+
+	let mut s = state_db.boxed_clone_canon(&H256::random());
+	s.add_to_account_cache(Address::random(), Some(Account::new(1.into(), 0.into(), HashMap::new(), vec![0u8; 10000])), true);
+	s.commit(&mut batch, 0, &h0, None).unwrap();
+	s.sync_cache(&[], &[], true);
+
+	let used = state_db.mem_used() - state_db.journal_db().mem_used();
+	assert!(used >= 10000);
+
+	// room for two accounts of the measured size.
+	state_db.set_cache_size(2 * used);
+	assert_eq!(state_db.account_cache.lock().accounts.capacity(), 2);
 }
 
+#[test]
+fn account_cache_size_follows_evictions() {
+	let mut state_db_result = get_temp_state_db();
+	let state_db = state_db_result.take();
+	let h0 = H256::random();
+	let h1 = H256::random();
+	let mut batch = DBTransaction::new(state_db.journal_db().backing());
+
+	let mut s = state_db.boxed_clone_canon(&H256::random());
+	s.add_to_account_cache(Address::random(), Some(Account::new(1.into(), 0.into(), HashMap::new(), vec![0u8; 10000])), true);
+	s.commit(&mut batch, 0, &h0, None).unwrap();
+	s.sync_cache(&[], &[], true);
+	let used = state_db.mem_used() - state_db.journal_db().mem_used();
+	state_db.set_cache_size(used);
+
+	// the large account is evicted to make room for the small one.
+	let small = Account::new_basic(2.into(), 0.into());
+	let small_size = small.mem_used();
+	let mut s = state_db.boxed_clone_canon(&h0);
+	s.add_to_account_cache(Address::random(), Some(small), true);
+	s.commit(&mut batch, 1, &h1, None).unwrap();
+	s.sync_cache(&[], &[], true);
+
+	assert_eq!(state_db.mem_used() - state_db.journal_db().mem_used(), small_size);
+}
+}
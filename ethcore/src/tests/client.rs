@@ -15,7 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use io::IoChannel;
-use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockID};
+use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockID, UncleID, Mode};
 use ethereum;
 use block::IsBlock;
 use tests::helpers::*;
@@ -23,7 +23,9 @@ use types::filter::Filter;
 use common::*;
 use devtools::*;
 use miner::Miner;
-use rlp::{Rlp, View};
+use rlp::{self, Rlp, View};
+use ethkey::KeyPair;
+use spec::Spec;
 
 #[test]
 fn imports_from_empty() {
@@ -126,6 +128,333 @@ fn query_bad_block() {
 	assert!(bad_block.is_none());
 }
 
+#[test]
+fn imports_blocks_without_executing_in_relay_mode() {
+	let dir = RandomTempPath::new();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let mut config = ClientConfig::default();
+	config.mode = Mode::Relay;
+	let miner = Arc::new(Miner::with_spec(&spec));
+
+	let client = Client::new(
+		config,
+		&spec,
+		dir.as_path(),
+		miner.clone(),
+		IoChannel::disconnected(),
+		&db_config
+	).unwrap();
+	// the state root of this block is wrong, which is only detected when it is executed
+	let block = get_bad_state_dummy_block();
+	client.import_block(block).unwrap();
+	client.flush_queue();
+	client.import_verified_blocks();
+
+	assert_eq!(client.chain_info().best_block_number, 1);
+	assert!(client.block_receipts(&client.chain_info().best_block_hash).is_none());
+	assert!(client.state().is_none());
+	assert!(client.balance(&Address::default(), BlockID::Latest).is_none());
+	assert!(client.call_contract(BlockID::Latest, Address::default(), vec![]).is_err());
+}
+
+#[test]
+fn disables_miner_in_relay_mode() {
+	use miner::MinerService;
+
+	let dir = RandomTempPath::new();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let mut config = ClientConfig::default();
+	config.mode = Mode::Relay;
+	let miner = Arc::new(Miner::with_spec(&spec));
+
+	let client = Client::new(
+		config,
+		&spec,
+		dir.as_path(),
+		miner.clone(),
+		IoChannel::disconnected(),
+		&db_config
+	).unwrap();
+	client.import_block(get_bad_state_dummy_block()).unwrap();
+	client.flush_queue();
+	client.import_verified_blocks();
+
+	miner.update_sealing(&*client);
+	miner.prepare_pending_block(&*client);
+	assert!(miner.map_sealing_work(&*client, |_| ()).is_none());
+	assert!(!miner.seal_empty_block(&*client));
+	assert!(miner.pending_block(1).is_none());
+}
+
+#[test]
+fn imports_headers_only_without_state() {
+	use miner::MinerService;
+
+	let dir = RandomTempPath::new();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let mut config = ClientConfig::default();
+	config.blockchain.store_bodies = false;
+	let miner = Arc::new(Miner::with_spec(&spec));
+
+	let client = Client::new(
+		config,
+		&spec,
+		dir.as_path(),
+		miner.clone(),
+		IoChannel::disconnected(),
+		&db_config
+	).unwrap();
+	client.import_block(get_good_dummy_block()).unwrap();
+	client.flush_queue();
+	client.import_verified_blocks();
+
+	assert_eq!(client.chain_info().best_block_number, 1);
+	assert!(!client.keeps_state());
+	assert!(!client.stores_bodies());
+	assert!(client.state().is_none());
+	assert!(client.block_body(BlockID::Latest).is_none());
+	assert_eq!(client.import_queued_transactions(&[]), 0);
+	miner.update_sealing(&*client);
+	assert!(miner.map_sealing_work(&*client, |_| ()).is_none());
+}
+
+#[test]
+fn refuses_database_with_other_no_bodies_setting() {
+	use client::Error as ClientError;
+
+	let dir = RandomTempPath::new();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let headers_only = || {
+		let mut config = ClientConfig::default();
+		config.blockchain.store_bodies = false;
+		config
+	};
+	let new_client = |config: ClientConfig| Client::new(
+		config,
+		&spec,
+		dir.as_path(),
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+		&db_config
+	);
+
+	new_client(headers_only()).unwrap();
+	match new_client(ClientConfig::default()) {
+		Err(ClientError::NoBodiesMismatch(true)) => {},
+		_ => panic!("A headers-only database must not be opened with bodies."),
+	}
+	assert!(new_client(headers_only()).is_ok());
+}
+
+#[test]
+fn stores_blocks_failing_queue_verification() {
+	let client_result = get_test_client_with_blocks(Vec::new());
+	let client = client_result.reference();
+	let spec = get_test_spec();
+
+	let mut header = Header::new();
+	header.set_gas_limit(spec.engine.params().min_gas_limit);
+	header.set_gas_used(spec.engine.params().min_gas_limit + U256::from(1));
+	header.set_difficulty(U256::from(0x20000));
+	header.set_timestamp(40);
+	header.set_number(1);
+	header.set_parent_hash(spec.genesis_header().hash());
+	let block = create_test_block(&header);
+
+	assert!(client.import_block(block.clone()).is_err());
+	let bad_blocks = client.bad_blocks();
+	assert_eq!(bad_blocks.len(), 1);
+	assert_eq!(bad_blocks[0].rlp, block);
+	assert!(bad_blocks[0].reason.starts_with("Stage 1 verification failed"));
+}
+
+/// Test chain whose transaction permission contract only allows basic transactions, with `sender` funded.
+fn permissioned_spec(sender: &Address) -> Spec {
+	// the contract at 0x05 returns 1 for every sender.
+	let json = include_str!("../../res/null_morden.json")
+		.replace(r#""networkID" : "0x2""#, r#""networkID" : "0x2", "transactionPermissionContract": "0x0000000000000000000000000000000000000005""#)
+		.replace(r#""accounts": {"#, &format!(r#""accounts": {{
+		"0000000000000000000000000000000000000005": {{ "balance": "0", "code": "0x600160005260206000f3" }},
+		"{}": {{ "balance": "1000000000000000000" }},"#, sender.hex()));
+	Spec::load(json.as_bytes()).expect("permissioned spec is valid")
+}
+
+fn permission_test_transactions(keypair: &KeyPair) -> (SignedTransaction, SignedTransaction) {
+	let transaction = |action, data| Transaction {
+		nonce: 0.into(),
+		gas_price: U256::from(20_000_000_000u64),
+		gas: U256::from(100_000),
+		action: action,
+		value: U256::from(1),
+		data: data,
+	}.sign(keypair.secret());
+	(transaction(Action::Call(Address::from(0x20)), vec![]), transaction(Action::Create, vec![0x60, 0x00]))
+}
+
+#[test]
+fn refuses_transactions_not_allowed_by_permission_contract() {
+	use miner::MinerService;
+
+	let keypair = KeyPair::from_secret("".sha3()).unwrap();
+	let dir = RandomTempPath::new();
+	let spec = permissioned_spec(&keypair.address());
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let miner = Arc::new(Miner::with_spec(&spec));
+	let client = Client::new(
+		ClientConfig::default(),
+		&spec,
+		dir.as_path(),
+		miner.clone(),
+		IoChannel::disconnected(),
+		&db_config
+	).unwrap();
+	let (transfer, create) = permission_test_transactions(&keypair);
+
+	match miner.import_own_transaction(&*client, create) {
+		Err(Error::Transaction(TransactionError::NotAllowed)) => {},
+		other => panic!("Contract creations are not allowed: {:?}", other),
+	}
+	assert!(miner.import_own_transaction(&*client, transfer).is_ok());
+}
+
+#[test]
+fn refuses_blocks_with_transactions_not_allowed_by_permission_contract() {
+	let keypair = KeyPair::from_secret("".sha3()).unwrap();
+	let dir = RandomTempPath::new();
+	let spec = permissioned_spec(&keypair.address());
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let client = Client::new(
+		ClientConfig::default(),
+		&spec,
+		dir.as_path(),
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+		&db_config
+	).unwrap();
+	let (transfer, create) = permission_test_transactions(&keypair);
+	let block_with = |t: SignedTransaction| {
+		let mut b = client.prepare_open_block(Address::default(), (3141562.into(), 31415620.into()), vec![]);
+		b.push_transaction(t, None).unwrap();
+		b.close_and_lock().seal(&*spec.engine, vec![]).unwrap().rlp_bytes()
+	};
+	let import = |block: Bytes| {
+		client.import_block(block).unwrap();
+		client.flush_queue();
+		client.import_verified_blocks();
+	};
+
+	import(block_with(create));
+	assert_eq!(client.chain_info().best_block_number, 0);
+	assert!(client.bad_blocks()[0].reason.contains("is not allowed"));
+
+	import(block_with(transfer));
+	assert_eq!(client.chain_info().best_block_number, 1);
+}
+
+fn dummy_chain(count: u64, difficulty: u64) -> Vec<Bytes> {
+	let spec = get_test_spec();
+	let mut parent = spec.genesis_header().hash();
+	(1..count + 1).map(|number| {
+		let mut header = Header::new();
+		header.set_gas_limit(spec.engine.params().min_gas_limit);
+		header.set_difficulty(U256::from(difficulty));
+		header.set_timestamp(number * 10);
+		header.set_number(number);
+		header.set_parent_hash(parent);
+		header.set_state_root(spec.genesis_header().state_root().clone());
+		parent = header.hash();
+		create_test_block(&header)
+	}).collect()
+}
+
+#[test]
+fn refuses_deep_reorgs_until_forced() {
+	let dir = RandomTempPath::new();
+	let spec = get_test_spec();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let mut config = ClientConfig::default();
+	config.max_reorg_depth = Some(1);
+
+	let client = Client::new(
+		config,
+		&spec,
+		dir.as_path(),
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+		&db_config
+	).unwrap();
+	let import = |blocks: &[Bytes]| {
+		for block in blocks {
+			client.import_block(block.clone()).unwrap();
+		}
+		client.flush_queue();
+		client.import_verified_blocks();
+	};
+	let hash = |block: &Bytes| BlockView::new(block).header_view().sha3();
+
+	let canon = dummy_chain(3, 0x20000);
+	let fork = dummy_chain(4, 0x30000);
+	import(&canon);
+	// the heavier fork would retract all three canonical blocks.
+	import(&fork[..3]);
+	assert_eq!(client.chain_info().best_block_hash, hash(&canon[2]));
+	assert!(client.block_header(BlockID::Hash(hash(&fork[2]))).is_none());
+
+	// descendants of a refused block are refused as well.
+	import(&fork[3..]);
+	assert!(client.block_header(BlockID::Hash(hash(&fork[3]))).is_none());
+	assert_eq!(client.chain_info().best_block_hash, hash(&canon[2]));
+
+	assert!(!client.force_reorg(hash(&canon[0])));
+	assert!(client.force_reorg(hash(&fork[2])));
+	client.flush_queue();
+	client.import_verified_blocks();
+	assert_eq!(client.chain_info().best_block_hash, hash(&fork[3]));
+	assert_eq!(client.chain_info().best_block_number, 4);
+	assert!(!client.force_reorg(hash(&fork[2])));
+}
+
+#[test]
+fn returns_no_uncles_above_best_block_after_reorg() {
+	let spec = get_test_spec();
+	let client_result = get_test_client_with_blocks(vec![]);
+	let client = client_result.reference();
+	let import = |blocks: &[Bytes]| {
+		for block in blocks {
+			client.import_block(block.clone()).unwrap();
+		}
+		client.flush_queue();
+		client.import_verified_blocks();
+	};
+	let hash = |block: &Bytes| BlockView::new(block).header_view().sha3();
+
+	// the third canonical block includes a sibling of the second one as uncle.
+	let mut canon = dummy_chain(2, 0x20000);
+	let mut uncle = BlockView::new(&canon[1]).header();
+	uncle.set_timestamp(25);
+	uncle.set_extra_data(vec![1]);
+	let mut header = BlockView::new(&canon[1]).header();
+	header.set_number(3);
+	header.set_timestamp(30);
+	header.set_parent_hash(hash(&canon[1]));
+	header.set_uncles_hash(rlp::encode(&vec![uncle.clone()]).to_vec().sha3());
+	header.set_state_root(spec.genesis_header().state_root().clone());
+	canon.push(create_test_block_with_data(&header, &[], &[uncle.clone()]));
+	import(&canon);
+
+	assert_eq!(client.uncle(UncleID { block: BlockID::Number(3), position: 0 }).unwrap(), rlp::encode(&uncle).to_vec());
+
+	// the heavier fork is shorter.
+	import(&dummy_chain(2, 0x40000));
+	assert_eq!(client.chain_info().best_block_number, 2);
+	assert!(client.uncle(UncleID { block: BlockID::Number(3), position: 0 }).is_none());
+	assert!(client.uncle(UncleID { block: BlockID::Hash(hash(&canon[2])), position: 0 }).is_some());
+}
+
 #[test]
 fn returns_chain_info() {
 	let dummy_block = get_good_dummy_block();
@@ -209,6 +538,76 @@ fn can_generate_gas_price_statistics() {
 	assert_eq!(s, vec_into![0, 1, 3, 5, 7, 9, 11, 13, 15]);
 }
 
+#[test]
+fn returns_state_diff() {
+	use types::account_diff::Diff;
+
+	let mut config = ClientConfig::default();
+	config.fat_db = true;
+	let client_result = generate_dummy_client_with_spec_config_and_data(Spec::new_null, config, 3, 1, &vec_into![0]);
+	let client = client_result.reference();
+	let author = KeyPair::from_secret("".sha3()).unwrap().address();
+
+	let diff = client.state_diff(BlockID::Number(1), BlockID::Number(3), vec![author.clone()]).unwrap();
+	assert_eq!(diff.raw.len(), 1);
+	assert_eq!(diff.raw[&author].nonce, Diff::Changed(1.into(), 3.into()));
+
+	let diff = client.state_diff(BlockID::Number(3), BlockID::Number(3), vec![author.clone()]).unwrap();
+	assert!(diff.raw.is_empty());
+
+	assert_eq!(client.state_diff(BlockID::Number(3), BlockID::Number(1), vec![author]), Err(StateDiffError::InvalidRange));
+}
+
+#[test]
+fn requires_fat_db_for_state_diff() {
+	let client_result = generate_dummy_client_with_data(3, 1, &vec_into![0]);
+	let client = client_result.reference();
+
+	assert_eq!(client.state_diff(BlockID::Number(1), BlockID::Number(3), vec![]), Err(StateDiffError::FatDbRequired));
+	assert_eq!(client.list_accounts(BlockID::Latest, None, 10), None);
+}
+
+#[test]
+fn reconstructs_balance_change_from_traces() {
+	use executive::contract_address;
+
+	let mut config = ClientConfig::default();
+	config.tracing.enabled = true;
+	let client_result = generate_dummy_client_with_spec_config_and_data(Spec::new_null, config, 3, 1, &vec_into![0]);
+	let client = client_result.reference();
+	let author = KeyPair::from_secret("".sha3()).unwrap().address();
+	let created = contract_address(&author, &1.into());
+
+	assert_eq!(client.balance_change(&created, BlockID::Number(2)), Some((U256::zero(), U256::zero())));
+	// block rewards are not traced
+	assert_eq!(client.balance_change(&author, BlockID::Number(2)), None);
+
+	let client_result = generate_dummy_client_with_data(3, 1, &vec_into![0]);
+	assert_eq!(client_result.reference().balance_change(&created, BlockID::Number(2)), None);
+}
+
+#[test]
+fn lists_accounts_in_pages() {
+	let mut config = ClientConfig::default();
+	config.fat_db = true;
+	let client_result = generate_dummy_client_with_spec_config_and_data(Spec::new_null, config, 3, 1, &vec_into![0]);
+	let client = client_result.reference();
+
+	let all = client.list_accounts(BlockID::Latest, None, u64::max_value()).unwrap();
+	assert!(all.len() >= 2);
+
+	let first = client.list_accounts(BlockID::Latest, None, 1).unwrap();
+	assert_eq!(first, vec![all[0].clone()]);
+	let rest = client.list_accounts(BlockID::Latest, Some(&all[0]), u64::max_value()).unwrap();
+	assert_eq!(rest, all[1..].to_vec());
+	assert!(client.list_accounts(BlockID::Latest, Some(&all[all.len() - 1]), 10).unwrap().is_empty());
+
+	// pages of an account which does not exist start at the position of its address hash
+	let missing = Address::from(0x1234);
+	let following: Vec<_> = all.iter().filter(|address| address.sha3() > missing.sha3()).cloned().collect();
+	assert_eq!(client.list_accounts(BlockID::Latest, Some(&missing), u64::max_value()).unwrap(), following);
+}
+
 #[test]
 fn can_handle_long_fork() {
 	let client_result = generate_dummy_client(1200);
@@ -234,7 +633,7 @@ fn can_mine() {
 	let client_result = get_test_client_with_blocks(vec![dummy_blocks[0].clone()]);
 	let client = client_result.reference();
 
-	let b = client.prepare_open_block(Address::default(), (3141562.into(), 31415620.into()), vec![]).close();
+	let b = client.prepare_open_block(Address::default(), (3141562.into(), 31415620.into()), vec![]).close().unwrap();
 
 	assert_eq!(*b.block().header().parent_hash(), BlockView::new(&dummy_blocks[0]).header_view().sha3());
 }
@@ -32,11 +32,7 @@ use rlp::{self, RlpStream, Stream};
 use db::COL_STATE;
 
 #[cfg(feature = "json-tests")]
-pub enum ChainEra {
-	Frontier,
-	Homestead,
-	DaoHardfork,
-}
+pub use json_tests::ChainEra;
 
 pub struct TestEngine {
 	engine: Arc<Engine>,
@@ -134,12 +130,16 @@ pub fn generate_dummy_client_with_data(block_number: u32, txs_per_block: usize,
 }
 
 pub fn generate_dummy_client_with_spec_and_data<F>(get_test_spec: F, block_number: u32, txs_per_block: usize, tx_gas_prices: &[U256]) -> GuardedTempResult<Arc<Client>> where F: Fn()->Spec {
+	generate_dummy_client_with_spec_config_and_data(get_test_spec, ClientConfig::default(), block_number, txs_per_block, tx_gas_prices)
+}
+
+pub fn generate_dummy_client_with_spec_config_and_data<F>(get_test_spec: F, config: ClientConfig, block_number: u32, txs_per_block: usize, tx_gas_prices: &[U256]) -> GuardedTempResult<Arc<Client>> where F: Fn()->Spec {
 	let dir = RandomTempPath::new();
 	let test_spec = get_test_spec();
 	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
 
 	let client = Client::new(
-		ClientConfig::default(),
+		config,
 		&test_spec,
 		dir.as_path(),
 		Arc::new(Miner::with_spec(&test_spec)),
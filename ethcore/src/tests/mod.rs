@@ -16,5 +16,6 @@
 
 pub mod helpers;
 mod client;
+mod private_transactions;
 #[cfg(feature="ipc")]
 mod rpc;
@@ -0,0 +1,216 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use io::IoChannel;
+use client::{Client, ClientConfig, ChainNotify};
+use account_provider::AccountProvider;
+use private_transactions::{Provider, ProviderConfig, Encryptor, AesEncryptor, Error, PrivateTransaction, SignedPrivateTransaction};
+use miner::{Miner, MinerService};
+use spec::Spec;
+use devtools::RandomTempPath;
+use ethkey::{KeyPair, sign};
+use rlp::{self, Encodable};
+use rustc_serialize::hex::ToHex;
+use transaction::{Transaction, SignedTransaction, Action};
+use util::{Address, Bytes, H256, U256, Hashable, Mutex};
+use util::kvdb::DatabaseConfig;
+
+/// Address of the private contract in the test spec.
+const CONTRACT: u64 = 0x10;
+
+/// Code of a private contract validated by `validators`. `getValidators` returns them and every
+/// other function returns empty bytes, so the private code and state are empty.
+fn private_contract_code(validators: &[Address]) -> String {
+	let selector = "getValidators()".sha3();
+	let mut store_validators = String::new();
+	for (i, validator) in validators.iter().enumerate() {
+		// PUSH20 validator PUSH1 offset MSTORE
+		store_validators.push_str(&format!("73{}60{:02x}52", validator.hex(), 0x40 + 0x20 * i));
+	}
+	format!(concat!(
+		// selector = calldata[0..32] / 2^224
+		"600035", "7c0100000000000000000000000000000000000000000000000000000000", "9004",
+		// jump to 0x36 if the selector is getValidators
+		"63{}", "14", "6036", "57",
+		// return empty bytes
+		"6020600052", "60406000f3",
+		// return the validators array
+		"5b", "6020600052", "60{:02x}602052", "{}", "60{:02x}6000f3"),
+		selector[..4].to_hex(), validators.len(), store_validators, 0x40 + 0x20 * validators.len())
+}
+
+fn private_spec(validators: &[Address], signer: &Address) -> Spec {
+	let json = include_str!("../../res/null_morden.json")
+		.replace(r#""accounts": {"#, &format!(r#""accounts": {{
+		"{}": {{ "balance": "0", "code": "0x{}" }},
+		"{}": {{ "balance": "1000000000000000000000" }},"#,
+		Address::from(CONTRACT).hex(), private_contract_code(validators), signer.hex()));
+	Spec::load(json.as_bytes()).expect("private contract spec is valid")
+}
+
+/// Records the messages the providers send to the network.
+#[derive(Default)]
+struct Broadcasts {
+	private: Mutex<Vec<Bytes>>,
+	signed: Mutex<Vec<Bytes>>,
+}
+
+impl ChainNotify for Broadcasts {
+	fn broadcast_private_transaction(&self, packet: Bytes) {
+		self.private.lock().push(packet);
+	}
+
+	fn broadcast_signed_private_transaction(&self, packet: Bytes) {
+		self.signed.lock().push(packet);
+	}
+}
+
+struct Setup {
+	_dir: RandomTempPath,
+	client: Arc<Client>,
+	miner: Arc<Miner>,
+	accounts: Arc<AccountProvider>,
+	broadcasts: Arc<Broadcasts>,
+	validators: Vec<KeyPair>,
+	signer: KeyPair,
+}
+
+impl Setup {
+	fn new() -> Self {
+		let key = |seed: &str| KeyPair::from_secret(seed.sha3()).unwrap();
+		let validators = vec![key("validator1"), key("validator2")];
+		let signer = key("signer");
+		let addresses: Vec<_> = validators.iter().map(|v| v.address()).collect();
+		let spec = private_spec(&addresses, &signer.address());
+
+		let dir = RandomTempPath::new();
+		let miner = Arc::new(Miner::with_spec(&spec));
+		miner.set_gas_floor_target(U256::from(5_000_000));
+		let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+		let client = Client::new(ClientConfig::default(), &spec, dir.as_path(), miner.clone(), IoChannel::disconnected(), &db_config).unwrap();
+
+		let accounts = Arc::new(AccountProvider::transient_provider());
+		for keypair in validators.iter().chain(Some(&signer)) {
+			let address = accounts.insert_account(keypair.secret().clone(), "").unwrap();
+			accounts.unlock_account_permanently(address, "".into()).unwrap();
+		}
+		let broadcasts = Arc::new(Broadcasts::default());
+		client.add_notify(broadcasts.clone());
+
+		Setup {
+			_dir: dir,
+			client: client,
+			miner: miner,
+			accounts: accounts,
+			broadcasts: broadcasts,
+			validators: validators,
+			signer: signer,
+		}
+	}
+
+	fn provider(&self, validator_accounts: Vec<Address>, signer_account: Option<Address>) -> Provider {
+		let config = ProviderConfig {
+			validator_accounts: validator_accounts,
+			signer_account: signer_account,
+		};
+		let encryptor: Box<Encryptor> = Box::new(AesEncryptor::new(|_: &Address| Ok(b"contract key".to_vec())));
+		Provider::new(config, encryptor, self.client.clone(), self.miner.clone(), self.accounts.clone())
+	}
+
+	fn transaction(&self) -> SignedTransaction {
+		Transaction {
+			nonce: U256::zero(),
+			action: Action::Call(Address::from(CONTRACT)),
+			gas: U256::from(100_000),
+			gas_price: U256::zero(),
+			value: U256::zero(),
+			data: vec![],
+		}.sign(&"sender".sha3())
+	}
+}
+
+#[test]
+fn creates_validates_signs_and_commits_private_transaction() {
+	let setup = Setup::new();
+	let creator = setup.provider(vec![setup.validators[0].address()], Some(setup.signer.address()));
+	let validator = setup.provider(vec![setup.validators[1].address()], None);
+
+	// the creator validates the transaction itself and waits for the other validator.
+	let receipt = creator.create_private_transaction(setup.transaction()).unwrap();
+	assert!(!receipt.committed);
+	assert_eq!(receipt.contract_address, Address::from(CONTRACT));
+	let private = setup.broadcasts.private.lock()[0].clone();
+	assert_eq!(rlp::decode::<PrivateTransaction>(&private).hash(), receipt.hash);
+	assert!(setup.miner.all_transactions().is_empty());
+
+	// the other validator executes it and signs the same state.
+	validator.import_private_transaction(&private).unwrap();
+	let signed_packet = setup.broadcasts.signed.lock()[0].clone();
+	let signed: SignedPrivateTransaction = rlp::decode(&signed_packet);
+	assert_eq!(signed.private_transaction_hash, receipt.hash);
+	assert_eq!(signed.signer().unwrap(), setup.validators[1].address());
+
+	// once all validators signed, the state is committed publicly.
+	creator.import_signed_private_transaction(&signed_packet).unwrap();
+	let transactions = setup.miner.all_transactions();
+	assert_eq!(transactions.len(), 1);
+	assert_eq!(transactions[0].action, Action::Call(Address::from(CONTRACT)));
+	assert_eq!(transactions[0].sender().unwrap(), setup.signer.address());
+}
+
+#[test]
+fn commits_immediately_when_all_validators_are_local() {
+	let setup = Setup::new();
+	let validators = setup.validators.iter().map(|v| v.address()).collect();
+	let creator = setup.provider(validators, Some(setup.signer.address()));
+
+	let receipt = creator.create_private_transaction(setup.transaction()).unwrap();
+	assert!(receipt.committed);
+	assert!(setup.broadcasts.private.lock().is_empty());
+	assert_eq!(setup.miner.all_transactions().len(), 1);
+}
+
+#[test]
+fn refuses_signatures_of_other_states_and_non_validators() {
+	let setup = Setup::new();
+	let creator = setup.provider(vec![setup.validators[0].address()], Some(setup.signer.address()));
+	let receipt = creator.create_private_transaction(setup.transaction()).unwrap();
+
+	let signed = |keypair: &KeyPair, state_hash: H256| SignedPrivateTransaction {
+		private_transaction_hash: receipt.hash,
+		contract: Address::from(CONTRACT),
+		state_hash: state_hash,
+		signature: sign(keypair.secret(), &state_hash).unwrap(),
+	}.rlp_bytes().to_vec();
+
+	match creator.import_signed_private_transaction(&signed(&setup.validators[1], H256::from(1))) {
+		Err(Error::StateMismatch) => {},
+		e => panic!("Expected state mismatch, got {:?}", e),
+	}
+
+	// signatures of unknown transactions are only relayed if made by a validator.
+	let relay = setup.provider(vec![], None);
+	let other = KeyPair::from_secret("other".sha3()).unwrap();
+	match relay.import_signed_private_transaction(&signed(&other, H256::from(1))) {
+		Err(Error::NotFromValidator(address)) => assert_eq!(address, other.address()),
+		e => panic!("Expected signature from non-validator to be refused, got {:?}", e),
+	}
+	assert!(setup.broadcasts.signed.lock().is_empty());
+	relay.import_signed_private_transaction(&signed(&setup.validators[1], H256::from(1))).unwrap();
+	assert_eq!(setup.broadcasts.signed.lock().len(), 1);
+	assert!(setup.miner.all_transactions().is_empty());
+}
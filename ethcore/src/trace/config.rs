@@ -25,9 +25,9 @@ pub struct Config {
 	pub enabled: bool,
 	/// Traces blooms configuration.
 	pub blooms: BloomConfig,
-	/// Preferef cache-size.
+	/// Preferred cache size in bytes.
 	pub pref_cache_size: usize,
-	/// Max cache-size.
+	/// Maximum cache size in bytes.
 	pub max_cache_size: usize,
 }
 
@@ -0,0 +1,117 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Code coverage derived from VM traces.
+
+use std::collections::{BTreeMap, BTreeSet};
+use util::{H256, Hashable};
+use trace::VMTrace;
+
+const PUSH1: u8 = 0x60;
+const PUSH32: u8 = 0x7f;
+
+/// Program counters of a single piece of code reached during execution.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CodeCoverage {
+	/// Hash of the executed code.
+	pub code_hash: H256,
+	/// Program counters of all instructions in the code.
+	pub instructions: BTreeSet<usize>,
+	/// Program counters of instructions which were executed.
+	pub executed: BTreeSet<usize>,
+}
+
+impl CodeCoverage {
+	fn new(code: &[u8]) -> Self {
+		CodeCoverage {
+			code_hash: code.sha3(),
+			instructions: instruction_positions(code),
+			executed: BTreeSet::new(),
+		}
+	}
+}
+
+/// Returns program counters at which instructions start, skipping `PUSH` data.
+fn instruction_positions(code: &[u8]) -> BTreeSet<usize> {
+	let mut positions = BTreeSet::new();
+	let mut pc = 0;
+	while pc < code.len() {
+		positions.insert(pc);
+		let instruction = code[pc];
+		pc += 1;
+		if instruction >= PUSH1 && instruction <= PUSH32 {
+			pc += (instruction - PUSH1 + 1) as usize;
+		}
+	}
+	positions
+}
+
+fn collect(trace: &VMTrace, coverage: &mut BTreeMap<H256, CodeCoverage>) {
+	if !trace.code.is_empty() {
+		let hash = trace.code.sha3();
+		let entry = coverage.entry(hash).or_insert_with(|| CodeCoverage::new(&trace.code));
+		entry.executed.extend(trace.operations.iter().map(|op| op.pc));
+	}
+
+	for sub in &trace.subs {
+		collect(sub, coverage);
+	}
+}
+
+/// Merges program counters executed in the trace and all its subtraces, grouped by code.
+pub fn code_coverage(trace: &VMTrace) -> Vec<CodeCoverage> {
+	let mut coverage = BTreeMap::new();
+	collect(trace, &mut coverage);
+	coverage.into_iter().map(|(_, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use util::U256;
+	use trace::{VMTrace, VMOperation};
+	use super::code_coverage;
+
+	fn op(pc: usize, instruction: u8) -> VMOperation {
+		VMOperation {
+			pc: pc,
+			instruction: instruction,
+			gas_cost: U256::from(3),
+			executed: None,
+		}
+	}
+
+	#[test]
+	fn should_skip_push_data_and_merge_subtraces() {
+		// PUSH1 0x01 PUSH1 0x00 JUMPI STOP
+		let code = vec![0x60, 0x01, 0x60, 0x00, 0x57, 0x00];
+		let trace = VMTrace {
+			parent_step: 0,
+			code: code.clone(),
+			operations: vec![op(0, 0x60), op(2, 0x60)],
+			subs: vec![VMTrace {
+				parent_step: 1,
+				code: code,
+				operations: vec![op(0, 0x60), op(4, 0x57)],
+				subs: vec![],
+			}],
+		};
+
+		let coverage = code_coverage(&trace);
+		assert_eq!(coverage.len(), 1);
+		assert_eq!(coverage[0].instructions.iter().cloned().collect::<Vec<_>>(), vec![0, 2, 4, 5]);
+		assert_eq!(coverage[0].executed.iter().cloned().collect::<Vec<_>>(), vec![0, 2, 4]);
+	}
+}
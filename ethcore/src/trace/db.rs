@@ -134,7 +134,7 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 		TraceDB {
 			traces: RwLock::new(HashMap::new()),
 			blooms: RwLock::new(HashMap::new()),
-			cache_manager: RwLock::new(CacheManager::new(config.pref_cache_size, config.max_cache_size, 10 * 1024)),
+			cache_manager: RwLock::new(CacheManager::new(config.pref_cache_size, config.max_cache_size)),
 			tracesdb: tracesdb,
 			bloom_config: config.blooms,
 			enabled: config.enabled,
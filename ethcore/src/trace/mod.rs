@@ -18,6 +18,7 @@
 
 mod bloom;
 mod config;
+mod coverage;
 mod db;
 mod error;
 mod executive_tracer;
@@ -27,6 +28,7 @@ mod noop_tracer;
 pub use types::trace_types::{filter, flat, localized, trace};
 pub use types::trace_types::error::Error as TraceError;
 pub use self::config::Config;
+pub use self::coverage::{CodeCoverage, code_coverage};
 pub use self::db::TraceDB;
 pub use self::error::Error;
 pub use types::trace_types::trace::{VMTrace, VMOperation, VMExecutedOperation, MemoryDiff, StorageDiff};
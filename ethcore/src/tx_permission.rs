@@ -0,0 +1,169 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction permissioning through a contract configured in the chain spec.
+
+use ethabi::Token;
+use lru_cache::LruCache;
+use util::{Address, Bytes, H256, U256, Mutex};
+use transaction::{SignedTransaction, Action};
+use contract_function::{ContractFunction, as_string};
+
+const PERMISSION_ABI: &'static [u8] = br#"[{"constant":true,"inputs":[{"name":"sender","type":"address"}],"name":"allowedTxTypes","outputs":[{"name":"","type":"uint32"}],"type":"function"}]"#;
+
+/// Number of (block, sender) pairs whose allowed transaction types are cached.
+const MAX_CACHE_SIZE: usize = 4096;
+
+/// Transaction types allowed by the permission contract, as bits of its result.
+pub mod types {
+	/// Value transfers to accounts without code.
+	pub const BASIC: u32 = 0b001;
+	/// Contract calls, including value transfers to contracts.
+	pub const CALL: u32 = 0b010;
+	/// Contract creations.
+	pub const CREATE: u32 = 0b100;
+}
+
+/// Client-side interface of the transaction permission contract.
+///
+/// The contract returns the transaction types a sender may use. The result only depends on the state
+/// the contract is called on, so it is cached per block and sender.
+pub struct TransactionPermission {
+	allowed_tx_types: ContractFunction,
+	cache: Mutex<LruCache<(H256, Address), u32>>,
+}
+
+impl TransactionPermission {
+	/// Create a new permission checker.
+	pub fn new() -> Self {
+		TransactionPermission {
+			allowed_tx_types: ContractFunction::new(PERMISSION_ABI, "allowedTxTypes"),
+			cache: Mutex::new(LruCache::new(MAX_CACHE_SIZE)),
+		}
+	}
+
+	/// Ask the permission contract at `contract` whether `t` may be included in a block on top of `parent`.
+	/// `has_code` tells whether the recipient is a contract and `call` executes a read-only call
+	/// on top of the state of `parent`, returning its output. Transactions are refused if the contract
+	/// can't be called.
+	pub fn is_allowed<F>(&self, contract: Address, parent: &H256, t: &SignedTransaction, has_code: bool, call: F) -> bool
+		where F: FnOnce(Address, Bytes) -> Result<Bytes, String>
+	{
+		let tx_type = match t.action {
+			Action::Create => types::CREATE,
+			Action::Call(_) if has_code || !t.data.is_empty() => types::CALL,
+			Action::Call(_) => types::BASIC,
+		};
+
+		match self.allowed_types(contract, parent, t, call) {
+			Ok(allowed) => allowed & tx_type != 0,
+			Err(e) => {
+				warn!(target: "tx_permission", "Error checking transaction {} permission: {}", t.hash(), e);
+				false
+			}
+		}
+	}
+
+	fn allowed_types<F>(&self, contract: Address, parent: &H256, t: &SignedTransaction, call: F) -> Result<u32, String>
+		where F: FnOnce(Address, Bytes) -> Result<Bytes, String>
+	{
+		let sender = try!(t.sender().map_err(as_string));
+		let key = (parent.clone(), sender.clone());
+		if let Some(allowed) = self.cache.lock().get_mut(&key) {
+			return Ok(*allowed);
+		}
+
+		let allowed = match try!(self.allowed_tx_types.call(contract, vec![Token::Address(sender.0)], call)) {
+			Token::Uint(ref value) => U256::from(&value[..]).low_u32(),
+			e => return Err(format!("Invalid transaction permission result: {:?}", e)),
+		};
+		self.cache.lock().insert(key, allowed);
+		Ok(allowed)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::Cell;
+	use rustc_serialize::hex::{ToHex, FromHex};
+	use util::{Address, H256, U256};
+	use transaction::{Transaction, Action};
+	use super::{TransactionPermission, types};
+
+	fn transaction(action: Action, data: Vec<u8>) -> ::transaction::SignedTransaction {
+		Transaction {
+			nonce: 0.into(),
+			action: action,
+			gas: 21000.into(),
+			gas_price: 0.into(),
+			value: U256::from(7),
+			data: data,
+		}.fake_sign(0x10.into())
+	}
+
+	fn result(allowed: u32) -> Result<Vec<u8>, String> {
+		Ok(format!("{:0>64x}", allowed).from_hex().unwrap())
+	}
+
+	#[test]
+	fn should_encode_permission_call() {
+		let permission = TransactionPermission::new();
+		let contract: Address = 0x05.into();
+		let t = transaction(Action::Call(0x20.into()), vec![]);
+
+		let allowed = permission.is_allowed(contract, &H256::from(1), &t, false, |address, data| {
+			assert_eq!(address, contract);
+			assert_eq!(data[4..].to_hex(), format!("{:0>64}", "10"));
+			result(types::BASIC)
+		});
+		assert!(allowed);
+		assert!(!permission.is_allowed(contract, &H256::from(2), &t, false, |_, _| Err("Call failed".into())));
+	}
+
+	#[test]
+	fn should_check_transaction_types() {
+		let permission = TransactionPermission::new();
+		let contract: Address = 0x05.into();
+		let transfer = transaction(Action::Call(0x20.into()), vec![]);
+		let call = transaction(Action::Call(0x20.into()), vec![0xab]);
+		let create = transaction(Action::Create, vec![0xab]);
+
+		let parent = H256::from(1);
+		assert!(permission.is_allowed(contract, &parent, &transfer, false, |_, _| result(types::BASIC)));
+		assert!(!permission.is_allowed(contract, &parent, &transfer, true, |_, _| result(types::BASIC)));
+		assert!(!permission.is_allowed(contract, &parent, &call, false, |_, _| result(types::BASIC)));
+		assert!(!permission.is_allowed(contract, &parent, &create, false, |_, _| result(types::BASIC)));
+
+		let parent = H256::from(2);
+		assert!(permission.is_allowed(contract, &parent, &call, false, |_, _| result(types::CALL | types::CREATE)));
+		assert!(permission.is_allowed(contract, &parent, &create, false, |_, _| result(types::CALL | types::CREATE)));
+	}
+
+	#[test]
+	fn should_cache_per_block_and_sender() {
+		let permission = TransactionPermission::new();
+		let contract: Address = 0x05.into();
+		let t = transaction(Action::Call(0x20.into()), vec![]);
+		let calls = Cell::new(0);
+		let call = |_: Address, _: Vec<u8>| { calls.set(calls.get() + 1); result(types::BASIC) };
+
+		assert!(permission.is_allowed(contract, &H256::from(1), &t, false, &call));
+		assert!(permission.is_allowed(contract, &H256::from(1), &t, false, &call));
+		assert_eq!(calls.get(), 1);
+		assert!(permission.is_allowed(contract, &H256::from(2), &t, false, &call));
+		assert_eq!(calls.get(), 2);
+	}
+}
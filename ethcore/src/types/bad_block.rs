@@ -0,0 +1,55 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bad block type definition
+
+use util::{H256, Bytes};
+use rlp::*;
+
+/// Block which failed verification, kept for triage.
+#[derive(Debug, Clone, PartialEq, Binary)]
+pub struct BadBlock {
+	/// Hash of the block.
+	pub hash: H256,
+	/// RLP of the block.
+	pub rlp: Bytes,
+	/// Why the block was rejected.
+	pub reason: String,
+	/// UNIX timestamp of the rejection.
+	pub timestamp: u64,
+}
+
+impl Encodable for BadBlock {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.append(&self.hash);
+		s.append(&self.rlp);
+		s.append(&self.reason);
+		s.append(&self.timestamp);
+	}
+}
+
+impl Decodable for BadBlock {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		Ok(BadBlock {
+			hash: try!(d.val_at(0)),
+			rlp: try!(d.val_at(1)),
+			reason: try!(d.val_at(2)),
+			timestamp: try!(d.val_at(3)),
+		})
+	}
+}
@@ -31,5 +31,8 @@ pub struct BlockChainInfo {
 	/// Best blockchain block hash.
 	pub best_block_hash: H256,
 	/// Best blockchain block number.
-	pub best_block_number: BlockNumber
+	pub best_block_number: BlockNumber,
+	/// Number of the oldest block stored. Blocks between genesis and this one
+	/// are missing after a warp sync.
+	pub first_block_number: BlockNumber,
 }
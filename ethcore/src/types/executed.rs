@@ -198,6 +198,32 @@ impl fmt::Display for CallError {
 	}
 }
 
+/// Error computing the difference between states of two blocks.
+#[derive(PartialEq, Debug, Clone, Binary)]
+pub enum StateDiffError {
+	/// Couldn't find one of the blocks in the chain.
+	BlockNotFound,
+	/// The first block is after the second one.
+	InvalidRange,
+	/// State of one of the blocks is pruned.
+	StatePruned,
+	/// Accounts and storage can only be enumerated with fat DB.
+	FatDbRequired,
+}
+
+impl fmt::Display for StateDiffError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use self::StateDiffError::*;
+
+		match *self {
+			BlockNotFound => write!(f, "Block couldn't be found in the chain"),
+			InvalidRange => write!(f, "The first block must not be after the second one"),
+			StatePruned => write!(f, "State of the block is not available (state was pruned?)"),
+			FatDbRequired => write!(f, "State diffs require fat DB, run with --fat-db on"),
+		}
+	}
+}
+
 /// Transaction execution result.
 pub type ExecutionResult = Result<Executed, ExecutionError>;
 
@@ -0,0 +1,59 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Operating mode of the client, in a form which can be passed over IPC.
+
+use std::time::Duration;
+use client::Mode as ClientMode;
+
+/// Operating mode of the client. Timeouts are in seconds.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Binary)]
+pub enum Mode {
+	/// Always on.
+	Active,
+	/// Sleeps after the given inactivity timeout and wakes up after the given alarm.
+	Passive(u64, u64),
+	/// Sleeps after the given inactivity timeout until the client is used again.
+	Dark(u64),
+	/// Never connects to the network.
+	Off,
+	/// Verifies and relays blocks and transactions without keeping state.
+	Relay,
+}
+
+impl From<ClientMode> for Mode {
+	fn from(mode: ClientMode) -> Self {
+		match mode {
+			ClientMode::Active => Mode::Active,
+			ClientMode::Passive(timeout, alarm) => Mode::Passive(timeout.as_secs(), alarm.as_secs()),
+			ClientMode::Dark(timeout) => Mode::Dark(timeout.as_secs()),
+			ClientMode::Off => Mode::Off,
+			ClientMode::Relay => Mode::Relay,
+		}
+	}
+}
+
+impl From<Mode> for ClientMode {
+	fn from(mode: Mode) -> Self {
+		match mode {
+			Mode::Active => ClientMode::Active,
+			Mode::Passive(timeout, alarm) => ClientMode::Passive(Duration::from_secs(timeout), Duration::from_secs(alarm)),
+			Mode::Dark(timeout) => ClientMode::Dark(Duration::from_secs(timeout)),
+			Mode::Off => ClientMode::Off,
+			Mode::Relay => ClientMode::Relay,
+		}
+	}
+}
@@ -19,7 +19,7 @@
 use engines::Engine;
 use error::Error;
 
-use util::{HeapSizeOf, H256};
+use util::{Bytes, HeapSizeOf, H256};
 
 pub use self::blocks::Blocks;
 pub use self::headers::Headers;
@@ -54,10 +54,12 @@ pub trait Kind: 'static + Sized + Send + Sync {
 	type Verified: Sized + Send + HasHash + HeapSizeOf;
 
 	/// Attempt to create the `Unverified` item from the input.
-	fn create(input: Self::Input, engine: &Engine) -> Result<Self::Unverified, Error>;
+	/// On failure the encoded input is returned with the error, if the kind has one.
+	fn create(input: Self::Input, engine: &Engine) -> Result<Self::Unverified, (Error, Option<Bytes>)>;
 
 	/// Attempt to verify the `Unverified` item using the given engine.
-	fn verify(unverified: Self::Unverified, engine: &Engine) -> Result<Self::Verified, Error>;
+	/// On failure the encoded item is returned with the error, if the kind has one.
+	fn verify(unverified: Self::Unverified, engine: &Engine) -> Result<Self::Verified, (Error, Option<Bytes>)>;
 }
 
 /// The blocks verification module.
@@ -79,23 +81,23 @@ pub mod blocks {
 		type Unverified = Unverified;
 		type Verified = PreverifiedBlock;
 
-		fn create(input: Self::Input, engine: &Engine) -> Result<Self::Unverified, Error> {
+		fn create(input: Self::Input, engine: &Engine) -> Result<Self::Unverified, (Error, Option<Bytes>)> {
 			match verify_block_basic(&input.header, &input.bytes, engine) {
 				Ok(()) => Ok(input),
 				Err(e) => {
 					warn!(target: "client", "Stage 1 block verification failed for {}: {:?}", input.hash(), e);
-					Err(e)
+					Err((e, Some(input.bytes)))
 				}
 			}
 		}
 
-		fn verify(un: Self::Unverified, engine: &Engine) -> Result<Self::Verified, Error> {
+		fn verify(un: Self::Unverified, engine: &Engine) -> Result<Self::Verified, (Error, Option<Bytes>)> {
 			let hash = un.hash();
 			match verify_block_unordered(un.header, un.bytes, engine) {
 				Ok(verified) => Ok(verified),
-				Err(e) => {
+				Err((e, bytes)) => {
 					warn!(target: "client", "Stage 2 block verification failed for {}: {:?}", hash, e);
-					Err(e)
+					Err((e, Some(bytes)))
 				}
 			}
 		}
@@ -156,6 +158,7 @@ pub mod headers {
 	use header::Header;
 	use verification::verify_header_params;
 
+	use util::Bytes;
 	use util::hash::H256;
 
 	impl HasHash for Header {
@@ -172,12 +175,12 @@ pub mod headers {
 		type Unverified = Header;
 		type Verified = Header;
 
-		fn create(input: Self::Input, engine: &Engine) -> Result<Self::Unverified, Error> {
-			verify_header_params(&input, engine).map(|_| input)
+		fn create(input: Self::Input, engine: &Engine) -> Result<Self::Unverified, (Error, Option<Bytes>)> {
+			verify_header_params(&input, engine).map(|_| input).map_err(|e| (e, None))
 		}
 
-		fn verify(unverified: Self::Unverified, engine: &Engine) -> Result<Self::Verified, Error> {
-			engine.verify_block_unordered(&unverified, None).map(|_| unverified)
+		fn verify(unverified: Self::Unverified, engine: &Engine) -> Result<Self::Verified, (Error, Option<Bytes>)> {
+			engine.verify_block_unordered(&unverified, None).map(|_| unverified).map_err(|e| (e, None))
 		}
 	}
 }
@@ -34,6 +34,8 @@ pub mod kind;
 
 const MIN_MEM_LIMIT: usize = 16384;
 const MIN_QUEUE_LIMIT: usize = 512;
+/// Maximal number of failed items kept until they are taken with `take_failed`.
+const MAX_FAILED: usize = 128;
 
 /// Type alias for block queue convenience.
 pub type BlockQueue = VerificationQueue<self::kind::Blocks>;
@@ -131,10 +133,23 @@ struct Verification<K: Kind> {
 	verified: Mutex<VecDeque<K::Verified>>,
 	verifying: Mutex<VecDeque<Verifying<K>>>,
 	bad: Mutex<HashSet<H256>>,
+	failed: Mutex<VecDeque<(Bytes, String)>>,
 	more_to_verify: SMutex<()>,
 	empty: SMutex<()>,
 }
 
+impl<K: Kind> Verification<K> {
+	fn note_failed(&self, bytes: Option<Bytes>, reason: String) {
+		if let Some(bytes) = bytes {
+			let mut failed = self.failed.lock();
+			if failed.len() >= MAX_FAILED {
+				failed.pop_front();
+			}
+			failed.push_back((bytes, reason));
+		}
+	}
+}
+
 impl<K: Kind> VerificationQueue<K> {
 	/// Creates a new queue instance.
 	pub fn new(config: Config, engine: Arc<Engine>, message_channel: IoChannel<ClientIoMessage>) -> Self {
@@ -143,6 +158,7 @@ impl<K: Kind> VerificationQueue<K> {
 			verified: Mutex::new(VecDeque::new()),
 			verifying: Mutex::new(VecDeque::new()),
 			bad: Mutex::new(HashSet::new()),
+			failed: Mutex::new(VecDeque::new()),
 			more_to_verify: SMutex::new(()),
 			empty: SMutex::new(()),
 
@@ -246,7 +262,9 @@ impl<K: Kind> VerificationQueue<K> {
 						ready.set();
 					}
 				},
-				Err(_) => {
+				Err((e, bytes)) => {
+					verification.note_failed(bytes, format!("Stage 2 verification failed: {:?}", e));
+
 					let mut verifying = verification.verifying.lock();
 					let mut verified = verification.verified.lock();
 					let mut bad = verification.bad.lock();
@@ -331,13 +349,19 @@ impl<K: Kind> VerificationQueue<K> {
 				self.more_to_verify.notify_all();
 				Ok(h)
 			},
-			Err(err) => {
+			Err((err, bytes)) => {
 				self.verification.bad.lock().insert(h.clone());
+				self.verification.note_failed(bytes, format!("Stage 1 verification failed: {:?}", err));
 				Err(err)
 			}
 		}
 	}
 
+	/// Take the encoded items which failed verification since the last call, with the failure reasons.
+	pub fn take_failed(&self) -> Vec<(Bytes, String)> {
+		self.verification.failed.lock().drain(..).collect()
+	}
+
 	/// Mark given item and all its children as bad. pauses verification
 	/// until complete.
 	pub fn mark_as_bad(&self, hashes: &[H256]) {
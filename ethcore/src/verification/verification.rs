@@ -65,26 +65,31 @@ pub fn verify_block_basic(header: &Header, bytes: &[u8], engine: &Engine) -> Res
 
 /// Phase 2 verification. Perform costly checks such as transaction signatures and block nonce for ethash.
 /// Still operates on a individual block
-/// Returns a `PreverifiedBlock` structure populated with transactions
-pub fn verify_block_unordered(header: Header, bytes: Bytes, engine: &Engine) -> Result<PreverifiedBlock, Error> {
-	try!(engine.verify_block_unordered(&header, Some(&bytes)));
-	for u in try!(UntrustedRlp::new(&bytes).at(2)).iter().map(|rlp| rlp.as_val::<Header>()) {
+/// Returns a `PreverifiedBlock` structure populated with transactions, or the error together with the block bytes.
+pub fn verify_block_unordered(header: Header, bytes: Bytes, engine: &Engine) -> Result<PreverifiedBlock, (Error, Bytes)> {
+	match verify_unordered_transactions(&header, &bytes, engine) {
+		Ok(transactions) => Ok(PreverifiedBlock {
+			header: header,
+			transactions: transactions,
+			bytes: bytes,
+		}),
+		Err(e) => Err((e, bytes)),
+	}
+}
+
+fn verify_unordered_transactions(header: &Header, bytes: &[u8], engine: &Engine) -> Result<Vec<SignedTransaction>, Error> {
+	try!(engine.verify_block_unordered(header, Some(bytes)));
+	for u in try!(UntrustedRlp::new(bytes).at(2)).iter().map(|rlp| rlp.as_val::<Header>()) {
 		try!(engine.verify_block_unordered(&try!(u), None));
 	}
 	// Verify transactions.
 	let mut transactions = Vec::new();
-	{
-		let v = BlockView::new(&bytes);
-		for t in v.transactions() {
-			try!(engine.verify_transaction(&t, &header));
-			transactions.push(t);
-		}
+	let v = BlockView::new(bytes);
+	for t in v.transactions() {
+		try!(engine.verify_transaction(&t, header));
+		transactions.push(t);
 	}
-	Ok(PreverifiedBlock {
-		header: header,
-		transactions: transactions,
-		bytes: bytes,
-	})
+	Ok(transactions)
 }
 
 /// Phase 3 verification. Check block information against parent and uncles.
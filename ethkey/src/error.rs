@@ -29,6 +29,8 @@ pub enum Error {
 	InvalidSignature,
 	/// Invalid AES message
 	InvalidMessage,
+	/// Invalid key derivation
+	InvalidDerivation,
 	/// IO Error
 	Io(::std::io::Error),
 	/// Custom
@@ -43,6 +45,7 @@ impl fmt::Display for Error {
 			Error::InvalidAddress => "Invalid address".into(),
 			Error::InvalidSignature => "Invalid EC signature".into(),
 			Error::InvalidMessage => "Invalid AES message".into(),
+			Error::InvalidDerivation => "Invalid key derivation".into(),
 			Error::Io(ref err) => format!("I/O error: {}", err),
 			Error::Custom(ref s) => s.clone(),
 		};
@@ -0,0 +1,334 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hierarchical deterministic keys (BIP-32).
+
+use secp256k1::key;
+use rcrypto::hmac::Hmac;
+use rcrypto::mac::Mac;
+use rcrypto::pbkdf2::pbkdf2;
+use rcrypto::sha2::Sha512;
+use bigint::hash::H256;
+use keccak::Keccak256;
+use super::{Secret, Public, Address, KeyPair, SECP256K1, Error, public_to_address};
+
+/// Indices from this one up are reserved for hardened derivation.
+pub const HARDENED_BIT: u32 = 0x8000_0000;
+
+const MASTER_KEY: &'static [u8] = b"Bitcoin seed";
+const MNEMONIC_ITERATIONS: u32 = 2048;
+
+/// Single step of key derivation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Derivation {
+	/// Hardened derivation with given index (`index'` in BIP-32 paths). Requires the parent secret.
+	Hard(u32),
+	/// Soft derivation with given index. Can be done from the parent public key.
+	Soft(u32),
+	/// Hardened derivation with 256-bit index, e.g. a hash of an user identifier.
+	/// Not part of BIP-32.
+	HardHash(H256),
+	/// Soft derivation with 256-bit index. Not part of BIP-32.
+	SoftHash(H256),
+}
+
+impl Derivation {
+	fn is_hard(&self) -> bool {
+		match *self {
+			Derivation::Hard(_) | Derivation::HardHash(_) => true,
+			Derivation::Soft(_) | Derivation::SoftHash(_) => false,
+		}
+	}
+
+	/// Index serialized the way it is appended to HMAC input.
+	fn index_bytes(&self) -> Result<Vec<u8>, Error> {
+		match *self {
+			Derivation::Hard(index) if index < HARDENED_BIT => Ok(u32_to_bytes(index | HARDENED_BIT)),
+			Derivation::Soft(index) if index < HARDENED_BIT => Ok(u32_to_bytes(index)),
+			Derivation::Hard(_) | Derivation::Soft(_) => Err(Error::InvalidDerivation),
+			Derivation::HardHash(ref hash) | Derivation::SoftHash(ref hash) => Ok(hash[..].to_vec()),
+		}
+	}
+}
+
+/// Secret key with chain code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtendedSecret {
+	secret: Secret,
+	chain_code: H256,
+}
+
+impl ExtendedSecret {
+	/// Extended secret for a plain (non-HD) key. Its chain code is the hash of the secret,
+	/// so derived keys depend on the secret only.
+	pub fn new(secret: Secret) -> Self {
+		let chain_code = H256::from(secret[..].keccak256());
+		ExtendedSecret::with_code(secret, chain_code)
+	}
+
+	/// New extended secret with given chain code.
+	pub fn with_code(secret: Secret, chain_code: H256) -> Self {
+		ExtendedSecret {
+			secret: secret,
+			chain_code: chain_code,
+		}
+	}
+
+	/// BIP-32 master key generated from seed.
+	pub fn from_seed(seed: &[u8]) -> Result<Self, Error> {
+		let (secret, chain_code) = hmac_sha512(MASTER_KEY, seed);
+		// make sure the secret is a valid key
+		try!(key::SecretKey::from_slice(&SECP256K1, &secret));
+		Ok(ExtendedSecret::with_code(secret, chain_code))
+	}
+
+	pub fn secret(&self) -> &Secret {
+		&self.secret
+	}
+
+	pub fn chain_code(&self) -> &H256 {
+		&self.chain_code
+	}
+
+	/// Derives child key. Fails in the (unlikely) case of the derived key being invalid,
+	/// in which case BIP-32 suggests to proceed with the next index.
+	pub fn derive(&self, derivation: &Derivation) -> Result<Self, Error> {
+		let mut data = Vec::with_capacity(65);
+		if derivation.is_hard() {
+			data.push(0);
+			data.extend_from_slice(&self.secret);
+		} else {
+			let keypair = try!(KeyPair::from_secret(self.secret.clone()));
+			data.extend_from_slice(&compress(keypair.public()));
+		}
+		data.extend(try!(derivation.index_bytes()));
+
+		let (tweak, chain_code) = hmac_sha512(&self.chain_code, &data);
+		let context = &SECP256K1;
+		let mut child = try!(key::SecretKey::from_slice(context, &tweak));
+		let parent = try!(key::SecretKey::from_slice(context, &self.secret));
+		try!(child.add_assign(context, &parent));
+
+		let mut secret = Secret::default();
+		secret.copy_from_slice(&child[0..32]);
+		Ok(ExtendedSecret::with_code(secret, chain_code))
+	}
+
+	/// Derives key along the path.
+	pub fn derive_path(&self, path: &[Derivation]) -> Result<Self, Error> {
+		path.iter().fold(Ok(self.clone()), |key, derivation| key.and_then(|key| key.derive(derivation)))
+	}
+}
+
+/// Public key with chain code. Allows to derive soft children without knowing the secret.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtendedPublic {
+	public: Public,
+	chain_code: H256,
+}
+
+impl ExtendedPublic {
+	/// New extended public key with given chain code.
+	pub fn with_code(public: Public, chain_code: H256) -> Self {
+		ExtendedPublic {
+			public: public,
+			chain_code: chain_code,
+		}
+	}
+
+	/// Public key of given extended secret.
+	pub fn from_secret(secret: &ExtendedSecret) -> Result<Self, Error> {
+		let keypair = try!(KeyPair::from_secret(secret.secret.clone()));
+		Ok(ExtendedPublic::with_code(keypair.public().clone(), secret.chain_code.clone()))
+	}
+
+	pub fn public(&self) -> &Public {
+		&self.public
+	}
+
+	pub fn chain_code(&self) -> &H256 {
+		&self.chain_code
+	}
+
+	pub fn address(&self) -> Address {
+		public_to_address(&self.public)
+	}
+
+	/// Derives soft child key. Hardened derivation requires the secret and fails.
+	pub fn derive(&self, derivation: &Derivation) -> Result<Self, Error> {
+		if derivation.is_hard() {
+			return Err(Error::InvalidDerivation);
+		}
+
+		let mut data = Vec::with_capacity(65);
+		data.extend_from_slice(&compress(&self.public));
+		data.extend(try!(derivation.index_bytes()));
+
+		let (tweak, chain_code) = hmac_sha512(&self.chain_code, &data);
+		let context = &SECP256K1;
+		let tweak = try!(key::SecretKey::from_slice(context, &tweak));
+		let mut uncompressed = [4u8; 65];
+		uncompressed[1..65].copy_from_slice(&self.public);
+		let mut child = try!(key::PublicKey::from_slice(context, &uncompressed));
+		try!(child.add_exp_assign(context, &tweak));
+
+		let serialized = child.serialize_vec(context, false);
+		let mut public = Public::default();
+		public.copy_from_slice(&serialized[1..65]);
+		Ok(ExtendedPublic::with_code(public, chain_code))
+	}
+
+	/// Derives key along the path.
+	pub fn derive_path(&self, path: &[Derivation]) -> Result<Self, Error> {
+		path.iter().fold(Ok(self.clone()), |key, derivation| key.and_then(|key| key.derive(derivation)))
+	}
+}
+
+/// Extended secret along with its public key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtendedKeyPair {
+	secret: ExtendedSecret,
+	public: ExtendedPublic,
+}
+
+impl ExtendedKeyPair {
+	pub fn new(secret: ExtendedSecret) -> Result<Self, Error> {
+		let public = try!(ExtendedPublic::from_secret(&secret));
+		Ok(ExtendedKeyPair {
+			secret: secret,
+			public: public,
+		})
+	}
+
+	/// BIP-32 master key pair generated from seed.
+	pub fn from_seed(seed: &[u8]) -> Result<Self, Error> {
+		ExtendedSecret::from_seed(seed).and_then(ExtendedKeyPair::new)
+	}
+
+	pub fn secret(&self) -> &ExtendedSecret {
+		&self.secret
+	}
+
+	pub fn public(&self) -> &ExtendedPublic {
+		&self.public
+	}
+
+	pub fn derive(&self, derivation: &Derivation) -> Result<Self, Error> {
+		self.secret.derive(derivation).and_then(ExtendedKeyPair::new)
+	}
+
+	pub fn derive_path(&self, path: &[Derivation]) -> Result<Self, Error> {
+		self.secret.derive_path(path).and_then(ExtendedKeyPair::new)
+	}
+}
+
+/// BIP-39 seed of mnemonic phrase protected with optional passphrase.
+/// Both are expected to be in NFKD form, which plain ASCII text always is.
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> Vec<u8> {
+	let mut mac = Hmac::new(Sha512::new(), mnemonic.as_bytes());
+	let salt = format!("mnemonic{}", passphrase);
+	let mut seed = vec![0u8; 64];
+	pbkdf2(&mut mac, salt.as_bytes(), MNEMONIC_ITERATIONS, &mut seed);
+	seed
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> (H256, H256) {
+	let mut hmac = Hmac::new(Sha512::new(), key);
+	hmac.input(data);
+	let mut output = [0u8; 64];
+	hmac.raw_result(&mut output);
+
+	let mut left = H256::default();
+	left.copy_from_slice(&output[0..32]);
+	let mut right = H256::default();
+	right.copy_from_slice(&output[32..64]);
+	(left, right)
+}
+
+/// Compressed SEC1 encoding of public key.
+fn compress(public: &Public) -> [u8; 33] {
+	let mut result = [0u8; 33];
+	result[0] = if public[63] & 1 == 0 { 2 } else { 3 };
+	result[1..33].copy_from_slice(&public[0..32]);
+	result
+}
+
+fn u32_to_bytes(value: u32) -> Vec<u8> {
+	vec![(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::{FromHex, ToHex};
+	use Secret;
+	use bigint::hash::H256;
+	use super::{ExtendedSecret, ExtendedPublic, ExtendedKeyPair, Derivation, mnemonic_to_seed};
+
+	fn master() -> ExtendedKeyPair {
+		// BIP-32 test vector 1
+		ExtendedKeyPair::from_seed(&"000102030405060708090a0b0c0d0e0f".from_hex().unwrap()).unwrap()
+	}
+
+	#[test]
+	fn should_generate_master_key_from_seed() {
+		let master = master();
+		assert_eq!(master.secret().secret(), &Secret::from_str("e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35").unwrap());
+		assert_eq!(master.secret().chain_code(), &H256::from_str("873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508").unwrap());
+	}
+
+	#[test]
+	fn should_derive_along_bip32_path() {
+		let master = master();
+		let child = master.derive(&Derivation::Hard(0)).unwrap();
+		assert_eq!(child.secret().secret(), &Secret::from_str("edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea").unwrap());
+		assert_eq!(child.secret().chain_code(), &H256::from_str("47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141").unwrap());
+
+		let path = [Derivation::Hard(0), Derivation::Soft(1), Derivation::Hard(2)];
+		let child = master.derive_path(&path).unwrap();
+		assert_eq!(child.secret().secret(), &Secret::from_str("cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca").unwrap());
+	}
+
+	#[test]
+	fn soft_public_derivation_should_match_secret_derivation() {
+		let parent = master().derive(&Derivation::Hard(44)).unwrap();
+		for derivation in &[Derivation::Soft(0), Derivation::Soft(7), Derivation::SoftHash(H256::from(42))] {
+			let from_secret = parent.derive(derivation).unwrap();
+			let from_public = parent.public().derive(derivation).unwrap();
+			assert_eq!(from_secret.public(), &from_public);
+		}
+	}
+
+	#[test]
+	fn should_not_derive_hardened_keys_from_public() {
+		let public = ExtendedPublic::from_secret(master().secret()).unwrap();
+		assert!(public.derive(&Derivation::Hard(0)).is_err());
+		assert!(public.derive(&Derivation::HardHash(H256::from(1))).is_err());
+	}
+
+	#[test]
+	fn should_reject_out_of_range_index() {
+		let secret = ExtendedSecret::with_code(Secret::from(1), H256::default());
+		assert!(secret.derive(&Derivation::Soft(0x8000_0000)).is_err());
+		assert!(secret.derive(&Derivation::Hard(0x8000_0000)).is_err());
+	}
+
+	#[test]
+	fn should_compute_bip39_seed() {
+		let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+		assert_eq!(mnemonic_to_seed(mnemonic, "TREZOR").to_hex(), "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+	}
+}
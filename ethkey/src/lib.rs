@@ -20,16 +20,20 @@ extern crate lazy_static;
 extern crate tiny_keccak;
 extern crate secp256k1;
 extern crate rustc_serialize;
+extern crate crypto as rcrypto;
 extern crate ethcore_bigint as bigint;
 
 mod brain;
 mod error;
+mod extended;
 mod keypair;
 mod keccak;
 mod prefix;
 mod random;
 mod signature;
 
+pub mod math;
+
 lazy_static! {
 	pub static ref SECP256K1: secp256k1::Secp256k1 = secp256k1::Secp256k1::new();
 }
@@ -42,6 +46,7 @@ pub trait Generator {
 
 pub use self::brain::Brain;
 pub use self::error::Error;
+pub use self::extended::{ExtendedSecret, ExtendedPublic, ExtendedKeyPair, Derivation, HARDENED_BIT, mnemonic_to_seed};
 pub use self::keypair::{KeyPair, public_to_address};
 pub use self::prefix::Prefix;
 pub use self::random::Random;
@@ -0,0 +1,158 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Arithmetic on curve points and scalars (modulo curve order).
+
+use secp256k1::key;
+use super::{Secret, Public, SECP256K1, Error, KeyPair};
+
+/// Returns the generator point of the curve.
+pub fn generation_point() -> Public {
+	let mut one = Secret::default();
+	one[31] = 1;
+	KeyPair::from_secret(one).expect("1 is a valid secret; qed").public().clone()
+}
+
+/// Inplace multiply public key by secret key (EC point * scalar).
+pub fn public_mul_secret(public: &mut Public, secret: &Secret) -> Result<(), Error> {
+	let context = &SECP256K1;
+	let secret = try!(key::SecretKey::from_slice(context, secret));
+	let mut key_public = try!(to_secp256k1_public(public));
+	try!(key_public.mul_assign(context, &secret));
+	set_public(public, &key_public);
+	Ok(())
+}
+
+/// Inplace add one public key to another (EC point + EC point).
+pub fn public_add(public: &mut Public, other: &Public) -> Result<(), Error> {
+	let mut key_public = try!(to_secp256k1_public(public));
+	let other = try!(to_secp256k1_public(other));
+	try!(key_public.add_assign(&SECP256K1, &other));
+	set_public(public, &key_public);
+	Ok(())
+}
+
+/// Inplace negate public key (-EC point).
+pub fn public_negate(public: &mut Public) -> Result<(), Error> {
+	let context = &SECP256K1;
+	let mut key_public = try!(to_secp256k1_public(public));
+	try!(key_public.mul_assign(context, &key::MINUS_ONE_KEY));
+	set_public(public, &key_public);
+	Ok(())
+}
+
+/// Inplace add one secret to another (scalar + scalar).
+pub fn secret_add(secret: &mut Secret, other: &Secret) -> Result<(), Error> {
+	let context = &SECP256K1;
+	let mut key_secret = try!(key::SecretKey::from_slice(context, secret));
+	let other = try!(key::SecretKey::from_slice(context, other));
+	try!(key_secret.add_assign(context, &other));
+	set_secret(secret, &key_secret);
+	Ok(())
+}
+
+/// Inplace subtract one secret from another (scalar - scalar).
+pub fn secret_sub(secret: &mut Secret, other: &Secret) -> Result<(), Error> {
+	let context = &SECP256K1;
+	let mut key_secret = try!(key::SecretKey::from_slice(context, secret));
+	let mut other = try!(key::SecretKey::from_slice(context, other));
+	try!(other.mul_assign(context, &key::MINUS_ONE_KEY));
+	try!(key_secret.add_assign(context, &other));
+	set_secret(secret, &key_secret);
+	Ok(())
+}
+
+/// Inplace multiply one secret by another (scalar * scalar).
+pub fn secret_mul(secret: &mut Secret, other: &Secret) -> Result<(), Error> {
+	let context = &SECP256K1;
+	let mut key_secret = try!(key::SecretKey::from_slice(context, secret));
+	let other = try!(key::SecretKey::from_slice(context, other));
+	try!(key_secret.mul_assign(context, &other));
+	set_secret(secret, &key_secret);
+	Ok(())
+}
+
+/// Inplace inverse secret (1 / scalar).
+pub fn secret_inv(secret: &mut Secret) -> Result<(), Error> {
+	let context = &SECP256K1;
+	let mut key_secret = try!(key::SecretKey::from_slice(context, secret));
+	try!(key_secret.inv_assign(context));
+	set_secret(secret, &key_secret);
+	Ok(())
+}
+
+fn to_secp256k1_public(public: &Public) -> Result<key::PublicKey, Error> {
+	let mut uncompressed = [4u8; 65];
+	uncompressed[1..65].copy_from_slice(public);
+	Ok(try!(key::PublicKey::from_slice(&SECP256K1, &uncompressed)))
+}
+
+fn set_public(public: &mut Public, key_public: &key::PublicKey) {
+	let serialized = key_public.serialize_vec(&SECP256K1, false);
+	public.copy_from_slice(&serialized[1..65]);
+}
+
+fn set_secret(secret: &mut Secret, key_secret: &key::SecretKey) {
+	secret.copy_from_slice(&key_secret[0..32]);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::super::{Random, Generator};
+	use super::{generation_point, public_mul_secret, public_add, public_negate, secret_add, secret_sub, secret_mul, secret_inv};
+
+	#[test]
+	fn public_arithmetic_should_match_secret_arithmetic() {
+		let a = Random.generate().unwrap();
+		let b = Random.generate().unwrap();
+
+		// (a + b) * G == a * G + b * G
+		let mut sum = a.secret().clone();
+		secret_add(&mut sum, b.secret()).unwrap();
+		let mut sum_public = generation_point();
+		public_mul_secret(&mut sum_public, &sum).unwrap();
+		let mut public = a.public().clone();
+		public_add(&mut public, b.public()).unwrap();
+		assert_eq!(sum_public, public);
+
+		// (a - b) * G + b * G == a * G
+		let mut diff = a.secret().clone();
+		secret_sub(&mut diff, b.secret()).unwrap();
+		let mut diff_public = generation_point();
+		public_mul_secret(&mut diff_public, &diff).unwrap();
+		public_add(&mut diff_public, b.public()).unwrap();
+		assert_eq!(&diff_public, a.public());
+
+		// a * G - a * G is not a point, so check -(a * G) + (a + b) * G == b * G instead
+		let mut negated = a.public().clone();
+		public_negate(&mut negated).unwrap();
+		public_add(&mut negated, &sum_public).unwrap();
+		assert_eq!(&negated, b.public());
+	}
+
+	#[test]
+	fn secret_inverse_should_be_multiplicative_inverse() {
+		let a = Random.generate().unwrap();
+		let b = Random.generate().unwrap();
+
+		let mut inv = b.secret().clone();
+		secret_inv(&mut inv).unwrap();
+		let mut result = a.secret().clone();
+		secret_mul(&mut result, b.secret()).unwrap();
+		secret_mul(&mut result, &inv).unwrap();
+		assert_eq!(&result, a.secret());
+	}
+}
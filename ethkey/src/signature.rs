@@ -78,6 +78,13 @@ impl PartialEq for Signature {
 // manual implementation required in Rust 1.13+, see `std::cmp::AssertParamIsEq`.
 impl Eq for Signature { }
 
+// manual implementation large arrays don't have trait impls by default.
+impl Clone for Signature {
+	fn clone(&self) -> Self {
+		Signature(self.0)
+	}
+}
+
 // also manual for the same reason, but the pretty printing might be useful.
 impl fmt::Debug for Signature {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
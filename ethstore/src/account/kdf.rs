@@ -44,6 +44,21 @@ pub enum Kdf {
 	Scrypt(Scrypt),
 }
 
+/// Key derivation function and its cost used for new key files.
+#[derive(Debug, PartialEq, Clone)]
+pub enum KdfParams {
+	/// PBKDF2 with HMAC-SHA256 and given number of iterations.
+	Pbkdf2 {
+		iterations: u32,
+	},
+	/// Scrypt with given CPU/memory cost (power of two), block size and parallelization.
+	Scrypt {
+		n: u32,
+		r: u32,
+		p: u32,
+	},
+}
+
 impl From<json::Prf> for Prf {
 	fn from(json: json::Prf) -> Self {
 		match json {
@@ -20,6 +20,6 @@ mod safe_account;
 mod version;
 
 pub use self::cipher::{Cipher, Aes128Ctr};
-pub use self::kdf::{Kdf, Pbkdf2, Scrypt, Prf};
+pub use self::kdf::{Kdf, KdfParams, Pbkdf2, Scrypt, Prf};
 pub use self::safe_account::{SafeAccount, Crypto};
 pub use self::version::Version;
@@ -18,7 +18,7 @@ use ethkey::{KeyPair, sign, Address, Secret, Signature, Message};
 use {json, Error, crypto};
 use crypto::Keccak256;
 use random::Random;
-use account::{Version, Cipher, Kdf, Aes128Ctr, Pbkdf2, Prf};
+use account::{Version, Cipher, Kdf, KdfParams, Aes128Ctr, Pbkdf2, Scrypt, Prf};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Crypto {
@@ -75,13 +75,16 @@ impl Into<json::KeyFile> for SafeAccount {
 }
 
 impl Crypto {
-	pub fn create(secret: &Secret, password: &str, iterations: u32) -> Self {
+	pub fn create(secret: &Secret, password: &str, kdf: &KdfParams) -> Self {
 		let salt: [u8; 32] = Random::random();
 		let iv: [u8; 16] = Random::random();
 
 		// two parts of derived key
 		// DK = [ DK[0..15] DK[16..31] ] = [derived_left_bits, derived_right_bits]
-		let (derived_left_bits, derived_right_bits) = crypto::derive_key_iterations(password, &salt, iterations);
+		let (derived_left_bits, derived_right_bits) = match *kdf {
+			KdfParams::Pbkdf2 { iterations } => crypto::derive_key_iterations(password, &salt, iterations),
+			KdfParams::Scrypt { n, r, p } => crypto::derive_key_scrypt(password, &salt, n, p, r),
+		};
 
 		let mut ciphertext = [0u8; 32];
 
@@ -96,12 +99,21 @@ impl Crypto {
 				iv: iv,
 			}),
 			ciphertext: ciphertext.to_vec(),
-			kdf: Kdf::Pbkdf2(Pbkdf2 {
-				dklen: crypto::KEY_LENGTH as u32,
-				salt: salt,
-				c: iterations,
-				prf: Prf::HmacSha256,
-			}),
+			kdf: match *kdf {
+				KdfParams::Pbkdf2 { iterations } => Kdf::Pbkdf2(Pbkdf2 {
+					dklen: crypto::KEY_LENGTH as u32,
+					salt: salt,
+					c: iterations,
+					prf: Prf::HmacSha256,
+				}),
+				KdfParams::Scrypt { n, r, p } => Kdf::Scrypt(Scrypt {
+					dklen: crypto::KEY_LENGTH as u32,
+					p: p,
+					n: n,
+					r: r,
+					salt: salt,
+				}),
+			},
 			mac: mac,
 		}
 	}
@@ -140,14 +152,14 @@ impl SafeAccount {
 		keypair: &KeyPair,
 		id: [u8; 16],
 		password: &str,
-		iterations: u32,
+		kdf: &KdfParams,
 		name: String,
 		meta: String
 	) -> Self {
 		SafeAccount {
 			id: id,
 			version: Version::V3,
-			crypto: Crypto::create(keypair.secret(), password, iterations),
+			crypto: Crypto::create(keypair.secret(), password, kdf),
 			address: keypair.address(),
 			filename: None,
 			name: name,
@@ -180,12 +192,12 @@ impl SafeAccount {
 		crypto::ecies::decrypt(&secret, shared_mac, message).map_err(From::from)
 	}
 
-	pub fn change_password(&self, old_password: &str, new_password: &str, iterations: u32) -> Result<Self, Error> {
+	pub fn change_password(&self, old_password: &str, new_password: &str, kdf: &KdfParams) -> Result<Self, Error> {
 		let secret = try!(self.crypto.secret(old_password));
 		let result = SafeAccount {
 			id: self.id.clone(),
 			version: self.version.clone(),
-			crypto: Crypto::create(&secret, new_password, iterations),
+			crypto: Crypto::create(&secret, new_password, kdf),
 			address: self.address.clone(),
 			filename: self.filename.clone(),
 			name: self.name.clone(),
@@ -202,21 +214,37 @@ impl SafeAccount {
 #[cfg(test)]
 mod tests {
 	use ethkey::{Generator, Random, verify_public, Message};
+	use account::{Kdf, KdfParams};
 	use super::{Crypto, SafeAccount};
 
+	const PBKDF2: KdfParams = KdfParams::Pbkdf2 { iterations: 10240 };
+
 	#[test]
 	fn crypto_create() {
 		let keypair = Random.generate().unwrap();
-		let crypto = Crypto::create(keypair.secret(), "this is sparta", 10240);
+		let crypto = Crypto::create(keypair.secret(), "this is sparta", &PBKDF2);
+		let secret = crypto.secret("this is sparta").unwrap();
+		assert_eq!(keypair.secret(), &secret);
+	}
+
+	#[test]
+	fn crypto_create_with_scrypt() {
+		let keypair = Random.generate().unwrap();
+		let crypto = Crypto::create(keypair.secret(), "this is sparta", &KdfParams::Scrypt { n: 1024, r: 8, p: 1 });
+		match crypto.kdf {
+			Kdf::Scrypt(ref params) => assert_eq!((params.n, params.r, params.p), (1024, 8, 1)),
+			_ => panic!("Expected scrypt key derivation."),
+		}
 		let secret = crypto.secret("this is sparta").unwrap();
 		assert_eq!(keypair.secret(), &secret);
+		assert!(crypto.secret("this is sparta!").is_err());
 	}
 
 	#[test]
 	#[should_panic]
 	fn crypto_invalid_password() {
 		let keypair = Random.generate().unwrap();
-		let crypto = Crypto::create(keypair.secret(), "this is sparta", 10240);
+		let crypto = Crypto::create(keypair.secret(), "this is sparta", &PBKDF2);
 		let _ = crypto.secret("this is sparta!").unwrap();
 	}
 
@@ -225,7 +253,7 @@ mod tests {
 		let keypair = Random.generate().unwrap();
 		let password = "hello world";
 		let message = Message::default();
-		let account = SafeAccount::create(&keypair, [0u8; 16], password, 10240, "Test".to_owned(), "{}".to_owned());
+		let account = SafeAccount::create(&keypair, [0u8; 16], password, &PBKDF2, "Test".to_owned(), "{}".to_owned());
 		let signature = account.sign(password, &message).unwrap();
 		assert!(verify_public(keypair.public(), &signature, &message).unwrap());
 	}
@@ -235,10 +263,9 @@ mod tests {
 		let keypair = Random.generate().unwrap();
 		let first_password = "hello world";
 		let sec_password = "this is sparta";
-		let i = 10240;
 		let message = Message::default();
-		let account = SafeAccount::create(&keypair, [0u8; 16], first_password, i, "Test".to_owned(), "{}".to_owned());
-		let new_account = account.change_password(first_password, sec_password, i).unwrap();
+		let account = SafeAccount::create(&keypair, [0u8; 16], first_password, &PBKDF2, "Test".to_owned(), "{}".to_owned());
+		let new_account = account.change_password(first_password, sec_password, &PBKDF2).unwrap();
 		assert!(account.sign(first_password, &message).is_ok());
 		assert!(account.sign(sec_password, &message).is_err());
 		assert!(new_account.sign(first_password, &message).is_err());
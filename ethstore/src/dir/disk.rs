@@ -22,10 +22,10 @@ use ethkey::Address;
 use {json, SafeAccount, Error};
 use super::KeyDirectory;
 
-const IGNORED_FILES: &'static [&'static str] = &["thumbs.db", "address_book.json"];
+const IGNORED_FILES: &'static [&'static str] = &["thumbs.db", "address_book.json", "vault.json"];
 
 #[cfg(not(windows))]
-fn restrict_permissions_to_owner(file_path: &Path) -> Result<(), i32>  {
+pub fn restrict_permissions_to_owner(file_path: &Path) -> Result<(), i32>  {
 	use std::ffi;
 	use libc;
 	let cstr = ffi::CString::new(file_path.to_str().unwrap()).unwrap();
@@ -36,7 +36,7 @@ fn restrict_permissions_to_owner(file_path: &Path) -> Result<(), i32>  {
 }
 
 #[cfg(windows)]
-fn restrict_permissions_to_owner(_file_path: &Path) -> Result<(), i32> {
+pub fn restrict_permissions_to_owner(_file_path: &Path) -> Result<(), i32> {
 	Ok(())
 }
 
@@ -158,7 +158,7 @@ mod test {
 	use std::{env, fs};
 	use super::DiskDirectory;
 	use dir::KeyDirectory;
-	use account::SafeAccount;
+	use account::{SafeAccount, KdfParams};
 	use ethkey::{Random, Generator};
 
 	#[test]
@@ -170,7 +170,7 @@ mod test {
 		let directory = DiskDirectory::create(dir.clone()).unwrap();
 
 		// when
-		let account = SafeAccount::create(&keypair, [0u8; 16], password, 1024, "Test".to_owned(), "{}".to_owned());
+		let account = SafeAccount::create(&keypair, [0u8; 16], password, &KdfParams::Pbkdf2 { iterations: 1024 }, "Test".to_owned(), "{}".to_owned());
 		let res = directory.insert(account);
 
 
@@ -21,6 +21,7 @@ use {SafeAccount, Error};
 mod disk;
 mod geth;
 mod parity;
+mod vault;
 
 pub enum DirectoryType {
 	Testnet,
@@ -37,3 +38,4 @@ pub trait KeyDirectory: Send + Sync {
 pub use self::disk::DiskDirectory;
 pub use self::geth::GethDirectory;
 pub use self::parity::ParityDirectory;
+pub use self::vault::{VaultDiskDirectory, list_vaults, vault_meta};
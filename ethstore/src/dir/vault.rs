@@ -0,0 +1,209 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::{PathBuf, Path};
+use ethkey::{Address, Random, Generator};
+use account::{Crypto, KdfParams};
+use {json, SafeAccount, Error};
+use super::{KeyDirectory, DiskDirectory};
+use super::disk::restrict_permissions_to_owner;
+
+/// Name of the file describing the vault.
+pub const VAULT_FILE_NAME: &'static str = "vault.json";
+
+/// Key directory stored in a named subdirectory of the main keys directory.
+/// Holds the vault password while the vault is opened.
+pub struct VaultDiskDirectory {
+	dir: DiskDirectory,
+	path: PathBuf,
+	name: String,
+	password: String,
+}
+
+impl VaultDiskDirectory {
+	/// Creates new vault `name` in `root` directory, protected with `password`.
+	pub fn create<P>(root: P, name: &str, password: &str, kdf: &KdfParams) -> Result<Self, Error> where P: AsRef<Path> {
+		let path = try!(vault_path(root, name));
+		if path.exists() {
+			return Err(Error::VaultAlreadyExists);
+		}
+
+		let secret = try!(Random.generate()).secret().clone();
+		let file = json::VaultFile {
+			crypto: Crypto::create(&secret, password, kdf).into(),
+			meta: "{}".to_owned(),
+		};
+
+		let dir = try!(DiskDirectory::create(&path));
+		if let Err(err) = write_vault_file(&path, &file) {
+			let _ = fs::remove_dir_all(&path);
+			return Err(err);
+		}
+
+		Ok(VaultDiskDirectory {
+			dir: dir,
+			path: path,
+			name: name.to_owned(),
+			password: password.to_owned(),
+		})
+	}
+
+	/// Opens existing vault `name` in `root` directory. Fails if the password is invalid.
+	pub fn at<P>(root: P, name: &str, password: &str) -> Result<Self, Error> where P: AsRef<Path> {
+		let path = try!(vault_path(root, name));
+		let file = try!(read_vault_file(&path));
+		let crypto: Crypto = file.crypto.into();
+		try!(crypto.secret(password));
+
+		Ok(VaultDiskDirectory {
+			dir: DiskDirectory::at(&path),
+			path: path,
+			name: name.to_owned(),
+			password: password.to_owned(),
+		})
+	}
+
+	/// Vault name.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Password used to encrypt all keys stored in the vault.
+	pub fn password(&self) -> &str {
+		&self.password
+	}
+
+	/// Vault metadata.
+	pub fn meta(&self) -> Result<String, Error> {
+		read_vault_file(&self.path).map(|file| file.meta)
+	}
+
+	/// Replaces vault metadata.
+	pub fn set_meta(&self, meta: &str) -> Result<(), Error> {
+		let mut file = try!(read_vault_file(&self.path));
+		file.meta = meta.to_owned();
+		write_vault_file(&self.path, &file)
+	}
+}
+
+impl KeyDirectory for VaultDiskDirectory {
+	fn load(&self) -> Result<Vec<SafeAccount>, Error> {
+		self.dir.load()
+	}
+
+	fn insert(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
+		self.dir.insert(account)
+	}
+
+	fn remove(&self, address: &Address) -> Result<(), Error> {
+		self.dir.remove(address)
+	}
+
+	fn path(&self) -> Option<&PathBuf> { Some(&self.path) }
+}
+
+/// Returns metadata of vault `name` in `root` directory. The vault does not need to be opened.
+pub fn vault_meta<P>(root: P, name: &str) -> Result<String, Error> where P: AsRef<Path> {
+	let path = try!(vault_path(root, name));
+	read_vault_file(&path).map(|file| file.meta)
+}
+
+/// Lists names of all vaults found in `root` directory.
+pub fn list_vaults<P>(root: P) -> Result<Vec<String>, Error> where P: AsRef<Path> {
+	let mut vaults: Vec<String> = try!(fs::read_dir(root))
+		.flat_map(Result::ok)
+		.filter(|entry| entry.path().join(VAULT_FILE_NAME).is_file())
+		.filter_map(|entry| entry.file_name().to_str().map(ToOwned::to_owned))
+		.collect();
+	vaults.sort();
+	Ok(vaults)
+}
+
+/// Vault names become directory names, so they must not escape `root`.
+fn vault_path<P>(root: P, name: &str) -> Result<PathBuf, Error> where P: AsRef<Path> {
+	let valid = !name.is_empty()
+		&& !name.starts_with(".")
+		&& !name.contains(|c: char| c == '/' || c == '\\' || c == ':');
+	if !valid {
+		return Err(Error::InvalidVaultName);
+	}
+	Ok(root.as_ref().join(name))
+}
+
+fn read_vault_file(vault_path: &Path) -> Result<json::VaultFile, Error> {
+	let path = vault_path.join(VAULT_FILE_NAME);
+	if !path.is_file() {
+		return Err(Error::VaultNotFound);
+	}
+	let file = try!(fs::File::open(&path));
+	json::VaultFile::load(file).map_err(|e| Error::Custom(format!("Invalid vault file {:?}: {:?}", path, e)))
+}
+
+fn write_vault_file(vault_path: &Path, file: &json::VaultFile) -> Result<(), Error> {
+	let path = vault_path.join(VAULT_FILE_NAME);
+	let mut out = try!(fs::File::create(&path));
+	try!(file.write(&mut out).map_err(|e| Error::Custom(format!("{:?}", e))));
+	try!(restrict_permissions_to_owner(&path).map_err(|_| Error::Io(::std::io::Error::last_os_error())));
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use std::{env, fs};
+	use account::KdfParams;
+	use dir::KeyDirectory;
+	use super::{VaultDiskDirectory, list_vaults, vault_meta};
+	use Error;
+
+	fn kdf() -> KdfParams {
+		KdfParams::Pbkdf2 { iterations: 1024 }
+	}
+
+	#[test]
+	fn should_create_and_open_vault() {
+		let mut root = env::temp_dir();
+		root.push("ethstore_should_create_and_open_vault");
+		let _ = fs::remove_dir_all(&root);
+		fs::create_dir_all(&root).unwrap();
+
+		{
+			let vault = VaultDiskDirectory::create(&root, "trading", "pass", &kdf()).unwrap();
+			assert_eq!(vault.name(), "trading");
+			assert!(vault.load().unwrap().is_empty());
+			vault.set_meta("{\"description\":\"hot keys\"}").unwrap();
+		}
+
+		assert!(VaultDiskDirectory::create(&root, "trading", "pass", &kdf()).is_err());
+		assert!(VaultDiskDirectory::at(&root, "trading", "wrong").is_err());
+		assert!(VaultDiskDirectory::at(&root, "trading", "pass").is_ok());
+		assert_eq!(vault_meta(&root, "trading").unwrap(), "{\"description\":\"hot keys\"}");
+		assert_eq!(list_vaults(&root).unwrap(), vec!["trading".to_owned()]);
+
+		let _ = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn should_reject_invalid_vault_names() {
+		let root = env::temp_dir();
+		for name in &["", ".hidden", "../escape", "a/b", "a\\b"] {
+			match VaultDiskDirectory::create(&root, name, "pass", &kdf()) {
+				Err(Error::InvalidVaultName) => {},
+				_ => panic!("vault name {:?} should be rejected", name),
+			}
+		}
+	}
+}
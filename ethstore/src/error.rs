@@ -27,7 +27,13 @@ pub enum Error {
 	InvalidAccount,
 	InvalidMessage,
 	InvalidKeyFile(String),
+	InvalidTypedData(String),
 	CreationFailed,
+	InvalidVaultName,
+	VaultAlreadyExists,
+	VaultNotFound,
+	VaultsNotSupported,
+	VaultAccountPassword,
 	EthKey(EthKeyError),
 	EthCrypto(EthCryptoError),
 	Custom(String),
@@ -42,7 +48,13 @@ impl fmt::Display for Error {
 			Error::InvalidAccount => "Invalid account".into(),
 			Error::InvalidMessage => "Invalid message".into(),
 			Error::InvalidKeyFile(ref reason) => format!("Invalid key file: {}", reason),
+			Error::InvalidTypedData(ref reason) => format!("Invalid typed data: {}", reason),
 			Error::CreationFailed => "Account creation failed".into(),
+			Error::InvalidVaultName => "Invalid vault name".into(),
+			Error::VaultAlreadyExists => "Vault already exists".into(),
+			Error::VaultNotFound => "Vault not found".into(),
+			Error::VaultsNotSupported => "Vaults are not supported by this key directory".into(),
+			Error::VaultAccountPassword => "Accounts in a vault are protected by the vault password".into(),
 			Error::EthKey(ref err) => err.to_string(),
 			Error::EthCrypto(ref err) => err.to_string(),
 			Error::Custom(ref s) => s.clone(),
@@ -14,25 +14,33 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::sync::RwLock;
 use std::mem;
 use ethkey::KeyPair;
 use crypto::KEY_ITERATIONS;
 use random::Random;
-use ethkey::{Signature, Address, Message, Secret};
-use dir::KeyDirectory;
-use account::SafeAccount;
+use ethkey::{Signature, Address, Message, Secret, Derivation, ExtendedSecret};
+use dir::{KeyDirectory, VaultDiskDirectory, list_vaults, vault_meta};
+use account::{SafeAccount, KdfParams};
 use {Error, SecretStore};
 use json;
 use json::UUID;
 use presale::PresaleWallet;
 use import;
 
+/// Opened vault along with the accounts it holds.
+struct Vault {
+	dir: VaultDiskDirectory,
+	cache: BTreeMap<Address, SafeAccount>,
+}
+
 pub struct EthStore {
 	dir: Box<KeyDirectory>,
-	iterations: u32,
+	kdf: KdfParams,
 	cache: RwLock<BTreeMap<Address, SafeAccount>>,
+	vaults: RwLock<HashMap<String, Vault>>,
 }
 
 impl EthStore {
@@ -41,17 +49,33 @@ impl EthStore {
 	}
 
 	pub fn open_with_iterations(directory: Box<KeyDirectory>, iterations: u32) -> Result<Self, Error> {
+		Self::open_with_kdf(directory, KdfParams::Pbkdf2 { iterations: iterations })
+	}
+
+	/// Opens the store. New key files are encrypted with keys derived using `kdf`.
+	pub fn open_with_kdf(directory: Box<KeyDirectory>, kdf: KdfParams) -> Result<Self, Error> {
 		let accounts = try!(directory.load());
 		let cache = accounts.into_iter().map(|account| (account.address.clone(), account)).collect();
 		let store = EthStore {
 			dir: directory,
-			iterations: iterations,
+			kdf: kdf,
 			cache: RwLock::new(cache),
+			vaults: RwLock::new(HashMap::new()),
 		};
 		Ok(store)
 	}
 
 	fn save(&self, account: SafeAccount) -> Result<(), Error> {
+		// accounts from opened vaults are saved back to their vault
+		{
+			let mut vaults = self.vaults.write().unwrap();
+			if let Some(vault) = vaults.values_mut().find(|vault| vault.cache.contains_key(&account.address)) {
+				let account = try!(vault.dir.insert(account));
+				vault.cache.insert(account.address.clone(), account);
+				return Ok(());
+			}
+		}
+
 		// save to file
 		let account = try!(self.dir.insert(account.clone()));
 
@@ -76,22 +100,61 @@ impl EthStore {
 				return Ok(account.clone())
 			}
 		}
+		{
+			let vaults = self.vaults.read().unwrap();
+			if let Some(account) = vaults.values().filter_map(|vault| vault.cache.get(address)).next() {
+				return Ok(account.clone())
+			}
+		}
 		try!(self.reload_accounts());
 		let cache = self.cache.read().unwrap();
 		cache.get(address).cloned().ok_or(Error::InvalidAccount)
 	}
+
+	/// Vaults are stored as subdirectories of the main keys directory.
+	fn vaults_root(&self) -> Result<PathBuf, Error> {
+		self.dir.path().cloned().ok_or(Error::VaultsNotSupported)
+	}
+
+	fn open_vault_dir(&self, dir: VaultDiskDirectory) -> Result<(), Error> {
+		let accounts = try!(dir.load());
+		let vault = Vault {
+			cache: accounts.into_iter().map(|account| (account.address.clone(), account)).collect(),
+			dir: dir,
+		};
+		self.vaults.write().unwrap().insert(vault.dir.name().to_owned(), vault);
+		Ok(())
+	}
 }
 
 impl SecretStore for EthStore {
 	fn insert_account(&self, secret: Secret, password: &str) -> Result<Address, Error> {
 		let keypair = try!(KeyPair::from_secret(secret).map_err(|_| Error::CreationFailed));
 		let id: [u8; 16] = Random::random();
-		let account = SafeAccount::create(&keypair, id, password, self.iterations, UUID::from(id).into(), "{}".to_owned());
+		let account = SafeAccount::create(&keypair, id, password, &self.kdf, UUID::from(id).into(), "{}".to_owned());
 		let address = account.address.clone();
 		try!(self.save(account));
 		Ok(address)
 	}
 
+	fn insert_account_from_seed(&self, seed: &[u8], path: &[Derivation], password: &str) -> Result<Address, Error> {
+		let master = try!(ExtendedSecret::from_seed(seed));
+		let derived = try!(master.derive_path(path));
+		self.insert_account(derived.secret().clone(), password)
+	}
+
+	fn derive_account(&self, address: &Address, password: &str, path: &[Derivation], save: bool) -> Result<Address, Error> {
+		let account = try!(self.get(address));
+		let secret = try!(account.crypto.secret(password));
+		let derived = try!(ExtendedSecret::new(secret).derive_path(path));
+		if save {
+			self.insert_account(derived.secret().clone(), password)
+		} else {
+			let keypair = try!(KeyPair::from_secret(derived.secret().clone()));
+			Ok(keypair.address())
+		}
+	}
+
 	fn import_presale(&self, json: &[u8], password: &str) -> Result<Address, Error> {
 		let json_wallet = try!(json::PresaleWallet::load(json).map_err(|_| Error::InvalidKeyFile("Invalid JSON format".to_owned())));
 		let wallet = PresaleWallet::from(json_wallet);
@@ -111,13 +174,25 @@ impl SecretStore for EthStore {
 
 	fn accounts(&self) -> Result<Vec<Address>, Error> {
 		try!(self.reload_accounts());
-		Ok(self.cache.read().unwrap().keys().cloned().collect())
+		let cache = self.cache.read().unwrap();
+		let mut accounts: Vec<Address> = cache.keys().cloned().collect();
+		// a copy of a vault account may be left in the main directory
+		for vault in self.vaults.read().unwrap().values() {
+			accounts.extend(vault.cache.keys().filter(|address| !cache.contains_key(address)).cloned());
+		}
+		Ok(accounts)
 	}
 
 	fn change_password(&self, address: &Address, old_password: &str, new_password: &str) -> Result<(), Error> {
+		// all accounts of a vault are encrypted with the vault password
+		let in_vault = self.vaults.read().unwrap().values().any(|vault| vault.cache.contains_key(address));
+		if in_vault && !self.cache.read().unwrap().contains_key(address) {
+			return Err(Error::VaultAccountPassword);
+		}
+
 		// change password
 		let account = try!(self.get(address));
-		let account = try!(account.change_password(old_password, new_password, self.iterations));
+		let account = try!(account.change_password(old_password, new_password, &self.kdf));
 
 		// save to file
 		self.save(account)
@@ -130,6 +205,15 @@ impl SecretStore for EthStore {
 		};
 
 		if can_remove {
+			{
+				let mut vaults = self.vaults.write().unwrap();
+				if let Some(vault) = vaults.values_mut().find(|vault| vault.cache.contains_key(address)) {
+					try!(vault.dir.remove(address));
+					vault.cache.remove(address);
+					return Ok(());
+				}
+			}
+
 			try!(self.dir.remove(address));
 			let mut cache = self.cache.write().unwrap();
 			cache.remove(address);
@@ -191,4 +275,82 @@ impl SecretStore for EthStore {
 	fn import_geth_accounts(&self, desired: Vec<Address>, testnet: bool) -> Result<Vec<Address>, Error> {
 		import::import_geth_accounts(&*self.dir, desired.into_iter().collect(), testnet)
 	}
+
+	fn create_vault(&self, name: &str, password: &str) -> Result<(), Error> {
+		let root = try!(self.vaults_root());
+		let dir = try!(VaultDiskDirectory::create(root, name, password, &self.kdf));
+		self.open_vault_dir(dir)
+	}
+
+	fn open_vault(&self, name: &str, password: &str) -> Result<(), Error> {
+		let root = try!(self.vaults_root());
+		let dir = try!(VaultDiskDirectory::at(root, name, password));
+		self.open_vault_dir(dir)
+	}
+
+	fn close_vault(&self, name: &str) -> Result<(), Error> {
+		self.vaults.write().unwrap().remove(name).map(|_| ()).ok_or(Error::VaultNotFound)
+	}
+
+	fn list_vaults(&self) -> Result<Vec<String>, Error> {
+		let root = try!(self.vaults_root());
+		list_vaults(root)
+	}
+
+	fn list_opened_vaults(&self) -> Result<Vec<String>, Error> {
+		let mut names: Vec<String> = self.vaults.read().unwrap().keys().cloned().collect();
+		names.sort();
+		Ok(names)
+	}
+
+	fn change_account_vault(&self, address: &Address, password: &str, vault: Option<&str>) -> Result<(), Error> {
+		let account = try!(self.get(address));
+		let mut vaults = self.vaults.write().unwrap();
+		let source = vaults.iter().find(|&(_, v)| v.cache.contains_key(address)).map(|(name, _)| name.clone());
+		if source.as_ref().map(|name| name.as_str()) == vault {
+			return Ok(());
+		}
+
+		// accounts moved into a vault are re-encrypted with the vault password,
+		// accounts moved out of a vault keep the vault password
+		match vault {
+			Some(name) => {
+				let target = try!(vaults.get_mut(name).ok_or(Error::VaultNotFound));
+				let account = try!(account.change_password(password, target.dir.password(), &self.kdf));
+				let account = try!(target.dir.insert(account));
+				target.cache.insert(address.clone(), account);
+			},
+			None => {
+				if !account.check_password(password) {
+					return Err(Error::InvalidPassword);
+				}
+				let account = try!(self.dir.insert(account));
+				self.cache.write().unwrap().insert(address.clone(), account);
+			},
+		}
+
+		match source {
+			Some(name) => {
+				let source = vaults.get_mut(&name).expect("source vault found above; qed");
+				try!(source.dir.remove(address));
+				source.cache.remove(address);
+			},
+			None => {
+				try!(self.dir.remove(address));
+				self.cache.write().unwrap().remove(address);
+			},
+		}
+		Ok(())
+	}
+
+	fn get_vault_meta(&self, name: &str) -> Result<String, Error> {
+		let root = try!(self.vaults_root());
+		vault_meta(root, name)
+	}
+
+	fn set_vault_meta(&self, name: &str, meta: &str) -> Result<(), Error> {
+		let vaults = self.vaults.read().unwrap();
+		let vault = try!(vaults.get(name).ok_or(Error::VaultNotFound));
+		vault.dir.set_meta(meta)
+	}
 }
@@ -34,6 +34,7 @@ extern crate ethcrypto as crypto;
 
 pub mod dir;
 pub mod ethkey;
+pub mod typed_data;
 
 mod account;
 mod json;
@@ -45,7 +46,7 @@ mod presale;
 mod random;
 mod secret_store;
 
-pub use self::account::SafeAccount;
+pub use self::account::{SafeAccount, KdfParams};
 pub use self::error::Error;
 pub use self::ethstore::EthStore;
 pub use self::import::{import_accounts, read_geth_accounts};
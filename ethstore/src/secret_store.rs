@@ -14,12 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethkey::{Address, Message, Signature, Secret};
+use ethkey::{Address, Message, Signature, Secret, Derivation};
 use Error;
 use json::UUID;
 
 pub trait SecretStore: Send + Sync {
 	fn insert_account(&self, secret: Secret, password: &str) -> Result<Address, Error>;
+	/// Inserts account with the key derived along `path` from BIP-32 master key of `seed`.
+	fn insert_account_from_seed(&self, seed: &[u8], path: &[Derivation], password: &str) -> Result<Address, Error>;
+	/// Derives new key from the account key along `path`. The new key is stored,
+	/// encrypted with the same password, only if `save` is set.
+	fn derive_account(&self, account: &Address, password: &str, path: &[Derivation], save: bool) -> Result<Address, Error>;
 	fn import_presale(&self, json: &[u8], password: &str) -> Result<Address, Error>;
 	fn import_wallet(&self, json: &[u8], password: &str) -> Result<Address, Error>;
 	fn change_password(&self, account: &Address, old_password: &str, new_password: &str) -> Result<(), Error>;
@@ -39,5 +44,22 @@ pub trait SecretStore: Send + Sync {
 	fn local_path(&self) -> String;
 	fn list_geth_accounts(&self, testnet: bool) -> Vec<Address>;
 	fn import_geth_accounts(&self, desired: Vec<Address>, testnet: bool) -> Result<Vec<Address>, Error>;
+
+	/// Creates new vault protected with `password` and opens it.
+	fn create_vault(&self, name: &str, password: &str) -> Result<(), Error>;
+	/// Opens existing vault, making its accounts available.
+	fn open_vault(&self, name: &str, password: &str) -> Result<(), Error>;
+	/// Closes opened vault. Its accounts are no longer available.
+	fn close_vault(&self, name: &str) -> Result<(), Error>;
+	/// Lists all vaults.
+	fn list_vaults(&self) -> Result<Vec<String>, Error>;
+	/// Lists opened vaults.
+	fn list_opened_vaults(&self) -> Result<Vec<String>, Error>;
+	/// Moves account to an opened vault, or back to the main directory if `vault` is `None`.
+	fn change_account_vault(&self, account: &Address, password: &str, vault: Option<&str>) -> Result<(), Error>;
+	/// Returns vault metadata.
+	fn get_vault_meta(&self, name: &str) -> Result<String, Error>;
+	/// Sets metadata of an opened vault.
+	fn set_vault_meta(&self, name: &str, meta: &str) -> Result<(), Error>;
 }
 
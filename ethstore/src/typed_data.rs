@@ -0,0 +1,258 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hashing of typed structured data.
+//!
+//! Data is a list of `(type, name, value)` fields. The signed hash is
+//! `keccak(keccak(schema) ++ keccak(values))`, where `schema` is the concatenation of
+//! `"<type> <name>"` of every field and `values` is the concatenation of the tightly packed
+//! values: 20 bytes for `address`, 1 byte for `bool`, the raw bytes of `string`, `bytes` and
+//! `bytesN`, and `N / 8` big-endian bytes for `uintN` and `intN`.
+
+use rustc_serialize::hex::FromHex;
+use crypto::Keccak256;
+use ethkey::Message;
+use Error;
+
+/// Single field of typed data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypedDataField {
+	/// Solidity type of the field, e.g. `uint256` or `address`.
+	pub kind: String,
+	/// Name of the field.
+	pub name: String,
+	/// Value of the field. Numbers are decimal or `0x` prefixed hex, binary data is `0x` prefixed hex.
+	pub value: String,
+}
+
+impl TypedDataField {
+	/// Create a new field.
+	pub fn new<T, N, V>(kind: T, name: N, value: V) -> Self where T: Into<String>, N: Into<String>, V: Into<String> {
+		TypedDataField {
+			kind: kind.into(),
+			name: name.into(),
+			value: value.into(),
+		}
+	}
+}
+
+/// Compute the hash of typed data to be signed.
+pub fn hash(fields: &[TypedDataField]) -> Result<Message, Error> {
+	if fields.is_empty() {
+		return Err(Error::InvalidTypedData("No fields given".into()));
+	}
+
+	let mut schema = Vec::new();
+	let mut values = Vec::new();
+	for field in fields {
+		schema.extend_from_slice(field.kind.as_bytes());
+		schema.push(b' ');
+		schema.extend_from_slice(field.name.as_bytes());
+		values.extend(try!(encode(field)));
+	}
+
+	let mut data = schema.keccak256().to_vec();
+	data.extend_from_slice(&values.keccak256());
+	Ok(data.keccak256().into())
+}
+
+fn encode(field: &TypedDataField) -> Result<Vec<u8>, Error> {
+	let invalid = |reason: &str| Error::InvalidTypedData(format!("{} {}: {}", field.kind, field.name, reason));
+	let kind = field.kind.as_str();
+	let value = field.value.trim();
+
+	match kind {
+		"address" => {
+			let address = try!(from_hex(value).map_err(|_| invalid("invalid hex")));
+			match address.len() {
+				20 => Ok(address),
+				_ => Err(invalid("expected 20 bytes")),
+			}
+		},
+		"bool" => match value {
+			"true" => Ok(vec![1]),
+			"false" => Ok(vec![0]),
+			_ => Err(invalid("expected true or false")),
+		},
+		"string" => Ok(field.value.as_bytes().to_vec()),
+		"bytes" => from_hex(value).map_err(|_| invalid("invalid hex")),
+		_ if kind.starts_with("bytes") => {
+			let size = try!(type_size(&kind[5..], 1, 32, 1).ok_or_else(|| invalid("unknown type")));
+			let bytes = try!(from_hex(value).map_err(|_| invalid("invalid hex")));
+			match bytes.len() == size {
+				true => Ok(bytes),
+				false => Err(invalid(&format!("expected {} bytes", size))),
+			}
+		},
+		_ if kind.starts_with("uint") => {
+			let bits = try!(type_size(&kind[4..], 8, 256, 8).ok_or_else(|| invalid("unknown type")));
+			let number = try!(parse_uint(value).ok_or_else(|| invalid("invalid number")));
+			match fits(&number, bits) {
+				true => Ok(number[32 - bits / 8..].to_vec()),
+				false => Err(invalid("number out of range")),
+			}
+		},
+		_ if kind.starts_with("int") => {
+			let bits = try!(type_size(&kind[3..], 8, 256, 8).ok_or_else(|| invalid("unknown type")));
+			let negative = value.starts_with('-');
+			let magnitude = try!(parse_uint(value.trim_left_matches('-')).ok_or_else(|| invalid("invalid number")));
+			let number = match negative {
+				true => negate(magnitude),
+				false => magnitude,
+			};
+			// the bits above the sign bit must be copies of it.
+			let sign_extended = |byte| number[..32 - bits / 8].iter().all(|b| *b == byte);
+			let in_range = match (negative, number[32 - bits / 8] & 0x80 != 0) {
+				(false, false) => sign_extended(0),
+				(true, true) => sign_extended(0xff),
+				// negative zero
+				(true, false) => number.iter().all(|b| *b == 0),
+				(false, true) => false,
+			};
+			match in_range {
+				true => Ok(number[32 - bits / 8..].to_vec()),
+				false => Err(invalid("number out of range")),
+			}
+		},
+		_ => Err(invalid("unknown type")),
+	}
+}
+
+/// Parse the size suffix of `bytesN`, `uintN` and `intN`. No suffix means the maximal size.
+fn type_size(suffix: &str, min: usize, max: usize, step: usize) -> Option<usize> {
+	if suffix.is_empty() {
+		return match step {
+			// `bytes` is handled separately as it's dynamic.
+			1 => None,
+			_ => Some(max),
+		};
+	}
+	match suffix.parse::<usize>() {
+		Ok(size) if size >= min && size <= max && size % step == 0 && !suffix.starts_with('0') => Some(size),
+		_ => None,
+	}
+}
+
+fn from_hex(value: &str) -> Result<Vec<u8>, ()> {
+	match value.starts_with("0x") {
+		true => value[2..].from_hex().map_err(|_| ()),
+		false => Err(()),
+	}
+}
+
+/// Parse a decimal or `0x` prefixed hex number into 32 big-endian bytes.
+fn parse_uint(value: &str) -> Option<[u8; 32]> {
+	let mut number = [0u8; 32];
+	if value.starts_with("0x") {
+		let digits = &value[2..];
+		if digits.is_empty() {
+			return None;
+		}
+		let padded = match digits.len() % 2 {
+			0 => digits.to_owned(),
+			_ => format!("0{}", digits),
+		};
+		let bytes = match padded.from_hex() {
+			Ok(ref bytes) if bytes.len() <= 32 => bytes.clone(),
+			_ => return None,
+		};
+		number[32 - bytes.len()..].copy_from_slice(&bytes);
+		return Some(number);
+	}
+
+	if value.is_empty() {
+		return None;
+	}
+	for digit in value.chars() {
+		let mut carry = match digit.to_digit(10) {
+			Some(digit) => digit,
+			None => return None,
+		};
+		for byte in number.iter_mut().rev() {
+			let product = *byte as u32 * 10 + carry;
+			*byte = product as u8;
+			carry = product >> 8;
+		}
+		if carry != 0 {
+			return None;
+		}
+	}
+	Some(number)
+}
+
+/// Whether the number fits in `bits` bits.
+fn fits(number: &[u8; 32], bits: usize) -> bool {
+	number[..32 - bits / 8].iter().all(|b| *b == 0)
+}
+
+/// Two's complement of the number.
+fn negate(mut number: [u8; 32]) -> [u8; 32] {
+	let mut carry = 1u16;
+	for byte in number.iter_mut().rev() {
+		let sum = (!*byte) as u16 + carry;
+		*byte = sum as u8;
+		carry = sum >> 8;
+	}
+	number
+}
+
+#[cfg(test)]
+mod tests {
+	use crypto::Keccak256;
+	use ethkey::Message;
+	use super::{hash, TypedDataField};
+
+	#[test]
+	fn should_hash_typed_data() {
+		let fields = vec![
+			TypedDataField::new("string", "message", "Hi, Alice!"),
+			TypedDataField::new("uint32", "value", "42"),
+			TypedDataField::new("int8", "delta", "-1"),
+			TypedDataField::new("address", "to", "0x0000000000000000000000000000000000000001"),
+			TypedDataField::new("bool", "urgent", "true"),
+			TypedDataField::new("bytes2", "tag", "0xbeef"),
+		];
+
+		let schema = b"string messageuint32 valueint8 deltaaddress tobool urgentbytes2 tag".keccak256();
+		let mut values = b"Hi, Alice!".to_vec();
+		values.extend_from_slice(&[0, 0, 0, 42, 0xff]);
+		values.extend_from_slice(&[0; 19]);
+		values.extend_from_slice(&[1, 1, 0xbe, 0xef]);
+		let mut data = schema.to_vec();
+		data.extend_from_slice(&values.keccak256());
+
+		assert_eq!(hash(&fields).unwrap(), Message::from(data.keccak256()));
+		// hex and decimal numbers are the same value
+		let mut hex_fields = fields.clone();
+		hex_fields[1].value = "0x2a".into();
+		assert_eq!(hash(&hex_fields).unwrap(), hash(&fields).unwrap());
+	}
+
+	#[test]
+	fn should_reject_invalid_typed_data() {
+		assert!(hash(&[]).is_err());
+		assert!(hash(&[TypedDataField::new("uint8", "value", "256")]).is_err());
+		assert!(hash(&[TypedDataField::new("int8", "value", "128")]).is_err());
+		assert!(hash(&[TypedDataField::new("int8", "value", "-129")]).is_err());
+		assert!(hash(&[TypedDataField::new("int8", "value", "-128")]).is_ok());
+		assert!(hash(&[TypedDataField::new("uint7", "value", "1")]).is_err());
+		assert!(hash(&[TypedDataField::new("bytes33", "value", "0x00")]).is_err());
+		assert!(hash(&[TypedDataField::new("bytes2", "value", "0x00")]).is_err());
+		assert!(hash(&[TypedDataField::new("address", "to", "0x01")]).is_err());
+		assert!(hash(&[TypedDataField::new("bool", "flag", "yes")]).is_err());
+		assert!(hash(&[TypedDataField::new("mapping", "value", "1")]).is_err());
+	}
+}
@@ -20,8 +20,8 @@ extern crate ethstore;
 mod util;
 
 use ethstore::{SecretStore, EthStore};
-use ethstore::ethkey::{Random, Generator, Secret, KeyPair, verify_address};
-use ethstore::dir::DiskDirectory;
+use ethstore::ethkey::{Random, Generator, Secret, KeyPair, Derivation, verify_address, mnemonic_to_seed};
+use ethstore::dir::{DiskDirectory, KeyDirectory};
 use util::TransientDir;
 
 #[test]
@@ -88,6 +88,96 @@ fn secret_store_remove_account() {
 	assert!(store.remove_account(&accounts[0], "").is_err());
 }
 
+#[test]
+fn secret_store_insert_account_from_seed() {
+	let dir = TransientDir::create().unwrap();
+	let store = EthStore::open(Box::new(dir)).unwrap();
+	let seed = mnemonic_to_seed("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about", "");
+	let path = [Derivation::Hard(44), Derivation::Hard(60), Derivation::Hard(0), Derivation::Soft(0), Derivation::Soft(0)];
+	let first = store.insert_account_from_seed(&seed, &path, "").unwrap();
+	let second = store.insert_account_from_seed(&seed, &path[..4], "").unwrap();
+	assert!(first != second);
+	assert_eq!(store.accounts().unwrap().len(), 2);
+}
+
+#[test]
+fn secret_store_derive_account() {
+	let dir = TransientDir::create().unwrap();
+	let store = EthStore::open(Box::new(dir)).unwrap();
+	let address = store.insert_account(random_secret(), "password").unwrap();
+
+	let derived = store.derive_account(&address, "password", &[Derivation::Soft(1)], false).unwrap();
+	assert!(derived != address);
+	assert_eq!(store.accounts().unwrap(), vec![address]);
+	assert!(store.derive_account(&address, "wrong", &[Derivation::Soft(1)], false).is_err());
+
+	// derivation is deterministic and saved key is encrypted with the same password
+	assert_eq!(store.derive_account(&address, "password", &[Derivation::Soft(1)], true).unwrap(), derived);
+	assert_eq!(store.accounts().unwrap().len(), 2);
+	assert!(store.sign(&derived, "password", &Default::default()).is_ok());
+}
+
+#[test]
+fn secret_store_move_account_to_vault() {
+	let dir = TransientDir::create().unwrap();
+	let store = EthStore::open(Box::new(dir)).unwrap();
+	let address = store.insert_account(random_secret(), "account").unwrap();
+	assert!(store.create_vault("trading", "vault").is_ok());
+	assert_eq!(store.list_vaults().unwrap(), vec!["trading".to_owned()]);
+	assert_eq!(store.list_opened_vaults().unwrap(), vec!["trading".to_owned()]);
+
+	// account is re-encrypted with the vault password
+	assert!(store.change_account_vault(&address, "account", Some("trading")).is_ok());
+	assert_eq!(store.accounts().unwrap(), vec![address]);
+	assert!(store.sign(&address, "account", &Default::default()).is_err());
+	assert!(store.sign(&address, "vault", &Default::default()).is_ok());
+
+	// closed vault hides its accounts
+	assert!(store.close_vault("trading").is_ok());
+	assert_eq!(store.accounts().unwrap().len(), 0);
+	assert!(store.open_vault("trading", "account").is_err());
+	assert!(store.open_vault("trading", "vault").is_ok());
+	assert_eq!(store.accounts().unwrap(), vec![address]);
+
+	// and moving it out keeps the vault password
+	assert!(store.change_account_vault(&address, "vault", None).is_ok());
+	assert!(store.close_vault("trading").is_ok());
+	assert_eq!(store.accounts().unwrap(), vec![address]);
+	assert!(store.sign(&address, "vault", &Default::default()).is_ok());
+}
+
+#[test]
+fn secret_store_vault_account_password() {
+	let dir = TransientDir::create().unwrap();
+	let path = dir.path().unwrap().clone();
+	let store = EthStore::open(Box::new(dir)).unwrap();
+	let secret = random_secret();
+	let address = store.insert_account(secret.clone(), "account").unwrap();
+	assert!(store.create_vault("trading", "vault").is_ok());
+	assert!(store.change_account_vault(&address, "account", Some("trading")).is_ok());
+
+	// vault accounts keep the vault password
+	assert!(store.change_password(&address, "vault", "other").is_err());
+	assert!(store.sign(&address, "vault", &Default::default()).is_ok());
+
+	// a copy of the account left in the main directory is listed once
+	let other = EthStore::open(Box::new(DiskDirectory::at(&path))).unwrap();
+	assert_eq!(other.insert_account(secret, "account").unwrap(), address);
+	assert_eq!(store.accounts().unwrap(), vec![address]);
+}
+
+#[test]
+fn secret_store_vault_meta() {
+	let dir = TransientDir::create().unwrap();
+	let store = EthStore::open(Box::new(dir)).unwrap();
+	assert!(store.create_vault("cold", "vault").is_ok());
+	assert_eq!(store.get_vault_meta("cold").unwrap(), "{}");
+	assert!(store.set_vault_meta("cold", "{\"passwordHint\":\"usual\"}").is_ok());
+	assert!(store.close_vault("cold").is_ok());
+	assert_eq!(store.get_vault_meta("cold").unwrap(), "{\"passwordHint\":\"usual\"}");
+	assert!(store.set_vault_meta("cold", "{}").is_err());
+}
+
 fn test_path() -> &'static str {
 	match ::std::fs::metadata("ethstore") {
 		Ok(_) => "ethstore/tests/res/geth_keystore",
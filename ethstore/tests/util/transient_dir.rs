@@ -71,4 +71,8 @@ impl KeyDirectory for TransientDir {
 	fn remove(&self, address: &Address) -> Result<(), Error> {
 		self.dir.remove(address)
 	}
+
+	fn path(&self) -> Option<&PathBuf> {
+		self.dir.path()
+	}
 }
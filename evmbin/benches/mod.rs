@@ -28,6 +28,7 @@ extern crate evm;
 extern crate ethcore_util;
 extern crate rustc_serialize;
 
+use std::sync::Arc;
 use self::test::{Bencher, black_box};
 
 use evm::run_vm;
@@ -53,7 +54,7 @@ fn simple_loop(gas: U256, b: &mut Bencher) {
 	b.iter(|| {
 		let mut params = ActionParams::default();
 		params.gas = gas;
-		params.code = Some(code.clone());
+		params.code = Some(Arc::new(code.clone()));
 
 		run_vm(params)
 	});
@@ -77,7 +78,7 @@ fn rng(gas: U256, b: &mut Bencher) {
 	b.iter(|| {
 		let mut params = ActionParams::default();
 		params.gas = gas;
-		params.code = Some(code.clone());
+		params.code = Some(Arc::new(code.clone()));
 
 		run_vm(params)
 	});
@@ -25,27 +25,51 @@ extern crate docopt;
 extern crate ethcore_util as util;
 
 mod ext;
+mod tracer;
 
+use std::fmt;
+use std::fs::File;
+use std::sync::Arc;
 use std::time::{Instant, Duration};
 use std::str::FromStr;
 use docopt::Docopt;
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json::Json;
 use util::{U256, FromHex, Uint, Bytes};
 use ethcore::evm::{Factory, VMType, Finalize};
 use ethcore::action_params::ActionParams;
+use ethcore::client::EvmTestClient;
+use ethcore::ethereum;
+use ethcore::spec::Spec;
+use ethcore::trace::{NoopTracer, NoopVMTracer};
+use tracer::{JsonTracer, hex};
 
 const USAGE: &'static str = r#"
 EVM implementation for Parity.
   Copyright 2016 Ethcore (UK) Limited
 
 Usage:
-    evmbin stats [options]
-    evmbin [-h | --help]
+    parity-evm stats [options]
+    parity-evm exec [options]
+    parity-evm [-h | --help]
+
+Commands:
+    stats              Execute the code without any state and display the time taken.
+    exec               Execute the code as a message call against an empty
+                       state.
 
 Transaction options:
     --code CODE        Contract code.
     --input DATA       Input data.
     --gas GAS          Supplied gas.
 
+Exec options:
+    --chain CHAIN      Rules the code is executed with: frontier, homestead
+                       or the path to a chain specification file, whose rules
+                       at the genesis block apply. Homestead if not given.
+    --json             Display every executed instruction and the result
+                       as JSON.
+
 General options:
     -h, --help         Display this message and exit.
 "#;
@@ -55,9 +79,14 @@ fn main() {
 	let args: Args = Docopt::new(USAGE).and_then(|d| d.decode()).unwrap_or_else(|e| e.exit());
 
 	let mut params = ActionParams::default();
-	params.gas = args.gas();
-	params.code = Some(args.code());
-	params.data = args.data();
+	params.gas = args.gas().unwrap_or_else(|e| die(e));
+	params.code = Some(Arc::new(args.code().unwrap_or_else(|e| die(e))));
+	params.data = args.data().unwrap_or_else(|e| die(e));
+
+	if args.cmd_exec {
+		let spec = args.spec().unwrap_or_else(|e| die(e));
+		return exec(spec, params, args.flag_json);
+	}
 
 	let result = run_vm(params);
 	println!("Gas used: {:?}", result.gas_used);
@@ -65,6 +94,37 @@ fn main() {
 	println!("Time: {}.{:.9}s", result.time.as_secs(), result.time.subsec_nanos());
 }
 
+fn exec(spec: Spec, params: ActionParams, json: bool) {
+	let initial_gas = params.gas;
+	let mut client = EvmTestClient::new(spec, VMType::Interpreter);
+
+	let start = Instant::now();
+	let result = match json {
+		true => client.call(params, &mut NoopTracer, &mut JsonTracer::default()),
+		false => client.call(params, &mut NoopTracer, &mut NoopVMTracer),
+	};
+	let time = start.elapsed();
+
+	match (result, json) {
+		(Ok(result), true) => println!(
+			"{{\"output\":\"0x{}\",\"gasUsed\":\"{}\",\"logs\":{},\"time\":{}}}",
+			result.output.to_hex(), hex(&(initial_gas - result.gas_left)), result.logs.len(), nanos(&time)
+		),
+		(Ok(result), false) => {
+			println!("Gas used: {:?}", initial_gas - result.gas_left);
+			println!("Output: 0x{}", result.output.to_hex());
+			println!("Logs: {}", result.logs.len());
+			println!("Time: {}.{:.9}s", time.as_secs(), time.subsec_nanos());
+		},
+		(Err(e), true) => println!("{{\"error\":{},\"time\":{}}}", Json::String(format!("{:?}", e)), nanos(&time)),
+		(Err(e), false) => die(format!("Execution failed: {:?}", e)),
+	}
+}
+
+fn nanos(duration: &Duration) -> u64 {
+	duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64
+}
+
 /// Execute VM with given `ActionParams`
 pub fn run_vm(params: ActionParams) -> ExecutionResults {
 	let initial_gas = params.gas;
@@ -96,34 +156,48 @@ pub struct ExecutionResults {
 #[derive(Debug, RustcDecodable)]
 struct Args {
 	cmd_stats: bool,
+	cmd_exec: bool,
+	flag_json: bool,
+	flag_chain: Option<String>,
 	flag_code: Option<String>,
 	flag_gas: Option<String>,
 	flag_input: Option<String>,
 }
 
 impl Args {
-	pub fn gas(&self) -> U256 {
-		self.flag_gas
-			.clone()
-			.and_then(|g| U256::from_str(&g).ok())
-			.unwrap_or_else(|| !U256::zero())
+	pub fn gas(&self) -> Result<U256, String> {
+		match self.flag_gas {
+			Some(ref gas) => U256::from_str(gas).map_err(|e| format!("Invalid gas {}: {:?}", gas, e)),
+			None => Ok(!U256::zero()),
+		}
+	}
+
+	pub fn code(&self) -> Result<Bytes, String> {
+		match self.flag_code {
+			Some(ref code) => code.from_hex().map_err(|e| format!("Invalid code: {}", e)),
+			None => Err("Code is required.".into()),
+		}
 	}
 
-	pub fn code(&self) -> Bytes {
-		self.flag_code
-			.clone()
-			.and_then(|c| c.from_hex().ok())
-			.unwrap_or_else(|| die("Code is required."))
+	pub fn spec(&self) -> Result<Spec, String> {
+		match self.flag_chain.as_ref().map(String::as_str) {
+			None | Some("homestead") => Ok(ethereum::new_homestead_test()),
+			Some("frontier") => Ok(ethereum::new_frontier_test()),
+			Some(path) => File::open(path)
+				.map_err(|e| format!("Could not load specification file {}: {}", path, e))
+				.and_then(Spec::load),
+		}
 	}
 
-	pub fn data(&self) -> Option<Bytes> {
-		self.flag_input
-			.clone()
-			.and_then(|d| d.from_hex().ok())
+	pub fn data(&self) -> Result<Option<Bytes>, String> {
+		match self.flag_input {
+			Some(ref input) => input.from_hex().map(Some).map_err(|e| format!("Invalid input: {}", e)),
+			None => Ok(None),
+		}
 	}
 }
 
-fn die(msg: &'static str) -> ! {
+fn die<T: fmt::Display>(msg: T) -> ! {
 	println!("{}", msg);
 	::std::process::exit(-1)
 }
@@ -0,0 +1,118 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! VM tracer printing every executed instruction as a line of JSON.
+
+use util::{U256, Uint};
+use ethcore::trace::{VMTracer, VMTrace};
+
+/// `0x` prefixed hex form of `value`, `0x0` for zero.
+pub fn hex(value: &U256) -> String {
+	if value.is_zero() {
+		"0x0".into()
+	} else {
+		// the `LowerHex` formatting of `U256` includes the `0x` prefix
+		format!("{:x}", value)
+	}
+}
+
+/// Prints `{"depth":1,"pc":0,"op":96,"gasCost":"0x3","gas":"0x..","stack":["0x2a"]}` for each
+/// executed instruction, where `stack` holds the items pushed by the instruction.
+pub struct JsonTracer {
+	depth: usize,
+	pc: usize,
+	instruction: u8,
+	gas_cost: U256,
+}
+
+impl Default for JsonTracer {
+	fn default() -> Self {
+		JsonTracer {
+			depth: 1,
+			pc: 0,
+			instruction: 0,
+			gas_cost: U256::zero(),
+		}
+	}
+}
+
+impl JsonTracer {
+	fn line(&self, gas_left: U256, stack_push: &[U256], store_diff: Option<(U256, U256)>) -> String {
+		let stack = stack_push.iter().map(|item| format!("\"{}\"", hex(item))).collect::<Vec<_>>().join(",");
+		let storage = match store_diff {
+			Some((key, value)) => format!(",\"storage\":{{\"{}\":\"{}\"}}", hex(&key), hex(&value)),
+			None => String::new(),
+		};
+		format!(
+			"{{\"depth\":{},\"pc\":{},\"op\":{},\"gasCost\":\"{}\",\"gas\":\"{}\",\"stack\":[{}]{}}}",
+			self.depth, self.pc, self.instruction, hex(&self.gas_cost), hex(&gas_left), stack, storage
+		)
+	}
+}
+
+impl VMTracer for JsonTracer {
+	fn trace_prepare_execute(&mut self, pc: usize, instruction: u8, gas_cost: &U256) -> bool {
+		self.pc = pc;
+		self.instruction = instruction;
+		self.gas_cost = *gas_cost;
+		true
+	}
+
+	fn trace_executed(&mut self, gas_left: U256, stack_push: &[U256], _mem_diff: Option<(usize, &[u8])>, store_diff: Option<(U256, U256)>) {
+		println!("{}", self.line(gas_left, stack_push, store_diff));
+	}
+
+	fn prepare_subtrace(&self, _code: &[u8]) -> Self {
+		JsonTracer {
+			depth: self.depth + 1,
+			..Default::default()
+		}
+	}
+
+	fn done_subtrace(&mut self, _sub: Self) {}
+
+	fn drain(self) -> Option<VMTrace> { None }
+}
+
+#[cfg(test)]
+mod tests {
+	use util::U256;
+	use ethcore::trace::VMTracer;
+	use super::{JsonTracer, hex};
+
+	#[test]
+	fn should_format_hex() {
+		assert_eq!(hex(&U256::zero()), "0x0");
+		assert_eq!(hex(&U256::from(0x2a)), "0x2a");
+	}
+
+	#[test]
+	fn should_format_executed_instructions() {
+		let mut tracer = JsonTracer::default();
+		tracer.trace_prepare_execute(2, 0x60, &U256::from(3));
+		assert_eq!(
+			tracer.line(U256::from(0x1000), &[U256::from(0x2a)], None),
+			r#"{"depth":1,"pc":2,"op":96,"gasCost":"0x3","gas":"0x1000","stack":["0x2a"]}"#
+		);
+
+		let mut sub = tracer.prepare_subtrace(&[]);
+		sub.trace_prepare_execute(4, 0x55, &U256::from(20_000));
+		assert_eq!(
+			sub.line(U256::from(0x10), &[], Some((U256::zero(), U256::from(0x2a)))),
+			r#"{"depth":2,"pc":4,"op":85,"gasCost":"0x4e20","gas":"0x10","stack":[],"storage":{"0x0":"0x2a"}}"#
+		);
+	}
+}
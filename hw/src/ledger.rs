@@ -0,0 +1,340 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ledger Nano S and Ledger Blue support, through the Ethereum application APDU protocol over HID.
+
+use std::cmp;
+use std::str::FromStr;
+use parking_lot::{Mutex, RwLock};
+use hidapi;
+use bigint::hash::H256;
+use ethkey::{Address, Signature};
+use super::{Error, WalletInfo};
+
+const LEDGER_VID: u16 = 0x2c97;
+const LEDGER_PIDS: [u16; 2] = [0x0000, 0x0001]; // Blue, Nano S
+
+/// Default Ethereum account path used by Ledger: 44'/60'/0'/0.
+const ETH_DERIVATION_PATH: [u32; 4] = [0x8000_002C, 0x8000_003C, 0x8000_0000, 0];
+
+const APDU_TAG: u8 = 0x05;
+const APDU_CLA: u8 = 0xe0;
+const APDU_CHANNEL: u16 = 0x0101;
+const APDU_SUCCESS: u16 = 0x9000;
+const APDU_USER_CANCEL: u16 = 0x6985;
+
+const HID_PACKET_SIZE: usize = 64;
+const MAX_CHUNK_SIZE: usize = 255;
+
+mod commands {
+	pub const GET_ETH_PUBLIC_ADDRESS: u8 = 0x02;
+	pub const SIGN_ETH_TRANSACTION: u8 = 0x04;
+}
+
+struct Device {
+	path: String,
+	info: WalletInfo,
+}
+
+/// Ledger devices manager.
+pub struct Manager {
+	/// Enumeration needs exclusive access, device I/O only shared one, so a request
+	/// waiting for confirmation on one device does not block the others.
+	usb: RwLock<hidapi::HidApi>,
+	devices: Mutex<Vec<Device>>,
+}
+
+impl Manager {
+	pub fn new() -> Result<Manager, Error> {
+		Ok(Manager {
+			usb: RwLock::new(try!(hidapi::HidApi::new())),
+			devices: Mutex::new(Vec::new()),
+		})
+	}
+
+	/// Re-enumerates attached devices. Returns number of attached Ledger devices.
+	pub fn update_devices(&self) -> Result<usize, Error> {
+		// a signing request is waiting for confirmation, keep the known devices until it's done
+		let mut usb = match self.usb.try_write() {
+			Some(usb) => usb,
+			None => return Ok(self.devices.lock().len()),
+		};
+		usb.refresh_devices();
+		let known = self.devices.lock().iter().map(|d| (d.path.clone(), d.info.clone())).collect::<Vec<_>>();
+
+		let mut devices = Vec::new();
+		for usb_device in usb.devices() {
+			if usb_device.vendor_id != LEDGER_VID || !LEDGER_PIDS.contains(&usb_device.product_id) {
+				continue;
+			}
+
+			// devices are queried only once, when attached
+			if let Some(&(ref path, ref info)) = known.iter().find(|&&(ref path, _)| path == &usb_device.path) {
+				devices.push(Device { path: path.clone(), info: info.clone() });
+				continue;
+			}
+
+			let handle = try!(usb.open_path(&usb_device.path));
+			match get_address(&handle) {
+				Ok(address) => {
+					info!(target: "hw", "Ledger wallet attached: {:?}", address);
+					devices.push(Device {
+						path: usb_device.path.clone(),
+						info: WalletInfo {
+							name: usb_device.product_string.clone().unwrap_or_else(|| "Ledger".into()),
+							manufacturer: usb_device.manufacturer_string.clone().unwrap_or_else(String::new),
+							serial: usb_device.serial_number.clone().unwrap_or_else(String::new),
+							address: address,
+						},
+					});
+				},
+				// the Ethereum application is not opened on the device
+				Err(e) => debug!(target: "hw", "Error reading Ledger address: {}", e),
+			}
+		}
+
+		let count = devices.len();
+		*self.devices.lock() = devices;
+		Ok(count)
+	}
+
+	pub fn list_devices(&self) -> Vec<WalletInfo> {
+		self.devices.lock().iter().map(|d| d.info.clone()).collect()
+	}
+
+	pub fn sign_transaction(&self, address: &Address, unsigned_transaction: &[u8]) -> Result<Signature, Error> {
+		let path = try!(self.devices.lock().iter()
+			.find(|d| &d.info.address == address)
+			.map(|d| d.path.clone())
+			.ok_or(Error::KeyNotFound));
+
+		let usb = self.usb.read();
+		let handle = try!(usb.open_path(&path));
+
+		// derivation path goes before the transaction in the first chunk
+		let mut data = derivation_path();
+		data.extend_from_slice(unsigned_transaction);
+
+		let mut result = Vec::new();
+		for (i, chunk) in data.chunks(MAX_CHUNK_SIZE).enumerate() {
+			let p1 = if i == 0 { 0x00 } else { 0x80 };
+			result = try!(send_apdu(&handle, commands::SIGN_ETH_TRANSACTION, p1, 0, chunk));
+		}
+
+		parse_signature(&result)
+	}
+}
+
+fn derivation_path() -> Vec<u8> {
+	let mut result = vec![ETH_DERIVATION_PATH.len() as u8];
+	for index in &ETH_DERIVATION_PATH {
+		result.extend_from_slice(&[(index >> 24) as u8, (index >> 16) as u8, (index >> 8) as u8, *index as u8]);
+	}
+	result
+}
+
+fn get_address(handle: &hidapi::HidDevice) -> Result<Address, Error> {
+	let response = try!(send_apdu(handle, commands::GET_ETH_PUBLIC_ADDRESS, 0, 0, &derivation_path()));
+	parse_address(&response)
+}
+
+/// Parses reply to `GET_ETH_PUBLIC_ADDRESS`.
+fn parse_address(response: &[u8]) -> Result<Address, Error> {
+	// public key length, public key, address length, address as hex string
+	let public_len = *try!(response.get(0).ok_or(Error::Protocol("Empty response".into()))) as usize;
+	let address_len = *try!(response.get(public_len + 1).ok_or(Error::Protocol("Missing address".into()))) as usize;
+	let start = public_len + 2;
+	if response.len() < start + address_len {
+		return Err(Error::Protocol("Truncated address".into()));
+	}
+	let address = String::from_utf8_lossy(&response[start..start + address_len]).into_owned();
+	Address::from_str(&address).map_err(|_| Error::Protocol(format!("Invalid address: {}", address)))
+}
+
+/// Parses reply to `SIGN_ETH_TRANSACTION`: v, r and s.
+fn parse_signature(result: &[u8]) -> Result<Signature, Error> {
+	if result.len() != 65 || result[0] < 27 {
+		return Err(Error::Protocol(format!("Invalid signature length: {}", result.len())));
+	}
+
+	let mut r = H256::default();
+	r.copy_from_slice(&result[1..33]);
+	let mut s = H256::default();
+	s.copy_from_slice(&result[33..65]);
+	Ok(Signature::from_rsv(&r, &s, result[0] - 27))
+}
+
+/// Sends APDU command split into HID packets and reads the reply.
+fn send_apdu(handle: &hidapi::HidDevice, command: u8, p1: u8, p2: u8, data: &[u8]) -> Result<Vec<u8>, Error> {
+	for packet in apdu_packets(command, p1, p2, data) {
+		try!(handle.write(&packet));
+	}
+
+	read_apdu_response(handle)
+}
+
+/// Splits APDU command into HID packets.
+fn apdu_packets(command: u8, p1: u8, p2: u8, data: &[u8]) -> Vec<[u8; HID_PACKET_SIZE + 1]> {
+	let mut message = vec![APDU_CLA, command, p1, p2, data.len() as u8];
+	message.extend_from_slice(data);
+
+	let mut packets = Vec::new();
+	let mut offset = 0;
+	let mut sequence = 0u16;
+	while offset < message.len() {
+		// first byte is the HID report number
+		let mut packet = [0u8; HID_PACKET_SIZE + 1];
+		packet[1] = (APDU_CHANNEL >> 8) as u8;
+		packet[2] = APDU_CHANNEL as u8;
+		packet[3] = APDU_TAG;
+		packet[4] = (sequence >> 8) as u8;
+		packet[5] = sequence as u8;
+		let mut header = 6;
+		if sequence == 0 {
+			packet[6] = (message.len() >> 8) as u8;
+			packet[7] = message.len() as u8;
+			header = 8;
+		}
+
+		let size = cmp::min(message.len() - offset, packet.len() - header);
+		packet[header..header + size].copy_from_slice(&message[offset..offset + size]);
+		packets.push(packet);
+		offset += size;
+		sequence += 1;
+	}
+
+	packets
+}
+
+fn read_apdu_response(handle: &hidapi::HidDevice) -> Result<Vec<u8>, Error> {
+	let mut message = Vec::new();
+	let mut message_size = 0;
+	let mut sequence = 0u16;
+	loop {
+		let mut packet = [0u8; HID_PACKET_SIZE];
+		let size = try!(handle.read(&mut packet));
+		if size < 5 || packet[0] != (APDU_CHANNEL >> 8) as u8 || packet[1] != APDU_CHANNEL as u8 || packet[2] != APDU_TAG {
+			return Err(Error::Protocol("Unexpected packet header".into()));
+		}
+		if ((packet[3] as u16) << 8 | packet[4] as u16) != sequence {
+			return Err(Error::Protocol("Unexpected packet sequence number".into()));
+		}
+
+		let mut offset = 5;
+		if sequence == 0 {
+			if size < 7 {
+				return Err(Error::Protocol("Packet too short".into()));
+			}
+			message_size = (packet[5] as usize) << 8 | packet[6] as usize;
+			offset = 7;
+		}
+
+		message.extend_from_slice(&packet[offset..size]);
+		if message.len() >= message_size {
+			message.truncate(message_size);
+			break;
+		}
+		sequence += 1;
+	}
+
+	check_status(message)
+}
+
+/// Strips the status word from the reply and checks it.
+fn check_status(mut message: Vec<u8>) -> Result<Vec<u8>, Error> {
+	if message.len() < 2 {
+		return Err(Error::Protocol("Response without status word".into()));
+	}
+	let status_offset = message.len() - 2;
+	let status = (message[status_offset] as u16) << 8 | message[status_offset + 1] as u16;
+	message.truncate(status_offset);
+	match status {
+		APDU_SUCCESS => Ok(message),
+		APDU_USER_CANCEL => Err(Error::UserCancel),
+		other => Err(Error::Protocol(format!("Device returned status 0x{:04x}", other))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use ethkey::Address;
+	use super::{derivation_path, apdu_packets, parse_address, parse_signature, check_status, commands};
+	use Error;
+
+	#[test]
+	fn should_encode_derivation_path() {
+		assert_eq!(derivation_path(), vec![4, 0x80, 0, 0, 0x2c, 0x80, 0, 0, 0x3c, 0x80, 0, 0, 0, 0, 0, 0, 0]);
+	}
+
+	#[test]
+	fn should_split_apdu_into_hid_packets() {
+		let data = vec![0xab; 100];
+		let packets = apdu_packets(commands::SIGN_ETH_TRANSACTION, 0x80, 0, &data);
+		assert_eq!(packets.len(), 2);
+
+		// report number, channel, tag, sequence, length, apdu header
+		assert_eq!(&packets[0][..13], &[0, 0x01, 0x01, 0x05, 0, 0, 0, 105, 0xe0, 0x04, 0x80, 0, 100]);
+		assert!(packets[0][13..].iter().all(|b| *b == 0xab));
+		// continuation packet has no length
+		assert_eq!(&packets[1][..6], &[0, 0x01, 0x01, 0x05, 0, 1]);
+		assert_eq!(&packets[1][6..6 + 48], &[0xab; 48][..]);
+		assert!(packets[1][6 + 48..].iter().all(|b| *b == 0));
+	}
+
+	#[test]
+	fn should_check_status_word() {
+		assert_eq!(check_status(vec![1, 2, 0x90, 0x00]).unwrap(), vec![1, 2]);
+		match check_status(vec![0x69, 0x85]) {
+			Err(Error::UserCancel) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		match check_status(vec![0x6a, 0x80]) {
+			Err(Error::Protocol(_)) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert!(check_status(vec![0x90]).is_err());
+	}
+
+	#[test]
+	fn should_parse_address() {
+		let address = "8bA1f109551bD432803012645Ac136ddd64DBA72";
+		let mut response = vec![65];
+		response.extend_from_slice(&[4; 65]);
+		response.push(address.len() as u8);
+		response.extend_from_slice(address.as_bytes());
+		assert_eq!(parse_address(&response).unwrap(), Address::from_str(&address.to_lowercase()).unwrap());
+
+		response.pop();
+		assert!(parse_address(&response).is_err());
+		assert!(parse_address(&[]).is_err());
+	}
+
+	#[test]
+	fn should_parse_signature() {
+		let mut result = vec![28];
+		result.extend_from_slice(&[1; 32]);
+		result.extend_from_slice(&[2; 32]);
+		let signature = parse_signature(&result).unwrap();
+		assert_eq!(signature.v(), 1);
+		assert_eq!(signature.r(), &[1u8; 32][..]);
+		assert_eq!(signature.s(), &[2u8; 32][..]);
+
+		result[0] = 1;
+		assert!(parse_signature(&result).is_err());
+		assert!(parse_signature(&result[..64]).is_err());
+	}
+}
@@ -0,0 +1,131 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hardware wallets management.
+
+#[macro_use]
+extern crate log;
+extern crate parking_lot;
+extern crate hidapi;
+extern crate ethkey;
+extern crate ethcore_bigint as bigint;
+
+mod ledger;
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use ethkey::{Address, Signature};
+
+/// How often attached devices are enumerated.
+const REFRESH_INTERVAL_MS: u64 = 500;
+
+/// Hardware wallet error.
+#[derive(Debug)]
+pub enum Error {
+	/// No attached device holds given address.
+	KeyNotFound,
+	/// Request was cancelled on the device.
+	UserCancel,
+	/// Unexpected reply from the device.
+	Protocol(String),
+	/// USB communication error.
+	Usb(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			Error::KeyNotFound => write!(f, "No hardware wallet with given address is attached"),
+			Error::UserCancel => write!(f, "Operation has been cancelled on the device"),
+			Error::Protocol(ref s) => write!(f, "Hardware wallet protocol error: {}", s),
+			Error::Usb(ref s) => write!(f, "USB error: {}", s),
+		}
+	}
+}
+
+impl From<hidapi::HidError> for Error {
+	fn from(err: hidapi::HidError) -> Self {
+		Error::Usb(format!("{:?}", err))
+	}
+}
+
+/// Attached hardware wallet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletInfo {
+	/// Device name.
+	pub name: String,
+	/// Device manufacturer.
+	pub manufacturer: String,
+	/// Device serial number.
+	pub serial: String,
+	/// Address of the default account on the device.
+	pub address: Address,
+}
+
+/// Keeps track of attached hardware wallets and forwards signing requests to them.
+pub struct HardwareWalletManager {
+	ledger: Arc<ledger::Manager>,
+	exiting: Arc<AtomicBool>,
+}
+
+impl HardwareWalletManager {
+	/// Opens USB context and starts watching for attached devices.
+	pub fn new() -> Result<Self, Error> {
+		let ledger = Arc::new(try!(ledger::Manager::new()));
+		let exiting = Arc::new(AtomicBool::new(false));
+
+		let thread_ledger = ledger.clone();
+		let thread_exiting = exiting.clone();
+		try!(thread::Builder::new().name("hw_wallets".into()).spawn(move || {
+			while !thread_exiting.load(Ordering::Acquire) {
+				if let Err(e) = thread_ledger.update_devices() {
+					debug!(target: "hw", "Error updating Ledger devices: {}", e);
+				}
+				thread::sleep(Duration::from_millis(REFRESH_INTERVAL_MS));
+			}
+		}).map_err(|e| Error::Usb(format!("Could not start device watcher: {}", e))));
+
+		Ok(HardwareWalletManager {
+			ledger: ledger,
+			exiting: exiting,
+		})
+	}
+
+	/// Lists attached wallets.
+	pub fn list_wallets(&self) -> Vec<WalletInfo> {
+		self.ledger.list_devices()
+	}
+
+	/// Returns wallet holding given address, if attached.
+	pub fn wallet_info(&self, address: &Address) -> Option<WalletInfo> {
+		self.ledger.list_devices().into_iter().find(|info| &info.address == address)
+	}
+
+	/// Signs RLP-encoded unsigned transaction on the device holding `address`.
+	/// Blocks until the user confirms or rejects the transaction on the device.
+	pub fn sign_transaction(&self, address: &Address, unsigned_transaction: &[u8]) -> Result<Signature, Error> {
+		self.ledger.sign_transaction(address, unsigned_transaction)
+	}
+}
+
+impl Drop for HardwareWalletManager {
+	fn drop(&mut self) {
+		self.exiting.store(true, Ordering::Release);
+	}
+}
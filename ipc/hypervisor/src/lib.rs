@@ -23,13 +23,22 @@ extern crate ethcore_ipc_nano as nanoipc;
 extern crate semver;
 #[macro_use] extern crate log;
 extern crate time;
+#[cfg(not(windows))]
+extern crate libc;
 
 pub mod service;
 
 /// Default value for hypervisor ipc listener
 pub const HYPERVISOR_IPC_URL: &'static str = "parity-internal-hyper-status.ipc";
 
+/// Number of times a crashed module is restarted before giving up on it
+const MAX_RESTARTS: usize = 5;
+
+/// Uptime after which a crash of a module is not counted against the earlier ones
+const STABLE_UPTIME_SECS: u64 = 600;
+
 use std::sync::{Arc,RwLock};
+use std::time::{Duration, Instant};
 use service::{HypervisorService, IpcModuleId};
 use std::process::{Command,Child};
 use std::collections::HashMap;
@@ -43,6 +52,8 @@ pub struct Hypervisor {
 	service: Arc<HypervisorService>,
 	ipc_worker: RwLock<nanoipc::Worker<HypervisorService>>,
 	processes: RwLock<HashMap<IpcModuleId, Child>>,
+	restarts: RwLock<HashMap<IpcModuleId, usize>>,
+	started: RwLock<HashMap<IpcModuleId, Instant>>,
 	modules: HashMap<IpcModuleId, BootArgs>,
 	pub io_path: String,
 }
@@ -107,6 +118,8 @@ impl Hypervisor {
 			service: service,
 			ipc_worker: RwLock::new(worker),
 			processes: RwLock::new(HashMap::new()),
+			restarts: RwLock::new(HashMap::new()),
+			started: RwLock::new(HashMap::new()),
 			modules: HashMap::new(),
 			io_path: "/tmp".to_owned(),
 		}
@@ -167,9 +180,58 @@ impl Hypervisor {
 			}
 
 			processes.insert(module_id, child);
+			self.started.write().unwrap().insert(module_id, Instant::now());
 		});
 	}
 
+	/// Answers check-ins and restarts the modules whose process terminated
+	/// without reporting a shutdown. Returns the modules that have been restarted.
+	/// Should be called in a loop; every call waits for ipc requests for a short while.
+	pub fn supervise(&self) -> Vec<IpcModuleId> {
+		self.ipc_worker.write().unwrap().poll();
+
+		let terminated = {
+			let mut processes = self.processes.write().unwrap();
+			let terminated = processes.iter()
+				.filter(|&(_, process)| has_exited(process))
+				.map(|(module_id, _)| *module_id)
+				.collect::<Vec<_>>();
+			for module_id in &terminated {
+				processes.remove(module_id);
+			}
+			terminated
+		};
+
+		terminated.into_iter()
+			.filter(|module_id| !self.service.is_shutdown(*module_id))
+			.filter(|module_id| self.restart_module(*module_id))
+			.collect()
+	}
+
+	fn restart_module(&self, module_id: IpcModuleId) -> bool {
+		let stable = self.started.read().unwrap().get(&module_id)
+			.map_or(false, |started| started.elapsed() >= Duration::from_secs(STABLE_UPTIME_SECS));
+		let restarts = {
+			let mut restarts = self.restarts.write().unwrap();
+			let count = restarts.entry(module_id).or_insert(0);
+			if stable {
+				// the module ran fine for a while, earlier crashes are not related
+				*count = 0;
+			}
+			*count += 1;
+			*count
+		};
+		if restarts > MAX_RESTARTS {
+			warn!(target: "hypervisor", "Module {} terminated unexpectedly {} times, not restarting it", module_id, MAX_RESTARTS);
+			return false;
+		}
+
+		warn!(target: "hypervisor", "Module {} terminated unexpectedly, restarting ({}/{})", module_id, restarts, MAX_RESTARTS);
+		self.service.reset_module(module_id);
+		self.start_module(module_id);
+		true
+	}
+
 	/// Reports if all modules are checked in
 	pub fn modules_ready(&self) -> bool {
 		self.service.unchecked_count() == 0
@@ -225,6 +287,19 @@ impl Hypervisor {
 	}
 }
 
+/// Checks whether the process has terminated, reaping it if so.
+#[cfg(not(windows))]
+fn has_exited(process: &Child) -> bool {
+	let pid = process.id() as libc::pid_t;
+	let mut status = 0;
+	unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) == pid }
+}
+
+#[cfg(windows)]
+fn has_exited(_process: &Child) -> bool {
+	false
+}
+
 impl Drop for Hypervisor {
 	fn drop(&mut self) {
 		self.shutdown();
@@ -236,6 +311,7 @@ mod tests {
 	use super::*;
 	use std::sync::atomic::{AtomicBool,Ordering};
 	use std::sync::Arc;
+	use std::time::{Duration, Instant};
 	use nanoipc;
 
 	#[test]
@@ -270,4 +346,47 @@ mod tests {
 
 		assert_eq!(true, hypervisor.modules_ready());
 	}
+
+	#[test]
+	fn restarts_terminated_module() {
+		let url = "ipc:///tmp/test-parity-hypervisor-30.ipc";
+		let test_module_id = 8080u64;
+
+		// the test binary only lists its tests and exits, as if the module crashed on startup
+		let hypervisor = Hypervisor::with_url(url)
+			.module(test_module_id, BootArgs::new().cli(vec!["--list".to_owned()]));
+		hypervisor.start();
+
+		let mut restarts = 0;
+		for _ in 0..200 {
+			restarts += hypervisor.supervise().len();
+			if restarts == super::MAX_RESTARTS { break; }
+		}
+		assert_eq!(restarts, super::MAX_RESTARTS);
+
+		for _ in 0..10 {
+			assert!(hypervisor.supervise().is_empty());
+		}
+	}
+
+	#[test]
+	fn resets_restart_count_after_stable_uptime() {
+		let url = "ipc:///tmp/test-parity-hypervisor-40.ipc";
+		let test_module_id = 8081u64;
+
+		let hypervisor = Hypervisor::with_url(url)
+			.module(test_module_id, BootArgs::new().cli(vec!["--list".to_owned()]));
+		hypervisor.start();
+
+		let mut restarts = 0;
+		for _ in 0..400 {
+			// every run of the module counts as stable
+			for started in hypervisor.started.write().unwrap().values_mut() {
+				*started = Instant::now() - Duration::from_secs(super::STABLE_UPTIME_SECS);
+			}
+			restarts += hypervisor.supervise().len();
+			if restarts > super::MAX_RESTARTS { break; }
+		}
+		assert!(restarts > super::MAX_RESTARTS);
+	}
 }
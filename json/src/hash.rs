@@ -20,7 +20,7 @@ use std::str::FromStr;
 use serde::{Deserialize, Deserializer, Serialize, Serializer, Error};
 use serde::de::Visitor;
 use rustc_serialize::hex::ToHex;
-use util::hash::{H64 as Hash64, H160 as Hash160, H256 as Hash256, H2048 as Hash2048};
+use util::hash::{H64 as Hash64, H160 as Hash160, H256 as Hash256, H512 as Hash512, H2048 as Hash2048};
 
 
 macro_rules! impl_hash {
@@ -87,6 +87,7 @@ macro_rules! impl_hash {
 impl_hash!(H64, Hash64);
 impl_hash!(Address, Hash160);
 impl_hash!(H256, Hash256);
+impl_hash!(H512, Hash512);
 impl_hash!(Bloom, Hash2048);
 
 #[cfg(test)]
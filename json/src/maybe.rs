@@ -8,7 +8,7 @@ use serde_json::Value;
 use serde_json::value;
 
 /// Deserializer of empty string values into optionals.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum MaybeEmpty<T> {
 	/// Some.
 	Some(T),
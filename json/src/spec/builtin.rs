@@ -25,12 +25,34 @@ pub struct Linear {
 	pub word: usize,
 }
 
+/// Pricing for modular exponentiation.
+#[derive(Debug, PartialEq, Deserialize, Clone)]
+pub struct Modexp {
+	/// Price divisor.
+	pub divisor: usize,
+}
+
+/// Pricing for alt_bn128 pairing check.
+#[derive(Debug, PartialEq, Deserialize, Clone)]
+pub struct AltBn128Pairing {
+	/// Base price.
+	pub base: usize,
+	/// Price per point pair.
+	pub pair: usize,
+}
+
 /// Pricing variants.
 #[derive(Debug, PartialEq, Deserialize, Clone)]
 pub enum Pricing {
 	/// Linear pricing.
 	#[serde(rename="linear")]
 	Linear(Linear),
+	/// Pricing for modular exponentiation.
+	#[serde(rename="modexp")]
+	Modexp(Modexp),
+	/// Pricing for alt_bn128 pairing check.
+	#[serde(rename="alt_bn128_pairing")]
+	AltBn128Pairing(AltBn128Pairing),
 }
 
 /// Spec builtin.
@@ -40,12 +62,14 @@ pub struct Builtin {
 	pub name: String,
 	/// Builtin pricing.
 	pub pricing: Pricing,
+	/// Block number at which the builtin becomes active.
+	pub activate_at: Option<u64>,
 }
 
 #[cfg(test)]
 mod tests {
 	use serde_json;
-	use spec::builtin::{Builtin, Pricing, Linear};
+	use spec::builtin::{Builtin, Pricing, Linear, Modexp, AltBn128Pairing};
 
 	#[test]
 	fn builtin_deserialization() {
@@ -56,5 +80,32 @@ mod tests {
 		let deserialized: Builtin = serde_json::from_str(s).unwrap();
 		assert_eq!(deserialized.name, "ecrecover");
 		assert_eq!(deserialized.pricing, Pricing::Linear(Linear { base: 3000, word: 0 }));
+		assert!(deserialized.activate_at.is_none());
+	}
+
+	#[test]
+	fn activate_at() {
+		let s = r#"{
+			"name": "late_start",
+			"activate_at": 100000,
+			"pricing": { "modexp": { "divisor": 5 } }
+		}"#;
+
+		let deserialized: Builtin = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.name, "late_start");
+		assert_eq!(deserialized.pricing, Pricing::Modexp(Modexp { divisor: 5 }));
+		assert_eq!(deserialized.activate_at, Some(100000));
+	}
+
+	#[test]
+	fn alt_bn128_pairing_deserialization() {
+		let s = r#"{
+			"name": "alt_bn128_pairing",
+			"activate_at": 4370000,
+			"pricing": { "alt_bn128_pairing": { "base": 100000, "pair": 80000 } }
+		}"#;
+
+		let deserialized: Builtin = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.pricing, Pricing::AltBn128Pairing(AltBn128Pairing { base: 100000, pair: 80000 }));
 	}
 }
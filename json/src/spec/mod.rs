@@ -28,7 +28,7 @@ pub mod ethash;
 pub mod basic_authority;
 
 pub use self::account::Account;
-pub use self::builtin::{Builtin, Pricing, Linear};
+pub use self::builtin::{Builtin, Pricing, Linear, Modexp, AltBn128Pairing};
 pub use self::genesis::Genesis;
 pub use self::params::Params;
 pub use self::spec::Spec;
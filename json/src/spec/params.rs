@@ -17,7 +17,7 @@
 //! Spec params deserialization.
 
 use uint::Uint;
-use hash::H256;
+use hash::{H256, H512, Address};
 
 /// Spec params.
 #[derive(Debug, PartialEq, Deserialize)]
@@ -43,6 +43,15 @@ pub struct Params {
 	/// Expected fork block hash.
 	#[serde(rename="forkCanonHash")]
 	pub fork_hash: Option<H256>,
+	/// Contract deciding which transactions are allowed.
+	#[serde(rename="transactionPermissionContract")]
+	pub transaction_permission_contract: Option<Address>,
+	/// Contract deciding which nodes may connect.
+	#[serde(rename="nodePermissionContract")]
+	pub node_permission_contract: Option<Address>,
+	/// Ids of the only nodes allowed to connect.
+	#[serde(rename="allowedNodes")]
+	pub allowed_nodes: Option<Vec<H512>>,
 }
 
 #[cfg(test)]
@@ -58,7 +67,10 @@ mod tests {
 			"networkID" : "0x1",
 			"subprotocolName" : "exp",
 			"minGasLimit": "0x1388",
-			"accountStartNonce": "0x00"
+			"accountStartNonce": "0x00",
+			"transactionPermissionContract": "0x0000000000000000000000000000000000000005",
+			"nodePermissionContract": "0x0000000000000000000000000000000000000006",
+			"allowedNodes": ["0x2ec4a3d3a4d5cafa38e0f6c3bd0c8d0a9c1ee3a5d89f9ff1a6ae86f8b1c6a8a5d2ec5c5a4e1f0bc41e9a67f9e6ee4a4ac7b9a5e12c4d4fd65ad30e5d8f6a0e5c"]
 		}"#;
 
 		let _deserialized: Params = serde_json::from_str(s).unwrap();
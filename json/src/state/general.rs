@@ -0,0 +1,175 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! General state test deserialization.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use serde_json;
+use serde_json::Error;
+use uint::Uint;
+use bytes::Bytes;
+use hash::{Address, H256};
+use maybe::MaybeEmpty;
+use state::{Env, AccountState, Transaction};
+
+/// General state test deserializer.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct GeneralTest(BTreeMap<String, GeneralState>);
+
+impl IntoIterator for GeneralTest {
+	type Item = <BTreeMap<String, GeneralState> as IntoIterator>::Item;
+	type IntoIter = <BTreeMap<String, GeneralState> as IntoIterator>::IntoIter;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl GeneralTest {
+	/// Loads test from json.
+	pub fn load<R>(reader: R) -> Result<Self, Error> where R: Read {
+		serde_json::from_reader(reader)
+	}
+}
+
+/// General state test deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct GeneralState {
+	/// Environment.
+	pub env: Env,
+	/// Pre state.
+	#[serde(rename="pre")]
+	pub pre_state: AccountState,
+	/// Transaction variants.
+	pub transaction: MultiTransaction,
+	/// Expected post states by fork name.
+	#[serde(rename="post")]
+	pub post_states: BTreeMap<String, Vec<PostStateResult>>,
+}
+
+/// Transaction with several data, gas limit and value variants.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct MultiTransaction {
+	/// Transaction data variants.
+	pub data: Vec<Bytes>,
+	/// Gas limit variants.
+	#[serde(rename="gasLimit")]
+	pub gas_limit: Vec<Uint>,
+	/// Gas price.
+	#[serde(rename="gasPrice")]
+	pub gas_price: Uint,
+	/// Nonce.
+	pub nonce: Uint,
+	/// Secret key.
+	#[serde(rename="secretKey")]
+	pub secret: H256,
+	/// To.
+	pub to: MaybeEmpty<Address>,
+	/// Value variants.
+	pub value: Vec<Uint>,
+}
+
+impl MultiTransaction {
+	/// Build the transaction variant picked by `indexes`, `None` if an index is out of range.
+	pub fn select(&self, indexes: &PostStateIndexes) -> Option<Transaction> {
+		match (self.data.get(indexes.data), self.gas_limit.get(indexes.gas), self.value.get(indexes.value)) {
+			(Some(data), Some(gas_limit), Some(value)) => Some(Transaction {
+				data: data.clone(),
+				gas_limit: *gas_limit,
+				gas_price: self.gas_price,
+				nonce: self.nonce,
+				secret: self.secret.clone(),
+				to: self.to.clone(),
+				value: *value,
+			}),
+			_ => None,
+		}
+	}
+}
+
+/// Expected post state of one transaction variant.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct PostStateResult {
+	/// Post state root.
+	pub hash: H256,
+	/// Transaction variant.
+	pub indexes: PostStateIndexes,
+}
+
+/// Indexes of the transaction variant.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct PostStateIndexes {
+	/// Index into the data variants.
+	pub data: usize,
+	/// Index into the gas limit variants.
+	pub gas: usize,
+	/// Index into the value variants.
+	pub value: usize,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use state::{GeneralState, PostStateIndexes};
+
+	#[test]
+	fn general_state_deserialization() {
+		let s = r#"{
+			"env" : {
+				"currentCoinbase" : "2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+				"currentDifficulty" : "0x020000",
+				"currentGasLimit" : "0x7fffffffffffffff",
+				"currentNumber" : "0x01",
+				"currentTimestamp" : "0x03e8",
+				"previousHash" : "5e20a0453cecd065ea59c37ac63e079ee08998b6045136a8ce6635c7912ec0b6"
+			},
+			"post" : {
+				"Homestead" : [
+					{
+						"hash" : "2f3d2d8e4fa1fcca4df4e2d4dea8ab7fd0fe2d0e1dda6b6bcb64b0c2b3f1c1c7",
+						"indexes" : { "data" : 0, "gas" : 1, "value" : 0 },
+						"logs" : "1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347"
+					}
+				]
+			},
+			"pre" : {
+				"a94f5374fce5edbc8e2a8697c15331677e6ebf0b" : {
+					"balance" : "0x0de0b6b3a7640000",
+					"code" : "0x",
+					"nonce" : "0x00",
+					"storage" : {
+					}
+				}
+			},
+			"transaction" : {
+				"data" : [ "0x", "0x01" ],
+				"gasLimit" : [ "0x061a80", "0x0f4240" ],
+				"gasPrice" : "0x01",
+				"nonce" : "0x00",
+				"secretKey" : "45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+				"to" : "1000000000000000000000000000000000000000",
+				"value" : [ "0x00" ]
+			}
+		}"#;
+		let deserialized: GeneralState = serde_json::from_str(s).unwrap();
+		let post = &deserialized.post_states["Homestead"][0];
+		assert_eq!(post.indexes, PostStateIndexes { data: 0, gas: 1, value: 0 });
+		let transaction = deserialized.transaction.select(&post.indexes).unwrap();
+		assert_eq!(transaction.gas_limit, deserialized.transaction.gas_limit[1]);
+		assert!(deserialized.transaction.select(&PostStateIndexes { data: 2, gas: 0, value: 0 }).is_none());
+	}
+}
@@ -0,0 +1,159 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Log file with size-based rotation.
+
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Number of rotated files kept next to the active log file.
+const MAX_ROTATED_FILES: usize = 5;
+
+/// Log file which is moved to `<path>.1` once it grows past the size limit.
+/// Older files are shifted up to `<path>.5`, the oldest one is removed.
+pub struct RotatingFile {
+	path: PathBuf,
+	max_size: Option<u64>,
+	size: u64,
+	file: File,
+}
+
+impl RotatingFile {
+	/// Open the log file, appending to an existing one. Without `max_size` the file is never rotated.
+	pub fn create<P: Into<PathBuf>>(path: P, max_size: Option<u64>) -> io::Result<Self> {
+		let mut path = path.into();
+		// the working directory changes when the process is daemonized
+		if path.is_relative() {
+			path = try!(env::current_dir()).join(path);
+		}
+		let file = try!(OpenOptions::new().create(true).append(true).open(&path));
+		Ok(RotatingFile {
+			path: path,
+			max_size: max_size,
+			size: try!(file.metadata()).len(),
+			file: file,
+		})
+	}
+
+	/// Write a single line, rotating the file first if it would exceed the limit.
+	pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+		let len = line.len() as u64 + 1;
+		if let Some(max_size) = self.max_size {
+			if self.size > 0 && self.size + len > max_size {
+				try!(self.rotate());
+			}
+		}
+
+		try!(self.file.write_all(line.as_bytes()));
+		try!(self.file.write_all(b"\n"));
+		self.size += len;
+		Ok(())
+	}
+
+	/// Reopen the file at its path, e.g. after it was moved away by an external log rotation tool.
+	pub fn reopen(&mut self) -> io::Result<()> {
+		let file = try!(OpenOptions::new().create(true).append(true).open(&self.path));
+		self.size = try!(file.metadata()).len();
+		self.file = file;
+		Ok(())
+	}
+
+	fn rotated_path(&self, index: usize) -> PathBuf {
+		let mut path = self.path.clone().into_os_string();
+		path.push(format!(".{}", index));
+		path.into()
+	}
+
+	fn rotate(&mut self) -> io::Result<()> {
+		let _ = fs::remove_file(self.rotated_path(MAX_ROTATED_FILES));
+		for index in (1..MAX_ROTATED_FILES).rev() {
+			let from = self.rotated_path(index);
+			if from.exists() {
+				try!(fs::rename(&from, self.rotated_path(index + 1)));
+			}
+		}
+		try!(fs::rename(&self.path, self.rotated_path(1)));
+
+		self.file = try!(File::create(&self.path));
+		self.size = 0;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::io::Read;
+	use std::path::Path;
+	use devtools::RandomTempPath;
+	use super::RotatingFile;
+
+	fn read(path: &Path) -> String {
+		let mut s = String::new();
+		fs::File::open(path).unwrap().read_to_string(&mut s).unwrap();
+		s
+	}
+
+	#[test]
+	fn should_rotate_when_size_exceeded() {
+		let temp = RandomTempPath::create_dir();
+		let dir = temp.as_path();
+		let path = dir.join("parity.log");
+
+		let mut file = RotatingFile::create(path.clone(), Some(10)).unwrap();
+		for line in &["aaaa", "bbbb", "cccc", "dddd", "eeee", "ffff", "gggg", "hhhh", "iiii", "jjjj", "kkkk", "llll", "mmmm"] {
+			file.write_line(line).unwrap();
+		}
+
+		assert_eq!(read(&path), "mmmm\n");
+		assert_eq!(read(&dir.join("parity.log.1")), "kkkk\nllll\n");
+		assert_eq!(read(&dir.join("parity.log.5")), "cccc\ndddd\n");
+		assert!(!dir.join("parity.log.6").exists());
+	}
+
+	#[test]
+	fn should_reopen_moved_file() {
+		let temp = RandomTempPath::create_dir();
+		let dir = temp.as_path();
+		let path = dir.join("parity.log");
+
+		let mut file = RotatingFile::create(path.clone(), None).unwrap();
+		file.write_line("before").unwrap();
+		fs::rename(&path, dir.join("parity.log.old")).unwrap();
+		file.reopen().unwrap();
+		file.write_line("after").unwrap();
+
+		assert_eq!(read(&dir.join("parity.log.old")), "before\n");
+		assert_eq!(read(&path), "after\n");
+	}
+
+	#[test]
+	fn should_append_to_existing_file() {
+		let temp = RandomTempPath::create_dir();
+		let dir = temp.as_path();
+		let path = dir.join("parity.log");
+
+		RotatingFile::create(path.clone(), Some(10)).unwrap().write_line("aaaa").unwrap();
+		let mut file = RotatingFile::create(path.clone(), Some(10)).unwrap();
+		file.write_line("bbbb").unwrap();
+		file.write_line("cccc").unwrap();
+
+		assert_eq!(read(&path), "cccc\n");
+		assert_eq!(read(&dir.join("parity.log.1")), "aaaa\nbbbb\n");
+	}
+}
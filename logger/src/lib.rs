@@ -25,22 +25,30 @@ extern crate env_logger;
 extern crate time;
 #[macro_use]
 extern crate lazy_static;
+#[cfg(test)]
+extern crate ethcore_devtools as devtools;
+
+mod file;
 
 use std::{env, thread};
 use std::sync::Arc;
-use std::fs::File;
-use std::io::Write;
 use isatty::{stderr_isatty, stdout_isatty};
-use env_logger::LogBuilder;
+use env_logger::{LogBuilder, Logger as EnvLogger};
+use rlog::{Log, LogLevel, LogLevelFilter, LogMetadata, LogRecord, MaxLogLevelFilter};
 use regex::Regex;
-use util::RotatingLogger;
+use util::{Mutex, RwLock, RotatingLogger};
 use util::log::Colour;
+use file::RotatingFile;
 
 #[derive(Debug, PartialEq)]
 pub struct Config {
 	pub mode: Option<String>,
 	pub color: bool,
 	pub file: Option<String>,
+	/// Size in bytes after which the log file is rotated.
+	pub file_max_size: Option<u64>,
+	/// Output log lines as JSON objects.
+	pub json: bool,
 }
 
 impl Default for Config {
@@ -49,48 +57,38 @@ impl Default for Config {
 			mode: None,
 			color: !cfg!(windows),
 			file: None,
+			file_max_size: None,
+			json: false,
 		}
 	}
 }
 
-/// Sets up the logger
-pub fn setup_log(config: &Config) -> Result<Arc<RotatingLogger>, String> {
-	use rlog::*;
-
-	let mut levels = String::new();
-	let mut builder = LogBuilder::new();
-	// Disable ws info logging by default.
-	builder.filter(Some("ws"), LogLevelFilter::Warn);
-	// Disable rustls info logging by default.
-	builder.filter(Some("rustls"), LogLevelFilter::Warn);
-	builder.filter(None, LogLevelFilter::Info);
+/// Formats log records and copies them to the log file and in-memory logs.
+struct Format {
+	enable_color: bool,
+	isatty: bool,
+	json: bool,
+	file: Option<Arc<Mutex<RotatingFile>>>,
+	logs: Arc<RotatingLogger>,
+}
 
-	if env::var("RUST_LOG").is_ok() {
-		let lvl = &env::var("RUST_LOG").unwrap();
-		levels.push_str(lvl);
-		levels.push_str(",");
-		builder.parse(lvl);
-	}
+lazy_static! {
+	static ref LOG_FILE: Mutex<Option<Arc<Mutex<RotatingFile>>>> = Mutex::new(None);
+}
 
-	if let Some(ref s) = config.mode {
-		levels.push_str(s);
-		builder.parse(s);
+/// Reopen the log file set up by `setup_log`. Used to release the old file after external log rotation.
+pub fn reopen_log_file() -> Result<(), String> {
+	match *LOG_FILE.lock() {
+		Some(ref file) => file.lock().reopen().map_err(|e| format!("Cannot reopen log file: {}", e)),
+		None => Ok(()),
 	}
+}
 
-	let isatty = stderr_isatty();
-	let enable_color = config.color && isatty;
-	let logs = Arc::new(RotatingLogger::new(levels));
-	let logger = logs.clone();
-
-	let maybe_file = match config.file.as_ref() {
-		Some(f) => Some(try!(File::create(f).map_err(|_| format!("Cannot write to log file given: {}", f)))),
-		None => None,
-	};
-
-	let format = move |record: &LogRecord| {
+impl Format {
+	fn format(&self, record: &LogRecord) -> String {
 		let timestamp = time::strftime("%Y-%m-%d %H:%M:%S %Z", &time::now()).unwrap();
 
-		let with_color = if max_log_level() <= LogLevelFilter::Info {
+		let with_color = if rlog::max_log_level() <= LogLevelFilter::Info {
 			format!("{} {}", Colour::Black.bold().paint(timestamp), record.args())
 		} else {
 			let name = thread::current().name().map_or_else(Default::default, |x| format!("{}", Colour::Blue.bold().paint(x)));
@@ -99,28 +97,210 @@ pub fn setup_log(config: &Config) -> Result<Arc<RotatingLogger>, String> {
 
 		let removed_color = kill_color(with_color.as_ref());
 
-		let ret = match enable_color {
-			true => with_color,
-			false => removed_color.clone(),
+		let ret = match (self.json, self.enable_color) {
+			(true, _) => json_line(record),
+			(false, true) => with_color,
+			(false, false) => removed_color.clone(),
 		};
 
-		if let Some(mut file) = maybe_file.as_ref() {
+		if let Some(ref file) = self.file {
+			let line = match self.json {
+				true => &ret,
+				false => &removed_color,
+			};
 			// ignore errors - there's nothing we can do
-			let _ = file.write_all(removed_color.as_bytes());
-			let _ = file.write_all(b"\n");
+			let _ = file.lock().write_line(line);
 		}
-		logger.append(removed_color);
-		if !isatty && record.level() <= LogLevel::Info && stdout_isatty() {
+		self.logs.append(removed_color);
+		if !self.isatty && record.level() <= LogLevel::Info && stdout_isatty() {
 			// duplicate INFO/WARN output to console
 			println!("{}", ret);
 		}
 
 		ret
-    };
+	}
+}
+
+/// Logger whose level directives can be changed while it is running.
+struct Logger {
+	format: Arc<Format>,
+	levels: Mutex<String>,
+	inner: RwLock<EnvLogger>,
+	max_level: Mutex<Option<MaxLogLevelFilter>>,
+}
+
+impl Logger {
+	fn new(levels: String, format: Arc<Format>) -> Self {
+		Logger {
+			inner: RwLock::new(build_logger(&levels, format.clone())),
+			format: format,
+			levels: Mutex::new(levels),
+			max_level: Mutex::new(None),
+		}
+	}
+
+	/// Apply `directives` on top of the current levels.
+	fn set_levels(&self, directives: &str) -> Result<(), String> {
+		try!(validate_directives(directives));
+
+		let mut levels = self.levels.lock();
+		let new_levels = combine_levels(&levels, directives);
+		let logger = build_logger(&new_levels, self.format.clone());
+		if let Some(ref max_level) = *self.max_level.lock() {
+			max_level.set(logger.filter());
+		}
+		*self.inner.write() = logger;
+		*levels = new_levels;
+		Ok(())
+	}
+}
+
+struct SharedLogger(Arc<Logger>);
+
+impl Log for SharedLogger {
+	fn enabled(&self, metadata: &LogMetadata) -> bool {
+		self.0.inner.read().enabled(metadata)
+	}
+
+	fn log(&self, record: &LogRecord) {
+		self.0.inner.read().log(record)
+	}
+}
+
+fn build_logger(levels: &str, format: Arc<Format>) -> EnvLogger {
+	let mut builder = LogBuilder::new();
+	// Disable ws info logging by default.
+	builder.filter(Some("ws"), LogLevelFilter::Warn);
+	// Disable rustls info logging by default.
+	builder.filter(Some("rustls"), LogLevelFilter::Warn);
+	builder.filter(None, LogLevelFilter::Info);
+	builder.parse(levels);
+	builder.format(move |record: &LogRecord| format.format(record));
+	builder.build()
+}
 
-	builder.format(format);
-	builder.init().unwrap();
+/// Apply module `directives` on top of `levels`, replacing the entries for the same modules.
+/// `env_logger` only accepts a single message filter at the very end of the spec,
+/// so the filter of `directives` replaces the one of `levels`.
+fn combine_levels(levels: &str, directives: &str) -> String {
+	let mut current = levels.splitn(2, '/');
+	let current_modules = current.next().unwrap_or("");
+	let current_filter = current.next();
+	let mut new = directives.splitn(2, '/');
+	let new_modules = new.next().unwrap_or("");
+	let filter = new.next().or(current_filter);
 
+	let new_modules = new_modules.split(',').filter(|d| !d.is_empty()).collect::<Vec<_>>();
+	let modules = current_modules.split(',')
+		.filter(|d| !d.is_empty())
+		.filter(|d| !new_modules.iter().any(|n| directive_module(n) == directive_module(d)))
+		.chain(new_modules.iter().cloned())
+		.collect::<Vec<_>>()
+		.join(",");
+	match filter {
+		Some(filter) => format!("{}/{}", modules, filter),
+		None => modules,
+	}
+}
+
+/// Module a level directive applies to, empty for the default level.
+fn directive_module(directive: &str) -> &str {
+	match directive.find('=') {
+		Some(pos) => &directive[..pos],
+		None if directive.parse::<LogLevelFilter>().is_ok() => "",
+		None => directive,
+	}
+}
+
+/// Check that `directives` conform to the `RUST_LOG` format, which `env_logger` would otherwise silently skip.
+fn validate_directives(directives: &str) -> Result<(), String> {
+	let mut parts = directives.split('/');
+	let modules = parts.next().unwrap_or("");
+	if let Some(filter) = parts.next() {
+		try!(Regex::new(filter).map_err(|e| format!("Invalid log filter {}: {}", filter, e)));
+	}
+	if parts.next().is_some() {
+		return Err(format!("Invalid logging spec {}: too many '/'s", directives));
+	}
+
+	for directive in modules.split(',').filter(|d| !d.is_empty()) {
+		let mut parts = directive.split('=');
+		match (parts.next(), parts.next(), parts.next()) {
+			(Some(_), None, None) => {},
+			(Some(module), Some(level), None) if !module.is_empty() => {
+				if !level.trim().is_empty() {
+					try!(level.trim().parse::<LogLevelFilter>().map_err(|_| format!("Invalid log level in {}", directive)));
+				}
+			},
+			_ => return Err(format!("Invalid logging directive {}", directive)),
+		}
+	}
+	Ok(())
+}
+
+fn escape_json(s: &str) -> String {
+	let mut escaped = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'"' => escaped.push_str("\\\""),
+			'\\' => escaped.push_str("\\\\"),
+			'\n' => escaped.push_str("\\n"),
+			'\r' => escaped.push_str("\\r"),
+			'\t' => escaped.push_str("\\t"),
+			c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+fn json_line(record: &LogRecord) -> String {
+	let message = kill_color(&format!("{}", record.args()));
+	format!(
+		"{{\"timestamp\":\"{}\",\"level\":\"{}\",\"target\":\"{}\",\"thread\":\"{}\",\"message\":\"{}\"}}",
+		time::now_utc().rfc3339(),
+		record.level(),
+		escape_json(record.target()),
+		escape_json(thread::current().name().unwrap_or("")),
+		escape_json(&message)
+	)
+}
+
+/// Sets up the logger
+pub fn setup_log(config: &Config) -> Result<Arc<RotatingLogger>, String> {
+	let mut levels = env::var("RUST_LOG").unwrap_or_else(|_| String::new());
+
+	if let Some(ref s) = config.mode {
+		levels = combine_levels(&levels, s);
+	}
+
+	let isatty = stderr_isatty();
+	let logs = Arc::new(RotatingLogger::new(levels.clone()));
+
+	let file = match config.file.as_ref() {
+		Some(f) => Some(Arc::new(Mutex::new(try!(RotatingFile::create(f.clone(), config.file_max_size)
+			.map_err(|_| format!("Cannot write to log file given: {}", f)))))),
+		None => None,
+	};
+	*LOG_FILE.lock() = file.clone();
+
+	let format = Arc::new(Format {
+		enable_color: config.color && isatty && !config.json,
+		isatty: isatty,
+		json: config.json,
+		file: file,
+		logs: logs.clone(),
+	});
+
+	let logger = Arc::new(Logger::new(levels, format));
+	let shared = logger.clone();
+	try!(rlog::set_logger(move |max_level| {
+		max_level.set(shared.inner.read().filter());
+		*shared.max_level.lock() = Some(max_level);
+		Box::new(SharedLogger(shared))
+	}).map_err(|e| format!("Cannot set up logger: {}", e)));
+
+	logs.set_levels_handler(Box::new(move |directives| logger.set_levels(directives)));
 	Ok(logs)
 }
 
@@ -144,3 +324,41 @@ fn should_remove_multiple_colour() {
 	let after = kill_color(&t);
 	assert_eq!(after, "test again");
 }
+
+#[test]
+fn should_validate_directives() {
+	assert!(validate_directives("sync=trace").is_ok());
+	assert!(validate_directives("info,sync=trace,own_tx").is_ok());
+	assert!(validate_directives("sync=trace/peer").is_ok());
+	assert!(validate_directives("sync=loud").is_err());
+	assert!(validate_directives("sync=trace=debug").is_err());
+	assert!(validate_directives("=trace").is_err());
+	assert!(validate_directives("sync/a/b").is_err());
+}
+
+#[test]
+fn should_keep_filter_at_end_when_combining_levels() {
+	assert_eq!(combine_levels("", "sync=trace"), "sync=trace");
+	assert_eq!(combine_levels("info,", "sync=trace"), "info,sync=trace");
+	assert_eq!(combine_levels("info/peer", "sync=trace"), "info,sync=trace/peer");
+	assert_eq!(combine_levels("info/peer", "sync=trace/block"), "info,sync=trace/block");
+	assert_eq!(combine_levels("info", "/block"), "info/block");
+}
+
+#[test]
+fn should_replace_levels_of_same_module() {
+	assert_eq!(combine_levels("info,sync=trace", "sync=debug"), "info,sync=debug");
+	assert_eq!(combine_levels("info,sync=trace", "warn"), "sync=trace,warn");
+	assert_eq!(combine_levels("info,own_tx", "own_tx=info,miner"), "info,own_tx=info,miner");
+
+	let mut levels = String::new();
+	for _ in 0..10 {
+		levels = combine_levels(&levels, "sync=trace");
+	}
+	assert_eq!(levels, "sync=trace");
+}
+
+#[test]
+fn should_escape_json() {
+	assert_eq!(escape_json("a \"quoted\" \\ path\nnext\u{1}"), "a \\\"quoted\\\" \\\\ path\\nnext\\u0001");
+}
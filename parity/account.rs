@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethcore::ethstore::{EthStore, SecretStore, import_accounts, read_geth_accounts};
+use ethcore::ethstore::{EthStore, SecretStore, KdfParams, import_accounts, read_geth_accounts};
 use ethcore::ethstore::dir::DiskDirectory;
 use ethcore::account_provider::AccountProvider;
 use helpers::{password_prompt, password_from_file};
@@ -29,7 +29,7 @@ pub enum AccountCmd {
 
 #[derive(Debug, PartialEq)]
 pub struct NewAccount {
-	pub iterations: u32,
+	pub kdf: KdfParams,
 	pub path: String,
 	pub password_file: Option<String>,
 }
@@ -62,9 +62,9 @@ fn keys_dir(path: String) -> Result<DiskDirectory, String> {
 	DiskDirectory::create(path).map_err(|e| format!("Could not open keys directory: {}", e))
 }
 
-fn secret_store(dir: Box<DiskDirectory>, iterations: Option<u32>) -> Result<EthStore, String> {
-	match iterations {
-		Some(i) => EthStore::open_with_iterations(dir, i),
+fn secret_store(dir: Box<DiskDirectory>, kdf: Option<KdfParams>) -> Result<EthStore, String> {
+	match kdf {
+		Some(kdf) => EthStore::open_with_kdf(dir, kdf),
 		_ => EthStore::open(dir) 
 	}.map_err(|e| format!("Could not open keys store: {}", e))
 }
@@ -76,7 +76,7 @@ fn new(n: NewAccount) -> Result<String, String> {
 	};
 
 	let dir = Box::new(try!(keys_dir(n.path)));
-	let secret_store = Box::new(try!(secret_store(dir, Some(n.iterations))));
+	let secret_store = Box::new(try!(secret_store(dir, Some(n.kdf))));
 	let acc_provider = AccountProvider::new(secret_store);
 	let new_account = try!(acc_provider.new_account(&password).map_err(|e| format!("Could not create new account: {}", e)));
 	Ok(format!("{:?}", new_account))
@@ -100,7 +100,7 @@ fn import(i: ImportAccounts) -> Result<String, String> {
 	let mut imported = 0;
 	for path in &i.from {
 		let from = DiskDirectory::at(path);
-		imported += try!(import_accounts(&from, &to).map_err(|_| "Importing accounts failed.")).len();
+		imported += try!(import_accounts(&from, &to).map_err(|e| format!("Importing accounts from {} failed: {}", path, e))).len();
 	}
 	Ok(format!("{}", imported))
 }
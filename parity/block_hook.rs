@@ -0,0 +1,224 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runs a user-provided command for every new canonical chain head.
+
+use std::io;
+use std::process::Command;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+use ethcore::client::{BlockChainClient, ChainNotify, Client};
+use ethcore::header::BlockNumber;
+use ethcore::ids::BlockID;
+use ethcore::views::HeaderView;
+use ethsync::SyncProvider;
+use util::{H256, Mutex, Condvar};
+
+/// Minimal interval between command runs while a major sync is in progress.
+const SYNC_DEBOUNCE_SECS: u64 = 60;
+
+/// Chain head waiting for the command to be run.
+#[derive(Default)]
+struct Pending {
+	head: Option<(BlockNumber, H256)>,
+	shutdown: bool,
+}
+
+/// Runs the command on a single worker thread, one head at a time.
+/// Heads arriving while the command is running replace each other, so only the latest one is run next.
+/// The worker is detached: a running command never delays shutdown, it just isn't followed by another one.
+struct Runner {
+	pending: Arc<(Mutex<Pending>, Condvar)>,
+}
+
+impl Runner {
+	fn new(command: String) -> io::Result<Self> {
+		let pending = Arc::new((Mutex::new(Pending::default()), Condvar::new()));
+		{
+			let pending = pending.clone();
+			try!(thread::Builder::new().name("on-new-block".into()).spawn(move || run_pending(&command, &pending)));
+		}
+		Ok(Runner {
+			pending: pending,
+		})
+	}
+
+	fn schedule(&self, number: BlockNumber, hash: H256) {
+		let (ref lock, ref ready) = *self.pending;
+		lock.lock().head = Some((number, hash));
+		ready.notify_one();
+	}
+}
+
+impl Drop for Runner {
+	fn drop(&mut self) {
+		let (ref lock, ref ready) = *self.pending;
+		lock.lock().shutdown = true;
+		ready.notify_one();
+	}
+}
+
+fn run_pending(command: &str, pending: &(Mutex<Pending>, Condvar)) {
+	let (ref lock, ref ready) = *pending;
+	loop {
+		let (number, hash) = {
+			let mut pending = lock.lock();
+			while pending.head.is_none() && !pending.shutdown {
+				ready.wait(&mut pending);
+			}
+			match pending.head.take() {
+				Some(head) if !pending.shutdown => head,
+				_ => return,
+			}
+		};
+		run(command, number, hash);
+	}
+}
+
+fn run(command: &str, number: BlockNumber, hash: H256) {
+	let status = shell(command)
+		.env("PARITY_BLOCK_NUMBER", format!("{}", number))
+		.env("PARITY_BLOCK_HASH", format!("0x{:?}", hash))
+		.status();
+	match status {
+		Ok(ref status) if status.success() => trace!(target: "block_hook", "Block #{} hook finished", number),
+		Ok(status) => warn!(target: "block_hook", "Block #{} hook `{}` exited with {}", number, command, status),
+		Err(e) => warn!(target: "block_hook", "Error running block hook `{}`: {}", command, e),
+	}
+}
+
+/// Spawns the `--on-new-block` command when the chain head changes.
+pub struct BlockHook {
+	runner: Runner,
+	client: Weak<Client>,
+	sync: Arc<SyncProvider>,
+	last_run: Mutex<Option<Instant>>,
+}
+
+impl BlockHook {
+	/// Create a new hook running `command` through the system shell.
+	pub fn new(command: String, client: &Arc<Client>, sync: Arc<SyncProvider>) -> io::Result<Self> {
+		Ok(BlockHook {
+			runner: try!(Runner::new(command)),
+			client: Arc::downgrade(client),
+			sync: sync,
+			last_run: Mutex::new(None),
+		})
+	}
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+	let mut cmd = Command::new("cmd");
+	cmd.arg("/C").arg(command);
+	cmd
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+	let mut cmd = Command::new("sh");
+	cmd.arg("-c").arg(command);
+	cmd
+}
+
+impl ChainNotify for BlockHook {
+	fn new_blocks(&self, _imported: Vec<H256>, _invalid: Vec<H256>, enacted: Vec<H256>, _retracted: Vec<H256>, _sealed: Vec<H256>, _duration: u64) {
+		let hash = match enacted.last() {
+			Some(hash) => *hash,
+			None => return,
+		};
+
+		let mut last_run = self.last_run.lock();
+		if self.sync.status().is_major_syncing() {
+			if let Some(last) = *last_run {
+				if last.elapsed() < Duration::from_secs(SYNC_DEBOUNCE_SECS) {
+					return;
+				}
+			}
+		}
+
+		let header = match self.client.upgrade() {
+			Some(client) => client.block_header(BlockID::Hash(hash)),
+			None => return,
+		};
+		if let Some(header) = header {
+			*last_run = Some(Instant::now());
+			self.runner.schedule(HeaderView::new(&header).number(), hash);
+		}
+	}
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+	use std::fs::File;
+	use std::io::Read;
+	use std::thread;
+	use std::time::{Duration, Instant};
+	use devtools::RandomTempPath;
+	use util::H256;
+	use super::Runner;
+
+	fn wait_for_lines(path: &RandomTempPath, last: &str) -> Vec<String> {
+		for _ in 0..100 {
+			let mut content = String::new();
+			if let Ok(mut file) = File::open(path.as_path()) {
+				file.read_to_string(&mut content).unwrap();
+			}
+			let lines: Vec<String> = content.lines().map(Into::into).collect();
+			if lines.last().map_or(false, |l| l == last) {
+				return lines;
+			}
+			thread::sleep(Duration::from_millis(50));
+		}
+		panic!("the command was not run for {}", last);
+	}
+
+	#[test]
+	fn runs_command_with_block_in_environment() {
+		let path = RandomTempPath::new();
+		let runner = Runner::new(format!("echo \"$PARITY_BLOCK_NUMBER $PARITY_BLOCK_HASH\" >> {}", path.as_str())).unwrap();
+		runner.schedule(5, H256::from(1));
+
+		let lines = wait_for_lines(&path, &format!("5 0x{:?}", H256::from(1)));
+		assert_eq!(lines.len(), 1);
+	}
+
+	#[test]
+	fn runs_only_latest_head_while_busy() {
+		let path = RandomTempPath::new();
+		let runner = Runner::new(format!("sleep 0.2; echo $PARITY_BLOCK_NUMBER >> {}", path.as_str())).unwrap();
+		for number in 1..5 {
+			runner.schedule(number, H256::from(number));
+		}
+
+		let lines = wait_for_lines(&path, "4");
+		assert!(lines.len() <= 2);
+		drop(runner);
+	}
+
+	#[test]
+	fn does_not_wait_for_running_command_on_drop() {
+		let path = RandomTempPath::new();
+		let runner = Runner::new(format!("echo started >> {}; sleep 5", path.as_str())).unwrap();
+		runner.schedule(1, H256::from(1));
+		wait_for_lines(&path, "started");
+
+		let start = Instant::now();
+		drop(runner);
+		assert!(start.elapsed() < Duration::from_secs(1));
+	}
+}
@@ -16,15 +16,15 @@
 
 use std::str::{FromStr, from_utf8};
 use std::{io, fs};
-use std::io::{BufReader, BufRead};
+use std::io::{BufReader, BufRead, Read};
 use std::time::{Instant, Duration};
 use std::thread::sleep;
 use std::sync::Arc;
-use rustc_serialize::hex::FromHex;
+use rustc_serialize::hex::{FromHex, ToHex};
 use ethcore_logger::{setup_log, Config as LogConfig};
 use io::{PanicHandler, ForwardPanic};
-use util::{ToPretty, Uint};
-use rlp::PayloadInfo;
+use util::{ToPretty, Uint, U256, Address};
+use rlp::{RlpReader, View};
 use ethcore::service::ClientService;
 use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, BlockImportError, BlockChainClient, BlockID};
 use ethcore::error::ImportError;
@@ -37,11 +37,14 @@ use helpers::{to_client_config, execute_upgrades};
 use dir::Directories;
 use user_defaults::UserDefaults;
 use fdlimit;
+use geth_chaindata::GethChainData;
 
 #[derive(Debug, PartialEq)]
 pub enum DataFormat {
 	Hex,
 	Binary,
+	/// go-ethereum `chaindata` directory; import only.
+	Geth,
 }
 
 impl Default for DataFormat {
@@ -55,8 +58,9 @@ impl FromStr for DataFormat {
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
 		match s {
-			"binary" | "bin" => Ok(DataFormat::Binary),
+			"binary" | "bin" | "rlp" => Ok(DataFormat::Binary),
 			"hex" => Ok(DataFormat::Hex),
+			"geth" => Ok(DataFormat::Geth),
 			x => Err(format!("Invalid format: {}", x))
 		}
 	}
@@ -66,6 +70,7 @@ impl FromStr for DataFormat {
 pub enum BlockchainCmd {
 	Import(ImportBlockchain),
 	Export(ExportBlockchain),
+	ExportState(ExportState),
 }
 
 #[derive(Debug, PartialEq)]
@@ -103,10 +108,31 @@ pub struct ExportBlockchain {
 	pub to_block: BlockID,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct ExportState {
+	pub spec: SpecType,
+	pub logger_config: LogConfig,
+	pub cache_config: CacheConfig,
+	pub dirs: Directories,
+	pub file_path: Option<String>,
+	pub pruning: Pruning,
+	pub compaction: DatabaseCompactionProfile,
+	pub wal: bool,
+	pub mode: Mode,
+	pub fat_db: Switch,
+	pub tracing: Switch,
+	pub at: BlockID,
+	/// Only accounts with at least this balance are exported.
+	pub min_balance: Option<U256>,
+	/// Accounts to export; all accounts (requires fat db) if `None`.
+	pub accounts: Option<Vec<Address>>,
+}
+
 pub fn execute(cmd: BlockchainCmd) -> Result<String, String> {
 	match cmd {
 		BlockchainCmd::Import(import_cmd) => execute_import(import_cmd),
 		BlockchainCmd::Export(export_cmd) => execute_export(export_cmd),
+		BlockchainCmd::ExportState(export_cmd) => execute_export_state(export_cmd),
 	}
 }
 
@@ -125,11 +151,8 @@ fn execute_import(cmd: ImportBlockchain) -> Result<String, String> {
 	// load spec file
 	let spec = try!(cmd.spec.spec());
 
-	// load genesis hash
-	let genesis_hash = spec.genesis_header().hash();
-
 	// database paths
-	let db_dirs = cmd.dirs.database(genesis_hash, spec.fork_name.clone());
+	let db_dirs = try!(cmd.dirs.database(&spec));
 
 	// user defaults path
 	let user_defaults_path = db_dirs.user_defaults_path();
@@ -140,7 +163,7 @@ fn execute_import(cmd: ImportBlockchain) -> Result<String, String> {
 	fdlimit::raise_fd_limit();
 
 	// select pruning algorithm
-	let algorithm = cmd.pruning.to_algorithm(&user_defaults);
+	let algorithm = try!(cmd.pruning.to_algorithm(&user_defaults, &db_dirs.existing_pruning()));
 
 	// check if tracing is on
 	let tracing = try!(tracing_switch_to_bool(cmd.tracing, &user_defaults));
@@ -153,10 +176,11 @@ fn execute_import(cmd: ImportBlockchain) -> Result<String, String> {
 	let snapshot_path = db_dirs.snapshot_path();
 
 	// execute upgrades
-	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile()));
+	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile(&client_path)));
 
 	// prepare client config
-	let client_config = to_client_config(&cmd.cache_config, cmd.mode, tracing, fat_db, cmd.compaction, cmd.wal, cmd.vm_type,  "".into(), algorithm);
+	let mut client_config = to_client_config(&cmd.cache_config, cmd.mode, tracing, fat_db, cmd.compaction, cmd.wal, cmd.vm_type,  "".into(), algorithm);
+	client_config.ancient_path = cmd.dirs.ancient_path.clone();
 
 	// build client
 	let service = try!(ClientService::start(
@@ -171,27 +195,6 @@ fn execute_import(cmd: ImportBlockchain) -> Result<String, String> {
 	panic_handler.forward_from(&service);
 	let client = service.client();
 
-	let mut instream: Box<io::Read> = match cmd.file_path {
-		Some(f) => Box::new(try!(fs::File::open(&f).map_err(|_| format!("Cannot open given file: {}", f)))),
-		None => Box::new(io::stdin()),
-	};
-
-	const READAHEAD_BYTES: usize = 8;
-
-	let mut first_bytes: Vec<u8> = vec![0; READAHEAD_BYTES];
-	let mut first_read = 0;
-
-	let format = match cmd.format {
-		Some(format) => format,
-		None => {
-			first_read = try!(instream.read(&mut first_bytes).map_err(|_| "Error reading from the file/stream."));
-			match first_bytes[0] {
-				0xf9 => DataFormat::Binary,
-				_ => DataFormat::Hex,
-			}
-		}
-	};
-
 	let informant = Informant::new(client.clone(), None, None, cmd.logger_config.color);
 
 	try!(service.register_io_handler(Arc::new(ImportIoHandler {
@@ -212,35 +215,20 @@ fn execute_import(cmd: ImportBlockchain) -> Result<String, String> {
 		Ok(())
 	};
 
-
-	match format {
-		DataFormat::Binary => {
-			loop {
-				let mut bytes = if first_read > 0 {first_bytes.clone()} else {vec![0; READAHEAD_BYTES]};
-				let n = if first_read > 0 {
-					first_read
-				} else {
-					try!(instream.read(&mut bytes).map_err(|_| "Error reading from the file/stream."))
-				};
-				if n == 0 { break; }
-				first_read = 0;
-				let s = try!(PayloadInfo::from(&bytes).map_err(|e| format!("Invalid RLP in the file/stream: {:?}", e))).total();
-				bytes.resize(s, 0);
-				try!(instream.read_exact(&mut bytes[n..]).map_err(|_| "Error reading from the file/stream."));
-				try!(do_import(bytes));
-			}
-		}
-		DataFormat::Hex => {
-			for line in BufReader::new(instream).lines() {
-				let s = try!(line.map_err(|_| "Error reading from the file/stream."));
-				let s = if first_read > 0 {from_utf8(&first_bytes).unwrap().to_owned() + &(s[..])} else {s};
-				first_read = 0;
-				let bytes = try!(s.from_hex().map_err(|_| "Invalid hex in file/stream."));
-				try!(do_import(bytes));
-			}
+	if let Some(DataFormat::Geth) = cmd.format {
+		let path = try!(cmd.file_path.ok_or("Path to geth chaindata directory is required."));
+		let chaindata = try!(GethChainData::open(&path));
+		// genesis is already in the database.
+		let mut number = 1;
+		while let Some(bytes) = try!(chaindata.block(number)) {
+			try!(do_import(bytes));
+			number += 1;
 		}
+	} else {
+		try!(import_stream(cmd.file_path, cmd.format, &do_import));
 	}
 	client.flush_queue();
+	try!(service.shutdown());
 
 	// save user defaults
 	user_defaults.pruning = algorithm;
@@ -261,26 +249,71 @@ fn execute_import(cmd: ImportBlockchain) -> Result<String, String> {
 	).into())
 }
 
-fn execute_export(cmd: ExportBlockchain) -> Result<String, String> {
-	// Setup panic handler
-	let panic_handler = PanicHandler::new_in_arc();
+fn import_stream<F>(file_path: Option<String>, format: Option<DataFormat>, do_import: &F) -> Result<(), String> where F: Fn(Vec<u8>) -> Result<(), String> {
+	let mut instream: Box<io::Read> = match file_path {
+		Some(f) => Box::new(try!(fs::File::open(&f).map_err(|_| format!("Cannot open given file: {}", f)))),
+		None => Box::new(io::stdin()),
+	};
 
-	// Setup logging
-	let _logger = setup_log(&cmd.logger_config);
+	const READAHEAD_BYTES: usize = 8;
 
-	// create dirs used by parity
-	try!(cmd.dirs.create_dirs());
+	let mut first_bytes: Vec<u8> = vec![0; READAHEAD_BYTES];
+	let mut first_read = 0;
 
-	let format = cmd.format.unwrap_or_default();
+	let format = match format {
+		Some(format) => format,
+		None => {
+			first_read = try!(instream.read(&mut first_bytes).map_err(|_| "Error reading from the file/stream."));
+			// every block is an RLP list longer than 55 bytes, hex dumps start with an ASCII digit.
+			match first_bytes[0] {
+				0xf8...0xff => DataFormat::Binary,
+				_ => DataFormat::Hex,
+			}
+		}
+	};
 
-	// load spec file
-	let spec = try!(cmd.spec.spec());
+	match format {
+		DataFormat::Binary => {
+			// blocks are read one by one, so the dump never has to fit in memory.
+			let mut reader = RlpReader::new(io::Cursor::new(&first_bytes[..first_read]).chain(instream));
+			while let Some(block) = try!(reader.next_item().map_err(|e| format!("Invalid RLP in the file/stream: {:?}", e))) {
+				try!(do_import(block.as_raw().to_vec()));
+			}
+		}
+		DataFormat::Hex => {
+			for line in BufReader::new(instream).lines() {
+				let s = try!(line.map_err(|_| "Error reading from the file/stream."));
+				let s = if first_read > 0 {from_utf8(&first_bytes).unwrap().to_owned() + &(s[..])} else {s};
+				first_read = 0;
+				let bytes = try!(s.from_hex().map_err(|_| "Invalid hex in file/stream."));
+				try!(do_import(bytes));
+			}
+		}
+		DataFormat::Geth => unreachable!("geth chaindata is handled separately; qed"),
+	}
+	Ok(())
+}
 
-	// load genesis hash
-	let genesis_hash = spec.genesis_header().hash();
+fn start_client(
+	dirs: Directories,
+	spec: SpecType,
+	pruning: Pruning,
+	tracing: Switch,
+	fat_db: Switch,
+	compaction: DatabaseCompactionProfile,
+	wal: bool,
+	cache_config: CacheConfig,
+	mode: Mode,
+) -> Result<ClientService, String> {
+
+	// create dirs used by parity
+	try!(dirs.create_dirs());
+
+	// load spec file
+	let spec = try!(spec.spec());
 
 	// database paths
-	let db_dirs = cmd.dirs.database(genesis_hash, spec.fork_name.clone());
+	let db_dirs = try!(dirs.database(&spec));
 
 	// user defaults path
 	let user_defaults_path = db_dirs.user_defaults_path();
@@ -291,33 +324,51 @@ fn execute_export(cmd: ExportBlockchain) -> Result<String, String> {
 	fdlimit::raise_fd_limit();
 
 	// select pruning algorithm
-	let algorithm = cmd.pruning.to_algorithm(&user_defaults);
+	let algorithm = try!(pruning.to_algorithm(&user_defaults, &db_dirs.existing_pruning()));
 
 	// check if tracing is on
-	let tracing = try!(tracing_switch_to_bool(cmd.tracing, &user_defaults));
+	let tracing = try!(tracing_switch_to_bool(tracing, &user_defaults));
 
 	// check if fatdb is on
-	let fat_db = try!(fatdb_switch_to_bool(cmd.fat_db, &user_defaults, algorithm));
+	let fat_db = try!(fatdb_switch_to_bool(fat_db, &user_defaults, algorithm));
 
 	// prepare client and snapshot paths.
 	let client_path = db_dirs.client_path(algorithm);
 	let snapshot_path = db_dirs.snapshot_path();
 
 	// execute upgrades
-	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile()));
+	try!(execute_upgrades(&db_dirs, algorithm, compaction.compaction_profile(&client_path)));
 
 	// prepare client config
-	let client_config = to_client_config(&cmd.cache_config, cmd.mode, tracing, fat_db, cmd.compaction, cmd.wal, VMType::default(), "".into(), algorithm);
+	let mut client_config = to_client_config(&cache_config, mode, tracing, fat_db, compaction, wal, VMType::default(), "".into(), algorithm);
+	client_config.ancient_path = dirs.ancient_path.clone();
 
 	let service = try!(ClientService::start(
 		client_config,
 		&spec,
 		&client_path,
 		&snapshot_path,
-		&cmd.dirs.ipc_path(),
+		&dirs.ipc_path(),
 		Arc::new(Miner::with_spec(&spec)),
 	).map_err(|e| format!("Client service error: {:?}", e)));
 
+	Ok(service)
+}
+
+fn execute_export(cmd: ExportBlockchain) -> Result<String, String> {
+	// Setup panic handler
+	let panic_handler = PanicHandler::new_in_arc();
+
+	// Setup logging
+	let _logger = setup_log(&cmd.logger_config);
+
+	let format = cmd.format.unwrap_or_default();
+	if format == DataFormat::Geth {
+		return Err("Exporting to geth chaindata is not supported.".into());
+	}
+
+	let service = try!(start_client(cmd.dirs, cmd.spec, cmd.pruning, cmd.tracing, cmd.fat_db, cmd.compaction, cmd.wal, cmd.cache_config, cmd.mode));
+
 	panic_handler.forward_from(&service);
 	let client = service.client();
 
@@ -326,28 +377,142 @@ fn execute_export(cmd: ExportBlockchain) -> Result<String, String> {
 		None => Box::new(io::stdout()),
 	};
 
-	let from = try!(client.block_number(cmd.from_block).ok_or("From block could not be found"));
-	let to = try!(client.block_number(cmd.to_block).ok_or("To block could not be found"));
-
-	for i in from..(to + 1) {
-		let b = try!(client.block(BlockID::Number(i)).ok_or("Error exporting incomplete chain"));
-		match format {
-			DataFormat::Binary => { out.write(&b).expect("Couldn't write to stream."); }
-			DataFormat::Hex => { out.write_fmt(format_args!("{}", b.pretty())).expect("Couldn't write to stream."); }
+	let (from_block, to_block) = (cmd.from_block, cmd.to_block);
+	let mut export = || -> Result<(), String> {
+		let from = try!(client.block_number(from_block).ok_or("From block could not be found"));
+		let to = try!(client.block_number(to_block).ok_or("To block could not be found"));
+
+		for i in from..(to + 1) {
+			let b = try!(client.block(BlockID::Number(i)).ok_or("Error exporting incomplete chain"));
+			match format {
+				DataFormat::Binary => { out.write_all(&b).expect("Couldn't write to stream."); }
+				DataFormat::Hex => { out.write_fmt(format_args!("{}\n", b.pretty())).expect("Couldn't write to stream."); }
+				DataFormat::Geth => unreachable!("checked above; qed"),
+			}
 		}
-	}
+		Ok(())
+	};
+	let result = export();
+	try!(service.shutdown());
+	try!(result);
 
 	Ok("Export completed.".into())
 }
 
+fn to_hex_quantity(value: &U256) -> String {
+	match value.is_zero() {
+		true => "0x0".into(),
+		false => format!("{:x}", value),
+	}
+}
+
+fn execute_export_state(cmd: ExportState) -> Result<String, String> {
+	// Setup panic handler
+	let panic_handler = PanicHandler::new_in_arc();
+
+	// Setup logging
+	let _logger = setup_log(&cmd.logger_config);
+
+	let service = try!(start_client(cmd.dirs, cmd.spec, cmd.pruning, cmd.tracing, cmd.fat_db, cmd.compaction, cmd.wal, cmd.cache_config, cmd.mode));
+
+	panic_handler.forward_from(&service);
+	let client = service.client();
+
+	let (accounts_to_export, file_path, at, min_balance) = (cmd.accounts, cmd.file_path, cmd.at, cmd.min_balance);
+	let export = move || -> Result<usize, String> {
+		let accounts = match accounts_to_export {
+			Some(accounts) => accounts,
+			None => try!(client.list_accounts(at, None, u64::max_value()).ok_or("Exporting all accounts requires --fat-db on.")),
+		};
+
+		let mut out: Box<io::Write> = match file_path {
+			Some(f) => Box::new(try!(fs::File::create(&f).map_err(|_| format!("Cannot write to file given: {}", f)))),
+			None => Box::new(io::stdout()),
+		};
+
+		let mut write = |s: &str| out.write_all(s.as_bytes()).map_err(|e| format!("Couldn't write to stream: {}", e));
+
+		try!(write("{ \"state\": {"));
+		let mut exported = 0;
+		for account in &accounts {
+			let balance = try!(client.balance(account, at).ok_or("Specified block is not available (state was pruned?)."));
+			if min_balance.map_or(false, |min| balance < min) {
+				continue;
+			}
+			let nonce = client.nonce(account, at).unwrap_or_else(U256::zero);
+
+			try!(write(&format!("{}\n\t\"0x{:?}\": {{\"balance\": \"{}\", \"nonce\": \"{}\"",
+				if exported == 0 { "" } else { "," },
+				account,
+				to_hex_quantity(&balance),
+				to_hex_quantity(&nonce),
+			)));
+
+			if let Some(Some(code)) = client.code(account, at) {
+				if !code.is_empty() {
+					try!(write(&format!(", \"code\": \"0x{}\"", code.to_hex())));
+				}
+			}
+
+			if let Some(keys) = client.list_storage(at, account) {
+				if !keys.is_empty() {
+					let storage = keys.iter()
+						.filter_map(|key| client.storage_at(account, key, at).map(|value| (key, value)))
+						.filter(|&(_, ref value)| !value.is_zero())
+						.map(|(key, value)| format!("\"0x{:?}\": \"0x{:?}\"", key, value))
+						.collect::<Vec<_>>();
+					try!(write(&format!(", \"storage\": {{{}}}", storage.join(", "))));
+				}
+			}
+
+			try!(write("}"));
+			exported += 1;
+		}
+		try!(write("\n}}\n"));
+
+		Ok(exported)
+	};
+	let result = export();
+	try!(service.shutdown());
+	let exported = try!(result);
+
+	Ok(format!("Exported {} accounts.", exported))
+}
+
 #[cfg(test)]
 mod test {
-	use super::DataFormat;
+	use std::cell::RefCell;
+	use std::fs;
+	use std::io::Write;
+	use devtools::RandomTempPath;
+	use rlp::{RlpStream, Stream};
+	use super::{DataFormat, import_stream};
 
 	#[test]
 	fn test_data_format_parsing() {
 		assert_eq!(DataFormat::Binary, "binary".parse().unwrap());
 		assert_eq!(DataFormat::Binary, "bin".parse().unwrap());
+		assert_eq!(DataFormat::Binary, "rlp".parse().unwrap());
 		assert_eq!(DataFormat::Hex, "hex".parse().unwrap());
+		assert_eq!(DataFormat::Geth, "geth".parse().unwrap());
+	}
+
+	#[test]
+	fn test_import_binary_stream() {
+		let blocks: Vec<Vec<u8>> = (0..3u8).map(|i| {
+			let mut block = RlpStream::new_list(2);
+			block.append(&i);
+			block.append(&vec![i; 100]);
+			block.out()
+		}).collect();
+		let temp = RandomTempPath::new();
+		let mut file = fs::File::create(temp.as_path()).unwrap();
+		for block in &blocks {
+			file.write_all(block).unwrap();
+		}
+
+		let imported = RefCell::new(Vec::new());
+		import_stream(Some(temp.as_str().to_owned()), None, &|block| { imported.borrow_mut().push(block); Ok(()) }).unwrap();
+		assert_eq!(imported.into_inner(), blocks);
 	}
 }
@@ -18,8 +18,10 @@ use std::cmp::max;
 
 const MIN_BC_CACHE_MB: u32 = 4;
 const MIN_DB_CACHE_MB: u32 = 2;
+const MIN_STATE_CACHE_MB: u32 = 4;
 const MIN_BLOCK_QUEUE_SIZE_LIMIT_MB: u32 = 16;
 const DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB: u32 = 50;
+const DEFAULT_STATE_CACHE_SIZE_MB: u32 = 25;
 const DEFAULT_TRACE_CACHE_SIZE: u32 = 20;
 
 /// Configuration for application cache sizes.
@@ -35,33 +37,39 @@ pub struct CacheConfig {
 	blockchain: u32,
 	/// Size of transaction queue cache.
 	queue: u32,
+	/// Size of the in-memory state (account) cache.
+	state: u32,
 	/// Size of traces cache.
 	traces: u32,
 }
 
 impl Default for CacheConfig {
 	fn default() -> Self {
-		CacheConfig::new(64, 8, DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB)
+		CacheConfig::new(64, 8, DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB, DEFAULT_STATE_CACHE_SIZE_MB)
 	}
 }
 
 impl CacheConfig {
 	/// Creates new cache config with cumulative size equal `total`.
+	/// Half of it goes to the database, a quarter to the state cache and
+	/// the rest is split evenly between the blockchain cache and the block queue.
 	pub fn new_with_total_cache_size(total: u32) -> Self {
 		CacheConfig {
-			db: total * 7 / 8,
+			db: total / 2,
 			blockchain: total / 8,
-			queue: DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB,
+			queue: total / 8,
+			state: total / 4,
 			traces: DEFAULT_TRACE_CACHE_SIZE,
 		}
 	}
 
 	/// Creates new cache config with gitven details.
-	pub fn new(db: u32, blockchain: u32, queue: u32) -> Self {
+	pub fn new(db: u32, blockchain: u32, queue: u32, state: u32) -> Self {
 		CacheConfig {
 			db: db,
 			blockchain: blockchain,
 			queue: queue,
+			state: state,
 			traces: DEFAULT_TRACE_CACHE_SIZE,
 		}
 	}
@@ -86,6 +94,11 @@ impl CacheConfig {
 		max(self.blockchain, MIN_BC_CACHE_MB)
 	}
 
+	/// Size of the state cache.
+	pub fn state(&self) -> u32 {
+		max(self.state, MIN_STATE_CACHE_MB)
+	}
+
 	/// Size of the traces cache.
 	pub fn traces(&self) -> u32 {
 		self.traces
@@ -99,21 +112,30 @@ mod tests {
 	#[test]
 	fn test_cache_config_constructor() {
 		let config = CacheConfig::new_with_total_cache_size(200);
-		assert_eq!(config.db, 175);
+		assert_eq!(config.db, 100);
 		assert_eq!(config.blockchain(), 25);
-		assert_eq!(config.queue(), 50);
+		assert_eq!(config.queue(), 25);
+		assert_eq!(config.state(), 50);
+	}
+
+	#[test]
+	fn test_cache_config_minimums() {
+		let config = CacheConfig::new_with_total_cache_size(16);
+		assert_eq!(config.blockchain(), 4);
+		assert_eq!(config.queue(), 16);
+		assert_eq!(config.state(), 4);
 	}
 
 	#[test]
 	fn test_cache_config_db_cache_sizes() {
 		let config = CacheConfig::new_with_total_cache_size(400);
-		assert_eq!(config.db, 350);
+		assert_eq!(config.db, 200);
 		assert_eq!(config.db_blockchain_cache_size(), 12);
-		assert_eq!(config.db_state_cache_size(), 262);
+		assert_eq!(config.db_state_cache_size(), 150);
 	}
 
 	#[test]
 	fn test_cache_config_default() {
-		assert_eq!(CacheConfig::default(), CacheConfig::new(64, 8, super::DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB));
+		assert_eq!(CacheConfig::default(), CacheConfig::new(64, 8, super::DEFAULT_BLOCK_QUEUE_SIZE_LIMIT_MB, super::DEFAULT_STATE_CACHE_SIZE_MB));
 	}
 }
@@ -26,7 +26,10 @@ usage! {
 		cmd_new: bool,
 		cmd_list: bool,
 		cmd_export: bool,
+		cmd_blocks: bool,
+		cmd_state: bool,
 		cmd_import: bool,
+		cmd_import_geth: bool,
 		cmd_signer: bool,
 		cmd_new_token: bool,
 		cmd_snapshot: bool,
@@ -34,6 +37,15 @@ usage! {
 		cmd_ui: bool,
 		cmd_tools: bool,
 		cmd_hash: bool,
+		cmd_db: bool,
+		cmd_compact: bool,
+		cmd_stats: bool,
+		cmd_kill: bool,
+		cmd_convert_pruning: bool,
+		cmd_check_journal: bool,
+		cmd_spec: bool,
+		cmd_validate: bool,
+		cmd_test: bool,
 
 		// Arguments
 		arg_pid_file: String,
@@ -68,6 +80,9 @@ usage! {
 		flag_extradata: Option<String>,
 		flag_cache: Option<u32>,
 
+		// -- Database Options
+		flag_to: Option<String>,
+
 		// -- Miscellaneous Options
 		flag_version: bool,
 		flag_no_config: bool,
@@ -78,17 +93,35 @@ usage! {
 		flag_mode_timeout: u64 = 300u64, or |c: &Config| otry!(c.parity).mode_timeout.clone(),
 		flag_mode_alarm: u64 = 3600u64, or |c: &Config| otry!(c.parity).mode_alarm.clone(),
 		flag_chain: String = "homestead", or |c: &Config| otry!(c.parity).chain.clone(),
+		flag_fork: String = "spec", or |c: &Config| otry!(c.parity).fork.clone(),
+		flag_max_reorg_depth: Option<u64> = None,
+			or |c: &Config| otry!(c.parity).max_reorg_depth.clone().map(Some),
+		flag_report_bad_blocks: Option<String> = None,
+			or |c: &Config| otry!(c.parity).report_bad_blocks.clone().map(Some),
 		flag_db_path: String = "$HOME/.parity", or |c: &Config| otry!(c.parity).db_path.clone(),
 		flag_keys_path: String = "$HOME/.parity/keys", or |c: &Config| otry!(c.parity).keys_path.clone(),
 		flag_identity: String = "", or |c: &Config| otry!(c.parity).identity.clone(),
+		flag_on_new_block: Option<String> = None,
+			or |c: &Config| otry!(c.parity).on_new_block.clone().map(Some),
+		flag_auto_update: String = "critical", or |c: &Config| otry!(c.parity).auto_update.clone(),
+		flag_release_url: Option<String> = None,
+			or |c: &Config| otry!(c.parity).release_url.clone().map(Some),
+		flag_release_signer: Option<String> = None,
+			or |c: &Config| otry!(c.parity).release_signer.clone().map(Some),
 
 		// -- Account Options
 		flag_unlock: Option<String> = None,
 			or |c: &Config| otry!(c.account).unlock.clone().map(|vec| Some(vec.join(","))),
 		flag_password: Vec<String> = Vec::new(),
 			or |c: &Config| otry!(c.account).password.clone(),
+		flag_unlock_mode: String = "permanent",
+			or |c: &Config| otry!(c.account).unlock_mode.clone(),
 		flag_keys_iterations: u32 = 10240u32,
 			or |c: &Config| otry!(c.account).keys_iterations.clone(),
+		flag_keys_kdf: String = "pbkdf2",
+			or |c: &Config| otry!(c.account).keys_kdf.clone(),
+		flag_hardware_wallets: bool = false,
+			or |c: &Config| otry!(c.account).enable_hardware.clone(),
 
 		flag_force_signer: bool = false,
 			or |c: &Config| otry!(c.signer).force.clone(),
@@ -100,6 +133,8 @@ usage! {
 			or |c: &Config| otry!(c.signer).interface.clone(),
 		flag_signer_path: String = "$HOME/.parity/signer",
 			or |c: &Config| otry!(c.signer).path.clone(),
+		flag_signer_request_timeout: u64 = 0u64,
+			or |c: &Config| otry!(c.signer).request_timeout.clone(),
 		// NOTE [todr] For security reasons don't put this to config files
 		flag_signer_no_validation: bool = false, or |_| None,
 
@@ -126,6 +161,18 @@ usage! {
 			or |c: &Config| otry!(c.network).reserved_peers.clone().map(Some),
 		flag_reserved_only: bool = false,
 			or |c: &Config| otry!(c.network).reserved_only.clone(),
+		flag_relay_txs_while_syncing: bool = false,
+			or |c: &Config| otry!(c.network).relay_txs_while_syncing.clone(),
+		flag_no_block_relay_while_syncing: bool = false,
+			or |c: &Config| otry!(c.network).relay_blocks_while_syncing.map(|r| !r).clone(),
+		flag_sync: String = "warp",
+			or |c: &Config| otry!(c.network).sync.clone(),
+		flag_max_download: Option<u32> = None,
+			or |c: &Config| otry!(c.network).max_download.clone().map(Some),
+		flag_max_upload: Option<u32> = None,
+			or |c: &Config| otry!(c.network).max_upload.clone().map(Some),
+		flag_ethstats: Option<String> = None,
+			or |c: &Config| otry!(c.network).ethstats.clone().map(Some),
 
 		// -- API and Console Options
 		// RPC
@@ -135,12 +182,22 @@ usage! {
 			or |c: &Config| otry!(c.rpc).port.clone(),
 		flag_jsonrpc_interface: String  = "local",
 			or |c: &Config| otry!(c.rpc).interface.clone(),
-		flag_jsonrpc_cors: Option<String> = None,
-			or |c: &Config| otry!(c.rpc).cors.clone().map(Some),
-		flag_jsonrpc_apis: String = "web3,eth,net,ethcore,personal,traces,rpc",
+		flag_jsonrpc_cors: String = "none",
+			or |c: &Config| otry!(c.rpc).cors.clone(),
+		flag_jsonrpc_apis: String = "web3,eth,net,ethcore,traces,rpc",
 			or |c: &Config| otry!(c.rpc).apis.clone().map(|vec| vec.join(",")),
 		flag_jsonrpc_hosts: String = "none",
 			or |c: &Config| otry!(c.rpc).hosts.clone().map(|vec| vec.join(",")),
+		flag_jsonrpc_query_budget: u64 = 10000u64,
+			or |c: &Config| otry!(c.rpc).query_budget.clone(),
+		flag_jsonrpc_slow_query: u64 = 2000u64,
+			or |c: &Config| otry!(c.rpc).slow_query.clone(),
+		flag_jsonrpc_max_batch_size: usize = 1000usize,
+			or |c: &Config| otry!(c.rpc).max_batch_size.clone(),
+		flag_jsonrpc_max_payload: usize = 5usize,
+			or |c: &Config| otry!(c.rpc).max_payload.clone(),
+		flag_jsonrpc_max_requests_per_second: usize = 0usize,
+			or |c: &Config| otry!(c.rpc).max_requests_per_second.clone(),
 
 		// IPC
 		flag_no_ipc: bool = false,
@@ -150,6 +207,24 @@ usage! {
 		flag_ipc_apis: String = "web3,eth,net,ethcore,personal,traces,rpc",
 			or |c: &Config| otry!(c.ipc).apis.clone().map(|vec| vec.join(",")),
 
+		// WS
+		flag_no_ws: bool = false,
+			or |c: &Config| otry!(c.websockets).disable.clone(),
+		flag_ws_port: u16 = 8546u16,
+			or |c: &Config| otry!(c.websockets).port.clone(),
+		flag_ws_interface: String = "local",
+			or |c: &Config| otry!(c.websockets).interface.clone(),
+		flag_ws_apis: String = "web3,eth,net,ethcore,traces,rpc",
+			or |c: &Config| otry!(c.websockets).apis.clone().map(|vec| vec.join(",")),
+		flag_ws_origins: String = "none",
+			or |c: &Config| otry!(c.websockets).origins.clone().map(|vec| vec.join(",")),
+		flag_ws_max_batch_size: usize = 1000usize,
+			or |c: &Config| otry!(c.websockets).max_batch_size.clone(),
+		flag_ws_max_payload: usize = 5usize,
+			or |c: &Config| otry!(c.websockets).max_payload.clone(),
+		flag_ws_max_requests_per_second: usize = 0usize,
+			or |c: &Config| otry!(c.websockets).max_requests_per_second.clone(),
+
 		// DAPPS
 		flag_no_dapps: bool = false,
 			or |c: &Config| otry!(c.dapps).disable.clone(),
@@ -166,6 +241,42 @@ usage! {
 		flag_dapps_pass: Option<String> = None,
 			or |c: &Config| otry!(c.dapps).pass.clone().map(Some),
 
+		// Metrics
+		flag_metrics_port: Option<u16> = None,
+			or |c: &Config| otry!(c.metrics).port.clone().map(Some),
+		flag_metrics_interface: String = "local",
+			or |c: &Config| otry!(c.metrics).interface.clone(),
+
+		// Secret Store
+		flag_no_secretstore: bool = false,
+			or |c: &Config| otry!(c.secretstore).disable.clone(),
+		flag_secretstore_secret: Option<String> = None,
+			or |c: &Config| otry!(c.secretstore).self_secret.clone().map(Some),
+		flag_secretstore_nodes: String = "",
+			or |c: &Config| otry!(c.secretstore).nodes.clone().map(|vec| vec.join(",")),
+		flag_secretstore_interface: String = "local",
+			or |c: &Config| otry!(c.secretstore).interface.clone(),
+		flag_secretstore_port: u16 = 8083u16,
+			or |c: &Config| otry!(c.secretstore).port.clone(),
+		flag_secretstore_http_interface: String = "local",
+			or |c: &Config| otry!(c.secretstore).http_interface.clone(),
+		flag_secretstore_http_port: u16 = 8082u16,
+			or |c: &Config| otry!(c.secretstore).http_port.clone(),
+		flag_secretstore_path: String = "$HOME/.parity/secretstore",
+			or |c: &Config| otry!(c.secretstore).path.clone(),
+
+		// Private Transactions
+		flag_private_tx_enabled: bool = false,
+			or |c: &Config| otry!(c.private_tx).enabled.clone(),
+		flag_private_signer: Option<String> = None,
+			or |c: &Config| otry!(c.private_tx).signer.clone().map(Some),
+		flag_private_validators: Option<String> = None,
+			or |c: &Config| otry!(c.private_tx).validators.clone().map(|vec| Some(vec.join(","))),
+		flag_private_account: Option<String> = None,
+			or |c: &Config| otry!(c.private_tx).account.clone().map(Some),
+		flag_private_sstore_url: Option<String> = None,
+			or |c: &Config| otry!(c.private_tx).sstore_url.clone().map(Some),
+
 		// -- Sealing/Mining Options
 		flag_author: Option<String> = None,
 			or |c: &Config| otry!(c.mining).author.clone().map(Some),
@@ -195,10 +306,26 @@ usage! {
 			or |c: &Config| otry!(c.mining).extra_data.clone().map(Some),
 		flag_tx_queue_size: usize = 2048usize,
 			or |c: &Config| otry!(c.mining).tx_queue_size.clone(),
+		flag_tx_queue_per_sender: Option<usize> = None,
+			or |c: &Config| otry!(c.mining).tx_queue_per_sender.clone().map(Some),
+		flag_tx_queue_mem_limit: u32 = 2u32,
+			or |c: &Config| otry!(c.mining).tx_queue_mem_limit.clone(),
+		flag_tx_queue_eviction: String = "gas_price",
+			or |c: &Config| otry!(c.mining).tx_queue_eviction.clone(),
 		flag_remove_solved: bool = false,
 			or |c: &Config| otry!(c.mining).remove_solved.clone(),
+		flag_tx_reorg_breaker: usize = 3usize,
+			or |c: &Config| otry!(c.mining).tx_reorg_breaker.clone(),
+		flag_refuse_service_transactions: bool = false,
+			or |c: &Config| otry!(c.mining).refuse_service_transactions.clone(),
 		flag_notify_work: Option<String> = None,
 			or |c: &Config| otry!(c.mining).notify_work.clone().map(|vec| Some(vec.join(","))),
+		flag_dev_period: u64 = 0u64,
+			or |c: &Config| otry!(c.mining).dev_period.clone(),
+		flag_dev_gas_limit: String = "6000000",
+			or |c: &Config| otry!(c.mining).dev_gas_limit.clone(),
+		flag_dev_seed: String = "parity-dev",
+			or |c: &Config| otry!(c.mining).dev_seed.clone(),
 
 		// -- Footprint Options
 		flag_tracing: String = "auto",
@@ -211,19 +338,44 @@ usage! {
 			or |c: &Config| otry!(c.footprint).cache_size_blocks.clone(),
 		flag_cache_size_queue: u32 = 50u32,
 			or |c: &Config| otry!(c.footprint).cache_size_queue.clone(),
+		flag_cache_size_state: u32 = 25u32,
+			or |c: &Config| otry!(c.footprint).cache_size_state.clone(),
 		flag_cache_size: Option<u32> = None,
 			or |c: &Config| otry!(c.footprint).cache_size.clone().map(Some),
 		flag_fast_and_loose: bool = false,
 			or |c: &Config| otry!(c.footprint).fast_and_loose.clone(),
-		flag_db_compaction: String = "ssd",
+		flag_db_compaction: String = "auto",
 			or |c: &Config| otry!(c.footprint).db_compaction.clone(),
+		flag_db_max_open_files: u32 = 512u32,
+			or |c: &Config| otry!(c.footprint).db_max_open_files.clone(),
+		flag_db_wal_size: Option<u32> = None,
+			or |c: &Config| otry!(c.footprint).db_wal_size.clone().map(Some),
+		flag_db_write_buffer_size: Option<u32> = None,
+			or |c: &Config| otry!(c.footprint).db_write_buffer_size.clone().map(Some),
 		flag_fat_db: String = "auto",
 			or |c: &Config| otry!(c.footprint).fat_db.clone(),
+		flag_cold_db: Option<String> = None,
+			or |c: &Config| otry!(c.footprint).cold_db.clone().map(Some),
+		flag_ancient_path: Option<String> = None,
+			or |c: &Config| otry!(c.footprint).ancient_path.clone().map(Some),
+		flag_ancient_horizon: u64 = 90000u64,
+			or |c: &Config| otry!(c.footprint).ancient_horizon.clone(),
+		flag_check_roots: bool = false,
+			or |c: &Config| otry!(c.footprint).check_roots.clone(),
+		flag_no_bodies: bool = false,
+			or |c: &Config| otry!(c.footprint).no_bodies.clone(),
 
 		// -- Import/Export Options
 		flag_from: String = "1", or |_| None,
 		flag_to: String = "latest", or |_| None,
 		flag_format: Option<String> = None, or |_| None,
+		flag_min_balance: Option<String> = None, or |_| None,
+		flag_accounts: Option<String> = None, or |_| None,
+
+		// -- Test Options
+		flag_test_kind: String = "auto", or |_| None,
+		flag_test_era: String = "homestead", or |_| None,
+		flag_test_filter: Option<String> = None, or |_| None,
 
 		// -- Snapshot Optons
 		flag_at: String = "latest", or |_| None,
@@ -240,6 +392,10 @@ usage! {
 			or |c: &Config| otry!(c.misc).logging.clone().map(Some),
 		flag_log_file: Option<String> = None,
 			or |c: &Config| otry!(c.misc).log_file.clone().map(Some),
+		flag_log_file_max_size: Option<u32> = None,
+			or |c: &Config| otry!(c.misc).log_file_max_size.clone().map(Some),
+		flag_log_format: String = "text",
+			or |c: &Config| otry!(c.misc).log_format.clone(),
 		flag_no_color: bool = false,
 			or |c: &Config| otry!(c.misc).color.map(|c| !c).clone(),
 	}
@@ -254,7 +410,11 @@ struct Config {
 	network: Option<Network>,
 	rpc: Option<Rpc>,
 	ipc: Option<Ipc>,
+	websockets: Option<Ws>,
 	dapps: Option<Dapps>,
+	metrics: Option<Metrics>,
+	secretstore: Option<SecretStore>,
+	private_tx: Option<PrivateTransactions>,
 	mining: Option<Mining>,
 	footprint: Option<Footprint>,
 	snapshots: Option<Snapshots>,
@@ -268,16 +428,26 @@ struct Operating {
 	mode_timeout: Option<u64>,
 	mode_alarm: Option<u64>,
 	chain: Option<String>,
+	fork: Option<String>,
+	max_reorg_depth: Option<u64>,
+	report_bad_blocks: Option<String>,
 	db_path: Option<String>,
 	keys_path: Option<String>,
 	identity: Option<String>,
+	on_new_block: Option<String>,
+	auto_update: Option<String>,
+	release_url: Option<String>,
+	release_signer: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Account {
 	unlock: Option<Vec<String>>,
 	password: Option<Vec<String>>,
+	unlock_mode: Option<String>,
 	keys_iterations: Option<u32>,
+	keys_kdf: Option<String>,
+	enable_hardware: Option<bool>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -287,6 +457,7 @@ struct Signer {
 	port: Option<u16>,
 	interface: Option<String>,
 	path: Option<String>,
+	request_timeout: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -302,6 +473,12 @@ struct Network {
 	node_key: Option<String>,
 	reserved_peers: Option<String>,
 	reserved_only: Option<bool>,
+	relay_txs_while_syncing: Option<bool>,
+	relay_blocks_while_syncing: Option<bool>,
+	sync: Option<String>,
+	max_download: Option<u32>,
+	max_upload: Option<u32>,
+	ethstats: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -312,6 +489,23 @@ struct Rpc {
 	cors: Option<String>,
 	apis: Option<Vec<String>>,
 	hosts: Option<Vec<String>>,
+	query_budget: Option<u64>,
+	slow_query: Option<u64>,
+	max_batch_size: Option<usize>,
+	max_payload: Option<usize>,
+	max_requests_per_second: Option<usize>,
+}
+
+#[derive(Default, Debug, PartialEq, RustcDecodable)]
+struct Ws {
+	disable: Option<bool>,
+	port: Option<u16>,
+	interface: Option<String>,
+	apis: Option<Vec<String>>,
+	origins: Option<Vec<String>>,
+	max_batch_size: Option<usize>,
+	max_payload: Option<usize>,
+	max_requests_per_second: Option<usize>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -332,6 +526,33 @@ struct Dapps {
 	pass: Option<String>,
 }
 
+#[derive(Default, Debug, PartialEq, RustcDecodable)]
+struct Metrics {
+	port: Option<u16>,
+	interface: Option<String>,
+}
+
+#[derive(Default, Debug, PartialEq, RustcDecodable)]
+struct SecretStore {
+	disable: Option<bool>,
+	self_secret: Option<String>,
+	nodes: Option<Vec<String>>,
+	interface: Option<String>,
+	port: Option<u16>,
+	http_interface: Option<String>,
+	http_port: Option<u16>,
+	path: Option<String>,
+}
+
+#[derive(Default, Debug, PartialEq, RustcDecodable)]
+struct PrivateTransactions {
+	enabled: Option<bool>,
+	signer: Option<String>,
+	validators: Option<Vec<String>>,
+	account: Option<String>,
+	sstore_url: Option<String>,
+}
+
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Mining {
 	author: Option<String>,
@@ -348,8 +569,16 @@ struct Mining {
 	gas_cap: Option<String>,
 	extra_data: Option<String>,
 	tx_queue_size: Option<usize>,
+	tx_queue_per_sender: Option<usize>,
+	tx_queue_mem_limit: Option<u32>,
+	tx_queue_eviction: Option<String>,
 	remove_solved: Option<bool>,
+	tx_reorg_breaker: Option<usize>,
+	refuse_service_transactions: Option<bool>,
 	notify_work: Option<Vec<String>>,
+	dev_period: Option<u64>,
+	dev_gas_limit: Option<String>,
+	dev_seed: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -361,8 +590,17 @@ struct Footprint {
 	cache_size_db: Option<u32>,
 	cache_size_blocks: Option<u32>,
 	cache_size_queue: Option<u32>,
+	cache_size_state: Option<u32>,
 	db_compaction: Option<String>,
+	db_max_open_files: Option<u32>,
+	db_wal_size: Option<u32>,
+	db_write_buffer_size: Option<u32>,
 	fat_db: Option<String>,
+	cold_db: Option<String>,
+	ancient_path: Option<String>,
+	ancient_horizon: Option<u64>,
+	check_roots: Option<bool>,
+	no_bodies: Option<bool>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -379,6 +617,8 @@ struct VM {
 struct Misc {
 	logging: Option<String>,
 	log_file: Option<String>,
+	log_file_max_size: Option<u32>,
+	log_format: Option<String>,
 	color: Option<bool>,
 }
 
@@ -386,7 +626,7 @@ struct Misc {
 mod tests {
 	use super::{
 		Args, ArgsError,
-		Config, Operating, Account, Signer, Network, Rpc, Ipc, Dapps, Mining, Footprint, Snapshots, VM, Misc
+		Config, Operating, Account, Signer, Network, Rpc, Ipc, Ws, Dapps, Mining, Footprint, Snapshots, VM, Misc
 	};
 	use toml;
 
@@ -420,6 +660,23 @@ mod tests {
 		assert_eq!(args.flag_chain, "xyz".to_owned());
 	}
 
+	#[test]
+	fn should_parse_rpc_cors_from_config() {
+		// given
+		let mut config = Config::default();
+		let mut rpc = Rpc::default();
+		rpc.cors = Some("none".into());
+		config.rpc = Some(rpc);
+
+		// when
+		let args = Args::parse_with_config(&["parity"], config).unwrap();
+		let default_args = Args::parse_with_config(&["parity"], Config::default()).unwrap();
+
+		// then
+		assert_eq!(args.flag_jsonrpc_cors, "none".to_owned());
+		assert_eq!(default_args.flag_jsonrpc_cors, "none".to_owned());
+	}
+
 	#[test]
 	fn should_parse_full_config() {
 		// given
@@ -437,7 +694,10 @@ mod tests {
 			cmd_new: false,
 			cmd_list: false,
 			cmd_export: false,
+			cmd_blocks: false,
+			cmd_state: false,
 			cmd_import: false,
+			cmd_import_geth: false,
 			cmd_signer: false,
 			cmd_new_token: false,
 			cmd_snapshot: false,
@@ -445,6 +705,15 @@ mod tests {
 			cmd_ui: false,
 			cmd_tools: false,
 			cmd_hash: false,
+			cmd_db: false,
+			cmd_compact: false,
+			cmd_stats: false,
+			cmd_kill: false,
+			cmd_convert_pruning: false,
+			cmd_check_journal: false,
+			cmd_spec: false,
+			cmd_validate: false,
+			cmd_test: false,
 
 			// Arguments
 			arg_pid_file: "".into(),
@@ -456,20 +725,31 @@ mod tests {
 			flag_mode_timeout: 300u64,
 			flag_mode_alarm: 3600u64,
 			flag_chain: "xyz".into(),
+			flag_fork: "spec".into(),
+			flag_max_reorg_depth: None,
+			flag_report_bad_blocks: None,
 			flag_db_path: "$HOME/.parity".into(),
 			flag_keys_path: "$HOME/.parity/keys".into(),
 			flag_identity: "".into(),
+			flag_on_new_block: None,
+			flag_auto_update: "critical".into(),
+			flag_release_url: None,
+			flag_release_signer: None,
 
 			// -- Account Options
 			flag_unlock: Some("0xdeadbeefcafe0000000000000000000000000000".into()),
 			flag_password: vec!["~/.safe/password.file".into()],
+			flag_unlock_mode: "permanent".into(),
 			flag_keys_iterations: 10240u32,
+			flag_keys_kdf: "pbkdf2".into(),
+			flag_hardware_wallets: false,
 
 			flag_force_signer: false,
 			flag_no_signer: false,
 			flag_signer_port: 8180u16,
 			flag_signer_interface: "127.0.0.1".into(),
 			flag_signer_path: "$HOME/.parity/signer".into(),
+			flag_signer_request_timeout: 0u64,
 			flag_signer_no_validation: false,
 
 			// -- Networking Options
@@ -484,21 +764,42 @@ mod tests {
 			flag_node_key: None,
 			flag_reserved_peers: Some("./path_to_file".into()),
 			flag_reserved_only: false,
+			flag_relay_txs_while_syncing: false,
+			flag_no_block_relay_while_syncing: false,
+			flag_sync: "warp".into(),
+			flag_max_download: None,
+			flag_max_upload: None,
+			flag_ethstats: None,
 
 			// -- API and Console Options
 			// RPC
 			flag_no_jsonrpc: false,
 			flag_jsonrpc_port: 8545u16,
 			flag_jsonrpc_interface: "local".into(),
-			flag_jsonrpc_cors: Some("null".into()),
-			flag_jsonrpc_apis: "web3,eth,net,personal,ethcore,traces,rpc".into(),
+			flag_jsonrpc_cors: "null".into(),
+			flag_jsonrpc_apis: "web3,eth,net,ethcore,traces,rpc".into(),
 			flag_jsonrpc_hosts: "none".into(),
+			flag_jsonrpc_query_budget: 10000u64,
+			flag_jsonrpc_slow_query: 2000u64,
+			flag_jsonrpc_max_batch_size: 1000usize,
+			flag_jsonrpc_max_payload: 5usize,
+			flag_jsonrpc_max_requests_per_second: 0usize,
 
 			// IPC
 			flag_no_ipc: false,
 			flag_ipc_path: "$HOME/.parity/jsonrpc.ipc".into(),
 			flag_ipc_apis: "web3,eth,net,personal,ethcore,traces,rpc".into(),
 
+			// WS
+			flag_no_ws: false,
+			flag_ws_port: 8546u16,
+			flag_ws_interface: "local".into(),
+			flag_ws_apis: "web3,eth,net,ethcore,traces,rpc".into(),
+			flag_ws_origins: "none".into(),
+			flag_ws_max_batch_size: 1000usize,
+			flag_ws_max_payload: 5usize,
+			flag_ws_max_requests_per_second: 0usize,
+
 			// DAPPS
 			flag_no_dapps: false,
 			flag_dapps_port: 8080u16,
@@ -507,6 +808,25 @@ mod tests {
 			flag_dapps_path: "$HOME/.parity/dapps".into(),
 			flag_dapps_user: Some("test_user".into()),
 			flag_dapps_pass: Some("test_pass".into()),
+			flag_metrics_port: Some(9100u16),
+			flag_metrics_interface: "local".into(),
+
+			// SECRETSTORE
+			flag_no_secretstore: false,
+			flag_secretstore_secret: None,
+			flag_secretstore_nodes: "".into(),
+			flag_secretstore_interface: "local".into(),
+			flag_secretstore_port: 8083u16,
+			flag_secretstore_http_interface: "local".into(),
+			flag_secretstore_http_port: 8082u16,
+			flag_secretstore_path: "$HOME/.parity/secretstore".into(),
+
+			// PRIVATE TRANSACTIONS
+			flag_private_tx_enabled: false,
+			flag_private_signer: None,
+			flag_private_validators: None,
+			flag_private_account: None,
+			flag_private_sstore_url: None,
 
 			// -- Sealing/Mining Options
 			flag_author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
@@ -523,8 +843,16 @@ mod tests {
 			flag_gas_cap: "6283184".into(),
 			flag_extra_data: Some("Parity".into()),
 			flag_tx_queue_size: 2048usize,
+			flag_tx_queue_per_sender: Some(16usize),
+			flag_tx_queue_mem_limit: 2u32,
+			flag_tx_queue_eviction: "gas_price".into(),
 			flag_remove_solved: false,
+			flag_tx_reorg_breaker: 3usize,
+			flag_refuse_service_transactions: false,
 			flag_notify_work: Some("http://localhost:3001".into()),
+			flag_dev_period: 0u64,
+			flag_dev_gas_limit: "6000000".into(),
+			flag_dev_seed: "parity-dev".into(),
 
 			// -- Footprint Options
 			flag_tracing: "auto".into(),
@@ -532,15 +860,31 @@ mod tests {
 			flag_cache_size_db: 64u32,
 			flag_cache_size_blocks: 8u32,
 			flag_cache_size_queue: 50u32,
+			flag_cache_size_state: 25u32,
 			flag_cache_size: Some(128),
 			flag_fast_and_loose: false,
-			flag_db_compaction: "ssd".into(),
+			flag_db_compaction: "auto".into(),
+			flag_db_max_open_files: 512u32,
+			flag_db_wal_size: Some(64),
+			flag_db_write_buffer_size: None,
 			flag_fat_db: "auto".into(),
+			flag_cold_db: None,
+			flag_ancient_path: None,
+			flag_ancient_horizon: 90000u64,
+			flag_check_roots: false,
+			flag_no_bodies: false,
 
 			// -- Import/Export Options
 			flag_from: "1".into(),
 			flag_to: "latest".into(),
 			flag_format: None,
+			flag_min_balance: None,
+			flag_accounts: None,
+
+			// -- Test Options
+			flag_test_kind: "auto".into(),
+			flag_test_era: "homestead".into(),
+			flag_test_filter: None,
 
 			// -- Snapshot Optons
 			flag_at: "latest".into(),
@@ -576,11 +920,16 @@ mod tests {
 			flag_extradata: None,
 			flag_cache: None,
 
+			// -- Database Options
+			flag_to: None,
+
 			// -- Miscellaneous Options
 			flag_version: false,
 			flag_config: "$HOME/.parity/config.toml".into(),
 			flag_logging: Some("own_tx=trace".into()),
 			flag_log_file: Some("/var/log/parity.log".into()),
+			flag_log_file_max_size: Some(100),
+			flag_log_format: "text".into(),
 			flag_no_color: false,
 			flag_no_config: false,
 		});
@@ -609,14 +958,24 @@ mod tests {
 				mode_timeout: Some(15u64),
 				mode_alarm: Some(10u64),
 				chain: Some("./chain.json".into()),
+				fork: None,
+				max_reorg_depth: None,
+				report_bad_blocks: None,
 				db_path: None,
 				keys_path: None,
 				identity: None,
+				on_new_block: None,
+				auto_update: None,
+				release_url: None,
+				release_signer: None,
 			}),
 			account: Some(Account {
 				unlock: Some(vec!["0x1".into(), "0x2".into(), "0x3".into()]),
 				password: Some(vec!["passwdfile path".into()]),
+				unlock_mode: None,
 				keys_iterations: None,
+				keys_kdf: None,
+				enable_hardware: None,
 			}),
 			signer: Some(Signer {
 				force: None,
@@ -624,6 +983,7 @@ mod tests {
 				port: None,
 				interface: None,
 				path: None,
+				request_timeout: None,
 			}),
 			network: Some(Network {
 				disable: Some(false),
@@ -637,6 +997,12 @@ mod tests {
 				node_key: None,
 				reserved_peers: Some("./path/to/reserved_peers".into()),
 				reserved_only: Some(true),
+				relay_txs_while_syncing: None,
+				relay_blocks_while_syncing: None,
+				sync: None,
+				max_download: Some(512),
+				max_upload: None,
+				ethstats: None,
 			}),
 			rpc: Some(Rpc {
 				disable: Some(true),
@@ -645,12 +1011,18 @@ mod tests {
 				cors: None,
 				apis: None,
 				hosts: None,
+				query_budget: None,
+				slow_query: None,
+				max_batch_size: None,
+				max_payload: None,
+				max_requests_per_second: None,
 			}),
 			ipc: Some(Ipc {
 				disable: None,
 				path: None,
 				apis: Some(vec!["rpc".into(), "eth".into()]),
 			}),
+			websockets: None,
 			dapps: Some(Dapps {
 				disable: None,
 				port: Some(8080),
@@ -660,6 +1032,9 @@ mod tests {
 				user: Some("username".into()),
 				pass: Some("password".into())
 			}),
+			metrics: None,
+			secretstore: None,
+			private_tx: None,
 			mining: Some(Mining {
 				author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
 				force_sealing: Some(true),
@@ -673,10 +1048,18 @@ mod tests {
 				gas_floor_target: None,
 				gas_cap: None,
 				tx_queue_size: Some(2048),
+				tx_queue_per_sender: None,
+				tx_queue_mem_limit: None,
+				tx_queue_eviction: None,
 				tx_gas_limit: None,
 				extra_data: None,
 				remove_solved: None,
+				tx_reorg_breaker: None,
+				refuse_service_transactions: None,
 				notify_work: None,
+				dev_period: None,
+				dev_gas_limit: None,
+				dev_seed: None,
 			}),
 			footprint: Some(Footprint {
 				tracing: Some("on".into()),
@@ -686,8 +1069,17 @@ mod tests {
 				cache_size_db: Some(128),
 				cache_size_blocks: Some(16),
 				cache_size_queue: Some(100),
+				cache_size_state: Some(25),
 				db_compaction: Some("ssd".into()),
+				db_max_open_files: None,
+				db_wal_size: None,
+				db_write_buffer_size: None,
 				fat_db: Some("off".into()),
+				cold_db: None,
+				ancient_path: None,
+				ancient_horizon: None,
+				check_roots: None,
+				no_bodies: None,
 			}),
 			snapshots: Some(Snapshots {
 				disable_periodic: Some(true),
@@ -698,6 +1090,8 @@ mod tests {
 			misc: Some(Misc {
 				logging: Some("own_tx=trace".into()),
 				log_file: Some("/var/log/parity.log".into()),
+				log_file_max_size: None,
+				log_format: None,
 				color: Some(true),
 			})
 		});
@@ -17,30 +17,41 @@
 use std::time::Duration;
 use std::io::Read;
 use std::net::SocketAddr;
-use std::path::PathBuf;
-use std::cmp::max;
+use std::path::{Path, PathBuf};
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
 use cli::{Args, ArgsError};
-use util::{Hashable, U256, Uint, Bytes, version_data, Secret, Address};
+use util::{Hashable, U256, Uint, Bytes, version_data, Secret, Address, H512};
 use util::log::Colour;
-use ethsync::{NetworkConfiguration, is_valid_node_url};
+use ethsync::{NetworkConfiguration, NatType, SyncMethod, is_valid_node_url};
 use ethcore::client::{VMType, Mode};
-use ethcore::miner::MinerOptions;
+use ethcore::miner::{MinerOptions, CircuitBreakerOptions};
+use ethcore::ethstore::KdfParams;
+use ethcore::json_tests::TestKind;
 
-use rpc::{IpcConfiguration, HttpConfiguration};
-use ethcore_rpc::NetworkSettings;
+use rpc::{IpcConfiguration, HttpConfiguration, WsConfiguration};
+use ethcore_rpc::{NetworkSettings, RequestLimits, HeavyQueryOptions};
 use cache::CacheConfig;
-use helpers::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_price, replace_home,
-geth_ipc_path, parity_ipc_path, to_bootnodes, to_addresses, to_address};
-use params::{ResealPolicy, AccountsConfig, GasPricerConfig, MinerExtras, SpecType};
+use helpers::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_eviction_strategy, to_price, replace_home,
+geth_ipc_path, parity_ipc_path, to_bootnodes, to_addresses, to_address, to_kdf};
+use params::{ResealPolicy, AccountsConfig, GasPricerConfig, MinerExtras, SpecType, ForkCheck};
 use ethcore_logger::Config as LogConfig;
 use dir::Directories;
 use dapps::Configuration as DappsConfiguration;
+use secretstore::Configuration as SecretStoreConfiguration;
+use private_tx::Configuration as PrivateTxConfiguration;
+use ethstats::Configuration as EthstatsConfiguration;
+use dev::Configuration as DevConfiguration;
+use metrics::Configuration as MetricsConfiguration;
+use updater::UpdaterConfig;
 use signer::Configuration as SignerConfiguration;
 use run::RunCmd;
-use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, DataFormat};
+use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, ExportState, DataFormat};
 use presale::ImportWallet;
 use account::{AccountCmd, NewAccount, ImportAccounts, ImportFromGethAccounts};
 use snapshot::{self, SnapshotCommand};
+use db::{self, DbCommand};
+use consensus_tests::ConsensusTestsCmd;
 
 #[derive(Debug, PartialEq)]
 pub enum Cmd {
@@ -51,7 +62,10 @@ pub enum Cmd {
 	Blockchain(BlockchainCmd),
 	SignerToken(String),
 	Snapshot(SnapshotCommand),
+	Db(DbCommand),
 	Hash(Option<String>),
+	ValidateSpec(String),
+	ConsensusTests(ConsensusTestsCmd),
 }
 
 #[derive(Debug, PartialEq)]
@@ -76,13 +90,17 @@ impl Configuration {
 		let vm_type = try!(self.vm_type());
 		let mode = try!(to_mode(&self.args.flag_mode, self.args.flag_mode_timeout, self.args.flag_mode_alarm));
 		let miner_options = try!(self.miner_options());
-		let logger_config = self.logger_config();
+		let logger_config = try!(self.logger_config());
 		let http_conf = try!(self.http_config());
 		let ipc_conf = try!(self.ipc_config());
+		let ws_conf = try!(self.ws_config());
 		let net_conf = try!(self.net_config());
+		let sync_method = try!(self.sync_method());
+		let ethstats_conf = try!(self.ethstats_config());
+		let updater_conf = try!(self.updater_config(&dirs));
 		let network_id = try!(self.network_id());
 		let cache_config = self.cache_config();
-		let spec = try!(self.chain().parse());
+		let spec = try!(self.spec());
 		let tracing = try!(self.args.flag_tracing.parse());
 		let fat_db = try!(self.args.flag_fat_db.parse());
 		let compaction = try!(self.args.flag_db_compaction.parse());
@@ -91,6 +109,9 @@ impl Configuration {
 		let geth_compatibility = self.args.flag_geth;
 		let signer_port = self.signer_port();
 		let dapps_conf = self.dapps_config();
+		let secretstore_conf = try!(self.secretstore_config());
+		let private_tx_conf = try!(self.private_tx_config());
+		let metrics_conf = self.metrics_config();
 		let signer_conf = self.signer_config();
 		let format = try!(self.format());
 
@@ -100,10 +121,25 @@ impl Configuration {
 			Cmd::SignerToken(dirs.signer)
 		} else if self.args.cmd_tools && self.args.cmd_hash {
 			Cmd::Hash(self.args.arg_file)
+		} else if self.args.cmd_spec && self.args.cmd_validate {
+			Cmd::ValidateSpec(self.args.arg_file.unwrap_or_default())
+		} else if self.args.cmd_test {
+			Cmd::ConsensusTests(ConsensusTestsCmd {
+				paths: self.args.arg_path,
+				kind: match self.args.flag_test_kind.as_str() {
+					"auto" => None,
+					"state" => Some(TestKind::State),
+					"general-state" => Some(TestKind::GeneralState),
+					"blockchain" => Some(TestKind::Blockchain),
+					other => return Err(format!("Invalid test kind: {}. Expected state, general-state, blockchain or auto.", other)),
+				},
+				era: try!(self.args.flag_test_era.parse()),
+				filter: self.args.flag_test_filter,
+			})
 		} else if self.args.cmd_account {
 			let account_cmd = if self.args.cmd_new {
 				let new_acc = NewAccount {
-					iterations: self.args.flag_keys_iterations,
+					kdf: try!(self.keys_kdf()),
 					path: dirs.keys,
 					password_file: self.args.flag_password.first().cloned(),
 				};
@@ -116,12 +152,17 @@ impl Configuration {
 					to: dirs.keys,
 				};
 				AccountCmd::Import(import_acc)
+			} else if self.args.cmd_import_geth {
+				AccountCmd::ImportFromGeth(ImportFromGethAccounts {
+					to: dirs.keys,
+					testnet: self.args.flag_testnet,
+				})
 			} else {
 				unreachable!();
 			};
 			Cmd::Account(account_cmd)
 		} else if self.args.flag_import_geth_keys {
-        	let account_cmd = AccountCmd::ImportFromGeth(
+			let account_cmd = AccountCmd::ImportFromGeth(
 				ImportFromGethAccounts {
 					to: dirs.keys,
 					testnet: self.args.flag_testnet
@@ -130,7 +171,7 @@ impl Configuration {
 			Cmd::Account(account_cmd)
 		} else if self.args.cmd_wallet {
 			let presale_cmd = ImportWallet {
-				iterations: self.args.flag_keys_iterations,
+				kdf: try!(self.keys_kdf()),
 				path: dirs.keys,
 				wallet_path: self.args.arg_path.first().unwrap().clone(),
 				password_file: self.args.flag_password.first().cloned(),
@@ -153,6 +194,30 @@ impl Configuration {
 				vm_type: vm_type,
 			};
 			Cmd::Blockchain(BlockchainCmd::Import(import_cmd))
+		} else if self.args.cmd_export && self.args.cmd_state {
+			let export_cmd = ExportState {
+				spec: spec,
+				logger_config: logger_config,
+				cache_config: cache_config,
+				dirs: dirs,
+				file_path: self.args.arg_file.clone(),
+				pruning: pruning,
+				compaction: compaction,
+				wal: wal,
+				mode: mode,
+				tracing: tracing,
+				fat_db: fat_db,
+				at: try!(to_block_id(&self.args.flag_at)),
+				min_balance: match self.args.flag_min_balance {
+					Some(ref balance) => Some(try!(to_u256(balance))),
+					None => None,
+				},
+				accounts: match self.args.flag_accounts {
+					Some(_) => Some(try!(to_addresses(&self.args.flag_accounts))),
+					None => None,
+				},
+			};
+			Cmd::Blockchain(BlockchainCmd::ExportState(export_cmd))
 		} else if self.args.cmd_export {
 			let export_cmd = ExportBlockchain {
 				spec: spec,
@@ -205,6 +270,27 @@ impl Configuration {
 				block_at: try!(to_block_id("latest")), // unimportant.
 			};
 			Cmd::Snapshot(restore_cmd)
+		} else if self.args.cmd_db {
+			let db_cmd = DbCommand {
+				cache_config: cache_config,
+				dirs: dirs,
+				spec: spec,
+				pruning: pruning,
+				compaction: compaction,
+				kind: if self.args.cmd_compact {
+					db::Kind::Compact
+				} else if self.args.cmd_stats {
+					db::Kind::Stats
+				} else if self.args.cmd_convert_pruning {
+					let to = self.args.flag_to.clone().unwrap_or_else(|| "fast".into());
+					db::Kind::ConvertPruning(try!(to.parse()))
+				} else if self.args.cmd_check_journal {
+					db::Kind::CheckJournal(self.args.arg_file.clone())
+				} else {
+					db::Kind::Kill
+				},
+			};
+			Cmd::Db(db_cmd)
 		} else {
 			let daemon = if self.args.cmd_daemon {
 				Some(self.args.arg_pid_file.clone())
@@ -216,18 +302,24 @@ impl Configuration {
 				cache_config: cache_config,
 				dirs: dirs,
 				spec: spec,
+				fork: try!(self.args.flag_fork.parse()),
+				max_reorg_depth: self.args.flag_max_reorg_depth,
+				report_bad_blocks: self.args.flag_report_bad_blocks.clone(),
 				pruning: pruning,
 				daemon: daemon,
 				logger_config: logger_config,
 				miner_options: miner_options,
 				http_conf: http_conf,
 				ipc_conf: ipc_conf,
+				ws_conf: ws_conf,
 				net_conf: net_conf,
 				network_id: network_id,
 				acc_conf: try!(self.accounts_config()),
 				gas_pricer: try!(self.gas_pricer_config()),
 				miner_extras: try!(self.miner_extras()),
 				mode: mode,
+				mode_timeout: self.args.flag_mode_timeout,
+				mode_alarm: self.args.flag_mode_alarm,
 				tracing: tracing,
 				fat_db: fat_db,
 				compaction: compaction,
@@ -235,14 +327,31 @@ impl Configuration {
 				vm_type: vm_type,
 				enable_network: enable_network,
 				geth_compatibility: geth_compatibility,
+				heavy_queries: self.heavy_queries(),
 				signer_port: signer_port,
 				net_settings: self.network_settings(),
 				dapps_conf: dapps_conf,
+				secretstore_conf: secretstore_conf,
+				private_tx_conf: private_tx_conf,
+				metrics_conf: metrics_conf,
 				signer_conf: signer_conf,
 				ui: self.args.cmd_ui,
 				name: self.args.flag_identity,
 				custom_bootnodes: self.args.flag_bootnodes.is_some(),
 				no_periodic_snapshot: self.args.flag_no_periodic_snapshot,
+				relay_txs_while_syncing: self.args.flag_relay_txs_while_syncing,
+				relay_blocks_while_syncing: !self.args.flag_no_block_relay_while_syncing,
+				sync_method: sync_method,
+				cold_db: self.args.flag_cold_db.as_ref().map(|p| replace_home(p)),
+				ancient_horizon: self.args.flag_ancient_horizon,
+				check_roots: self.args.flag_check_roots,
+				no_bodies: self.args.flag_no_bodies,
+				db_max_open_files: min(self.args.flag_db_max_open_files, i32::max_value() as u32) as i32,
+				db_wal_size: self.args.flag_db_wal_size.map(|size| size as u64 * 1024 * 1024),
+				db_write_buffer_size: self.args.flag_db_write_buffer_size.map(|size| size as u64 * 1024 * 1024),
+				on_new_block: self.args.flag_on_new_block,
+				ethstats_conf: ethstats_conf,
+				updater_conf: updater_conf,
 			};
 			Cmd::Run(run_cmd)
 		};
@@ -252,7 +361,7 @@ impl Configuration {
 
 	fn enable_network(&self, mode: &Mode) -> bool {
 		match *mode {
-			Mode::Dark(_) => false,
+			Mode::Dark(_) | Mode::Off => false,
 			_ => !self.args.flag_no_network,
 		}
 	}
@@ -291,16 +400,30 @@ impl Configuration {
 	fn cache_config(&self) -> CacheConfig {
 		match self.args.flag_cache_size.or(self.args.flag_cache) {
 			Some(size) => CacheConfig::new_with_total_cache_size(size),
-			None => CacheConfig::new(self.args.flag_cache_size_db, self.args.flag_cache_size_blocks, self.args.flag_cache_size_queue),
+			None => CacheConfig::new(self.args.flag_cache_size_db, self.args.flag_cache_size_blocks, self.args.flag_cache_size_queue, self.args.flag_cache_size_state),
 		}
 	}
 
-	fn logger_config(&self) -> LogConfig {
-		LogConfig {
+	fn logger_config(&self) -> Result<LogConfig, String> {
+		let json = match self.args.flag_log_format.as_str() {
+			"text" => false,
+			"json" => true,
+			format => return Err(format!("Invalid log format: {}", format)),
+		};
+
+		// a daemon has no terminal to log to
+		let file = match (self.args.flag_log_file.clone(), self.args.cmd_daemon) {
+			(None, true) => Some(Path::new(&self.directories().db).join("parity.log").to_string_lossy().into_owned()),
+			(file, _) => file,
+		};
+
+		Ok(LogConfig {
 			mode: self.args.flag_logging.clone(),
 			color: !self.args.flag_no_color && !cfg!(windows),
-			file: self.args.flag_log_file.clone(),
-		}
+			file: file,
+			file_max_size: self.args.flag_log_file_max_size.map(|mb| mb as u64 * 1024 * 1024),
+			json: json,
+		})
 	}
 
 	fn chain(&self) -> String {
@@ -311,6 +434,13 @@ impl Configuration {
 		}
 	}
 
+	fn spec(&self) -> Result<SpecType, String> {
+		match try!(self.chain().parse()) {
+			SpecType::Dev(_) => self.dev_config().map(SpecType::Dev),
+			spec => Ok(spec),
+		}
+	}
+
 	fn max_peers(&self) -> u32 {
 		let peers = self.args.flag_max_peers as u32;
 		max(self.min_peers(), peers)
@@ -324,12 +454,18 @@ impl Configuration {
 		self.args.flag_notify_work.as_ref().map_or_else(Vec::new, |s| s.split(',').map(|s| s.to_owned()).collect())
 	}
 
+	fn keys_kdf(&self) -> Result<KdfParams, String> {
+		to_kdf(&self.args.flag_keys_kdf, self.args.flag_keys_iterations)
+	}
+
 	fn accounts_config(&self) -> Result<AccountsConfig, String> {
 		let cfg = AccountsConfig {
-			iterations: self.args.flag_keys_iterations,
+			kdf: try!(self.keys_kdf()),
 			testnet: self.args.flag_testnet,
 			password_files: self.args.flag_password.clone(),
 			unlocked_accounts: try!(to_addresses(&self.args.flag_unlock)),
+			unlock_mode: try!(self.args.flag_unlock_mode.parse()),
+			enable_hardware_wallets: self.args.flag_hardware_wallets,
 		};
 
 		Ok(cfg)
@@ -348,10 +484,22 @@ impl Configuration {
 				None => U256::max_value(),
 			},
 			tx_queue_size: self.args.flag_tx_queue_size,
+			tx_queue_per_sender: self.args.flag_tx_queue_per_sender
+				.unwrap_or_else(|| max(16, self.args.flag_tx_queue_size / 100)),
+			tx_queue_memory_limit: match self.args.flag_tx_queue_mem_limit {
+				0 => None,
+				mb => Some(mb as usize * 1024 * 1024),
+			},
+			tx_queue_eviction: try!(to_eviction_strategy(&self.args.flag_tx_queue_eviction)),
 			pending_set: try!(to_pending_set(&self.args.flag_relay_set)),
 			reseal_min_period: Duration::from_millis(self.args.flag_reseal_min_period),
 			work_queue_size: self.args.flag_work_queue_size,
 			enable_resubmission: !self.args.flag_remove_solved,
+			reorg_circuit_breaker: CircuitBreakerOptions {
+				threshold: self.args.flag_tx_reorg_breaker,
+				..Default::default()
+			},
+			refuse_service_transactions: self.args.flag_refuse_service_transactions,
 		};
 
 		Ok(options)
@@ -364,6 +512,10 @@ impl Configuration {
 			interface: self.signer_interface(),
 			signer_path: self.directories().signer,
 			skip_origin_validation: self.args.flag_signer_no_validation,
+			request_timeout: match self.args.flag_signer_request_timeout {
+				0 => None,
+				timeout => Some(timeout),
+			},
 		}
 	}
 
@@ -379,6 +531,27 @@ impl Configuration {
 		}
 	}
 
+	fn secretstore_config(&self) -> Result<SecretStoreConfiguration, String> {
+		Ok(SecretStoreConfiguration {
+			enabled: self.secretstore_enabled(),
+			self_secret: try!(self.secretstore_self_secret()),
+			nodes: try!(self.secretstore_nodes()),
+			interface: self.secretstore_interface(),
+			port: self.args.flag_secretstore_port,
+			http_interface: self.secretstore_http_interface(),
+			http_port: self.args.flag_secretstore_http_port,
+			data_path: replace_home(&self.args.flag_secretstore_path),
+		})
+	}
+
+	fn metrics_config(&self) -> MetricsConfiguration {
+		MetricsConfiguration {
+			enabled: self.args.flag_metrics_port.is_some(),
+			interface: self.metrics_interface(),
+			port: self.args.flag_metrics_port.unwrap_or(9100),
+		}
+	}
+
 	fn gas_pricer_config(&self) -> Result<GasPricerConfig, String> {
 		if let Some(d) = self.args.flag_gasprice.as_ref() {
 			return Ok(GasPricerConfig::Fixed(try!(to_u256(d))));
@@ -408,9 +581,8 @@ impl Configuration {
 
 	fn extra_data(&self) -> Result<Bytes, String> {
 		match self.args.flag_extradata.as_ref().or(self.args.flag_extra_data.as_ref()) {
-			Some(x) if x.len() <= 32 => Ok(x.as_bytes().to_owned()),
+			Some(x) => Ok(x.as_bytes().to_owned()),
 			None => Ok(version_data()),
-			Some(_) => Err("Extra data must be at most 32 characters".into()),
 		}
 	}
 
@@ -445,9 +617,31 @@ impl Configuration {
 		Ok((listen_address, public_address))
 	}
 
+	fn nat_type(&self) -> Result<Option<NatType>, String> {
+		match self.args.flag_nat.as_str() {
+			"any" => Ok(Some(NatType::Any)),
+			"upnp" => Ok(Some(NatType::UPnP)),
+			"pmp" => Ok(Some(NatType::NatPmp)),
+			"none" => Ok(None),
+			nat if nat.starts_with("extip:") => Ok(None),
+			nat => Err(format!("Invalid NAT method: {}", nat)),
+		}
+	}
+
+	fn sync_method(&self) -> Result<SyncMethod, String> {
+		match self.args.flag_sync.as_str() {
+			"warp" => Ok(SyncMethod::Warp),
+			"fast" => Ok(SyncMethod::Fast),
+			"full" => Ok(SyncMethod::Full),
+			method => Err(format!("Invalid sync method: {}", method)),
+		}
+	}
+
 	fn net_config(&self) -> Result<NetworkConfiguration, String> {
 		let mut ret = NetworkConfiguration::new();
-		ret.nat_enabled = self.args.flag_nat == "any" || self.args.flag_nat == "upnp";
+		let nat_type = try!(self.nat_type());
+		ret.nat_enabled = nat_type.is_some();
+		ret.nat_type = nat_type.unwrap_or(NatType::Any);
 		ret.boot_nodes = try!(to_bootnodes(&self.args.flag_bootnodes));
 		let (listen, public) = try!(self.net_addresses());
 		ret.listen_address = listen.map(|l| format!("{}", l));
@@ -456,23 +650,69 @@ impl Configuration {
 		ret.discovery_enabled = !self.args.flag_no_discovery && !self.args.flag_nodiscover;
 		ret.max_peers = self.max_peers();
 		ret.min_peers = self.min_peers();
-		let mut net_path = PathBuf::from(self.directories().db);
-		net_path.push("network");
-		let net_specific_path = net_path.join(&try!(self.network_specific_path()));
+		let spec = try!(try!(self.spec()).spec());
+		let net_path = self.directories().network_path(&spec);
+		// nodes are kept per network id, so that custom networks of the chain don't share them
+		let net_specific_path = net_path.join(format!("{}", try!(self.network_id()).unwrap_or_else(|| spec.network_id())));
 		ret.config_path = Some(net_path.to_str().unwrap().to_owned());
 		ret.net_config_path = Some(net_specific_path.to_str().unwrap().to_owned());
 		ret.reserved_nodes = try!(self.init_reserved_nodes());
 		ret.allow_non_reserved = !self.args.flag_reserved_only;
+		ret.max_download = self.args.flag_max_download.map(|kbps| kbps as u64 * 1024);
+		ret.max_upload = self.args.flag_max_upload.map(|kbps| kbps as u64 * 1024);
 		Ok(ret)
 	}
 
-	fn network_specific_path(&self) -> Result<PathBuf, String> {
-		let spec_type : SpecType = try!(self.chain().parse());
-		let spec = try!(spec_type.spec());
-		let id = try!(self.network_id());
-		let mut path = PathBuf::new();
-		path.push(format!("{}", id.unwrap_or_else(|| spec.network_id())));
-		Ok(path)
+	fn ethstats_config(&self) -> Result<Option<EthstatsConfiguration>, String> {
+		match self.args.flag_ethstats {
+			Some(ref server) => server.parse().map(Some),
+			None => Ok(None),
+		}
+	}
+
+	fn dev_config(&self) -> Result<DevConfiguration, String> {
+		Ok(DevConfiguration {
+			period: match self.args.flag_dev_period {
+				0 => None,
+				period => Some(period),
+			},
+			gas_limit: try!(to_u256(&self.args.flag_dev_gas_limit)),
+			seed: self.args.flag_dev_seed.clone(),
+		})
+	}
+
+	fn private_tx_config(&self) -> Result<PrivateTxConfiguration, String> {
+		let to_account = |account: &Option<String>| match *account {
+			Some(ref account) => to_address(Some(account.clone())).map(Some),
+			None => Ok(None),
+		};
+
+		Ok(PrivateTxConfiguration {
+			enabled: self.args.flag_private_tx_enabled,
+			signer_account: try!(to_account(&self.args.flag_private_signer)),
+			validator_accounts: try!(to_addresses(&self.args.flag_private_validators)),
+			key_account: try!(to_account(&self.args.flag_private_account)),
+			sstore_url: self.args.flag_private_sstore_url.clone(),
+		})
+	}
+
+	fn updater_config(&self, dirs: &Directories) -> Result<Option<UpdaterConfig>, String> {
+		let manifest_url = match self.args.flag_release_url {
+			Some(ref url) => url.clone(),
+			None => return Ok(None),
+		};
+		let signer = match self.args.flag_release_signer {
+			Some(ref signer) => Some(try!(to_address(Some(signer.clone())))),
+			None => None,
+		};
+
+		Ok(Some(UpdaterConfig {
+			filter: try!(self.args.flag_auto_update.parse()),
+			manifest_url: manifest_url,
+			signer: signer,
+			updates_path: Path::new(&dirs.db).join("updates"),
+			current_version: try!(env!("CARGO_PKG_VERSION").parse()),
+		}))
 	}
 
 	fn network_id(&self) -> Result<Option<U256>, String> {
@@ -488,8 +728,25 @@ impl Configuration {
 	}
 
 	fn rpc_cors(&self) -> Option<Vec<String>> {
-		let cors = self.args.flag_jsonrpc_cors.clone().or(self.args.flag_rpccorsdomain.clone());
-		cors.map(|c| c.split(',').map(|s| s.to_owned()).collect())
+		let cors = self.args.flag_rpccorsdomain.clone().unwrap_or(self.args.flag_jsonrpc_cors.clone());
+		match cors.as_ref() {
+			"none" => return Some(Vec::new()),
+			"all" => return None,
+			_ => {}
+		}
+		Some(cors.split(',').map(|s| s.to_owned()).collect())
+	}
+
+	fn heavy_queries(&self) -> HeavyQueryOptions {
+		let to_duration = |ms| match ms {
+			0 => None,
+			ms => Some(Duration::from_millis(ms)),
+		};
+
+		HeavyQueryOptions {
+			budget: to_duration(self.args.flag_jsonrpc_query_budget),
+			slow_query: to_duration(self.args.flag_jsonrpc_slow_query),
+		}
 	}
 
 	fn rpc_hosts(&self) -> Option<Vec<String>> {
@@ -530,6 +787,45 @@ impl Configuration {
 			apis: try!(self.rpc_apis().parse()),
 			hosts: self.rpc_hosts(),
 			cors: self.rpc_cors(),
+			limits: request_limits(
+				self.args.flag_jsonrpc_max_batch_size,
+				self.args.flag_jsonrpc_max_payload,
+				self.args.flag_jsonrpc_max_requests_per_second,
+			),
+		};
+
+		if conf.enabled && conf.interface != "127.0.0.1" {
+			let unsafe_apis = conf.apis.unsafe_apis();
+			if !unsafe_apis.is_empty() {
+				warn!("{:?} enabled on a non-local JSON-RPC interface ({}). Anyone who can reach it can use them.", unsafe_apis, conf.interface);
+			}
+		}
+
+		Ok(conf)
+	}
+
+	fn ws_origins(&self) -> Option<Vec<String>> {
+		match self.args.flag_ws_origins.as_ref() {
+			"none" => return Some(Vec::new()),
+			"all" => return None,
+			_ => {}
+		}
+		let origins = self.args.flag_ws_origins.split(',').map(|o| o.into()).collect();
+		Some(origins)
+	}
+
+	fn ws_config(&self) -> Result<WsConfiguration, String> {
+		let conf = WsConfiguration {
+			enabled: !self.args.flag_no_ws,
+			interface: self.ws_interface(),
+			port: self.args.flag_ws_port,
+			apis: try!(self.args.flag_ws_apis.parse()),
+			origins: self.ws_origins(),
+			limits: request_limits(
+				self.args.flag_ws_max_batch_size,
+				self.args.flag_ws_max_payload,
+				self.args.flag_ws_max_requests_per_second,
+			),
 		};
 
 		Ok(conf)
@@ -582,6 +878,7 @@ impl Configuration {
 			db: db_path,
 			dapps: dapps_path,
 			signer: signer_path,
+			ancient_path: self.args.flag_ancient_path.as_ref().map(|p| replace_home(p)),
 		}
 	}
 
@@ -616,6 +913,22 @@ impl Configuration {
 		}.into()
 	}
 
+	fn ws_interface(&self) -> String {
+		match self.args.flag_ws_interface.as_str() {
+			"all" => "0.0.0.0",
+			"local" => "127.0.0.1",
+			x => x,
+		}.into()
+	}
+
+	fn metrics_interface(&self) -> String {
+		match self.args.flag_metrics_interface.as_str() {
+			"all" => "0.0.0.0",
+			"local" => "127.0.0.1",
+			x => x,
+		}.into()
+	}
+
 	fn dapps_interface(&self) -> String {
 		match self.args.flag_dapps_interface.as_str() {
 			"local" => "127.0.0.1",
@@ -623,6 +936,58 @@ impl Configuration {
 		}.into()
 	}
 
+	fn secretstore_interface(&self) -> String {
+		match self.args.flag_secretstore_interface.as_str() {
+			"all" => "0.0.0.0",
+			"local" => "127.0.0.1",
+			x => x,
+		}.into()
+	}
+
+	fn secretstore_http_interface(&self) -> String {
+		match self.args.flag_secretstore_http_interface.as_str() {
+			"all" => "0.0.0.0",
+			"local" => "127.0.0.1",
+			x => x,
+		}.into()
+	}
+
+	fn secretstore_self_secret(&self) -> Result<Option<Secret>, String> {
+		match self.args.flag_secretstore_secret {
+			Some(ref s) => Ok(Some(try!(s.parse()
+				.map_err(|e| format!("Invalid secret store secret: {}. Error: {:?}", s, e))))),
+			None => Ok(None),
+		}
+	}
+
+	fn secretstore_nodes(&self) -> Result<BTreeMap<H512, (String, u16)>, String> {
+		let mut nodes = BTreeMap::new();
+		for node in self.args.flag_secretstore_nodes.split(',').filter(|n| n != &"") {
+			let public_and_addr: Vec<_> = node.split('@').collect();
+			if public_and_addr.len() != 2 {
+				return Err(format!("Invalid secret store node: {}", node));
+			}
+
+			let ip_and_port: Vec<_> = public_and_addr[1].split(':').collect();
+			if ip_and_port.len() != 2 {
+				return Err(format!("Invalid secret store node: {}", node));
+			}
+
+			let public = try!(public_and_addr[0].parse()
+				.map_err(|e| format!("Invalid public key in secret store node: {}. Error: {:?}", public_and_addr[0], e)));
+			let port = try!(ip_and_port[1].parse()
+				.map_err(|e| format!("Invalid port in secret store node: {}. Error: {:?}", ip_and_port[1], e)));
+
+			nodes.insert(public, (ip_and_port[0].into(), port));
+		}
+
+		Ok(nodes)
+	}
+
+	fn secretstore_enabled(&self) -> bool {
+		!self.args.flag_no_secretstore && cfg!(feature = "secretstore")
+	}
+
 	fn dapps_enabled(&self) -> bool {
 		!self.args.flag_dapps_off && !self.args.flag_no_dapps && cfg!(feature = "dapps")
 	}
@@ -640,20 +1005,38 @@ impl Configuration {
 	}
 }
 
+fn request_limits(max_batch_size: usize, max_payload_mb: usize, max_requests_per_second: usize) -> RequestLimits {
+	RequestLimits {
+		max_batch_size: max_batch_size,
+		max_payload: max_payload_mb * 1024 * 1024,
+		max_requests_per_second: match max_requests_per_second {
+			0 => None,
+			limit => Some(limit),
+		},
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use cli::Args;
-	use ethcore_rpc::NetworkSettings;
+	use ethcore_rpc::{NetworkSettings, HeavyQueryOptions};
 	use ethcore::client::{VMType, BlockID};
+	use ethcore::ethstore::KdfParams;
 	use helpers::{replace_home, default_network_config};
 	use run::RunCmd;
 	use signer::Configuration as SignerConfiguration;
-	use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, DataFormat};
+	use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, ExportState, DataFormat};
+	use util::{U256, Address};
+	use util::journaldb::Algorithm;
+	use db::{self, DbCommand};
+	use consensus_tests::ConsensusTestsCmd;
+	use ethcore::json_tests::{ChainEra, TestKind};
 	use presale::ImportWallet;
-	use account::{AccountCmd, NewAccount, ImportAccounts};
+	use account::{AccountCmd, NewAccount, ImportAccounts, ImportFromGethAccounts};
 	use devtools::{RandomTempPath};
 	use std::io::Write;
+	use std::time::Duration;
 	use std::fs::{File, create_dir};
 
 	#[derive(Debug, PartialEq)]
@@ -677,7 +1060,7 @@ mod tests {
 		let args = vec!["parity", "account", "new"];
 		let conf = parse(&args);
 		assert_eq!(conf.into_command().unwrap(), Cmd::Account(AccountCmd::New(NewAccount {
-			iterations: 10240,
+			kdf: KdfParams::Pbkdf2 { iterations: 10240 },
 			path: replace_home("$HOME/.parity/keys"),
 			password_file: None,
 		})));
@@ -702,12 +1085,22 @@ mod tests {
 		})));
 	}
 
+	#[test]
+	fn test_command_account_import_geth() {
+		let args = vec!["parity", "account", "import-geth", "--testnet"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Account(AccountCmd::ImportFromGeth(ImportFromGethAccounts {
+			to: replace_home("$HOME/.parity/testnet_keys"),
+			testnet: true,
+		})));
+	}
+
 	#[test]
 	fn test_command_wallet_import() {
 		let args = vec!["parity", "wallet", "import", "my_wallet.json", "--password", "pwd"];
 		let conf = parse(&args);
 		assert_eq!(conf.into_command().unwrap(), Cmd::ImportPresaleWallet(ImportWallet {
-			iterations: 10240,
+			kdf: KdfParams::Pbkdf2 { iterations: 10240 },
 			path: replace_home("$HOME/.parity/keys"),
 			wallet_path: "my_wallet.json".into(),
 			password_file: Some("pwd".into()),
@@ -779,6 +1172,119 @@ mod tests {
 		})));
 	}
 
+	#[test]
+	fn test_command_blockchain_export_blocks() {
+		let args = vec!["parity", "export", "blocks", "--from", "5", "--to", "10", "--format", "rlp", "blockchain.rlp"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Blockchain(BlockchainCmd::Export(ExportBlockchain {
+			spec: Default::default(),
+			logger_config: Default::default(),
+			cache_config: Default::default(),
+			dirs: Default::default(),
+			file_path: Some("blockchain.rlp".into()),
+			pruning: Default::default(),
+			format: Some(DataFormat::Binary),
+			compaction: Default::default(),
+			wal: true,
+			mode: Default::default(),
+			tracing: Default::default(),
+			fat_db: Default::default(),
+			from_block: BlockID::Number(5),
+			to_block: BlockID::Number(10),
+		})));
+	}
+
+	#[test]
+	fn test_command_export_state() {
+		let args = vec!["parity", "export", "state", "--at", "100", "--min-balance", "1000", "--accounts", "0x0000000000000000000000000000000000000001", "state.json"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Blockchain(BlockchainCmd::ExportState(ExportState {
+			spec: Default::default(),
+			logger_config: Default::default(),
+			cache_config: Default::default(),
+			dirs: Default::default(),
+			file_path: Some("state.json".into()),
+			pruning: Default::default(),
+			compaction: Default::default(),
+			wal: true,
+			mode: Default::default(),
+			tracing: Default::default(),
+			fat_db: Default::default(),
+			at: BlockID::Number(100),
+			min_balance: Some(U256::from(1000)),
+			accounts: Some(vec![Address::from(1)]),
+		})));
+	}
+
+	#[test]
+	fn test_command_db_stats() {
+		let args = vec!["parity", "db", "stats"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Db(DbCommand {
+			cache_config: Default::default(),
+			dirs: Default::default(),
+			spec: Default::default(),
+			pruning: Default::default(),
+			compaction: Default::default(),
+			kind: db::Kind::Stats,
+		}));
+	}
+
+	#[test]
+	fn test_command_db_convert_pruning() {
+		let args = vec!["parity", "db", "convert-pruning", "--to", "fast"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Db(DbCommand {
+			cache_config: Default::default(),
+			dirs: Default::default(),
+			spec: Default::default(),
+			pruning: Default::default(),
+			compaction: Default::default(),
+			kind: db::Kind::ConvertPruning(Algorithm::OverlayRecent),
+		}));
+	}
+
+	#[test]
+	fn test_command_db_check_journal() {
+		let args = vec!["parity", "db", "check-journal", "snapshot.bin"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Db(DbCommand {
+			cache_config: Default::default(),
+			dirs: Default::default(),
+			spec: Default::default(),
+			pruning: Default::default(),
+			compaction: Default::default(),
+			kind: db::Kind::CheckJournal(Some("snapshot.bin".into())),
+		}));
+	}
+
+	#[test]
+	fn test_command_spec_validate() {
+		let args = vec!["parity", "spec", "validate", "chain.json"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::ValidateSpec("chain.json".into()));
+	}
+
+	#[test]
+	fn test_command_consensus_tests() {
+		let args = vec!["parity", "test", "tests/StateTests", "extra.json", "--test-era", "frontier", "--test-filter", "Call"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::ConsensusTests(ConsensusTestsCmd {
+			paths: vec!["tests/StateTests".into(), "extra.json".into()],
+			kind: None,
+			era: ChainEra::Frontier,
+			filter: Some("Call".into()),
+		}));
+
+		let args = vec!["parity", "test", "add11.json", "--test-kind", "general-state"];
+		match parse(&args).into_command().unwrap() {
+			Cmd::ConsensusTests(cmd) => assert_eq!(cmd.kind, Some(TestKind::GeneralState)),
+			_ => panic!("expected the test command"),
+		}
+		assert!(parse(&["parity", "test", "tests", "--test-kind", "vm"]).into_command().is_err());
+		assert!(parse(&["parity", "test", "tests", "--test-era", "metropolis"]).into_command().is_err());
+	}
+
 	#[test]
 	fn test_command_signer_new_token() {
 		let args = vec!["parity", "signer", "new-token"];
@@ -795,33 +1301,62 @@ mod tests {
 			cache_config: Default::default(),
 			dirs: Default::default(),
 			spec: Default::default(),
+			fork: ForkCheck::Spec,
+			max_reorg_depth: None,
+			report_bad_blocks: None,
 			pruning: Default::default(),
 			daemon: None,
 			logger_config: Default::default(),
 			miner_options: Default::default(),
 			http_conf: Default::default(),
 			ipc_conf: Default::default(),
+			ws_conf: Default::default(),
 			net_conf: default_network_config(),
 			network_id: None,
 			acc_conf: Default::default(),
 			gas_pricer: Default::default(),
 			miner_extras: Default::default(),
 			mode: Default::default(),
+			mode_timeout: 300,
+			mode_alarm: 3600,
 			tracing: Default::default(),
 			compaction: Default::default(),
 			wal: true,
 			vm_type: Default::default(),
 			enable_network: true,
 			geth_compatibility: false,
+			heavy_queries: HeavyQueryOptions {
+				budget: Some(Duration::from_millis(10000)),
+				slow_query: Some(Duration::from_millis(2000)),
+			},
 			signer_port: Some(8180),
 			net_settings: Default::default(),
 			dapps_conf: Default::default(),
+			secretstore_conf: SecretStoreConfiguration {
+				enabled: cfg!(feature = "secretstore"),
+				..Default::default()
+			},
+			metrics_conf: Default::default(),
 			signer_conf: Default::default(),
 			ui: false,
 			name: "".into(),
 			custom_bootnodes: false,
 			fat_db: Default::default(),
 			no_periodic_snapshot: false,
+			relay_txs_while_syncing: false,
+			relay_blocks_while_syncing: true,
+			sync_method: SyncMethod::Warp,
+			cold_db: None,
+			ancient_horizon: 90000,
+			check_roots: false,
+			no_bodies: false,
+			db_max_open_files: 512,
+			db_wal_size: None,
+			db_write_buffer_size: None,
+			on_new_block: None,
+			ethstats_conf: None,
+			updater_conf: None,
+			private_tx_conf: Default::default(),
 		}));
 	}
 
@@ -891,6 +1426,25 @@ mod tests {
 		assert_eq!(conf3.rpc_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
 	}
 
+	#[test]
+	fn should_parse_rpc_cors() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--jsonrpc-cors", "none"]);
+		let conf2 = parse(&["parity", "--jsonrpc-cors", "all"]);
+		let conf3 = parse(&["parity", "--jsonrpc-cors", "http://ethcore.io,null"]);
+		let conf4 = parse(&["parity", "--jsonrpc-cors", "null"]);
+
+		// then
+		assert_eq!(conf0.rpc_cors(), Some(Vec::new()));
+		assert_eq!(conf1.rpc_cors(), Some(Vec::new()));
+		assert_eq!(conf2.rpc_cors(), None);
+		assert_eq!(conf3.rpc_cors(), Some(vec!["http://ethcore.io".into(), "null".into()]));
+		assert_eq!(conf4.rpc_cors(), Some(vec!["null".into()]));
+	}
+
 	#[test]
 	fn should_parse_dapps_hosts() {
 		// given
@@ -908,6 +1462,22 @@ mod tests {
 		assert_eq!(conf3.dapps_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
 	}
 
+	#[test]
+	fn should_parse_secretstore_nodes() {
+		// given
+		let public = "843645726384530ffb0c52f175278143b5a93959af7864460f5a4fec9afd1450cfb8aef63dec90657f43f55b13e0a73c7524d4e9a13c051b4e5f1e53f39ecd91";
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--secretstore-nodes", &format!("{}@127.0.0.1:8083", public)]);
+		let conf2 = parse(&["parity", "--secretstore-nodes", "127.0.0.1:8083"]);
+
+		// then
+		assert_eq!(conf0.secretstore_nodes(), Ok(BTreeMap::new()));
+		assert_eq!(conf1.secretstore_nodes(), Ok(vec![(public.parse().unwrap(), ("127.0.0.1".into(), 8083))].into_iter().collect()));
+		assert!(conf2.secretstore_nodes().is_err());
+	}
+
 	#[test]
 	fn should_disable_signer_in_geth_compat() {
 		// given
@@ -941,6 +1511,7 @@ mod tests {
 		let conf1 = parse(&["parity", "--signer-path", "signer", "--signer-no-validation"]);
 		let conf2 = parse(&["parity", "--signer-path", "signer", "--signer-port", "3123"]);
 		let conf3 = parse(&["parity", "--signer-path", "signer", "--signer-interface", "test"]);
+		let conf4 = parse(&["parity", "--signer-path", "signer", "--signer-request-timeout", "300"]);
 
 		// then
 		assert_eq!(conf0.signer_config(), SignerConfiguration {
@@ -949,6 +1520,7 @@ mod tests {
 			interface: "127.0.0.1".into(),
 			signer_path: "signer".into(),
 			skip_origin_validation: false,
+			request_timeout: None,
 		});
 		assert_eq!(conf1.signer_config(), SignerConfiguration {
 			enabled: true,
@@ -956,6 +1528,7 @@ mod tests {
 			interface: "127.0.0.1".into(),
 			signer_path: "signer".into(),
 			skip_origin_validation: true,
+			request_timeout: None,
 		});
 		assert_eq!(conf2.signer_config(), SignerConfiguration {
 			enabled: true,
@@ -963,6 +1536,7 @@ mod tests {
 			interface: "127.0.0.1".into(),
 			signer_path: "signer".into(),
 			skip_origin_validation: false,
+			request_timeout: None,
 		});
 		assert_eq!(conf3.signer_config(), SignerConfiguration {
 			enabled: true,
@@ -970,6 +1544,15 @@ mod tests {
 			interface: "test".into(),
 			signer_path: "signer".into(),
 			skip_origin_validation: false,
+			request_timeout: None,
+		});
+		assert_eq!(conf4.signer_config(), SignerConfiguration {
+			enabled: true,
+			port: 8180,
+			interface: "127.0.0.1".into(),
+			signer_path: "signer".into(),
+			skip_origin_validation: false,
+			request_timeout: Some(300),
 		});
 	}
 
@@ -983,5 +1566,158 @@ mod tests {
 		let conf = Configuration::parse(&args).unwrap();
 		assert!(conf.init_reserved_nodes().is_ok());
 	}
-}
 
+	#[test]
+	fn should_parse_nat_method() {
+		let conf = parse(&["parity", "--nat", "pmp"]);
+		let net = conf.net_config().unwrap();
+		assert!(net.nat_enabled);
+		assert_eq!(net.nat_type, NatType::NatPmp);
+
+		let conf = parse(&["parity", "--nat", "upnp"]);
+		assert_eq!(conf.net_config().unwrap().nat_type, NatType::UPnP);
+
+		let conf = parse(&["parity", "--nat", "none"]);
+		assert!(!conf.net_config().unwrap().nat_enabled);
+
+		let conf = parse(&["parity", "--nat", "extip:1.2.3.4"]);
+		let net = conf.net_config().unwrap();
+		assert!(!net.nat_enabled);
+		assert_eq!(net.public_address, Some("1.2.3.4:30303".into()));
+
+		let conf = parse(&["parity", "--nat", "stun"]);
+		assert!(conf.net_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_sync_method() {
+		assert_eq!(parse(&["parity"]).sync_method(), Ok(SyncMethod::Warp));
+		assert_eq!(parse(&["parity", "--sync", "fast"]).sync_method(), Ok(SyncMethod::Fast));
+		assert_eq!(parse(&["parity", "--sync", "full"]).sync_method(), Ok(SyncMethod::Full));
+		assert!(parse(&["parity", "--sync", "light"]).sync_method().is_err());
+	}
+
+	#[test]
+	fn should_parse_db_tuning() {
+		match parse(&["parity", "--db-max-open-files", "4294967295", "--db-wal-size", "128"]).into_command().unwrap() {
+			Cmd::Run(cmd) => {
+				assert_eq!(cmd.db_max_open_files, i32::max_value());
+				assert_eq!(cmd.db_wal_size, Some(128 * 1024 * 1024));
+			},
+			_ => panic!("Should be Cmd::Run"),
+		}
+	}
+
+	#[test]
+	fn should_parse_bandwidth_limits() {
+		let net = parse(&["parity"]).net_config().unwrap();
+		assert_eq!(net.max_download, None);
+		assert_eq!(net.max_upload, None);
+
+		let net = parse(&["parity", "--max-download", "512", "--max-upload", "64"]).net_config().unwrap();
+		assert_eq!(net.max_download, Some(512 * 1024));
+		assert_eq!(net.max_upload, Some(64 * 1024));
+	}
+
+	#[test]
+	fn should_keep_nodes_per_network_id() {
+		let net = parse(&["parity", "--db-path", "/tmp/parity-net"]).net_config().unwrap();
+		assert_eq!(net.config_path, Some("/tmp/parity-net/chains/frontier_homestead-d4e56740f876aef8/network".into()));
+		assert_eq!(net.net_config_path, Some("/tmp/parity-net/chains/frontier_homestead-d4e56740f876aef8/network/1".into()));
+
+		let net = parse(&["parity", "--db-path", "/tmp/parity-net", "--network-id", "0x2a"]).net_config().unwrap();
+		assert_eq!(net.config_path, Some("/tmp/parity-net/chains/frontier_homestead-d4e56740f876aef8/network".into()));
+		assert_eq!(net.net_config_path, Some("/tmp/parity-net/chains/frontier_homestead-d4e56740f876aef8/network/42".into()));
+	}
+
+	#[test]
+	fn should_parse_log_file_options() {
+		let conf = parse(&["parity", "--log-file", "parity.log", "--log-file-max-size", "10", "--log-format", "json"]).logger_config().unwrap();
+		assert_eq!(conf.file, Some("parity.log".into()));
+		assert_eq!(conf.file_max_size, Some(10 * 1024 * 1024));
+		assert!(conf.json);
+
+		assert!(!parse(&["parity"]).logger_config().unwrap().json);
+		assert!(parse(&["parity", "--log-format", "xml"]).logger_config().is_err());
+	}
+
+	#[test]
+	fn should_log_to_file_in_daemon_mode() {
+		let conf = parse(&["parity", "daemon", "parity.pid", "--db-path", "/tmp/parity-daemon"]).logger_config().unwrap();
+		assert_eq!(conf.file, Some("/tmp/parity-daemon/parity.log".into()));
+
+		let conf = parse(&["parity", "daemon", "parity.pid", "--log-file", "node.log"]).logger_config().unwrap();
+		assert_eq!(conf.file, Some("node.log".into()));
+	}
+
+	#[test]
+	fn should_enable_metrics_with_port() {
+		assert!(!parse(&["parity"]).metrics_config().enabled);
+
+		let conf = parse(&["parity", "--metrics-port", "9200", "--metrics-interface", "all"]).metrics_config();
+		assert_eq!(conf, MetricsConfiguration {
+			enabled: true,
+			interface: "0.0.0.0".into(),
+			port: 9200,
+		});
+	}
+
+	#[test]
+	fn should_parse_updater_config() {
+		use updater::UpdateFilter;
+
+		let dirs = Directories::default();
+		assert_eq!(parse(&["parity"]).updater_config(&dirs), Ok(None));
+
+		let conf = parse(&["parity", "--release-url", "https://example.com/release.json", "--auto-update", "all",
+			"--release-signer", "0xdeadbeefcafe0000000000000000000000000001"]).updater_config(&dirs).unwrap().unwrap();
+		assert_eq!(conf.filter, UpdateFilter::All);
+		assert_eq!(conf.manifest_url, "https://example.com/release.json");
+		assert_eq!(conf.signer, Some("deadbeefcafe0000000000000000000000000001".parse().unwrap()));
+		assert_eq!(conf.updates_path, Path::new(&dirs.db).join("updates"));
+
+		assert!(parse(&["parity", "--release-url", "https://example.com/release.json", "--auto-update", "some"])
+			.updater_config(&dirs).is_err());
+	}
+
+	#[test]
+	fn should_parse_private_tx_config() {
+		assert_eq!(parse(&["parity"]).private_tx_config(), Ok(Default::default()));
+
+		let conf = parse(&["parity", "--private-tx-enabled", "--private-signer", "0xdeadbeefcafe0000000000000000000000000001",
+			"--private-validators", "0xdeadbeefcafe0000000000000000000000000002,0xdeadbeefcafe0000000000000000000000000003",
+			"--private-sstore-url", "http://127.0.0.1:8082"]).private_tx_config().unwrap();
+		assert!(conf.enabled);
+		assert_eq!(conf.signer_account, Some("deadbeefcafe0000000000000000000000000001".parse().unwrap()));
+		assert_eq!(conf.validator_accounts, vec![
+			"deadbeefcafe0000000000000000000000000002".parse().unwrap(),
+			"deadbeefcafe0000000000000000000000000003".parse().unwrap(),
+		]);
+		assert_eq!(conf.key_account, None);
+		assert_eq!(conf.sstore_url, Some("http://127.0.0.1:8082".into()));
+
+		assert!(parse(&["parity", "--private-signer", "0xnope"]).private_tx_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_dev_config() {
+		assert_eq!(parse(&["parity"]).dev_config(), Ok(Default::default()));
+
+		let conf = parse(&["parity", "--chain", "dev", "--dev-period", "5", "--dev-gas-limit", "0x7a1200", "--dev-seed", "ci"]).dev_config().unwrap();
+		assert_eq!(conf.period, Some(5));
+		assert_eq!(conf.gas_limit, 8_000_000.into());
+		assert_eq!(conf.seed, "ci".to_owned());
+
+		assert!(parse(&["parity", "--dev-gas-limit", "lots"]).dev_config().is_err());
+	}
+
+	#[test]
+	fn should_apply_dev_config_to_dev_chain() {
+		let conf = parse(&["parity", "--chain", "dev", "--dev-gas-limit", "0x7a1200"]);
+		let dev_conf = conf.dev_config().unwrap();
+
+		assert_eq!(conf.spec(), Ok(SpecType::Dev(dev_conf)));
+		assert_eq!(conf.spec().unwrap().spec().unwrap().genesis_header().gas_limit(), &8_000_000.into());
+		assert_eq!(parse(&["parity", "--dev-gas-limit", "0x7a1200"]).spec(), Ok(SpecType::Mainnet));
+	}
+}
@@ -0,0 +1,100 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `parity test`: running the consensus JSON tests against this build.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use ethcore::json_tests::{self, ChainEra, TestKind};
+
+#[derive(Debug, PartialEq)]
+pub struct ConsensusTestsCmd {
+	/// Test files or directories searched recursively for `.json` files.
+	pub paths: Vec<String>,
+	/// Format of the test files, guessed from their paths if `None`.
+	pub kind: Option<TestKind>,
+	/// Chain rules the tests run with.
+	pub era: ChainEra,
+	/// Only run tests with names containing this string.
+	pub filter: Option<String>,
+}
+
+pub fn execute(cmd: ConsensusTestsCmd) -> Result<String, String> {
+	let mut files = Vec::new();
+	for path in &cmd.paths {
+		try!(collect_files(Path::new(path), &mut files));
+	}
+	files.sort();
+
+	let filter = |name: &str| cmd.filter.as_ref().map_or(true, |f| name.contains(f.as_str()));
+	let mut passed = 0;
+	let mut failed = Vec::new();
+	let mut invalid = Vec::new();
+
+	for file in files {
+		let kind = match cmd.kind.or_else(|| TestKind::from_path(&file)) {
+			Some(kind) => kind,
+			None => {
+				invalid.push(format!("{}: unknown test format, use --test-kind", file.display()));
+				continue;
+			},
+		};
+
+		match json_tests::run_file(&file, kind, cmd.era, &filter) {
+			Ok(outcomes) => for outcome in outcomes {
+				match outcome.failure {
+					None => passed += 1,
+					Some(failure) => {
+						println!("FAIL {} {}\n{}", file.display(), outcome.name, failure);
+						failed.push(format!("{} {}", file.display(), outcome.name));
+					},
+				}
+			},
+			Err(e) => invalid.push(format!("{}: {}", file.display(), e)),
+		}
+	}
+
+	let mut report = format!("{} passed, {} failed, {} files skipped.", passed, failed.len(), invalid.len());
+	for name in &failed {
+		report.push_str(&format!("\nFailed: {}", name));
+	}
+	for file in &invalid {
+		report.push_str(&format!("\nSkipped: {}", file));
+	}
+
+	match failed.is_empty() && invalid.is_empty() {
+		true => Ok(report),
+		false => Err(report),
+	}
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+	let metadata = try!(fs::metadata(path).map_err(|e| format!("Error reading {}: {}", path.display(), e)));
+	if !metadata.is_dir() {
+		files.push(path.to_path_buf());
+		return Ok(());
+	}
+
+	let entries = try!(fs::read_dir(path).map_err(|e| format!("Error reading {}: {}", path.display(), e)));
+	for entry in entries {
+		let entry = try!(entry.map_err(|e| format!("Error reading {}: {}", path.display(), e)));
+		let path = entry.path();
+		if path.is_dir() || path.extension().map_or(false, |ext| ext == "json") {
+			try!(collect_files(&path, files));
+		}
+	}
+	Ok(())
+}
@@ -162,8 +162,9 @@ mod server {
 
 		fn call(&self, address: Address, data: Bytes) -> Result<Bytes, String> {
 			let from = Address::default();
+			let nonce = try!(self.client.nonce(&from, BlockID::Latest).ok_or_else(|| "State is not available in relay mode.".to_owned()));
 			let transaction = Transaction {
-				nonce: self.client.latest_nonce(&from),
+				nonce: nonce,
 				action: Action::Call(address),
 				gas: U256::from(50_000_000),
 				gas_price: U256::default(),
@@ -0,0 +1,411 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Database maintenance commands.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use util::{journaldb, snappy};
+use util::journaldb::Algorithm;
+use util::kvdb::{Database, DatabaseConfig};
+use util::migration::Batch;
+use ethcore::client::DatabaseCompactionProfile;
+use ethcore::db;
+use ethcore::snapshot::{self, StateRebuilder};
+use ethcore::snapshot::io::{SnapshotReader, PackedReader};
+use cache::CacheConfig;
+use params::{SpecType, Pruning};
+use helpers::execute_upgrades;
+use migration::default_migration_settings;
+use dir::{Directories, DatabaseDirectories};
+use user_defaults::UserDefaults;
+
+/// Kinds of database commands.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Kind {
+	/// Rewrite the database, dropping obsolete data.
+	Compact,
+	/// Print per-column statistics.
+	Stats,
+	/// Remove the chain data of the selected chain.
+	Kill,
+	/// Rebuild an archive database with the given pruning algorithm.
+	ConvertPruning(Algorithm),
+	/// Verify the state journal, repairing it from the given snapshot file if any.
+	CheckJournal(Option<String>),
+}
+
+/// Command for database maintenance.
+#[derive(Debug, PartialEq)]
+pub struct DbCommand {
+	pub cache_config: CacheConfig,
+	pub dirs: Directories,
+	pub spec: SpecType,
+	pub pruning: Pruning,
+	pub compaction: DatabaseCompactionProfile,
+	pub kind: Kind,
+}
+
+/// Names of the database columns, the default one first.
+const COLUMNS: &'static [(Option<u32>, &'static str)] = &[
+	(None, "default"),
+	(db::COL_STATE, "state"),
+	(db::COL_HEADERS, "headers"),
+	(db::COL_BODIES, "bodies"),
+	(db::COL_EXTRA, "extras"),
+	(db::COL_TRACE, "traces"),
+	(db::COL_ACCOUNT_BLOOM, "account bloom"),
+	(db::COL_BAD_BLOCKS, "bad blocks"),
+];
+
+/// Sum of sizes of all files in the directory.
+fn dir_size(path: &Path) -> u64 {
+	fs::read_dir(path).map(|entries| entries
+		.filter_map(Result::ok)
+		.filter_map(|entry| entry.metadata().ok())
+		.filter(|metadata| metadata.is_file())
+		.map(|metadata| metadata.len())
+		.sum()
+	).unwrap_or(0)
+}
+
+fn open_database(config: &DatabaseConfig, path: &Path) -> Result<Database, String> {
+	let path_str = try!(path.to_str().ok_or_else(|| format!("Invalid database path: {}", path.display())));
+	Database::open(config, path_str).map_err(|e| format!("Cannot open database at {}: {}", path_str, e))
+}
+
+impl DbCommand {
+	/// Database directories of the selected chain, its user defaults and the pruning algorithm in use.
+	fn chain_database(&self) -> Result<(DatabaseDirectories, UserDefaults, Algorithm), String> {
+		let spec = try!(self.spec.spec());
+		let db_dirs = try!(self.dirs.database(&spec));
+		let user_defaults = try!(UserDefaults::load(&db_dirs.user_defaults_path()));
+		let algorithm = try!(self.pruning.to_algorithm(&user_defaults, &db_dirs.existing_pruning()));
+		Ok((db_dirs, user_defaults, algorithm))
+	}
+
+	/// Path and configuration of the selected chain's client database, upgraded to the current version.
+	fn client_database(&self) -> Result<(PathBuf, DatabaseConfig), String> {
+		let (db_dirs, _, algorithm) = try!(self.chain_database());
+
+		let client_path = db_dirs.client_path(algorithm);
+		if fs::metadata(&client_path).is_err() {
+			return Err(format!("No database found at {}", client_path.display()));
+		}
+
+		let mut db_config = DatabaseConfig::with_columns(db::NUM_COLUMNS);
+		db_config.compaction = self.compaction.compaction_profile(&client_path);
+
+		try!(execute_upgrades(&db_dirs, algorithm, db_config.compaction));
+		Ok((client_path, db_config))
+	}
+
+	/// Path of the ancient blocks database, if one is configured and exists.
+	fn ancient_database(&self) -> Option<PathBuf> {
+		self.dirs.ancient_path.as_ref()
+			.map(PathBuf::from)
+			.and_then(|path| if fs::metadata(&path).is_ok() { Some(path) } else { None })
+	}
+
+	/// Compact the client database and the ancient blocks database, if any.
+	fn compact(&self) -> Result<String, String> {
+		let (client_path, db_config) = try!(self.client_database());
+		let mut out = try!(compact_database(&client_path, &db_config));
+		if let Some(ancient_path) = self.ancient_database() {
+			info!("Compacting ancient blocks database");
+			out.push_str(&format!("\nAncient blocks: {}", try!(compact_database(&ancient_path, &db_config))));
+		}
+		Ok(out)
+	}
+
+	/// Per-column key counts and sizes of the client database and the ancient blocks database, if any.
+	fn stats(&self) -> Result<String, String> {
+		let (client_path, db_config) = try!(self.client_database());
+		let mut out = try!(database_stats(&client_path, &db_config));
+		if let Some(ancient_path) = self.ancient_database() {
+			out.push_str(&try!(database_stats(&ancient_path, &db_config)));
+		}
+		out.push_str(&format!("Configured cache: state {} MB, blockchain {} MB", self.cache_config.db_state_cache_size(), self.cache_config.db_blockchain_cache_size()));
+		Ok(out)
+	}
+	/// Rebuild the state of an archive database from the best block with a pruning journal
+	/// and switch the chain over to the new database. The archive database is removed afterwards.
+	fn convert_pruning(&self, to: Algorithm) -> Result<String, String> {
+		if to == Algorithm::Archive {
+			return Err("Cannot convert to archive: pruned state cannot be recovered".into());
+		}
+
+		let (db_dirs, mut user_defaults, algorithm) = try!(self.chain_database());
+		if algorithm != Algorithm::Archive {
+			return Err(format!("Only archive databases can be converted, this one uses {} pruning", algorithm.as_str()));
+		}
+
+		let (client_path, db_config) = try!(self.client_database());
+		let target_path = db_dirs.client_path(to);
+		if fs::metadata(&target_path).is_ok() {
+			return Err(format!("A {} database already exists at {}", to.as_str(), target_path.display()));
+		}
+
+		let mut temp_path = target_path.clone();
+		temp_path.set_file_name("temp_conversion");
+		// remove leftovers of an interrupted conversion.
+		let _ = fs::remove_dir_all(&temp_path);
+		try!(fs::create_dir_all(&temp_path).map_err(|e| format!("Cannot create {}: {}", temp_path.display(), e)));
+		let size_before = dir_size(&client_path);
+
+		{
+			let source = try!(open_database(&db_config, &client_path));
+			let mut dest = try!(open_database(&db_config, &temp_path));
+			let config = default_migration_settings(&db_config.compaction);
+
+			// the state and its bloom are rebuilt below.
+			for &(col, name) in COLUMNS.iter().filter(|&&(col, _)| col != db::COL_STATE && col != db::COL_ACCOUNT_BLOOM) {
+				info!("Copying {} column", name);
+				let mut batch = Batch::new(&config, col);
+				for (key, value) in source.iter(col) {
+					try!(batch.insert(key.to_vec(), value.to_vec(), &mut dest).map_err(|e| format!("{}", e)));
+				}
+				try!(batch.commit(&mut dest).map_err(|e| format!("{}", e)));
+			}
+		}
+
+		let block_number = {
+			let source = Arc::new(try!(open_database(&db_config, &client_path)));
+			let dest = Arc::new(try!(open_database(&db_config, &temp_path)));
+			info!("Rebuilding state with {} pruning", to.as_str());
+			try!(snapshot::rebuild_head_state(source, dest, to, &Default::default()).map_err(|e| format!("State conversion failed: {}", e)))
+		};
+
+		try!(fs::rename(&temp_path, &target_path).map_err(|e| format!("{}", e)));
+		user_defaults.pruning = to;
+		try!(user_defaults.save(&db_dirs.user_defaults_path()));
+		try!(fs::remove_dir_all(&client_path).map_err(|e| format!("Cannot remove {}: {}", client_path.display(), e)));
+
+		let size_after = dir_size(&target_path);
+		Ok(format!("Converted state at block #{} to {} pruning, database reduced from {} to {} MB",
+			block_number, to.as_str(), size_before / 1024 / 1024, size_after / 1024 / 1024))
+	}
+
+	/// Check that all nodes referenced by the state journal exist. Missing nodes are taken from
+	/// the state of `snapshot_file`, which has to be recent enough to contain them.
+	fn check_journal(&self, snapshot_file: Option<&str>) -> Result<String, String> {
+		let (_, _, algorithm) = try!(self.chain_database());
+		let (client_path, db_config) = try!(self.client_database());
+		let database = Arc::new(try!(open_database(&db_config, &client_path)));
+
+		let report = try!(journaldb::verify_journal(&database, algorithm, db::COL_STATE));
+		let summary = format!("Checked {} journal records: {} missing nodes, {} dangling deletions",
+			report.records, report.missing.len(), report.dangling_deletions.len());
+		if report.is_consistent() {
+			return Ok(summary);
+		}
+		for key in &report.missing {
+			info!("Missing node {:?}", key);
+		}
+		for key in &report.dangling_deletions {
+			info!("Dangling deletion of {:?}", key);
+		}
+
+		let snapshot_file = match snapshot_file {
+			Some(file) => file,
+			None => return Err(format!("{}\nRun again with a snapshot file to repair the journal", summary)),
+		};
+
+		let mut temp_path = client_path.clone();
+		temp_path.set_file_name("temp_repair");
+		// remove leftovers of an interrupted repair.
+		let _ = fs::remove_dir_all(&temp_path);
+		let result = {
+			let snapshot_db = Arc::new(try!(open_database(&db_config, &temp_path)));
+			try!(restore_state(snapshot_file, snapshot_db.clone()));
+
+			let mut nodes = Vec::new();
+			let mut unrecoverable = 0;
+			for key in report.missing.iter().chain(report.dangling_deletions.iter()) {
+				match try!(snapshot_db.get(db::COL_STATE, key)) {
+					Some(value) => nodes.push((key.clone(), value.to_vec())),
+					None => unrecoverable += 1,
+				}
+			}
+
+			let repaired = try!(journaldb::repair_journal(database, algorithm, db::COL_STATE, nodes).map_err(|e| format!("{}", e)));
+			match unrecoverable {
+				0 => Ok(format!("{}\nRepaired {} nodes", summary, repaired)),
+				_ => Err(format!("{}\nRepaired {} nodes, {} nodes are not in the snapshot", summary, repaired, unrecoverable)),
+			}
+		};
+		let _ = fs::remove_dir_all(&temp_path);
+		result
+	}
+
+	/// Remove the databases of the selected chain. Keys and the network identity are stored elsewhere and are not touched.
+	fn kill(&self) -> Result<String, String> {
+		let spec = try!(self.spec.spec());
+		let db_path = try!(self.dirs.database(&spec)).db_path();
+		if fs::metadata(&db_path).is_err() {
+			return Err(format!("No chain data found at {}", db_path.display()));
+		}
+		if Path::new(&self.dirs.keys).starts_with(&db_path) {
+			return Err(format!("Refusing to remove {}: it contains the keys directory {}", db_path.display(), self.dirs.keys));
+		}
+
+		let ancient_path = self.ancient_database();
+		if let Some(ref ancient_path) = ancient_path {
+			if Path::new(&self.dirs.keys).starts_with(ancient_path) {
+				return Err(format!("Refusing to remove {}: it contains the keys directory {}", ancient_path.display(), self.dirs.keys));
+			}
+		}
+
+		try!(fs::remove_dir_all(&db_path).map_err(|e| format!("Cannot remove {}: {}", db_path.display(), e)));
+		let mut out = format!("Removed chain data at {}", db_path.display());
+		if let Some(ancient_path) = ancient_path {
+			try!(fs::remove_dir_all(&ancient_path).map_err(|e| format!("Cannot remove {}: {}", ancient_path.display(), e)));
+			out.push_str(&format!("\nRemoved ancient blocks at {}", ancient_path.display()));
+		}
+		Ok(out)
+	}
+}
+
+/// Compact every column of the database in place, dropping what RocksDB keeps for deleted or overwritten keys.
+fn compact_database(path: &Path, db_config: &DatabaseConfig) -> Result<String, String> {
+	let size_before = dir_size(path);
+
+	{
+		let database = try!(open_database(db_config, path));
+		for &(col, name) in COLUMNS {
+			info!("Compacting {} column", name);
+			try!(database.compact(col));
+		}
+	}
+
+	let size_after = dir_size(path);
+	Ok(format!("Database compacted from {} to {} MB", size_before / 1024 / 1024, size_after / 1024 / 1024))
+}
+
+/// Per-column key counts and sizes of the database.
+fn database_stats(path: &Path, db_config: &DatabaseConfig) -> Result<String, String> {
+	let database = try!(open_database(db_config, path));
+
+	let mut out = format!("Database: {}\n", path.display());
+	out.push_str(&format!("{:<16}{:>14}{:>14}{:>14}\n", "column", "keys", "keys MB", "values MB"));
+	for &(col, name) in COLUMNS {
+		let (mut count, mut keys_size, mut values_size) = (0u64, 0u64, 0u64);
+		for (key, value) in database.iter(col) {
+			count += 1;
+			keys_size += key.len() as u64;
+			values_size += value.len() as u64;
+		}
+		out.push_str(&format!("{:<16}{:>14}{:>14}{:>14}\n", name, count, keys_size / 1024 / 1024, values_size / 1024 / 1024));
+	}
+	out.push_str(&format!("On disk: {} MB\n", dir_size(path) / 1024 / 1024));
+	Ok(out)
+}
+
+/// Rebuild the state of a packed snapshot into an archive database.
+fn restore_state(snapshot_file: &str, dest: Arc<Database>) -> Result<(), String> {
+	let reader = try!(try!(PackedReader::new(Path::new(snapshot_file)).map_err(|e| format!("Cannot open snapshot: {}", e)))
+		.ok_or_else(|| format!("{} is not a snapshot", snapshot_file)));
+	let manifest = reader.manifest();
+	info!("Restoring state of block #{} from the snapshot", manifest.block_number);
+
+	let mut rebuilder = StateRebuilder::new(dest, Algorithm::Archive);
+	for &state_hash in &manifest.state_hashes {
+		let chunk = try!(reader.chunk(state_hash).map_err(|e| format!("Cannot read snapshot chunk {:?}: {}", state_hash, e)));
+		let chunk = try!(snappy::decompress(&chunk).map_err(|e| format!("Invalid snapshot chunk {:?}: {}", state_hash, e)));
+		try!(rebuilder.feed(&chunk).map_err(|e| format!("Invalid snapshot chunk {:?}: {}", state_hash, e)));
+	}
+	Ok(())
+}
+
+/// Execute this database command.
+pub fn execute(cmd: DbCommand) -> Result<String, String> {
+	match cmd.kind.clone() {
+		Kind::Compact => cmd.compact(),
+		Kind::Stats => cmd.stats(),
+		Kind::Kill => cmd.kill(),
+		Kind::ConvertPruning(to) => cmd.convert_pruning(to),
+		Kind::CheckJournal(file) => cmd.check_journal(file.as_ref().map(String::as_str)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use devtools::RandomTempPath;
+	use dir::Directories;
+	use params::SpecType;
+	use super::{execute, DbCommand, Kind};
+
+	#[test]
+	fn kill_should_leave_keys_untouched() {
+		let temp = RandomTempPath::create_dir();
+		let dirs = Directories {
+			db: temp.as_str().to_owned(),
+			keys: temp.as_path().join("keys").to_string_lossy().into_owned(),
+			..Directories::default()
+		};
+		let key_file = temp.as_path().join("keys").join("UTC--wallet");
+		fs::create_dir_all(temp.as_path().join("keys")).unwrap();
+		fs::File::create(&key_file).unwrap();
+
+		let spec = SpecType::Mainnet.spec().unwrap();
+		let db_path = dirs.database(&spec).unwrap().db_path();
+		fs::create_dir_all(&db_path).unwrap();
+
+		let cmd = DbCommand {
+			cache_config: Default::default(),
+			dirs: dirs,
+			spec: SpecType::Mainnet,
+			pruning: Default::default(),
+			compaction: Default::default(),
+			kind: Kind::Kill,
+		};
+		execute(cmd).unwrap();
+
+		assert!(key_file.exists());
+		assert!(!db_path.exists());
+	}
+
+	#[test]
+	fn kill_should_remove_ancient_blocks() {
+		let temp = RandomTempPath::create_dir();
+		let ancient_path = temp.as_path().join("ancient");
+		let dirs = Directories {
+			db: temp.as_str().to_owned(),
+			ancient_path: Some(ancient_path.to_string_lossy().into_owned()),
+			..Directories::default()
+		};
+		fs::create_dir_all(&ancient_path).unwrap();
+
+		let spec = SpecType::Mainnet.spec().unwrap();
+		let db_path = dirs.database(&spec).unwrap().db_path();
+		fs::create_dir_all(&db_path).unwrap();
+
+		let cmd = DbCommand {
+			cache_config: Default::default(),
+			dirs: dirs,
+			spec: SpecType::Mainnet,
+			pruning: Default::default(),
+			compaction: Default::default(),
+			kind: Kind::Kill,
+		};
+		execute(cmd).unwrap();
+
+		assert!(!db_path.exists());
+		assert!(!ancient_path.exists());
+	}
+}
@@ -0,0 +1,177 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Local development chain (`--chain dev`).
+//!
+//! The chain is sealed instantly and its genesis prefunds accounts derived from a seed,
+//! so the same seed gives the same chain and the same accounts on every machine.
+
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+use ethcore::account_provider::AccountProvider;
+use ethcore::client::Client;
+use ethcore::ethstore::ethkey::{Brain, Generator, KeyPair};
+use ethcore::miner::Miner;
+use ethcore::spec::Spec;
+use util::U256;
+
+/// Number of prefunded accounts.
+const ACCOUNTS: usize = 10;
+
+#[derive(Debug, PartialEq)]
+/// Development chain configuration
+pub struct Configuration {
+	/// Seal a block every `period` seconds, even without transactions.
+	pub period: Option<u64>,
+	/// Gas limit of the genesis block and the blocks sealed on top of it.
+	pub gas_limit: U256,
+	/// Seed the prefunded accounts are derived from.
+	pub seed: String,
+}
+
+impl Default for Configuration {
+	fn default() -> Self {
+		Configuration {
+			period: None,
+			gas_limit: U256::from(6_000_000),
+			seed: "parity-dev".into(),
+		}
+	}
+}
+
+/// Balance of every prefunded account, 1 000 000 ether.
+fn balance() -> U256 {
+	U256::from(1_000_000) * U256::from(1_000_000_000_000_000_000u64)
+}
+
+/// Keys of the prefunded accounts.
+pub fn accounts(seed: &str) -> Vec<KeyPair> {
+	(0..ACCOUNTS)
+		.map(|i| Brain::new(format!("{}{}", seed, i)).generate().expect("Brain wallets always find a valid key; qed"))
+		.collect()
+}
+
+/// Specification of the development chain.
+pub fn spec(conf: &Configuration) -> Spec {
+	let mut spec = Spec::new_dev();
+	spec.gas_limit = conf.gas_limit;
+	let addresses = accounts(&conf.seed).iter().map(KeyPair::address).collect::<Vec<_>>();
+	spec.prefund_accounts(&addresses, balance());
+	spec
+}
+
+/// Import the prefunded accounts with an empty password and unlock them permanently.
+/// `account_provider` should be backed by the keys directory of the development chain, not the user's keys.
+pub fn import_accounts(conf: &Configuration, account_provider: &AccountProvider) -> Result<(), String> {
+	let existing = try!(account_provider.accounts().map_err(|e| format!("Error listing accounts: {}", e)));
+	for pair in accounts(&conf.seed) {
+		let address = pair.address();
+		if !existing.contains(&address) {
+			try!(account_provider.insert_account(pair.secret().clone(), "")
+				.map_err(|e| format!("Error importing development account {}: {}", address, e)));
+		}
+		try!(account_provider.unlock_account_permanently(address, String::new())
+			.map_err(|e| format!("Error unlocking development account {}: {}", address, e)));
+	}
+	info!("Development accounts derived from seed \"{}\" are unlocked with an empty password.", conf.seed);
+	Ok(())
+}
+
+/// Seal a block every `period` seconds, whether or not there are pending transactions.
+/// The sealing thread stops once the client is dropped.
+pub fn start(conf: &Configuration, client: Weak<Client>, miner: Arc<Miner>) -> Result<(), String> {
+	let period = match conf.period {
+		Some(period) if period > 0 => Duration::from_secs(period),
+		_ => return Ok(()),
+	};
+
+	thread::Builder::new().name("dev-sealing".into()).spawn(move || loop {
+		thread::sleep(period);
+		let client = match client.upgrade() {
+			Some(client) => client,
+			None => break,
+		};
+		if !miner.seal_empty_block(&*client) {
+			warn!(target: "miner", "Error sealing a development block");
+		}
+	})
+	.map(|_| ())
+	.map_err(|e| format!("Error starting development block sealing: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::thread;
+	use std::time::Duration;
+	use devtools::RandomTempPath;
+	use ethcore::client::{Client, ClientConfig, BlockChainClient};
+	use ethcore::db::NUM_COLUMNS;
+	use ethcore::miner::Miner;
+	use io::IoChannel;
+	use util::kvdb::DatabaseConfig;
+	use super::{Configuration, accounts, spec, start};
+
+	#[test]
+	fn should_derive_accounts_from_seed() {
+		let addresses = |seed: &str| accounts(seed).iter().map(|pair| pair.address()).collect::<Vec<_>>();
+		let first = addresses("seed");
+		assert_eq!(first.len(), 10);
+		assert_eq!(first, addresses("seed"));
+		assert!(first != addresses("other seed"));
+	}
+
+	#[test]
+	fn should_use_configured_gas_limit() {
+		let default = spec(&Configuration::default());
+		let custom = spec(&Configuration { gas_limit: 8_000_000.into(), ..Default::default() });
+
+		assert_eq!(custom.genesis_header().gas_limit(), &8_000_000.into());
+		assert!(default.genesis_header().hash() != custom.genesis_header().hash());
+	}
+
+	#[test]
+	fn should_seal_blocks_until_client_is_dropped() {
+		// given
+		let conf = Configuration { period: Some(1), ..Default::default() };
+		let spec = spec(&conf);
+		let temp = RandomTempPath::new();
+		let miner = Arc::new(Miner::with_spec(&spec));
+		let db_config = DatabaseConfig::with_columns(NUM_COLUMNS);
+		let client = Client::new(ClientConfig::default(), &spec, temp.as_path(), miner.clone(), IoChannel::disconnected(), &db_config).unwrap();
+
+		// when
+		start(&conf, Arc::downgrade(&client), miner.clone()).unwrap();
+		thread::sleep(Duration::from_millis(2500));
+		client.flush_queue();
+
+		// then
+		assert!(client.chain_info().best_block_number >= 1);
+
+		// the detached thread only holds the miner until it notices the client is gone
+		drop(client);
+		let mut miner = miner;
+		for _ in 0..50 {
+			miner = match Arc::try_unwrap(miner) {
+				Ok(_) => return,
+				Err(miner) => miner,
+			};
+			thread::sleep(Duration::from_millis(100));
+		}
+		panic!("Development sealing thread didn't stop after the client was dropped");
+	}
+}
@@ -16,8 +16,9 @@
 
 use std::fs;
 use std::path::{PathBuf, Path};
-use util::{H64, H256};
+use util::{H64, H256, U256};
 use util::journaldb::Algorithm;
+use ethcore::spec::Spec;
 use helpers::replace_home;
 
 // this const is irrelevent cause we do have migrations now,
@@ -30,6 +31,8 @@ pub struct Directories {
 	pub keys: String,
 	pub signer: String,
 	pub dapps: String,
+	/// Database keeping bodies and receipts of ancient blocks, if any.
+	pub ancient_path: Option<String>,
 }
 
 impl Default for Directories {
@@ -39,6 +42,7 @@ impl Default for Directories {
 			keys: replace_home("$HOME/.parity/keys"),
 			signer: replace_home("$HOME/.parity/signer"),
 			dapps: replace_home("$HOME/.parity/dapps"),
+			ancient_path: None,
 		}
 	}
 }
@@ -52,13 +56,43 @@ impl Directories {
 		Ok(())
 	}
 
-	/// Database paths.
-	pub fn database(&self, genesis_hash: H256, fork_name: Option<String>) -> DatabaseDirectories {
-		DatabaseDirectories {
-			path: self.db.clone(),
-			genesis_hash: genesis_hash,
-			fork_name: fork_name,
-		}
+	/// Directory holding the database, network key and nodes of the chain.
+	/// Keyed by the genesis hash too, so that specs with the same name but a different genesis,
+	/// like development chains with another seed, don't share data.
+	pub fn chain_path(&self, spec: &Spec) -> PathBuf {
+		let mut dir = Path::new(&self.db).to_path_buf();
+		dir.push("chains");
+		dir.push(chain_dir_name(&spec.name, &spec.genesis_header().hash()));
+		dir
+	}
+
+	/// Directory holding the network key of the chain. Nodes files are kept in a subdirectory per network id.
+	pub fn network_path(&self, spec: &Spec) -> PathBuf {
+		let mut dir = self.chain_path(spec);
+		dir.push("network");
+		dir
+	}
+
+	/// Directory holding the keys of the development accounts of the chain.
+	pub fn dev_keys_path(&self, spec: &Spec) -> PathBuf {
+		let mut dir = self.chain_path(spec);
+		dir.push("keys");
+		dir
+	}
+
+	/// Database paths of the chain. Data of the chain left in the legacy flat layout
+	/// is moved into the chain directory first.
+	pub fn database(&self, spec: &Spec) -> Result<DatabaseDirectories, String> {
+		let dirs = DatabaseDirectories {
+			path: self.chain_path(spec).to_string_lossy().into_owned(),
+			base_path: self.db.clone(),
+			genesis_hash: spec.genesis_header().hash(),
+			fork_name: spec.fork_name.clone(),
+			network_id: spec.network_id(),
+			ancient_path: self.ancient_path.as_ref().map(PathBuf::from),
+		};
+		try!(dirs.migrate_legacy_layout().map_err(|e| format!("Error moving chain data to {}: {}", dirs.path, e)));
+		Ok(dirs)
 	}
 
 	/// Get the ipc sockets path
@@ -67,22 +101,73 @@ impl Directories {
 		dir.push("ipc");
 		dir
 	}
+
+	/// Get the node events journal path
+	pub fn node_events_path(&self) -> PathBuf {
+		let mut path = Path::new(&self.db).to_path_buf();
+		path.push("node_events");
+		path
+	}
 }
 
 #[derive(Debug, PartialEq)]
 pub struct DatabaseDirectories {
 	pub path: String,
+	pub base_path: String,
 	pub genesis_hash: H256,
 	pub fork_name: Option<String>,
+	pub network_id: U256,
+	pub ancient_path: Option<PathBuf>,
 }
 
 impl DatabaseDirectories {
+	fn fork_dir_name(&self) -> String {
+		format!("{:?}{}", H64::from(self.genesis_hash), self.fork_name.as_ref().map(|f| format!("-{}", f)).unwrap_or_default())
+	}
+
 	fn fork_path(&self) -> PathBuf {
 		let mut dir = Path::new(&self.path).to_path_buf();
-		dir.push(format!("{:?}{}", H64::from(self.genesis_hash), self.fork_name.as_ref().map(|f| format!("-{}", f)).unwrap_or_default()));
+		dir.push("db");
+		dir.push(self.fork_dir_name());
 		dir
 	}
 
+	/// Move the database, network key and nodes file of the chain from the legacy flat layout.
+	/// The network key and nodes file go with the first database moved, so that every chain
+	/// keeps its own network key. Chains without a legacy database start with a new key.
+	fn migrate_legacy_layout(&self) -> Result<(), String> {
+		let legacy = Path::new(&self.base_path);
+		let legacy_db = legacy.join(self.fork_dir_name());
+		let db = self.fork_path();
+		if !legacy_db.exists() || db.exists() {
+			return Ok(());
+		}
+
+		try!(fs::create_dir_all(Path::new(&self.path).join("db")).map_err(|e| e.to_string()));
+		try!(fs::rename(&legacy_db, &db).map_err(|e| e.to_string()));
+		info!("Moved chain database from {} to {}", legacy_db.display(), db.display());
+
+		let network = Path::new(&self.path).join("network");
+		let network_id = format!("{}", self.network_id);
+		let legacy_files = [
+			(legacy.join("network").join("key"), network.join("key")),
+			(legacy.join("network").join(&network_id).join("nodes.json"), network.join(&network_id).join("nodes.json")),
+		];
+		for &(ref from, ref to) in &legacy_files {
+			if from.exists() && !to.exists() {
+				try!(fs::create_dir_all(to.parent().expect("joined to the network directory; qed")).map_err(|e| e.to_string()));
+				try!(fs::rename(from, to).map_err(|e| e.to_string()));
+			}
+		}
+		Ok(())
+	}
+
+	/// Directory holding all databases, user defaults and snapshots of the chain.
+	/// The network key and nodes file are kept outside of it.
+	pub fn db_path(&self) -> PathBuf {
+		self.fork_path()
+	}
+
 	/// Get the root path for database
 	pub fn version_path(&self, pruning: Algorithm) -> PathBuf {
 		let mut dir = self.fork_path();
@@ -97,6 +182,14 @@ impl DatabaseDirectories {
 		dir
 	}
 
+	/// Pruning algorithms for which a client database exists.
+	pub fn existing_pruning(&self) -> Vec<Algorithm> {
+		[Algorithm::Archive, Algorithm::EarlyMerge, Algorithm::OverlayRecent, Algorithm::RefCounted].iter()
+			.cloned()
+			.filter(|algorithm| fs::metadata(self.client_path(*algorithm)).is_ok())
+			.collect()
+	}
+
 	/// Get user defaults path
 	pub fn user_defaults_path(&self) -> PathBuf {
 		let mut dir = self.fork_path();
@@ -104,6 +197,13 @@ impl DatabaseDirectories {
 		dir
 	}
 
+	/// Get the path of the file holding queued transactions between restarts.
+	pub fn pending_transactions_path(&self) -> PathBuf {
+		let mut dir = self.fork_path();
+		dir.push("pending_transactions.rlp");
+		dir
+	}
+
 	/// Get the path for the snapshot directory given the genesis hash and fork name.
 	pub fn snapshot_path(&self) -> PathBuf {
 		let mut dir = self.fork_path();
@@ -112,9 +212,19 @@ impl DatabaseDirectories {
 	}
 }
 
+/// Name of the directory holding the data of the chain with the given spec name and genesis hash.
+fn chain_dir_name(spec_name: &str, genesis_hash: &H256) -> String {
+	let name: String = spec_name.to_lowercase().chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+	format!("{}-{:?}", name, H64::from(*genesis_hash))
+}
+
 #[cfg(test)]
 mod tests {
-	use super::Directories;
+	use std::fs;
+	use std::path::Path;
+	use ethcore::ethereum;
+	use devtools::RandomTempPath;
+	use super::{Directories, chain_dir_name};
 	use helpers::replace_home;
 
 	#[test]
@@ -124,7 +234,69 @@ mod tests {
 			keys: replace_home("$HOME/.parity/keys"),
 			signer: replace_home("$HOME/.parity/signer"),
 			dapps: replace_home("$HOME/.parity/dapps"),
+			ancient_path: None,
 		};
 		assert_eq!(expected, Directories::default());
 	}
+
+	#[test]
+	fn should_name_chain_directories() {
+		let hash = "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3".parse().unwrap();
+		assert_eq!(chain_dir_name("Frontier/Homestead", &hash), "frontier_homestead-d4e56740f876aef8");
+		assert_eq!(chain_dir_name("Ropsten", &hash), "ropsten-d4e56740f876aef8");
+		assert_eq!(chain_dir_name("Ethereum Classic", &hash), "ethereum_classic-d4e56740f876aef8");
+	}
+
+	#[test]
+	fn should_key_chain_directories_by_genesis() {
+		use dev;
+		let dirs = Directories::default();
+		let mut conf = dev::Configuration::default();
+		let first = dirs.chain_path(&dev::spec(&conf));
+		conf.seed = "another seed".into();
+		assert!(first != dirs.chain_path(&dev::spec(&conf)));
+	}
+
+	#[test]
+	fn should_migrate_legacy_layout() {
+		let temp = RandomTempPath::new();
+		let base = temp.as_path().to_path_buf();
+		let dirs = Directories {
+			db: base.to_string_lossy().into_owned(),
+			..Directories::default()
+		};
+		let spec = ethereum::new_frontier();
+		let legacy_db = base.join(format!("{:?}", ::util::H64::from(spec.genesis_header().hash()))).join("v5.3-sec-overlayrecent");
+		fs::create_dir_all(&legacy_db).unwrap();
+		fs::create_dir_all(base.join("network").join("1")).unwrap();
+		fs::File::create(base.join("network").join("key")).unwrap();
+		fs::File::create(base.join("network").join("1").join("nodes.json")).unwrap();
+
+		let db_dirs = dirs.database(&spec).unwrap();
+
+		let chain = Path::new(&db_dirs.path);
+		assert_eq!(chain, base.join("chains").join("frontier_homestead-d4e56740f876aef8").as_path());
+		assert!(!legacy_db.exists());
+		assert!(db_dirs.version_path(::util::journaldb::Algorithm::OverlayRecent).exists());
+		assert!(chain.join("network").join("key").exists());
+		assert!(chain.join("network").join("1").join("nodes.json").exists());
+		assert!(!base.join("network").join("key").exists());
+	}
+
+	#[test]
+	fn should_not_share_legacy_network_key_with_new_chains() {
+		let temp = RandomTempPath::new();
+		let base = temp.as_path().to_path_buf();
+		let dirs = Directories {
+			db: base.to_string_lossy().into_owned(),
+			..Directories::default()
+		};
+		fs::create_dir_all(base.join("network")).unwrap();
+		fs::File::create(base.join("network").join("key")).unwrap();
+
+		let db_dirs = dirs.database(&ethereum::new_morden()).unwrap();
+
+		assert!(!Path::new(&db_dirs.path).join("network").join("key").exists());
+		assert!(base.join("network").join("key").exists());
+	}
 }
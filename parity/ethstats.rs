@@ -0,0 +1,420 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reporting node status to an eth-netstats server.
+
+use std::env;
+use std::str::FromStr;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde_json::{self, Value};
+use serde_json::builder::ObjectBuilder;
+use ws;
+use ethcore::client::{Client, BlockChainClient, BlockID};
+use ethcore::miner::{Miner, MinerService, ExternalMiner, ExternalMinerService};
+use ethcore::views::BlockView;
+use ethsync::SyncProvider;
+use util::{H256, U256, Uint, Mutex};
+use util::misc::version;
+use informant::MillisecondDuration;
+
+const REPORT_TIMER: ws::util::Token = ws::util::Token(0);
+const PING_TIMER: ws::util::Token = ws::util::Token(1);
+/// How often the head block, pending transactions and node stats are reported.
+const REPORT_INTERVAL_MS: u64 = 5_000;
+/// How often the latency to the server is measured.
+const PING_INTERVAL_MS: u64 = 15_000;
+/// Delay before reconnecting after the connection is lost.
+const RECONNECT_DELAY_SECS: u64 = 10;
+
+#[derive(Debug, PartialEq, Clone)]
+/// Ethstats reporting configuration
+pub struct Configuration {
+	/// Name the node is displayed under.
+	pub name: String,
+	/// Secret required by the server.
+	pub secret: String,
+	/// Server host and port.
+	pub host: String,
+}
+
+impl FromStr for Configuration {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || format!("Invalid ethstats server: {}. Expected name:secret@host:port.", s);
+		let at = try!(s.rfind('@').ok_or_else(|| invalid()));
+		let (credentials, host) = (&s[..at], &s[at + 1..]);
+		let colon = try!(credentials.find(':').ok_or_else(|| invalid()));
+		let (name, secret) = (&credentials[..colon], &credentials[colon + 1..]);
+		if name.is_empty() || !host.contains(':') {
+			return Err(invalid());
+		}
+		Ok(Configuration {
+			name: name.into(),
+			secret: secret.into(),
+			host: host.into(),
+		})
+	}
+}
+
+/// Ethstats reporting dependencies
+pub struct Dependencies {
+	/// Blockchain client.
+	pub client: Arc<Client>,
+	/// Sync status provider.
+	pub sync: Arc<SyncProvider>,
+	/// Miner, for the transaction pool and sealing status.
+	pub miner: Arc<Miner>,
+	/// External miners, for the hashrate.
+	pub external_miner: Arc<ExternalMiner>,
+	/// Network identifier.
+	pub network_id: U256,
+	/// Port the node listens on for peers.
+	pub network_port: u16,
+}
+
+/// Node handles used for reporting. Weak, so that the reporting thread doesn't keep the client alive.
+struct Node {
+	client: Weak<Client>,
+	sync: Weak<SyncProvider>,
+	miner: Weak<Miner>,
+	external_miner: Weak<ExternalMiner>,
+	network_id: U256,
+	network_port: u16,
+}
+
+/// Share of time the node has been connected to the server since reporting started.
+struct Uptime {
+	started: Instant,
+	connected: Duration,
+	connected_since: Option<Instant>,
+}
+
+impl Uptime {
+	fn new() -> Self {
+		Uptime {
+			started: Instant::now(),
+			connected: Duration::from_secs(0),
+			connected_since: None,
+		}
+	}
+
+	fn connect(&mut self) {
+		self.connected_since = Some(Instant::now());
+	}
+
+	fn disconnect(&mut self) {
+		if let Some(since) = self.connected_since.take() {
+			self.connected = self.connected + since.elapsed();
+		}
+	}
+
+	/// Connected time in percent.
+	fn percentage(&self) -> u64 {
+		let connected = self.connected + self.connected_since.map_or(Duration::from_secs(0), |since| since.elapsed());
+		match self.started.elapsed().as_milliseconds() {
+			0 => 100,
+			total => ::std::cmp::min(100, connected.as_milliseconds() * 100 / total),
+		}
+	}
+}
+
+/// Start reporting to the ethstats server, reconnecting whenever the connection drops.
+/// Reporting stops once the client is shut down.
+pub fn start(conf: Option<Configuration>, deps: Dependencies) -> Result<(), String> {
+	let conf = match conf {
+		Some(conf) => Arc::new(conf),
+		None => return Ok(()),
+	};
+	let node = Arc::new(Node {
+		client: Arc::downgrade(&deps.client),
+		sync: Arc::downgrade(&deps.sync),
+		miner: Arc::downgrade(&deps.miner),
+		external_miner: Arc::downgrade(&deps.external_miner),
+		network_id: deps.network_id,
+		network_port: deps.network_port,
+	});
+	let uptime = Arc::new(Mutex::new(Uptime::new()));
+	let url = format!("ws://{}/api", conf.host);
+
+	thread::Builder::new().name("ethstats".into()).spawn(move || {
+		while node.client.upgrade().is_some() {
+			match ws::connect(url.clone(), |out| Session::new(out, conf.clone(), node.clone(), uptime.clone())) {
+				Ok(_) => debug!(target: "ethstats", "Disconnected from {}", conf.host),
+				Err(e) => warn!(target: "ethstats", "Error connecting to {}: {}", conf.host, e),
+			}
+			uptime.lock().disconnect();
+			thread::sleep(Duration::from_secs(RECONNECT_DELAY_SECS));
+		}
+	})
+	.map(|_| ())
+	.map_err(|e| format!("Error starting ethstats reporting: {}", e))
+}
+
+struct Session {
+	out: ws::Sender,
+	conf: Arc<Configuration>,
+	node: Arc<Node>,
+	uptime: Arc<Mutex<Uptime>>,
+	reported_head: Option<H256>,
+}
+
+fn hex(hash: &H256) -> String {
+	format!("0x{:?}", hash)
+}
+
+fn now_ms() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_milliseconds()).unwrap_or(0)
+}
+
+/// Release of the running kernel, as reported by `uname -r`.
+#[cfg(unix)]
+fn os_version() -> String {
+	extern crate libc;
+	use std::ffi::CStr;
+
+	let mut name: libc::utsname = unsafe { ::std::mem::zeroed() };
+	match unsafe { libc::uname(&mut name) } {
+		0 => unsafe { CStr::from_ptr(name.release.as_ptr()) }.to_string_lossy().into_owned(),
+		_ => String::new(),
+	}
+}
+
+#[cfg(not(unix))]
+fn os_version() -> String {
+	String::new()
+}
+
+impl Session {
+	fn new(out: ws::Sender, conf: Arc<Configuration>, node: Arc<Node>, uptime: Arc<Mutex<Uptime>>) -> Self {
+		Session {
+			out: out,
+			conf: conf,
+			node: node,
+			uptime: uptime,
+			reported_head: None,
+		}
+	}
+
+	fn emit(&self, event: &str, payload: ObjectBuilder) -> ws::Result<()> {
+		let payload = payload.insert("id", &self.conf.name).build();
+		let message = ObjectBuilder::new()
+			.insert("emit", vec![Value::String(event.into()), payload])
+			.build();
+		self.out.send(serde_json::to_string(&message).expect("JSON values are always serializable; qed"))
+	}
+
+	/// Highest eth protocol version negotiated with the connected peers,
+	/// or the highest one supported if there are none yet.
+	fn protocol_version(&self) -> u32 {
+		self.node.sync.upgrade().map_or(0, |sync| sync.peers().iter()
+			.map(|peer| peer.eth_version)
+			.max()
+			.unwrap_or(sync.status().protocol_version as u32))
+	}
+
+	fn hello(&self) -> ws::Result<()> {
+		self.emit("hello", ObjectBuilder::new()
+			.insert("secret", &self.conf.secret)
+			.insert_object("info", |info| info
+				.insert("name", &self.conf.name)
+				.insert("node", version())
+				.insert("port", self.node.network_port)
+				.insert("net", format!("{}", self.node.network_id))
+				.insert("protocol", format!("eth/{}", self.protocol_version()))
+				.insert("api", "No")
+				.insert("os", env::consts::OS)
+				.insert("os_v", os_version())
+				.insert("client", version())
+				.insert("canUpdateHistory", false)
+			)
+		)
+	}
+
+	fn report_block(&mut self, client: &Client) -> ws::Result<()> {
+		let chain_info = client.chain_info();
+		if self.reported_head == Some(chain_info.best_block_hash) {
+			return Ok(());
+		}
+		let block = match client.block(BlockID::Hash(chain_info.best_block_hash)) {
+			Some(block) => block,
+			None => return Ok(()),
+		};
+		let view = BlockView::new(&block);
+		let header = view.header();
+		let transactions: Vec<Value> = view.transaction_hashes().iter()
+			.map(|hash| ObjectBuilder::new().insert("hash", hex(hash)).build())
+			.collect();
+		let uncles: Vec<String> = view.uncle_hashes().iter().map(hex).collect();
+
+		self.reported_head = Some(chain_info.best_block_hash);
+		self.emit("block", ObjectBuilder::new()
+			.insert_object("block", |b| b
+				.insert("number", header.number())
+				.insert("hash", hex(&header.hash()))
+				.insert("parentHash", hex(header.parent_hash()))
+				.insert("timestamp", header.timestamp())
+				.insert("miner", format!("0x{:?}", header.author()))
+				.insert("gasUsed", header.gas_used().low_u64())
+				.insert("gasLimit", header.gas_limit().low_u64())
+				.insert("difficulty", format!("{}", header.difficulty()))
+				.insert("totalDifficulty", format!("{}", chain_info.total_difficulty))
+				.insert("transactions", transactions)
+				.insert("transactionsRoot", hex(header.transactions_root()))
+				.insert("stateRoot", hex(header.state_root()))
+				.insert("uncles", uncles)
+			)
+		)
+	}
+
+	fn report_pending(&self, miner: &Miner) -> ws::Result<()> {
+		let pending = miner.status().transactions_in_pending_queue;
+		self.emit("pending", ObjectBuilder::new()
+			.insert_object("stats", |s| s.insert("pending", pending))
+		)
+	}
+
+	fn report_stats(&self, sync: &SyncProvider, miner: &Miner, external_miner: &ExternalMiner) -> ws::Result<()> {
+		let status = sync.status();
+		let uptime = self.uptime.lock().percentage();
+		self.emit("stats", ObjectBuilder::new()
+			.insert_object("stats", |s| s
+				.insert("active", true)
+				.insert("syncing", status.is_major_syncing())
+				.insert("mining", miner.is_sealing())
+				.insert("hashrate", external_miner.hashrate().low_u64())
+				.insert("peers", status.num_peers)
+				.insert("gasPrice", miner.sensible_gas_price().low_u64())
+				.insert("uptime", uptime)
+			)
+		)
+	}
+
+	fn report(&mut self) -> ws::Result<()> {
+		let node = self.node.clone();
+		match (node.client.upgrade(), node.sync.upgrade(), node.miner.upgrade(), node.external_miner.upgrade()) {
+			(Some(client), Some(sync), Some(miner), Some(external_miner)) => {
+				try!(self.report_block(&client));
+				try!(self.report_pending(&miner));
+				self.report_stats(&*sync, &miner, &external_miner)
+			},
+			_ => {
+				debug!(target: "ethstats", "Client shut down, closing connection");
+				self.out.close(ws::CloseCode::Away)
+			},
+		}
+	}
+
+	fn ping(&self) -> ws::Result<()> {
+		self.emit("node-ping", ObjectBuilder::new().insert("clientTime", now_ms()))
+	}
+}
+
+impl ws::Handler for Session {
+	fn on_open(&mut self, _: ws::Handshake) -> ws::Result<()> {
+		info!(target: "ethstats", "Reporting to {} as {}", self.conf.host, self.conf.name);
+		self.uptime.lock().connect();
+		try!(self.hello());
+		try!(self.report());
+		try!(self.ping());
+		try!(self.out.timeout(REPORT_INTERVAL_MS, REPORT_TIMER));
+		self.out.timeout(PING_INTERVAL_MS, PING_TIMER)
+	}
+
+	fn on_timeout(&mut self, event: ws::util::Token) -> ws::Result<()> {
+		match event {
+			REPORT_TIMER => {
+				try!(self.report());
+				self.out.timeout(REPORT_INTERVAL_MS, REPORT_TIMER)
+			},
+			PING_TIMER => {
+				try!(self.ping());
+				self.out.timeout(PING_INTERVAL_MS, PING_TIMER)
+			},
+			_ => Ok(()),
+		}
+	}
+
+	fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+		let message: Value = match serde_json::from_str(try!(msg.as_text())) {
+			Ok(message) => message,
+			Err(e) => {
+				debug!(target: "ethstats", "Invalid message from server: {}", e);
+				return Ok(());
+			},
+		};
+		let emit = match message.find("emit").and_then(Value::as_array) {
+			Some(emit) => emit,
+			None => return Ok(()),
+		};
+		match emit.get(0).and_then(Value::as_str) {
+			Some("node-pong") => {
+				let sent = emit.get(1).and_then(|p| p.find("clientTime")).and_then(Value::as_u64);
+				if let Some(sent) = sent {
+					let latency = now_ms().saturating_sub(sent) / 2;
+					try!(self.emit("latency", ObjectBuilder::new().insert("latency", latency)));
+				}
+			},
+			Some(event) => trace!(target: "ethstats", "Ignoring {} message", event),
+			None => {},
+		}
+		Ok(())
+	}
+
+	fn on_close(&mut self, code: ws::CloseCode, reason: &str) {
+		debug!(target: "ethstats", "Connection closed: {:?} {}", code, reason);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use super::{Configuration, Uptime};
+
+	#[test]
+	fn should_report_connected_time_as_uptime() {
+		let now = Instant::now();
+		let mut uptime = Uptime {
+			started: now - Duration::from_secs(100),
+			connected: Duration::from_secs(30),
+			connected_since: None,
+		};
+		assert_eq!(uptime.percentage(), 30);
+
+		uptime.connected_since = Some(now - Duration::from_secs(20));
+		assert_eq!(uptime.percentage(), 50);
+
+		uptime.disconnect();
+		assert_eq!(uptime.percentage(), 50);
+		assert!(uptime.connected_since.is_none());
+	}
+
+	#[test]
+	fn should_parse_configuration() {
+		assert_eq!("node:s3cr3t@stats.example.com:3000".parse(), Ok(Configuration {
+			name: "node".into(),
+			secret: "s3cr3t".into(),
+			host: "stats.example.com:3000".into(),
+		}));
+		assert_eq!("node:a:b@c@127.0.0.1:3000".parse::<Configuration>().map(|c| (c.secret, c.host)),
+			Ok(("a:b@c".into(), "127.0.0.1:3000".into())));
+		assert!("node@127.0.0.1:3000".parse::<Configuration>().is_err());
+		assert!("node:secret@localhost".parse::<Configuration>().is_err());
+		assert!(":secret@localhost:3000".parse::<Configuration>().is_err());
+	}
+}
@@ -0,0 +1,119 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reader of go-ethereum `chaindata` databases.
+//!
+//! Both the current key schema (`h` + number + hash) and the one used
+//! before geth 1.5 (`block-` + hash + `-header`) are supported.
+
+use util::{Bytes, H256, Database, DatabaseConfig};
+use rlp::{RlpStream, Stream, UntrustedRlp, View};
+
+const HEADER_PREFIX: &'static [u8] = b"h";
+const CANONICAL_SUFFIX: &'static [u8] = b"n";
+const BODY_PREFIX: &'static [u8] = b"b";
+
+const LEGACY_BLOCK_PREFIX: &'static [u8] = b"block-";
+const LEGACY_BLOCK_NUM_PREFIX: &'static [u8] = b"block-num-";
+const LEGACY_HEADER_SUFFIX: &'static [u8] = b"-header";
+const LEGACY_BODY_SUFFIX: &'static [u8] = b"-body";
+
+fn encode_number(number: u64) -> [u8; 8] {
+	let mut out = [0u8; 8];
+	for (i, byte) in out.iter_mut().enumerate() {
+		*byte = (number >> (8 * (7 - i))) as u8;
+	}
+	out
+}
+
+fn key(parts: &[&[u8]]) -> Vec<u8> {
+	parts.iter().fold(Vec::new(), |mut key, part| {
+		key.extend_from_slice(part);
+		key
+	})
+}
+
+/// Read-only view of geth's canonical chain.
+pub struct GethChainData {
+	db: Database,
+}
+
+impl GethChainData {
+	/// Opens `chaindata` directory. geth's data is never modified.
+	pub fn open(path: &str) -> Result<Self, String> {
+		let db = try!(Database::open_read_only(&DatabaseConfig::default(), path)
+			.map_err(|e| format!("Cannot open geth chaindata at {}: {}", path, e)));
+		Ok(GethChainData {
+			db: db,
+		})
+	}
+
+	fn get(&self, key: &[u8]) -> Option<Bytes> {
+		self.db.get(None, key).ok().and_then(|v| v).map(|v| v.to_vec())
+	}
+
+	fn canonical_hash(&self, number: u64) -> Option<H256> {
+		let n = encode_number(number);
+		self.get(&key(&[HEADER_PREFIX, &n, CANONICAL_SUFFIX]))
+			.or_else(|| self.get(&key(&[LEGACY_BLOCK_NUM_PREFIX, &n])))
+			.and_then(|h| match h.len() {
+				32 => Some(H256::from_slice(&h)),
+				_ => None,
+			})
+	}
+
+	fn header(&self, number: u64, hash: &H256) -> Option<Bytes> {
+		self.get(&key(&[HEADER_PREFIX, &encode_number(number), hash]))
+			.or_else(|| self.get(&key(&[LEGACY_BLOCK_PREFIX, hash, LEGACY_HEADER_SUFFIX])))
+	}
+
+	fn body(&self, number: u64, hash: &H256) -> Option<Bytes> {
+		self.get(&key(&[BODY_PREFIX, &encode_number(number), hash]))
+			.or_else(|| self.get(&key(&[LEGACY_BLOCK_PREFIX, hash, LEGACY_BODY_SUFFIX])))
+	}
+
+	/// Returns RLP of canonical block with given number, or `None` if the chain is shorter.
+	pub fn block(&self, number: u64) -> Result<Option<Bytes>, String> {
+		let hash = match self.canonical_hash(number) {
+			Some(hash) => hash,
+			None => return Ok(None),
+		};
+		let header = try!(self.header(number, &hash).ok_or_else(|| format!("Missing header of block #{}", number)));
+		let body = try!(self.body(number, &hash).ok_or_else(|| format!("Missing body of block #{}", number)));
+
+		let body = UntrustedRlp::new(&body);
+		let (transactions, uncles) = match (body.at(0), body.at(1)) {
+			(Ok(t), Ok(u)) => (t, u),
+			_ => return Err(format!("Invalid body of block #{}", number)),
+		};
+
+		let mut stream = RlpStream::new_list(3);
+		stream.append_raw(&header, 1);
+		stream.append_raw(transactions.as_raw(), 1);
+		stream.append_raw(uncles.as_raw(), 1);
+		Ok(Some(stream.out()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::encode_number;
+
+	#[test]
+	fn should_encode_number_big_endian() {
+		assert_eq!(encode_number(0x0102), [0, 0, 0, 0, 0, 0, 1, 2]);
+	}
+}
@@ -22,7 +22,8 @@ use std::fs::File;
 use util::{clean_0x, U256, Uint, Address, path, CompactionProfile};
 use util::journaldb::Algorithm;
 use ethcore::client::{Mode, BlockID, VMType, DatabaseCompactionProfile, ClientConfig};
-use ethcore::miner::PendingSet;
+use ethcore::miner::{PendingSet, EvictionStrategy};
+use ethcore::ethstore::KdfParams;
 use cache::CacheConfig;
 use dir::DatabaseDirectories;
 use upgrade::upgrade;
@@ -58,7 +59,9 @@ pub fn to_mode(s: &str, timeout: u64, alarm: u64) -> Result<Mode, String> {
 		"active" => Ok(Mode::Active),
 		"passive" => Ok(Mode::Passive(Duration::from_secs(timeout), Duration::from_secs(alarm))),
 		"dark" => Ok(Mode::Dark(Duration::from_secs(timeout))),
-		_ => Err(format!("{}: Invalid address for --mode. Must be one of active, passive or dark.", s)),
+		"offline" => Ok(Mode::Off),
+		"relay" => Ok(Mode::Relay),
+		_ => Err(format!("{}: Invalid address for --mode. Must be one of active, passive, dark, offline or relay.", s)),
 	}
 }
 
@@ -93,6 +96,14 @@ pub fn to_pending_set(s: &str) -> Result<PendingSet, String> {
 	}
 }
 
+pub fn to_eviction_strategy(s: &str) -> Result<EvictionStrategy, String> {
+	match s {
+		"gas_price" => Ok(EvictionStrategy::GasPrice),
+		"oldest" => Ok(EvictionStrategy::Oldest),
+		other => Err(format!("Invalid transaction queue eviction strategy: {:?}", other)),
+	}
+}
+
 pub fn to_address(s: Option<String>) -> Result<Address, String> {
 	match s {
 		Some(ref a) => clean_0x(a).parse().map_err(|_| format!("Invalid address: {:?}", a)),
@@ -109,6 +120,28 @@ pub fn to_addresses(s: &Option<String>) -> Result<Vec<Address>, String> {
 	}
 }
 
+/// Parses key derivation function for new key files: `pbkdf2` or `scrypt:N:R:P`.
+pub fn to_kdf(s: &str, iterations: u32) -> Result<KdfParams, String> {
+	let invalid = || format!("Invalid key derivation function: {}. Expected pbkdf2 or scrypt:N:R:P.", s);
+	let mut parts = s.split(':');
+	match parts.next() {
+		Some("pbkdf2") if parts.next().is_none() => Ok(KdfParams::Pbkdf2 { iterations: iterations }),
+		Some("scrypt") => {
+			let params = try!(parts.map(|p| p.parse::<u32>()).collect::<Result<Vec<_>, _>>().map_err(|_| invalid()));
+			if params.len() != 3 {
+				return Err(invalid());
+			}
+			let (n, r, p) = (params[0], params[1], params[2]);
+			if n > 1 && n.is_power_of_two() && r > 0 && p > 0 {
+				Ok(KdfParams::Scrypt { n: n, r: r, p: p })
+			} else {
+				Err(invalid())
+			}
+		},
+		_ => Err(invalid()),
+	}
+}
+
 /// Tries to parse string as a price.
 pub fn to_price(s: &str) -> Result<f32, String> {
 	s.parse::<f32>().map_err(|_| format!("Invalid transaciton price 's' given. Must be a decimal number."))
@@ -145,6 +178,10 @@ pub fn geth_ipc_path(testnet: bool) -> String {
 pub fn parity_ipc_path(s: &str) -> String {
 	// Windows path should not be hardcoded here.
 	if cfg!(windows) {
+		// allow choosing a different named pipe, e.g. to run several instances.
+		if s.starts_with(r"\\.\pipe\") {
+			return s.to_owned();
+		}
 		return r"\\.\pipe\parity.jsonrpc".to_owned();
 	}
 
@@ -168,14 +205,15 @@ pub fn to_bootnodes(bootnodes: &Option<String>) -> Result<Vec<String>, String> {
 
 #[cfg(test)]
 pub fn default_network_config() -> ::ethsync::NetworkConfiguration {
-	use ethsync::NetworkConfiguration;
+	use ethsync::{NetworkConfiguration, NatType};
 	NetworkConfiguration {
-		config_path: Some(replace_home("$HOME/.parity/network")),
-		net_config_path: Some(replace_home("$HOME/.parity/network/1")),
+		config_path: Some(replace_home("$HOME/.parity/chains/frontier_homestead-d4e56740f876aef8/network")),
+		net_config_path: Some(replace_home("$HOME/.parity/chains/frontier_homestead-d4e56740f876aef8/network/1")),
 		listen_address: Some("0.0.0.0:30303".into()),
 		public_address: None,
 		udp_port: None,
 		nat_enabled: true,
+		nat_type: NatType::Any,
 		discovery_enabled: true,
 		boot_nodes: Vec::new(),
 		use_secret: None,
@@ -183,6 +221,8 @@ pub fn default_network_config() -> ::ethsync::NetworkConfiguration {
 		min_peers: 25,
 		reserved_nodes: Vec::new(),
 		allow_non_reserved: true,
+		max_download: None,
+		max_upload: None,
 	}
 }
 
@@ -211,6 +251,8 @@ pub fn to_client_config(
 	client_config.db_cache_size = Some(cache_config.db_state_cache_size() as usize);
 	// db queue cache size, in bytes
 	client_config.queue.max_mem_use = cache_config.queue() as usize * mb;
+	// state cache size, in bytes
+	client_config.state_cache_size = Some(cache_config.state() as usize * mb);
 	// in bytes
 	client_config.tracing.max_cache_size = cache_config.traces() as usize * mb;
 	// in bytes
@@ -233,7 +275,7 @@ pub fn execute_upgrades(
 	compaction_profile: CompactionProfile
 ) -> Result<(), String> {
 
-	match upgrade(Some(&dirs.path)) {
+	match upgrade(Some(&dirs.base_path)) {
 		Ok(upgrades_applied) if upgrades_applied > 0 => {
 			debug!("Executed {} upgrade scripts - ok", upgrades_applied);
 		},
@@ -244,7 +286,8 @@ pub fn execute_upgrades(
 	}
 
 	let client_path = dirs.version_path(pruning);
-	migrate(&client_path, pruning, compaction_profile).map_err(|e| format!("{}", e))
+	let ancient_path = dirs.ancient_path.as_ref().map(|path| path.as_path());
+	migrate(&client_path, ancient_path, pruning, compaction_profile).map_err(|e| format!("{}", e))
 }
 
 /// Prompts user asking for password.
@@ -300,8 +343,9 @@ mod tests {
 	use devtools::RandomTempPath;
 	use util::{U256};
 	use ethcore::client::{Mode, BlockID};
-	use ethcore::miner::PendingSet;
-	use super::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_address, to_addresses, to_price, geth_ipc_path, to_bootnodes, password_from_file};
+	use ethcore::miner::{PendingSet, EvictionStrategy};
+	use ethcore::ethstore::KdfParams;
+	use super::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_eviction_strategy, to_address, to_addresses, to_price, to_kdf, geth_ipc_path, to_bootnodes, password_from_file};
 
 	#[test]
 	fn test_to_duration() {
@@ -328,6 +372,8 @@ mod tests {
 		assert_eq!(to_mode("active", 0, 0).unwrap(), Mode::Active);
 		assert_eq!(to_mode("passive", 10, 20).unwrap(), Mode::Passive(Duration::from_secs(10), Duration::from_secs(20)));
 		assert_eq!(to_mode("dark", 20, 30).unwrap(), Mode::Dark(Duration::from_secs(20)));
+		assert_eq!(to_mode("offline", 20, 30).unwrap(), Mode::Off);
+		assert_eq!(to_mode("relay", 20, 30).unwrap(), Mode::Relay);
 		assert!(to_mode("other", 20, 30).is_err());
 	}
 
@@ -359,6 +405,13 @@ mod tests {
 		assert!(to_pending_set("othe").is_err());
 	}
 
+	#[test]
+	fn test_eviction_strategy() {
+		assert_eq!(to_eviction_strategy("gas_price").unwrap(), EvictionStrategy::GasPrice);
+		assert_eq!(to_eviction_strategy("oldest").unwrap(), EvictionStrategy::Oldest);
+		assert!(to_eviction_strategy("newest").is_err());
+	}
+
 	#[test]
 	fn test_to_address() {
 		assert_eq!(
@@ -400,6 +453,16 @@ mod tests {
 		assert_eq!(to_price("2.33").unwrap(), 2.33);
 	}
 
+	#[test]
+	fn test_to_kdf() {
+		assert_eq!(to_kdf("pbkdf2", 10240).unwrap(), KdfParams::Pbkdf2 { iterations: 10240 });
+		assert_eq!(to_kdf("scrypt:262144:8:1", 10240).unwrap(), KdfParams::Scrypt { n: 262144, r: 8, p: 1 });
+		assert!(to_kdf("scrypt:1000:8:1", 10240).is_err());
+		assert!(to_kdf("scrypt:1024:8", 10240).is_err());
+		assert!(to_kdf("pbkdf2:10", 10240).is_err());
+		assert!(to_kdf("bcrypt", 10240).is_err());
+	}
+
 	#[test]
 	#[cfg(windows)]
 	fn test_geth_ipc_path() {
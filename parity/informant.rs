@@ -148,6 +148,8 @@ impl Informant {
 			)
 		);
 
+		debug!(target: "import", "Blockchain cache: {:?}", cache_info);
+
 		*self.chain_info.write() = Some(chain_info);
 		*self.cache_info.write() = Some(cache_info);
 		*write_report = Some(report);
@@ -30,6 +30,7 @@ extern crate ethcore;
 extern crate ethsync;
 extern crate env_logger;
 extern crate ethcore_logger;
+extern crate parity_updater as updater;
 extern crate ctrlc;
 extern crate fdlimit;
 extern crate time;
@@ -41,6 +42,7 @@ extern crate ethcore_ipc as ipc;
 extern crate ethcore_ipc_nano as nanoipc;
 extern crate serde;
 extern crate serde_json;
+extern crate ws;
 extern crate rlp;
 
 extern crate json_ipc_server as jsonipc;
@@ -70,6 +72,9 @@ extern crate ethcore_stratum;
 #[cfg(feature = "dapps")]
 extern crate ethcore_dapps;
 
+#[cfg(feature = "secretstore")]
+extern crate ethcore_secretstore;
+
 macro_rules! dependency {
 	($dep_ty:ident, $url:expr) => {
 		{
@@ -87,6 +92,11 @@ mod upgrade;
 mod rpc;
 mod dapps;
 mod informant;
+mod block_hook;
+mod ethstats;
+mod dev;
+mod consensus_tests;
+mod metrics;
 mod io_handler;
 mod cli;
 mod configuration;
@@ -101,9 +111,15 @@ mod dir;
 mod modules;
 mod account;
 mod blockchain;
+mod geth_chaindata;
 mod presale;
 mod snapshot;
+mod db;
 mod run;
+mod signals;
+mod pending_transactions;
+mod secretstore;
+mod private_tx;
 #[cfg(feature="ipc")]
 mod sync;
 #[cfg(feature="ipc")]
@@ -116,10 +132,14 @@ mod stratum;
 use std::{process, env};
 use std::io::BufReader;
 use std::fs::File;
+use std::path::Path;
 use util::sha3::sha3;
+use ethcore::spec::Spec;
 use cli::Args;
 use configuration::{Cmd, Configuration};
 use deprecated::find_deprecated;
+use run::RunCmd;
+use updater::{UpdateFilter, installed_binary};
 
 fn print_hash_of(maybe_file: Option<String>) -> Result<String, String> {
 	if let Some(file) = maybe_file {
@@ -131,19 +151,74 @@ fn print_hash_of(maybe_file: Option<String>) -> Result<String, String> {
 	}
 }
 
+fn validate_spec(file: String) -> Result<String, String> {
+	let f = try!(File::open(&file).map_err(|e| format!("Unable to open {}: {}", file, e)));
+	let spec = try!(Spec::load_validated(BufReader::new(f)));
+	Ok(format!("Chain specification {} is valid.\nGenesis hash: {}", spec.name, spec.genesis_header().hash().hex()))
+}
+
+/// Replace the current process with `binary`, passing the same arguments.
+/// Only returns if the binary couldn't be started.
+#[cfg(unix)]
+fn exec_binary(binary: &Path) -> Result<i32, String> {
+	use std::os::unix::process::CommandExt;
+	let error = process::Command::new(binary).args(&env::args().skip(1).collect::<Vec<_>>()).exec();
+	Err(format!("{}", error))
+}
+
+/// Run `binary` with the same arguments and return its exit code.
+/// Processes can't be replaced on this platform, so the current one waits for it.
+#[cfg(not(unix))]
+fn exec_binary(binary: &Path) -> Result<i32, String> {
+	process::Command::new(binary).args(&env::args().skip(1).collect::<Vec<_>>()).status()
+		.map(|status| status.code().unwrap_or(1))
+		.map_err(|e| format!("{}", e))
+}
+
+/// Run the newer binary installed by the updater in place of this one and return its exit code.
+/// Returns `None` if the current binary should keep running.
+fn run_installed_binary(cmd: &RunCmd) -> Option<i32> {
+	let binary = match cmd.updater_conf {
+		Some(ref conf) if conf.filter != UpdateFilter::None => match installed_binary(&conf.updates_path, &conf.current_version) {
+			Ok(Some(binary)) => binary,
+			Ok(None) => return None,
+			Err(e) => {
+				println!("Not running the updated binary: {}. Falling back to the current version.", e);
+				return None;
+			},
+		},
+		_ => return None,
+	};
+
+	println!("Running updated binary {}", binary.display());
+	match exec_binary(&binary) {
+		Ok(code) => Some(code),
+		Err(e) => {
+			println!("Error running {}: {}. Falling back to the current version.", binary.display(), e);
+			None
+		},
+	}
+}
+
 fn execute(command: Cmd) -> Result<String, String> {
 	match command {
 		Cmd::Run(run_cmd) => {
+			if let Some(code) = run_installed_binary(&run_cmd) {
+				process::exit(code);
+			}
 			try!(run::execute(run_cmd));
 			Ok("".into())
 		},
 		Cmd::Version => Ok(Args::print_version()),
 		Cmd::Hash(maybe_file) => print_hash_of(maybe_file),
+		Cmd::ValidateSpec(file) => validate_spec(file),
 		Cmd::Account(account_cmd) => account::execute(account_cmd),
 		Cmd::ImportPresaleWallet(presale_cmd) => presale::execute(presale_cmd),
 		Cmd::Blockchain(blockchain_cmd) => blockchain::execute(blockchain_cmd),
 		Cmd::SignerToken(path) => signer::new_token(path),
 		Cmd::Snapshot(snapshot_cmd) => snapshot::execute(snapshot_cmd),
+		Cmd::Db(db_cmd) => db::execute(db_cmd),
+		Cmd::ConsensusTests(tests_cmd) => consensus_tests::execute(tests_cmd),
 	}
 }
 
@@ -0,0 +1,125 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! HTTP endpoint exposing node metrics to Prometheus.
+
+use std::sync::Arc;
+use hyper::server::{Server, Handler, Request, Response, Listening};
+use hyper::status::StatusCode;
+use hyper::uri::RequestUri;
+use ethcore::client::{Client, BlockChainClient};
+use ethcore::miner::{Miner, MinerService};
+use ethsync::SyncProvider;
+use util::metrics;
+
+#[derive(Debug, PartialEq, Clone)]
+/// Metrics server configuration
+pub struct Configuration {
+	/// Is the metrics server enabled?
+	pub enabled: bool,
+	/// Interface to listen on.
+	pub interface: String,
+	/// Port to listen on.
+	pub port: u16,
+}
+
+impl Default for Configuration {
+	fn default() -> Self {
+		Configuration {
+			enabled: false,
+			interface: "127.0.0.1".into(),
+			port: 9100,
+		}
+	}
+}
+
+/// Metrics server dependencies
+pub struct Dependencies {
+	/// Blockchain client.
+	pub client: Arc<Client>,
+	/// Sync status provider.
+	pub sync: Arc<SyncProvider>,
+	/// Miner, for the transaction queue status.
+	pub miner: Arc<Miner>,
+}
+
+struct MetricsHandler {
+	deps: Dependencies,
+}
+
+impl MetricsHandler {
+	/// Sample the gauges which are read from the node state rather than updated as events happen.
+	fn update_gauges(&self) {
+		let registry = metrics::registry();
+		let chain_info = self.deps.client.chain_info();
+		let queue_info = self.deps.client.queue_info();
+		let sync_status = self.deps.sync.status();
+		let miner_status = self.deps.miner.status();
+
+		registry.gauge("parity_chain_best_block", "Number of the best block.")
+			.set(chain_info.best_block_number as usize);
+		registry.gauge("parity_block_queue_unverified", "Blocks waiting for verification.")
+			.set(queue_info.unverified_queue_size);
+		registry.gauge("parity_block_queue_verifying", "Blocks being verified.")
+			.set(queue_info.verifying_queue_size);
+		registry.gauge("parity_block_queue_verified", "Verified blocks waiting for import.")
+			.set(queue_info.verified_queue_size);
+		registry.gauge("parity_block_queue_memory_bytes", "Memory used by the block queue.")
+			.set(queue_info.mem_used);
+		registry.gauge("parity_transaction_queue_pending", "Transactions ready to be included in a block.")
+			.set(miner_status.transactions_in_pending_queue);
+		registry.gauge("parity_transaction_queue_future", "Transactions waiting for a nonce gap to be filled.")
+			.set(miner_status.transactions_in_future_queue);
+		registry.gauge("parity_blockchain_cache_bytes", "Memory used by the blockchain caches.")
+			.set(self.deps.client.blockchain_cache_info().total());
+		registry.gauge("parity_peers_connected", "Number of connected peers.")
+			.set(sync_status.num_peers);
+		registry.gauge("parity_peers_active", "Number of peers sync is downloading from.")
+			.set(sync_status.num_active_peers);
+	}
+}
+
+impl Handler for MetricsHandler {
+	fn handle(&self, req: Request, mut res: Response) {
+		let is_metrics = match req.uri {
+			RequestUri::AbsolutePath(ref path) => path == "/metrics",
+			_ => false,
+		};
+		if !is_metrics {
+			*res.status_mut() = StatusCode::NotFound;
+			return;
+		}
+
+		self.update_gauges();
+		res.headers_mut().set_raw("Content-Type", vec![b"text/plain; version=0.0.4".to_vec()]);
+		if let Err(e) = res.send(metrics::registry().render().as_bytes()) {
+			debug!(target: "metrics", "Error sending metrics: {}", e);
+		}
+	}
+}
+
+/// Start the metrics server. The server runs until the returned handle is closed.
+pub fn start(conf: Configuration, deps: Dependencies) -> Result<Option<Listening>, String> {
+	if !conf.enabled {
+		return Ok(None);
+	}
+
+	let addr = format!("{}:{}", conf.interface, conf.port);
+	let server = try!(Server::http(&*addr).map_err(|e| format!("Invalid metrics listen address {}: {}", addr, e)));
+	server.handle(MetricsHandler { deps: deps })
+		.map(Some)
+		.map_err(|e| format!("Metrics server error: {}", e))
+}
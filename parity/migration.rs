@@ -21,8 +21,8 @@ use std::path::{Path, PathBuf};
 use std::fmt::{Display, Formatter, Error as FmtError};
 use std::sync::Arc;
 use util::journaldb::Algorithm;
-use util::migration::{Manager as MigrationManager, Config as MigrationConfig, Error as MigrationError, Migration};
-use util::kvdb::{CompactionProfile, Database, DatabaseConfig};
+use util::migration::{Manager as MigrationManager, Config as MigrationConfig, Error as MigrationError, Migration, read_version};
+use util::kvdb::{CompactionProfile, Database, DatabaseConfig, DEFAULT_WAL_SIZE_LIMIT};
 use ethcore::migrations;
 use ethcore::db;
 use ethcore::migrations::Extract;
@@ -30,7 +30,7 @@ use ethcore::migrations::Extract;
 /// Database is assumed to be at default version, when no version file is found.
 const DEFAULT_VERSION: u32 = 5;
 /// Current version of database models.
-const CURRENT_VERSION: u32 = 10;
+const CURRENT_VERSION: u32 = 11;
 /// First version of the consolidated database.
 const CONSOLIDATION_VERSION: u32 = 9;
 /// Defines how many items are migrated to the new version of database at once.
@@ -146,6 +146,15 @@ pub fn default_migration_settings(compaction_profile: &CompactionProfile) -> Mig
 fn consolidated_database_migrations(compaction_profile: &CompactionProfile) -> Result<MigrationManager, Error> {
 	let mut manager = MigrationManager::new(default_migration_settings(compaction_profile));
 	try!(manager.add_migration(migrations::ToV10::new()).map_err(|_| Error::MigrationImpossible));
+	try!(manager.add_migration(migrations::TO_V11).map_err(|_| Error::MigrationImpossible));
+	Ok(manager)
+}
+
+/// Migrations on the ancient blocks database. It only holds bodies and receipts,
+/// so it just needs the same columns as the consolidated database.
+fn ancient_database_migrations(compaction_profile: &CompactionProfile) -> Result<MigrationManager, Error> {
+	let mut manager = MigrationManager::new(default_migration_settings(compaction_profile));
+	try!(manager.add_migration(migrations::TO_V11).map_err(|_| Error::MigrationImpossible));
 	Ok(manager)
 }
 
@@ -169,6 +178,8 @@ fn consolidate_database(
 		compaction: config.compaction_profile,
 		columns: None,
 		wal: true,
+		wal_size_limit: DEFAULT_WAL_SIZE_LIMIT,
+		write_buffer_size: None,
 	};
 
 	let old_path_str = try!(old_db_path.to_str().ok_or(Error::MigrationImpossible));
@@ -187,7 +198,20 @@ fn consolidate_database(
 
 
 /// Migrates database at given position with given migration rules.
+/// Version recorded in the database by the migrations already applied to it, if any.
+/// It is ahead of the version file if a migration completed but the file was not updated.
+fn recorded_version(db_path: &Path) -> Result<Option<u32>, Error> {
+	let db_path = try!(db_path.to_str().ok_or(Error::MigrationImpossible));
+	let db = try!(Database::open_read_only(&DatabaseConfig::default(), db_path).map_err(MigrationError::Custom));
+	read_version(&db).map_err(Into::into)
+}
+
 fn migrate_database(version: u32, db_path: PathBuf, mut migrations: MigrationManager) -> Result<(), Error> {
+	let version = match try!(recorded_version(&db_path)) {
+		Some(recorded) => ::std::cmp::max(version, recorded),
+		None => version,
+	};
+
 	// check if migration is needed
 	if !migrations.is_needed(version) {
 		return Ok(())
@@ -200,6 +224,11 @@ fn migrate_database(version: u32, db_path: PathBuf, mut migrations: MigrationMan
 	// migrate old database to the new one
 	let temp_path = try!(migrations.execute(&db_path, version));
 
+	// migrations performed in place leave the database where it was.
+	if temp_path == db_path {
+		return Ok(());
+	}
+
 	// create backup
 	try!(fs::rename(&db_path, &backup_path));
 
@@ -221,7 +250,7 @@ fn exists(path: &Path) -> bool {
 }
 
 /// Migrates the database.
-pub fn migrate(path: &Path, pruning: Algorithm, compaction_profile: CompactionProfile) -> Result<(), Error> {
+pub fn migrate(path: &Path, ancient_path: Option<&Path>, pruning: Algorithm, compaction_profile: CompactionProfile) -> Result<(), Error> {
 	// read version file.
 	let version = try!(current_version(path));
 
@@ -264,6 +293,15 @@ pub fn migrate(path: &Path, pruning: Algorithm, compaction_profile: CompactionPr
 		println!("Migration finished");
 	}
 
+	// The ancient blocks database is versioned together with the client database.
+	if let Some(ancient_path) = ancient_path {
+		if version >= CONSOLIDATION_VERSION && version < CURRENT_VERSION && exists(ancient_path) {
+			println!("Migrating ancient blocks database to version {}", CURRENT_VERSION);
+			try!(migrate_database(version, ancient_path.to_owned(), try!(ancient_database_migrations(&compaction_profile))));
+			println!("Migration finished");
+		}
+	}
+
 	// update version file.
 	update_version(path)
 }
@@ -14,9 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::thread;
 use std::sync::Arc;
-use ethcore::client::BlockChainClient;
-use hypervisor::Hypervisor;
+use std::sync::atomic::{AtomicBool, Ordering};
+use ethcore::client::{BlockChainClient, ChainNotify};
+use hypervisor::{Hypervisor, SYNC_MODULE_ID};
 use ethsync::{SyncConfig, NetworkConfiguration, NetworkError};
 use ethcore::snapshot::SnapshotService;
 #[cfg(not(feature="ipc"))]
@@ -53,7 +55,6 @@ pub mod service_urls {
 #[cfg(not(feature="ipc"))]
 mod no_ipc_deps {
 	pub use ethsync::{EthSync, SyncProvider, ManageNetwork};
-	pub use ethcore::client::ChainNotify;
 }
 
 #[cfg(feature="ipc")]
@@ -71,7 +72,7 @@ mod ipc_deps {
 	pub use ethsync::remote::{SyncClient, NetworkManagerClient};
 	pub use ethsync::ServiceConfiguration;
 	pub use ethcore::client::remote::ChainNotifyClient;
-	pub use hypervisor::{SYNC_MODULE_ID, BootArgs, HYPERVISOR_IPC_URL};
+	pub use hypervisor::{BootArgs, HYPERVISOR_IPC_URL};
 	pub use nanoipc::{GuardedSocket, NanoSocket, generic_client, fast_client};
 	pub use ipc::IpcSocket;
 	pub use ipc::binary::serialize;
@@ -161,3 +162,44 @@ pub fn sync
 	let eth_sync = try!(EthSync::new(sync_cfg, client, snapshot_service, net_cfg));
 	Ok((eth_sync.clone() as Arc<SyncProvider>, eth_sync.clone() as Arc<ManageNetwork>, eth_sync.clone() as Arc<ChainNotify>))
 }
+
+/// Restarts crashed module processes until dropped. Dropping it shuts the modules down.
+pub struct Supervisor {
+	stop: Arc<AtomicBool>,
+	thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for Supervisor {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::SeqCst);
+		if let Some(thread) = self.thread.take() {
+			if thread.join().is_err() {
+				warn!("Hypervisor thread panicked");
+			}
+		}
+	}
+}
+
+/// Supervise the module processes started by the hypervisor.
+/// The network is started again in a restarted sync process once it checks in.
+pub fn supervise(hypervisor: Option<Hypervisor>, chain_notify: Arc<ChainNotify>, enable_network: bool) -> Supervisor {
+	let stop = Arc::new(AtomicBool::new(false));
+	let thread_stop = stop.clone();
+	let thread = hypervisor.map(|hypervisor| thread::Builder::new().name("hypervisor".into()).spawn(move || {
+		let mut sync_restarting = false;
+		while !thread_stop.load(Ordering::SeqCst) {
+			sync_restarting |= hypervisor.supervise().contains(&SYNC_MODULE_ID);
+			if sync_restarting && hypervisor.modules_ready() {
+				sync_restarting = false;
+				if enable_network {
+					chain_notify.start();
+				}
+			}
+		}
+	}).expect("Error starting hypervisor thread"));
+
+	Supervisor {
+		stop: stop,
+		thread: thread,
+	}
+}
@@ -14,22 +14,27 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{str, fs};
+use std::str;
 use std::time::Duration;
-use util::{Address, U256, version_data};
+use util::{Address, U256, H256, version_data};
 use util::journaldb::Algorithm;
 use ethcore::spec::Spec;
+use ethcore::header::BlockNumber;
 use ethcore::ethereum;
 use ethcore::miner::{GasPricer, GasPriceCalibratorOptions};
+use ethcore::ethstore::KdfParams;
 use user_defaults::UserDefaults;
+use dev;
 
 #[derive(Debug, PartialEq)]
 pub enum SpecType {
 	Mainnet,
 	Testnet,
+	Ropsten,
 	Olympic,
 	Classic,
 	Expanse,
+	Dev(dev::Configuration),
 	Custom(String),
 }
 
@@ -47,8 +52,10 @@ impl str::FromStr for SpecType {
 			"frontier" | "homestead" | "mainnet" => SpecType::Mainnet,
 			"frontier-dogmatic" | "homestead-dogmatic" | "classic" => SpecType::Classic,
 			"morden" | "testnet" => SpecType::Testnet,
+			"ropsten" => SpecType::Ropsten,
 			"olympic" => SpecType::Olympic,
 			"expanse" => SpecType::Expanse,
+			"dev" => SpecType::Dev(Default::default()),
 			other => SpecType::Custom(other.into()),
 		};
 		Ok(spec)
@@ -60,13 +67,12 @@ impl SpecType {
 		match *self {
 			SpecType::Mainnet => Ok(ethereum::new_frontier()),
 			SpecType::Testnet => Ok(ethereum::new_morden()),
+			SpecType::Ropsten => Ok(ethereum::new_ropsten()),
 			SpecType::Olympic => Ok(ethereum::new_olympic()),
 			SpecType::Classic => Ok(ethereum::new_classic()),
 			SpecType::Expanse => Ok(ethereum::new_expanse()),
-			SpecType::Custom(ref filename) => {
-				let file = try!(fs::File::open(filename).map_err(|_| "Could not load specification file."));
-				Spec::load(file)
-			}
+			SpecType::Dev(ref conf) => Ok(dev::spec(conf)),
+			SpecType::Custom(ref name_or_path) => Spec::load_named(name_or_path),
 		}
 	}
 }
@@ -95,12 +101,29 @@ impl str::FromStr for Pruning {
 }
 
 impl Pruning {
-	pub fn to_algorithm(&self, user_defaults: &UserDefaults) -> Algorithm {
+	/// Resolves the pruning algorithm given the user defaults and the algorithms
+	/// of the client databases which already exist.
+	pub fn to_algorithm(&self, user_defaults: &UserDefaults, existing: &[Algorithm]) -> Result<Algorithm, String> {
 		match *self {
-			Pruning::Specific(algo) => algo,
-			Pruning::Auto => user_defaults.pruning,
+			Pruning::Specific(algo) => {
+				if !existing.is_empty() && !existing.contains(&algo) {
+					warn!("No {} database found, syncing from scratch. Existing databases: {}", algo.as_str(), Self::names(existing));
+				}
+				Ok(algo)
+			},
+			Pruning::Auto if existing.is_empty() => Ok(user_defaults.pruning),
+			Pruning::Auto if !user_defaults.is_first_launch && existing.contains(&user_defaults.pruning) => Ok(user_defaults.pruning),
+			Pruning::Auto if existing.len() == 1 => {
+				info!("Detected {} pruning from the existing database", existing[0].as_str());
+				Ok(existing[0])
+			},
+			Pruning::Auto => Err(format!("Found databases for several pruning methods ({}). Select one with --pruning.", Self::names(existing))),
 		}
 	}
+
+	fn names(algorithms: &[Algorithm]) -> String {
+		algorithms.iter().map(Algorithm::as_str).collect::<Vec<_>>().join(", ")
+	}
 }
 
 #[derive(Debug, PartialEq)]
@@ -139,21 +162,107 @@ impl str::FromStr for ResealPolicy {
 	}
 }
 
+/// How accounts given with `--unlock` are unlocked.
+#[derive(Debug, PartialEq, Clone)]
+pub enum UnlockMode {
+	/// Unlocked until the node stops.
+	Permanent,
+	/// Unlocked for given number of seconds, locked again afterwards.
+	Timed(u32),
+	/// Locked again after the first signature.
+	Single,
+}
+
+impl Default for UnlockMode {
+	fn default() -> Self {
+		UnlockMode::Permanent
+	}
+}
+
+impl str::FromStr for UnlockMode {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"permanent" => Ok(UnlockMode::Permanent),
+			"single" => Ok(UnlockMode::Single),
+			other if other.starts_with("timed:") => match other[6..].parse::<u32>() {
+				// unlock duration is kept in milliseconds
+				Ok(secs) if secs > 0 && secs <= u32::max_value() / 1000 => Ok(UnlockMode::Timed(secs)),
+				_ => Err(format!("Invalid unlock duration: {}", &other[6..])),
+			},
+			other => Err(format!("Invalid unlock mode: {}", other)),
+		}
+	}
+}
+
+/// Which block peers are checked against to make sure they are on the same side of a fork.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ForkCheck {
+	/// Fork block and hash of the chain specification.
+	Spec,
+	/// Peers are not checked.
+	Off,
+	/// Peers must have the given block hash at the given block number.
+	Block(BlockNumber, H256),
+}
+
+impl Default for ForkCheck {
+	fn default() -> Self {
+		ForkCheck::Spec
+	}
+}
+
+impl str::FromStr for ForkCheck {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"spec" => Ok(ForkCheck::Spec),
+			"none" => Ok(ForkCheck::Off),
+			other => {
+				let mut parts = other.splitn(2, ':');
+				let number = parts.next().and_then(|n| n.parse::<BlockNumber>().ok());
+				let hash = parts.next().and_then(|h| h.trim_left_matches("0x").parse::<H256>().ok());
+				match (number, hash) {
+					(Some(number), Some(hash)) => Ok(ForkCheck::Block(number, hash)),
+					_ => Err(format!("Invalid fork block: {}", other)),
+				}
+			},
+		}
+	}
+}
+
+impl ForkCheck {
+	/// Returns the fork block peers are checked against, if any.
+	pub fn fork_block(&self, spec: &Spec) -> Option<(BlockNumber, H256)> {
+		match *self {
+			ForkCheck::Spec => spec.fork_block(),
+			ForkCheck::Off => None,
+			ForkCheck::Block(number, hash) => Some((number, hash)),
+		}
+	}
+}
+
 #[derive(Debug, PartialEq)]
 pub struct AccountsConfig {
-	pub iterations: u32,
+	pub kdf: KdfParams,
 	pub testnet: bool,
 	pub password_files: Vec<String>,
 	pub unlocked_accounts: Vec<Address>,
+	pub unlock_mode: UnlockMode,
+	pub enable_hardware_wallets: bool,
 }
 
 impl Default for AccountsConfig {
 	fn default() -> Self {
 		AccountsConfig {
-			iterations: 10240,
+			kdf: KdfParams::Pbkdf2 { iterations: 10240 },
 			testnet: false,
 			password_files: Vec::new(),
 			unlocked_accounts: Vec::new(),
+			unlock_mode: UnlockMode::default(),
+			enable_hardware_wallets: false,
 		}
 	}
 }
@@ -268,7 +377,8 @@ pub fn fatdb_switch_to_bool(switch: Switch, user_defaults: &UserDefaults, algori
 mod tests {
 	use util::journaldb::Algorithm;
 	use user_defaults::UserDefaults;
-	use super::{SpecType, Pruning, ResealPolicy, Switch, tracing_switch_to_bool};
+	use util::H256;
+	use super::{SpecType, Pruning, ResealPolicy, Switch, UnlockMode, ForkCheck, tracing_switch_to_bool};
 
 	#[test]
 	fn test_spec_type_parsing() {
@@ -277,7 +387,9 @@ mod tests {
 		assert_eq!(SpecType::Mainnet, "mainnet".parse().unwrap());
 		assert_eq!(SpecType::Testnet, "testnet".parse().unwrap());
 		assert_eq!(SpecType::Testnet, "morden".parse().unwrap());
+		assert_eq!(SpecType::Ropsten, "ropsten".parse().unwrap());
 		assert_eq!(SpecType::Olympic, "olympic".parse().unwrap());
+		assert_eq!(SpecType::Dev(Default::default()), "dev".parse().unwrap());
 	}
 
 	#[test]
@@ -292,6 +404,33 @@ mod tests {
 		assert_eq!(Pruning::Specific(Algorithm::EarlyMerge), "light".parse().unwrap());
 		assert_eq!(Pruning::Specific(Algorithm::OverlayRecent), "fast".parse().unwrap());
 		assert_eq!(Pruning::Specific(Algorithm::RefCounted), "basic".parse().unwrap());
+		assert_eq!(Pruning::Specific(Algorithm::RefCounted), "refcounted".parse().unwrap());
+	}
+
+	#[test]
+	fn test_unlock_mode_parsing() {
+		assert_eq!(UnlockMode::Permanent, "permanent".parse().unwrap());
+		assert_eq!(UnlockMode::Single, "single".parse().unwrap());
+		assert_eq!(UnlockMode::Timed(300), "timed:300".parse().unwrap());
+		assert!("timed:0".parse::<UnlockMode>().is_err());
+		assert!("timed:5000000".parse::<UnlockMode>().is_err());
+		assert!("forever".parse::<UnlockMode>().is_err());
+	}
+
+	#[test]
+	fn test_pruning_auto_detection() {
+		let first_launch = UserDefaults::default();
+		let mut defaults = UserDefaults::default();
+		defaults.is_first_launch = false;
+		defaults.pruning = Algorithm::Archive;
+
+		assert_eq!(Pruning::Auto.to_algorithm(&first_launch, &[]), Ok(Algorithm::OverlayRecent));
+		assert_eq!(Pruning::Auto.to_algorithm(&defaults, &[]), Ok(Algorithm::Archive));
+		assert_eq!(Pruning::Auto.to_algorithm(&first_launch, &[Algorithm::Archive]), Ok(Algorithm::Archive));
+		assert_eq!(Pruning::Auto.to_algorithm(&defaults, &[Algorithm::Archive, Algorithm::OverlayRecent]), Ok(Algorithm::Archive));
+		assert_eq!(Pruning::Auto.to_algorithm(&defaults, &[Algorithm::EarlyMerge]), Ok(Algorithm::EarlyMerge));
+		assert!(Pruning::Auto.to_algorithm(&first_launch, &[Algorithm::Archive, Algorithm::OverlayRecent]).is_err());
+		assert_eq!(Pruning::Specific(Algorithm::RefCounted).to_algorithm(&defaults, &[Algorithm::Archive]), Ok(Algorithm::RefCounted));
 	}
 
 	#[test]
@@ -317,6 +456,28 @@ mod tests {
 		assert_eq!(all, ResealPolicy::default());
 	}
 
+	#[test]
+	fn test_fork_check_parsing() {
+		assert_eq!(ForkCheck::Spec, "spec".parse().unwrap());
+		assert_eq!(ForkCheck::Off, "none".parse().unwrap());
+		let hash = "94365e3a8c0b35089c1d1195081fe7489b528a84b22199c916180db8b28ade7f";
+		let expected = ForkCheck::Block(1920000, hash.parse::<H256>().unwrap());
+		assert_eq!(expected, format!("1920000:0x{}", hash).parse().unwrap());
+		assert_eq!(expected, format!("1920000:{}", hash).parse().unwrap());
+		assert!("1920000".parse::<ForkCheck>().is_err());
+		assert!(format!("0x1d4c00:{}", hash).parse::<ForkCheck>().is_err());
+		assert!("1920000:0x1234".parse::<ForkCheck>().is_err());
+	}
+
+	#[test]
+	fn test_fork_check_block() {
+		let spec = SpecType::Classic.spec().unwrap();
+		assert_eq!(ForkCheck::Spec.fork_block(&spec), spec.fork_block());
+		assert!(ForkCheck::Spec.fork_block(&spec).is_some());
+		assert_eq!(ForkCheck::Off.fork_block(&spec), None);
+		assert_eq!(ForkCheck::Block(10, H256::from(1)).fork_block(&spec), Some((10, H256::from(1))));
+	}
+
 	#[test]
 	fn test_switch_parsing() {
 		assert_eq!(Switch::On, "on".parse().unwrap());
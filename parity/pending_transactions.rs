@@ -0,0 +1,121 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Keeping the transaction queue between restarts.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use ethcore::transaction::SignedTransaction;
+use rlp::{self, UntrustedRlp, View};
+
+fn temp_path(path: &Path) -> PathBuf {
+	let mut temp = path.to_path_buf().into_os_string();
+	temp.push(".tmp");
+	temp.into()
+}
+
+/// Write `transactions` to `path`. The file is replaced atomically,
+/// so a crash while saving leaves the previous file intact.
+pub fn save(path: &Path, transactions: &[SignedTransaction]) -> Result<(), String> {
+	let temp = temp_path(path);
+	let write = || -> ::std::io::Result<()> {
+		let mut file = try!(fs::File::create(&temp));
+		try!(file.write_all(&rlp::encode(&transactions.to_vec())));
+		try!(file.sync_all());
+		fs::rename(&temp, path)
+	};
+
+	write().map_err(|e| {
+		let _ = fs::remove_file(&temp);
+		format!("{}: {}", path.display(), e)
+	})
+}
+
+/// Read and remove the transactions saved at `path`, the file is only valid for a single restart.
+/// Returns no transactions if there is no file or it is invalid.
+pub fn restore(path: &Path) -> Vec<SignedTransaction> {
+	let mut bytes = Vec::new();
+	match fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)) {
+		Ok(_) => {},
+		Err(_) => return Vec::new(),
+	}
+	let _ = fs::remove_file(path);
+
+	match UntrustedRlp::new(&bytes).as_val() {
+		Ok(transactions) => transactions,
+		Err(e) => {
+			warn!("Ignoring invalid pending transactions file {}: {:?}", path.display(), e);
+			Vec::new()
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::io::Write;
+	use devtools::RandomTempPath;
+	use ethcore::ethstore::ethkey::{Brain, Generator};
+	use ethcore::transaction::{Transaction, Action, SignedTransaction};
+	use util::U256;
+	use super::{save, restore, temp_path};
+
+	fn transaction(nonce: u64) -> SignedTransaction {
+		let keypair = Brain::new("pending".into()).generate().unwrap();
+		Transaction {
+			nonce: nonce.into(),
+			gas_price: U256::from(20_000_000_000u64),
+			gas: U256::from(21_000),
+			action: Action::Call(keypair.address()),
+			value: U256::from(1),
+			data: Vec::new(),
+		}.sign(keypair.secret())
+	}
+
+	#[test]
+	fn should_restore_saved_transactions_once() {
+		let temp = RandomTempPath::create_dir();
+		let path = temp.as_path().join("pending_transactions.rlp");
+		let transactions = vec![transaction(0), transaction(1)];
+
+		save(&path, &transactions).unwrap();
+		assert!(!temp_path(&path).exists());
+		assert_eq!(restore(&path), transactions);
+		assert!(!path.exists());
+		assert!(restore(&path).is_empty());
+	}
+
+	#[test]
+	fn should_replace_previous_file() {
+		let temp = RandomTempPath::create_dir();
+		let path = temp.as_path().join("pending_transactions.rlp");
+
+		save(&path, &[transaction(0), transaction(1)]).unwrap();
+		save(&path, &[transaction(2)]).unwrap();
+		assert_eq!(restore(&path), vec![transaction(2)]);
+	}
+
+	#[test]
+	fn should_ignore_invalid_file() {
+		let temp = RandomTempPath::create_dir();
+		let path = temp.as_path().join("pending_transactions.rlp");
+		fs::File::create(&path).unwrap().write_all(b"garbage").unwrap();
+
+		assert!(restore(&path).is_empty());
+		assert!(!path.exists());
+	}
+}
@@ -14,14 +14,14 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethcore::ethstore::{PresaleWallet, EthStore};
+use ethcore::ethstore::{PresaleWallet, EthStore, KdfParams};
 use ethcore::ethstore::dir::DiskDirectory;
 use ethcore::account_provider::AccountProvider;
 use helpers::{password_prompt, password_from_file};
 
 #[derive(Debug, PartialEq)]
 pub struct ImportWallet {
-	pub iterations: u32,
+	pub kdf: KdfParams,
 	pub path: String,
 	pub wallet_path: String,
 	pub password_file: Option<String>,
@@ -34,7 +34,7 @@ pub fn execute(cmd: ImportWallet) -> Result<String, String> {
 	};
 
 	let dir = Box::new(DiskDirectory::create(cmd.path).unwrap());
-	let secret_store = Box::new(EthStore::open_with_iterations(dir, cmd.iterations).unwrap());
+	let secret_store = Box::new(EthStore::open_with_kdf(dir, cmd.kdf).unwrap());
 	let acc_provider = AccountProvider::new(secret_store);
 	let wallet = try!(PresaleWallet::open(cmd.wallet_path).map_err(|_| "Unable to open presale wallet."));
 	let kp = try!(wallet.decrypt(&password).map_err(|_| "Invalid password."));
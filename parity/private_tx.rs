@@ -0,0 +1,166 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Private transactions setup. Contract keys are requested from the Secret Store over HTTP.
+
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::time::Duration;
+use hyper::{self, Next, Url};
+use hyper::client::{Client, Handler, Request, Response};
+use hyper::net::HttpStream;
+use rustc_serialize::hex::FromHex;
+use ethcore::account_provider::AccountProvider;
+use ethcore::client::Client as EthClient;
+use ethcore::miner::Miner;
+use ethcore::private_transactions::{Provider, ProviderConfig, AesEncryptor};
+use util::{Address, Bytes, Hashable};
+
+/// Timeout of Secret Store requests.
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// Private transactions configuration.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Configuration {
+	/// Are private transactions enabled?
+	pub enabled: bool,
+	/// Account signing the public transactions which commit new states.
+	pub signer_account: Option<Address>,
+	/// Local accounts validating private contracts.
+	pub validator_accounts: Vec<Address>,
+	/// Account requesting contract keys from the Secret Store. Defaults to the signer account.
+	pub key_account: Option<Address>,
+	/// URL of the Secret Store HTTP interface.
+	pub sstore_url: Option<String>,
+}
+
+/// Create the private transactions provider and register it with the client.
+pub fn start(conf: Configuration, client: Arc<EthClient>, miner: Arc<Miner>, accounts: Arc<AccountProvider>) -> Result<Option<Arc<Provider>>, String> {
+	if !conf.enabled {
+		return Ok(None);
+	}
+
+	let url = try!(conf.sstore_url.ok_or_else(|| "Private transactions require --private-sstore-url.".to_owned()));
+	let key_account = try!(conf.key_account.or(conf.signer_account)
+		.ok_or_else(|| "Private transactions require --private-account or --private-signer.".to_owned()));
+	let keys = KeyFetcher {
+		url: url,
+		account: key_account,
+		accounts: accounts.clone(),
+	};
+	let encryptor = AesEncryptor::new(move |contract: &Address| keys.document_key(contract));
+
+	let config = ProviderConfig {
+		validator_accounts: conf.validator_accounts,
+		signer_account: conf.signer_account,
+	};
+	let provider = Arc::new(Provider::new(config, Box::new(encryptor), client, miner, accounts));
+	try!(Provider::start(&provider));
+	Ok(Some(provider))
+}
+
+/// Requests the document keys of private contracts from the Secret Store.
+struct KeyFetcher {
+	url: String,
+	account: Address,
+	accounts: Arc<AccountProvider>,
+}
+
+impl KeyFetcher {
+	/// Fetch and decrypt the key of the document identified by the hash of `contract`.
+	fn document_key(&self, contract: &Address) -> Result<Bytes, String> {
+		let document = contract.sha3();
+		let signature = try!(self.accounts.sign(self.account, document.clone())
+			.map_err(|e| format!("Error signing key request: {}", e)));
+		let url = format!("{}/{:?}/{}", self.url.trim_right_matches('/'), document, signature);
+		let url = try!(Url::parse(&url).map_err(|e| format!("Invalid Secret Store URL {}: {}", url, e)));
+
+		let body = try!(get(url));
+		let encrypted = try!(body.trim().trim_matches('"').trim_left_matches("0x").from_hex()
+			.map_err(|e| format!("Invalid Secret Store response: {}", e)));
+		self.accounts.decrypt(self.account, &[], &encrypted)
+			.map_err(|e| format!("Error decrypting document key: {}", e))
+	}
+}
+
+/// Send a GET request to `url`, returning the body of a successful response.
+fn get(url: Url) -> Result<String, String> {
+	let client = try!(Client::new().map_err(|e| format!("Error creating HTTP client: {}", e)));
+	let (tx, rx) = mpsc::channel();
+	let result = client.request(url, GetHandler { body: Vec::new(), result: Some(tx) })
+		.map_err(|e| format!("Error requesting document key: {:?}", e))
+		.and_then(|_| rx.recv().unwrap_or_else(|_| Err("Secret Store request aborted".into())));
+	client.close();
+	result
+}
+
+struct GetHandler {
+	body: Vec<u8>,
+	result: Option<mpsc::Sender<Result<String, String>>>,
+}
+
+impl GetHandler {
+	fn finish(&mut self, result: Result<String, String>) -> Next {
+		if let Some(tx) = self.result.take() {
+			let _ = tx.send(result);
+		}
+		Next::end()
+	}
+
+	fn wait() -> Next {
+		Next::read().timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+	}
+}
+
+impl Drop for GetHandler {
+	fn drop(&mut self) {
+		self.finish(Err("Secret Store request aborted".into()));
+	}
+}
+
+impl Handler<HttpStream> for GetHandler {
+	fn on_request(&mut self, _: &mut Request) -> Next { Self::wait() }
+	fn on_request_writable(&mut self, _: &mut hyper::Encoder<HttpStream>) -> Next { Self::wait() }
+
+	fn on_response(&mut self, response: Response) -> Next {
+		match response.status().is_success() {
+			true => Self::wait(),
+			false => self.finish(Err(format!("Secret Store responded with {}", response.status()))),
+		}
+	}
+
+	fn on_response_readable(&mut self, decoder: &mut hyper::Decoder<HttpStream>) -> Next {
+		let mut buf = [0u8; 1024];
+		match decoder.read(&mut buf) {
+			Ok(0) => {
+				let body = String::from_utf8(::std::mem::replace(&mut self.body, Vec::new()))
+					.map_err(|_| "Secret Store response is not valid UTF-8".to_owned());
+				self.finish(body)
+			},
+			Ok(read) => {
+				self.body.extend_from_slice(&buf[..read]);
+				Self::wait()
+			},
+			Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Self::wait(),
+			Err(e) => self.finish(Err(format!("Error reading Secret Store response: {}", e))),
+		}
+	}
+
+	fn on_error(&mut self, err: hyper::Error) -> Next {
+		self.finish(Err(format!("Secret Store request failed: {}", err)))
+	}
+}
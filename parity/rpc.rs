@@ -18,7 +18,7 @@ use std::fmt;
 use std::sync::Arc;
 use std::net::SocketAddr;
 use io::PanicHandler;
-use ethcore_rpc::{RpcServerError, RpcServer as Server};
+use ethcore_rpc::{RpcServerError, WsServerError, RpcServer as Server, RequestLimits, HeavyQueryOptions};
 use jsonipc;
 use rpc_apis;
 use rpc_apis::ApiSet;
@@ -26,6 +26,7 @@ use helpers::parity_ipc_path;
 
 pub use jsonipc::Server as IpcServer;
 pub use ethcore_rpc::Server as HttpServer;
+pub use ethcore_rpc::WsServer;
 
 #[derive(Debug, PartialEq)]
 pub struct HttpConfiguration {
@@ -35,6 +36,7 @@ pub struct HttpConfiguration {
 	pub apis: ApiSet,
 	pub cors: Option<Vec<String>>,
 	pub hosts: Option<Vec<String>>,
+	pub limits: RequestLimits,
 }
 
 impl Default for HttpConfiguration {
@@ -43,9 +45,33 @@ impl Default for HttpConfiguration {
 			enabled: true,
 			interface: "127.0.0.1".into(),
 			port: 8545,
-			apis: ApiSet::UnsafeContext,
-			cors: None,
+			apis: ApiSet::PublicContext,
+			cors: Some(Vec::new()),
 			hosts: Some(Vec::new()),
+			limits: RequestLimits::default(),
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub struct WsConfiguration {
+	pub enabled: bool,
+	pub interface: String,
+	pub port: u16,
+	pub apis: ApiSet,
+	pub origins: Option<Vec<String>>,
+	pub limits: RequestLimits,
+}
+
+impl Default for WsConfiguration {
+	fn default() -> Self {
+		WsConfiguration {
+			enabled: true,
+			interface: "127.0.0.1".into(),
+			port: 8546,
+			apis: ApiSet::PublicContext,
+			origins: Some(Vec::new()),
+			limits: RequestLimits::default(),
 		}
 	}
 }
@@ -89,7 +115,7 @@ pub fn new_http(conf: HttpConfiguration, deps: &Dependencies) -> Result<Option<H
 
 	let url = format!("{}:{}", conf.interface, conf.port);
 	let addr = try!(url.parse().map_err(|_| format!("Invalid JSONRPC listen host/port given: {}", url)));
-	Ok(Some(try!(setup_http_rpc_server(deps, &addr, conf.cors, conf.hosts, conf.apis))))
+	Ok(Some(try!(setup_http_rpc_server(deps, &addr, conf.cors, conf.hosts, conf.limits, conf.apis))))
 }
 
 fn setup_rpc_server(apis: ApiSet, deps: &Dependencies) -> Result<Server, String> {
@@ -97,16 +123,27 @@ fn setup_rpc_server(apis: ApiSet, deps: &Dependencies) -> Result<Server, String>
 	Ok(rpc_apis::setup_rpc(server, deps.apis.clone(), apis))
 }
 
+/// IPC is only reachable by local users, so heavy calls are not aborted there.
+fn setup_ipc_server(apis: ApiSet, deps: &Dependencies) -> Result<Server, String> {
+	let server = Server::new();
+	let heavy_queries = HeavyQueryOptions {
+		budget: None,
+		slow_query: deps.apis.heavy_queries.slow_query,
+	};
+	Ok(rpc_apis::setup_rpc_with_heavy_queries(server, deps.apis.clone(), apis, heavy_queries))
+}
+
 pub fn setup_http_rpc_server(
 	dependencies: &Dependencies,
 	url: &SocketAddr,
 	cors_domains: Option<Vec<String>>,
 	allowed_hosts: Option<Vec<String>>,
+	limits: RequestLimits,
 	apis: ApiSet
 ) -> Result<HttpServer, String> {
 	let server = try!(setup_rpc_server(apis, dependencies));
 	let ph = dependencies.panic_handler.clone();
-	let start_result = server.start_http(url, cors_domains, allowed_hosts, ph);
+	let start_result = server.start_http(url, cors_domains, allowed_hosts, limits, ph);
 	match start_result {
 		Err(RpcServerError::IoError(err)) => Err(format!("RPC io error: {}", err)),
 		Err(e) => Err(format!("RPC error: {:?}", e)),
@@ -114,16 +151,91 @@ pub fn setup_http_rpc_server(
 	}
 }
 
+pub fn new_ws(conf: WsConfiguration, deps: &Dependencies) -> Result<Option<WsServer>, String> {
+	if !conf.enabled {
+		return Ok(None);
+	}
+
+	let url = format!("{}:{}", conf.interface, conf.port);
+	let addr = try!(url.parse().map_err(|_| format!("Invalid WebSockets listen host/port given: {}", url)));
+	Ok(Some(try!(setup_ws_rpc_server(deps, &addr, conf.origins, conf.limits, conf.apis))))
+}
+
+pub fn setup_ws_rpc_server(
+	dependencies: &Dependencies,
+	url: &SocketAddr,
+	allowed_origins: Option<Vec<String>>,
+	limits: RequestLimits,
+	apis: ApiSet
+) -> Result<WsServer, String> {
+	let server = try!(setup_rpc_server(apis, dependencies));
+	let ph = dependencies.panic_handler.clone();
+	let storage_watcher = dependencies.apis.storage_watcher.clone();
+	match server.start_ws(url, allowed_origins, limits, ph, Some(storage_watcher)) {
+		Err(WsServerError::IoError(err)) => Err(format!("WebSockets io error: {}", err)),
+		Err(e) => Err(format!("WebSockets error: {:?}", e)),
+		Ok(server) => Ok(server),
+	}
+}
+
 pub fn new_ipc(conf: IpcConfiguration, deps: &Dependencies) -> Result<Option<IpcServer>, String> {
 	if !conf.enabled { return Ok(None); }
 	Ok(Some(try!(setup_ipc_rpc_server(deps, &conf.socket_addr, conf.apis))))
 }
 
+/// Prepares the location of a Unix domain socket: creates its directory and removes
+/// a socket left behind by an instance which did not shut down cleanly.
+#[cfg(unix)]
+fn prepare_ipc_path(addr: &str) -> Result<(), String> {
+	use std::fs;
+	use std::os::unix::fs::FileTypeExt;
+	use std::os::unix::net::UnixStream;
+	use std::path::Path;
+
+	let path = Path::new(addr);
+	if let Some(parent) = path.parent() {
+		try!(fs::create_dir_all(parent).map_err(|e| format!("Cannot create IPC directory {}: {}", parent.display(), e)));
+	}
+
+	if let Ok(metadata) = fs::metadata(path) {
+		if !metadata.file_type().is_socket() {
+			return Err(format!("IPC path {} exists and is not a socket.", addr));
+		}
+		if UnixStream::connect(path).is_ok() {
+			return Err(format!("IPC path {} is already in use. Make sure that no other instance is running.", addr));
+		}
+		warn!("Removing stale IPC socket {}", addr);
+		try!(fs::remove_file(path).map_err(|e| format!("Cannot remove stale IPC socket {}: {}", addr, e)));
+	}
+	Ok(())
+}
+
+#[cfg(not(unix))]
+fn prepare_ipc_path(_addr: &str) -> Result<(), String> {
+	Ok(())
+}
+
 pub fn setup_ipc_rpc_server(dependencies: &Dependencies, addr: &str, apis: ApiSet) -> Result<IpcServer, String> {
-	let server = try!(setup_rpc_server(apis, dependencies));
+	try!(prepare_ipc_path(addr));
+	let server = try!(setup_ipc_server(apis, dependencies));
 	match server.start_ipc(addr) {
 		Err(jsonipc::Error::Io(io_error)) => Err(format!("RPC io error: {}", io_error)),
 		Err(any_error) => Err(format!("Rpc error: {:?}", any_error)),
 		Ok(server) => Ok(server)
 	}
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+	use std::fs::File;
+	use devtools::RandomTempPath;
+	use super::prepare_ipc_path;
+
+	#[test]
+	fn should_not_remove_regular_file_at_ipc_path() {
+		let temp = RandomTempPath::new();
+		File::create(temp.as_path()).unwrap();
+		assert!(prepare_ipc_path(temp.as_str()).is_err());
+		assert!(temp.as_path().exists());
+	}
+}
@@ -15,86 +15,21 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::BTreeMap;
-use std::collections::HashSet;
-use std::cmp::PartialEq;
-use std::str::FromStr;
 use std::sync::Arc;
 use util::RotatingLogger;
 use ethcore::miner::{Miner, ExternalMiner};
 use ethcore::client::Client;
 use ethcore::account_provider::AccountProvider;
+use ethcore::private_transactions::Provider as PrivateTransactions;
 use ethsync::{ManageNetwork, SyncProvider};
-use ethcore_rpc::{Extendable, NetworkSettings};
-pub use ethcore_rpc::SignerService;
-
-
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
-pub enum Api {
-	Web3,
-	Net,
-	Eth,
-	Personal,
-	Signer,
-	Ethcore,
-	EthcoreSet,
-	Traces,
-	Rpc,
-}
-
-impl FromStr for Api {
-	type Err = String;
-
-	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		use self::Api::*;
-
-		match s {
-			"web3" => Ok(Web3),
-			"net" => Ok(Net),
-			"eth" => Ok(Eth),
-			"personal" => Ok(Personal),
-			"signer" => Ok(Signer),
-			"ethcore" => Ok(Ethcore),
-			"ethcore_set" => Ok(EthcoreSet),
-			"traces" => Ok(Traces),
-			"rpc" => Ok(Rpc),
-			api => Err(format!("Unknown api: {}", api))
-		}
-	}
-}
-
-#[derive(Debug)]
-pub enum ApiSet {
-	SafeContext,
-	UnsafeContext,
-	List(HashSet<Api>),
-}
-
-impl Default for ApiSet {
-	fn default() -> Self {
-		ApiSet::UnsafeContext
-	}
-}
-
-impl PartialEq for ApiSet {
-	fn eq(&self, other: &Self) -> bool {
-		self.list_apis() == other.list_apis()
-	}
-}
-
-impl FromStr for ApiSet {
-	type Err = String;
-
-	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		s.split(',')
-			.map(Api::from_str)
-			.collect::<Result<_, _>>()
-			.map(ApiSet::List)
-	}
-}
+use updater::Service as UpdateService;
+use ethcore_rpc::{Extendable, NetworkSettings, HeavyQueryOptions};
+pub use ethcore_rpc::{SignerService, StorageWatcher, Api, ApiSet};
 
 pub struct Dependencies {
 	pub signer_port: Option<u16>,
 	pub signer_service: Arc<SignerService>,
+	pub storage_watcher: Arc<StorageWatcher>,
 	pub client: Arc<Client>,
 	pub sync: Arc<SyncProvider>,
 	pub net: Arc<ManageNetwork>,
@@ -105,6 +40,11 @@ pub struct Dependencies {
 	pub settings: Arc<NetworkSettings>,
 	pub net_service: Arc<ManageNetwork>,
 	pub geth_compatibility: bool,
+	pub heavy_queries: HeavyQueryOptions,
+	pub updater: Option<Arc<UpdateService>>,
+	pub private_tx: Option<Arc<PrivateTransactions>>,
+	pub mode_timeout: u64,
+	pub mode_alarm: u64,
 }
 
 fn to_modules(apis: &[Api]) -> BTreeMap<String, String> {
@@ -120,29 +60,20 @@ fn to_modules(apis: &[Api]) -> BTreeMap<String, String> {
 			Api::EthcoreSet => ("ethcore_set", "1.0"),
 			Api::Traces => ("traces", "1.0"),
 			Api::Rpc => ("rpc", "1.0"),
+			Api::Private => ("private", "1.0"),
 		};
 		modules.insert(name.into(), version.into());
 	}
 	modules
 }
 
-impl ApiSet {
-	pub fn list_apis(&self) -> HashSet<Api> {
-		match *self {
-			ApiSet::List(ref apis) => apis.clone(),
-			ApiSet::UnsafeContext => {
-				vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Ethcore, Api::Traces, Api::Rpc]
-					.into_iter().collect()
-			},
-			_ => {
-				vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Signer, Api::Ethcore, Api::EthcoreSet, Api::Traces, Api::Rpc]
-					.into_iter().collect()
-			},
-		}
-	}
+pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet) -> T {
+	let heavy_queries = deps.heavy_queries.clone();
+	setup_rpc_with_heavy_queries(server, deps, apis, heavy_queries)
 }
 
-pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet) -> T {
+/// Same as `setup_rpc`, but with limits for heavy calls specific to the transport.
+pub fn setup_rpc_with_heavy_queries<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet, heavy_queries: HeavyQueryOptions) -> T {
 	use ethcore_rpc::v1::*;
 
 	// it's turned into vector, cause ont of the cases requires &[]
@@ -165,11 +96,12 @@ pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet
 					EthClientOptions {
 						allow_pending_receipt_query: !deps.geth_compatibility,
 						send_block_number_in_get_work: !deps.geth_compatibility,
+						heavy_queries: heavy_queries.clone(),
 					}
 				);
 				server.add_delegate(client.to_delegate());
 
-				let filter_client = EthFilterClient::new(&deps.client, &deps.miner);
+				let filter_client = EthFilterClient::new(&deps.client, &deps.miner, heavy_queries.clone());
 				server.add_delegate(filter_client.to_delegate());
 
 				if deps.signer_port.is_some() {
@@ -179,69 +111,32 @@ pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet
 				}
 			},
 			Api::Personal => {
-				server.add_delegate(PersonalClient::new(&deps.secret_store, &deps.client, &deps.miner, deps.signer_port, deps.geth_compatibility).to_delegate());
+				let signer = deps.signer_port.map(|_| &deps.signer_service);
+				server.add_delegate(PersonalClient::new(&deps.secret_store, &deps.client, &deps.miner, deps.signer_port, signer, deps.geth_compatibility).to_delegate());
 			},
 			Api::Signer => {
 				server.add_delegate(SignerClient::new(&deps.secret_store, &deps.client, &deps.miner, &deps.signer_service).to_delegate());
 			},
 			Api::Ethcore => {
 				let signer = deps.signer_port.map(|_| deps.signer_service.clone());
-				server.add_delegate(EthcoreClient::new(&deps.client, &deps.miner, &deps.sync, &deps.net_service, deps.logger.clone(), deps.settings.clone(), signer).to_delegate())
+				server.add_delegate(EthcoreClient::new(&deps.client, &deps.miner, &deps.sync, &deps.net_service, deps.logger.clone(), deps.settings.clone(), signer, deps.storage_watcher.clone(), deps.updater.clone(), heavy_queries.clone()).to_delegate())
 			},
 			Api::EthcoreSet => {
-				server.add_delegate(EthcoreSetClient::new(&deps.client, &deps.miner, &deps.net_service).to_delegate())
+				server.add_delegate(EthcoreSetClient::new(&deps.client, &deps.miner, &deps.net_service, deps.logger.clone(), deps.mode_timeout, deps.mode_alarm).to_delegate())
 			},
 			Api::Traces => {
-				server.add_delegate(TracesClient::new(&deps.client, &deps.miner).to_delegate())
+				server.add_delegate(TracesClient::new(&deps.client, &deps.miner, heavy_queries.clone()).to_delegate())
 			},
 			Api::Rpc => {
 				let modules = to_modules(&apis);
 				server.add_delegate(RpcClient::new(modules).to_delegate());
-			}
+			},
+			Api::Private => {
+				if let Some(ref provider) = deps.private_tx {
+					server.add_delegate(PrivateClient::new(provider, &deps.client, &deps.miner).to_delegate());
+				}
+			},
 		}
 	}
 	server
 }
-
-#[cfg(test)]
-mod test {
-	use super::{Api, ApiSet};
-
-	#[test]
-	fn test_api_parsing() {
-		assert_eq!(Api::Web3, "web3".parse().unwrap());
-		assert_eq!(Api::Net, "net".parse().unwrap());
-		assert_eq!(Api::Eth, "eth".parse().unwrap());
-		assert_eq!(Api::Personal, "personal".parse().unwrap());
-		assert_eq!(Api::Signer, "signer".parse().unwrap());
-		assert_eq!(Api::Ethcore, "ethcore".parse().unwrap());
-		assert_eq!(Api::EthcoreSet, "ethcore_set".parse().unwrap());
-		assert_eq!(Api::Traces, "traces".parse().unwrap());
-		assert_eq!(Api::Rpc, "rpc".parse().unwrap());
-		assert!("rp".parse::<Api>().is_err());
-	}
-
-	#[test]
-	fn test_api_set_default() {
-		assert_eq!(ApiSet::UnsafeContext, ApiSet::default());
-	}
-
-	#[test]
-	fn test_api_set_parsing() {
-		assert_eq!(ApiSet::List(vec![Api::Web3, Api::Eth].into_iter().collect()), "web3,eth".parse().unwrap());
-	}
-
-	#[test]
-	fn test_api_set_unsafe_context() {
-		let expected = vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Ethcore, Api::Traces, Api::Rpc]
-			.into_iter().collect();
-		assert_eq!(ApiSet::UnsafeContext.list_apis(), expected);
-	}
-
-	#[test]
-	fn test_api_set_safe_context() {
-		let expected = vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Signer, Api::Ethcore, Api::EthcoreSet, Api::Traces, Api::Rpc]
-			.into_iter().collect();
-		assert_eq!(ApiSet::SafeContext.list_apis(), expected);
-	}
-}
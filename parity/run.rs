@@ -14,28 +14,36 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::{cmp, thread};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, Condvar};
+use std::sync::atomic::{AtomicBool, Ordering};
 use ctrlc::CtrlC;
 use fdlimit::raise_fd_limit;
-use ethcore_logger::{Config as LogConfig, setup_log};
-use ethcore_rpc::NetworkSettings;
+use ethcore_logger::{Config as LogConfig, setup_log, reopen_log_file};
+use ethcore_rpc::{NetworkSettings, HeavyQueryOptions};
 use ethsync::NetworkConfiguration;
-use util::{Colour, version, U256};
+use util::{Colour, version, U256, Address};
+use util::node_events::{self, NodeEvents, NodeEventKind};
 use io::{MayPanic, ForwardPanic, PanicHandler};
-use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, ChainNotify};
+use ethcore::client::{Client, Mode, DatabaseCompactionProfile, VMType, ChainNotify};
 use ethcore::service::ClientService;
-use ethcore::account_provider::AccountProvider;
-use ethcore::miner::{Miner, MinerService, ExternalMiner, MinerOptions};
+use ethcore::account_provider::{AccountProvider, AccountProviderSettings};
+use ethcore::miner::{Miner, MinerService, ExternalMiner, MinerOptions, check_extra_data};
 use ethcore::snapshot;
-use ethsync::{SyncConfig, SyncProvider};
+use ethsync::{SyncConfig, SyncProvider, SyncMethod};
 use informant::Informant;
+use block_hook::BlockHook;
+use signals::{self, Signal};
+use pending_transactions;
+use updater::{Updater, UpdaterConfig, Service as UpdateService};
 
-use rpc::{HttpServer, IpcServer, HttpConfiguration, IpcConfiguration};
+use rpc::{HttpServer, IpcServer, WsServer, HttpConfiguration, IpcConfiguration, WsConfiguration};
 use signer::SignerServer;
 use dapps::WebappServer;
 use io_handler::ClientIoHandler;
 use params::{
-	SpecType, Pruning, AccountsConfig, GasPricerConfig, MinerExtras, Switch,
+	SpecType, ForkCheck, Pruning, AccountsConfig, UnlockMode, GasPricerConfig, MinerExtras, Switch,
 	tracing_switch_to_bool, fatdb_switch_to_bool,
 };
 use helpers::{to_client_config, execute_upgrades, passwords_from_files};
@@ -43,6 +51,11 @@ use dir::Directories;
 use cache::CacheConfig;
 use user_defaults::UserDefaults;
 use dapps;
+use secretstore;
+use private_tx;
+use ethstats;
+use dev;
+use metrics;
 use signer;
 use modules;
 use rpc_apis;
@@ -60,6 +73,9 @@ pub struct RunCmd {
 	pub cache_config: CacheConfig,
 	pub dirs: Directories,
 	pub spec: SpecType,
+	pub fork: ForkCheck,
+	pub max_reorg_depth: Option<u64>,
+	pub report_bad_blocks: Option<String>,
 	pub pruning: Pruning,
 	/// Some if execution should be daemonized. Contains pid_file path.
 	pub daemon: Option<String>,
@@ -67,12 +83,15 @@ pub struct RunCmd {
 	pub miner_options: MinerOptions,
 	pub http_conf: HttpConfiguration,
 	pub ipc_conf: IpcConfiguration,
+	pub ws_conf: WsConfiguration,
 	pub net_conf: NetworkConfiguration,
 	pub network_id: Option<U256>,
 	pub acc_conf: AccountsConfig,
 	pub gas_pricer: GasPricerConfig,
 	pub miner_extras: MinerExtras,
 	pub mode: Mode,
+	pub mode_timeout: u64,
+	pub mode_alarm: u64,
 	pub tracing: Switch,
 	pub fat_db: Switch,
 	pub compaction: DatabaseCompactionProfile,
@@ -80,14 +99,31 @@ pub struct RunCmd {
 	pub vm_type: VMType,
 	pub enable_network: bool,
 	pub geth_compatibility: bool,
+	pub heavy_queries: HeavyQueryOptions,
 	pub signer_port: Option<u16>,
 	pub net_settings: NetworkSettings,
 	pub dapps_conf: dapps::Configuration,
+	pub secretstore_conf: secretstore::Configuration,
+	pub private_tx_conf: private_tx::Configuration,
+	pub metrics_conf: metrics::Configuration,
 	pub signer_conf: signer::Configuration,
 	pub ui: bool,
 	pub name: String,
 	pub custom_bootnodes: bool,
 	pub no_periodic_snapshot: bool,
+	pub relay_txs_while_syncing: bool,
+	pub relay_blocks_while_syncing: bool,
+	pub sync_method: SyncMethod,
+	pub cold_db: Option<String>,
+	pub ancient_horizon: u64,
+	pub check_roots: bool,
+	pub no_bodies: bool,
+	pub db_max_open_files: i32,
+	pub db_wal_size: Option<u64>,
+	pub db_write_buffer_size: Option<u64>,
+	pub on_new_block: Option<String>,
+	pub ethstats_conf: Option<ethstats::Configuration>,
+	pub updater_conf: Option<UpdaterConfig>,
 }
 
 pub fn execute(cmd: RunCmd) -> Result<(), String> {
@@ -103,14 +139,17 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	// create dirs used by parity
 	try!(cmd.dirs.create_dirs());
 
+	// open node events journal
+	let journal = try!(NodeEvents::open(cmd.dirs.node_events_path()).map_err(|e| format!("Error opening node events journal: {}", e)));
+	node_events::set_journal(Arc::new(journal));
+	node_events::record_panics();
+	node_events::record_event(NodeEventKind::Start, &version());
+
 	// load spec
 	let spec = try!(cmd.spec.spec());
 
-	// load genesis hash
-	let genesis_hash = spec.genesis_header().hash();
-
 	// database paths
-	let db_dirs = cmd.dirs.database(genesis_hash, spec.fork_name.clone());
+	let db_dirs = try!(cmd.dirs.database(&spec));
 
 	// user defaults path
 	let user_defaults_path = db_dirs.user_defaults_path();
@@ -119,7 +158,7 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	let mut user_defaults = try!(UserDefaults::load(&user_defaults_path));
 
 	// select pruning algorithm
-	let algorithm = cmd.pruning.to_algorithm(&user_defaults);
+	let algorithm = try!(cmd.pruning.to_algorithm(&user_defaults, &db_dirs.existing_pruning()));
 
 	// check if tracing is on
 	let tracing = try!(tracing_switch_to_bool(cmd.tracing, &user_defaults));
@@ -132,9 +171,10 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	let snapshot_path = db_dirs.snapshot_path();
 
 	// execute upgrades
-	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile()));
+	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile(&client_path)));
 
 	// run in daemon mode
+	let daemonized = cmd.daemon.is_some();
 	if let Some(pid_file) = cmd.daemon {
 		try!(daemonize(pid_file));
 	}
@@ -159,20 +199,40 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	}
 
 	// create sync config
-	let mut sync_config = SyncConfig::default();
-	sync_config.network_id = match cmd.network_id {
+	let network_id = match cmd.network_id {
 		Some(id) => id,
 		None => spec.network_id(),
 	};
+	let mut sync_config = SyncConfig::default();
+	sync_config.network_id = network_id;
 	if spec.subprotocol_name().len() != 3 {
 		warn!("Your chain specification's subprotocol length is not 3. Ignoring.");
 	} else {
 		sync_config.subprotocol_name.clone_from_slice(spec.subprotocol_name().as_bytes());
 	}
-	sync_config.fork_block = spec.fork_block();
+	sync_config.fork_block = cmd.fork.fork_block(&spec);
+	sync_config.propagate_transactions_while_syncing = cmd.relay_txs_while_syncing;
+	sync_config.propagate_blocks_while_syncing = cmd.relay_blocks_while_syncing;
+	sync_config.sync_method = cmd.sync_method;
+	sync_config.relay = cmd.mode == Mode::Relay;
+
+	if cmd.mode == Mode::Relay && cmd.miner_options.force_sealing {
+		return Err("Blocks can't be sealed in relay mode.".into());
+	}
 
 	// prepare account provider
-	let account_provider = Arc::new(try!(prepare_account_provider(&cmd.dirs, cmd.acc_conf)));
+	// development accounts are unlocked with an empty password, their keys are kept apart from the user's keys
+	let keys_path = match cmd.spec {
+		SpecType::Dev(_) => cmd.dirs.dev_keys_path(&spec),
+		_ => PathBuf::from(&cmd.dirs.keys),
+	};
+	let account_provider = try!(prepare_account_provider(&keys_path, cmd.acc_conf));
+	if let SpecType::Dev(ref dev_conf) = cmd.spec {
+		try!(dev::import_accounts(dev_conf, &account_provider));
+	}
+
+	// the extra data is a template, check that it fits once rendered
+	try!(check_extra_data(&cmd.miner_extras.extra_data, spec.engine.maximum_extra_data_size()));
 
 	// create miner
 	let miner = Miner::new(cmd.miner_options, cmd.gas_pricer.into(), &spec, Some(account_provider.clone()));
@@ -181,9 +241,14 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	miner.set_gas_ceil_target(cmd.miner_extras.gas_ceil_target);
 	miner.set_extra_data(cmd.miner_extras.extra_data);
 	miner.set_transactions_limit(cmd.miner_extras.transactions_limit);
+	if let SpecType::Dev(ref dev_conf) = cmd.spec {
+		// keep the gas limit of the dev chain constant
+		miner.set_gas_floor_target(dev_conf.gas_limit);
+		miner.set_gas_ceil_target(dev_conf.gas_limit);
+	}
 
 	// create client config
-	let client_config = to_client_config(
+	let mut client_config = to_client_config(
 		&cmd.cache_config,
 		cmd.mode,
 		tracing,
@@ -194,6 +259,16 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 		cmd.name,
 		algorithm,
 	);
+	client_config.cold_db_path = cmd.cold_db;
+	client_config.ancient_path = cmd.dirs.ancient_path.clone();
+	client_config.blockchain.ancient_horizon = cmd.ancient_horizon;
+	client_config.check_roots = cmd.check_roots;
+	client_config.max_reorg_depth = cmd.max_reorg_depth;
+	client_config.bad_block_report_url = cmd.report_bad_blocks;
+	client_config.blockchain.store_bodies = !cmd.no_bodies;
+	client_config.db_max_open_files = Some(cmd.db_max_open_files);
+	client_config.db_wal_size = cmd.db_wal_size;
+	client_config.db_write_buffer_size = cmd.db_write_buffer_size;
 
 	// set up bootnodes
 	let mut net_conf = cmd.net_conf;
@@ -221,6 +296,15 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	let client = service.client();
 	let snapshot_service = service.snapshot_service();
 
+	// re-import transactions queued when the node was last shut down
+	let pending_transactions_path = db_dirs.pending_transactions_path();
+	restore_pending_transactions(&pending_transactions_path, &client, &miner);
+
+	// seal empty blocks on the dev chain
+	if let SpecType::Dev(ref dev_conf) = cmd.spec {
+		try!(dev::start(dev_conf, Arc::downgrade(&client), miner.clone()));
+	}
+
 	// create external miner
 	let external_miner = Arc::new(ExternalMiner::default());
 
@@ -236,13 +320,34 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 		chain_notify.start();
 	}
 
+	// restart the sync process if it crashes
+	let supervisor = modules::supervise(hypervisor, chain_notify.clone(), cmd.enable_network);
+
+	// storage slots watched over rpc are checked after every import
+	let storage_watcher = Arc::new(rpc_apis::StorageWatcher::new(&client));
+	service.add_notify(storage_watcher.clone());
+
 	// set up dependencies for rpc servers
 	let signer_path = cmd.signer_conf.signer_path.clone();
+	// check for new releases
+	let updater = match cmd.updater_conf {
+		Some(updater_conf) => {
+			let updater = Arc::new(try!(Updater::new(updater_conf)));
+			try!(Updater::start(&updater));
+			Some(updater)
+		},
+		None => None,
+	};
+
+	// private transactions are relayed over the sync protocol
+	let private_tx = try!(private_tx::start(cmd.private_tx_conf, client.clone(), miner.clone(), account_provider.clone()));
+
 	let deps_for_rpc_apis = Arc::new(rpc_apis::Dependencies {
 		signer_port: cmd.signer_port,
 		signer_service: Arc::new(rpc_apis::SignerService::new(move || {
 			signer::generate_new_token(signer_path.clone()).map_err(|e| format!("{:?}", e))
 		})),
+		storage_watcher: storage_watcher,
 		client: client.clone(),
 		sync: sync_provider.clone(),
 		net: manage_network.clone(),
@@ -253,6 +358,11 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 		settings: Arc::new(cmd.net_settings.clone()),
 		net_service: manage_network.clone(),
 		geth_compatibility: cmd.geth_compatibility,
+		heavy_queries: cmd.heavy_queries,
+		updater: updater.map(|updater| updater as Arc<UpdateService>),
+		private_tx: private_tx,
+		mode_timeout: cmd.mode_timeout,
+		mode_alarm: cmd.mode_alarm,
 	});
 
 	let dependencies = rpc::Dependencies {
@@ -263,6 +373,7 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	// start rpc servers
 	let http_server = try!(rpc::new_http(cmd.http_conf, &dependencies));
 	let ipc_server = try!(rpc::new_ipc(cmd.ipc_conf, &dependencies));
+	let ws_server = try!(rpc::new_ws(cmd.ws_conf, &dependencies));
 
 	let dapps_deps = dapps::Dependencies {
 		panic_handler: panic_handler.clone(),
@@ -274,6 +385,31 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	// start dapps server
 	let dapps_server = try!(dapps::new(cmd.dapps_conf.clone(), dapps_deps));
 
+	// secret store key server
+	let secretstore_deps = secretstore::Dependencies {
+		client: client.clone(),
+	};
+	let _secretstore_key_server = try!(secretstore::start(cmd.secretstore_conf.clone(), secretstore_deps));
+
+	// report to an ethstats server
+	let ethstats_deps = ethstats::Dependencies {
+		client: client.clone(),
+		sync: sync_provider.clone(),
+		miner: miner.clone(),
+		external_miner: external_miner.clone(),
+		network_id: network_id,
+		network_port: cmd.net_settings.network_port,
+	};
+	try!(ethstats::start(cmd.ethstats_conf, ethstats_deps));
+
+	// expose metrics over http
+	let metrics_deps = metrics::Dependencies {
+		client: client.clone(),
+		sync: sync_provider.clone(),
+		miner: miner.clone(),
+	};
+	let _metrics_server = try!(metrics::start(cmd.metrics_conf, metrics_deps));
+
 	let signer_deps = signer::Dependencies {
 		panic_handler: panic_handler.clone(),
 		apis: deps_for_rpc_apis.clone(),
@@ -285,6 +421,11 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	let informant = Arc::new(Informant::new(service.client(), Some(sync_provider.clone()), Some(manage_network.clone()), cmd.logger_config.color));
 	let info_notify: Arc<ChainNotify> = informant.clone();
 	service.add_notify(info_notify);
+
+	if let Some(command) = cmd.on_new_block {
+		let hook = try!(BlockHook::new(command, &service.client(), sync_provider.clone()).map_err(|e| format!("Error starting block hook: {}", e)));
+		service.add_notify(Arc::new(hook));
+	}
 	let io_handler = Arc::new(ClientIoHandler {
 		client: service.client(),
 		info: informant,
@@ -296,7 +437,8 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	service.register_io_handler(io_handler.clone()).expect("Error registering IO handler");
 
 	// the watcher must be kept alive.
-	let _watcher = match cmd.no_periodic_snapshot {
+	// there is no state to snapshot in relay mode.
+	let _watcher = match cmd.no_periodic_snapshot || cmd.mode == Mode::Relay {
 		true => None,
 		false => {
 			let sync = sync_provider.clone();
@@ -327,7 +469,7 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	try!(user_defaults.save(&user_defaults_path));
 
 	// Handle exit
-	wait_for_exit(panic_handler, http_server, ipc_server, dapps_server, signer_server);
+	let panicked = wait_for_exit(panic_handler, daemonized, http_server, ipc_server, ws_server, dapps_server, signer_server);
 
 	// to make sure timer does not spawn requests while shutdown is in progress
 	io_handler.shutdown.store(true, ::std::sync::atomic::Ordering::SeqCst);
@@ -336,7 +478,25 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 
 	// hypervisor should be shutdown first while everything still works and can be
 	// terminated gracefully
-	drop(hypervisor);
+	drop(supervisor);
+
+	// keep the transaction queue and buffered database writes
+	let transactions = miner.all_transactions();
+	match pending_transactions::save(&pending_transactions_path, &transactions) {
+		Ok(_) => info!("Saved {} pending transactions", transactions.len()),
+		Err(e) => warn!("Error saving pending transactions: {}", e),
+	}
+	// after a panic the database may be inconsistent, leave it to be checked on the next start
+	if !panicked {
+		if let Err(e) = service.shutdown() {
+			warn!("Error shutting down the client: {}", e);
+		}
+	}
+
+	match panicked {
+		true => node_events::record_event(NodeEventKind::UncleanShutdown, "Shut down after a fatal error."),
+		false => node_events::record_event(NodeEventKind::Shutdown, ""),
+	}
 
 	Ok(())
 }
@@ -358,45 +518,114 @@ fn daemonize(_pid_file: String) -> Result<(), String> {
 	Err("daemon is no supported on windows".into())
 }
 
-fn prepare_account_provider(dirs: &Directories, cfg: AccountsConfig) -> Result<AccountProvider, String> {
-	use ethcore::ethstore::EthStore;
+fn restore_pending_transactions(path: &Path, client: &Arc<Client>, miner: &Miner) {
+	let transactions = pending_transactions::restore(path);
+	if transactions.is_empty() {
+		return;
+	}
+	let imported = miner.import_restored_transactions(&**client, transactions).into_iter().filter(Result::is_ok).count();
+	info!("Restored {} pending transactions", imported);
+}
+
+fn prepare_account_provider(keys_path: &Path, cfg: AccountsConfig) -> Result<Arc<AccountProvider>, String> {
+	use ethcore::ethstore::{EthStore, read_geth_accounts};
 	use ethcore::ethstore::dir::DiskDirectory;
 
 	let passwords = try!(passwords_from_files(cfg.password_files));
 
-	let dir = Box::new(try!(DiskDirectory::create(dirs.keys.clone()).map_err(|e| format!("Could not open keys directory: {}", e))));
-	let account_service = AccountProvider::new(Box::new(
-		try!(EthStore::open_with_iterations(dir, cfg.iterations).map_err(|e| format!("Could not open keys directory: {}", e)))
-	));
-
-	for a in cfg.unlocked_accounts {
-		if passwords.iter().find(|p| account_service.unlock_account_permanently(a, (*p).clone()).is_ok()).is_none() {
-			return Err(format!("No password found to unlock account {}. Make sure valid password is present in files passed using `--password`.", a));
+	let dir = Box::new(try!(DiskDirectory::create(keys_path).map_err(|e| format!("Could not open keys directory: {}", e))));
+	let account_settings = AccountProviderSettings {
+		enable_hardware_wallets: cfg.enable_hardware_wallets,
+	};
+	let account_service = Arc::new(AccountProvider::new_with_settings(Box::new(
+		try!(EthStore::open_with_kdf(dir, cfg.kdf).map_err(|e| format!("Could not open keys directory: {}", e)))
+	), account_settings));
+
+	if account_service.accounts().map(|a| a.is_empty()).unwrap_or(false) {
+		let geth_accounts = read_geth_accounts(cfg.testnet);
+		if !geth_accounts.is_empty() {
+			info!("Found {} account(s) in the Geth keystore. Run {} to use them with Parity.", geth_accounts.len(), Colour::White.bold().paint("parity account import-geth"));
 		}
 	}
 
+	try!(unlock_accounts(&account_service, cfg.unlocked_accounts, cfg.unlock_mode, passwords));
+
 	Ok(account_service)
 }
 
+/// Unlocks accounts in given mode. Deriving keys is slow, so accounts are unlocked in parallel.
+fn unlock_accounts(account_service: &Arc<AccountProvider>, accounts: Vec<Address>, mode: UnlockMode, passwords: Vec<String>) -> Result<(), String> {
+	if accounts.is_empty() {
+		return Ok(());
+	}
+
+	let threads = cmp::min(::num_cpus::get(), accounts.len());
+	let chunk_size = (accounts.len() + threads - 1) / threads;
+	let passwords = Arc::new(passwords);
+	let handles = accounts.chunks(chunk_size).map(|chunk| {
+		let chunk = chunk.to_vec();
+		let account_service = account_service.clone();
+		let passwords = passwords.clone();
+		let mode = mode.clone();
+		thread::spawn(move || {
+			let unlock = |a: &Address, p: &String| match mode {
+				UnlockMode::Permanent => account_service.unlock_account_permanently(a.clone(), p.clone()),
+				UnlockMode::Timed(secs) => account_service.unlock_account_timed(a.clone(), p.clone(), secs * 1000),
+				UnlockMode::Single => account_service.unlock_account_temporarily(a.clone(), p.clone()),
+			};
+			chunk.into_iter()
+				.filter(|a| passwords.iter().find(|p| unlock(a, p).is_ok()).is_none())
+				.collect::<Vec<_>>()
+		})
+	}).collect::<Vec<_>>();
+
+	let mut locked = Vec::new();
+	for handle in handles {
+		locked.extend(try!(handle.join().map_err(|_| "Unlocking accounts failed.".to_owned())));
+	}
+
+	match locked.first() {
+		Some(a) => Err(format!("No password found to unlock account {}. Make sure valid password is present in files passed using `--password`.", a)),
+		None => Ok(()),
+	}
+}
+
 fn wait_for_exit(
 	panic_handler: Arc<PanicHandler>,
+	daemonized: bool,
 	_http_server: Option<HttpServer>,
 	_ipc_server: Option<IpcServer>,
+	_ws_server: Option<WsServer>,
 	_dapps_server: Option<WebappServer>,
 	_signer_server: Option<SignerServer>
-	) {
+	) -> bool {
 	let exit = Arc::new(Condvar::new());
+	let panicked = Arc::new(AtomicBool::new(false));
 
 	// Handle possible exits
 	let e = exit.clone();
 	CtrlC::set_handler(move || { e.notify_all(); });
 
+	// Shut down on SIGTERM, reopen the log file on SIGHUP if there is no terminal to hang up
+	let e = exit.clone();
+	let signal_handler = signals::set_handler(daemonized, move |signal| match signal {
+		Signal::Terminate => e.notify_all(),
+		Signal::Hangup => if let Err(err) = reopen_log_file() {
+			warn!("{}", err);
+		},
+	});
+	if let Err(err) = signal_handler {
+		warn!("{}", err);
+	}
+
 	// Handle panics
 	let e = exit.clone();
-	panic_handler.on_panic(move |_reason| { e.notify_all(); });
+	let p = panicked.clone();
+	panic_handler.on_panic(move |_reason| { p.store(true, Ordering::SeqCst); e.notify_all(); });
 
 	// Wait for signal
 	let mutex = Mutex::new(());
 	let _ = exit.wait(mutex.lock().unwrap());
 	info!("Finishing work, please wait...");
+	panicked.load(Ordering::SeqCst)
 }
@@ -0,0 +1,175 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::collections::BTreeMap;
+use ethcore::client::Client;
+use ethcore::ethstore::ethkey::{Secret, Public};
+use helpers::replace_home;
+
+#[derive(Debug, PartialEq, Clone)]
+/// Secret store configuration
+pub struct Configuration {
+	/// Is secret store functionality enabled?
+	pub enabled: bool,
+	/// This node secret.
+	pub self_secret: Option<Secret>,
+	/// Other nodes ids and addresses.
+	pub nodes: BTreeMap<Public, (String, u16)>,
+	/// Interface to listen for other nodes on.
+	pub interface: String,
+	/// Port to listen for other nodes on.
+	pub port: u16,
+	/// Interface to listen HTTP requests on.
+	pub http_interface: String,
+	/// Port to listen HTTP requests on.
+	pub http_port: u16,
+	/// Data directory path for secret store.
+	pub data_path: String,
+}
+
+impl Default for Configuration {
+	fn default() -> Self {
+		Configuration {
+			enabled: true,
+			self_secret: None,
+			nodes: BTreeMap::new(),
+			interface: "127.0.0.1".into(),
+			port: 8083,
+			http_interface: "127.0.0.1".into(),
+			http_port: 8082,
+			data_path: replace_home("$HOME/.parity/secretstore"),
+		}
+	}
+}
+
+/// Secret store dependencies
+pub struct Dependencies {
+	/// Blockchain client.
+	pub client: Arc<Client>,
+}
+
+pub use self::server::KeyServer;
+
+#[cfg(not(feature = "secretstore"))]
+mod server {
+	use super::{Configuration, Dependencies};
+
+	/// Noop key server implementation
+	pub struct KeyServer;
+
+	impl KeyServer {
+		/// Create new noop key server
+		pub fn new(_conf: Configuration, _deps: Dependencies) -> Result<Self, String> {
+			Err("Your Parity version has been compiled without Secret Store support.".into())
+		}
+	}
+}
+
+#[cfg(feature = "secretstore")]
+mod server {
+	use std::sync::Arc;
+	use util::{Bytes, Address, U256};
+	use ethcore::transaction::{Transaction, Action};
+	use ethcore::client::{Client, BlockChainClient, BlockID};
+	use ethcore::ethstore::ethkey::KeyPair;
+	use ethcore_secretstore as secretstore;
+	use super::{Configuration, Dependencies};
+
+	/// Key server
+	pub struct KeyServer {
+		_key_server: Box<secretstore::KeyServer>,
+	}
+
+	impl KeyServer {
+		/// Create new key server
+		pub fn new(conf: Configuration, deps: Dependencies) -> Result<Self, String> {
+			let self_secret = try!(conf.self_secret.ok_or_else(|| "self secret is required when using secretstore".to_owned()));
+			let mut conf = secretstore::ServiceConfiguration {
+				listener_address: secretstore::NodeAddress {
+					address: conf.http_interface.clone(),
+					port: conf.http_port,
+				},
+				data_path: conf.data_path.clone(),
+				cluster_config: secretstore::ClusterConfiguration {
+					self_secret: self_secret,
+					listener_address: secretstore::NodeAddress {
+						address: conf.interface.clone(),
+						port: conf.port,
+					},
+					nodes: conf.nodes.into_iter().map(|(p, (ip, port))| (p, secretstore::NodeAddress {
+						address: ip,
+						port: port,
+					})).collect(),
+				},
+			};
+
+			let self_public = try!(KeyPair::from_secret(conf.cluster_config.self_secret.clone()).map_err(|e| format!("{}", e))).public().clone();
+			let self_address = conf.cluster_config.listener_address.clone();
+			conf.cluster_config.nodes.insert(self_public, self_address);
+
+			let key_server = try!(secretstore::start(Arc::new(SecretStoreClient { client: deps.client }), conf)
+				.map_err(|e| format!("Error starting KeyServer: {}", e)));
+
+			Ok(KeyServer {
+				_key_server: key_server,
+			})
+		}
+	}
+
+	struct SecretStoreClient {
+		client: Arc<Client>,
+	}
+
+	impl secretstore::ContractClient for SecretStoreClient {
+		fn registrar(&self) -> Result<Address, String> {
+			self.client.additional_params().get("registrar")
+				.ok_or_else(|| "Registrar not defined.".into())
+				.and_then(|registrar| {
+					registrar.parse().map_err(|e| format!("Invalid registrar address: {:?}", e))
+				})
+		}
+
+		fn call(&self, address: Address, data: Bytes) -> Result<Bytes, String> {
+			let from = Address::default();
+			let nonce = try!(self.client.nonce(&from, BlockID::Latest).ok_or_else(|| "State is not available in relay mode.".to_owned()));
+			let transaction = Transaction {
+				nonce: nonce,
+				action: Action::Call(address),
+				gas: U256::from(50_000_000),
+				gas_price: U256::default(),
+				value: U256::default(),
+				data: data,
+			}.fake_sign(from);
+
+			self.client.call(&transaction, BlockID::Latest, Default::default())
+				.map_err(|e| format!("{:?}", e))
+				.map(|executed| {
+					executed.output
+				})
+		}
+	}
+}
+
+/// Start secret store-related functionality
+pub fn start(conf: Configuration, deps: Dependencies) -> Result<Option<KeyServer>, String> {
+	if !conf.enabled {
+		return Ok(None);
+	}
+
+	KeyServer::new(conf, deps)
+		.map(|s| Some(s))
+}
@@ -0,0 +1,106 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Handling of the signals used to control a daemonized node.
+
+/// Signal delivered to the process.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Signal {
+	/// SIGHUP, the log file should be reopened.
+	Hangup,
+	/// SIGTERM, the node should shut down.
+	Terminate,
+}
+
+#[cfg(not(windows))]
+mod platform {
+	extern crate libc;
+
+	use std::thread;
+	use std::time::Duration;
+	use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
+	use super::Signal;
+
+	/// How often the flags set by the signal handler are checked.
+	const POLL_INTERVAL_MS: u64 = 100;
+
+	static HANGUP: AtomicBool = ATOMIC_BOOL_INIT;
+	static TERMINATE: AtomicBool = ATOMIC_BOOL_INIT;
+
+	// only async-signal-safe operations are allowed here, so the signal is just recorded
+	extern "C" fn handler(signal: libc::c_int) {
+		match signal {
+			libc::SIGHUP => HANGUP.store(true, Ordering::SeqCst),
+			_ => TERMINATE.store(true, Ordering::SeqCst),
+		}
+	}
+
+	pub fn set_handler<F>(hangup: bool, f: F) -> Result<(), String> where F: Fn(Signal) + Send + 'static {
+		unsafe {
+			if hangup {
+				libc::signal(libc::SIGHUP, handler as libc::sighandler_t);
+			}
+			libc::signal(libc::SIGTERM, handler as libc::sighandler_t);
+		}
+
+		thread::Builder::new().name("signals".into()).spawn(move || loop {
+			thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+			if HANGUP.swap(false, Ordering::SeqCst) {
+				f(Signal::Hangup);
+			}
+			if TERMINATE.swap(false, Ordering::SeqCst) {
+				f(Signal::Terminate);
+			}
+		})
+		.map(|_| ())
+		.map_err(|e| format!("Error starting signal handler: {}", e))
+	}
+}
+
+#[cfg(windows)]
+mod platform {
+	use super::Signal;
+
+	pub fn set_handler<F>(_hangup: bool, _f: F) -> Result<(), String> where F: Fn(Signal) + Send + 'static {
+		Ok(())
+	}
+}
+
+/// Call `f` for every SIGTERM the process receives, and for every SIGHUP if `hangup` is set.
+/// Otherwise SIGHUP keeps its default action of terminating the process, as expected when
+/// the controlling terminal goes away. The callback runs on a dedicated thread. Does nothing on Windows.
+pub fn set_handler<F>(hangup: bool, f: F) -> Result<(), String> where F: Fn(Signal) + Send + 'static {
+	platform::set_handler(hangup, f)
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+	extern crate libc;
+
+	use std::sync::mpsc;
+	use std::time::Duration;
+	use super::{set_handler, Signal};
+
+	#[test]
+	fn should_forward_hangup_to_handler() {
+		let (tx, rx) = mpsc::channel();
+		let tx = ::std::sync::Mutex::new(tx);
+		set_handler(true, move |signal| { let _ = tx.lock().unwrap().send(signal); }).unwrap();
+
+		unsafe { libc::raise(libc::SIGHUP); }
+		assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(Signal::Hangup));
+	}
+}
@@ -17,9 +17,9 @@
 use std::io;
 use std::sync::Arc;
 use std::path::PathBuf;
+use std::time::Duration;
 use ansi_term::Colour;
 use io::{ForwardPanic, PanicHandler};
-use util::path::restrict_permissions_owner;
 use rpc_apis;
 use ethcore_signer as signer;
 use helpers::replace_home;
@@ -34,6 +34,7 @@ pub struct Configuration {
 	pub interface: String,
 	pub signer_path: String,
 	pub skip_origin_validation: bool,
+	pub request_timeout: Option<u64>,
 }
 
 impl Default for Configuration {
@@ -44,6 +45,7 @@ impl Default for Configuration {
 			interface: "127.0.0.1".into(),
 			signer_path: replace_home("$HOME/.parity/signer"),
 			skip_origin_validation: false,
+			request_timeout: None,
 		}
 	}
 }
@@ -64,7 +66,6 @@ pub fn start(conf: Configuration, deps: Dependencies) -> Result<Option<SignerSer
 fn codes_path(path: String) -> PathBuf {
 	let mut p = PathBuf::from(path);
 	p.push(CODES_FILENAME);
-	let _ = restrict_permissions_owner(&p);
 	p
 }
 
@@ -98,6 +99,7 @@ fn do_start(conf: Configuration, deps: Dependencies) -> Result<SignerServer, Str
 			info!("If you do not intend this, exit now.");
 		}
 		let server = server.skip_origin_validation(conf.skip_origin_validation);
+		let server = server.request_timeout(conf.request_timeout.map(Duration::from_secs));
 		let server = rpc_apis::setup_rpc(server, deps.apis, rpc_apis::ApiSet::SafeContext);
 		server.start(addr)
 	};
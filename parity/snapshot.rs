@@ -128,11 +128,8 @@ impl SnapshotCommand {
 		// load spec file
 		let spec = try!(self.spec.spec());
 
-		// load genesis hash
-		let genesis_hash = spec.genesis_header().hash();
-
 		// database paths
-		let db_dirs = self.dirs.database(genesis_hash, spec.fork_name.clone());
+		let db_dirs = try!(self.dirs.database(&spec));
 
 		// user defaults path
 		let user_defaults_path = db_dirs.user_defaults_path();
@@ -146,7 +143,7 @@ impl SnapshotCommand {
 		fdlimit::raise_fd_limit();
 
 		// select pruning algorithm
-		let algorithm = self.pruning.to_algorithm(&user_defaults);
+		let algorithm = try!(self.pruning.to_algorithm(&user_defaults, &db_dirs.existing_pruning()));
 
 		// check if tracing is on
 		let tracing = try!(tracing_switch_to_bool(self.tracing, &user_defaults));
@@ -159,10 +156,11 @@ impl SnapshotCommand {
 		let snapshot_path = db_dirs.snapshot_path();
 
 		// execute upgrades
-		try!(execute_upgrades(&db_dirs, algorithm, self.compaction.compaction_profile()));
+		try!(execute_upgrades(&db_dirs, algorithm, self.compaction.compaction_profile(&client_path)));
 
 		// prepare client config
-		let client_config = to_client_config(&self.cache_config, self.mode, tracing, fat_db, self.compaction, self.wal, VMType::default(), "".into(), algorithm);
+		let mut client_config = to_client_config(&self.cache_config, self.mode, tracing, fat_db, self.compaction, self.wal, VMType::default(), "".into(), algorithm);
+		client_config.ancient_path = self.dirs.ancient_path.clone();
 
 		let service = try!(ClientService::start(
 			client_config,
@@ -186,27 +184,26 @@ impl SnapshotCommand {
 
 		let snapshot = service.snapshot_service();
 
-		if let Some(file) = file {
+		let result = if let Some(file) = file {
 			info!("Attempting to restore from snapshot at '{}'", file);
 
-			let reader = PackedReader::new(Path::new(&file))
+			PackedReader::new(Path::new(&file))
 				.map_err(|e| format!("Couldn't open snapshot file: {}", e))
-				.and_then(|x| x.ok_or("Snapshot file has invalid format.".into()));
-
-			let reader = try!(reader);
-			try!(restore_using(snapshot, &reader, true));
+				.and_then(|x| x.ok_or("Snapshot file has invalid format.".into()))
+				.and_then(|reader| restore_using(snapshot, &reader, true))
 		} else {
 			info!("Attempting to restore from local snapshot.");
 
 			// attempting restoration with recovery will lead to deadlock
 			// as we currently hold a read lock on the service's reader.
 			match *snapshot.reader() {
-				Some(ref reader) => try!(restore_using(snapshot.clone(), reader, false)),
-				None => return Err("No local snapshot found.".into()),
+				Some(ref reader) => restore_using(snapshot.clone(), reader, false),
+				None => Err("No local snapshot found.".into()),
 			}
-		}
+		};
 
-		Ok(())
+		try!(service.shutdown());
+		result
 	}
 
 	/// Take a snapshot from the head of the chain.
@@ -240,7 +237,9 @@ impl SnapshotCommand {
 			}
  		});
 
-		if let Err(e) = service.client().take_snapshot(writer, block_at, &*progress) {
+		let result = service.client().take_snapshot(writer, block_at, &*progress);
+		try!(service.shutdown());
+		if let Err(e) = result {
 			let _ = ::std::fs::remove_file(&file_path);
 			return Err(format!("Encountered fatal error while creating snapshot: {}", e));
 		}
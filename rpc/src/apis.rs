@@ -0,0 +1,192 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Sets of APIs exposed over a transport.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// A single RPC API.
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub enum Api {
+	/// Web3 (Safe)
+	Web3,
+	/// Net (Safe)
+	Net,
+	/// Eth (Safe)
+	Eth,
+	/// Personal: account management and signing (Unsafe)
+	Personal,
+	/// Signer: confirming pending requests (Unsafe, Trusted Signer only)
+	Signer,
+	/// Ethcore (Safe)
+	Ethcore,
+	/// Ethcore Set: changing node settings at runtime (Unsafe)
+	EthcoreSet,
+	/// Traces (Safe)
+	Traces,
+	/// Rpc (Safe)
+	Rpc,
+	/// Private transactions: exposes decrypted private contract state (Unsafe)
+	Private,
+}
+
+impl FromStr for Api {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		use self::Api::*;
+
+		match s {
+			"web3" => Ok(Web3),
+			"net" => Ok(Net),
+			"eth" => Ok(Eth),
+			"personal" => Ok(Personal),
+			"signer" => Ok(Signer),
+			"ethcore" => Ok(Ethcore),
+			"ethcore_set" => Ok(EthcoreSet),
+			"traces" => Ok(Traces),
+			"rpc" => Ok(Rpc),
+			"private" => Ok(Private),
+			api => Err(format!("Unknown api: {}", api))
+		}
+	}
+}
+
+impl Api {
+	/// Is this API safe to expose to anyone who can reach the transport?
+	pub fn is_safe(&self) -> bool {
+		match *self {
+			Api::Personal | Api::Signer | Api::EthcoreSet | Api::Private => false,
+			_ => true,
+		}
+	}
+}
+
+/// Set of APIs exposed over a transport.
+#[derive(Debug)]
+pub enum ApiSet {
+	/// All APIs. Used by the Trusted Signer.
+	SafeContext,
+	/// APIs for local transports, including account management.
+	UnsafeContext,
+	/// APIs which are safe to expose to any client, e.g. over HTTP.
+	PublicContext,
+	/// Explicit list of APIs.
+	List(HashSet<Api>),
+}
+
+impl Default for ApiSet {
+	fn default() -> Self {
+		ApiSet::UnsafeContext
+	}
+}
+
+impl PartialEq for ApiSet {
+	fn eq(&self, other: &Self) -> bool {
+		self.list_apis() == other.list_apis()
+	}
+}
+
+impl FromStr for ApiSet {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.split(',')
+			.map(Api::from_str)
+			.collect::<Result<_, _>>()
+			.map(ApiSet::List)
+	}
+}
+
+impl ApiSet {
+	/// APIs in this set.
+	pub fn list_apis(&self) -> HashSet<Api> {
+		match *self {
+			ApiSet::List(ref apis) => apis.clone(),
+			ApiSet::PublicContext => {
+				vec![Api::Web3, Api::Net, Api::Eth, Api::Ethcore, Api::Traces, Api::Rpc]
+					.into_iter().collect()
+			},
+			ApiSet::UnsafeContext => {
+				vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Ethcore, Api::Traces, Api::Rpc]
+					.into_iter().collect()
+			},
+			ApiSet::SafeContext => {
+				vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Signer, Api::Ethcore, Api::EthcoreSet, Api::Traces, Api::Rpc, Api::Private]
+					.into_iter().collect()
+			},
+		}
+	}
+
+	/// APIs in this set which allow managing accounts or the node.
+	pub fn unsafe_apis(&self) -> Vec<Api> {
+		self.list_apis().into_iter().filter(|api| !api.is_safe()).collect()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Api, ApiSet};
+
+	#[test]
+	fn test_api_parsing() {
+		assert_eq!(Api::Web3, "web3".parse().unwrap());
+		assert_eq!(Api::Net, "net".parse().unwrap());
+		assert_eq!(Api::Eth, "eth".parse().unwrap());
+		assert_eq!(Api::Personal, "personal".parse().unwrap());
+		assert_eq!(Api::Signer, "signer".parse().unwrap());
+		assert_eq!(Api::Ethcore, "ethcore".parse().unwrap());
+		assert_eq!(Api::EthcoreSet, "ethcore_set".parse().unwrap());
+		assert_eq!(Api::Traces, "traces".parse().unwrap());
+		assert_eq!(Api::Rpc, "rpc".parse().unwrap());
+		assert_eq!(Api::Private, "private".parse().unwrap());
+		assert!("rp".parse::<Api>().is_err());
+	}
+
+	#[test]
+	fn test_api_set_default() {
+		assert_eq!(ApiSet::UnsafeContext, ApiSet::default());
+	}
+
+	#[test]
+	fn test_api_set_parsing() {
+		assert_eq!(ApiSet::List(vec![Api::Web3, Api::Eth].into_iter().collect()), "web3,eth".parse().unwrap());
+	}
+
+	#[test]
+	fn test_api_set_public_context() {
+		let expected = vec![Api::Web3, Api::Net, Api::Eth, Api::Ethcore, Api::Traces, Api::Rpc]
+			.into_iter().collect();
+		assert_eq!(ApiSet::PublicContext.list_apis(), expected);
+		assert!(ApiSet::PublicContext.unsafe_apis().is_empty());
+	}
+
+	#[test]
+	fn test_api_set_unsafe_context() {
+		let expected = vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Ethcore, Api::Traces, Api::Rpc]
+			.into_iter().collect();
+		assert_eq!(ApiSet::UnsafeContext.list_apis(), expected);
+		assert_eq!(ApiSet::UnsafeContext.unsafe_apis(), vec![Api::Personal]);
+	}
+
+	#[test]
+	fn test_api_set_safe_context() {
+		let expected = vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Signer, Api::Ethcore, Api::EthcoreSet, Api::Traces, Api::Rpc, Api::Private]
+			.into_iter().collect();
+		assert_eq!(ApiSet::SafeContext.list_apis(), expected);
+	}
+}
@@ -0,0 +1,423 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! JSON-RPC over HTTP with request limits.
+
+use std::{io, thread};
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use hyper::{self, header, server, Control, Decoder, Encoder, Next, StatusCode};
+use hyper::header::AccessControlAllowOrigin;
+use hyper::method::Method;
+use hyper::net::HttpStream;
+use jsonrpc_core::IoHandler;
+use jsonrpc_http_server::is_host_header_valid;
+use io::PanicHandler;
+use util::Mutex;
+use limits::{RequestLimits, AddressRateLimiter};
+
+/// Headers browsers may send with requests, returned in responses to CORS preflight requests.
+const ALLOWED_HEADERS: &'static [u8] = b"Origin, Content-Type, Accept";
+
+/// HTTP server startup error.
+#[derive(Debug)]
+pub enum RpcServerError {
+	/// Wrapped `std::io::Error`
+	IoError(io::Error),
+	/// Other `hyper` error
+	Other(hyper::error::Error),
+}
+
+impl From<hyper::error::Error> for RpcServerError {
+	fn from(err: hyper::error::Error) -> Self {
+		match err {
+			hyper::error::Error::Io(e) => RpcServerError::IoError(e),
+			e => RpcServerError::Other(e),
+		}
+	}
+}
+
+/// Returns the `Access-Control-Allow-Origin` header for a request from `origin`.
+/// `None` allows every origin.
+fn cors_header(cors_domains: &Option<Vec<AccessControlAllowOrigin>>, origin: Option<&str>) -> Option<AccessControlAllowOrigin> {
+	match (cors_domains.as_ref(), origin) {
+		(None, _) => Some(AccessControlAllowOrigin::Any),
+		(Some(_), None) => None,
+		(Some(domains), Some(origin)) => domains.iter().filter_map(|domain| match *domain {
+			AccessControlAllowOrigin::Any => Some(AccessControlAllowOrigin::Any),
+			AccessControlAllowOrigin::Null if origin == "null" => Some(AccessControlAllowOrigin::Null),
+			AccessControlAllowOrigin::Value(ref v) if v == origin => Some(AccessControlAllowOrigin::Value(v.clone())),
+			_ => None,
+		}).next(),
+	}
+}
+
+/// Adds the address the server is listening on to `allowed_hosts`.
+fn allowed_hosts(hosts: Option<Vec<String>>, addr: &SocketAddr) -> Option<Vec<String>> {
+	hosts.map(|mut hosts| {
+		let bind_address = format!("{}", addr);
+		hosts.push(bind_address.replace("127.0.0.1", "localhost"));
+		hosts.push(bind_address);
+		hosts
+	})
+}
+
+struct Reply {
+	status: StatusCode,
+	content: String,
+	json: bool,
+}
+
+impl Reply {
+	fn json(status: StatusCode, content: String) -> Self {
+		Reply { status: status, content: content, json: true }
+	}
+
+	fn error(status: StatusCode, content: &str) -> Self {
+		Reply { status: status, content: content.into(), json: false }
+	}
+}
+
+struct Handler {
+	handler: Arc<IoHandler>,
+	control: Option<Control>,
+	cors_domains: Arc<Option<Vec<AccessControlAllowOrigin>>>,
+	allowed_hosts: Arc<Option<Vec<String>>>,
+	limits: Arc<RequestLimits>,
+	rate_limiter: Arc<Mutex<AddressRateLimiter>>,
+	panic_handler: Arc<PanicHandler>,
+	cors: Option<AccessControlAllowOrigin>,
+	preflight: bool,
+	request: Vec<u8>,
+	reply: Arc<Mutex<Option<Reply>>>,
+	write_pos: usize,
+}
+
+impl Handler {
+	fn respond(&mut self, reply: Reply) -> Next {
+		*self.reply.lock() = Some(reply);
+		Next::write()
+	}
+
+	fn process(&mut self) -> Next {
+		let request = match String::from_utf8(::std::mem::replace(&mut self.request, Vec::new())) {
+			Ok(request) => request,
+			Err(_) => return self.respond(Reply::error(StatusCode::BadRequest, "Request is not valid UTF-8.")),
+		};
+
+		if let Err(response) = self.limits.check(&request) {
+			debug!(target: "rpc", "Rejected HTTP request: {}", response);
+			return self.respond(Reply::json(StatusCode::Ok, response));
+		}
+
+		match self.handler.handle_request(&request) {
+			Some(async) => {
+				let reply = self.reply.clone();
+				let control = self.control.take().expect("Request is processed only once; control is set at start; qed");
+				async.on_result(move |result| {
+					*reply.lock() = Some(Reply::json(StatusCode::Ok, result));
+					if let Err(e) = control.ready(Next::write()) {
+						warn!(target: "rpc", "Error while sending HTTP response: {:?}", e);
+					}
+				});
+				Next::wait()
+			},
+			// Notifications have no response.
+			None => self.respond(Reply::json(StatusCode::Ok, String::new())),
+		}
+	}
+}
+
+impl server::Handler<HttpStream> for Handler {
+	fn on_request(&mut self, request: server::Request<HttpStream>) -> Next {
+		if let Some(ref hosts) = *self.allowed_hosts {
+			if !is_host_header_valid(&request, hosts) {
+				return self.respond(Reply::error(StatusCode::Forbidden, "Provided Host header is not whitelisted."));
+			}
+		}
+
+		let origin = request.headers().get_raw("origin")
+			.and_then(|list| list.get(0))
+			.and_then(|origin| String::from_utf8(origin.clone()).ok());
+		self.cors = cors_header(&self.cors_domains, origin.as_ref().map(|o| o.as_str()));
+
+		match *request.method() {
+			Method::Options => {
+				self.preflight = true;
+				return self.respond(Reply::json(StatusCode::Ok, String::new()));
+			},
+			Method::Post => {},
+			_ => return self.respond(Reply::error(StatusCode::MethodNotAllowed, "Only POST requests are supported.")),
+		}
+
+		let address = request.transport().0.peer_addr()
+			.map(|addr| addr.ip())
+			.unwrap_or_else(|_| IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)));
+		if let Err(response) = self.rate_limiter.lock().check(address) {
+			debug!(target: "rpc", "Rejected HTTP request: {}", response);
+			return self.respond(Reply::json(StatusCode::TooManyRequests, response));
+		}
+
+		// Don't even read the payload if it's known to be too large.
+		if let Some(&header::ContentLength(len)) = request.headers().get::<header::ContentLength>() {
+			if let Err(response) = self.limits.check_payload(len as usize) {
+				debug!(target: "rpc", "Rejected HTTP request: {}", response);
+				return self.respond(Reply::json(StatusCode::PayloadTooLarge, response));
+			}
+		}
+
+		Next::read()
+	}
+
+	fn on_request_readable(&mut self, decoder: &mut Decoder<HttpStream>) -> Next {
+		let mut buf = [0u8; 4096];
+		loop {
+			match decoder.read(&mut buf) {
+				Ok(0) => return self.process(),
+				Ok(n) => {
+					self.request.extend_from_slice(&buf[..n]);
+					// Stop reading as soon as the payload limit is exceeded.
+					if self.request.len() > self.limits.max_payload {
+						return self.process();
+					}
+				},
+				Err(e) => return match e.kind() {
+					io::ErrorKind::WouldBlock => Next::read(),
+					_ => Next::end(),
+				},
+			}
+		}
+	}
+
+	fn on_response(&mut self, res: &mut server::Response) -> Next {
+		let (status, json) = match *self.reply.lock() {
+			Some(ref reply) => (reply.status, reply.json),
+			None => return Next::end(),
+		};
+
+		res.set_status(status);
+		{
+			let headers = res.headers_mut();
+			if json {
+				headers.set(header::ContentType::json());
+			} else {
+				headers.set(header::ContentType::plaintext());
+			}
+			headers.set(header::Allow(vec![Method::Options, Method::Post]));
+			if let Some(cors) = self.cors.take() {
+				headers.set(cors);
+				if self.preflight {
+					headers.set(header::AccessControlAllowMethods(vec![Method::Options, Method::Post]));
+					headers.set_raw("Access-Control-Allow-Headers", vec![ALLOWED_HEADERS.to_vec()]);
+				}
+			}
+		}
+		Next::write()
+	}
+
+	fn on_response_writable(&mut self, encoder: &mut Encoder<HttpStream>) -> Next {
+		let reply = self.reply.lock();
+		let bytes = match *reply {
+			Some(ref reply) => reply.content.as_bytes(),
+			None => return Next::end(),
+		};
+		if self.write_pos == bytes.len() {
+			return Next::end();
+		}
+
+		match encoder.write(&bytes[self.write_pos..]) {
+			Ok(written) => {
+				self.write_pos += written;
+				Next::write()
+			},
+			Err(e) => match e.kind() {
+				io::ErrorKind::WouldBlock => Next::write(),
+				_ => Next::end(),
+			},
+		}
+	}
+}
+
+impl Drop for Handler {
+	fn drop(&mut self) {
+		if thread::panicking() {
+			self.panic_handler.notify_all("Panic in RPC thread.".to_owned());
+		}
+	}
+}
+
+/// HTTP server handle. The server is shut down when the handle is dropped.
+pub struct Server {
+	server: Option<server::Listening>,
+}
+
+impl Server {
+	/// Starts a new server in a separate thread.
+	/// Requests exceeding `limits` are rejected without being processed. HTTP requests do not belong
+	/// to a long-lived connection, so the rate limit applies to all requests from the same address.
+	pub fn start(
+		addr: &SocketAddr,
+		handler: Arc<IoHandler>,
+		cors_domains: Option<Vec<AccessControlAllowOrigin>>,
+		allowed_hosts: Option<Vec<String>>,
+		limits: RequestLimits,
+		panic_handler: Arc<PanicHandler>,
+	) -> Result<Server, RpcServerError> {
+		let cors_domains = Arc::new(cors_domains);
+		let allowed_hosts = Arc::new(self::allowed_hosts(allowed_hosts, addr));
+		let rate_limiter = Arc::new(Mutex::new(limits.address_rate_limiter()));
+		let limits = Arc::new(limits);
+
+		let (listening, server) = try!(try!(hyper::Server::http(addr)).handle(move |control| Handler {
+			handler: handler.clone(),
+			control: Some(control),
+			cors_domains: cors_domains.clone(),
+			allowed_hosts: allowed_hosts.clone(),
+			limits: limits.clone(),
+			rate_limiter: rate_limiter.clone(),
+			panic_handler: panic_handler.clone(),
+			cors: None,
+			preflight: false,
+			request: Vec::new(),
+			reply: Arc::new(Mutex::new(None)),
+			write_pos: 0,
+		}));
+
+		thread::Builder::new()
+			.name("jsonrpc-http".into())
+			.spawn(move || server.run())
+			.expect("Thread spawning never fails; qed");
+
+		Ok(Server {
+			server: Some(listening),
+		})
+	}
+
+	/// Returns address that this server is bound to.
+	pub fn addr(&self) -> &SocketAddr {
+		self.server.as_ref().expect("server is always Some at the start; it's consumed only when object is dropped; qed").addr()
+	}
+}
+
+impl Drop for Server {
+	fn drop(&mut self) {
+		self.server.take().expect("server is always Some at the start; it's consumed only when object is dropped; qed").close()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::{Read, Write};
+	use std::net::TcpStream;
+	use std::sync::Arc;
+	use std::time::Duration;
+	use hyper::header::AccessControlAllowOrigin;
+	use jsonrpc_core::{IoHandler, Params, Value};
+	use io::PanicHandler;
+	use limits::RequestLimits;
+	use super::{cors_header, Server};
+
+	fn serve(limits: RequestLimits) -> Server {
+		let io = IoHandler::new();
+		io.add_method("hello", |_params: Params| Ok(Value::String("world".into())));
+		Server::start(&"127.0.0.1:0".parse().unwrap(), Arc::new(io), None, None, limits, PanicHandler::new_in_arc()).unwrap()
+	}
+
+	fn send(server: &Server, request: &str) -> String {
+		let mut stream = TcpStream::connect(server.addr()).unwrap();
+		stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+		stream.write_all(request.as_bytes()).unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		response
+	}
+
+	fn post(server: &Server, body: &str) -> String {
+		send(server, &format!(
+			"POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+			server.addr(), body.len(), body
+		))
+	}
+
+	fn limits() -> RequestLimits {
+		RequestLimits {
+			max_batch_size: 2,
+			max_payload: 128,
+			max_requests_per_second: None,
+		}
+	}
+
+	#[test]
+	fn should_handle_requests_within_limits() {
+		let server = serve(limits());
+		let response = post(&server, r#"[{"jsonrpc":"2.0","method":"hello","id":1},{"jsonrpc":"2.0","method":"hello","id":2}]"#);
+		assert!(response.starts_with("HTTP/1.1 200"), response);
+		assert!(response.ends_with(r#"[{"jsonrpc":"2.0","result":"world","id":1},{"jsonrpc":"2.0","result":"world","id":2}]"#), response);
+	}
+
+	#[test]
+	fn should_reject_too_large_batch() {
+		let server = serve(limits());
+		let request = r#"[{"jsonrpc":"2.0","method":"hello","id":1},{"jsonrpc":"2.0","method":"hello","id":2},{"jsonrpc":"2.0","method":"hello","id":3}]"#;
+		let response = post(&server, request);
+		assert!(response.contains(r#""code":-32070"#), response);
+		assert!(!response.contains("world"), response);
+	}
+
+	#[test]
+	fn should_reject_too_large_request() {
+		let server = serve(limits());
+		let request = format!(r#"{{"jsonrpc":"2.0","method":"hello","params":["{}"],"id":1}}"#, ::std::iter::repeat("a").take(256).collect::<String>());
+		let response = post(&server, &request);
+		assert!(response.contains(r#""code":-32071"#), response);
+	}
+
+	#[test]
+	fn should_rate_limit_requests() {
+		let server = serve(RequestLimits {
+			max_requests_per_second: Some(1),
+			..limits()
+		});
+		let request = r#"{"jsonrpc":"2.0","method":"hello","id":1}"#;
+		assert!(post(&server, request).contains("world"));
+		let response = post(&server, request);
+		assert!(response.starts_with("HTTP/1.1 429"), response);
+		assert!(response.contains(r#""code":-32072"#), response);
+	}
+
+	#[test]
+	fn should_answer_preflight_requests() {
+		let server = serve(limits());
+		let response = send(&server, &format!(
+			"OPTIONS / HTTP/1.1\r\nHost: {}\r\nOrigin: http://parity.io\r\nAccess-Control-Request-Method: POST\r\nAccess-Control-Request-Headers: content-type\r\nConnection: close\r\n\r\n",
+			server.addr()
+		));
+		assert!(response.starts_with("HTTP/1.1 200"), response);
+		assert!(response.contains("Access-Control-Allow-Origin: *"), response);
+		assert!(response.contains("Access-Control-Allow-Headers: Origin, Content-Type, Accept"), response);
+	}
+
+	#[test]
+	fn should_return_cors_header_for_allowed_origins() {
+		let domains = Some(vec![AccessControlAllowOrigin::Null, AccessControlAllowOrigin::Value("http://parity.io".into())]);
+		assert_eq!(cors_header(&None, Some("http://ethcore.io")), Some(AccessControlAllowOrigin::Any));
+		assert_eq!(cors_header(&domains, Some("null")), Some(AccessControlAllowOrigin::Null));
+		assert_eq!(cors_header(&domains, Some("http://parity.io")), Some(AccessControlAllowOrigin::Value("http://parity.io".into())));
+		assert_eq!(cors_header(&domains, Some("http://ethcore.io")), None);
+		assert_eq!(cors_header(&domains, None), None);
+	}
+}
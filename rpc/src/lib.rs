@@ -24,6 +24,7 @@ extern crate serde;
 extern crate serde_json;
 extern crate jsonrpc_core;
 extern crate jsonrpc_http_server;
+extern crate hyper;
 
 extern crate ethcore_io as io;
 extern crate ethcore;
@@ -37,6 +38,8 @@ extern crate ethcore_ipc;
 extern crate time;
 extern crate rlp;
 extern crate fetch;
+extern crate parity_updater as updater;
+extern crate ws;
 
 #[macro_use]
 extern crate log;
@@ -53,9 +56,16 @@ use std::net::SocketAddr;
 use io::PanicHandler;
 use self::jsonrpc_core::{IoHandler, IoDelegate};
 
-pub use jsonrpc_http_server::{ServerBuilder, Server, RpcServerError};
+pub use http_server::{Server, RpcServerError};
+pub use ws_server::{WsServer, WsServerError};
+pub use apis::{Api, ApiSet};
+pub use limits::RequestLimits;
 pub mod v1;
-pub use v1::{SigningQueue, SignerService, ConfirmationsQueue, NetworkSettings};
+mod apis;
+mod http_server;
+mod limits;
+mod ws_server;
+pub use v1::{SigningQueue, SignerService, ConfirmationsQueue, NetworkSettings, StorageWatcher, HeavyQueryOptions};
 
 /// An object that can be extended with `IoDelegates`
 pub trait Extendable {
@@ -84,31 +94,43 @@ impl RpcServer {
 	}
 
 	/// Start http server asynchronously and returns result with `Server` handle on success or an error.
+	/// CORS headers are returned only to `cors_domains` and requests with a `Host` header outside of
+	/// `allowed_hosts` are rejected. `None` disables the respective validation.
+	/// Requests exceeding `limits` are rejected without being processed.
 	pub fn start_http(
 		&self,
 		addr: &SocketAddr,
 		cors_domains: Option<Vec<String>>,
 		allowed_hosts: Option<Vec<String>>,
+		limits: RequestLimits,
 		panic_handler: Arc<PanicHandler>,
 		) -> Result<Server, RpcServerError> {
 
 		let cors_domains = cors_domains.map(|domains| {
 			domains.into_iter()
 				.map(|v| match v.as_str() {
-					"*" => jsonrpc_http_server::AccessControlAllowOrigin::Any,
-					"null" => jsonrpc_http_server::AccessControlAllowOrigin::Null,
-					v => jsonrpc_http_server::AccessControlAllowOrigin::Value(v.into()),
+					"*" => hyper::header::AccessControlAllowOrigin::Any,
+					"null" => hyper::header::AccessControlAllowOrigin::Null,
+					v => hyper::header::AccessControlAllowOrigin::Value(v.into()),
 				})
 				.collect()
 		});
 
-		ServerBuilder::new(self.handler.clone())
-			.cors(cors_domains.into())
-			.allowed_hosts(allowed_hosts.into())
-			.panic_handler(move || {
-				panic_handler.notify_all("Panic in RPC thread.".to_owned());
-			})
-			.start_http(addr)
+		Server::start(addr, self.handler.clone(), cors_domains, allowed_hosts, limits, panic_handler)
+	}
+
+	/// Start `WebSockets` server asynchronously and returns result with `WsServer` handle on success or an error.
+	/// Browser connections are accepted only from `allowed_origins`, unless it is `None`.
+	/// Requests exceeding `limits` are rejected without being processed.
+	pub fn start_ws(
+		&self,
+		addr: &SocketAddr,
+		allowed_origins: Option<Vec<String>>,
+		limits: RequestLimits,
+		panic_handler: Arc<PanicHandler>,
+		storage_watcher: Option<Arc<StorageWatcher>>,
+		) -> Result<WsServer, WsServerError> {
+		WsServer::start(addr, self.handler.clone(), allowed_origins, limits, panic_handler, storage_watcher)
 	}
 
 	/// Start ipc server asynchronously and returns result with `Server` handle on success or an error.
@@ -0,0 +1,280 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits on the size of incoming JSON-RPC requests.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use serde_json;
+use jsonrpc_core::{Error, Failure, Id, Output, Response, Version};
+use v1::helpers::errors;
+
+/// Default maximal number of calls in a single batch.
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 1000;
+/// Default maximal size of a single request in bytes.
+pub const DEFAULT_MAX_PAYLOAD: usize = 5 * 1024 * 1024;
+/// Number of addresses tracked by an `AddressRateLimiter` before idle ones are dropped.
+const MAX_TRACKED_ADDRESSES: usize = 1024;
+
+/// Limits on incoming requests.
+/// Calls within an accepted batch are executed independently and each one gets its own result or error.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RequestLimits {
+	/// Maximal number of calls in a single batch.
+	pub max_batch_size: usize,
+	/// Maximal size of a single request (batch or call) in bytes.
+	pub max_payload: usize,
+	/// Maximal number of requests per second on a single connection.
+	pub max_requests_per_second: Option<usize>,
+}
+
+impl Default for RequestLimits {
+	fn default() -> Self {
+		RequestLimits {
+			max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+			max_payload: DEFAULT_MAX_PAYLOAD,
+			max_requests_per_second: None,
+		}
+	}
+}
+
+fn error_response(error: Error) -> String {
+	let response = Response::Single(Output::Failure(Failure {
+		jsonrpc: Version::V2,
+		error: error,
+		id: Id::Null,
+	}));
+	serde_json::to_string(&response).expect("Response is always serializable; qed")
+}
+
+impl RequestLimits {
+	/// Checks the raw request against the limits.
+	/// Returns a serialized error response if the request should not be processed.
+	pub fn check(&self, request: &str) -> Result<(), String> {
+		self.validate(request).map_err(error_response)
+	}
+
+	/// Checks the size of a request before it is read.
+	/// Returns a serialized error response if the request should not be processed.
+	pub fn check_payload(&self, len: usize) -> Result<(), String> {
+		self.validate_payload(len).map_err(error_response)
+	}
+
+	/// Creates a rate limiter for a new connection.
+	pub fn rate_limiter(&self) -> RateLimiter {
+		RateLimiter {
+			limit: self.max_requests_per_second,
+			window_start: Instant::now(),
+			count: 0,
+		}
+	}
+
+	/// Creates a rate limiter for transports without long-lived connections, limiting each remote address.
+	pub fn address_rate_limiter(&self) -> AddressRateLimiter {
+		AddressRateLimiter {
+			limits: self.clone(),
+			limiters: HashMap::new(),
+		}
+	}
+
+	fn validate_payload(&self, len: usize) -> Result<(), Error> {
+		match len > self.max_payload {
+			true => Err(errors::request_too_large(len, self.max_payload)),
+			false => Ok(()),
+		}
+	}
+
+	fn validate(&self, request: &str) -> Result<(), Error> {
+		try!(self.validate_payload(request.len()));
+
+		// Only batches need to be inspected, single calls are parsed by the handler.
+		if !request.trim_left().starts_with('[') {
+			return Ok(());
+		}
+
+		let calls = batch_size(request);
+		match calls > self.max_batch_size {
+			true => Err(errors::batch_too_large(calls, self.max_batch_size)),
+			false => Ok(()),
+		}
+	}
+}
+
+/// Number of elements of the JSON array `batch`, counted without parsing them.
+/// Invalid requests are reported by the handler, so the count only needs to be right for valid ones.
+fn batch_size(batch: &str) -> usize {
+	let mut depth = 0i32;
+	let mut in_string = false;
+	let mut escaped = false;
+	let mut commas = 0;
+	let mut has_elements = false;
+	for byte in batch.bytes() {
+		if in_string {
+			match byte {
+				_ if escaped => escaped = false,
+				b'\\' => escaped = true,
+				b'"' => in_string = false,
+				_ => {},
+			}
+			continue;
+		}
+		if depth == 1 && byte != b']' && !(byte as char).is_whitespace() {
+			has_elements = true;
+		}
+		match byte {
+			b'"' => in_string = true,
+			b'[' | b'{' => depth += 1,
+			b']' | b'}' => depth -= 1,
+			b',' if depth == 1 => commas += 1,
+			_ => {},
+		}
+	}
+	match has_elements {
+		true => commas + 1,
+		false => 0,
+	}
+}
+
+/// Limits the number of requests from each remote address within one second windows.
+#[derive(Debug)]
+pub struct AddressRateLimiter {
+	limits: RequestLimits,
+	limiters: HashMap<IpAddr, RateLimiter>,
+}
+
+impl AddressRateLimiter {
+	/// Records a new request from `address`.
+	/// Returns a serialized error response if the address exceeded its limit.
+	pub fn check(&mut self, address: IpAddr) -> Result<(), String> {
+		if self.limits.max_requests_per_second.is_none() {
+			return Ok(());
+		}
+		if self.limiters.len() >= MAX_TRACKED_ADDRESSES && !self.limiters.contains_key(&address) {
+			// an idle address starts a new window anyway.
+			self.limiters.retain(|_, limiter| limiter.window_start.elapsed() < Duration::from_secs(1));
+		}
+		let limits = &self.limits;
+		self.limiters.entry(address).or_insert_with(|| limits.rate_limiter()).check()
+	}
+}
+
+/// Limits the number of requests on a single connection within one second windows.
+#[derive(Debug)]
+pub struct RateLimiter {
+	limit: Option<usize>,
+	window_start: Instant,
+	count: usize,
+}
+
+impl RateLimiter {
+	/// Records a new request.
+	/// Returns a serialized error response if the connection exceeded its limit.
+	pub fn check(&mut self) -> Result<(), String> {
+		let limit = match self.limit {
+			Some(limit) => limit,
+			None => return Ok(()),
+		};
+
+		if self.window_start.elapsed() >= Duration::from_secs(1) {
+			self.window_start = Instant::now();
+			self.count = 0;
+		}
+
+		self.count += 1;
+		if self.count > limit {
+			return Err(error_response(errors::too_many_requests(limit)));
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::iter;
+	use std::net::IpAddr;
+	use super::{RequestLimits, batch_size};
+
+	fn limits() -> RequestLimits {
+		RequestLimits {
+			max_batch_size: 2,
+			max_payload: 128,
+			max_requests_per_second: Some(2),
+		}
+	}
+
+	#[test]
+	fn should_accept_requests_within_limits() {
+		let limits = limits();
+		assert_eq!(limits.check(r#"{"jsonrpc":"2.0","method":"web3_clientVersion","params":[],"id":1}"#), Ok(()));
+		assert_eq!(limits.check(r#"[{"jsonrpc":"2.0","method":"a","id":1},{"jsonrpc":"2.0","method":"b","id":2}]"#), Ok(()));
+		assert_eq!(limits.check("[invalid"), Ok(()));
+	}
+
+	#[test]
+	fn should_reject_too_large_batch() {
+		let request = r#"[{"jsonrpc":"2.0","method":"a","id":1},{"jsonrpc":"2.0","method":"b","id":2},{"jsonrpc":"2.0","method":"c","id":3}]"#;
+		let response = r#"{"jsonrpc":"2.0","error":{"code":-32070,"message":"Batch of 3 requests exceeds the limit of 2 requests.","data":null},"id":null}"#;
+		assert_eq!(limits().check(request), Err(response.to_owned()));
+	}
+
+	#[test]
+	fn should_reject_too_large_request() {
+		let request = format!(r#"{{"jsonrpc":"2.0","method":"eth_sendRawTransaction","params":["0x{}"],"id":1}}"#, iter::repeat("00").take(64).collect::<String>());
+		let response = format!(r#"{{"jsonrpc":"2.0","error":{{"code":-32071,"message":"Request of {} bytes exceeds the limit of 128 bytes.","data":null}},"id":null}}"#, request.len());
+		assert_eq!(limits().check(&request), Err(response));
+	}
+
+	#[test]
+	fn should_limit_requests_per_connection() {
+		let limits = limits();
+		let mut limiter = limits.rate_limiter();
+		assert_eq!(limiter.check(), Ok(()));
+		assert_eq!(limiter.check(), Ok(()));
+		let response = r#"{"jsonrpc":"2.0","error":{"code":-32072,"message":"Too many requests. The limit is 2 requests per second.","data":null},"id":null}"#;
+		assert_eq!(limiter.check(), Err(response.to_owned()));
+
+		// other connections are not affected
+		assert_eq!(limits.rate_limiter().check(), Ok(()));
+	}
+
+	#[test]
+	fn should_limit_requests_per_address() {
+		let mut limiter = limits().address_rate_limiter();
+		let first: IpAddr = "127.0.0.1".parse().unwrap();
+		let second: IpAddr = "10.0.0.1".parse().unwrap();
+		assert_eq!(limiter.check(first), Ok(()));
+		assert_eq!(limiter.check(first), Ok(()));
+		assert!(limiter.check(first).is_err());
+		assert_eq!(limiter.check(second), Ok(()));
+	}
+
+	#[test]
+	fn should_count_batch_elements() {
+		assert_eq!(batch_size("[]"), 0);
+		assert_eq!(batch_size(" [ ] "), 0);
+		assert_eq!(batch_size(r#"[{"method":"a","params":[1,2,{"x":","}]}]"#), 1);
+		assert_eq!(batch_size(r#"[{"method":"a\",]"},{"method":"b"}, {}]"#), 3);
+	}
+
+	#[test]
+	fn should_not_limit_requests_by_default() {
+		let mut limiter = RequestLimits::default().rate_limiter();
+		for _ in 0..10_000 {
+			assert_eq!(limiter.check(), Ok(()));
+		}
+	}
+}
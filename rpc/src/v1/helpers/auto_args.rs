@@ -40,6 +40,7 @@ use serde::{Serialize, Deserialize};
 /// Valid forms:
 ///  - `#[rpc(name = "name_here")]` (a synchronous rpc function which should be bound to the given name)
 ///  - `#[rpc(async, name = "name_here")]` (an async rpc function which should be bound to the given name)
+///  - `#[rpc(name = "name_here", alias = ["other_name"])]` (a synchronous rpc function additionally bound to the aliases)
 ///
 /// Synchronous function format:
 /// `fn foo(&self, Param1, Param2, Param3) -> Out`.
@@ -86,9 +87,41 @@ macro_rules! build_rpc_trait {
 		(name = $name: expr)
 		fn $method: ident (&self $(, $param: ty)*) -> Result<$out: ty, Error>
 	) => {
-		$del.add_method($name, move |base, params| {
-			(Self::$method as fn(&_ $(, $param)*) -> Result<$out, Error>).wrap_rpc(base, params)
-		})
+		{
+			// looked up once at registration rather than on every request.
+			let histogram = ::util::metrics::registry()
+				.histogram("parity_rpc_request_seconds", "Time taken to handle a synchronous RPC request.");
+			$del.add_method($name, move |base, params| {
+				let start = ::std::time::Instant::now();
+				let result = (Self::$method as fn(&_ $(, $param)*) -> Result<$out, Error>).wrap_rpc(base, params);
+				histogram.observe_since(start);
+				result
+			})
+		}
+	};
+
+	( WRAP $del: expr =>
+		(name = $name: expr, alias = [ ])
+		fn $method: ident (&self $(, $param: ty)*) -> Result<$out: ty, Error>
+	) => {
+		build_rpc_trait!(WRAP $del =>
+			(name = $name)
+			fn $method (&self $(, $param)*) -> Result<$out, Error>
+		)
+	};
+
+	( WRAP $del: expr =>
+		(name = $name: expr, alias = [ $alias: expr $(, $rest: expr)* ])
+		fn $method: ident (&self $(, $param: ty)*) -> Result<$out: ty, Error>
+	) => {
+		build_rpc_trait!(WRAP $del =>
+			(name = $alias)
+			fn $method (&self $(, $param)*) -> Result<$out, Error>
+		);
+		build_rpc_trait!(WRAP $del =>
+			(name = $name, alias = [ $($rest),* ])
+			fn $method (&self $(, $param)*) -> Result<$out, Error>
+		)
 	};
 
 	( WRAP $del: expr =>
@@ -14,19 +14,33 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use util::{Address, H256, U256, Uint};
+use util::{Address, H256, H520, U256, Uint, Bytes, Hashable};
 use util::bytes::ToPretty;
 use ethcore::miner::MinerService;
-use ethcore::client::MiningBlockChainClient;
+use ethcore::client::{BlockChainClient, MiningBlockChainClient, IpcMode};
 use ethcore::transaction::{Action, SignedTransaction, Transaction};
 use ethcore::account_provider::AccountProvider;
+use ethstore::typed_data::{self, TypedDataField};
+use ethkey::{recover, public_to_address};
 use jsonrpc_core::{Error, Value, to_value};
 use v1::helpers::TransactionRequest;
-use v1::types::{H256 as RpcH256, H520 as RpcH520};
+use v1::types::{H160 as RpcH160, H256 as RpcH256, H520 as RpcH520, Bytes as RpcBytes};
 use v1::helpers::errors;
 
-fn prepare_transaction<C, M>(client: &C, miner: &M, request: TransactionRequest) -> Transaction where C: MiningBlockChainClient, M: MinerService {
-	Transaction {
+/// Fails for requests which need the state when the client runs in relay or headers-only mode.
+pub fn ensure_state<C: BlockChainClient>(client: &C) -> Result<(), Error> {
+	if client.mode() == IpcMode::Relay {
+		return Err(errors::relay_mode());
+	}
+	match client.keeps_state() {
+		true => Ok(()),
+		false => Err(errors::headers_only()),
+	}
+}
+
+fn prepare_transaction<C, M>(client: &C, miner: &M, request: TransactionRequest) -> Result<Transaction, Error> where C: MiningBlockChainClient, M: MinerService {
+	try!(ensure_state(client));
+	Ok(Transaction {
 		nonce: request.nonce
 			.or_else(|| miner
 					 .last_nonce(&request.from)
@@ -38,12 +52,20 @@ fn prepare_transaction<C, M>(client: &C, miner: &M, request: TransactionRequest)
 		gas_price: request.gas_price.unwrap_or_else(|| default_gas_price(client, miner)),
 		value: request.value.unwrap_or_else(U256::zero),
 		data: request.data.map_or_else(Vec::new, |b| b.to_vec()),
-	}
+	})
 }
 
 pub fn dispatch_transaction<C, M>(client: &C, miner: &M, signed_transaction: SignedTransaction) -> Result<RpcH256, Error>
 	where C: MiningBlockChainClient, M: MinerService {
 	let hash = RpcH256::from(signed_transaction.hash());
+	if client.mode() == IpcMode::Relay {
+		// without state the transaction can only be gossiped.
+		return match client.relay_transactions(vec![signed_transaction]) {
+			0 => Err(errors::transaction_not_relayed()),
+			_ => Ok(hash),
+		};
+	}
+	try!(ensure_state(client));
 
 	let import = miner.import_own_transaction(client, signed_transaction);
 
@@ -58,16 +80,72 @@ pub fn signature_with_password(accounts: &AccountProvider, address: Address, has
 		.map(|hash| to_value(&RpcH520::from(hash)))
 }
 
-pub fn unlock_sign_and_dispatch<C, M>(client: &C, miner: &M, request: TransactionRequest, account_provider: &AccountProvider, password: String) -> Result<Value, Error>
+/// Hash of a message signed with `personal_sign`:
+/// `keccak("\x19Ethereum Signed Message:\n" + len(message) + message)`.
+/// The prefix makes the signature unusable as a transaction signature.
+pub fn eth_data_hash(mut data: Bytes) -> H256 {
+	let mut message_data = format!("\x19Ethereum Signed Message:\n{}", data.len()).into_bytes();
+	message_data.append(&mut data);
+	message_data.sha3()
+}
+
+/// Recovers the address which signed the prefixed message.
+pub fn recover_message_signer(data: Bytes, signature: H520) -> Result<Value, Error> {
+	let hash = eth_data_hash(data);
+	recover(&signature.into(), &hash)
+		.map(|public| to_value(&RpcH160::from(public_to_address(&public))))
+		.map_err(|e| errors::invalid_params("Signature", e))
+}
+
+/// Computes the hash of typed data to be signed.
+pub fn typed_data_hash(data: &[TypedDataField]) -> Result<H256, Error> {
+	typed_data::hash(data).map_err(|e| errors::invalid_params("Typed data", e.to_string()))
+}
+
+pub fn typed_data_signature_with_password(accounts: &AccountProvider, address: Address, data: Vec<TypedDataField>, pass: String) -> Result<Value, Error> {
+	typed_data_hash(&data).and_then(|hash| signature_with_password(accounts, address, hash, pass))
+}
+
+pub fn decrypt_with_password(accounts: &AccountProvider, address: Address, msg: Bytes, pass: String) -> Result<Value, Error> {
+	accounts.decrypt_with_password(address, pass, &[0; 0], &msg)
+		.map_err(errors::from_password_error)
+		.map(|plain| to_value(&RpcBytes::from(plain)))
+}
+
+/// Signs transaction with given password without dispatching it.
+pub fn unlock_sign<C, M>(client: &C, miner: &M, request: TransactionRequest, account_provider: &AccountProvider, password: String) -> Result<SignedTransaction, Error>
 	where C: MiningBlockChainClient, M: MinerService {
 
 	let address = request.from;
-	let signed_transaction = {
-		let t = prepare_transaction(client, miner, request);
-		let hash = t.hash();
-		let signature = try!(account_provider.sign_with_password(address, password, hash).map_err(errors::from_password_error));
-		t.with_signature(signature)
+	let t = try!(prepare_transaction(client, miner, request));
+	let signature = match account_provider.is_hardware_address(address) {
+		true => try!(account_provider.sign_with_hardware(address, &t).map_err(errors::from_hardware_error)),
+		false => try!(account_provider.sign_with_password(address, password, t.hash()).map_err(errors::from_password_error)),
+	};
+	Ok(t.with_signature(signature))
+}
+
+/// Signs transaction with unlocked account without dispatching it.
+pub fn sign<C, M>(client: &C, miner: &M, request: TransactionRequest, account_provider: &AccountProvider, address: Address) -> Result<SignedTransaction, Error>
+	where C: MiningBlockChainClient, M: MinerService {
+
+	let t = try!(prepare_transaction(client, miner, request));
+	let signature = match account_provider.is_hardware_address(address) {
+		true => try!(account_provider.sign_with_hardware(address, &t).map_err(errors::from_hardware_error)),
+		false => try!(account_provider.sign(address, t.hash()).map_err(errors::from_signing_error)),
 	};
+	Ok(t.with_signature(signature))
+}
+
+/// Returns raw RLP of signed transaction.
+pub fn signed_transaction_rlp(signed_transaction: &SignedTransaction) -> Value {
+	to_value(&RpcBytes::new(::rlp::encode(signed_transaction).to_vec()))
+}
+
+pub fn unlock_sign_and_dispatch<C, M>(client: &C, miner: &M, request: TransactionRequest, account_provider: &AccountProvider, password: String) -> Result<Value, Error>
+	where C: MiningBlockChainClient, M: MinerService {
+
+	let signed_transaction = try!(unlock_sign(client, miner, request, account_provider, password));
 
 	trace!(target: "miner", "send_transaction: dispatching tx: {}", ::rlp::encode(&signed_transaction).to_vec().pretty());
 	dispatch_transaction(&*client, &*miner, signed_transaction).map(to_value)
@@ -76,12 +154,7 @@ pub fn unlock_sign_and_dispatch<C, M>(client: &C, miner: &M, request: Transactio
 pub fn sign_and_dispatch<C, M>(client: &C, miner: &M, request: TransactionRequest, account_provider: &AccountProvider, address: Address) -> Result<Value, Error>
 	where C: MiningBlockChainClient, M: MinerService {
 
-	let signed_transaction = {
-		let t = prepare_transaction(client, miner, request);
-		let hash = t.hash();
-		let signature = try!(account_provider.sign(address, hash).map_err(errors::from_signing_error));
-		t.with_signature(signature)
-	};
+	let signed_transaction = try!(sign(client, miner, request, account_provider, address));
 
 	trace!(target: "miner", "send_transaction: dispatching tx: {}", ::rlp::encode(&signed_transaction).to_vec().pretty());
 	dispatch_transaction(&*client, &*miner, signed_transaction).map(to_value)
@@ -23,6 +23,7 @@ macro_rules! rpc_unimplemented {
 use std::fmt;
 use ethcore::error::Error as EthcoreError;
 use ethcore::account_provider::{Error as AccountError};
+use ethcore::private_transactions::Error as PrivateTransactionError;
 use fetch::FetchError;
 use jsonrpc_core::{Error, ErrorCode, Value};
 
@@ -41,8 +42,14 @@ mod codes {
 	pub const REQUEST_REJECTED: i64 = -32040;
 	pub const REQUEST_REJECTED_LIMIT: i64 = -32041;
 	pub const REQUEST_NOT_FOUND: i64 = -32042;
+	pub const NO_REFUSED_BLOCK: i64 = -32043;
 	pub const COMPILATION_ERROR: i64 = -32050;
 	pub const FETCH_ERROR: i64 = -32060;
+	pub const BATCH_TOO_LARGE: i64 = -32070;
+	pub const REQUEST_TOO_LARGE: i64 = -32071;
+	pub const TOO_MANY_REQUESTS: i64 = -32072;
+	pub const QUERY_TOO_HEAVY: i64 = -32073;
+	pub const PRIVATE_ERROR: i64 = -32080;
 }
 
 pub fn unimplemented() -> Error {
@@ -77,6 +84,46 @@ pub fn request_rejected_limit() -> Error {
 	}
 }
 
+pub fn no_refused_block() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::NO_REFUSED_BLOCK),
+		message: "No block with this hash was refused.".into(),
+		data: None,
+	}
+}
+
+pub fn batch_too_large(size: usize, limit: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::BATCH_TOO_LARGE),
+		message: format!("Batch of {} requests exceeds the limit of {} requests.", size, limit),
+		data: None,
+	}
+}
+
+pub fn request_too_large(size: usize, limit: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::REQUEST_TOO_LARGE),
+		message: format!("Request of {} bytes exceeds the limit of {} bytes.", size, limit),
+		data: None,
+	}
+}
+
+pub fn too_many_requests(limit: usize) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::TOO_MANY_REQUESTS),
+		message: format!("Too many requests. The limit is {} requests per second.", limit),
+		data: None,
+	}
+}
+
+pub fn query_too_heavy(method: &str) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::QUERY_TOO_HEAVY),
+		message: "Query too heavy, narrow the range of blocks.".into(),
+		data: Some(Value::String(method.into())),
+	}
+}
+
 pub fn account<T: fmt::Debug>(error: &str, details: T) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::ACCOUNT_ERROR),
@@ -117,6 +164,54 @@ pub fn state_pruned() -> Error {
 	}
 }
 
+pub fn state_pruned_without_traces() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+		message: "This request is not supported because your node is running with state pruning and without tracing. Run with --pruning=archive or --tracing on.".into(),
+		data: None
+	}
+}
+
+pub fn relay_mode() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+		message: "This request is not supported because your node is running in relay mode and keeps no state.".into(),
+		data: None
+	}
+}
+
+pub fn transaction_not_relayed() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::TRANSACTION_ERROR),
+		message: "Transaction has an invalid signature or was already relayed.".into(),
+		data: None
+	}
+}
+
+pub fn headers_only() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+		message: "This request is not supported because your node only stores block headers (--no-bodies) and keeps no state.".into(),
+		data: None
+	}
+}
+
+pub fn fat_db_required() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+		message: "This request is not supported because your node is running without fat DB. Run with --fat-db on.".into(),
+		data: None
+	}
+}
+
+pub fn unknown_block() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+		message: "Unknown block number".into(),
+		data: None
+	}
+}
+
 pub fn no_work() -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::NO_WORK),
@@ -181,6 +276,22 @@ pub fn from_password_error(error: AccountError) -> Error {
 	}
 }
 
+pub fn from_hardware_error(error: AccountError) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::ACCOUNT_ERROR),
+		message: "Hardware wallet did not sign the transaction. Make sure the device is attached, unlocked and the Ethereum application is opened.".into(),
+		data: Some(Value::String(format!("{}", error))),
+	}
+}
+
+pub fn from_private_transaction_error(error: PrivateTransactionError) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::PRIVATE_ERROR),
+		message: format!("{}", error),
+		data: None,
+	}
+}
+
 pub fn from_transaction_error(error: EthcoreError) -> Error {
 	use ethcore::error::TransactionError::*;
 
@@ -194,6 +305,9 @@ pub fn from_transaction_error(error: EthcoreError) -> Error {
 			LimitReached => {
 				"There are too many transactions in the queue. Your transaction was dropped due to limit. Try increasing the fee.".into()
 			},
+			QueueThrottled => {
+				"Transaction queue is throttled due to repeated chain reorganisations. Try again later.".into()
+			},
 			InsufficientGasPrice { minimal, got } => {
 				format!("Transaction gas price is too low. It does not satisfy your node's minimal gas price (minimal: {}, got: {}). Try increasing the gas price.", minimal, got)
 			},
@@ -204,6 +318,7 @@ pub fn from_transaction_error(error: EthcoreError) -> Error {
 				format!("Transaction cost exceeds current gas limit. Limit: {}, got: {}. Try decreasing supplied gas.", limit, got)
 			},
 			InvalidGasLimit(_) => "Supplied gas is beyond limit.".into(),
+			NotAllowed => "Transaction is not permitted by the chain's permission contract.".into(),
 		};
 		Error {
 			code: ErrorCode::ServerError(codes::TRANSACTION_ERROR),
@@ -0,0 +1,235 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Execution budget and slow query logging for heavy calls.
+
+use std::cmp;
+use std::fmt;
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+use jsonrpc_core::Error;
+use util::Address;
+use ethcore::client::{BlockChainClient, BlockID, TraceFilter};
+use ethcore::error::StateDiffError;
+use ethcore::filter::Filter;
+use ethcore::header::BlockNumber;
+use ethcore::log_entry::LocalizedLogEntry;
+use ethcore::state_diff::StateDiff;
+use ethcore::trace::LocalizedTrace;
+use ethcore::views::HeaderView;
+use v1::helpers::errors;
+
+/// Number of blocks whose blooms are looked up at once. The budget is checked between chunks
+/// and before each block read.
+const CHUNK_SIZE: BlockNumber = 1000;
+/// Number of accounts diffed at once. The budget is checked between chunks.
+const STATE_DIFF_CHUNK_SIZE: usize = 64;
+
+/// Limits for calls which may scan large parts of the chain (`eth_getLogs`, `trace_filter`).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HeavyQueryOptions {
+	/// Execution time after which the call is aborted.
+	pub budget: Option<Duration>,
+	/// Calls running longer than this are logged together with their parameters.
+	pub slow_query: Option<Duration>,
+}
+
+/// Measures execution time of a single heavy call.
+/// Slow calls are logged when the timer is dropped.
+pub struct QueryTimer<'a> {
+	method: &'static str,
+	params: &'a fmt::Debug,
+	options: &'a HeavyQueryOptions,
+	started: Instant,
+}
+
+impl<'a> QueryTimer<'a> {
+	/// Starts measuring the call.
+	pub fn start(method: &'static str, params: &'a fmt::Debug, options: &'a HeavyQueryOptions) -> Self {
+		QueryTimer {
+			method: method,
+			params: params,
+			options: options,
+			started: Instant::now(),
+		}
+	}
+
+	/// Returns an error if the call already exceeded its budget.
+	pub fn check(&self) -> Result<(), Error> {
+		match self.options.budget {
+			Some(budget) if self.started.elapsed() > budget => Err(errors::query_too_heavy(self.method)),
+			_ => Ok(()),
+		}
+	}
+
+	fn chunked(&self) -> bool {
+		self.options.budget.is_some()
+	}
+}
+
+impl<'a> Drop for QueryTimer<'a> {
+	fn drop(&mut self) {
+		let elapsed = self.started.elapsed();
+		match self.options.slow_query {
+			Some(threshold) if elapsed > threshold => {
+				let ms = elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000;
+				warn!(target: "rpc", "Slow query {} took {} ms: {:?}", self.method, ms, self.params);
+			},
+			_ => {},
+		}
+	}
+}
+
+fn block_number<C: BlockChainClient>(client: &C, id: &BlockID) -> Option<BlockNumber> {
+	match *id {
+		BlockID::Number(number) => Some(number),
+		BlockID::Earliest => Some(0),
+		BlockID::Latest | BlockID::Pending => Some(client.chain_info().best_block_number),
+		BlockID::Hash(_) => client.block_header(id.clone()).map(|header| HeaderView::new(&header).number()),
+	}
+}
+
+/// Splits the range into chunks of `CHUNK_SIZE` blocks.
+fn chunks<C: BlockChainClient>(client: &C, from: &BlockID, to: &BlockID) -> Vec<(BlockID, BlockID)> {
+	match (block_number(client, from), block_number(client, to)) {
+		(Some(from), Some(to)) => {
+			let mut chunks = Vec::new();
+			let mut start = from;
+			while start <= to {
+				let end = cmp::min(start.saturating_add(CHUNK_SIZE - 1), to);
+				chunks.push((BlockID::Number(start), BlockID::Number(end)));
+				start = end + 1;
+			}
+			chunks
+		},
+		// let the client handle unknown blocks
+		_ => vec![(from.clone(), to.clone())],
+	}
+}
+
+/// Returns logs matching the filter or an error if the query exceeds its budget.
+pub fn logs<C: BlockChainClient>(client: &C, filter: &Filter, timer: &QueryTimer) -> Result<Vec<LocalizedLogEntry>, Error> {
+	if !timer.chunked() {
+		return Ok(client.logs(filter.clone()));
+	}
+
+	let blooms = filter.bloom_possibilities();
+	let mut logs = Vec::new();
+	for (from, to) in chunks(client, &filter.from_block, &filter.to_block) {
+		try!(timer.check());
+		// only the blooms are looked up for the whole chunk, the receipts are read block by block
+		let blocks: BTreeSet<BlockNumber> = blooms.iter()
+			.filter_map(|bloom| client.blocks_with_bloom(bloom, from.clone(), to.clone()))
+			.flat_map(|blocks| blocks)
+			.collect();
+		for number in blocks {
+			try!(timer.check());
+			let mut block = filter.clone();
+			block.from_block = BlockID::Number(number);
+			block.to_block = BlockID::Number(number);
+			logs.extend(client.logs(block));
+		}
+	}
+	Ok(logs)
+}
+
+/// Returns traces matching the filter or an error if the query exceeds its budget.
+pub fn traces<C: BlockChainClient>(client: &C, filter: TraceFilter, timer: &QueryTimer) -> Result<Option<Vec<LocalizedTrace>>, Error> {
+	if !timer.chunked() {
+		return Ok(client.filter_traces(filter));
+	}
+
+	let (from, to) = match (block_number(client, &filter.range.start), block_number(client, &filter.range.end)) {
+		(Some(from), Some(to)) => (from, to),
+		// let the client handle unknown blocks
+		_ => return Ok(client.filter_traces(filter)),
+	};
+
+	let mut traces = Vec::new();
+	for number in from..to.saturating_add(1) {
+		try!(timer.check());
+		let block = TraceFilter {
+			range: BlockID::Number(number)..BlockID::Number(number),
+			from_address: filter.from_address.clone(),
+			to_address: filter.to_address.clone(),
+		};
+		match client.filter_traces(block) {
+			Some(block_traces) => traces.extend(block_traces),
+			None => return Ok(None),
+		}
+	}
+	Ok(Some(traces))
+}
+
+fn state_diff_error(e: StateDiffError) -> Error {
+	match e {
+		StateDiffError::BlockNotFound => errors::unknown_block(),
+		StateDiffError::InvalidRange => errors::invalid_params("to", e),
+		StateDiffError::StatePruned => errors::state_pruned(),
+		StateDiffError::FatDbRequired => errors::fat_db_required(),
+	}
+}
+
+/// Returns the state diff of the given accounts or an error if the query exceeds its budget.
+pub fn state_diff<C: BlockChainClient>(client: &C, from: BlockID, to: BlockID, addresses: Vec<Address>, timer: &QueryTimer) -> Result<StateDiff, Error> {
+	if !timer.chunked() || addresses.len() <= STATE_DIFF_CHUNK_SIZE {
+		return client.state_diff(from, to, addresses).map_err(state_diff_error);
+	}
+
+	let mut diff = StateDiff { raw: Default::default() };
+	for chunk in addresses.chunks(STATE_DIFF_CHUNK_SIZE) {
+		try!(timer.check());
+		let chunk_diff = try!(client.state_diff(from.clone(), to.clone(), chunk.to_vec()).map_err(state_diff_error));
+		diff.raw.extend(chunk_diff.raw);
+	}
+	Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use ethcore::client::{TestBlockChainClient, EachBlockWith, BlockID};
+	use super::{chunks, HeavyQueryOptions, QueryTimer};
+
+	#[test]
+	fn should_split_range_into_chunks() {
+		let client = TestBlockChainClient::new();
+		client.add_blocks(2500, EachBlockWith::Nothing);
+
+		let chunks = chunks(&client, &BlockID::Number(10), &BlockID::Latest);
+		assert_eq!(chunks, vec![
+			(BlockID::Number(10), BlockID::Number(1009)),
+			(BlockID::Number(1010), BlockID::Number(2009)),
+			(BlockID::Number(2010), BlockID::Number(2500)),
+		]);
+	}
+
+	#[test]
+	fn should_reject_query_over_budget() {
+		let options = HeavyQueryOptions {
+			budget: Some(Duration::from_millis(0)),
+			slow_query: None,
+		};
+		let params = ();
+		let timer = QueryTimer::start("eth_getLogs", &params, &options);
+		::std::thread::sleep(Duration::from_millis(1));
+		assert!(timer.check().is_err());
+
+		let unlimited = HeavyQueryOptions::default();
+		let timer = QueryTimer::start("eth_getLogs", &params, &unlimited);
+		assert!(timer.check().is_ok());
+	}
+}
@@ -21,6 +21,7 @@ pub mod auto_args;
 pub mod errors;
 
 pub mod dispatch;
+pub mod heavy_query;
 pub mod params;
 
 mod poll_manager;
@@ -29,10 +30,13 @@ mod requests;
 mod signer;
 mod signing_queue;
 mod network_settings;
+mod storage_watcher;
 
+pub use self::heavy_query::HeavyQueryOptions;
 pub use self::poll_manager::PollManager;
 pub use self::poll_filter::{PollFilter, limit_logs};
 pub use self::requests::{TransactionRequest, FilledTransactionRequest, ConfirmationRequest, ConfirmationPayload, CallRequest};
 pub use self::signing_queue::{ConfirmationsQueue, ConfirmationPromise, ConfirmationResult, SigningQueue, QueueEvent};
 pub use self::signer::SignerService;
 pub use self::network_settings::NetworkSettings;
+pub use self::storage_watcher::{StorageWatcher, StorageChange};
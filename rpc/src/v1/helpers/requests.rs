@@ -15,6 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use util::{Address, U256, Bytes, H256};
+use ethstore::typed_data::TypedDataField;
 
 /// Transaction request coming from RPC
 #[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
@@ -103,4 +104,10 @@ pub enum ConfirmationPayload {
 	Transaction(FilledTransactionRequest),
 	/// Sign request
 	Sign(Address, H256),
+	/// Decrypt request
+	Decrypt(Address, Bytes),
+	/// Sign prefixed message request
+	SignMessage(Address, Bytes),
+	/// Sign typed data request
+	SignTypedData(Address, Vec<TypedDataField>),
 }
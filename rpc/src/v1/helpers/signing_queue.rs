@@ -18,9 +18,12 @@ use std::mem;
 use std::cell::RefCell;
 use std::sync::{mpsc, Arc};
 use std::collections::BTreeMap;
-use jsonrpc_core;
+use std::time::{Duration, Instant};
+use jsonrpc_core::{self, Value, to_value};
+use serde_json;
 use util::{Mutex, RwLock, U256};
 use v1::helpers::{ConfirmationRequest, ConfirmationPayload};
+use v1::types::{ConfirmationRequest as RpcConfirmationRequest, U256 as RpcU256};
 
 /// Result that can be returned from JSON RPC.
 pub type RpcResult = Result<jsonrpc_core::Value, jsonrpc_core::Error>;
@@ -36,6 +39,8 @@ pub enum QueueEvent {
 	RequestRejected(U256),
 	/// Request resolved.
 	RequestConfirmed(U256),
+	/// Request was not resolved in time and got rejected.
+	RequestExpired(U256),
 }
 
 /// Defines possible errors returned from queue receiving method.
@@ -105,6 +110,7 @@ pub struct ConfirmationToken {
 	result: Arc<Mutex<ConfirmationResult>>,
 	listeners: Arc<Mutex<Vec<Listener>>>,
 	request: ConfirmationRequest,
+	added: Instant,
 }
 
 pub struct ConfirmationPromise {
@@ -169,6 +175,7 @@ pub struct ConfirmationsQueue {
 	queue: RwLock<BTreeMap<U256, ConfirmationToken>>,
 	sender: Mutex<mpsc::Sender<QueueEvent>>,
 	receiver: Mutex<Option<mpsc::Receiver<QueueEvent>>>,
+	timeout: RwLock<Option<Duration>>,
 }
 
 impl Default for ConfirmationsQueue {
@@ -180,6 +187,7 @@ impl Default for ConfirmationsQueue {
 			queue: RwLock::new(BTreeMap::new()),
 			sender: Mutex::new(send),
 			receiver: Mutex::new(Some(recv)),
+			timeout: RwLock::new(None),
 		}
 	}
 }
@@ -213,6 +221,65 @@ impl ConfirmationsQueue {
 		self.notify(QueueEvent::Finish);
 	}
 
+	/// Sets how long requests wait for confirmation before they expire.
+	/// `None` means requests never expire.
+	pub fn set_timeout(&self, timeout: Option<Duration>) {
+		*self.timeout.write() = timeout;
+	}
+
+	/// Returns how long requests wait for confirmation before they expire.
+	pub fn timeout(&self) -> Option<Duration> {
+		*self.timeout.read()
+	}
+
+	/// Rejects requests which have been waiting longer than the timeout.
+	/// Notifies the receiver with `RequestExpired` for each of them.
+	pub fn remove_expired(&self) -> Vec<ConfirmationRequest> {
+		let timeout = match self.timeout() {
+			Some(timeout) => timeout,
+			None => return Vec::new(),
+		};
+
+		let expired: Vec<U256> = self.queue.read().iter()
+			.filter(|&(_, token)| token.added.elapsed() >= timeout)
+			.map(|(id, _)| *id)
+			.collect();
+
+		expired.into_iter()
+			.filter_map(|id| {
+				debug!(target: "own_tx", "Signer: Request expired ({:?}).", id);
+				self.remove(id, None, QueueEvent::RequestExpired(id))
+			})
+			.collect()
+	}
+
+	/// Returns a JSON-RPC notification (`signer_requestEvent`) describing the event to push to Signer UIs.
+	/// New requests are sent with their payload, so that UIs don't need to poll `signer_requestsToConfirm`.
+	pub fn event_notification(&self, event: &QueueEvent) -> Option<String> {
+		let (kind, id) = match *event {
+			QueueEvent::Finish => return None,
+			QueueEvent::NewRequest(id) => ("new", id),
+			QueueEvent::RequestConfirmed(id) => ("confirmed", id),
+			QueueEvent::RequestRejected(id) => ("rejected", id),
+			QueueEvent::RequestExpired(id) => ("expired", id),
+		};
+		let request = match *event {
+			QueueEvent::NewRequest(ref id) => self.peek(id).map(RpcConfirmationRequest::from),
+			_ => None,
+		};
+
+		let mut params = BTreeMap::new();
+		params.insert("event".to_owned(), to_value(&kind));
+		params.insert("id".to_owned(), to_value(&RpcU256::from(id)));
+		params.insert("request".to_owned(), to_value(&request));
+
+		let mut notification = BTreeMap::new();
+		notification.insert("jsonrpc".to_owned(), to_value(&"2.0"));
+		notification.insert("method".to_owned(), to_value(&"signer_requestEvent"));
+		notification.insert("params".to_owned(), Value::Array(vec![Value::Object(params)]));
+		serde_json::to_string(&Value::Object(notification)).ok()
+	}
+
 	/// Notifies receiver about the event happening in this queue.
 	fn notify(&self, message: QueueEvent) {
 		// We don't really care about the result
@@ -221,15 +288,12 @@ impl ConfirmationsQueue {
 
 	/// Removes requests from this queue and notifies `ConfirmationPromise` holders about the result.
 	/// Notifies also a receiver about that event.
-	fn remove(&self, id: U256, result: Option<RpcResult>) -> Option<ConfirmationRequest> {
+	fn remove(&self, id: U256, result: Option<RpcResult>, event: QueueEvent) -> Option<ConfirmationRequest> {
 		let token = self.queue.write().remove(&id);
 
 		if let Some(token) = token {
 			// notify receiver about the event
-			self.notify(result.clone().map_or_else(
-				|| QueueEvent::RequestRejected(id),
-				|_| QueueEvent::RequestConfirmed(id)
-			));
+			self.notify(event);
 			// notify token holders about resolution
 			token.resolve(result);
 			// return a result
@@ -270,6 +334,7 @@ impl SigningQueue for ConfirmationsQueue {
 					id: id,
 					payload: request,
 				},
+				added: Instant::now(),
 			});
 			queue.get(&id).map(|token| token.as_promise()).expect("Token was just inserted.")
 		};
@@ -284,12 +349,12 @@ impl SigningQueue for ConfirmationsQueue {
 
 	fn request_rejected(&self, id: U256) -> Option<ConfirmationRequest> {
 		debug!(target: "own_tx", "Signer: Request rejected ({:?}).", id);
-		self.remove(id, None)
+		self.remove(id, None, QueueEvent::RequestRejected(id))
 	}
 
 	fn request_confirmed(&self, id: U256, result: RpcResult) -> Option<ConfirmationRequest> {
 		debug!(target: "own_tx", "Signer: Transaction confirmed ({:?}).", id);
-		self.remove(id, Some(result))
+		self.remove(id, Some(result), QueueEvent::RequestConfirmed(id))
 	}
 
 	fn requests(&self) -> Vec<ConfirmationRequest> {
@@ -400,4 +465,37 @@ mod test {
 		assert_eq!(el.id, U256::from(1));
 		assert_eq!(el.payload, request);
 	}
+
+	#[test]
+	fn should_expire_requests() {
+		// given
+		let queue = ConfirmationsQueue::default();
+		queue.add_request(request()).unwrap();
+		assert!(queue.remove_expired().is_empty());
+
+		// when
+		queue.set_timeout(Some(Duration::from_millis(0)));
+		let expired = queue.remove_expired();
+
+		// then
+		assert_eq!(expired.len(), 1);
+		assert_eq!(expired[0].id, U256::from(1));
+		assert!(queue.is_empty());
+	}
+
+	#[test]
+	fn should_describe_events_as_notifications() {
+		// given
+		let queue = ConfirmationsQueue::default();
+		queue.add_request(ConfirmationPayload::Sign(1.into(), 5.into())).unwrap();
+
+		// when
+		let new = queue.event_notification(&QueueEvent::NewRequest(1.into()));
+		let expired = queue.event_notification(&QueueEvent::RequestExpired(1.into()));
+
+		// then
+		assert_eq!(new, Some(r#"{"jsonrpc":"2.0","method":"signer_requestEvent","params":[{"event":"new","id":"0x1","request":{"id":"0x1","payload":{"sign":{"address":"0x0000000000000000000000000000000000000001","hash":"0x0000000000000000000000000000000000000000000000000000000000000005"}}}}]}"#.to_owned()));
+		assert_eq!(expired, Some(r#"{"jsonrpc":"2.0","method":"signer_requestEvent","params":[{"event":"expired","id":"0x1","request":null}]}"#.to_owned()));
+		assert_eq!(queue.event_notification(&QueueEvent::Finish), None);
+	}
 }
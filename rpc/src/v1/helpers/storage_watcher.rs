@@ -0,0 +1,336 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Watch list of storage slots checked after every block import.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use jsonrpc_core::{Value, to_value};
+use serde_json;
+use util::{Address, H256, Mutex};
+use ethcore::client::{BlockChainClient, ChainNotify};
+use ethcore::ids::BlockID;
+use v1::types::{StorageChange as RpcStorageChange, U256 as RpcU256};
+
+/// Maximal number of uncollected changes kept for a single watch.
+/// Oldest changes are dropped first.
+const MAX_PENDING_CHANGES: usize = 1024;
+
+/// Lifetime of a watch which is not polled (in seconds).
+const WATCH_LIFETIME: u64 = 60;
+
+/// Change of a watched storage slot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageChange {
+	/// Number of the block in which the new value was observed.
+	pub block_number: u64,
+	/// Hash of the block in which the new value was observed.
+	pub block_hash: H256,
+	/// Contract address.
+	pub address: Address,
+	/// Storage slot.
+	pub key: H256,
+	/// Value before the change.
+	pub old_value: H256,
+	/// Value after the change.
+	pub new_value: H256,
+}
+
+struct StorageWatch {
+	/// Watched slots with the last value seen.
+	slots: HashMap<(Address, H256), H256>,
+	/// Changes not yet collected.
+	changes: VecDeque<StorageChange>,
+	/// When the watch was installed or last polled.
+	last_poll: Instant,
+	/// Connection the changes are pushed to, if any.
+	subscriber: Option<Subscriber>,
+}
+
+/// Connection receiving changes of a watch as they happen.
+struct Subscriber {
+	/// Id of the connection.
+	session: usize,
+	/// Sends a notification to the connection. Returns `false` if it is closed.
+	send: Box<Fn(String) -> bool + Send>,
+}
+
+/// Keeps a list of watched `(contract, slot)` pairs and records their changes.
+///
+/// Watched slots are read from the best block's state every time the chain
+/// advances, so a caller doesn't have to query each slot separately. When several
+/// blocks are imported at once, only the value at the new best block is compared.
+///
+/// Changes are kept for polling. Like filters, watches which are not polled within
+/// their lifetime are removed. A connection may subscribe to a watch instead; its changes
+/// are then pushed to that connection only, as `ethcore_storageWatchChanged` notifications,
+/// and the watch lives as long as the connection.
+pub struct StorageWatcher {
+	client: Weak<BlockChainClient>,
+	watches: Mutex<HashMap<usize, StorageWatch>>,
+	next_id: Mutex<usize>,
+	lifetime: Duration,
+}
+
+impl StorageWatcher {
+	/// Creates new watcher reading state from given client.
+	pub fn new<C>(client: &Arc<C>) -> Self where C: BlockChainClient + 'static {
+		Self::with_lifetime(client, Duration::from_secs(WATCH_LIFETIME))
+	}
+
+	/// Creates new watcher removing watches which are not polled within `lifetime`.
+	pub fn with_lifetime<C>(client: &Arc<C>, lifetime: Duration) -> Self where C: BlockChainClient + 'static {
+		let client: Arc<BlockChainClient> = client.clone();
+		StorageWatcher {
+			client: Arc::downgrade(&client),
+			watches: Mutex::new(HashMap::new()),
+			next_id: Mutex::new(0),
+			lifetime: lifetime,
+		}
+	}
+
+	/// Pushes further changes of the watch to the connection `session` instead of keeping them for polling.
+	/// `send` returns `false` once the connection is closed. The watch does not expire, it is removed
+	/// together with the connection, see `remove_session`.
+	/// Returns `false` if there is no such watch or another connection has already subscribed to it.
+	pub fn subscribe<F>(&self, id: usize, session: usize, send: F) -> bool where F: Fn(String) -> bool + Send + 'static {
+		let mut watches = self.watches.lock();
+		let watch = match watches.get_mut(&id) {
+			Some(watch) => watch,
+			None => return false,
+		};
+		if watch.subscriber.as_ref().map_or(false, |s| s.session != session) {
+			return false;
+		}
+		watch.changes.clear();
+		watch.subscriber = Some(Subscriber {
+			session: session,
+			send: Box::new(send),
+		});
+		true
+	}
+
+	/// Removes all watches the connection `session` has subscribed to.
+	pub fn remove_session(&self, session: usize) {
+		self.watches.lock().retain(|_, watch| watch.subscriber.as_ref().map_or(true, |s| s.session != session));
+	}
+
+	/// Starts watching given slots. Returns id of the watch.
+	pub fn add_watch(&self, slots: Vec<(Address, H256)>) -> usize {
+		let client = self.client.upgrade();
+		let slots = slots.into_iter().map(|(address, key)| {
+			let value = client.as_ref()
+				.and_then(|c| c.storage_at(&address, &key, BlockID::Latest))
+				.unwrap_or_else(H256::new);
+			((address, key), value)
+		}).collect();
+
+		let mut next_id = self.next_id.lock();
+		let id = *next_id;
+		*next_id += 1;
+		let mut watches = self.watches.lock();
+		self.prune(&mut watches);
+		watches.insert(id, StorageWatch {
+			slots: slots,
+			changes: VecDeque::new(),
+			last_poll: Instant::now(),
+			subscriber: None,
+		});
+		id
+	}
+
+	/// Returns and clears changes recorded for given watch since the last call.
+	/// Returns `None` if there is no such watch or it has expired.
+	pub fn take_changes(&self, id: usize) -> Option<Vec<StorageChange>> {
+		let mut watches = self.watches.lock();
+		self.prune(&mut watches);
+		watches.get_mut(&id).map(|watch| {
+			watch.last_poll = Instant::now();
+			watch.changes.drain(..).collect()
+		})
+	}
+
+	/// Stops watching. Returns `false` if there was no such watch.
+	pub fn remove_watch(&self, id: usize) -> bool {
+		self.watches.lock().remove(&id).is_some()
+	}
+
+	/// Removes watches which were not polled within their lifetime. Subscribed watches are kept.
+	fn prune(&self, watches: &mut HashMap<usize, StorageWatch>) {
+		let lifetime = self.lifetime;
+		watches.retain(|_, watch| watch.subscriber.is_some() || watch.last_poll.elapsed() < lifetime);
+	}
+
+	fn check_slots(&self, client: &BlockChainClient) {
+		let info = client.chain_info();
+		let mut watches = self.watches.lock();
+		self.prune(&mut watches);
+		let mut closed = Vec::new();
+		for (&id, watch) in watches.iter_mut() {
+			for (&(ref address, ref key), value) in &mut watch.slots {
+				let new_value = match client.storage_at(address, key, BlockID::Latest) {
+					Some(v) => v,
+					None => continue,
+				};
+				if new_value == *value {
+					continue;
+				}
+				let change = StorageChange {
+					block_number: info.best_block_number,
+					block_hash: info.best_block_hash.clone(),
+					address: address.clone(),
+					key: key.clone(),
+					old_value: value.clone(),
+					new_value: new_value.clone(),
+				};
+				*value = new_value;
+				match watch.subscriber {
+					Some(ref subscriber) => {
+						let sent = change_notification(id, &change).map_or(true, |notification| (subscriber.send)(notification));
+						if !sent {
+							closed.push(id);
+							break;
+						}
+					},
+					None => {
+						if watch.changes.len() == MAX_PENDING_CHANGES {
+							watch.changes.pop_front();
+						}
+						watch.changes.push_back(change);
+					},
+				}
+			}
+		}
+		for id in closed {
+			watches.remove(&id);
+		}
+	}
+}
+
+/// Returns a JSON-RPC notification (`ethcore_storageWatchChanged`) describing the change of a slot watched by `id`.
+fn change_notification(id: usize, change: &StorageChange) -> Option<String> {
+	let mut params = BTreeMap::new();
+	params.insert("watch".to_owned(), to_value(&RpcU256::from(id)));
+	params.insert("change".to_owned(), to_value(&RpcStorageChange::from(change.clone())));
+
+	let mut notification = BTreeMap::new();
+	notification.insert("jsonrpc".to_owned(), to_value(&"2.0"));
+	notification.insert("method".to_owned(), to_value(&"ethcore_storageWatchChanged"));
+	notification.insert("params".to_owned(), Value::Array(vec![Value::Object(params)]));
+	serde_json::to_string(&Value::Object(notification)).ok()
+}
+
+impl ChainNotify for StorageWatcher {
+	fn new_blocks(
+		&self,
+		_imported: Vec<H256>,
+		_invalid: Vec<H256>,
+		enacted: Vec<H256>,
+		_retracted: Vec<H256>,
+		_sealed: Vec<H256>,
+		_duration: u64)
+	{
+		if enacted.is_empty() || self.watches.lock().is_empty() {
+			return;
+		}
+
+		if let Some(client) = self.client.upgrade() {
+			self.check_slots(&*client);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{mpsc, Arc};
+	use std::time::Duration;
+	use util::{Address, H256};
+	use ethcore::client::{TestBlockChainClient, BlockChainClient, ChainNotify, EachBlockWith};
+	use super::StorageWatcher;
+
+	fn import_block(client: &TestBlockChainClient, watcher: &StorageWatcher) {
+		client.add_blocks(1, EachBlockWith::Nothing);
+		let best = client.chain_info().best_block_hash;
+		watcher.new_blocks(vec![best.clone()], vec![], vec![best], vec![], vec![], 0);
+	}
+
+	fn subscribe(watcher: &StorageWatcher, id: usize, session: usize) -> mpsc::Receiver<String> {
+		let (tx, rx) = mpsc::channel();
+		assert!(watcher.subscribe(id, session, move |notification| tx.send(notification).is_ok()));
+		rx
+	}
+
+	#[test]
+	fn should_push_changes_to_the_subscribed_session() {
+		// given
+		let client = Arc::new(TestBlockChainClient::new());
+		let watcher = StorageWatcher::new(&client);
+		client.set_storage(Address::from(1), H256::from(2), H256::from(3));
+		client.set_storage(Address::from(1), H256::from(5), H256::from(6));
+		let id = watcher.add_watch(vec![(Address::from(1), H256::from(2))]);
+		let other = watcher.add_watch(vec![(Address::from(1), H256::from(5))]);
+		let notifications = subscribe(&watcher, id, 1);
+		let other_notifications = subscribe(&watcher, other, 2);
+		assert!(!watcher.subscribe(id, 2, |_| true));
+
+		// when
+		client.set_storage(Address::from(1), H256::from(2), H256::from(4));
+		import_block(&client, &watcher);
+
+		// then
+		let notification = notifications.try_recv().unwrap();
+		assert!(notification.contains(r#""method":"ethcore_storageWatchChanged""#));
+		assert!(notification.contains(r#""watch":"0x0""#));
+		assert!(notification.contains(r#""newValue":"0x0000000000000000000000000000000000000000000000000000000000000004""#));
+		assert!(notifications.try_recv().is_err());
+		assert!(other_notifications.try_recv().is_err());
+		// pushed changes are not kept for polling
+		assert_eq!(watcher.take_changes(id), Some(vec![]));
+	}
+
+	#[test]
+	fn should_keep_subscribed_watches_until_the_session_is_closed() {
+		// given
+		let client = Arc::new(TestBlockChainClient::new());
+		let watcher = StorageWatcher::with_lifetime(&client, Duration::from_millis(0));
+		let id = watcher.add_watch(vec![(Address::from(1), H256::from(2))]);
+		let _notifications = subscribe(&watcher, id, 1);
+
+		// when
+		import_block(&client, &watcher);
+
+		// then
+		assert_eq!(watcher.take_changes(id), Some(vec![]));
+		watcher.remove_session(1);
+		assert_eq!(watcher.take_changes(id), None);
+	}
+
+	#[test]
+	fn should_expire_watches_which_are_not_polled() {
+		// given
+		let client = Arc::new(TestBlockChainClient::new());
+		let watcher = StorageWatcher::with_lifetime(&client, Duration::from_millis(0));
+		let id = watcher.add_watch(vec![(Address::from(1), H256::from(2))]);
+
+		// when
+		import_block(&client, &watcher);
+
+		// then
+		assert_eq!(watcher.take_changes(id), None);
+		assert!(!watcher.remove_watch(id));
+	}
+}
@@ -43,12 +43,13 @@ use ethcore::filter::Filter as EthcoreFilter;
 use self::ethash::SeedHashCompute;
 use v1::traits::Eth;
 use v1::types::{
-	Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, SyncInfo,
+	Block, BlockTransactions, BlockNumber, BlockNumberOrHash, Bytes, SyncStatus, SyncInfo,
 	Transaction, CallRequest, Index, Filter, Log, Receipt, Work,
 	H64 as RpcH64, H256 as RpcH256, H160 as RpcH160, U256 as RpcU256,
 };
-use v1::helpers::{CallRequest as CRequest, errors, limit_logs};
-use v1::helpers::dispatch::{default_gas_price, dispatch_transaction};
+use v1::helpers::{CallRequest as CRequest, errors, limit_logs, HeavyQueryOptions};
+use v1::helpers::heavy_query::{self, QueryTimer};
+use v1::helpers::dispatch::{default_gas_price, dispatch_transaction, ensure_state};
 use v1::helpers::auto_args::Trailing;
 
 /// Eth RPC options
@@ -57,6 +58,8 @@ pub struct EthClientOptions {
 	pub allow_pending_receipt_query: bool,
 	/// Send additional block number when asking for work
 	pub send_block_number_in_get_work: bool,
+	/// Limits for `eth_getLogs`
+	pub heavy_queries: HeavyQueryOptions,
 }
 
 impl Default for EthClientOptions {
@@ -64,6 +67,7 @@ impl Default for EthClientOptions {
 		EthClientOptions {
 			allow_pending_receipt_query: true,
 			send_block_number_in_get_work: true,
+			heavy_queries: HeavyQueryOptions::default(),
 		}
 	}
 }
@@ -104,6 +108,12 @@ impl<C, S: ?Sized, M, EM> EthClient<C, S, M, EM> where
 		}
 	}
 
+	/// Make sure `pending` queries are answered from the miner's open block rather than the latest one.
+	fn prepare_pending_block(&self) -> Result<(), Error> {
+		take_weak!(self.miner).prepare_pending_block(&*take_weak!(self.client));
+		Ok(())
+	}
+
 	fn block(&self, id: BlockID, include_txs: bool) -> Result<Option<Block>, Error> {
 		let client = take_weak!(self.client);
 		match (client.block(id.clone()), client.block_total_difficulty(id)) {
@@ -186,6 +196,7 @@ impl<C, S: ?Sized, M, EM> EthClient<C, S, M, EM> where
 
 	fn sign_call(&self, request: CRequest) -> Result<SignedTransaction, Error> {
 		let (client, miner) = (take_weak!(self.client), take_weak!(self.miner));
+		try!(ensure_state(&*client));
 		let from = request.from.unwrap_or(Address::zero());
 		Ok(EthTransaction {
 			nonce: request.nonce.unwrap_or_else(|| client.latest_nonce(&from)),
@@ -258,15 +269,29 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 		match status.state {
 			SyncState::Idle => Ok(SyncStatus::None),
 			SyncState::Waiting | SyncState::Blocks | SyncState::NewBlocks | SyncState::ChainHead
-				| SyncState::SnapshotManifest | SyncState::SnapshotData | SyncState::SnapshotWaiting => {
+				| SyncState::SnapshotManifest | SyncState::SnapshotData | SyncState::SnapshotWaiting
+				| SyncState::StatePivot | SyncState::StateData => {
 				let current_block = U256::from(take_weak!(self.client).chain_info().best_block_number);
 				let highest_block = U256::from(status.highest_block_number.unwrap_or(status.start_block_number));
 
 				if highest_block > current_block + U256::from(6) {
+					let warp = match status.state {
+						SyncState::SnapshotData | SyncState::SnapshotWaiting => true,
+						_ => false,
+					};
+					let state_download = match status.state {
+						SyncState::StatePivot | SyncState::StateData => true,
+						_ => false,
+					};
 					let info = SyncInfo {
 						starting_block: status.start_block_number.into(),
 						current_block: current_block.into(),
 						highest_block: highest_block.into(),
+						warp_chunks_amount: if warp { Some(status.num_snapshot_chunks.into()) } else { None },
+						warp_chunks_processed: if warp { Some(status.snapshot_chunks_done.into()) } else { None },
+						state_nodes_downloaded: if state_download { Some(status.state_nodes_downloaded.into()) } else { None },
+						state_nodes_pending: if state_download { Some(status.state_nodes_pending.into()) } else { None },
+						estimated_time_remaining: status.estimated_time_remaining.map(Into::into),
 					};
 					Ok(SyncStatus::Info(info))
 				} else {
@@ -315,12 +340,23 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 		Ok(RpcU256::from(take_weak!(self.client).chain_info().best_block_number))
 	}
 
+	fn chain_id(&self) -> Result<RpcU256, Error> {
+		try!(self.active());
+
+		// transactions are not signed with a chain id yet, so it is the network id.
+		Ok(RpcU256::from(take_weak!(self.client).network_id()))
+	}
+
 	fn balance(&self, address: RpcH160, num: Trailing<BlockNumber>) -> Result<RpcU256, Error> {
 		try!(self.active());
+		try!(ensure_state(&*take_weak!(self.client)));
 
 		let address = address.into();
 		match num.0 {
-			BlockNumber::Pending => Ok(take_weak!(self.miner).balance(&*take_weak!(self.client), &address).into()),
+			BlockNumber::Pending => {
+				try!(self.prepare_pending_block());
+				Ok(take_weak!(self.miner).balance(&*take_weak!(self.client), &address).into())
+			},
 			id => match take_weak!(self.client).balance(&address, id.into()) {
 				Some(balance) => Ok(balance.into()),
 				None => Err(errors::state_pruned()),
@@ -330,10 +366,14 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 
 	fn storage_at(&self, address: RpcH160, pos: RpcU256, num: Trailing<BlockNumber>) -> Result<RpcH256, Error> {
 		try!(self.active());
+		try!(ensure_state(&*take_weak!(self.client)));
 		let address: Address = RpcH160::into(address);
 		let position: U256 = RpcU256::into(pos);
 		match num.0 {
-			BlockNumber::Pending => Ok(take_weak!(self.miner).storage_at(&*take_weak!(self.client), &address, &H256::from(position)).into()),
+			BlockNumber::Pending => {
+				try!(self.prepare_pending_block());
+				Ok(take_weak!(self.miner).storage_at(&*take_weak!(self.client), &address, &H256::from(position)).into())
+			},
 			id => match take_weak!(self.client).storage_at(&address, &H256::from(position), id.into()) {
 				Some(s) => Ok(s.into()),
 				None => Err(errors::state_pruned()),
@@ -343,10 +383,14 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 
 	fn transaction_count(&self, address: RpcH160, num: Trailing<BlockNumber>) -> Result<RpcU256, Error> {
 		try!(self.active());
+		try!(ensure_state(&*take_weak!(self.client)));
 
 		let address: Address = RpcH160::into(address);
 		match num.0 {
-			BlockNumber::Pending => Ok(take_weak!(self.miner).nonce(&*take_weak!(self.client), &address).into()),
+			BlockNumber::Pending => {
+				try!(self.prepare_pending_block());
+				Ok(take_weak!(self.miner).nonce(&*take_weak!(self.client), &address).into())
+			},
 			id => match take_weak!(self.client).nonce(&address, id.into()) {
 				Some(nonce) => Ok(nonce.into()),
 				None => Err(errors::state_pruned()),
@@ -399,10 +443,14 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 
 	fn code_at(&self, address: RpcH160, num: Trailing<BlockNumber>) -> Result<Bytes, Error> {
 		try!(self.active());
+		try!(ensure_state(&*take_weak!(self.client)));
 
 		let address: Address = RpcH160::into(address);
 		match num.0 {
-			BlockNumber::Pending => Ok(take_weak!(self.miner).code(&*take_weak!(self.client), &address).map_or_else(Bytes::default, Bytes::new)),
+			BlockNumber::Pending => {
+				try!(self.prepare_pending_block());
+				Ok(take_weak!(self.miner).code(&*take_weak!(self.client), &address).map_or_else(Bytes::default, Bytes::new))
+			},
 			_ => match take_weak!(self.client).code(&address, num.0.into()) {
 				Some(code) => Ok(code.map_or_else(Bytes::default, Bytes::new)),
 				None => Err(errors::state_pruned()),
@@ -419,6 +467,9 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 	fn block_by_number(&self, num: BlockNumber, include_txs: bool) -> Result<Option<Block>, Error> {
 		try!(self.active());
 
+		if let BlockNumber::Pending = num {
+			try!(self.prepare_pending_block());
+		}
 		self.block(num.into(), include_txs)
 	}
 
@@ -430,6 +481,16 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 		Ok(try!(self.transaction(TransactionID::Hash(hash))).or_else(|| miner.transaction(client.chain_info().best_block_number, &hash).map(Into::into)))
 	}
 
+	fn raw_transaction_by_hash(&self, hash: RpcH256) -> Result<Option<Bytes>, Error> {
+		try!(self.active());
+		let hash: H256 = hash.into();
+		let miner = take_weak!(self.miner);
+		let client = take_weak!(self.client);
+		Ok(client.transaction(TransactionID::Hash(hash)).map(|t| t.signed)
+			.or_else(|| miner.transaction(client.chain_info().best_block_number, &hash))
+			.map(|t| Bytes::new(rlp::encode(&t).to_vec())))
+	}
+
 	fn transaction_by_block_hash_and_index(&self, hash: RpcH256, index: Index) -> Result<Option<Transaction>, Error> {
 		try!(self.active());
 
@@ -445,17 +506,26 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 	fn transaction_receipt(&self, hash: RpcH256) -> Result<Option<Receipt>, Error> {
 		try!(self.active());
 
-		let miner = take_weak!(self.miner);
-		let best_block = take_weak!(self.client).chain_info().best_block_number;
+		let client = take_weak!(self.client);
 		let hash: H256 = hash.into();
-		match (miner.pending_receipt(best_block, &hash), self.options.allow_pending_receipt_query) {
-			(Some(receipt), true) => Ok(Some(receipt.into())),
-			_ => {
-				let client = take_weak!(self.client);
-				let receipt = client.transaction_receipt(TransactionID::Hash(hash));
-				Ok(receipt.map(Into::into))
-			}
+		// the pending block is only prepared for transactions which are not mined yet.
+		if let Some(receipt) = client.transaction_receipt(TransactionID::Hash(hash)) {
+			return Ok(Some(receipt.into()));
+		}
+		if !self.options.allow_pending_receipt_query {
+			return Ok(None);
 		}
+
+		try!(self.prepare_pending_block());
+		let best_block = client.chain_info().best_block_number;
+		Ok(take_weak!(self.miner).pending_receipt(best_block, &hash).map(Into::into))
+	}
+
+	fn block_receipts(&self, block: BlockNumberOrHash) -> Result<Option<Vec<Receipt>>, Error> {
+		try!(self.active());
+
+		let receipts = take_weak!(self.client).block_receipts_localized(block.into());
+		Ok(receipts.map(|receipts| receipts.into_iter().map(Into::into).collect()))
 	}
 
 	fn uncle_by_block_hash_and_index(&self, hash: RpcH256, index: Index) -> Result<Option<Block>, Error> {
@@ -482,9 +552,11 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 	}
 
 	fn logs(&self, filter: Filter) -> Result<Vec<Log>, Error> {
+		let params = filter.clone();
+		let timer = QueryTimer::start("eth_getLogs", &params, &self.options.heavy_queries);
 		let include_pending = filter.to_block == Some(BlockNumber::Pending);
 		let filter: EthcoreFilter = filter.into();
-		let mut logs = take_weak!(self.client).logs(filter.clone())
+		let mut logs = try!(heavy_query::logs(&*take_weak!(self.client), &filter, &timer))
 			.into_iter()
 			.map(From::from)
 			.collect::<Vec<Log>>();
@@ -589,7 +661,10 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 		let signed = try!(self.sign_call(request));
 
 		let r = match num.0 {
-			BlockNumber::Pending => take_weak!(self.miner).call(&*take_weak!(self.client), &signed, Default::default()),
+			BlockNumber::Pending => {
+				try!(self.prepare_pending_block());
+				take_weak!(self.miner).call(&*take_weak!(self.client), &signed, Default::default())
+			},
 			num => take_weak!(self.client).call(&signed, num.into(), Default::default()),
 		};
 
@@ -602,7 +677,10 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 		let request = CallRequest::into(request);
 		let signed = try!(self.sign_call(request));
 		let r = match num.0 {
-			BlockNumber::Pending => take_weak!(self.miner).call(&*take_weak!(self.client), &signed, Default::default()),
+			BlockNumber::Pending => {
+				try!(self.prepare_pending_block());
+				take_weak!(self.miner).call(&*take_weak!(self.client), &signed, Default::default())
+			},
 			num => take_weak!(self.client).call(&signed, num.into(), Default::default()),
 		};
 
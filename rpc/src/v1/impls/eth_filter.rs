@@ -25,7 +25,8 @@ use ethcore::client::{BlockChainClient, BlockID};
 use util::Mutex;
 use v1::traits::EthFilter;
 use v1::types::{BlockNumber, Index, Filter, FilterChanges, Log, H256 as RpcH256, U256 as RpcU256};
-use v1::helpers::{PollFilter, PollManager, limit_logs};
+use v1::helpers::{PollFilter, PollManager, HeavyQueryOptions, limit_logs};
+use v1::helpers::heavy_query::{self, QueryTimer};
 use v1::impls::eth::pending_logs;
 
 /// Eth filter rpc implementation.
@@ -36,6 +37,7 @@ pub struct EthFilterClient<C, M> where
 	client: Weak<C>,
 	miner: Weak<M>,
 	polls: Mutex<PollManager<PollFilter>>,
+	heavy_queries: HeavyQueryOptions,
 }
 
 impl<C, M> EthFilterClient<C, M> where
@@ -43,11 +45,12 @@ impl<C, M> EthFilterClient<C, M> where
 	M: MinerService {
 
 	/// Creates new Eth filter client.
-	pub fn new(client: &Arc<C>, miner: &Arc<M>) -> Self {
+	pub fn new(client: &Arc<C>, miner: &Arc<M>, heavy_queries: HeavyQueryOptions) -> Self {
 		EthFilterClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
 			polls: Mutex::new(PollManager::new()),
+			heavy_queries: heavy_queries,
 		}
 	}
 
@@ -185,9 +188,10 @@ impl<C, M> EthFilter for EthFilterClient<C, M>
 		let mut polls = self.polls.lock();
 		match polls.poll(&index.value()) {
 			Some(&PollFilter::Logs(ref _block_number, ref _previous_log, ref filter)) => {
+				let timer = QueryTimer::start("eth_getFilterLogs", filter, &self.heavy_queries);
 				let include_pending = filter.to_block == Some(BlockNumber::Pending);
 				let filter: EthcoreFilter = filter.clone().into();
-				let mut logs = take_weak!(self.client).logs(filter.clone())
+				let mut logs = try!(heavy_query::logs(&*take_weak!(self.client), &filter, &timer))
 					.into_iter()
 					.map(From::from)
 					.collect::<Vec<Log>>();
@@ -23,10 +23,11 @@ use ethcore::client::MiningBlockChainClient;
 use util::{U256, Address, H256, Mutex};
 use transient_hashmap::TransientHashMap;
 use ethcore::account_provider::AccountProvider;
+use ethstore::typed_data;
 use v1::helpers::{errors, SigningQueue, ConfirmationPromise, ConfirmationResult, ConfirmationPayload, TransactionRequest as TRequest, FilledTransactionRequest as FilledRequest, SignerService};
-use v1::helpers::dispatch::{default_gas_price, sign_and_dispatch};
+use v1::helpers::dispatch::{self, default_gas_price, sign_and_dispatch};
 use v1::traits::EthSigning;
-use v1::types::{TransactionRequest, H160 as RpcH160, H256 as RpcH256, H520 as RpcH520, U256 as RpcU256, Bytes as RpcBytes};
+use v1::types::{TransactionRequest, TypedDataField, H160 as RpcH160, H256 as RpcH256, H520 as RpcH520, U256 as RpcU256, Bytes as RpcBytes};
 
 fn fill_optional_fields<C, M>(request: TRequest, client: &C, miner: &M) -> FilledRequest
 	where C: MiningBlockChainClient, M: MinerService {
@@ -93,6 +94,42 @@ impl<C, M> EthSigningQueueClient<C, M> where C: MiningBlockChainClient, M: Miner
 		})
 	}
 
+	fn dispatch_sign_typed_data(&self, params: Params) -> Result<DispatchResult, Error> {
+		from_params::<(RpcH160, Vec<TypedDataField>)>(params).and_then(|(address, data)| {
+			let address: Address = address.into();
+			let data = data.into_iter().map(Into::into).collect::<Vec<typed_data::TypedDataField>>();
+			let hash = try!(dispatch::typed_data_hash(&data));
+
+			let accounts = take_weak!(self.accounts);
+			if accounts.is_unlocked(address) {
+				return Ok(DispatchResult::Value(to_value(&accounts.sign(address, hash).ok().map_or_else(RpcH520::default, Into::into))))
+			}
+
+			let signer = take_weak!(self.signer);
+			signer.add_request(ConfirmationPayload::SignTypedData(address, data))
+				.map(DispatchResult::Promise)
+				.map_err(|_| errors::request_rejected_limit())
+		})
+	}
+
+	fn dispatch_decrypt(&self, params: Params) -> Result<DispatchResult, Error> {
+		from_params::<(RpcH160, RpcBytes)>(params).and_then(|(address, msg)| {
+			let address: Address = address.into();
+
+			let accounts = take_weak!(self.accounts);
+			if accounts.is_unlocked(address) {
+				return accounts.decrypt(address, &[0; 0], &msg.0)
+					.map(|plain| DispatchResult::Value(to_value(&RpcBytes::from(plain))))
+					.map_err(errors::from_signing_error);
+			}
+
+			let signer = take_weak!(self.signer);
+			signer.add_request(ConfirmationPayload::Decrypt(address, msg.0))
+				.map(DispatchResult::Promise)
+				.map_err(|_| errors::request_rejected_limit())
+		})
+	}
+
 	fn dispatch_transaction(&self, params: Params) -> Result<DispatchResult, Error> {
 		from_params::<(TransactionRequest, )>(params)
 			.and_then(|(request, )| {
@@ -131,6 +168,19 @@ impl<C, M> EthSigning for EthSigningQueueClient<C, M>
 		}
 	}
 
+	fn sign_typed_data(&self, params: Params, ready: Ready) {
+		let res = self.active().and_then(|_| self.dispatch_sign_typed_data(params));
+		match res {
+			Ok(DispatchResult::Promise(promise)) => {
+				promise.wait_for_result(move |result| {
+					ready.ready(result.unwrap_or_else(|| Err(errors::request_rejected())))
+				})
+			},
+			Ok(DispatchResult::Value(v)) => ready.ready(Ok(v)),
+			Err(e) => ready.ready(Err(e)),
+		}
+	}
+
 	fn post_sign(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		self.dispatch_sign(params).map(|result| match result {
@@ -168,13 +218,29 @@ impl<C, M> EthSigning for EthSigningQueueClient<C, M>
 		})
 	}
 
-	fn decrypt_message(&self, params: Params) -> Result<Value, Error> {
+	fn sign_transaction(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
-		from_params::<(RpcH160, RpcBytes)>(params).and_then(|(_account, _ciphertext)| {
-			Err(errors::unimplemented())
+		from_params::<(TransactionRequest, )>(params).and_then(|(request, )| {
+			let request: TRequest = request.into();
+			let sender = request.from;
+			let signed = try!(dispatch::sign(&*take_weak!(self.client), &*take_weak!(self.miner), request, &*take_weak!(self.accounts), sender));
+			Ok(dispatch::signed_transaction_rlp(&signed))
 		})
 	}
 
+	fn decrypt_message(&self, params: Params, ready: Ready) {
+		let res = self.active().and_then(|_| self.dispatch_decrypt(params));
+		match res {
+			Ok(DispatchResult::Promise(promise)) => {
+				promise.wait_for_result(move |result| {
+					ready.ready(result.unwrap_or_else(|| Err(errors::request_rejected())))
+				})
+			},
+			Ok(DispatchResult::Value(v)) => ready.ready(Ok(v)),
+			Err(e) => ready.ready(Err(e)),
+		}
+	}
+
 	fn check_request(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		let mut pending = self.pending.lock();
@@ -238,6 +304,16 @@ impl<C, M> EthSigning for EthSigningUnsafeClient<C, M> where
 			}))
 	}
 
+	fn sign_typed_data(&self, params: Params, ready: Ready) {
+		ready.ready(self.active()
+			.and_then(|_| from_params::<(RpcH160, Vec<TypedDataField>)>(params))
+			.and_then(|(address, data)| {
+				let data = data.into_iter().map(Into::into).collect::<Vec<typed_data::TypedDataField>>();
+				let hash = try!(dispatch::typed_data_hash(&data));
+				Ok(to_value(&take_weak!(self.accounts).sign(address.into(), hash).ok().map_or_else(RpcH520::default, Into::into)))
+			}))
+	}
+
 	fn send_transaction(&self, params: Params, ready: Ready) {
 		ready.ready(self.active()
 			.and_then(|_| from_params::<(TransactionRequest, )>(params))
@@ -248,14 +324,25 @@ impl<C, M> EthSigning for EthSigningUnsafeClient<C, M> where
 			}))
 	}
 
-	fn decrypt_message(&self, params: Params) -> Result<Value, Error> {
+	fn sign_transaction(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
-		from_params::<(RpcH160, RpcBytes)>(params).and_then(|(address, ciphertext)| {
-			let s = try!(take_weak!(self.accounts).decrypt(address.into(), &[0; 0], &ciphertext.0).map_err(|_| Error::internal_error()));
-			Ok(to_value(RpcBytes::from(s)))
+		from_params::<(TransactionRequest, )>(params).and_then(|(request, )| {
+			let request: TRequest = request.into();
+			let sender = request.from;
+			let signed = try!(dispatch::sign(&*take_weak!(self.client), &*take_weak!(self.miner), request, &*take_weak!(self.accounts), sender));
+			Ok(dispatch::signed_transaction_rlp(&signed))
 		})
 	}
 
+	fn decrypt_message(&self, params: Params, ready: Ready) {
+		ready.ready(self.active()
+			.and_then(|_| from_params::<(RpcH160, RpcBytes)>(params))
+			.and_then(|(address, ciphertext)| {
+				let s = try!(take_weak!(self.accounts).decrypt(address.into(), &[0; 0], &ciphertext.0).map_err(errors::from_signing_error));
+				Ok(to_value(&RpcBytes::from(s)))
+			}))
+	}
+
 	fn post_sign(&self, _: Params) -> Result<Value, Error> {
 		// We don't support this in non-signer mode.
 		Err(errors::signer_disabled())
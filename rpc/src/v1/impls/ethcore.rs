@@ -15,27 +15,44 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Ethcore-specific rpc implementation.
-use std::{fs, io};
+use std::{cmp, fs, io};
+use std::collections::BTreeMap;
 use std::sync::{mpsc, Arc, Weak};
 use std::str::FromStr;
 
-use util::{RotatingLogger, Address, Mutex, sha3};
+use util::{RotatingLogger, Address, Mutex, Hashable, sha3};
 use util::misc::version_data;
+use util::node_events;
 
 use crypto::ecies;
 use fetch::{Client as FetchClient, Fetch};
 use ethkey::{Brain, Generator};
 use ethstore::random_phrase;
 use ethsync::{SyncProvider, ManageNetwork};
-use ethcore::miner::MinerService;
-use ethcore::client::{MiningBlockChainClient};
+use ethcore::miner::{MinerService, decode_extra_data};
+use ethcore::client::{MiningBlockChainClient, IpcMode};
 use ethcore::ids::BlockID;
+use ethcore::header::Header as EthHeader;
+use updater::Service as UpdateService;
 
 use jsonrpc_core::Error;
 use v1::traits::Ethcore;
-use v1::types::{Bytes, U256, H160, H256, H512, Peers, Transaction, RpcSettings};
-use v1::helpers::{errors, SigningQueue, SignerService, NetworkSettings};
-use v1::helpers::auto_args::Ready;
+use v1::types::{Bytes, U256, H160, H256, H512, BadBlock, BalanceSample, BlockNumber, Builtin, ChainParams, ChainSpec, ChainStatus, Header, Health, Index, NodeEvent, Peers, NetStats, StateDiff, StateDiffAccounts, Transaction, QueuedTransaction, RpcSettings, StorageSlot, StorageChange, Upgrade};
+use v1::helpers::{errors, SigningQueue, SignerService, NetworkSettings, StorageWatcher, HeavyQueryOptions};
+use v1::helpers::heavy_query::{self, QueryTimer};
+use v1::helpers::auto_args::{Ready, Trailing};
+use v1::helpers::dispatch::ensure_state;
+
+/// Maximal number of headers returned by a single `ethcore_blockHeaders` call.
+const MAX_BLOCK_HEADERS: u64 = 1024;
+/// Maximal number of samples returned by a single `ethcore_balanceHistory` call.
+const MAX_BALANCE_SAMPLES: u64 = 1024;
+/// Maximal number of blocks replayed backwards from traces to reconstruct pruned balances.
+const MAX_RECONSTRUCTED_BLOCKS: u64 = 100_000;
+/// Number of blocks `ethcore_extraDataHistogram` looks at by default.
+const DEFAULT_HISTOGRAM_BLOCKS: u64 = 100;
+/// Maximal number of blocks `ethcore_extraDataHistogram` looks at.
+const MAX_HISTOGRAM_BLOCKS: u64 = 10_000;
 
 /// Ethcore implementation.
 pub struct EthcoreClient<C, M, S: ?Sized, F=FetchClient> where
@@ -51,6 +68,9 @@ pub struct EthcoreClient<C, M, S: ?Sized, F=FetchClient> where
 	logger: Arc<RotatingLogger>,
 	settings: Arc<NetworkSettings>,
 	signer: Option<Arc<SignerService>>,
+	storage_watcher: Arc<StorageWatcher>,
+	updater: Option<Arc<UpdateService>>,
+	heavy_queries: HeavyQueryOptions,
 	fetch: Mutex<F>
 }
 
@@ -66,9 +86,12 @@ impl<C, M, S: ?Sized> EthcoreClient<C, M, S> where
 		net: &Arc<ManageNetwork>,
 		logger: Arc<RotatingLogger>,
 		settings: Arc<NetworkSettings>,
-		signer: Option<Arc<SignerService>>
+		signer: Option<Arc<SignerService>>,
+		storage_watcher: Arc<StorageWatcher>,
+		updater: Option<Arc<UpdateService>>,
+		heavy_queries: HeavyQueryOptions
 	) -> Self {
-		Self::with_fetch(client, miner, sync, net, logger, settings, signer)
+		Self::with_fetch(client, miner, sync, net, logger, settings, signer, storage_watcher, updater, heavy_queries)
 	}
 }
 
@@ -86,7 +109,10 @@ impl<C, M, S: ?Sized, F> EthcoreClient<C, M, S, F> where
 		net: &Arc<ManageNetwork>,
 		logger: Arc<RotatingLogger>,
 		settings: Arc<NetworkSettings>,
-		signer: Option<Arc<SignerService>>
+		signer: Option<Arc<SignerService>>,
+		storage_watcher: Arc<StorageWatcher>,
+		updater: Option<Arc<UpdateService>>,
+		heavy_queries: HeavyQueryOptions
 		) -> Self {
 		EthcoreClient {
 			client: Arc::downgrade(client),
@@ -96,6 +122,9 @@ impl<C, M, S: ?Sized, F> EthcoreClient<C, M, S, F> where
 			logger: logger,
 			settings: settings,
 			signer: signer,
+			storage_watcher: storage_watcher,
+			updater: updater,
+			heavy_queries: heavy_queries,
 			fetch: Mutex::new(F::default()),
 		}
 	}
@@ -107,6 +136,21 @@ impl<C, M, S: ?Sized, F> EthcoreClient<C, M, S, F> where
 	}
 }
 
+/// Resolve an inclusive block range to block numbers.
+fn block_range<C: MiningBlockChainClient>(client: &C, from: BlockNumber, to: BlockNumber) -> Result<(u64, u64), Error> {
+	let best_block = client.chain_info().best_block_number;
+	let number = |n: BlockNumber| match n {
+		BlockNumber::Num(n) => n,
+		BlockNumber::Earliest => 0,
+		BlockNumber::Latest | BlockNumber::Pending => best_block,
+	};
+	let (from, to) = (number(from), number(to));
+	if from > to {
+		return Err(errors::invalid_params("to", "Range end is lower than its start."));
+	}
+	Ok((from, to))
+}
+
 impl<C, M, S: ?Sized, F> Ethcore for EthcoreClient<C, M, S, F> where
 	M: MinerService + 'static,
 	C: MiningBlockChainClient + 'static,
@@ -119,6 +163,12 @@ impl<C, M, S: ?Sized, F> Ethcore for EthcoreClient<C, M, S, F> where
 		Ok(take_weak!(self.miner).transactions_limit())
 	}
 
+	fn author(&self) -> Result<H160, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.miner).author().into())
+	}
+
 	fn min_gas_price(&self) -> Result<U256, Error> {
 		try!(self.active());
 
@@ -153,7 +203,7 @@ impl<C, M, S: ?Sized, F> Ethcore for EthcoreClient<C, M, S, F> where
 	fn dev_logs_levels(&self) -> Result<String, Error> {
 		try!(self.active());
 
-		Ok(self.logger.levels().to_owned())
+		Ok(self.logger.levels())
 	}
 
 	fn net_chain(&self) -> Result<String, Error> {
@@ -165,16 +215,25 @@ impl<C, M, S: ?Sized, F> Ethcore for EthcoreClient<C, M, S, F> where
 	fn net_peers(&self) -> Result<Peers, Error> {
 		try!(self.active());
 
-		let sync_status = take_weak!(self.sync).status();
+		let sync = take_weak!(self.sync);
+		let sync_status = sync.status();
 		let net_config = take_weak!(self.net).network_config();
+		let peers = sync.peers().into_iter().map(Into::into).collect();
 
 		Ok(Peers {
 			active: sync_status.num_active_peers,
 			connected: sync_status.num_peers,
 			max: sync_status.current_max_peers(net_config.min_peers, net_config.max_peers),
+			peers: peers,
 		})
 	}
 
+	fn net_stats(&self) -> Result<NetStats, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.net).network_traffic().into())
+	}
+
 	fn net_port(&self) -> Result<u16, Error> {
 		try!(self.active());
 
@@ -187,16 +246,38 @@ impl<C, M, S: ?Sized, F> Ethcore for EthcoreClient<C, M, S, F> where
 		Ok(self.settings.name.clone())
 	}
 
-	fn registry_address(&self) -> Result<Option<H160>, Error> {
+	fn mode(&self) -> Result<String, Error> {
+		// querying the mode shouldn't wake up a sleeping client
+		Ok(match take_weak!(self.client).mode() {
+			IpcMode::Active => "active",
+			IpcMode::Passive(..) => "passive",
+			IpcMode::Dark(..) => "dark",
+			IpcMode::Off => "offline",
+			IpcMode::Relay => "relay",
+		}.into())
+	}
+
+	fn upgrade_ready(&self) -> Result<Option<Upgrade>, Error> {
+		try!(self.active());
+		Ok(self.updater.as_ref().and_then(|updater| updater.upgrade_ready()).map(Into::into))
+	}
+
+	fn registry_address(&self, name: Trailing<String>) -> Result<Option<H160>, Error> {
 		try!(self.active());
 
-		Ok(
-			take_weak!(self.client)
-				.additional_params()
-				.get("registrar")
-				.and_then(|s| Address::from_str(s).ok())
-				.map(|s| H160::from(s))
-		)
+		let client = take_weak!(self.client);
+		let name = name.0;
+		if name.is_empty() {
+			return Ok(
+				client
+					.additional_params()
+					.get("registrar")
+					.and_then(|s| Address::from_str(s).ok())
+					.map(|s| H160::from(s))
+			);
+		}
+
+		Ok(client.registry_address(name).map(H160::from))
 	}
 
 	fn rpc_settings(&self) -> Result<RpcSettings, Error> {
@@ -244,19 +325,22 @@ impl<C, M, S: ?Sized, F> Ethcore for EthcoreClient<C, M, S, F> where
 		Ok(Brain::new(phrase).generate().unwrap().address().into())
 	}
 
-	fn list_accounts(&self) -> Result<Option<Vec<H160>>, Error> {
+	fn list_accounts(&self, count: u64, after: Trailing<Option<H160>>) -> Result<Option<Vec<H160>>, Error> {
 		try!(self.active());
+		try!(ensure_state(&*take_weak!(self.client)));
 
+		let after = after.0.map(Into::into);
 		Ok(take_weak!(self.client)
-			.list_accounts(BlockID::Latest)
+			.list_accounts(BlockID::Latest, after.as_ref(), count)
 			.map(|a| a.into_iter().map(Into::into).collect()))
 	}
 
-	fn list_storage_keys(&self, _address: H160) -> Result<Option<Vec<H256>>, Error> {
+	fn list_storage_keys(&self, address: H160) -> Result<Option<Vec<H256>>, Error> {
 		try!(self.active());
 
-		// TODO: implement this
-		Ok(None)
+		Ok(take_weak!(self.client)
+			.list_storage(BlockID::Latest, &address.into())
+			.map(|s| s.into_iter().map(Into::into).collect()))
 	}
 
 	fn encrypt_message(&self, key: H512, phrase: Bytes) -> Result<Bytes, Error> {
@@ -273,6 +357,262 @@ impl<C, M, S: ?Sized, F> Ethcore for EthcoreClient<C, M, S, F> where
 		Ok(take_weak!(self.miner).all_transactions().into_iter().map(Into::into).collect::<Vec<_>>())
 	}
 
+	fn queued_transactions(&self) -> Result<Vec<QueuedTransaction>, Error> {
+		try!(self.active());
+
+		Ok(QueuedTransaction::from_queue(take_weak!(self.miner).all_transactions()))
+	}
+
+	fn future_transactions(&self) -> Result<Vec<QueuedTransaction>, Error> {
+		try!(self.active());
+
+		Ok(QueuedTransaction::from_queue(take_weak!(self.miner).future_transactions()))
+	}
+
+	fn new_storage_watch(&self, slots: Vec<StorageSlot>) -> Result<U256, Error> {
+		try!(self.active());
+
+		let slots = slots.into_iter().map(|s| (s.address.into(), s.key.into())).collect();
+		Ok(self.storage_watcher.add_watch(slots).into())
+	}
+
+	fn storage_watch_changes(&self, index: Index) -> Result<Vec<StorageChange>, Error> {
+		try!(self.active());
+
+		Ok(self.storage_watcher.take_changes(index.value())
+			.map(|changes| changes.into_iter().map(Into::into).collect())
+			.unwrap_or_default())
+	}
+
+	fn uninstall_storage_watch(&self, index: Index) -> Result<bool, Error> {
+		try!(self.active());
+
+		Ok(self.storage_watcher.remove_watch(index.value()))
+	}
+
+	fn health(&self) -> Result<Health, Error> {
+		try!(self.active());
+
+		let sync_status = take_weak!(self.sync).status();
+		Ok(Health {
+			peers: sync_status.num_active_peers,
+			syncing: sync_status.is_major_syncing(),
+			tx_queue_throttled: take_weak!(self.miner).is_admission_throttled(),
+		})
+	}
+
+	fn chain_status(&self) -> Result<ChainStatus, Error> {
+		try!(self.active());
+
+		let first_block = take_weak!(self.client).chain_info().first_block_number;
+		Ok(ChainStatus {
+			block_gap: if first_block > 1 { Some((1.into(), (first_block - 1).into())) } else { None },
+		})
+	}
+
+	fn block_header(&self, number: BlockNumber) -> Result<Option<Bytes>, Error> {
+		try!(self.active());
+
+		let client = take_weak!(self.client);
+		// answer `pending` from the miner's open block rather than the latest one.
+		if let BlockNumber::Pending = number {
+			take_weak!(self.miner).prepare_pending_block(&*client);
+		}
+		Ok(client.block_header(number.into()).map(Bytes::new))
+	}
+
+	fn block_headers(&self, from: BlockNumber, to: BlockNumber, max: Trailing<u64>) -> Result<Vec<Header>, Error> {
+		try!(self.active());
+
+		let client = take_weak!(self.client);
+		let (from, to) = try!(block_range(&*client, from, to));
+		let max = match max.0 {
+			0 => MAX_BLOCK_HEADERS,
+			max => cmp::min(max, MAX_BLOCK_HEADERS),
+		};
+
+		let mut headers = Vec::new();
+		for n in (from..to.saturating_add(1)).take(max as usize) {
+			match client.block_header(BlockID::Number(n)) {
+				Some(header) => headers.push(::rlp::decode::<EthHeader>(&header).into()),
+				None => break,
+			}
+		}
+		Ok(headers)
+	}
+
+	fn balance_history(&self, address: H160, from: BlockNumber, to: BlockNumber, step: Trailing<u64>) -> Result<Vec<BalanceSample>, Error> {
+		try!(self.active());
+
+		let client = take_weak!(self.client);
+		try!(ensure_state(&*client));
+		let (from, to) = try!(block_range(&*client, from, to));
+		let step = cmp::max(step.0, 1);
+		if (to - from) / step >= MAX_BALANCE_SAMPLES {
+			return Err(errors::query_too_heavy("ethcore_balanceHistory"));
+		}
+
+		let address = address.into();
+		let mut samples = Vec::new();
+		let mut number = from;
+		loop {
+			samples.push((number, client.balance(&address, BlockID::Number(number))));
+			match number.checked_add(step) {
+				Some(next) if next <= to => number = next,
+				_ => break,
+			}
+		}
+
+		// Pruning removes the oldest states first, reconstruct them backwards from traces
+		// starting at the oldest sample whose state is still available.
+		let pruned = samples.iter().take_while(|&&(_, balance)| balance.is_none()).count();
+		if pruned > 0 {
+			let (mut number, mut balance) = match samples.get(pruned) {
+				Some(&(number, Some(balance))) => (number, balance),
+				_ => {
+					let best = client.chain_info().best_block_number;
+					match client.balance(&address, BlockID::Number(best)) {
+						Some(balance) => (best, balance),
+						None => return Err(errors::state_pruned()),
+					}
+				},
+			};
+			if number - samples[0].0 > MAX_RECONSTRUCTED_BLOCKS {
+				return Err(errors::query_too_heavy("ethcore_balanceHistory"));
+			}
+
+			'reconstruct: for sample in samples[..pruned].iter_mut().rev() {
+				while number > sample.0 {
+					match client.balance_change(&address, BlockID::Number(number)) {
+						Some((gained, lost)) if balance + lost >= gained => balance = balance + lost - gained,
+						None if client.block_traces(BlockID::Number(number)).is_none() => return Err(errors::state_pruned_without_traces()),
+						// rewards and irregular state changes are not traced, older balances stay unknown
+						_ => break 'reconstruct,
+					}
+					number -= 1;
+				}
+				sample.1 = Some(balance);
+			}
+		}
+
+		Ok(samples.into_iter().map(|(number, balance)| BalanceSample {
+			number: number.into(),
+			balance: balance.map(Into::into),
+		}).collect())
+	}
+
+	fn block_body(&self, number: BlockNumber) -> Result<Option<Bytes>, Error> {
+		try!(self.active());
+
+		let client = take_weak!(self.client);
+		if let BlockNumber::Pending = number {
+			take_weak!(self.miner).prepare_pending_block(&*client);
+		}
+		Ok(client.block_body(number.into()).map(Bytes::new))
+	}
+
+	fn state_diff(&self, from: BlockNumber, to: BlockNumber, accounts: StateDiffAccounts) -> Result<StateDiff, Error> {
+		try!(self.active());
+		let client = take_weak!(self.client);
+		try!(ensure_state(&*client));
+
+		let params = (from.clone(), to.clone(), accounts.clone());
+		let timer = QueryTimer::start("ethcore_stateDiff", &params, &self.heavy_queries);
+		let (from, to): (BlockID, BlockID) = (from.into(), to.into());
+		let addresses: Vec<Address> = match accounts {
+			StateDiffAccounts::Listed(addresses) => addresses.into_iter().map(Into::into).collect(),
+			StateDiffAccounts::Page(page) => {
+				if client.list_accounts(BlockID::Latest, None, 0).is_none() {
+					return Err(errors::fat_db_required());
+				}
+				let after = page.after.map(Into::into);
+				let pre = try!(client.list_accounts(from.clone(), after.as_ref(), page.count).ok_or_else(errors::state_pruned));
+				let post = try!(client.list_accounts(to.clone(), after.as_ref(), page.count).ok_or_else(errors::state_pruned));
+
+				// both pages are in trie order, merge them and keep the first `count` accounts.
+				let mut addresses: Vec<Address> = pre.into_iter().chain(post.into_iter()).collect();
+				addresses.sort_by_key(|address| address.sha3());
+				addresses.dedup();
+				addresses.truncate(page.count as usize);
+				addresses
+			},
+		};
+
+		heavy_query::state_diff(&*client, from, to, addresses, &timer).map(Into::into)
+	}
+
+	fn chain_params(&self) -> Result<ChainParams, Error> {
+		try!(self.active());
+
+		let client = take_weak!(self.client);
+		let network_id = client.network_id();
+		Ok(ChainParams {
+			chain_id: network_id.into(),
+			network_id: network_id.into(),
+			engine: client.engine_name(),
+			forks: client.fork_transitions().into_iter().map(|(name, block)| (name, block.into())).collect(),
+			engine_params: client.additional_params(),
+		})
+	}
+
+	fn chain_spec(&self) -> Result<ChainSpec, Error> {
+		let params = try!(self.chain_params());
+
+		let client = take_weak!(self.client);
+		Ok(ChainSpec {
+			params: params,
+			builtins: client.builtins().into_iter().map(|(address, builtin)| (address.into(), Builtin {
+				name: builtin.name,
+				activate_at: builtin.activate_at.into(),
+			})).collect(),
+			genesis_hash: client.chain_info().genesis_hash.into(),
+		})
+	}
+
+	fn cht_root(&self, index: Index) -> Result<Option<H256>, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.client).cht_root(index.value() as u64).map(Into::into))
+	}
+
+	fn extra_data_histogram(&self, blocks: Trailing<u64>) -> Result<BTreeMap<String, u64>, Error> {
+		try!(self.active());
+
+		let client = take_weak!(self.client);
+		let blocks = match blocks.0 {
+			0 => DEFAULT_HISTOGRAM_BLOCKS,
+			blocks => cmp::min(blocks, MAX_HISTOGRAM_BLOCKS),
+		};
+		let best_block = client.chain_info().best_block_number;
+
+		let mut histogram = BTreeMap::new();
+		for n in (best_block.saturating_sub(blocks - 1)..best_block + 1).rev() {
+			match client.block_header(BlockID::Number(n)) {
+				Some(header) => {
+					let header: EthHeader = ::rlp::decode(&header);
+					*histogram.entry(decode_extra_data(header.extra_data())).or_insert(0) += 1;
+				},
+				// blocks before the snapshot of a warp sync
+				None => break,
+			}
+		}
+		Ok(histogram)
+	}
+
+	fn bad_blocks(&self) -> Result<Vec<BadBlock>, Error> {
+		try!(self.active());
+
+		Ok(take_weak!(self.client).bad_blocks().into_iter().map(Into::into).collect())
+	}
+
+	fn node_events(&self) -> Result<Vec<NodeEvent>, Error> {
+		try!(self.active());
+
+		Ok(node_events::journal()
+			.map(|journal| journal.events().into_iter().map(Into::into).collect())
+			.unwrap_or_default())
+	}
+
 	fn hash_content(&self, ready: Ready<H256>, url: String) {
 		let res = self.active();
 
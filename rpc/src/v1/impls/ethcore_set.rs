@@ -17,12 +17,13 @@
 /// Ethcore-specific rpc interface for operations altering the settings.
 use std::sync::{Arc, Weak};
 use jsonrpc_core::*;
-use ethcore::miner::MinerService;
-use ethcore::client::MiningBlockChainClient;
+use ethcore::miner::{MinerService, check_extra_data};
+use ethcore::client::{MiningBlockChainClient, IpcMode};
 use ethsync::ManageNetwork;
+use util::RotatingLogger;
 use v1::helpers::errors;
 use v1::traits::EthcoreSet;
-use v1::types::{Bytes, H160, U256};
+use v1::types::{Bytes, H160, H256, U256, Transaction};
 
 /// Ethcore-specific rpc interface for operations altering the settings.
 pub struct EthcoreSetClient<C, M> where
@@ -32,17 +33,25 @@ pub struct EthcoreSetClient<C, M> where
 	client: Weak<C>,
 	miner: Weak<M>,
 	net: Weak<ManageNetwork>,
+	logger: Arc<RotatingLogger>,
+	mode_timeout: u64,
+	mode_alarm: u64,
 }
 
 impl<C, M> EthcoreSetClient<C, M> where
 	C: MiningBlockChainClient,
 	M: MinerService {
 	/// Creates new `EthcoreSetClient`.
-	pub fn new(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>) -> Self {
+	/// `mode_timeout` and `mode_alarm` are the seconds after which a passive or dark client goes to sleep
+	/// and a sleeping passive client wakes up, used when the mode is changed.
+	pub fn new(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>, logger: Arc<RotatingLogger>, mode_timeout: u64, mode_alarm: u64) -> Self {
 		EthcoreSetClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
 			net: Arc::downgrade(net),
+			logger: logger,
+			mode_timeout: mode_timeout,
+			mode_alarm: mode_alarm,
 		}
 	}
 
@@ -81,7 +90,10 @@ impl<C, M> EthcoreSet for EthcoreSetClient<C, M> where
 	fn set_extra_data(&self, extra_data: Bytes) -> Result<bool, Error> {
 		try!(self.active());
 
-		take_weak!(self.miner).set_extra_data(extra_data.to_vec());
+		let miner = take_weak!(self.miner);
+		let extra_data = extra_data.to_vec();
+		try!(check_extra_data(&extra_data, miner.maximum_extra_data_size()).map_err(|e| errors::invalid_params("Extra data", e)));
+		miner.set_extra_data(extra_data);
 		Ok(true)
 	}
 
@@ -147,4 +159,44 @@ impl<C, M> EthcoreSet for EthcoreSetClient<C, M> where
 		take_weak!(self.net).stop_network();
 		Ok(true)
 	}
+
+	fn set_mode(&self, mode: String) -> Result<bool, Error> {
+		let client = take_weak!(self.client);
+		if client.mode() == IpcMode::Relay {
+			return Err(errors::relay_mode());
+		}
+
+		let mode = match mode.as_str() {
+			"active" => IpcMode::Active,
+			"passive" => IpcMode::Passive(self.mode_timeout, self.mode_alarm),
+			"dark" => IpcMode::Dark(self.mode_timeout),
+			"offline" => IpcMode::Off,
+			other => return Err(errors::invalid_params("mode", format!("Unknown mode: {}", other))),
+		};
+		client.set_mode(mode);
+		Ok(true)
+	}
+
+	fn set_logger_level(&self, levels: String) -> Result<bool, Error> {
+		match self.logger.set_levels(&levels) {
+			Ok(()) => Ok(true),
+			Err(e) => Err(errors::invalid_params("Logger levels", e)),
+		}
+	}
+
+	fn force_reorg(&self, hash: H256) -> Result<bool, Error> {
+		if take_weak!(self.client).force_reorg(hash.into()) {
+			Ok(true)
+		} else {
+			Err(errors::no_refused_block())
+		}
+	}
+
+	fn remove_transaction(&self, hash: H256) -> Result<Option<Transaction>, Error> {
+		try!(self.active());
+
+		let miner = take_weak!(self.miner);
+		let client = take_weak!(self.client);
+		Ok(miner.remove_pending_transaction(&*client, &hash.into()).map(Into::into))
+	}
 }
@@ -33,6 +33,7 @@ mod ethcore_set;
 mod net;
 mod personal;
 mod personal_signer;
+mod private;
 mod rpc;
 mod traces;
 mod web3;
@@ -47,4 +48,5 @@ pub use self::personal_signer::SignerClient;
 pub use self::ethcore::EthcoreClient;
 pub use self::ethcore_set::EthcoreSetClient;
 pub use self::traces::TracesClient;
+pub use self::private::PrivateClient;
 pub use self::rpc::RpcClient;
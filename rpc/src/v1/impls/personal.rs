@@ -21,10 +21,11 @@ use util::{Address};
 use jsonrpc_core::*;
 use ethkey::{Brain, Generator};
 use v1::traits::Personal;
-use v1::types::{H160 as RpcH160, TransactionRequest};
-use v1::helpers::{errors, TransactionRequest as TRequest};
+use super::eth_signing::DispatchResult;
+use v1::types::{H160 as RpcH160, H520 as RpcH520, Bytes as RpcBytes, TransactionRequest, DeriveHash, DeriveHierarchicalItem};
+use v1::helpers::{errors, TransactionRequest as TRequest, SignerService, SigningQueue, ConfirmationPayload};
 use v1::helpers::params::expect_no_params;
-use v1::helpers::dispatch::unlock_sign_and_dispatch;
+use v1::helpers::dispatch::{unlock_sign, unlock_sign_and_dispatch, signed_transaction_rlp, eth_data_hash, recover_message_signer};
 use ethcore::account_provider::AccountProvider;
 use ethcore::client::MiningBlockChainClient;
 use ethcore::miner::MinerService;
@@ -35,17 +36,19 @@ pub struct PersonalClient<C, M> where C: MiningBlockChainClient, M: MinerService
 	client: Weak<C>,
 	miner: Weak<M>,
 	signer_port: Option<u16>,
+	signer: Option<Weak<SignerService>>,
 	allow_perm_unlock: bool,
 }
 
 impl<C, M> PersonalClient<C, M> where C: MiningBlockChainClient, M: MinerService {
-	/// Creates new PersonalClient
-	pub fn new(store: &Arc<AccountProvider>, client: &Arc<C>, miner: &Arc<M>, signer_port: Option<u16>, allow_perm_unlock: bool) -> Self {
+	/// Creates new PersonalClient. Messages signed with locked accounts are sent to `signer` for confirmation.
+	pub fn new(store: &Arc<AccountProvider>, client: &Arc<C>, miner: &Arc<M>, signer_port: Option<u16>, signer: Option<&Arc<SignerService>>, allow_perm_unlock: bool) -> Self {
 		PersonalClient {
 			accounts: Arc::downgrade(store),
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
 			signer_port: signer_port,
+			signer: signer.map(Arc::downgrade),
 			allow_perm_unlock: allow_perm_unlock,
 		}
 	}
@@ -55,10 +58,49 @@ impl<C, M> PersonalClient<C, M> where C: MiningBlockChainClient, M: MinerService
 		take_weak!(self.client).keep_alive();
 		Ok(())
 	}
+
+	fn dispatch_sign_message(&self, params: Params) -> Result<DispatchResult, Error> {
+		from_params::<(RpcBytes, RpcH160)>(params).and_then(|(data, address)| {
+			let address: Address = address.into();
+
+			let accounts = take_weak!(self.accounts);
+			let signer = match self.signer {
+				Some(ref signer) if !accounts.is_unlocked(address) => take_weak!(signer),
+				_ => {
+					let hash = eth_data_hash(data.0);
+					return Ok(DispatchResult::Value(to_value(&accounts.sign(address, hash).ok().map_or_else(RpcH520::default, Into::into))))
+				},
+			};
+
+			signer.add_request(ConfirmationPayload::SignMessage(address, data.0))
+				.map(DispatchResult::Promise)
+				.map_err(|_| errors::request_rejected_limit())
+		})
+	}
 }
 
 impl<C: 'static, M: 'static> Personal for PersonalClient<C, M> where C: MiningBlockChainClient, M: MinerService {
 
+	fn sign(&self, params: Params, ready: Ready) {
+		let res = self.active().and_then(|_| self.dispatch_sign_message(params));
+		match res {
+			Ok(DispatchResult::Promise(promise)) => {
+				promise.wait_for_result(move |result| {
+					ready.ready(result.unwrap_or_else(|| Err(errors::request_rejected())))
+				})
+			},
+			Ok(DispatchResult::Value(v)) => ready.ready(Ok(v)),
+			Err(e) => ready.ready(Err(e)),
+		}
+	}
+
+	fn ec_recover(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(RpcBytes, RpcH520)>(params).and_then(|(data, signature)| {
+			recover_message_signer(data.0, signature.into())
+		})
+	}
+
 	fn signer_enabled(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		try!(expect_no_params(params));
@@ -146,6 +188,18 @@ impl<C: 'static, M: 'static> Personal for PersonalClient<C, M> where C: MiningBl
 			})
 	}
 
+	fn sign_transaction(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(TransactionRequest, String)>(params)
+			.and_then(|(request, password)| {
+				let request: TRequest = request.into();
+				let accounts = take_weak!(self.accounts);
+
+				unlock_sign(&*take_weak!(self.client), &*take_weak!(self.miner), request, &*accounts, password)
+					.map(|signed| signed_transaction_rlp(&signed))
+			})
+	}
+
 	fn set_account_name(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		let store = take_weak!(self.accounts);
@@ -202,4 +256,98 @@ impl<C: 'static, M: 'static> Personal for PersonalClient<C, M> where C: MiningBl
 			).into_iter().map(Into::into).collect::<Vec<RpcH160>>()))
 		})
 	}
+
+	fn new_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(String, String)>(params).and_then(|(name, password)| {
+			let store = take_weak!(self.accounts);
+			try!(store.create_vault(&name, &password).map_err(|e| errors::account("Could not create vault.", e)));
+			Ok(Value::Bool(true))
+		})
+	}
+
+	fn open_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(String, String)>(params).and_then(|(name, password)| {
+			let store = take_weak!(self.accounts);
+			try!(store.open_vault(&name, &password).map_err(|e| errors::account("Could not open vault.", e)));
+			Ok(Value::Bool(true))
+		})
+	}
+
+	fn close_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(String,)>(params).and_then(|(name,)| {
+			let store = take_weak!(self.accounts);
+			try!(store.close_vault(&name).map_err(|e| errors::account("Could not close vault.", e)));
+			Ok(Value::Bool(true))
+		})
+	}
+
+	fn list_vaults(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+		let store = take_weak!(self.accounts);
+		let vaults = try!(store.list_vaults().map_err(|e| errors::account("Could not list vaults.", e)));
+		Ok(to_value(&vaults))
+	}
+
+	fn list_opened_vaults(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+		let store = take_weak!(self.accounts);
+		let vaults = try!(store.list_opened_vaults().map_err(|e| errors::account("Could not list vaults.", e)));
+		Ok(to_value(&vaults))
+	}
+
+	fn change_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(RpcH160, String, String)>(params).and_then(|(addr, password, vault)| {
+			let store = take_weak!(self.accounts);
+			let vault = if vault.is_empty() { None } else { Some(vault.as_str()) };
+			try!(store.change_vault(addr.into(), &password, vault).map_err(|e| errors::account("Could not change vault.", e)));
+			Ok(Value::Bool(true))
+		})
+	}
+
+	fn get_vault_meta(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(String,)>(params).and_then(|(name,)| {
+			let store = take_weak!(self.accounts);
+			let meta = try!(store.get_vault_meta(&name).map_err(|e| errors::account("Could not get vault metadata.", e)));
+			Ok(to_value(&meta))
+		})
+	}
+
+	fn set_vault_meta(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(String, String)>(params).and_then(|(name, meta)| {
+			let store = take_weak!(self.accounts);
+			try!(store.set_vault_meta(&name, &meta).map_err(|e| errors::account("Could not set vault metadata.", e)));
+			Ok(Value::Bool(true))
+		})
+	}
+
+	fn derive_address_hash(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(RpcH160, String, DeriveHash, bool)>(params).and_then(|(addr, password, derivation, save)| {
+			let store = take_weak!(self.accounts);
+			let address = try!(store.derive_account(&addr.into(), password, &[derivation.into()], save)
+				.map_err(|e| errors::account("Could not derive account.", e)));
+			Ok(to_value(&RpcH160::from(address)))
+		})
+	}
+
+	fn derive_address_index(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(RpcH160, String, Vec<DeriveHierarchicalItem>, bool)>(params).and_then(|(addr, password, items, save)| {
+			let path = try!(items.iter()
+				.map(|item| item.to_derivation().ok_or_else(|| errors::invalid_params("index", "Index has to be lower than 2^31.")))
+				.collect::<Result<Vec<_>, _>>());
+			let store = take_weak!(self.accounts);
+			let address = try!(store.derive_account(&addr.into(), password, &path, save)
+				.map_err(|e| errors::account("Could not derive account.", e)));
+			Ok(to_value(&RpcH160::from(address)))
+		})
+	}
 }
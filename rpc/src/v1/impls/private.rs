@@ -0,0 +1,88 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+//! Private transactions rpc implementation.
+
+use std::sync::{Arc, Weak};
+use jsonrpc_core::*;
+use rlp::{UntrustedRlp, View};
+use ethcore::client::BlockChainClient;
+use ethcore::miner::MinerService;
+use ethcore::private_transactions::Provider;
+use ethcore::transaction::{Transaction as EthTransaction, SignedTransaction, Action};
+use util::{Address, U256};
+use v1::helpers::{errors, CallRequest as CRequest};
+use v1::helpers::auto_args::Trailing;
+use v1::helpers::dispatch::ensure_state;
+use v1::traits::Private;
+use v1::types::{Bytes, BlockNumber, CallRequest, PrivateTransactionReceipt};
+
+/// Private transactions rpc implementation.
+pub struct PrivateClient<C, M> where C: BlockChainClient, M: MinerService {
+	provider: Weak<Provider>,
+	client: Weak<C>,
+	miner: Weak<M>,
+}
+
+impl<C, M> PrivateClient<C, M> where C: BlockChainClient, M: MinerService {
+	/// Creates new `PrivateClient`.
+	pub fn new(provider: &Arc<Provider>, client: &Arc<C>, miner: &Arc<M>) -> Self {
+		PrivateClient {
+			provider: Arc::downgrade(provider),
+			client: Arc::downgrade(client),
+			miner: Arc::downgrade(miner),
+		}
+	}
+
+	fn sign_call(&self, request: CRequest) -> Result<SignedTransaction, Error> {
+		let (client, miner) = (take_weak!(self.client), take_weak!(self.miner));
+		try!(ensure_state(&*client));
+		let from = request.from.unwrap_or(Address::zero());
+		Ok(EthTransaction {
+			nonce: request.nonce.unwrap_or_else(|| client.latest_nonce(&from)),
+			action: request.to.map_or(Action::Create, Action::Call),
+			gas: request.gas.unwrap_or(U256::from(50_000_000)),
+			gas_price: request.gas_price.unwrap_or_else(|| miner.sensible_gas_price()),
+			value: request.value.unwrap_or_else(U256::zero),
+			data: request.data.map_or_else(Vec::new, |d| d.to_vec())
+		}.fake_sign(from))
+	}
+
+	fn active(&self) -> Result<(), Error> {
+		// TODO: only call every 30s at most.
+		take_weak!(self.client).keep_alive();
+		Ok(())
+	}
+}
+
+impl<C, M> Private for PrivateClient<C, M> where C: BlockChainClient + 'static, M: MinerService + 'static {
+	fn send_transaction(&self, raw: Bytes) -> Result<PrivateTransactionReceipt, Error> {
+		try!(self.active());
+		try!(ensure_state(&*take_weak!(self.client)));
+		let signed: SignedTransaction = try!(UntrustedRlp::new(&raw.to_vec()).as_val()
+			.map_err(|e| errors::invalid_params("Transaction is not valid RLP", e)));
+		take_weak!(self.provider).create_private_transaction(signed)
+			.map(Into::into)
+			.map_err(errors::from_private_transaction_error)
+	}
+
+	fn call(&self, request: CallRequest, num: Trailing<BlockNumber>) -> Result<Bytes, Error> {
+		try!(self.active());
+		let signed = try!(self.sign_call(CallRequest::into(request)));
+		take_weak!(self.provider).private_call(num.0.into(), &signed)
+			.map(|executed| Bytes(executed.output))
+			.map_err(errors::from_private_transaction_error)
+	}
+}
@@ -19,34 +19,54 @@
 use std::sync::{Weak, Arc};
 use jsonrpc_core::*;
 use rlp::{UntrustedRlp, View};
-use ethcore::client::{BlockChainClient, CallAnalytics, TransactionID, TraceId};
+use ethcore::client::{BlockChainClient, CallAnalytics, Executed, TransactionID, TraceId};
+use ethcore::trace::code_coverage;
 use ethcore::miner::MinerService;
 use ethcore::transaction::{Transaction as EthTransaction, SignedTransaction, Action};
 use v1::traits::Traces;
-use v1::helpers::{errors, CallRequest as CRequest};
+use v1::helpers::{errors, CallRequest as CRequest, HeavyQueryOptions};
+use v1::helpers::heavy_query::{self, QueryTimer};
+use v1::helpers::dispatch::ensure_state;
 use v1::helpers::params::from_params_default_third;
 use v1::types::{TraceFilter, LocalizedTrace, BlockNumber, Index, CallRequest, Bytes, TraceResults, H256};
 
 fn to_call_analytics(flags: Vec<String>) -> CallAnalytics {
 	CallAnalytics {
 		transaction_tracing: flags.contains(&("trace".to_owned())),
-		vm_tracing: flags.contains(&("vmTrace".to_owned())),
+		vm_tracing: flags.contains(&("vmTrace".to_owned())) || flags.contains(&("coverage".to_owned())),
 		state_diffing: flags.contains(&("stateDiff".to_owned())),
 	}
 }
 
+fn to_trace_results(mut executed: Executed, flags: &[String]) -> TraceResults {
+	let coverage = match flags.contains(&("coverage".to_owned())) {
+		true => executed.vm_trace.as_ref().map(|t| code_coverage(t).into_iter().map(Into::into).collect()),
+		false => None,
+	};
+	// VM trace was only collected to compute coverage.
+	if !flags.contains(&("vmTrace".to_owned())) {
+		executed.vm_trace = None;
+	}
+
+	let mut results = TraceResults::from(executed);
+	results.coverage = coverage;
+	results
+}
+
 /// Traces api implementation.
 pub struct TracesClient<C, M> where C: BlockChainClient, M: MinerService {
 	client: Weak<C>,
 	miner: Weak<M>,
+	heavy_queries: HeavyQueryOptions,
 }
 
 impl<C, M> TracesClient<C, M> where C: BlockChainClient, M: MinerService {
 	/// Creates new Traces client.
-	pub fn new(client: &Arc<C>, miner: &Arc<M>) -> Self {
+	pub fn new(client: &Arc<C>, miner: &Arc<M>, heavy_queries: HeavyQueryOptions) -> Self {
 		TracesClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
+			heavy_queries: heavy_queries,
 		}
 	}
 
@@ -54,6 +74,7 @@ impl<C, M> TracesClient<C, M> where C: BlockChainClient, M: MinerService {
 	fn sign_call(&self, request: CRequest) -> Result<SignedTransaction, Error> {
 		let client = take_weak!(self.client);
 		let miner = take_weak!(self.miner);
+		try!(ensure_state(&*client));
 		let from = request.from.unwrap_or(0.into());
 		Ok(EthTransaction {
 			nonce: request.nonce.unwrap_or_else(|| client.latest_nonce(&from)),
@@ -77,8 +98,9 @@ impl<C, M> Traces for TracesClient<C, M> where C: BlockChainClient + 'static, M:
 		try!(self.active());
 		from_params::<(TraceFilter,)>(params)
 			.and_then(|(filter, )| {
+				let timer = QueryTimer::start("trace_filter", &filter, &self.heavy_queries);
 				let client = take_weak!(self.client);
-				let traces = client.filter_traces(filter.into());
+				let traces = try!(heavy_query::traces(&*client, filter.clone().into(), &timer));
 				let traces = traces.map_or_else(Vec::new, |traces| traces.into_iter().map(LocalizedTrace::from).collect());
 				Ok(to_value(&traces))
 			})
@@ -123,12 +145,12 @@ impl<C, M> Traces for TracesClient<C, M> where C: BlockChainClient + 'static, M:
 
 	fn call(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
-		from_params_default_third(params)
+		from_params_default_third::<CallRequest, Vec<String>>(params)
 			.and_then(|(request, flags, block)| {
 				let request = CallRequest::into(request);
 				let signed = try!(self.sign_call(request));
-				match take_weak!(self.client).call(&signed, block.into(), to_call_analytics(flags)) {
-					Ok(e) => Ok(to_value(&TraceResults::from(e))),
+				match take_weak!(self.client).call(&signed, block.into(), to_call_analytics(flags.clone())) {
+					Ok(e) => Ok(to_value(&to_trace_results(e, &flags))),
 					_ => Ok(Value::Null),
 				}
 			})
@@ -136,12 +158,12 @@ impl<C, M> Traces for TracesClient<C, M> where C: BlockChainClient + 'static, M:
 
 	fn raw_transaction(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
-		from_params_default_third(params)
+		from_params_default_third::<Bytes, Vec<String>>(params)
 			.and_then(|(raw_transaction, flags, block)| {
 				let raw_transaction = Bytes::to_vec(raw_transaction);
 				match UntrustedRlp::new(&raw_transaction).as_val() {
-					Ok(signed) => match take_weak!(self.client).call(&signed, block.into(), to_call_analytics(flags)) {
-						Ok(e) => Ok(to_value(&TraceResults::from(e))),
+					Ok(signed) => match take_weak!(self.client).call(&signed, block.into(), to_call_analytics(flags.clone())) {
+						Ok(e) => Ok(to_value(&to_trace_results(e, &flags))),
 						_ => Ok(Value::Null),
 					},
 					Err(e) => Err(errors::invalid_params("Transaction is not valid RLP", e)),
@@ -151,10 +173,10 @@ impl<C, M> Traces for TracesClient<C, M> where C: BlockChainClient + 'static, M:
 
 	fn replay_transaction(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
-		from_params::<(H256, _)>(params)
+		from_params::<(H256, Vec<String>)>(params)
 			.and_then(|(transaction_hash, flags)| {
-				match take_weak!(self.client).replay(TransactionID::Hash(transaction_hash.into()), to_call_analytics(flags)) {
-					Ok(e) => Ok(to_value(&TraceResults::from(e))),
+				match take_weak!(self.client).replay(TransactionID::Hash(transaction_hash.into()), to_call_analytics(flags.clone())) {
+					Ok(e) => Ok(to_value(&to_trace_results(e, &flags))),
 					_ => Ok(Value::Null),
 				}
 			})
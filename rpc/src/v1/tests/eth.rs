@@ -24,18 +24,23 @@ use ethcore::spec::{Genesis, Spec};
 use ethcore::block::Block;
 use ethcore::views::BlockView;
 use ethcore::ethereum;
-use ethcore::miner::{MinerOptions, GasPricer, MinerService, ExternalMiner, Miner, PendingSet};
+use ethcore::miner::{MinerOptions, GasPricer, MinerService, ExternalMiner, Miner, PendingSet, EvictionStrategy};
 use ethcore::account_provider::AccountProvider;
+use ethcore::private_transactions::{Provider, ProviderConfig, AesEncryptor};
+use ethcore::transaction::{Transaction, Action};
 use devtools::RandomTempPath;
 use util::Hashable;
 use io::IoChannel;
 use util::{U256, H256, Uint, Address};
+use rlp;
+use rustc_serialize::hex::ToHex;
 use jsonrpc_core::IoHandler;
 use ethjson::blockchain::BlockChain;
 
 use v1::types::U256 as NU256;
 use v1::traits::eth::{Eth, EthSigning};
-use v1::impls::{EthClient, EthSigningUnsafeClient};
+use v1::traits::Private;
+use v1::impls::{EthClient, EthSigningUnsafeClient, PrivateClient};
 use v1::tests::helpers::{TestSyncProvider, Config};
 
 fn account_provider() -> Arc<AccountProvider> {
@@ -57,11 +62,16 @@ fn miner_service(spec: &Spec, accounts: Arc<AccountProvider>) -> Arc<Miner> {
 			reseal_on_external_tx: true,
 			reseal_on_own_tx: true,
 			tx_queue_size: 1024,
+			tx_queue_per_sender: usize::max_value(),
+			tx_queue_memory_limit: None,
+			tx_queue_eviction: EvictionStrategy::GasPrice,
 			tx_gas_limit: !U256::zero(),
 			pending_set: PendingSet::SealingOrElseQueue,
 			reseal_min_period: Duration::from_secs(0),
 			work_queue_size: 50,
 			enable_resubmission: true,
+			reorg_circuit_breaker: Default::default(),
+			refuse_service_transactions: false,
 		},
 		GasPricer::new_fixed(20_000_000_000u64.into()),
 		&spec,
@@ -437,6 +447,33 @@ fn starting_nonce_test() {
 	assert_eq!(r#"{"jsonrpc":"2.0","result":"0x100","id":15}"#, &sample);
 }
 
+#[test]
+fn rpc_private_send_transaction() {
+	let tester = EthTester::from_spec(Spec::new_test());
+	let miner = Arc::new(Miner::with_spec(&Spec::new_test()));
+	let encryptor = AesEncryptor::new(|_: &Address| Ok(vec![1, 2, 3]));
+	let provider = Arc::new(Provider::new(ProviderConfig::default(), Box::new(encryptor), tester.client.clone(), miner.clone(), tester.accounts.clone()));
+	let handler = IoHandler::new();
+	handler.add_delegate(PrivateClient::new(&provider, &tester.client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc":"2.0","method":"private_sendTransaction","params":["0x1234"],"id":1}"#;
+	let response = handler.handle_request_sync(request).unwrap();
+	assert!(response.contains(r#""code":-32602"#), "Expected invalid params, got {}", response);
+
+	let transaction = Transaction {
+		nonce: U256::zero(),
+		action: Action::Call(Address::from(5)),
+		gas: U256::from(100_000),
+		gas_price: U256::zero(),
+		value: U256::zero(),
+		data: vec![],
+	}.sign(&"sender".sha3());
+	let request = r#"{"jsonrpc":"2.0","method":"private_sendTransaction","params":["0x"#.to_owned()
+		+ &rlp::encode(&transaction).to_vec().to_hex() + r#""],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32080,"message":"No account configured to sign public transactions","data":null},"id":1}"#;
+	assert_eq!(handler.handle_request_sync(&request).unwrap(), response);
+}
+
 register_test!(eth_transaction_count_1, verify_transaction_counts, "BlockchainTests/bcWalletTest");
 register_test!(eth_transaction_count_2, verify_transaction_counts, "BlockchainTests/bcTotalDifficultyTest");
 register_test!(eth_transaction_count_3, verify_transaction_counts, "BlockchainTests/bcGasPricerTest");
@@ -34,10 +34,14 @@ pub struct TestMinerService {
 	pub latest_closed_block: Mutex<Option<ClosedBlock>>,
 	/// Pre-existed pending transactions
 	pub pending_transactions: Mutex<HashMap<H256, SignedTransaction>>,
+	/// Transactions waiting for a nonce gap to be filled.
+	pub future_transactions: Mutex<Vec<SignedTransaction>>,
 	/// Pre-existed pending receipts
 	pub pending_receipts: Mutex<BTreeMap<H256, Receipt>>,
 	/// Last nonces.
 	pub last_nonces: RwLock<HashMap<Address, U256>>,
+	/// Number of times the pending block was prepared.
+	pub pending_block_requests: Mutex<usize>,
 
 	min_gas_price: RwLock<U256>,
 	gas_range_target: RwLock<(U256, U256)>,
@@ -53,8 +57,10 @@ impl Default for TestMinerService {
 			imported_transactions: Mutex::new(Vec::new()),
 			latest_closed_block: Mutex::new(None),
 			pending_transactions: Mutex::new(HashMap::new()),
+			future_transactions: Mutex::new(Vec::new()),
 			pending_receipts: Mutex::new(BTreeMap::new()),
 			last_nonces: RwLock::new(HashMap::new()),
+			pending_block_requests: Mutex::new(0),
 			min_gas_price: RwLock::new(U256::from(20_000_000)),
 			gas_range_target: RwLock::new((U256::from(12345), U256::from(54321))),
 			author: RwLock::new(Address::zero()),
@@ -84,6 +90,10 @@ impl MinerService for TestMinerService {
 		*self.extra_data.write() = extra_data;
 	}
 
+	fn maximum_extra_data_size(&self) -> usize {
+		32
+	}
+
 	/// Set the lower gas limit we wish to target when sealing a new block.
 	fn set_gas_floor_target(&self, target: U256) {
 		self.gas_range_target.write().0 = target;
@@ -182,9 +192,13 @@ impl MinerService for TestMinerService {
 		unimplemented!();
 	}
 
+	fn prepare_pending_block(&self, _chain: &MiningBlockChainClient) {
+		*self.pending_block_requests.lock() += 1;
+	}
+
 	fn map_sealing_work<F, T>(&self, chain: &MiningBlockChainClient, f: F) -> Option<T> where F: FnOnce(&ClosedBlock) -> T {
 		let open_block = chain.prepare_open_block(self.author(), *self.gas_range_target.write(), self.extra_data());
-		Some(f(&open_block.close()))
+		open_block.close().ok().map(|block| f(&block))
 	}
 
 	fn transaction(&self, _best_block: BlockNumber, hash: &H256) -> Option<SignedTransaction> {
@@ -199,6 +213,14 @@ impl MinerService for TestMinerService {
 		self.pending_transactions.lock().values().cloned().collect()
 	}
 
+	fn future_transactions(&self) -> Vec<SignedTransaction> {
+		self.future_transactions.lock().clone()
+	}
+
+	fn remove_pending_transaction(&self, _chain: &MiningBlockChainClient, hash: &H256) -> Option<SignedTransaction> {
+		self.pending_transactions.lock().remove(hash)
+	}
+
 	fn pending_receipt(&self, _best_block: BlockNumber, hash: &H256) -> Option<RichReceipt> {
 		// Not much point implementing this since the logic is complex and the only thing it relies on is pending_receipts, which is already tested.
 		self.pending_receipts(0).get(hash).map(|r|
@@ -225,6 +247,10 @@ impl MinerService for TestMinerService {
 		false
 	}
 
+	fn is_admission_throttled(&self) -> bool {
+		false
+	}
+
 	/// Submit `seal` as a valid solution for the header of `pow_hash`.
 	/// Will check the seal, but not actually insert the block into the chain.
 	fn submit_seal(&self, _chain: &MiningBlockChainClient, _pow_hash: H256, _seal: Vec<Bytes>) -> Result<(), Error> {
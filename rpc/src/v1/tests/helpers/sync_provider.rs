@@ -17,7 +17,7 @@
 //! Test implementation of SyncProvider.
 
 use util::{RwLock, U256};
-use ethsync::{SyncProvider, SyncStatus, SyncState};
+use ethsync::{SyncProvider, SyncStatus, SyncState, PeerInfo};
 
 /// TestSyncProvider config.
 pub struct Config {
@@ -51,6 +51,9 @@ impl TestSyncProvider {
 				mem_used: 0,
 				num_snapshot_chunks: 0,
 				snapshot_chunks_done: 0,
+				state_nodes_downloaded: 0,
+				state_nodes_pending: 0,
+				estimated_time_remaining: None,
 			}),
 		}
 	}
@@ -60,5 +63,40 @@ impl SyncProvider for TestSyncProvider {
 	fn status(&self) -> SyncStatus {
 		self.status.read().clone()
 	}
+
+	fn peers(&self) -> Vec<PeerInfo> {
+		vec![
+			PeerInfo {
+				id: Some("node1".to_owned()),
+				client_version: "Parity/1".to_owned(),
+				capabilities: vec!["eth/62".to_owned(), "eth/63".to_owned()],
+				remote_address: "127.0.0.1:7777".to_owned(),
+				local_address: "127.0.0.1:8888".to_owned(),
+				ping_ms: Some(25),
+				packets_received: 100,
+				packets_sent: 50,
+				bytes_received: 12800,
+				bytes_sent: 6400,
+				eth_version: 62,
+				eth_difficulty: None,
+				eth_head: 50.into(),
+			},
+			PeerInfo {
+				id: None,
+				client_version: "Parity/2".to_owned(),
+				capabilities: vec!["eth/63".to_owned(), "eth/64".to_owned()],
+				remote_address: "Handshake".to_owned(),
+				local_address: "127.0.0.1:3333".to_owned(),
+				ping_ms: None,
+				packets_received: 0,
+				packets_sent: 0,
+				bytes_received: 0,
+				bytes_sent: 0,
+				eth_version: 64,
+				eth_difficulty: Some(40.into()),
+				eth_head: 60.into(),
+			},
+		]
+	}
 }
 
@@ -21,9 +21,9 @@ use std::time::{Instant, Duration};
 use jsonrpc_core::IoHandler;
 use util::{Uint, U256, Address, H256, FixedHash, Mutex};
 use ethcore::account_provider::AccountProvider;
-use ethcore::client::{TestBlockChainClient, EachBlockWith, Executed, TransactionID};
+use ethcore::client::{TestBlockChainClient, BlockChainClient, EachBlockWith, Executed, TransactionID, IpcMode};
 use ethcore::log_entry::{LocalizedLogEntry, LogEntry};
-use ethcore::receipt::LocalizedReceipt;
+use ethcore::receipt::{LocalizedReceipt, Receipt};
 use ethcore::transaction::{Transaction, Action};
 use ethcore::miner::{ExternalMiner, MinerService};
 use ethsync::SyncState;
@@ -76,7 +76,7 @@ impl EthTester {
 		let hashrates = Arc::new(Mutex::new(HashMap::new()));
 		let external_miner = Arc::new(ExternalMiner::new(hashrates.clone()));
 		let eth = EthClient::new(&client, &sync, &ap, &miner, &external_miner, options).to_delegate();
-		let filter = EthFilterClient::new(&client, &miner).to_delegate();
+		let filter = EthFilterClient::new(&client, &miner, Default::default()).to_delegate();
 		let sign = EthSigningUnsafeClient::new(&client, &ap, &miner).to_delegate();
 		let io = IoHandler::new();
 		io.add_delegate(eth);
@@ -342,6 +342,16 @@ fn rpc_eth_block_number() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_chain_id() {
+	let tester = EthTester::default();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_chainId", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x2","id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_eth_balance() {
 	let tester = EthTester::default();
@@ -378,6 +388,26 @@ fn rpc_eth_balance_pending() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_state_requests_in_relay_mode() {
+	let tester = EthTester::default();
+	tester.client.set_balance(Address::from(1), U256::from(5));
+	tester.client.set_mode(IpcMode::Relay);
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"This request is not supported because your node is running in relay mode and keeps no state.","data":null},"id":1}"#;
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["0x0000000000000000000000000000000000000001", "pending"], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["0x0000000000000000000000000000000000000001", "latest"], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_call", "params": [{"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567"}, "latest"], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_blockNumber", "params": [], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(r#"{"jsonrpc":"2.0","result":"0x0","id":1}"#.to_owned()));
+}
+
 #[test]
 fn rpc_eth_storage_at() {
 	let tester = EthTester::default();
@@ -467,6 +497,35 @@ fn rpc_eth_pending_transaction_by_hash() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_pending_raw_transaction_by_hash() {
+	use util::*;
+	use ethcore::transaction::*;
+
+	let tester = EthTester::default();
+	{
+		let tx: SignedTransaction = ::rlp::decode(&FromHex::from_hex("f85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804").unwrap());
+		tester.miner.pending_transactions.lock().insert(H256::zero(), tx);
+	}
+
+	let response = r#"{"jsonrpc":"2.0","result":"0xf85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804","id":1}"#;
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_getRawTransactionByHash",
+		"params": ["0x0000000000000000000000000000000000000000000000000000000000000000"],
+		"id": 1
+	}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_getRawTransactionByHash",
+		"params": ["0x0000000000000000000000000000000000000000000000000000000000000001"],
+		"id": 1
+	}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(r#"{"jsonrpc":"2.0","result":null,"id":1}"#.to_owned()));
+}
+
 
 #[test]
 fn rpc_eth_uncle_count_by_block_hash() {
@@ -810,6 +869,33 @@ fn rpc_eth_send_raw_transaction() {
 	assert_eq!(tester.io.handle_request_sync(&req), Some(res));
 }
 
+#[test]
+fn rpc_eth_send_raw_transaction_in_relay_mode() {
+	let tester = EthTester::default();
+	tester.client.set_mode(IpcMode::Relay);
+	let address = tester.accounts_provider.new_account("abcd").unwrap();
+	tester.accounts_provider.unlock_account_permanently(address, "abcd".into()).unwrap();
+
+	let t = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(0x9184e72a000u64),
+		gas: U256::from(0x76c0),
+		action: Action::Call(Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap()),
+		value: U256::from(0x9184e72au64),
+		data: vec![]
+	};
+	let signature = tester.accounts_provider.sign(address, t.hash()).unwrap();
+	let t = t.with_signature(signature);
+
+	let rlp = ::rlp::encode(&t).to_vec().to_hex();
+	let req = r#"{"jsonrpc": "2.0", "method": "eth_sendRawTransaction", "params": ["0x"#.to_owned() + &rlp + r#""], "id": 1}"#;
+	let res = r#"{"jsonrpc":"2.0","result":""#.to_owned() + &format!("0x{:?}", t.hash()) + r#"","id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(&req), Some(res));
+	// relayed transactions are not imported to the queue
+	assert!(tester.miner.imported_transactions.lock().is_empty());
+}
+
 #[test]
 fn rpc_eth_transaction_receipt() {
 	let receipt = LocalizedReceipt {
@@ -852,6 +938,35 @@ fn rpc_eth_transaction_receipt() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_block_receipts() {
+	let block_hash = H256::from_str("ed76641c68a1c641aee09a94b3b471f4dc0316efe5ac19cf488e2674cf8d05b5").unwrap();
+	let receipt = |index: usize, cumulative_gas_used: u64| LocalizedReceipt {
+		transaction_hash: H256::from(index as u64),
+		transaction_index: index,
+		block_hash: block_hash.clone(),
+		block_number: 0x4510c,
+		cumulative_gas_used: U256::from(cumulative_gas_used),
+		gas_used: U256::from(0x10),
+		contract_address: None,
+		logs: vec![],
+	};
+
+	let tester = EthTester::default();
+	tester.client.set_transaction_receipt(TransactionID::Hash(H256::from(2)), receipt(1, 0x20));
+	tester.client.set_transaction_receipt(TransactionID::Hash(H256::from(1)), receipt(0, 0x10));
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_getBlockReceipts",
+		"params": ["0xed76641c68a1c641aee09a94b3b471f4dc0316efe5ac19cf488e2674cf8d05b5"],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"blockHash":"0xed76641c68a1c641aee09a94b3b471f4dc0316efe5ac19cf488e2674cf8d05b5","blockNumber":"0x4510c","contractAddress":null,"cumulativeGasUsed":"0x10","gasUsed":"0x10","logs":[],"transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000000","transactionIndex":"0x0"},{"blockHash":"0xed76641c68a1c641aee09a94b3b471f4dc0316efe5ac19cf488e2674cf8d05b5","blockNumber":"0x4510c","contractAddress":null,"cumulativeGasUsed":"0x20","gasUsed":"0x10","logs":[],"transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000001","transactionIndex":"0x1"}],"id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_eth_transaction_receipt_null() {
 	let tester = EthTester::default();
@@ -867,6 +982,60 @@ fn rpc_eth_transaction_receipt_null() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_pending_queries_prepare_pending_block() {
+	let tester = EthTester::default();
+	let address = Address::from(1);
+	tester.client.set_balance(address.clone(), U256::from(5));
+	tester.miner.last_nonces.write().insert(address, U256::from(3));
+	let prepared = || *tester.miner.pending_block_requests.lock();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["0x0000000000000000000000000000000000000001", "latest"], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(r#"{"jsonrpc":"2.0","result":"0x5","id":1}"#.to_owned()));
+	assert_eq!(prepared(), 0);
+
+	// pending state comes from the miner, which doesn't know about the client's balance
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_getBalance", "params": ["0x0000000000000000000000000000000000000001", "pending"], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(r#"{"jsonrpc":"2.0","result":"0x0","id":1}"#.to_owned()));
+	assert_eq!(prepared(), 1);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_getTransactionCount", "params": ["0x0000000000000000000000000000000000000001", "pending"], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(r#"{"jsonrpc":"2.0","result":"0x3","id":1}"#.to_owned()));
+	assert_eq!(prepared(), 2);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_getBlockByNumber", "params": ["pending", false], "id": 1}"#;
+	tester.io.handle_request_sync(request);
+	assert_eq!(prepared(), 3);
+}
+
+#[test]
+fn rpc_eth_transaction_receipt_prepares_pending_block_only_for_unmined_transactions() {
+	let tester = EthTester::default();
+	let prepared = || *tester.miner.pending_block_requests.lock();
+	let mined = H256::from(1);
+	tester.client.set_transaction_receipt(TransactionID::Hash(mined), LocalizedReceipt {
+		transaction_hash: mined,
+		transaction_index: 0,
+		block_hash: H256::from(5),
+		block_number: 5,
+		cumulative_gas_used: U256::from(21_000),
+		gas_used: U256::from(21_000),
+		contract_address: None,
+		logs: vec![],
+	});
+	tester.miner.pending_receipts.lock().insert(H256::from(2), Receipt::new(H256::zero(), U256::from(21_000), vec![]));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_getTransactionReceipt", "params": ["0x0000000000000000000000000000000000000000000000000000000000000001"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"blockHash":"0x0000000000000000000000000000000000000000000000000000000000000005","blockNumber":"0x5","contractAddress":null,"cumulativeGasUsed":"0x5208","gasUsed":"0x5208","logs":[],"transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000001","transactionIndex":"0x0"},"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(prepared(), 0);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_getTransactionReceipt", "params": ["0x0000000000000000000000000000000000000000000000000000000000000002"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"blockHash":null,"blockNumber":null,"contractAddress":null,"cumulativeGasUsed":"0x5208","gasUsed":"0x5208","logs":[],"transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000000","transactionIndex":"0x0"},"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(prepared(), 1);
+}
+
 // These tests are incorrect: their output is undefined as long as eth_getCompilers is [].
 // Will ignore for now, but should probably be replaced by more substantial tests which check
 // the output of eth_getCompilers to determine whether to test. CI systems can then be preinstalled
@@ -934,6 +1103,7 @@ fn rpc_get_work_should_not_return_block_number() {
 	let eth_tester = EthTester::new_with_options(EthClientOptions {
 		allow_pending_receipt_query: true,
 		send_block_number_in_get_work: false,
+		heavy_queries: Default::default(),
 	});
 	eth_tester.miner.set_author(Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap());
 
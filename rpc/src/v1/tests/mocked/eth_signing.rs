@@ -20,12 +20,16 @@ use jsonrpc_core::{IoHandler, to_value};
 use v1::impls::EthSigningQueueClient;
 use v1::traits::EthSigning;
 use v1::helpers::{SignerService, SigningQueue};
-use v1::types::{H256 as RpcH256, H520 as RpcH520};
+use v1::types::{H256 as RpcH256, H520 as RpcH520, Bytes as RpcBytes};
 use v1::tests::helpers::TestMinerService;
 use util::{Address, FixedHash, Uint, U256, H256, H520};
 use ethcore::account_provider::AccountProvider;
 use ethcore::client::TestBlockChainClient;
 use ethcore::transaction::{Transaction, Action};
+use rustc_serialize::hex::ToHex;
+use ethkey::{Generator, Random};
+use ethstore::typed_data::{self, TypedDataField};
+use crypto::ecies;
 
 struct EthSigningTester {
 	pub signer: Arc<SignerService>,
@@ -87,6 +91,83 @@ fn should_add_sign_to_queue() {
 	}));
 }
 
+#[test]
+fn should_sign_typed_data_if_account_is_unlocked() {
+	// given
+	let tester = eth_signing();
+	let acc = tester.accounts.new_account("test").unwrap();
+	tester.accounts.unlock_account_permanently(acc, "test".into()).unwrap();
+
+	let hash = typed_data::hash(&[TypedDataField::new("string", "message", "Hi, Alice!"), TypedDataField::new("uint32", "value", "42")]).unwrap();
+	let signature = tester.accounts.sign(acc, hash).unwrap();
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_signTypedData",
+		"params": [
+			""#.to_owned() + format!("0x{:?}", acc).as_ref() + r#"",
+			[{"type": "string", "name": "message", "value": "Hi, Alice!"}, {"type": "uint32", "name": "value", "value": 42}]
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":""#.to_owned() + format!("0x{}", signature).as_ref() + r#"","id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+}
+
+#[test]
+fn should_add_sign_typed_data_to_queue() {
+	// given
+	let tester = eth_signing();
+	let address = Address::random();
+	assert_eq!(tester.signer.requests().len(), 0);
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_signTypedData",
+		"params": [
+			""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"",
+			[{"type": "bool", "name": "accepted", "value": true}]
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","id":1}"#;
+
+	// then
+	let async_result = tester.io.handle_request(&request).unwrap();
+	assert_eq!(tester.signer.requests().len(), 1);
+	// respond
+	tester.signer.request_confirmed(U256::from(1), Ok(to_value(&RpcH520::from(H520::default()))));
+	assert!(async_result.on_result(move |res| {
+		assert_eq!(res, response.to_owned());
+	}));
+}
+
+#[test]
+fn should_reject_invalid_typed_data() {
+	// given
+	let tester = eth_signing();
+	let address = Address::random();
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_signTypedData",
+		"params": [
+			""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"",
+			[{"type": "uint8", "name": "value", "value": 256}]
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: Typed data","data":"\"Invalid typed data: uint8 value: number out of range\""},"id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+}
+
 #[test]
 fn should_post_sign_to_queue() {
 	// given
@@ -263,3 +344,96 @@ fn should_dispatch_transaction_if_account_is_unlock() {
 	// then
 	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
 }
+
+#[test]
+fn should_sign_transaction_without_dispatching_it() {
+	// given
+	let tester = eth_signing();
+	let acc = tester.accounts.new_account("test").unwrap();
+	tester.accounts.unlock_account_permanently(acc, "test".into()).unwrap();
+
+	let t = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(0x9184e72a000u64),
+		gas: U256::from(0x76c0),
+		action: Action::Call(Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap()),
+		value: U256::from(0x9184e72au64),
+		data: vec![]
+	};
+	let signature = tester.accounts.sign(acc, t.hash()).unwrap();
+	let t = t.with_signature(signature);
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_signTransaction",
+		"params": [{
+			"from": ""#.to_owned() + format!("0x{:?}", acc).as_ref() + r#"",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0",
+			"gasPrice": "0x9184e72a000",
+			"value": "0x9184e72a"
+		}],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x"#.to_owned() + &::rlp::encode(&t).to_vec().to_hex() + r#"","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+}
+
+#[test]
+fn should_add_decrypt_to_queue() {
+	// given
+	let tester = eth_signing();
+	let address = Address::random();
+	assert_eq!(tester.signer.requests().len(), 0);
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_decryptMessage",
+		"params": [
+			""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"",
+			"0x012345"
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x0102","id":1}"#;
+
+	// then
+	let async_result = tester.io.handle_request(&request).unwrap();
+	assert_eq!(tester.signer.requests().len(), 1);
+	// respond
+	tester.signer.request_confirmed(U256::from(1), Ok(to_value(&RpcBytes::from(vec![1, 2]))));
+	assert!(async_result.on_result(move |res| {
+		assert_eq!(res, response.to_owned());
+	}));
+}
+
+#[test]
+fn should_decrypt_if_account_is_unlocked() {
+	// given
+	let tester = eth_signing();
+	let kp = Random.generate().unwrap();
+	tester.accounts.insert_account(kp.secret().clone(), "test").unwrap();
+	tester.accounts.unlock_account_permanently(kp.address(), "test".into()).unwrap();
+	let ciphertext = ecies::encrypt(kp.public(), &[0; 0], &[1, 2, 3]).unwrap();
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "parity_decryptMessage",
+		"params": [
+			""#.to_owned() + format!("0x{:?}", kp.address()).as_ref() + r#"",
+			"0x"# + &ciphertext.to_hex() + r#""
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x010203","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+}
@@ -18,11 +18,13 @@ use std::sync::Arc;
 use util::log::RotatingLogger;
 use util::U256;
 use ethsync::ManageNetwork;
-use ethcore::client::{TestBlockChainClient};
+use ethkey::{Generator, Random};
+use ethcore::client::{TestBlockChainClient, BlockChainClient, ChainNotify, EachBlockWith};
+use ethcore::miner::MinerService;
 
 use jsonrpc_core::IoHandler;
 use v1::{Ethcore, EthcoreClient};
-use v1::helpers::{SignerService, NetworkSettings};
+use v1::helpers::{SignerService, NetworkSettings, StorageWatcher};
 use v1::tests::helpers::{TestSyncProvider, Config, TestMinerService, TestFetch};
 use super::manage_network::TestManageNetwork;
 
@@ -60,6 +62,10 @@ fn network_service() -> Arc<ManageNetwork> {
 	Arc::new(TestManageNetwork)
 }
 
+fn storage_watcher(client: &Arc<TestBlockChainClient>) -> Arc<StorageWatcher> {
+	Arc::new(StorageWatcher::new(client))
+}
+
 type TestEthcoreClient = EthcoreClient<TestBlockChainClient, TestMinerService, TestSyncProvider, TestFetch>;
 
 fn ethcore_client(
@@ -68,7 +74,7 @@ fn ethcore_client(
 	sync: &Arc<TestSyncProvider>,
 	net: &Arc<ManageNetwork>)
 	-> TestEthcoreClient {
-	EthcoreClient::with_fetch(client, miner, sync, net, logger(), settings(), None)
+	EthcoreClient::with_fetch(client, miner, sync, net, logger(), settings(), None, storage_watcher(client), None, Default::default())
 }
 
 #[test]
@@ -119,6 +125,22 @@ fn rpc_ethcore_gas_floor_target() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_author() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+	miner.set_author(10.into());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_author", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x000000000000000000000000000000000000000a","id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_ethcore_min_gas_price() {
 	let miner = miner_service();
@@ -143,7 +165,7 @@ fn rpc_ethcore_dev_logs() {
 	let logger = logger();
 	logger.append("a".to_owned());
 	logger.append("b".to_owned());
-	let ethcore: TestEthcoreClient = EthcoreClient::with_fetch(&client, &miner, &sync, &net, logger.clone(), settings(), None);
+	let ethcore: TestEthcoreClient = EthcoreClient::with_fetch(&client, &miner, &sync, &net, logger.clone(), settings(), None, storage_watcher(&client), None, Default::default());
 	let io = IoHandler::new();
 	io.add_delegate(ethcore.to_delegate());
 
@@ -208,7 +230,26 @@ fn rpc_ethcore_net_peers() {
 	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_netPeers", "params":[], "id": 1}"#;
-	let response = r#"{"jsonrpc":"2.0","result":{"active":0,"connected":120,"max":50},"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"active":0,"connected":120,"max":50,"peers":[{"id":"node1","name":"Parity/1","caps":["eth/62","eth/63"],"network":{"remoteAddress":"127.0.0.1:7777","localAddress":"127.0.0.1:8888","latency":25,"ingress":100,"egress":50,"ingressBytes":12800,"egressBytes":6400},"protocols":{"eth":{"version":62,"difficulty":null,"head":"0x0000000000000000000000000000000000000000000000000000000000000032"}}},{"id":null,"name":"Parity/2","caps":["eth/63","eth/64"],"network":{"remoteAddress":"Handshake","localAddress":"127.0.0.1:3333","latency":null,"ingress":0,"egress":0,"ingressBytes":0,"egressBytes":0},"protocols":{"eth":{"version":64,"difficulty":"0x28","head":"0x000000000000000000000000000000000000000000000000000000000000003c"}}}]},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_netPeers", "params":[], "id": 1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_net_stats() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_netStats", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"ingressBytes":4096,"egressBytes":1024,"ingressRate":512,"egressRate":128,"protocols":{"eth":{"ingressBytes":3072,"egressBytes":768}}},"id":1}"#;
 
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
@@ -266,7 +307,7 @@ fn rpc_ethcore_unsigned_transactions_count() {
 	let net = network_service();
 	let io = IoHandler::new();
 	let signer = Arc::new(SignerService::new_test());
-	let ethcore: TestEthcoreClient = EthcoreClient::with_fetch(&client, &miner, &sync, &net, logger(), settings(), Some(signer));
+	let ethcore: TestEthcoreClient = EthcoreClient::with_fetch(&client, &miner, &sync, &net, logger(), settings(), Some(signer), storage_watcher(&client), None, Default::default());
 	io.add_delegate(ethcore.to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_unsignedTransactionsCount", "params":[], "id": 1}"#;
@@ -319,3 +360,418 @@ fn rpc_ethcore_pending_transactions() {
 
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
+
+#[test]
+fn rpc_ethcore_pending_transactions_with_position_and_future_transactions() {
+	use rustc_serialize::hex::FromHex;
+	use ethcore::transaction::SignedTransaction;
+
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let tx: SignedTransaction = ::rlp::decode(&FromHex::from_hex("f85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804").unwrap());
+	let queued = r#"[{"position":0,"transaction":{"blockHash":null,"blockNumber":null,"creates":null,"from":"0x0f65fe9276bc9a24ae7083ae28e2660ef72df99e","gas":"0x5208","gasPrice":"0x1","hash":"0x41df922fd0d4766fcc02e161f8295ec28522f329ae487f14d811e4b64c8d6e31","input":"0x","nonce":"0x0","publicKey":"0x7ae46da747962c2ee46825839c1ef9298e3bd2e70ca2938495c3693a485ec3eaa8f196327881090ff64cf4fbb0a48485d4f83098e189ed3b7a87d5941b59f789","raw":"0xf85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804","to":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","transactionIndex":null,"value":"0xa"}}]"#;
+	let response = |result: &str| format!(r#"{{"jsonrpc":"2.0","result":{},"id":1}}"#, result);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_pendingTransactionsWithPosition", "params":[], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response("[]")));
+
+	miner.pending_transactions.lock().insert(tx.hash(), tx.clone());
+	assert_eq!(io.handle_request_sync(request), Some(response(queued)));
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_pendingTransactionsWithPosition", "params":[], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response(queued)));
+
+	miner.pending_transactions.lock().clear();
+	miner.future_transactions.lock().push(tx);
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_futureTransactions", "params":[], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response(queued)));
+}
+
+#[test]
+fn rpc_ethcore_health() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_health", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"peers":0,"syncing":false,"txQueueThrottled":false},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_registry_address() {
+	let miner = miner_service();
+	let client = client_service();
+	client.registry.write().insert("githubhint".into(), "0000000000000000000000000000000000000005".parse().unwrap());
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_registryAddress", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_registryAddress", "params":["githubhint"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x0000000000000000000000000000000000000005","id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_registryAddress", "params":["unknown"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_mode() {
+	use ethcore::client::IpcMode;
+
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_mode", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"active","id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	*client.mode.write() = IpcMode::Off;
+	let response = r#"{"jsonrpc":"2.0","result":"offline","id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_upgrade_ready() {
+	use updater::{Service, Upgrade, Release};
+
+	struct TestUpdater;
+	impl Service for TestUpdater {
+		fn upgrade_ready(&self) -> Option<Upgrade> {
+			Some(Upgrade {
+				release: Release {
+					version: "1.4.1".parse().unwrap(),
+					critical: true,
+					platform: "linux-x86_64".into(),
+					binary_url: "https://example.com/parity".into(),
+					binary_hash: 5.into(),
+					signature: Default::default(),
+				},
+				installed: false,
+			})
+		}
+	}
+
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_upgradeReady", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let io = IoHandler::new();
+	let updater: Arc<Service> = Arc::new(TestUpdater);
+	let ethcore: TestEthcoreClient = EthcoreClient::with_fetch(&client, &miner, &sync, &net, logger(), settings(), None, storage_watcher(&client), Some(updater), Default::default());
+	io.add_delegate(ethcore.to_delegate());
+	let response = r#"{"jsonrpc":"2.0","result":{"version":"1.4.1","critical":true,"binary":"0x0000000000000000000000000000000000000000000000000000000000000005","installed":false},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_chain_status() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_chainStatus", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"blockGap":null},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_extra_data_histogram() {
+	let miner = miner_service();
+	let client = client_service();
+	client.add_blocks(5, EachBlockWith::Nothing);
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_extraDataHistogram", "params":[3], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"":3},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_block_headers() {
+	use serde_json::{self, Value};
+
+	let miner = miner_service();
+	let client = client_service();
+	client.add_blocks(5, EachBlockWith::Nothing);
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let numbers = |request: &str| -> Vec<String> {
+		let response: Value = serde_json::from_str(&io.handle_request_sync(request).unwrap()).unwrap();
+		response.find("result").unwrap().as_array().unwrap().iter()
+			.map(|header| header.find("number").unwrap().as_str().unwrap().to_owned())
+			.collect()
+	};
+
+	assert_eq!(numbers(r#"{"jsonrpc": "2.0", "method": "parity_blockHeaders", "params":["0x1", "latest", 2], "id": 1}"#), vec!["0x1", "0x2"]);
+	assert_eq!(numbers(r#"{"jsonrpc": "2.0", "method": "parity_blockHeaders", "params":["0x3", "0x10"], "id": 1}"#), vec!["0x3", "0x4", "0x5"]);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_blockHeaders", "params":["0x3", "0x1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: to","data":"\"Range end is lower than its start.\""},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_balance_history() {
+	let miner = miner_service();
+	let client = client_service();
+	client.add_blocks(5, EachBlockWith::Nothing);
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_balanceHistory", "params":["0x0000000000000000000000000000000000000001", "0x1", "latest", 2], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"This request is not supported because your node is running with state pruning. Run with --pruning=archive.","data":null},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_balanceHistory", "params":["0x0000000000000000000000000000000000000001", "0x0", "0x100000"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32073,"message":"Query too heavy, narrow the range of blocks.","data":"ethcore_balanceHistory"},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_block_header_and_body() {
+	use ethcore::client::BlockID;
+	use rustc_serialize::hex::ToHex;
+
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_blockHeader", "params":["0x0"], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":"0x{}","id":1}}"#, client.block_header(BlockID::Number(0)).unwrap().to_hex());
+	assert_eq!(io.handle_request_sync(request), Some(response));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_blockBody", "params":["latest"], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":"0x{}","id":1}}"#, client.block_body(BlockID::Latest).unwrap().to_hex());
+	assert_eq!(io.handle_request_sync(request), Some(response));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_blockHeader", "params":["0x64"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_state_diff() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_stateDiff", "params":["0x0", "latest", ["0x0000000000000000000000000000000000000001"]], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	// the test client runs without fat DB, so accounts can't be paged
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_stateDiff", "params":["0x0", "latest", {"count": 10}], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"This request is not supported because your node is running without fat DB. Run with --fat-db on.","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	// accounts have to be listed or paged
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_stateDiff", "params":["0x0", "latest"], "id": 1}"#;
+	assert!(io.handle_request_sync(request).unwrap().contains("error"));
+}
+
+#[test]
+fn rpc_ethcore_list_accounts() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	// the test client runs without fat DB
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_listAccounts", "params":[10], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_listAccounts", "params":[10, "0x0000000000000000000000000000000000000001"], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_chain_params() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_chainParams", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"chainId":"0x2","networkId":"0x2","engine":"NullEngine","forks":{},"engineParams":{}},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_chainParams", "params":[], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_chain_spec() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_chainSpec", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"params":{"chainId":"0x2","networkId":"0x2","engine":"NullEngine","forks":{},"engineParams":{}},"builtins":{"#.to_owned()
+		+ r#""0x0000000000000000000000000000000000000001":{"name":"ecrecover","activateAt":"0x0"},"#
+		+ r#""0x0000000000000000000000000000000000000002":{"name":"sha256","activateAt":"0x0"},"#
+		+ r#""0x0000000000000000000000000000000000000003":{"name":"ripemd160","activateAt":"0x0"},"#
+		+ r#""0x0000000000000000000000000000000000000004":{"name":"identity","activateAt":"0x0"}},"#
+		+ &format!(r#""genesisHash":"0x{}"}},"id":1}}"#, client.chain_info().genesis_hash.hex());
+
+	assert_eq!(io.handle_request_sync(request), Some(response));
+}
+
+#[test]
+fn rpc_parity_cht_root() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+	client.cht_roots.write().insert(1, 5.into());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_chtRoot", "params":["0x1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x0000000000000000000000000000000000000000000000000000000000000005","id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_chtRoot", "params":["0x2"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_bad_blocks() {
+	use ethcore::bad_block::BadBlock;
+
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+	client.bad_blocks.write().push(BadBlock {
+		hash: 5.into(),
+		rlp: vec![0xc0],
+		reason: "Stage 4 verification failed".into(),
+		timestamp: 1480000000,
+	});
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_badBlocks", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"hash":"0x0000000000000000000000000000000000000000000000000000000000000005","rlp":"0xc0","reason":"Stage 4 verification failed","timestamp":1480000000}],"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_storage_watch() {
+	use util::{Address, H256};
+
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let watcher = storage_watcher(&client);
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreClient::with_fetch(&client, &miner, &sync, &net, logger(), settings(), None, watcher.clone(), None, Default::default()).to_delegate());
+
+	let address = Address::from(1);
+	let key = H256::from(2);
+	client.set_storage(address, key, H256::from(3));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_newStorageWatch", "params": [[{"address":"0x0000000000000000000000000000000000000001","key":"0x0000000000000000000000000000000000000000000000000000000000000002"}]], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x0","id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	// no change yet
+	client.add_blocks(1, EachBlockWith::Nothing);
+	let best = client.chain_info().best_block_hash;
+	watcher.new_blocks(vec![best.clone()], vec![], vec![best.clone()], vec![], vec![], 0);
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getStorageWatchChanges", "params": ["0x0"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[],"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	client.set_storage(address, key, H256::from(4));
+	client.add_blocks(1, EachBlockWith::Nothing);
+	let best = client.chain_info().best_block_hash;
+	watcher.new_blocks(vec![best.clone()], vec![], vec![best.clone()], vec![], vec![], 0);
+	let response = format!(r#"{{"jsonrpc":"2.0","result":[{{"blockNumber":"0x2","blockHash":"0x{:?}","address":"0x0000000000000000000000000000000000000001","key":"0x0000000000000000000000000000000000000000000000000000000000000002","oldValue":"0x0000000000000000000000000000000000000000000000000000000000000003","newValue":"0x0000000000000000000000000000000000000000000000000000000000000004"}}],"id":1}}"#, best);
+	assert_eq!(io.handle_request_sync(request), Some(response));
+
+	// changes are drained
+	let response = r#"{"jsonrpc":"2.0","result":[],"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_uninstallStorageWatch", "params": ["0x0"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_encrypt_message() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let public = Random.generate().unwrap().public().clone();
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_encryptMessage", "params":[""#.to_owned() + format!("0x{:?}", public).as_ref() + r#"", "0x010203"], "id": 1}"#;
+	let response = io.handle_request_sync(&request).unwrap();
+
+	// ephemeral public key, iv, ciphertext and mac
+	let expected_len = (1 + 64 + 16 + 3 + 32) * 2;
+	assert!(response.starts_with(r#"{"jsonrpc":"2.0","result":"0x04"#));
+	assert_eq!(response.len(), r#"{"jsonrpc":"2.0","result":"0x","id":1}"#.len() + expected_len);
+}
@@ -21,7 +21,7 @@ use v1::{EthcoreSet, EthcoreSetClient};
 use ethcore::miner::MinerService;
 use ethcore::client::TestBlockChainClient;
 use v1::tests::helpers::TestMinerService;
-use util::{U256, Address};
+use util::{U256, Address, RotatingLogger};
 use rustc_serialize::hex::FromHex;
 use super::manage_network::TestManageNetwork;
 use ethsync::ManageNetwork;
@@ -38,8 +38,17 @@ fn network_service() -> Arc<TestManageNetwork> {
 	Arc::new(TestManageNetwork)
 }
 
+fn logger() -> Arc<RotatingLogger> {
+	let logger = RotatingLogger::new("rpc=trace".to_owned());
+	logger.set_levels_handler(Box::new(|levels| match levels.contains('=') {
+		true => Ok(()),
+		false => Err(format!("Invalid directive: {}", levels)),
+	}));
+	Arc::new(logger)
+}
+
 fn ethcore_set_client(client: &Arc<TestBlockChainClient>, miner: &Arc<TestMinerService>, net: &Arc<TestManageNetwork>) -> EthcoreSetClient<TestBlockChainClient, TestMinerService> {
-	EthcoreSetClient::new(client, miner, &(net.clone() as Arc<ManageNetwork>))
+	EthcoreSetClient::new(client, miner, &(net.clone() as Arc<ManageNetwork>), logger(), 300, 3600)
 }
 
 #[test]
@@ -87,6 +96,48 @@ fn rpc_ethcore_set_extra_data() {
 	assert_eq!(miner.extra_data(), "cd1722f3947def4cf144679da39c4c32bdc35681".from_hex().unwrap());
 }
 
+#[test]
+fn rpc_ethcore_set_extra_data_over_limit() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setExtraData", "params":["0x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: Extra data","data":"\"Extra data is 33 bytes long, the maximum for this chain is 32 bytes.\""},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(miner.extra_data(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn rpc_parity_set_miner_settings() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setMinGasPrice", "params":["0x4a817c800"], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setGasFloorTarget", "params":["0x47b760"], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setExtraData", "params":["0x706172697479"], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setAuthor", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681"], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setTransactionsLimit", "params":[1024], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	assert_eq!(miner.minimal_gas_price(), U256::from(20_000_000_000u64));
+	assert_eq!(miner.gas_floor_target(), U256::from(4_700_000));
+	assert_eq!(miner.extra_data(), b"parity".to_vec());
+	assert_eq!(miner.author(), Address::from_str("cd1722f3947def4cf144679da39c4c32bdc35681").unwrap());
+	assert_eq!(miner.transactions_limit(), 1024);
+}
+
 #[test]
 fn rpc_ethcore_set_author() {
 	let miner = miner_service();
@@ -115,4 +166,132 @@ fn rpc_ethcore_set_transactions_limit() {
 
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 	assert_eq!(miner.transactions_limit(), 10_240_240);
-}
\ No newline at end of file
+}
+#[test]
+fn rpc_parity_reserved_peers_management() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	let enode = "enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770";
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_addReservedPeer", "params":["{}"], "id": 1}}"#, enode);
+	assert_eq!(io.handle_request_sync(&request), Some(response.to_owned()));
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_removeReservedPeer", "params":["{}"], "id": 1}}"#, enode);
+	assert_eq!(io.handle_request_sync(&request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_dropNonReservedPeers", "params":[], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_acceptNonReservedPeers", "params":[], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_set_logger_level() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let logger = logger();
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreSetClient::new(&client, &miner, &(network as Arc<ManageNetwork>), logger.clone(), 300, 3600).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setLoggerLevel", "params":["sync=trace"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(logger.levels(), "rpc=trace,sync=trace");
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setLoggerLevel", "params":["trace"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: Logger levels","data":"\"Invalid directive: trace\""},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(logger.levels(), "rpc=trace,sync=trace");
+}
+
+#[test]
+fn rpc_parity_set_mode() {
+	use ethcore::client::IpcMode;
+
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setMode", "params":["dark"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(*client.mode.read(), IpcMode::Dark(300));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setMode", "params":["sleepy"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Couldn't parse parameters: mode","data":"\"Unknown mode: sleepy\""},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(*client.mode.read(), IpcMode::Dark(300));
+}
+
+#[test]
+fn rpc_parity_set_mode_uses_configured_timeouts() {
+	use ethcore::client::IpcMode;
+
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreSetClient::new(&client, &miner, &(network as Arc<ManageNetwork>), logger(), 60, 600).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setMode", "params":["passive"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(*client.mode.read(), IpcMode::Passive(60, 600));
+
+	*client.mode.write() = IpcMode::Relay;
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setMode", "params":["active"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"This request is not supported because your node is running in relay mode and keeps no state.","data":null},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(*client.mode.read(), IpcMode::Relay);
+}
+
+#[test]
+fn rpc_parity_force_reorg() {
+	use util::H256;
+
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+
+	client.refused_reorgs.write().insert(H256::from(1));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_forceReorg", "params":["0x0000000000000000000000000000000000000000000000000000000000000001"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32043,"message":"No block with this hash was refused.","data":null},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_parity_remove_transaction() {
+	use ethcore::transaction::SignedTransaction;
+
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+
+	let tx: SignedTransaction = ::rlp::decode(&FromHex::from_hex("f85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804").unwrap());
+	miner.pending_transactions.lock().insert(tx.hash(), tx);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_removeTransaction", "params":["0x41df922fd0d4766fcc02e161f8295ec28522f329ae487f14d811e4b64c8d6e31"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"blockHash":null,"blockNumber":null,"creates":null,"from":"0x0f65fe9276bc9a24ae7083ae28e2660ef72df99e","gas":"0x5208","gasPrice":"0x1","hash":"0x41df922fd0d4766fcc02e161f8295ec28522f329ae487f14d811e4b64c8d6e31","input":"0x","nonce":"0x0","publicKey":"0x7ae46da747962c2ee46825839c1ef9298e3bd2e70ca2938495c3693a485ec3eaa8f196327881090ff64cf4fbb0a48485d4f83098e189ed3b7a87d5941b59f789","raw":"0xf85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804","to":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","transactionIndex":null,"value":"0xa"},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert!(miner.pending_transactions.lock().is_empty());
+
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethsync::{ManageNetwork, NetworkConfiguration};
+use ethsync::{ManageNetwork, NetworkConfiguration, NetworkTraffic, ProtocolTrafficInfo};
 
 pub struct TestManageNetwork;
 
@@ -27,4 +27,13 @@ impl ManageNetwork for TestManageNetwork {
 	fn start_network(&self) {}
 	fn stop_network(&self) {}
 	fn network_config(&self) -> NetworkConfiguration { NetworkConfiguration::new_local() }
+	fn network_traffic(&self) -> NetworkTraffic {
+		NetworkTraffic {
+			bytes_received: 4096,
+			bytes_sent: 1024,
+			receive_rate: 512,
+			send_rate: 128,
+			protocols: vec![ProtocolTrafficInfo { protocol: "eth".into(), bytes_received: 3072, bytes_sent: 768 }],
+		}
+	}
 }
@@ -16,18 +16,26 @@
 
 use std::sync::Arc;
 use std::str::FromStr;
-use jsonrpc_core::IoHandler;
-use util::{U256, Uint, Address};
+use jsonrpc_core::{IoHandler, to_value};
+use util::{U256, Uint, Address, H520, Hashable};
 use ethcore::account_provider::AccountProvider;
 use v1::{PersonalClient, Personal};
+use v1::helpers::{SignerService, SigningQueue};
+use v1::types::H520 as RpcH520;
 use v1::tests::helpers::TestMinerService;
 use ethcore::client::TestBlockChainClient;
 use ethcore::transaction::{Action, Transaction};
+use ethstore::EthStore;
+use ethstore::ethkey::Derivation;
+use ethstore::dir::DiskDirectory;
+use devtools::RandomTempPath;
+use rustc_serialize::hex::ToHex;
 
 struct PersonalTester {
 	accounts: Arc<AccountProvider>,
 	io: IoHandler,
 	miner: Arc<TestMinerService>,
+	signer: Arc<SignerService>,
 	// these unused fields are necessary to keep the data alive
 	// as the handler has only weak pointers.
 	_client: Arc<TestBlockChainClient>,
@@ -47,10 +55,14 @@ fn miner_service() -> Arc<TestMinerService> {
 }
 
 fn setup(signer: Option<u16>) -> PersonalTester {
-	let accounts = accounts_provider();
+	setup_with_accounts(accounts_provider(), signer)
+}
+
+fn setup_with_accounts(accounts: Arc<AccountProvider>, signer: Option<u16>) -> PersonalTester {
 	let client = blockchain_client();
 	let miner = miner_service();
-	let personal = PersonalClient::new(&accounts, &client, &miner, signer, false);
+	let signer_service = Arc::new(SignerService::new_test());
+	let personal = PersonalClient::new(&accounts, &client, &miner, signer, signer.map(|_| &signer_service), false);
 
 	let io = IoHandler::new();
 	io.add_delegate(personal.to_delegate());
@@ -59,6 +71,7 @@ fn setup(signer: Option<u16>) -> PersonalTester {
 		accounts: accounts,
 		io: io,
 		miner: miner,
+		signer: signer_service,
 		_client: client,
 	};
 
@@ -93,6 +106,73 @@ fn should_return_port_number_if_signer_is_enabled() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn should_sign_prefixed_message_and_recover_signer() {
+	// given
+	let tester = setup(None);
+	let acc = tester.accounts.new_account("test").unwrap();
+	tester.accounts.unlock_account_permanently(acc, "test".into()).unwrap();
+
+	let mut message = b"\x19Ethereum Signed Message:\n5".to_vec();
+	message.extend_from_slice(b"Hello");
+	let signature = tester.accounts.sign(acc, message.sha3()).unwrap();
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "personal_sign",
+		"params": [
+			"0x48656c6c6f",
+			""#.to_owned() + format!("0x{:?}", acc).as_ref() + r#""
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":""#.to_owned() + format!("0x{}", signature).as_ref() + r#"","id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+
+	// then
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "personal_ecRecover",
+		"params": [
+			"0x48656c6c6f",
+			""#.to_owned() + format!("0x{}", signature).as_ref() + r#""
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":""#.to_owned() + format!("0x{:?}", acc).as_ref() + r#"","id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+}
+
+#[test]
+fn should_add_prefixed_message_to_queue() {
+	// given
+	let tester = setup(Some(8180));
+	let address = Address::random();
+	assert_eq!(tester.signer.requests().len(), 0);
+
+	// when
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "personal_sign",
+		"params": [
+			"0x48656c6c6f",
+			""#.to_owned() + format!("0x{:?}", address).as_ref() + r#""
+		],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","id":1}"#;
+
+	// then
+	let async_result = tester.io.handle_request(&request).unwrap();
+	assert_eq!(tester.signer.requests().len(), 1);
+	// respond
+	tester.signer.request_confirmed(U256::from(1), Ok(to_value(&RpcH520::from(H520::default()))));
+	assert!(async_result.on_result(move |res| {
+		assert_eq!(res, response.to_owned());
+	}));
+}
+
 #[test]
 fn accounts() {
 	let tester = setup(None);
@@ -252,3 +332,109 @@ fn sign_and_send_transaction() {
 
 	assert_eq!(tester.io.handle_request_sync(request.as_ref()), Some(response));
 }
+
+#[test]
+fn sign_transaction() {
+	let tester = setup(None);
+	let address = tester.accounts.new_account("password123").unwrap();
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "personal_signTransaction",
+		"params": [{
+			"from": ""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0",
+			"gasPrice": "0x9184e72a000",
+			"value": "0x9184e72a"
+		}, "password123"],
+		"id": 1
+	}"#;
+
+	let t = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(0x9184e72a000u64),
+		gas: U256::from(0x76c0),
+		action: Action::Call(Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap()),
+		value: U256::from(0x9184e72au64),
+		data: vec![]
+	};
+	tester.accounts.unlock_account_temporarily(address, "password123".into()).unwrap();
+	let signature = tester.accounts.sign(address, t.hash()).unwrap();
+	let t = t.with_signature(signature);
+
+	let response = r#"{"jsonrpc":"2.0","result":"0x"#.to_owned() + &::rlp::encode(&t).to_vec().to_hex() + r#"","id":1}"#;
+
+	assert_eq!(tester.io.handle_request_sync(request.as_ref()), Some(response));
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+}
+
+#[test]
+fn should_create_open_and_close_vaults() {
+	// given
+	let temp = RandomTempPath::create_dir();
+	let store = EthStore::open(Box::new(DiskDirectory::create(temp.as_path()).unwrap())).unwrap();
+	let tester = setup_with_accounts(Arc::new(AccountProvider::new(Box::new(store))), None);
+	let address = tester.accounts.new_account("password").unwrap();
+
+	// when
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_newVault", "params": ["cold", "vault_password"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_changeVault", "params": ["0x{}", "password", "cold"], "id": 1}}"#, address.hex());
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_setVaultMeta", "params": ["cold", "{\"description\":\"savings\"}"], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_closeVault", "params": ["cold"], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	// then
+	assert_eq!(tester.accounts.accounts().unwrap().len(), 0);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_listVaults", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":["cold"],"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_listOpenedVaults", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[],"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_getVaultMeta", "params": ["cold"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"{\"description\":\"savings\"}","id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "parity_openVault", "params": ["cold", "vault_password"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(tester.accounts.accounts().unwrap(), vec![address]);
+}
+
+#[test]
+fn should_derive_addresses() {
+	// given
+	let tester = setup(None);
+	let address = tester.accounts.new_account("password").unwrap();
+	let path = [Derivation::Hard(0), Derivation::Soft(1)];
+	let expected = tester.accounts.derive_account(&address, "password".into(), &path, false).unwrap();
+
+	// when
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_deriveAddressIndex", "params": ["0x{}", "password", [{{"index": 0, "type": "hard"}}, {{"index": 1, "type": "soft"}}], false], "id": 1}}"#, address.hex());
+	let response = format!(r#"{{"jsonrpc":"2.0","result":"0x{}","id":1}}"#, expected.hex());
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response));
+	assert_eq!(tester.accounts.accounts().unwrap(), vec![address]);
+
+	// when
+	let hash = Derivation::HardHash(5.into());
+	let expected = tester.accounts.derive_account(&address, "password".into(), &[hash], false).unwrap();
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "parity_deriveAddressHash", "params": ["0x{}", "password", {{"hash": "0x0000000000000000000000000000000000000000000000000000000000000005", "type": "hard"}}, true], "id": 1}}"#, address.hex());
+	let response = format!(r#"{{"jsonrpc":"2.0","result":"0x{}","id":1}}"#, expected.hex());
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response));
+	assert_eq!(tester.accounts.accounts().unwrap().len(), 2);
+}
@@ -24,6 +24,10 @@ use ethcore::transaction::{Transaction, Action};
 use v1::{SignerClient, PersonalSigner};
 use v1::tests::helpers::TestMinerService;
 use v1::helpers::{SigningQueue, SignerService, FilledTransactionRequest, ConfirmationPayload};
+use v1::helpers::dispatch::eth_data_hash;
+use ethkey::{Generator, Random};
+use ethstore::typed_data::{self, TypedDataField};
+use crypto::ecies;
 
 struct PersonalSignerTester {
 	signer: Arc<SignerService>,
@@ -121,6 +125,31 @@ fn should_reject_transaction_from_queue_without_dispatching() {
 	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
 }
 
+#[test]
+fn should_expose_signer_namespace() {
+	// given
+	let tester = signer_tester();
+	tester.signer.add_request(ConfirmationPayload::Transaction(FilledTransactionRequest {
+		from: Address::from(1),
+		to: Some(Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap()),
+		gas_price: U256::from(10_000),
+		gas: U256::from(10_000_000),
+		value: U256::from(1),
+		data: vec![],
+		nonce: None,
+	})).unwrap();
+	assert_eq!(tester.signer.requests().len(), 1);
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"signer_rejectRequest","params":["0x1"],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+}
+
 #[test]
 fn should_not_remove_transaction_if_password_is_invalid() {
 	// given
@@ -161,6 +190,62 @@ fn should_not_remove_sign_if_password_is_invalid() {
 	assert_eq!(tester.signer.requests().len(), 1);
 }
 
+#[test]
+fn should_confirm_decrypt_with_password() {
+	// given
+	let tester = signer_tester();
+	let kp = Random.generate().unwrap();
+	tester.accounts.insert_account(kp.secret().clone(), "test").unwrap();
+	let ciphertext = ecies::encrypt(kp.public(), &[0; 0], &[1, 2, 3]).unwrap();
+	tester.signer.add_request(ConfirmationPayload::Decrypt(kp.address(), ciphertext)).unwrap();
+	assert_eq!(tester.signer.requests().len(), 1);
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"personal_confirmRequest","params":["0x1",{},"test"],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x010203","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+}
+
+#[test]
+fn should_confirm_sign_message_with_password() {
+	// given
+	let tester = signer_tester();
+	let address = tester.accounts.new_account("test").unwrap();
+	let signature = tester.accounts.sign_with_password(address, "test".into(), eth_data_hash(b"Hello".to_vec())).unwrap();
+	tester.signer.add_request(ConfirmationPayload::SignMessage(address, b"Hello".to_vec())).unwrap();
+	assert_eq!(tester.signer.requests().len(), 1);
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"personal_confirmRequest","params":["0x1",{},"test"],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":""#.to_owned() + format!("0x{}", signature).as_ref() + r#"","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+}
+
+#[test]
+fn should_confirm_sign_typed_data_with_password() {
+	// given
+	let tester = signer_tester();
+	let address = tester.accounts.new_account("test").unwrap();
+	let data = vec![TypedDataField::new("uint32", "value", "42")];
+	let signature = tester.accounts.sign_with_password(address, "test".into(), typed_data::hash(&data).unwrap()).unwrap();
+	tester.signer.add_request(ConfirmationPayload::SignTypedData(address, data)).unwrap();
+	assert_eq!(tester.signer.requests().len(), 1);
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"personal_confirmRequest","params":["0x1",{},"test"],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":""#.to_owned() + format!("0x{}", signature).as_ref() + r#"","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+}
+
 #[test]
 fn should_confirm_transaction_and_dispatch() {
 	//// given
@@ -18,7 +18,7 @@
 use std::sync::Arc;
 use jsonrpc_core::*;
 
-use v1::types::{Block, BlockNumber, Bytes, CallRequest, Filter, FilterChanges, Index};
+use v1::types::{Block, BlockNumber, BlockNumberOrHash, Bytes, CallRequest, Filter, FilterChanges, Index};
 use v1::types::{Log, Receipt, SyncStatus, Transaction, Work};
 use v1::types::{H64, H160, H256, U256};
 
@@ -59,6 +59,10 @@ build_rpc_trait! {
 		#[rpc(name = "eth_blockNumber")]
 		fn block_number(&self) -> Result<U256, Error>;
 
+		/// Returns the chain id used for transaction replay protection.
+		#[rpc(name = "eth_chainId")]
+		fn chain_id(&self) -> Result<U256, Error>;
+
 		/// Returns balance of the given account.
 		#[rpc(name = "eth_getBalance")]
 		fn balance(&self, H160, Trailing<BlockNumber>) -> Result<U256, Error>;
@@ -115,6 +119,10 @@ build_rpc_trait! {
 		#[rpc(name = "eth_getTransactionByHash")]
 		fn transaction_by_hash(&self, H256) -> Result<Option<Transaction>, Error>;
 
+		/// Get RLP-encoded transaction by its hash.
+		#[rpc(name = "eth_getRawTransactionByHash")]
+		fn raw_transaction_by_hash(&self, H256) -> Result<Option<Bytes>, Error>;
+
 		/// Returns transaction at given block hash and index.
 		#[rpc(name = "eth_getTransactionByBlockHashAndIndex")]
 		fn transaction_by_block_hash_and_index(&self, H256, Index) -> Result<Option<Transaction>, Error>;
@@ -127,6 +135,10 @@ build_rpc_trait! {
 		#[rpc(name = "eth_getTransactionReceipt")]
 		fn transaction_receipt(&self, H256) -> Result<Option<Receipt>, Error>;
 
+		/// Returns receipts of all transactions in given block.
+		#[rpc(name = "eth_getBlockReceipts")]
+		fn block_receipts(&self, BlockNumberOrHash) -> Result<Option<Vec<Receipt>>, Error>;
+
 		/// Returns an uncles at given block and index.
 		#[rpc(name = "eth_getUncleByBlockHashAndIndex")]
 		fn uncle_by_block_hash_and_index(&self, H256, Index) -> Result<Option<Block>, Error>;
@@ -204,6 +216,11 @@ pub trait EthSigning: Sized + Send + Sync + 'static {
 	/// Signs the data with given address signature.
 	fn sign(&self, _: Params, _: Ready);
 
+	/// Signs the hash of typed structured data with given address signature.
+	/// First parameter is the address, second is the list of `{type, name, value}` fields.
+	/// Requires confirmation in Trusted Signer unless the account is unlocked.
+	fn sign_typed_data(&self, _: Params, _: Ready);
+
 	/// Posts sign request asynchronously.
 	/// Will return a confirmation ID for later use with check_transaction.
 	fn post_sign(&self, _: Params) -> Result<Value, Error>;
@@ -218,6 +235,9 @@ pub trait EthSigning: Sized + Send + Sync + 'static {
 	/// Will return a transaction ID for later use with check_transaction.
 	fn post_transaction(&self, _: Params) -> Result<Value, Error>;
 
+	/// Signs transaction with an unlocked account and returns its raw RLP without broadcasting it.
+	fn sign_transaction(&self, _: Params) -> Result<Value, Error>;
+
 	/// Checks the progress of a previously posted request (transaction/sign).
 	/// Should be given a valid send_transaction ID.
 	/// Returns the transaction hash, the zero hash (not yet available),
@@ -227,17 +247,21 @@ pub trait EthSigning: Sized + Send + Sync + 'static {
 
 	/// Decrypt some ECIES-encrypted message.
 	/// First parameter is the address with which it is encrypted, second is the ciphertext.
-	fn decrypt_message(&self, _: Params) -> Result<Value, Error>;
+	/// Requires confirmation in Trusted Signer unless the account is unlocked.
+	fn decrypt_message(&self, _: Params, _: Ready);
 
 	/// Should be used to convert object to io delegate.
 	fn to_delegate(self) -> IoDelegate<Self> {
 		let mut delegate = IoDelegate::new(Arc::new(self));
 		delegate.add_async_method("eth_sign", EthSigning::sign);
+		delegate.add_async_method("eth_signTypedData", EthSigning::sign_typed_data);
 		delegate.add_async_method("eth_sendTransaction", EthSigning::send_transaction);
 		delegate.add_method("eth_postSign", EthSigning::post_sign);
 		delegate.add_method("eth_postTransaction", EthSigning::post_transaction);
+		delegate.add_method("eth_signTransaction", EthSigning::sign_transaction);
 		delegate.add_method("eth_checkRequest", EthSigning::check_request);
-		delegate.add_method("ethcore_decryptMessage", EthSigning::decrypt_message);
+		delegate.add_async_method("ethcore_decryptMessage", EthSigning::decrypt_message);
+		delegate.add_async_method("parity_decryptMessage", EthSigning::decrypt_message);
 		delegate
 	}
 }
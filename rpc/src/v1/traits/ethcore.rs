@@ -15,32 +15,37 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Ethcore-specific rpc interface.
+use std::collections::BTreeMap;
 use jsonrpc_core::Error;
 
-use v1::helpers::auto_args::{Wrap, WrapAsync, Ready};
-use v1::types::{H160, H256, H512, U256, Bytes, Peers, Transaction, RpcSettings};
+use v1::helpers::auto_args::{Wrap, WrapAsync, Ready, Trailing};
+use v1::types::{H160, H256, H512, U256, Bytes, BadBlock, BalanceSample, BlockNumber, ChainParams, ChainSpec, ChainStatus, Header, Health, Index, NodeEvent, Peers, NetStats, StateDiff, StateDiffAccounts, Transaction, QueuedTransaction, RpcSettings, StorageSlot, StorageChange, Upgrade};
 
 build_rpc_trait! {
 	/// Ethcore-specific rpc interface.
 	pub trait Ethcore {
 		/// Returns current transactions limit.
-		#[rpc(name = "ethcore_transactionsLimit")]
+		#[rpc(name = "ethcore_transactionsLimit", alias = ["parity_transactionsLimit"])]
 		fn transactions_limit(&self) -> Result<usize, Error>;
 
 		/// Returns mining extra data.
-		#[rpc(name = "ethcore_extraData")]
+		#[rpc(name = "ethcore_extraData", alias = ["parity_extraData"])]
 		fn extra_data(&self) -> Result<Bytes, Error>;
 
 		/// Returns mining gas floor target.
-		#[rpc(name = "ethcore_gasFloorTarget")]
+		#[rpc(name = "ethcore_gasFloorTarget", alias = ["parity_gasFloorTarget"])]
 		fn gas_floor_target(&self) -> Result<U256, Error>;
 
 		/// Returns mining gas floor cap.
-		#[rpc(name = "ethcore_gasCeilTarget")]
+		#[rpc(name = "ethcore_gasCeilTarget", alias = ["parity_gasCeilTarget"])]
 		fn gas_ceil_target(&self) -> Result<U256, Error>;
 
+		/// Returns the author of mined blocks.
+		#[rpc(name = "ethcore_author", alias = ["parity_author"])]
+		fn author(&self) -> Result<H160, Error>;
+
 		/// Returns minimal gas price for transaction to be included in queue.
-		#[rpc(name = "ethcore_minGasPrice")]
+		#[rpc(name = "ethcore_minGasPrice", alias = ["parity_minGasPrice"])]
 		fn min_gas_price(&self) -> Result<U256, Error>;
 
 		/// Returns latest logs
@@ -56,9 +61,13 @@ build_rpc_trait! {
 		fn net_chain(&self) -> Result<String, Error>;
 
 		/// Returns peers details
-		#[rpc(name = "ethcore_netPeers")]
+		#[rpc(name = "ethcore_netPeers", alias = ["parity_netPeers"])]
 		fn net_peers(&self) -> Result<Peers, Error>;
 
+		/// Returns network traffic statistics
+		#[rpc(name = "ethcore_netStats", alias = ["parity_netStats"])]
+		fn net_stats(&self) -> Result<NetStats, Error>;
+
 		/// Returns network port
 		#[rpc(name = "ethcore_netPort")]
 		fn net_port(&self) -> Result<u16, Error>;
@@ -71,6 +80,14 @@ build_rpc_trait! {
 		#[rpc(name = "ethcore_nodeName")]
 		fn node_name(&self) -> Result<String, Error>;
 
+		/// Returns the operating mode: "active", "passive", "dark", "offline" or "relay".
+		#[rpc(name = "ethcore_mode", alias = ["parity_mode"])]
+		fn mode(&self) -> Result<String, Error>;
+
+		/// Returns the newest release if it's newer than the running node, or null.
+		#[rpc(name = "ethcore_upgradeReady", alias = ["parity_upgradeReady"])]
+		fn upgrade_ready(&self) -> Result<Option<Upgrade>, Error>;
+
 		/// Returns default extra data
 		#[rpc(name = "ethcore_defaultExtraData")]
 		fn default_extra_data(&self) -> Result<Bytes, Error>;
@@ -93,12 +110,14 @@ build_rpc_trait! {
 		fn phrase_to_address(&self, String) -> Result<H160, Error>;
 
 		/// Returns the value of the registrar for this network.
-		#[rpc(name = "ethcore_registryAddress")]
-		fn registry_address(&self) -> Result<Option<H160>, Error>;
+		/// If a name is given, returns the address registered for it in the registrar instead.
+		#[rpc(name = "ethcore_registryAddress", alias = ["parity_registryAddress"])]
+		fn registry_address(&self, Trailing<String>) -> Result<Option<H160>, Error>;
 
-		/// Returns all addresses if Fat DB is enabled (`--fat-db`), or null if not.
+		/// Returns up to `count` addresses following the given one (ordered by address hash) if Fat DB is enabled (`--fat-db`),
+		/// or null if not.
 		#[rpc(name = "ethcore_listAccounts")]
-		fn list_accounts(&self) -> Result<Option<Vec<H160>>, Error>;
+		fn list_accounts(&self, u64, Trailing<Option<H160>>) -> Result<Option<Vec<H160>>, Error>;
 
 		/// Returns all storage keys of the given address (first parameter) if Fat DB is enabled (`--fat-db`),
 		/// or null if not.
@@ -107,13 +126,95 @@ build_rpc_trait! {
 
 		/// Encrypt some data with a public key under ECIES.
 		/// First parameter is the 512-byte destination public key, second is the message.
-		#[rpc(name = "ethcore_encryptMessage")]
+		#[rpc(name = "ethcore_encryptMessage", alias = ["parity_encryptMessage"])]
 		fn encrypt_message(&self, H512, Bytes) -> Result<Bytes, Error>;
 
 		/// Returns all pending transactions from transaction queue.
 		#[rpc(name = "ethcore_pendingTransactions")]
 		fn pending_transactions(&self) -> Result<Vec<Transaction>, Error>;
 
+		/// Returns transactions ready to be included in a block along with their position in the queue.
+		#[rpc(name = "ethcore_pendingTransactionsWithPosition", alias = ["parity_pendingTransactionsWithPosition"])]
+		fn queued_transactions(&self) -> Result<Vec<QueuedTransaction>, Error>;
+
+		/// Returns transactions which can't be included until a nonce gap is filled.
+		#[rpc(name = "ethcore_futureTransactions", alias = ["parity_futureTransactions"])]
+		fn future_transactions(&self) -> Result<Vec<QueuedTransaction>, Error>;
+
+		/// Starts watching given storage slots. Values are checked after every block import.
+		/// Watches which are not polled for a minute are removed. Returns id of the watch.
+		/// Over WebSockets, `ethcore_subscribeStorageWatch` with the id pushes the changes
+		/// to that connection as `ethcore_storageWatchChanged` until it is closed.
+		#[rpc(name = "ethcore_newStorageWatch")]
+		fn new_storage_watch(&self, Vec<StorageSlot>) -> Result<U256, Error>;
+
+		/// Returns changes of watched slots since the last poll.
+		#[rpc(name = "ethcore_getStorageWatchChanges")]
+		fn storage_watch_changes(&self, Index) -> Result<Vec<StorageChange>, Error>;
+
+		/// Stops watching storage slots.
+		#[rpc(name = "ethcore_uninstallStorageWatch")]
+		fn uninstall_storage_watch(&self, Index) -> Result<bool, Error>;
+
+		/// Returns a summary of the node's condition.
+		#[rpc(name = "ethcore_health")]
+		fn health(&self) -> Result<Health, Error>;
+
+		/// Returns information on the local chain database, e.g. blocks missing after a warp sync.
+		#[rpc(name = "ethcore_chainStatus", alias = ["parity_chainStatus"])]
+		fn chain_status(&self) -> Result<ChainStatus, Error>;
+
+		/// Returns RLP-encoded header of the given block.
+		#[rpc(name = "ethcore_blockHeader", alias = ["parity_blockHeader"])]
+		fn block_header(&self, BlockNumber) -> Result<Option<Bytes>, Error>;
+
+		/// Returns headers of blocks in the given inclusive range, at most `max` of them.
+		/// The number of returned headers is capped by the server regardless of `max`.
+		#[rpc(name = "ethcore_blockHeaders", alias = ["parity_blockHeaders"])]
+		fn block_headers(&self, BlockNumber, BlockNumber, Trailing<u64>) -> Result<Vec<Header>, Error>;
+
+		/// Returns RLP-encoded body (transactions and uncles) of the given block.
+		#[rpc(name = "ethcore_blockBody", alias = ["parity_blockBody"])]
+		fn block_body(&self, BlockNumber) -> Result<Option<Bytes>, Error>;
+
+		/// Returns changed balances, nonces, code and storage between two blocks, either of the listed accounts
+		/// or of a page of `count` accounts following `after` in state trie order. Requires fat DB.
+		/// Pruned states of recent blocks are rebuilt by replaying blocks.
+		#[rpc(name = "ethcore_stateDiff", alias = ["parity_stateDiff"])]
+		fn state_diff(&self, BlockNumber, BlockNumber, StateDiffAccounts) -> Result<StateDiff, Error>;
+
+		/// Returns balances of an account after every `step`-th block in the given inclusive range.
+		/// Pruned balances are reconstructed from traces, they are `null` when an unrecorded reward to the account gets in the way.
+		#[rpc(name = "ethcore_balanceHistory", alias = ["parity_balanceHistory"])]
+		fn balance_history(&self, H160, BlockNumber, BlockNumber, Trailing<u64>) -> Result<Vec<BalanceSample>, Error>;
+
+		/// Returns the active chain's id, fork transition blocks and engine parameters.
+		#[rpc(name = "ethcore_chainParams", alias = ["parity_chainParams"])]
+		fn chain_params(&self) -> Result<ChainParams, Error>;
+
+		/// Returns the resolved chain spec: chain parameters, builtin contracts and genesis hash.
+		/// Nodes of the same network are expected to return the same value.
+		#[rpc(name = "ethcore_chainSpec", alias = ["parity_chainSpec"])]
+		fn chain_spec(&self) -> Result<ChainSpec, Error>;
+
+		/// Returns the root of the Canonical Hash Trie with the given index, mapping the numbers of 2048 blocks
+		/// to their hashes and total difficulties. Only generated by nodes running with `--no-bodies`.
+		#[rpc(name = "ethcore_chtRoot", alias = ["parity_chtRoot"])]
+		fn cht_root(&self, Index) -> Result<Option<H256>, Error>;
+
+		/// Returns how many of the latest blocks (100 unless given) carry each extra data,
+		/// decoded to a readable string. Useful to see which pools signal for a fork.
+		#[rpc(name = "ethcore_extraDataHistogram", alias = ["parity_extraDataHistogram"])]
+		fn extra_data_histogram(&self, Trailing<u64>) -> Result<BTreeMap<String, u64>, Error>;
+
+		/// Returns the blocks which failed verification, oldest first, with the failure reasons.
+		#[rpc(name = "ethcore_badBlocks", alias = ["parity_badBlocks"])]
+		fn bad_blocks(&self) -> Result<Vec<BadBlock>, Error>;
+
+		/// Returns journal of node lifecycle events (starts, shutdowns, database repairs, fatal errors).
+		#[rpc(name = "ethcore_nodeEvents")]
+		fn node_events(&self) -> Result<Vec<NodeEvent>, Error>;
+
 		/// Hash a file content under given URL.
 		#[rpc(async, name = "ethcore_hashContent")]
 		fn hash_content(&self, Ready<H256>, String);
@@ -19,53 +19,53 @@
 use jsonrpc_core::Error;
 
 use v1::helpers::auto_args::Wrap;
-use v1::types::{Bytes, H160, U256};
+use v1::types::{Bytes, H160, H256, U256, Transaction};
 
 build_rpc_trait! {
 	/// Ethcore-specific rpc interface for operations altering the settings.
 	pub trait EthcoreSet {
 		/// Sets new minimal gas price for mined blocks.
-		#[rpc(name = "ethcore_setMinGasPrice")]
+		#[rpc(name = "ethcore_setMinGasPrice", alias = ["parity_setMinGasPrice"])]
 		fn set_min_gas_price(&self, U256) -> Result<bool, Error>;
 
 		/// Sets new gas floor target for mined blocks.
-		#[rpc(name = "ethcore_setGasFloorTarget")]
+		#[rpc(name = "ethcore_setGasFloorTarget", alias = ["parity_setGasFloorTarget"])]
 		fn set_gas_floor_target(&self, U256) -> Result<bool, Error>;
 
 		/// Sets new gas ceiling target for mined blocks.
-		#[rpc(name = "ethcore_setGasCeilTarget")]
+		#[rpc(name = "ethcore_setGasCeilTarget", alias = ["parity_setGasCeilTarget"])]
 		fn set_gas_ceil_target(&self, U256) -> Result<bool, Error>;
 
 		/// Sets new extra data for mined blocks.
-		#[rpc(name = "ethcore_setExtraData")]
+		#[rpc(name = "ethcore_setExtraData", alias = ["parity_setExtraData"])]
 		fn set_extra_data(&self, Bytes) -> Result<bool, Error>;
 
 		/// Sets new author for mined block.
-		#[rpc(name = "ethcore_setAuthor")]
+		#[rpc(name = "ethcore_setAuthor", alias = ["parity_setAuthor"])]
 		fn set_author(&self, H160) -> Result<bool, Error>;
 
 		/// Sets the limits for transaction queue.
-		#[rpc(name = "ethcore_setTransactionsLimit")]
+		#[rpc(name = "ethcore_setTransactionsLimit", alias = ["parity_setTransactionsLimit"])]
 		fn set_transactions_limit(&self, usize) -> Result<bool, Error>;
 
 		/// Sets the maximum amount of gas a single transaction may consume.
-		#[rpc(name = "ethcore_setMaxTransactionGas")]
+		#[rpc(name = "ethcore_setMaxTransactionGas", alias = ["parity_setMaxTransactionGas"])]
 		fn set_tx_gas_limit(&self, U256) -> Result<bool, Error>;
 
 		/// Add a reserved peer.
-		#[rpc(name = "ethcore_addReservedPeer")]
+		#[rpc(name = "ethcore_addReservedPeer", alias = ["parity_addReservedPeer"])]
 		fn add_reserved_peer(&self, String) -> Result<bool, Error>;
 
 		/// Remove a reserved peer.
-		#[rpc(name = "ethcore_removeReservedPeer")]
+		#[rpc(name = "ethcore_removeReservedPeer", alias = ["parity_removeReservedPeer"])]
 		fn remove_reserved_peer(&self, String) -> Result<bool, Error>;
 
 		/// Drop all non-reserved peers.
-		#[rpc(name = "ethcore_dropNonReservedPeers")]
+		#[rpc(name = "ethcore_dropNonReservedPeers", alias = ["parity_dropNonReservedPeers"])]
 		fn drop_non_reserved_peers(&self) -> Result<bool, Error>;
 
 		/// Accept non-reserved peers (default behavior)
-		#[rpc(name = "ethcore_acceptNonReservedPeers")]
+		#[rpc(name = "ethcore_acceptNonReservedPeers", alias = ["parity_acceptNonReservedPeers"])]
 		fn accept_non_reserved_peers(&self) -> Result<bool, Error>;
 
 		/// Start the network.
@@ -75,5 +75,23 @@ build_rpc_trait! {
 		/// Stop the network.
 		#[rpc(name = "ethcore_stopNetwork")]
 		fn stop_network(&self) -> Result<bool, Error>;
+
+		/// Change the operating mode to "active", "passive", "dark" or "offline".
+		#[rpc(name = "ethcore_setMode", alias = ["parity_setMode"])]
+		fn set_mode(&self, String) -> Result<bool, Error>;
+
+		/// Adjust logging levels, e.g. `sync=trace`.
+		#[rpc(name = "ethcore_setLoggerLevel", alias = ["parity_setLoggerLevel"])]
+		fn set_logger_level(&self, String) -> Result<bool, Error>;
+
+		/// Import a block refused for reorganizing deeper than `--max-reorg-depth`,
+		/// along with its refused descendants.
+		#[rpc(name = "ethcore_forceReorg", alias = ["parity_forceReorg"])]
+		fn force_reorg(&self, H256) -> Result<bool, Error>;
+
+		/// Remove a transaction from the queue. Later transactions of the same sender wait for the nonce gap to be filled.
+		/// Returns the removed transaction or `null` if it was not queued.
+		#[rpc(name = "ethcore_removeTransaction", alias = ["parity_removeTransaction"])]
+		fn remove_transaction(&self, H256) -> Result<Option<Transaction>, Error>;
 	}
 }
\ No newline at end of file
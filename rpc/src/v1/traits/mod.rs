@@ -23,6 +23,7 @@ pub mod personal;
 pub mod ethcore;
 pub mod ethcore_set;
 pub mod traces;
+pub mod private;
 pub mod rpc;
 
 pub use self::web3::Web3;
@@ -32,6 +33,7 @@ pub use self::personal::{Personal, PersonalSigner};
 pub use self::ethcore::Ethcore;
 pub use self::ethcore_set::EthcoreSet;
 pub use self::traces::Traces;
+pub use self::private::Private;
 pub use self::rpc::Rpc;
 
 
@@ -42,6 +42,18 @@ pub trait Personal: Sized + Send + Sync + 'static {
 	/// Sends transaction and signs it in single call. The account is not unlocked in such case.
 	fn sign_and_send_transaction(&self, _: Params) -> Result<Value, Error>;
 
+	/// Signs transaction with given password and returns its raw RLP without broadcasting it.
+	fn sign_transaction(&self, _: Params) -> Result<Value, Error>;
+
+	/// Signs the message prefixed with `"\x19Ethereum Signed Message:\n" + len(message)`.
+	/// First parameter is the message, second is the address.
+	/// Requires confirmation in Trusted Signer unless the account is unlocked.
+	fn sign(&self, _: Params, _: Ready);
+
+	/// Returns the address which signed the message with `personal_sign`.
+	/// First parameter is the message, second is the signature.
+	fn ec_recover(&self, _: Params) -> Result<Value, Error>;
+
 	/// Returns `true` if Trusted Signer is enabled, `false` otherwise.
 	fn signer_enabled(&self, _: Params) -> Result<Value, Error>;
 
@@ -60,6 +72,39 @@ pub trait Personal: Sized + Send + Sync + 'static {
 	/// Imports a number of Geth accounts, with the list provided as the argument.
 	fn import_geth_accounts(&self, _: Params) -> Result<Value, Error>;
 
+	/// Creates new vault with given name and password and opens it.
+	fn new_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Opens existing vault, making its accounts available.
+	fn open_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Closes opened vault, locking its accounts.
+	fn close_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Lists all vaults.
+	fn list_vaults(&self, _: Params) -> Result<Value, Error>;
+
+	/// Lists opened vaults.
+	fn list_opened_vaults(&self, _: Params) -> Result<Value, Error>;
+
+	/// Moves an account to an opened vault, or back to the main keys directory if vault name is empty.
+	/// Second parameter is the current password of the account.
+	fn change_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns vault metadata string.
+	fn get_vault_meta(&self, _: Params) -> Result<Value, Error>;
+
+	/// Sets metadata string of an opened vault.
+	fn set_vault_meta(&self, _: Params) -> Result<Value, Error>;
+
+	/// Derives new address from given account using 256-bit index (hash).
+	/// Params are account address, password, derivation and whether the derived key should be saved.
+	fn derive_address_hash(&self, _: Params) -> Result<Value, Error>;
+
+	/// Derives new address from given account along BIP-32 path.
+	/// Params are account address, password, derivation path and whether the derived key should be saved.
+	fn derive_address_index(&self, _: Params) -> Result<Value, Error>;
+
 	/// Should be used to convert object to io delegate.
 	fn to_delegate(self) -> IoDelegate<Self> {
 		let mut delegate = IoDelegate::new(Arc::new(self));
@@ -70,11 +115,24 @@ pub trait Personal: Sized + Send + Sync + 'static {
 		delegate.add_method("personal_newAccountFromWallet", Personal::new_account_from_wallet);
 		delegate.add_method("personal_unlockAccount", Personal::unlock_account);
 		delegate.add_method("personal_signAndSendTransaction", Personal::sign_and_send_transaction);
+		delegate.add_method("personal_signTransaction", Personal::sign_transaction);
+		delegate.add_async_method("personal_sign", Personal::sign);
+		delegate.add_method("personal_ecRecover", Personal::ec_recover);
 		delegate.add_method("personal_setAccountName", Personal::set_account_name);
 		delegate.add_method("personal_setAccountMeta", Personal::set_account_meta);
 		delegate.add_method("personal_accountsInfo", Personal::accounts_info);
 		delegate.add_method("personal_listGethAccounts", Personal::geth_accounts);
 		delegate.add_method("personal_importGethAccounts", Personal::import_geth_accounts);
+		delegate.add_method("parity_newVault", Personal::new_vault);
+		delegate.add_method("parity_openVault", Personal::open_vault);
+		delegate.add_method("parity_closeVault", Personal::close_vault);
+		delegate.add_method("parity_listVaults", Personal::list_vaults);
+		delegate.add_method("parity_listOpenedVaults", Personal::list_opened_vaults);
+		delegate.add_method("parity_changeVault", Personal::change_vault);
+		delegate.add_method("parity_getVaultMeta", Personal::get_vault_meta);
+		delegate.add_method("parity_setVaultMeta", Personal::set_vault_meta);
+		delegate.add_method("parity_deriveAddressHash", Personal::derive_address_hash);
+		delegate.add_method("parity_deriveAddressIndex", Personal::derive_address_index);
 
 		delegate
 	}
@@ -102,6 +160,10 @@ pub trait PersonalSigner: Sized + Send + Sync + 'static {
 		delegate.add_method("personal_confirmRequest", PersonalSigner::confirm_request);
 		delegate.add_method("personal_rejectRequest", PersonalSigner::reject_request);
 		delegate.add_method("personal_generateAuthorizationToken", PersonalSigner::generate_token);
+		delegate.add_method("signer_requestsToConfirm", PersonalSigner::requests_to_confirm);
+		delegate.add_method("signer_confirmRequest", PersonalSigner::confirm_request);
+		delegate.add_method("signer_rejectRequest", PersonalSigner::reject_request);
+		delegate.add_method("signer_generateAuthorizationToken", PersonalSigner::generate_token);
 		delegate
 	}
 }
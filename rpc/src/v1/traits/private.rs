@@ -0,0 +1,34 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+//! Private transactions rpc interface.
+
+use jsonrpc_core::Error;
+
+use v1::helpers::auto_args::{Trailing, Wrap};
+use v1::types::{Bytes, BlockNumber, CallRequest, PrivateTransactionReceipt};
+
+build_rpc_trait! {
+	/// Private transactions rpc interface.
+	pub trait Private {
+		/// Encrypts a signed transaction to a private contract and sends it to the contract's validators.
+		#[rpc(name = "private_sendTransaction")]
+		fn send_transaction(&self, Bytes) -> Result<PrivateTransactionReceipt, Error>;
+
+		/// Calls a private contract against its decrypted state, returning the output data.
+		#[rpc(name = "private_call")]
+		fn call(&self, CallRequest, Trailing<BlockNumber>) -> Result<Bytes, Error>;
+	}
+}
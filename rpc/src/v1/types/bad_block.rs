@@ -0,0 +1,44 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block which failed verification.
+
+use ethcore::bad_block::BadBlock as EthBadBlock;
+use v1::types::{Bytes, H256};
+
+/// Block which failed verification, with the failure reason.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BadBlock {
+	/// Block hash
+	pub hash: H256,
+	/// RLP of the block
+	pub rlp: Bytes,
+	/// Why the block was rejected
+	pub reason: String,
+	/// Unix timestamp (seconds) of the rejection
+	pub timestamp: u64,
+}
+
+impl From<EthBadBlock> for BadBlock {
+	fn from(b: EthBadBlock) -> Self {
+		BadBlock {
+			hash: b.hash.into(),
+			rlp: b.rlp.into(),
+			reason: b.reason,
+			timestamp: b.timestamp,
+		}
+	}
+}
@@ -0,0 +1,45 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Account balance history.
+
+use v1::types::U256;
+
+/// Balance of an account after a given block.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BalanceSample {
+	/// Block number
+	pub number: U256,
+	/// Balance after the block, `None` if the block's state is not available
+	pub balance: Option<U256>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::BalanceSample;
+
+	#[test]
+	fn test_serialize_balance_sample() {
+		let t = BalanceSample { number: 16.into(), balance: Some(5.into()) };
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"number":"0x10","balance":"0x5"}"#);
+
+		let t = BalanceSample { number: 16.into(), balance: None };
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"number":"0x10","balance":null}"#);
+	}
+}
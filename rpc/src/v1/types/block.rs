@@ -15,6 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use serde::{Serialize, Serializer};
+use ethcore::header::Header as EthHeader;
 use v1::types::{Bytes, Transaction, H160, H256, H2048, U256};
 
 /// Block Transactions
@@ -93,11 +94,79 @@ pub struct Block {
 	pub size: Option<U256>,
 }
 
+/// Block header representation
+#[derive(Debug, Serialize)]
+pub struct Header {
+	/// Hash of the block
+	pub hash: H256,
+	/// Hash of the parent
+	#[serde(rename="parentHash")]
+	pub parent_hash: H256,
+	/// Hash of the uncles
+	#[serde(rename="sha3Uncles")]
+	pub uncles_hash: H256,
+	/// Authors address
+	pub author: H160,
+	/// State root hash
+	#[serde(rename="stateRoot")]
+	pub state_root: H256,
+	/// Transactions root hash
+	#[serde(rename="transactionsRoot")]
+	pub transactions_root: H256,
+	/// Transactions receipts root hash
+	#[serde(rename="receiptsRoot")]
+	pub receipts_root: H256,
+	/// Block number
+	pub number: U256,
+	/// Gas Used
+	#[serde(rename="gasUsed")]
+	pub gas_used: U256,
+	/// Gas Limit
+	#[serde(rename="gasLimit")]
+	pub gas_limit: U256,
+	/// Extra data
+	#[serde(rename="extraData")]
+	pub extra_data: Bytes,
+	/// Logs bloom
+	#[serde(rename="logsBloom")]
+	pub logs_bloom: H2048,
+	/// Timestamp
+	pub timestamp: U256,
+	/// Difficulty
+	pub difficulty: U256,
+	/// Seal fields
+	#[serde(rename="sealFields")]
+	pub seal_fields: Vec<Bytes>,
+}
+
+impl From<EthHeader> for Header {
+	fn from(h: EthHeader) -> Self {
+		Header {
+			hash: h.hash().into(),
+			parent_hash: h.parent_hash().clone().into(),
+			uncles_hash: h.uncles_hash().clone().into(),
+			author: h.author().clone().into(),
+			state_root: h.state_root().clone().into(),
+			transactions_root: h.transactions_root().clone().into(),
+			receipts_root: h.receipts_root().clone().into(),
+			number: h.number().into(),
+			gas_used: h.gas_used().clone().into(),
+			gas_limit: h.gas_limit().clone().into(),
+			extra_data: h.extra_data().clone().into(),
+			logs_bloom: h.log_bloom().clone().into(),
+			timestamp: h.timestamp().into(),
+			difficulty: h.difficulty().clone().into(),
+			seal_fields: h.seal().iter().map(|f| ::rlp::decode(f)).map(Bytes::new).collect(),
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use serde_json;
+	use ethcore::header::Header as EthHeader;
 	use v1::types::{Transaction, H160, H256, H2048, Bytes, U256};
-	use super::{Block, BlockTransactions};
+	use super::{Block, BlockTransactions, Header};
 
 	#[test]
 	fn test_serialize_block_transactions() {
@@ -138,4 +207,16 @@ mod tests {
 		let serialized = serde_json::to_string(&block).unwrap();
 		assert_eq!(serialized, r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","parentHash":"0x0000000000000000000000000000000000000000000000000000000000000000","sha3Uncles":"0x0000000000000000000000000000000000000000000000000000000000000000","author":"0x0000000000000000000000000000000000000000","miner":"0x0000000000000000000000000000000000000000","stateRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","transactionsRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","receiptsRoot":"0x0000000000000000000000000000000000000000000000000000000000000000","number":"0x0","gasUsed":"0x0","gasLimit":"0x0","extraData":"0x","logsBloom":"0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000","timestamp":"0x0","difficulty":"0x0","totalDifficulty":"0x0","sealFields":["0x","0x"],"uncles":[],"transactions":[],"size":"0x45"}"#);
 	}
+
+	#[test]
+	fn test_serialize_header() {
+		let mut header = EthHeader::default();
+		header.set_number(2);
+		header.set_seal(vec![::rlp::encode(&vec![1u8]).to_vec()]);
+
+		let serialized = serde_json::to_string(&Header::from(header.clone())).unwrap();
+		assert!(serialized.starts_with(&format!(r#"{{"hash":"0x{:?}","parentHash":"#, header.hash())));
+		assert!(serialized.contains(r#""number":"0x2""#));
+		assert!(serialized.ends_with(r#""sealFields":["0x01"]}"#));
+	}
 }
@@ -14,9 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::str::FromStr;
 use serde::{Deserialize, Deserializer, Error};
 use serde::de::Visitor;
 use ethcore::client::BlockID;
+use v1::types::H256;
 
 /// Represents rpc api block number param.
 #[derive(Debug, PartialEq, Clone)]
@@ -75,9 +77,54 @@ impl Into<BlockID> for BlockNumber {
 	}
 }
 
+/// Represents rpc api param referring to a block either by number or by hash.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BlockNumberOrHash {
+	/// Block number or tag
+	Number(BlockNumber),
+	/// Block hash
+	Hash(H256),
+}
+
+impl Deserialize for BlockNumberOrHash {
+	fn deserialize<D>(deserializer: &mut D) -> Result<BlockNumberOrHash, D::Error>
+	where D: Deserializer {
+		deserializer.deserialize(BlockNumberOrHashVisitor)
+	}
+}
+
+struct BlockNumberOrHashVisitor;
+
+impl Visitor for BlockNumberOrHashVisitor {
+	type Value = BlockNumberOrHash;
+
+	fn visit_str<E>(&mut self, value: &str) -> Result<Self::Value, E> where E: Error {
+		// block numbers never take up 32 bytes
+		if value.len() == 66 && value.starts_with("0x") {
+			H256::from_str(&value[2..]).map(BlockNumberOrHash::Hash).map_err(|_| Error::custom("invalid block hash"))
+		} else {
+			BlockNumberVisitor.visit_str(value).map(BlockNumberOrHash::Number)
+		}
+	}
+
+	fn visit_string<E>(&mut self, value: String) -> Result<Self::Value, E> where E: Error {
+		self.visit_str(value.as_ref())
+	}
+}
+
+impl Into<BlockID> for BlockNumberOrHash {
+	fn into(self) -> BlockID {
+		match self {
+			BlockNumberOrHash::Number(number) => number.into(),
+			BlockNumberOrHash::Hash(hash) => BlockID::Hash(hash.into()),
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use ethcore::client::BlockID;
+	use v1::types::H256;
 	use super::*;
 	use serde_json;
 
@@ -88,6 +135,17 @@ mod tests {
 		assert_eq!(deserialized, vec![BlockNumber::Num(10), BlockNumber::Num(10), BlockNumber::Latest, BlockNumber::Earliest, BlockNumber::Pending])
 	}
 
+	#[test]
+	fn block_number_or_hash_deserialization() {
+		let s = r#"["0xa", "latest", "0x0000000000000000000000000000000000000000000000000000000000000005"]"#;
+		let deserialized: Vec<BlockNumberOrHash> = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized, vec![
+			BlockNumberOrHash::Number(BlockNumber::Num(10)),
+			BlockNumberOrHash::Number(BlockNumber::Latest),
+			BlockNumberOrHash::Hash(H256::from(5u64)),
+		]);
+	}
+
 	#[test]
 	fn block_number_into() {
 		assert_eq!(BlockID::Number(100), BlockNumber::Num(100).into());
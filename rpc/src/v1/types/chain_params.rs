@@ -0,0 +1,98 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain parameters.
+
+use std::collections::BTreeMap;
+use v1::types::{U256, H160, H256};
+
+/// Parameters of the chain the node is running.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ChainParams {
+	/// Chain id used for transaction replay protection
+	#[serde(rename="chainId")]
+	pub chain_id: U256,
+	/// Network id
+	#[serde(rename="networkId")]
+	pub network_id: U256,
+	/// Consensus engine name
+	pub engine: String,
+	/// Fork transition blocks by name
+	pub forks: BTreeMap<String, U256>,
+	/// Engine-specific parameters
+	#[serde(rename="engineParams")]
+	pub engine_params: BTreeMap<String, String>,
+}
+
+/// Builtin contract of the chain.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Builtin {
+	/// Builtin name
+	pub name: String,
+	/// Activation block
+	#[serde(rename="activateAt")]
+	pub activate_at: U256,
+}
+
+/// Resolved chain spec of the running node.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ChainSpec {
+	/// Chain parameters
+	pub params: ChainParams,
+	/// Builtin contracts by address
+	pub builtins: BTreeMap<H160, Builtin>,
+	/// Genesis block hash
+	#[serde(rename="genesisHash")]
+	pub genesis_hash: H256,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{ChainParams, ChainSpec, Builtin};
+
+	#[test]
+	fn chain_params_serialization() {
+		let params = ChainParams {
+			chain_id: 1u64.into(),
+			network_id: 1u64.into(),
+			engine: "Ethash".into(),
+			forks: map!["homestead".to_owned() => 1150000u64.into()],
+			engine_params: map!["durationLimit".to_owned() => "13".to_owned()],
+		};
+
+		let serialized = serde_json::to_string(&params).unwrap();
+		assert_eq!(serialized, r#"{"chainId":"0x1","networkId":"0x1","engine":"Ethash","forks":{"homestead":"0x118c30"},"engineParams":{"durationLimit":"13"}}"#);
+	}
+
+	#[test]
+	fn chain_spec_serialization() {
+		let spec = ChainSpec {
+			params: ChainParams {
+				chain_id: 2u64.into(),
+				network_id: 2u64.into(),
+				engine: "NullEngine".into(),
+				forks: Default::default(),
+				engine_params: Default::default(),
+			},
+			builtins: map![1u64.into() => Builtin { name: "ecrecover".into(), activate_at: 0u64.into() }],
+			genesis_hash: 5u64.into(),
+		};
+
+		let serialized = serde_json::to_string(&spec).unwrap();
+		assert_eq!(serialized, r#"{"params":{"chainId":"0x2","networkId":"0x2","engine":"NullEngine","forks":{},"engineParams":{}},"builtins":{"0x0000000000000000000000000000000000000001":{"name":"ecrecover","activateAt":"0x0"}},"genesisHash":"0x0000000000000000000000000000000000000000000000000000000000000005"}"#);
+	}
+}
@@ -16,7 +16,7 @@
 
 //! Types used in Confirmations queue (Trusted Signer)
 
-use v1::types::{U256, TransactionRequest, H160, H256};
+use v1::types::{U256, TransactionRequest, TypedDataField, H160, H256, Bytes};
 use v1::helpers;
 
 
@@ -47,6 +47,33 @@ pub struct SignRequest {
 	pub hash: H256,
 }
 
+/// Sign prefixed message request
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+pub struct SignMessageRequest {
+	/// Address
+	pub address: H160,
+	/// Message to sign, without the prefix
+	pub data: Bytes,
+}
+
+/// Sign typed data request
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+pub struct SignTypedDataRequest {
+	/// Address
+	pub address: H160,
+	/// Fields of the data to sign
+	pub data: Vec<TypedDataField>,
+}
+
+/// Decrypt request
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+pub struct DecryptRequest {
+	/// Address
+	pub address: H160,
+	/// Message to decrypt
+	pub msg: Bytes,
+}
+
 /// Confirmation payload, i.e. the thing to be confirmed
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
 pub enum ConfirmationPayload {
@@ -56,6 +83,15 @@ pub enum ConfirmationPayload {
 	/// Signature
 	#[serde(rename="sign")]
 	Sign(SignRequest),
+	/// Signature of a prefixed message
+	#[serde(rename="signMessage")]
+	SignMessage(SignMessageRequest),
+	/// Signature of typed data
+	#[serde(rename="signTypedData")]
+	SignTypedData(SignTypedDataRequest),
+	/// Decryption
+	#[serde(rename="decrypt")]
+	Decrypt(DecryptRequest),
 }
 
 impl From<helpers::ConfirmationPayload> for ConfirmationPayload {
@@ -66,6 +102,18 @@ impl From<helpers::ConfirmationPayload> for ConfirmationPayload {
 				address: address.into(),
 				hash: hash.into(),
 			}),
+			helpers::ConfirmationPayload::SignMessage(address, data) => ConfirmationPayload::SignMessage(SignMessageRequest {
+				address: address.into(),
+				data: data.into(),
+			}),
+			helpers::ConfirmationPayload::SignTypedData(address, data) => ConfirmationPayload::SignTypedData(SignTypedDataRequest {
+				address: address.into(),
+				data: data.into_iter().map(Into::into).collect(),
+			}),
+			helpers::ConfirmationPayload::Decrypt(address, msg) => ConfirmationPayload::Decrypt(DecryptRequest {
+				address: address.into(),
+				msg: msg.into(),
+			}),
 		}
 	}
 }
@@ -84,6 +132,7 @@ mod tests {
 	use serde_json;
 	use v1::types::U256;
 	use v1::helpers;
+	use ethstore::typed_data::TypedDataField;
 	use super::*;
 
 	#[test]
@@ -102,6 +151,56 @@ mod tests {
 		assert_eq!(res.unwrap(), expected.to_owned());
 	}
 
+	#[test]
+	fn should_serialize_sign_message_confirmation() {
+		// given
+		let request = helpers::ConfirmationRequest {
+			id: 15.into(),
+			payload: helpers::ConfirmationPayload::SignMessage(1.into(), b"Hello".to_vec()),
+		};
+
+		// when
+		let res = serde_json::to_string(&ConfirmationRequest::from(request));
+		let expected = r#"{"id":"0xf","payload":{"signMessage":{"address":"0x0000000000000000000000000000000000000001","data":"0x48656c6c6f"}}}"#;
+
+		// then
+		assert_eq!(res.unwrap(), expected.to_owned());
+	}
+
+	#[test]
+	fn should_serialize_sign_typed_data_confirmation() {
+		// given
+		let request = helpers::ConfirmationRequest {
+			id: 15.into(),
+			payload: helpers::ConfirmationPayload::SignTypedData(1.into(), vec![
+				TypedDataField::new("uint32", "value", "42"),
+			]),
+		};
+
+		// when
+		let res = serde_json::to_string(&ConfirmationRequest::from(request));
+		let expected = r#"{"id":"0xf","payload":{"signTypedData":{"address":"0x0000000000000000000000000000000000000001","data":[{"type":"uint32","name":"value","value":"42"}]}}}"#;
+
+		// then
+		assert_eq!(res.unwrap(), expected.to_owned());
+	}
+
+	#[test]
+	fn should_serialize_decrypt_confirmation() {
+		// given
+		let request = helpers::ConfirmationRequest {
+			id: 15.into(),
+			payload: helpers::ConfirmationPayload::Decrypt(1.into(), vec![1, 2, 3]),
+		};
+
+		// when
+		let res = serde_json::to_string(&ConfirmationRequest::from(request));
+		let expected = r#"{"id":"0xf","payload":{"decrypt":{"address":"0x0000000000000000000000000000000000000001","msg":"0x010203"}}}"#;
+
+		// then
+		assert_eq!(res.unwrap(), expected.to_owned());
+	}
+
 	#[test]
 	fn should_serialize_transaction_confirmation() {
 		// given
@@ -0,0 +1,125 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Key derivation types.
+
+use serde::{Deserialize, Deserializer, Error};
+use serde::de::Visitor;
+use ethkey::{Derivation, HARDENED_BIT};
+use v1::types::H256;
+
+/// Type of key derivation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DerivationType {
+	/// Soft derivation, children can be derived from the parent public key.
+	Soft,
+	/// Hardened derivation.
+	Hard,
+}
+
+impl Deserialize for DerivationType {
+	fn deserialize<D>(deserializer: &mut D) -> Result<DerivationType, D::Error>
+	where D: Deserializer {
+		deserializer.deserialize(DerivationTypeVisitor)
+	}
+}
+
+struct DerivationTypeVisitor;
+
+impl Visitor for DerivationTypeVisitor {
+	type Value = DerivationType;
+
+	fn visit_str<E>(&mut self, value: &str) -> Result<Self::Value, E> where E: Error {
+		match value {
+			"soft" => Ok(DerivationType::Soft),
+			"hard" => Ok(DerivationType::Hard),
+			_ => Err(Error::custom("invalid derivation type, expected 'soft' or 'hard'")),
+		}
+	}
+
+	fn visit_string<E>(&mut self, value: String) -> Result<Self::Value, E> where E: Error {
+		self.visit_str(value.as_ref())
+	}
+}
+
+/// Derivation with 256-bit index.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DeriveHash {
+	/// Index
+	pub hash: H256,
+	/// Derivation type
+	#[serde(rename="type")]
+	pub derivation_type: DerivationType,
+}
+
+/// Single step of BIP-32 derivation path.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DeriveHierarchicalItem {
+	/// Index, has to be lower than 2^31
+	pub index: u64,
+	/// Derivation type
+	#[serde(rename="type")]
+	pub derivation_type: DerivationType,
+}
+
+impl From<DeriveHash> for Derivation {
+	fn from(d: DeriveHash) -> Self {
+		match d.derivation_type {
+			DerivationType::Soft => Derivation::SoftHash(d.hash.into()),
+			DerivationType::Hard => Derivation::HardHash(d.hash.into()),
+		}
+	}
+}
+
+impl DeriveHierarchicalItem {
+	/// Converts to derivation step. Returns `None` if index is out of range.
+	pub fn to_derivation(&self) -> Option<Derivation> {
+		if self.index >= HARDENED_BIT as u64 {
+			return None;
+		}
+		let index = self.index as u32;
+		Some(match self.derivation_type {
+			DerivationType::Soft => Derivation::Soft(index),
+			DerivationType::Hard => Derivation::Hard(index),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use ethkey::Derivation;
+	use super::{DeriveHash, DeriveHierarchicalItem, DerivationType};
+
+	#[test]
+	fn should_deserialize_derivation() {
+		let s = r#"[{"index": 0, "type": "hard"}, {"index": 5, "type": "soft"}]"#;
+		let path: Vec<DeriveHierarchicalItem> = serde_json::from_str(s).unwrap();
+		assert_eq!(path[0].derivation_type, DerivationType::Hard);
+		assert_eq!(path.iter().map(|item| item.to_derivation().unwrap()).collect::<Vec<_>>(), vec![Derivation::Hard(0), Derivation::Soft(5)]);
+
+		let s = r#"{"hash": "0x0000000000000000000000000000000000000000000000000000000000000001", "type": "soft"}"#;
+		let hash: DeriveHash = serde_json::from_str(s).unwrap();
+		assert_eq!(Derivation::from(hash), Derivation::SoftHash(1.into()));
+	}
+
+	#[test]
+	fn should_reject_invalid_derivation() {
+		assert!(serde_json::from_str::<DeriveHierarchicalItem>(r#"{"index": 0, "type": "medium"}"#).is_err());
+		let item: DeriveHierarchicalItem = serde_json::from_str(r#"{"index": 2147483648, "type": "soft"}"#).unwrap();
+		assert_eq!(item.to_derivation(), None);
+	}
+}
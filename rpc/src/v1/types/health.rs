@@ -0,0 +1,29 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node health.
+
+/// Summary of the node's condition.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Health {
+	/// Number of active peers
+	pub peers: usize,
+	/// Is the node catching up with the chain?
+	pub syncing: bool,
+	/// Is transaction admission tightened because of repeated deep chain reorganisations?
+	#[serde(rename="txQueueThrottled")]
+	pub tx_queue_throttled: bool,
+}
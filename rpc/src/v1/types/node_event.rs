@@ -0,0 +1,40 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Node lifecycle event.
+
+use util::node_events::NodeEvent as JournalEvent;
+
+/// Entry of the node events journal.
+#[derive(Debug, Serialize)]
+pub struct NodeEvent {
+	/// Unix timestamp (seconds)
+	pub timestamp: u64,
+	/// Event kind, one of `start`, `shutdown`, `unclean-shutdown`, `database-repair` or `fatal`
+	pub kind: String,
+	/// Details (e.g. panic message and backtrace)
+	pub details: String,
+}
+
+impl From<JournalEvent> for NodeEvent {
+	fn from(e: JournalEvent) -> Self {
+		NodeEvent {
+			timestamp: e.timestamp,
+			kind: e.kind.to_string(),
+			details: e.details,
+		}
+	}
+}
@@ -0,0 +1,46 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Receipt of a private transaction.
+
+use ethcore::private_transactions::Receipt as EthPrivateReceipt;
+use v1::types::{Bytes, H160, H256};
+
+/// Receipt of a private transaction.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PrivateTransactionReceipt {
+	/// Hash of the private transaction
+	#[serde(rename="transactionHash")]
+	pub transaction_hash: H256,
+	/// Address of the private contract
+	#[serde(rename="contractAddress")]
+	pub contract_address: H160,
+	/// Output of the private execution
+	pub output: Bytes,
+	/// Whether the new state was committed without waiting for remote validators
+	pub committed: bool,
+}
+
+impl From<EthPrivateReceipt> for PrivateTransactionReceipt {
+	fn from(r: EthPrivateReceipt) -> Self {
+		PrivateTransactionReceipt {
+			transaction_hash: r.hash.into(),
+			contract_address: r.contract_address.into(),
+			output: r.output.into(),
+			committed: r.committed,
+		}
+	}
+}
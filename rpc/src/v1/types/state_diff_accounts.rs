@@ -0,0 +1,75 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Accounts compared by a state diff.
+
+use serde::{Deserialize, Deserializer, Error};
+use serde_json::value;
+use jsonrpc_core::Value;
+use v1::types::H160;
+
+/// Page of accounts in state trie order.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountsPage {
+	/// Maximal number of accounts
+	pub count: u64,
+	/// Account after which the page starts
+	pub after: Option<H160>,
+}
+
+/// Accounts compared by a state diff, either listed or a page of the state.
+#[derive(Debug, PartialEq, Clone)]
+pub enum StateDiffAccounts {
+	/// Listed accounts
+	Listed(Vec<H160>),
+	/// Page of accounts
+	Page(AccountsPage),
+}
+
+impl Deserialize for StateDiffAccounts {
+	fn deserialize<D>(deserializer: &mut D) -> Result<StateDiffAccounts, D::Error>
+	where D: Deserializer {
+		let v = try!(Value::deserialize(deserializer));
+
+		Deserialize::deserialize(&mut value::Deserializer::new(v.clone())).map(StateDiffAccounts::Listed)
+			.or_else(|_| Deserialize::deserialize(&mut value::Deserializer::new(v)).map(StateDiffAccounts::Page))
+			.map_err(|_| Error::custom("expected a list of addresses or a page with count and after"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{StateDiffAccounts, AccountsPage};
+
+	#[test]
+	fn test_deserialize_state_diff_accounts() {
+		let s = r#"["0x0000000000000000000000000000000000000001"]"#;
+		let deserialized: StateDiffAccounts = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized, StateDiffAccounts::Listed(vec![1.into()]));
+
+		let s = r#"{"count":10,"after":"0x0000000000000000000000000000000000000001"}"#;
+		let deserialized: StateDiffAccounts = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized, StateDiffAccounts::Page(AccountsPage { count: 10, after: Some(1.into()) }));
+
+		let s = r#"{"count":10}"#;
+		let deserialized: StateDiffAccounts = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized, StateDiffAccounts::Page(AccountsPage { count: 10, after: None }));
+
+		assert!(serde_json::from_str::<StateDiffAccounts>(r#"{"after":null}"#).is_err());
+	}
+}
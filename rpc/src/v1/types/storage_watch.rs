@@ -0,0 +1,79 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage watch types.
+
+use v1::helpers::StorageChange as WatcherChange;
+use v1::types::{H160, H256, U256};
+
+/// Storage slot to watch.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StorageSlot {
+	/// Contract address
+	pub address: H160,
+	/// Storage position
+	pub key: H256,
+}
+
+/// Change of a watched storage slot.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageChange {
+	/// Block Number
+	#[serde(rename="blockNumber")]
+	pub block_number: U256,
+	/// Block Hash
+	#[serde(rename="blockHash")]
+	pub block_hash: H256,
+	/// Contract address
+	pub address: H160,
+	/// Storage position
+	pub key: H256,
+	/// Value before the change
+	#[serde(rename="oldValue")]
+	pub old_value: H256,
+	/// Value after the change
+	#[serde(rename="newValue")]
+	pub new_value: H256,
+}
+
+impl From<WatcherChange> for StorageChange {
+	fn from(c: WatcherChange) -> Self {
+		StorageChange {
+			block_number: c.block_number.into(),
+			block_hash: c.block_hash.into(),
+			address: c.address.into(),
+			key: c.key.into(),
+			old_value: c.old_value.into(),
+			new_value: c.new_value.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::StorageSlot;
+
+	#[test]
+	fn storage_slot_deserialization() {
+		let s = r#"{"address":"0x0000000000000000000000000000000000000001","key":"0x0000000000000000000000000000000000000000000000000000000000000002"}"#;
+		let slot: StorageSlot = serde_json::from_str(s).unwrap();
+		assert_eq!(slot, StorageSlot {
+			address: 1u64.into(),
+			key: 2u64.into(),
+		});
+	}
+}
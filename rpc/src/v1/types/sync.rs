@@ -14,8 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use serde::{Serialize, Serializer};
-use v1::types::U256;
+use ethsync::{PeerInfo as SyncPeerInfo, NetworkTraffic};
+use v1::types::{U256, H256};
 
 /// Sync info
 #[derive(Default, Debug, Serialize, PartialEq)]
@@ -29,6 +31,29 @@ pub struct SyncInfo {
 	/// Highest block seen so far
 	#[serde(rename="highestBlock")]
 	pub highest_block: U256,
+	/// Number of snapshot chunks to download during warp sync
+	#[serde(rename="warpChunksAmount", skip_serializing_if="Option::is_none")]
+	pub warp_chunks_amount: Option<U256>,
+	/// Number of snapshot chunks downloaded so far
+	#[serde(rename="warpChunksProcessed", skip_serializing_if="Option::is_none")]
+	pub warp_chunks_processed: Option<U256>,
+	/// Number of state trie nodes downloaded so far
+	#[serde(rename="stateNodesDownloaded", skip_serializing_if="Option::is_none")]
+	pub state_nodes_downloaded: Option<U256>,
+	/// Number of state trie nodes requested but not yet written
+	#[serde(rename="stateNodesPending", skip_serializing_if="Option::is_none")]
+	pub state_nodes_pending: Option<U256>,
+	/// Estimated seconds until the current stage completes
+	#[serde(rename="estimatedTimeRemaining", skip_serializing_if="Option::is_none")]
+	pub estimated_time_remaining: Option<U256>,
+}
+
+/// Chain status
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct ChainStatus {
+	/// Range of blocks missing from the database, e.g. ancient blocks not yet downloaded after a warp sync
+	#[serde(rename="blockGap")]
+	pub block_gap: Option<(U256, U256)>,
 }
 
 /// Peers info
@@ -40,6 +65,94 @@ pub struct Peers {
 	pub connected: usize,
 	/// Max number of peers
 	pub max: u32,
+	/// Detailed information on peers
+	pub peers: Vec<PeerInfo>,
+}
+
+/// Peer connection information
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PeerInfo {
+	/// Public node id
+	pub id: Option<String>,
+	/// Node client ID
+	pub name: String,
+	/// Capabilities
+	pub caps: Vec<String>,
+	/// Network information
+	pub network: PeerNetworkInfo,
+	/// Protocols information
+	pub protocols: PeerProtocolsInfo,
+}
+
+/// Peer network information
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PeerNetworkInfo {
+	/// Remote endpoint address
+	#[serde(rename="remoteAddress")]
+	pub remote_address: String,
+	/// Local endpoint address
+	#[serde(rename="localAddress")]
+	pub local_address: String,
+	/// Ping delay in milliseconds
+	pub latency: Option<u64>,
+	/// Number of packets received from the peer
+	pub ingress: u64,
+	/// Number of packets sent to the peer
+	pub egress: u64,
+	/// Number of packet bytes received from the peer
+	#[serde(rename="ingressBytes")]
+	pub ingress_bytes: u64,
+	/// Number of packet bytes sent to the peer
+	#[serde(rename="egressBytes")]
+	pub egress_bytes: u64,
+}
+
+/// Network traffic statistics
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct NetStats {
+	/// Total number of bytes received
+	#[serde(rename="ingressBytes")]
+	pub ingress_bytes: u64,
+	/// Total number of bytes sent
+	#[serde(rename="egressBytes")]
+	pub egress_bytes: u64,
+	/// Bytes received per second
+	#[serde(rename="ingressRate")]
+	pub ingress_rate: u64,
+	/// Bytes sent per second
+	#[serde(rename="egressRate")]
+	pub egress_rate: u64,
+	/// Packet bytes transferred per subprotocol
+	pub protocols: BTreeMap<String, ProtocolTraffic>,
+}
+
+/// Packet bytes transferred over a subprotocol
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct ProtocolTraffic {
+	/// Number of packet bytes received
+	#[serde(rename="ingressBytes")]
+	pub ingress_bytes: u64,
+	/// Number of packet bytes sent
+	#[serde(rename="egressBytes")]
+	pub egress_bytes: u64,
+}
+
+/// Peer protocols information
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PeerProtocolsInfo {
+	/// Ethereum protocol information
+	pub eth: Option<PeerEthereumProtocolInfo>,
+}
+
+/// Peer Ethereum protocol information
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PeerEthereumProtocolInfo {
+	/// Negotiated ethereum protocol version
+	pub version: u32,
+	/// Peer total difficulty if known
+	pub difficulty: Option<U256>,
+	/// SHA3 of peer best block hash
+	pub head: H256,
 }
 
 /// Sync status
@@ -51,6 +164,49 @@ pub enum SyncStatus {
 	None
 }
 
+impl From<SyncPeerInfo> for PeerInfo {
+	fn from(p: SyncPeerInfo) -> PeerInfo {
+		PeerInfo {
+			id: p.id,
+			name: p.client_version,
+			caps: p.capabilities,
+			network: PeerNetworkInfo {
+				remote_address: p.remote_address,
+				local_address: p.local_address,
+				latency: p.ping_ms,
+				ingress: p.packets_received,
+				egress: p.packets_sent,
+				ingress_bytes: p.bytes_received,
+				egress_bytes: p.bytes_sent,
+			},
+			protocols: PeerProtocolsInfo {
+				eth: Some(PeerEthereumProtocolInfo {
+					version: p.eth_version,
+					difficulty: p.eth_difficulty.map(|d| d.into()),
+					head: p.eth_head.into(),
+				})
+			},
+		}
+	}
+}
+
+impl From<NetworkTraffic> for NetStats {
+	fn from(t: NetworkTraffic) -> NetStats {
+		NetStats {
+			ingress_bytes: t.bytes_received,
+			egress_bytes: t.bytes_sent,
+			ingress_rate: t.receive_rate,
+			egress_rate: t.send_rate,
+			protocols: t.protocols.into_iter()
+				.map(|p| (p.protocol, ProtocolTraffic {
+					ingress_bytes: p.bytes_received,
+					egress_bytes: p.bytes_sent,
+				}))
+				.collect(),
+		}
+	}
+}
+
 impl Serialize for SyncStatus {
 	fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
 	where S: Serializer {
@@ -64,20 +220,40 @@ impl Serialize for SyncStatus {
 #[cfg(test)]
 mod tests {
 	use serde_json;
-	use super::{SyncInfo, SyncStatus, Peers};
+	use super::{SyncInfo, SyncStatus, ChainStatus, Peers};
 
 	#[test]
 	fn test_serialize_sync_info() {
 		let t = SyncInfo::default();
 		let serialized = serde_json::to_string(&t).unwrap();
 		assert_eq!(serialized, r#"{"startingBlock":"0x0","currentBlock":"0x0","highestBlock":"0x0"}"#);
+
+		let t = SyncInfo {
+			warp_chunks_amount: Some(10.into()),
+			warp_chunks_processed: Some(4.into()),
+			estimated_time_remaining: Some(60.into()),
+			..Default::default()
+		};
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"startingBlock":"0x0","currentBlock":"0x0","highestBlock":"0x0","warpChunksAmount":"0xa","warpChunksProcessed":"0x4","estimatedTimeRemaining":"0x3c"}"#);
+	}
+
+	#[test]
+	fn test_serialize_chain_status() {
+		let t = ChainStatus::default();
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"blockGap":null}"#);
+
+		let t = ChainStatus { block_gap: Some((1.into(), 1000.into())) };
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"blockGap":["0x1","0x3e8"]}"#);
 	}
 
 	#[test]
 	fn test_serialize_peers() {
 		let t = Peers::default();
 		let serialized = serde_json::to_string(&t).unwrap();
-		assert_eq!(serialized, r#"{"active":0,"connected":0,"max":0}"#);
+		assert_eq!(serialized, r#"{"active":0,"connected":0,"max":0,"peers":[]}"#);
 	}
 
 	#[test]
@@ -553,6 +553,9 @@ pub struct TraceResults {
 	/// The transaction trace.
 	#[serde(rename="stateDiff")]
 	pub state_diff: Option<StateDiff>,
+	/// Executed instructions of each code, present only if requested.
+	#[serde(skip_serializing_if="Option::is_none")]
+	pub coverage: Option<Vec<CodeCoverage>>,
 }
 
 impl From<Executed> for TraceResults {
@@ -562,6 +565,29 @@ impl From<Executed> for TraceResults {
 			trace: t.trace.into_iter().map(Into::into).collect(),
 			vm_trace: t.vm_trace.map(Into::into),
 			state_diff: t.state_diff.map(Into::into),
+			coverage: None,
+		}
+	}
+}
+
+#[derive(Debug, Serialize)]
+/// Program counters of a single code reached during the call.
+pub struct CodeCoverage {
+	/// Hash of the code.
+	#[serde(rename="codeHash")]
+	pub code_hash: H256,
+	/// Program counters of executed instructions.
+	pub executed: Vec<usize>,
+	/// Program counters of instructions which were not executed.
+	pub missed: Vec<usize>,
+}
+
+impl From<et::CodeCoverage> for CodeCoverage {
+	fn from(c: et::CodeCoverage) -> Self {
+		CodeCoverage {
+			code_hash: c.code_hash.into(),
+			missed: c.instructions.difference(&c.executed).cloned().collect(),
+			executed: c.executed.into_iter().collect(),
 		}
 	}
 }
@@ -581,11 +607,29 @@ mod tests {
 			trace: vec![],
 			vm_trace: None,
 			state_diff: None,
+			coverage: None,
 		};
 		let serialized = serde_json::to_string(&r).unwrap();
 		assert_eq!(serialized, r#"{"output":"0x60","trace":[],"vmTrace":null,"stateDiff":null}"#);
 	}
 
+	#[test]
+	fn should_serialize_code_coverage() {
+		let r = TraceResults {
+			output: Bytes::new(vec![]),
+			trace: vec![],
+			vm_trace: None,
+			state_diff: None,
+			coverage: Some(vec![CodeCoverage {
+				code_hash: 1.into(),
+				executed: vec![0, 2],
+				missed: vec![4],
+			}]),
+		};
+		let serialized = serde_json::to_string(&r).unwrap();
+		assert_eq!(serialized, r#"{"output":"0x","trace":[],"vmTrace":null,"stateDiff":null,"coverage":[{"codeHash":"0x0000000000000000000000000000000000000000000000000000000000000001","executed":[0,2],"missed":[4]}]}"#);
+	}
+
 	#[test]
 	fn test_trace_call_serialize() {
 		let t = LocalizedTrace {
@@ -110,6 +110,28 @@ impl From<SignedTransaction> for Transaction {
 	}
 }
 
+/// Transaction waiting in the queue.
+#[derive(Debug, Serialize)]
+pub struct QueuedTransaction {
+	/// Position in the queue, the transaction at 0 is included first.
+	pub position: usize,
+	/// Transaction
+	pub transaction: Transaction,
+}
+
+impl QueuedTransaction {
+	/// Number queued transactions by their position.
+	pub fn from_queue(transactions: Vec<SignedTransaction>) -> Vec<Self> {
+		transactions.into_iter()
+			.enumerate()
+			.map(|(position, t)| QueuedTransaction {
+				position: position,
+				transaction: t.into(),
+			})
+			.collect()
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::Transaction;
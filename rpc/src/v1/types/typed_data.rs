@@ -0,0 +1,126 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Typed structured data to sign.
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer, Error};
+use serde::de::Visitor;
+use ethstore::typed_data;
+
+/// Single field of typed data, e.g. `{"type": "uint32", "name": "value", "value": 42}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TypedDataField {
+	/// Solidity type of the field
+	#[serde(rename="type")]
+	pub kind: String,
+	/// Name of the field
+	pub name: String,
+	/// Value of the field
+	pub value: TypedDataValue,
+}
+
+impl From<typed_data::TypedDataField> for TypedDataField {
+	fn from(field: typed_data::TypedDataField) -> Self {
+		TypedDataField {
+			kind: field.kind,
+			name: field.name,
+			value: TypedDataValue(field.value),
+		}
+	}
+}
+
+impl Into<typed_data::TypedDataField> for TypedDataField {
+	fn into(self) -> typed_data::TypedDataField {
+		typed_data::TypedDataField {
+			kind: self.kind,
+			name: self.name,
+			value: self.value.0,
+		}
+	}
+}
+
+/// Value of a typed data field. Accepts strings, numbers and booleans and is always
+/// serialized as a string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypedDataValue(pub String);
+
+impl Serialize for TypedDataValue {
+	fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+	where S: Serializer {
+		serializer.serialize_str(&self.0)
+	}
+}
+
+impl Deserialize for TypedDataValue {
+	fn deserialize<D>(deserializer: &mut D) -> Result<TypedDataValue, D::Error>
+	where D: Deserializer {
+		deserializer.deserialize(TypedDataValueVisitor)
+	}
+}
+
+struct TypedDataValueVisitor;
+
+impl Visitor for TypedDataValueVisitor {
+	type Value = TypedDataValue;
+
+	fn visit_bool<E>(&mut self, value: bool) -> Result<Self::Value, E> where E: Error {
+		Ok(TypedDataValue(value.to_string()))
+	}
+
+	fn visit_i64<E>(&mut self, value: i64) -> Result<Self::Value, E> where E: Error {
+		Ok(TypedDataValue(value.to_string()))
+	}
+
+	fn visit_u64<E>(&mut self, value: u64) -> Result<Self::Value, E> where E: Error {
+		Ok(TypedDataValue(value.to_string()))
+	}
+
+	fn visit_str<E>(&mut self, value: &str) -> Result<Self::Value, E> where E: Error {
+		Ok(TypedDataValue(value.to_owned()))
+	}
+
+	fn visit_string<E>(&mut self, value: String) -> Result<Self::Value, E> where E: Error {
+		Ok(TypedDataValue(value))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{TypedDataField, TypedDataValue};
+
+	#[test]
+	fn should_deserialize_typed_data() {
+		let s = r#"[
+			{"type": "string", "name": "message", "value": "Hi, Alice!"},
+			{"type": "uint32", "name": "value", "value": 42},
+			{"type": "bool", "name": "urgent", "value": true}
+		]"#;
+		let deserialized: Vec<TypedDataField> = serde_json::from_str(s).unwrap();
+
+		assert_eq!(deserialized, vec![
+			TypedDataField { kind: "string".into(), name: "message".into(), value: TypedDataValue("Hi, Alice!".into()) },
+			TypedDataField { kind: "uint32".into(), name: "value".into(), value: TypedDataValue("42".into()) },
+			TypedDataField { kind: "bool".into(), name: "urgent".into(), value: TypedDataValue("true".into()) },
+		]);
+	}
+
+	#[test]
+	fn should_serialize_values_as_strings() {
+		let field = TypedDataField { kind: "uint32".into(), name: "value".into(), value: TypedDataValue("42".into()) };
+		assert_eq!(serde_json::to_string(&field).unwrap(), r#"{"type":"uint32","name":"value","value":"42"}"#);
+	}
+}
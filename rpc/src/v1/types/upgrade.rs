@@ -0,0 +1,44 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity. If not, see <http://www.gnu.org/licenses/>.
+
+//! Release upgrade.
+
+use updater::Upgrade as UpdaterUpgrade;
+use v1::types::H256;
+
+/// Release newer than the running node.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Upgrade {
+	/// Released version
+	pub version: String,
+	/// Does the release contain critical fixes?
+	pub critical: bool,
+	/// Hash of the released binary
+	pub binary: H256,
+	/// Has the binary been installed? If so, it will be used after a restart.
+	pub installed: bool,
+}
+
+impl From<UpdaterUpgrade> for Upgrade {
+	fn from(u: UpdaterUpgrade) -> Self {
+		Upgrade {
+			version: u.release.version.to_string(),
+			critical: u.release.critical,
+			binary: u.release.binary_hash.into(),
+			installed: u.installed,
+		}
+	}
+}
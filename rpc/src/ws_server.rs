@@ -0,0 +1,300 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! JSON-RPC over `WebSockets`.
+
+use std::{io, thread};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use serde_json;
+use jsonrpc_core::{IoHandler, Request, Call, Params, Response, Output, Success, Failure, Value, Version, from_params};
+use io::PanicHandler;
+use util::Mutex;
+use limits::{RequestLimits, RateLimiter};
+use v1::StorageWatcher;
+use v1::types::Index;
+use ws;
+
+/// `WebSockets`-only method subscribing the connection to changes of a storage watch.
+const SUBSCRIBE_STORAGE_WATCH: &'static str = "ethcore_subscribeStorageWatch";
+
+/// `WebSockets` server startup error.
+#[derive(Debug)]
+pub enum WsServerError {
+	/// Wrapped `std::io::Error`
+	IoError(io::Error),
+	/// Other `ws-rs` error
+	WebSocket(ws::Error),
+}
+
+impl From<ws::Error> for WsServerError {
+	fn from(err: ws::Error) -> Self {
+		match err.kind {
+			ws::ErrorKind::Io(e) => WsServerError::IoError(e),
+			_ => WsServerError::WebSocket(err),
+		}
+	}
+}
+
+impl From<io::Error> for WsServerError {
+	fn from(err: io::Error) -> Self {
+		WsServerError::IoError(err)
+	}
+}
+
+/// Checks the `Origin` header of a connection request.
+/// Requests without the header do not come from a browser and are always accepted.
+fn origin_is_allowed(allowed_origins: &Option<Vec<String>>, origin: Option<&[u8]>) -> bool {
+	match (allowed_origins.as_ref(), origin) {
+		(None, _) | (_, None) => true,
+		(Some(allowed), Some(origin)) => allowed.iter().any(|o| o == "*" || o.as_bytes() == origin),
+	}
+}
+
+struct Session {
+	id: usize,
+	out: Arc<Mutex<ws::Sender>>,
+	handler: Arc<IoHandler>,
+	allowed_origins: Arc<Option<Vec<String>>>,
+	limits: Arc<RequestLimits>,
+	rate_limiter: RateLimiter,
+	storage_watcher: Option<Arc<StorageWatcher>>,
+}
+
+impl Session {
+	/// Handles `ethcore_subscribeStorageWatch` calls, which need to know the connection.
+	/// Returns `None` for any other request.
+	fn handle_subscription(&self, req: &str) -> Option<String> {
+		let watcher = match self.storage_watcher {
+			Some(ref watcher) if req.contains(SUBSCRIBE_STORAGE_WATCH) => watcher,
+			_ => return None,
+		};
+		let request = serde_json::from_str::<Request>(req);
+		let call = match request {
+			Ok(Request::Single(Call::MethodCall(ref call))) if call.method == SUBSCRIBE_STORAGE_WATCH => call,
+			_ => return None,
+		};
+
+		let result = from_params::<(Index,)>(call.params.clone().unwrap_or(Params::None)).map(|(index,)| {
+			let out = self.out.clone();
+			watcher.subscribe(index.value(), self.id, move |notification| out.lock().send(notification).is_ok())
+		});
+		let output = match result {
+			Ok(subscribed) => Output::Success(Success {
+				jsonrpc: Version::V2,
+				result: Value::Bool(subscribed),
+				id: call.id.clone(),
+			}),
+			Err(error) => Output::Failure(Failure {
+				jsonrpc: Version::V2,
+				error: error,
+				id: call.id.clone(),
+			}),
+		};
+		serde_json::to_string(&Response::Single(output)).ok()
+	}
+}
+
+impl Drop for Session {
+	fn drop(&mut self) {
+		if let Some(ref watcher) = self.storage_watcher {
+			watcher.remove_session(self.id);
+		}
+	}
+}
+
+impl ws::Handler for Session {
+	fn on_request(&mut self, req: &ws::Request) -> ws::Result<ws::Response> {
+		let origin = req.header("origin").or_else(|| req.header("Origin")).map(|x| &x[..]);
+		if !origin_is_allowed(&self.allowed_origins, origin) {
+			warn!(target: "rpc", "Blocked WebSockets connection from untrusted origin.");
+			return Ok(ws::Response::forbidden("Origin not allowed.".into()));
+		}
+
+		ws::Response::from_request(req)
+	}
+
+	fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+		let req = try!(msg.as_text());
+		if let Err(response) = self.rate_limiter.check().and_then(|_| self.limits.check(req)) {
+			debug!(target: "rpc", "Rejected WebSockets request: {}", response);
+			return self.out.lock().send(response);
+		}
+
+		if let Some(response) = self.handle_subscription(req) {
+			return self.out.lock().send(response);
+		}
+
+		if let Some(async) = self.handler.handle_request(req) {
+			let out = self.out.clone();
+			async.on_result(move |result| {
+				if let Err(e) = out.lock().send(result) {
+					warn!(target: "rpc", "Error while sending WebSockets response: {:?}", e);
+				}
+			});
+		}
+		Ok(())
+	}
+}
+
+struct Factory {
+	handler: Arc<IoHandler>,
+	allowed_origins: Arc<Option<Vec<String>>>,
+	limits: Arc<RequestLimits>,
+	storage_watcher: Option<Arc<StorageWatcher>>,
+	next_session: usize,
+}
+
+impl ws::Factory for Factory {
+	type Handler = Session;
+
+	fn connection_made(&mut self, sender: ws::Sender) -> Session {
+		self.next_session += 1;
+		Session {
+			id: self.next_session,
+			out: Arc::new(Mutex::new(sender)),
+			handler: self.handler.clone(),
+			allowed_origins: self.allowed_origins.clone(),
+			limits: self.limits.clone(),
+			rate_limiter: self.limits.rate_limiter(),
+			storage_watcher: self.storage_watcher.clone(),
+		}
+	}
+}
+
+/// `WebSockets` server handle. Connections stay open until either side closes them,
+/// the server is shut down when the handle is dropped.
+pub struct WsServer {
+	addr: SocketAddr,
+	broadcaster: ws::Sender,
+	handle: Option<thread::JoinHandle<()>>,
+}
+
+impl WsServer {
+	/// Binds to `addr` and serves connections in a separate thread.
+	/// Connections may subscribe to watches of `storage_watcher` with `ethcore_subscribeStorageWatch`.
+	pub fn start(
+		addr: &SocketAddr,
+		handler: Arc<IoHandler>,
+		allowed_origins: Option<Vec<String>>,
+		limits: RequestLimits,
+		panic_handler: Arc<PanicHandler>,
+		storage_watcher: Option<Arc<StorageWatcher>>,
+	) -> Result<WsServer, WsServerError> {
+		let config = {
+			let mut config = ws::Settings::default();
+			config.method_strict = true;
+			config.shutdown_on_interrupt = false;
+			config
+		};
+
+		let ws = try!(ws::Builder::new().with_settings(config).build(Factory {
+			handler: handler,
+			allowed_origins: Arc::new(allowed_origins),
+			limits: Arc::new(limits),
+			storage_watcher: storage_watcher,
+			next_session: 0,
+		}));
+		let broadcaster = ws.broadcaster();
+
+		// bind before spawning so that listen errors are reported to the caller.
+		let ws = try!(ws.bind(addr));
+		let local_addr = try!(ws.local_addr());
+		let handle = thread::spawn(move || {
+			if let Err(e) = ws.run() {
+				panic_handler.notify_all(format!("WebSockets RPC server on {} stopped: {:?}", local_addr, WsServerError::from(e)));
+			}
+		});
+
+		Ok(WsServer {
+			addr: local_addr,
+			broadcaster: broadcaster,
+			handle: Some(handle),
+		})
+	}
+
+	/// Returns the address this server is listening on.
+	pub fn addr(&self) -> &SocketAddr {
+		&self.addr
+	}
+}
+
+impl Drop for WsServer {
+	fn drop(&mut self) {
+		if let Err(e) = self.broadcaster.shutdown() {
+			warn!(target: "rpc", "WebSockets server was not closed cleanly: {:?}", e);
+		}
+		if let Some(handle) = self.handle.take() {
+			let _ = handle.join();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::sync::mpsc;
+	use jsonrpc_core::{IoHandler, Params, Value};
+	use io::PanicHandler;
+	use limits::RequestLimits;
+	use ws;
+	use super::{origin_is_allowed, WsServer};
+
+	fn serve() -> WsServer {
+		let io = IoHandler::new();
+		io.add_method("hello", |_params: Params| Ok(Value::String("world".into())));
+		WsServer::start(&"127.0.0.1:0".parse().unwrap(), Arc::new(io), None, RequestLimits::default(), PanicHandler::new_in_arc(), None).unwrap()
+	}
+
+	#[test]
+	fn should_respond_to_requests() {
+		let server = serve();
+		let (tx, rx) = mpsc::channel();
+
+		ws::connect(format!("ws://{}", server.addr()), |out| {
+			out.send(r#"{"jsonrpc":"2.0","method":"hello","id":1}"#).unwrap();
+			let tx = tx.clone();
+			move |msg: ws::Message| {
+				tx.send(try!(msg.into_text())).unwrap();
+				out.close(ws::CloseCode::Normal)
+			}
+		}).unwrap();
+
+		assert_eq!(rx.recv().unwrap(), r#"{"jsonrpc":"2.0","result":"world","id":1}"#);
+	}
+
+	#[test]
+	fn should_fail_to_start_on_address_in_use() {
+		let server = serve();
+		let io = Arc::new(IoHandler::new());
+		assert!(WsServer::start(server.addr(), io, None, RequestLimits::default(), PanicHandler::new_in_arc(), None).is_err());
+	}
+
+	#[test]
+	fn should_validate_origin() {
+		let any = None;
+		let none = Some(vec![]);
+		let some = Some(vec!["http://localhost:3000".to_owned()]);
+		let wildcard = Some(vec!["*".to_owned()]);
+
+		assert!(origin_is_allowed(&any, Some(&b"http://evil.com"[..])));
+		assert!(origin_is_allowed(&none, None));
+		assert!(!origin_is_allowed(&none, Some(&b"http://localhost:3000"[..])));
+		assert!(origin_is_allowed(&some, Some(&b"http://localhost:3000"[..])));
+		assert!(!origin_is_allowed(&some, Some(&b"http://evil.com"[..])));
+		assert!(origin_is_allowed(&wildcard, Some(&b"http://evil.com"[..])));
+	}
+}
@@ -7,6 +7,7 @@ export TARGETS="
 	-p ethcore-bigint\
 	-p ethcore-dapps \
 	-p ethcore-rpc \
+	-p ethcore-secretstore \
 	-p ethcore-signer \
 	-p ethcore-util \
 	-p ethcore-network \
@@ -17,4 +18,5 @@ export TARGETS="
 	-p ethcore-ipc \
 	-p ethcore-ipc-tests \
 	-p ethcore-ipc-nano \
+	-p parity-updater \
 	-p parity"
@@ -0,0 +1,125 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use ethabi::{Interface, Contract, Token};
+use ethkey::{Public, public_to_address};
+use util::{Address, Bytes, Hashable};
+use types::{Error, DocumentAddress};
+
+/// Name of the permissioning contract in the registrar.
+const ACL_CHECKER_CONTRACT_REGISTRY_NAME: &'static str = "secretstore_acl_checker";
+
+const ACL_CHECKER_ABI: &'static [u8] = br#"[{"constant":true,"inputs":[{"name":"user","type":"address"},{"name":"document","type":"bytes32"}],"name":"checkPermissions","outputs":[{"name":"","type":"bool"}],"type":"function"}]"#;
+
+/// ACL storage of Secret Store.
+pub trait AclStorage: Send + Sync {
+	/// Checks if requester with given public has access to given document.
+	fn check(&self, public: &Public, document: &DocumentAddress) -> Result<bool, Error>;
+}
+
+/// RAW Contract interface.
+/// Should execute transaction using current blockchain state.
+pub trait ContractClient: Send + Sync {
+	/// Get registrar address
+	fn registrar(&self) -> Result<Address, String>;
+	/// Call Contract
+	fn call(&self, address: Address, data: Bytes) -> Result<Bytes, String>;
+}
+
+/// On-chain ACL storage implementation.
+pub struct OnChainAclStorage {
+	registrar: Contract,
+	acl_checker: Contract,
+	client: Arc<ContractClient>,
+}
+
+impl OnChainAclStorage {
+	pub fn new(client: Arc<ContractClient>) -> Self {
+		let registrar = Interface::load(include_bytes!("./registrar.json")).expect("registrar.json is valid ABI");
+		let acl_checker = Interface::load(ACL_CHECKER_ABI).expect("ACL checker ABI is valid");
+
+		OnChainAclStorage {
+			registrar: Contract::new(registrar),
+			acl_checker: Contract::new(acl_checker),
+			client: client,
+		}
+	}
+
+	fn acl_checker_address(&self) -> Result<Address, Error> {
+		let get_address = try!(self.registrar.function("getAddress".into()).map_err(as_internal));
+		let params = try!(get_address.encode_call(
+			vec![Token::FixedBytes((*ACL_CHECKER_CONTRACT_REGISTRY_NAME.sha3()).to_vec()), Token::String("A".into())]
+		).map_err(as_internal));
+		let registrar = try!(self.client.registrar().map_err(Error::Internal));
+		let output = try!(self.client.call(registrar, params).map_err(Error::Internal));
+		let result = try!(get_address.decode_output(output).map_err(as_internal));
+
+		match result.get(0) {
+			Some(&Token::Address(address)) if address != *Address::default() => Ok(address.into()),
+			Some(&Token::Address(_)) => Err(Error::Internal("ACL checker contract is not registered".into())),
+			e => Err(Error::Internal(format!("Invalid registrar result: {:?}", e))),
+		}
+	}
+}
+
+impl AclStorage for OnChainAclStorage {
+	fn check(&self, public: &Public, document: &DocumentAddress) -> Result<bool, Error> {
+		let address = try!(self.acl_checker_address());
+		let check_permissions = try!(self.acl_checker.function("checkPermissions".into()).map_err(as_internal));
+		let params = try!(check_permissions.encode_call(
+			vec![Token::Address(public_to_address(public).0), Token::FixedBytes(document.to_vec())]
+		).map_err(as_internal));
+		let output = try!(self.client.call(address, params).map_err(Error::Internal));
+		let result = try!(check_permissions.decode_output(output).map_err(as_internal));
+
+		match result.get(0) {
+			Some(&Token::Bool(allowed)) => Ok(allowed),
+			e => Err(Error::Internal(format!("Invalid ACL checker result: {:?}", e))),
+		}
+	}
+}
+
+fn as_internal<E: ::std::fmt::Debug>(e: E) -> Error {
+	Error::Internal(format!("{:?}", e))
+}
+
+#[cfg(test)]
+pub mod tests {
+	use std::collections::HashSet;
+	use util::RwLock;
+	use ethkey::Public;
+	use types::{Error, DocumentAddress};
+	use super::AclStorage;
+
+	/// Dummy ACL storage implementation.
+	#[derive(Default)]
+	pub struct DummyAclStorage {
+		prohibited: RwLock<HashSet<(Public, DocumentAddress)>>,
+	}
+
+	impl DummyAclStorage {
+		pub fn prohibit(&self, public: Public, document: DocumentAddress) {
+			self.prohibited.write().insert((public, document));
+		}
+	}
+
+	impl AclStorage for DummyAclStorage {
+		fn check(&self, public: &Public, document: &DocumentAddress) -> Result<bool, Error> {
+			Ok(!self.prohibited.read().contains(&(public.clone(), document.clone())))
+		}
+	}
+}
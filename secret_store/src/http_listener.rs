@@ -0,0 +1,214 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use hyper::server::{Server as HttpServer, Handler as HttpHandler, Request as HttpRequest, Response as HttpResponse, Listening};
+use hyper::method::Method as HttpMethod;
+use hyper::status::StatusCode as HttpStatusCode;
+use hyper::uri::RequestUri;
+use rustc_serialize::hex::{ToHex, FromHex};
+use traits::KeyServer;
+use types::{Error, NodeAddress, DocumentAddress, DocumentEncryptedKey, RequestSignature};
+
+/// Key server http-requests listener.
+/// Available requests:
+/// To generate document key: POST /document/signature/threshold
+/// To get document key: GET /document/signature
+pub struct KeyServerHttpListener<T: KeyServer + 'static> {
+	http_server: Option<Listening>,
+	handler: Arc<KeyServerSharedHttpHandler<T>>,
+}
+
+/// Parsed http request.
+#[derive(Debug, Clone, PartialEq)]
+enum Request {
+	/// Invalid request.
+	Invalid,
+	/// Generate document key.
+	GenerateDocumentKey(DocumentAddress, RequestSignature, usize),
+	/// Request document key.
+	GetDocumentKey(DocumentAddress, RequestSignature),
+}
+
+/// Http handler.
+struct KeyServerHttpHandler<T: KeyServer + 'static> {
+	handler: Arc<KeyServerSharedHttpHandler<T>>,
+}
+
+/// Shared http handler.
+struct KeyServerSharedHttpHandler<T: KeyServer + 'static> {
+	key_server: T,
+}
+
+impl<T> KeyServerHttpListener<T> where T: KeyServer + 'static {
+	/// Start KeyServer http listener.
+	pub fn start(listener_address: &NodeAddress, key_server: T) -> Result<Self, Error> {
+		let shared_handler = Arc::new(KeyServerSharedHttpHandler {
+			key_server: key_server,
+		});
+		let handler = KeyServerHttpHandler {
+			handler: shared_handler.clone(),
+		};
+
+		let listener_address = format!("{}:{}", listener_address.address, listener_address.port);
+		let http_server = try!(HttpServer::http(&listener_address).map_err(|e| Error::Internal(format!("{:?}", e))));
+		let http_server = try!(http_server.handle(handler).map_err(|e| Error::Internal(format!("{:?}", e))));
+
+		Ok(KeyServerHttpListener {
+			http_server: Some(http_server),
+			handler: shared_handler,
+		})
+	}
+}
+
+impl<T> KeyServer for KeyServerHttpListener<T> where T: KeyServer + 'static {
+	fn generate_document_key(&self, signature: &RequestSignature, document: &DocumentAddress, threshold: usize) -> Result<DocumentEncryptedKey, Error> {
+		self.handler.key_server.generate_document_key(signature, document, threshold)
+	}
+
+	fn document_key(&self, signature: &RequestSignature, document: &DocumentAddress) -> Result<DocumentEncryptedKey, Error> {
+		self.handler.key_server.document_key(signature, document)
+	}
+}
+
+impl<T> Drop for KeyServerHttpListener<T> where T: KeyServer + 'static {
+	fn drop(&mut self) {
+		// ignore error as we are dropping anyway
+		self.http_server.take().map(|mut s| { let _ = s.close(); });
+	}
+}
+
+impl<T> HttpHandler for KeyServerHttpHandler<T> where T: KeyServer + 'static {
+	fn handle(&self, req: HttpRequest, mut res: HttpResponse) {
+		let req_uri = match req.uri {
+			RequestUri::AbsolutePath(ref path) => path.clone(),
+			_ => {
+				*res.status_mut() = HttpStatusCode::NotFound;
+				return;
+			},
+		};
+
+		let result = match parse_request(&req.method, &req_uri) {
+			Request::GenerateDocumentKey(document, signature, threshold) =>
+				self.handler.key_server.generate_document_key(&signature, &document, threshold),
+			Request::GetDocumentKey(document, signature) =>
+				self.handler.key_server.document_key(&signature, &document),
+			Request::Invalid => {
+				warn!(target: "secretstore", "Ignoring invalid {}-request {}", req.method, redact_signature(&req_uri));
+				*res.status_mut() = HttpStatusCode::BadRequest;
+				return;
+			},
+		};
+
+		match result {
+			Ok(document_key) => {
+				let body = format!("\"0x{}\"", document_key.to_hex());
+				if let Err(err) = res.send(body.as_bytes()) {
+					warn!(target: "secretstore", "Error writing response: {}", err);
+				}
+			},
+			Err(err) => {
+				warn!(target: "secretstore", "{}-request {} has failed with: {}", req.method, redact_signature(&req_uri), err);
+				*res.status_mut() = match err {
+					Error::BadSignature => HttpStatusCode::BadRequest,
+					Error::AccessDenied => HttpStatusCode::Forbidden,
+					Error::DocumentNotFound => HttpStatusCode::NotFound,
+					Error::DocumentAlreadyStored | Error::InvalidThreshold => HttpStatusCode::BadRequest,
+					Error::Cluster(_) | Error::Database(_) | Error::Internal(_) => HttpStatusCode::InternalServerError,
+				};
+			},
+		}
+	}
+}
+
+fn parse_request(method: &HttpMethod, uri_path: &str) -> Request {
+	let path: Vec<&str> = uri_path.trim_matches('/').split('/').collect();
+	let (document, signature) = match (path.get(0).and_then(|p| parse_hex(p)), path.get(1).and_then(|p| parse_hex(p))) {
+		(Some(ref document), Some(ref signature)) if document.len() == 32 && signature.len() == 65 => {
+			let mut signature_bytes = [0u8; 65];
+			signature_bytes.copy_from_slice(signature);
+			(DocumentAddress::from_slice(document), RequestSignature::from(signature_bytes))
+		},
+		_ => return Request::Invalid,
+	};
+
+	match (method, path.len()) {
+		(&HttpMethod::Post, 3) => match usize::from_str(path[2]) {
+			Ok(threshold) => Request::GenerateDocumentKey(document, signature, threshold),
+			Err(_) => Request::Invalid,
+		},
+		(&HttpMethod::Get, 2) => Request::GetDocumentKey(document, signature),
+		_ => Request::Invalid,
+	}
+}
+
+/// Replaces requester signature in the request path, so that the path can be logged.
+fn redact_signature(uri_path: &str) -> String {
+	uri_path.trim_matches('/').split('/').enumerate()
+		.map(|(i, part)| if i == 1 { "<signature>" } else { part })
+		.fold(String::new(), |mut path, part| {
+			path.push('/');
+			path.push_str(part);
+			path
+		})
+}
+
+fn parse_hex(value: &str) -> Option<Vec<u8>> {
+	let value = if value.starts_with("0x") { &value[2..] } else { value };
+	value.from_hex().ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use hyper::method::Method as HttpMethod;
+	use types::{DocumentAddress, RequestSignature};
+	use super::{parse_request, redact_signature, Request};
+
+	#[test]
+	fn parse_request_successful() {
+		let document = "0x0000000000000000000000000000000000000000000000000000000000000001";
+		let signature = "0xa199fb39e11eefb61c78a4074a53c0d4424600a3e74aad4fb9d93a26c30d067e1d4d29936de0c73f19827394a1dd049480a0d581aee7ae7546968da7d3d1c2fd01";
+
+		assert_eq!(parse_request(&HttpMethod::Get, &format!("/{}/{}", document, signature)),
+			Request::GetDocumentKey(DocumentAddress::from(1), signature[2..].parse::<RequestSignature>().unwrap()));
+		assert_eq!(parse_request(&HttpMethod::Post, &format!("/{}/{}/2", document, signature)),
+			Request::GenerateDocumentKey(DocumentAddress::from(1), signature[2..].parse::<RequestSignature>().unwrap(), 2));
+	}
+
+	#[test]
+	fn parse_request_failed() {
+		let document = "0x0000000000000000000000000000000000000000000000000000000000000001";
+		let signature = "0xa199fb39e11eefb61c78a4074a53c0d4424600a3e74aad4fb9d93a26c30d067e1d4d29936de0c73f19827394a1dd049480a0d581aee7ae7546968da7d3d1c2fd01";
+
+		assert_eq!(parse_request(&HttpMethod::Get, ""), Request::Invalid);
+		assert_eq!(parse_request(&HttpMethod::Get, &format!("/{}", document)), Request::Invalid);
+		assert_eq!(parse_request(&HttpMethod::Get, &format!("/{}/{}/2", document, signature)), Request::Invalid);
+		assert_eq!(parse_request(&HttpMethod::Post, &format!("/{}/{}", document, signature)), Request::Invalid);
+		assert_eq!(parse_request(&HttpMethod::Post, &format!("/{}/{}/x", document, signature)), Request::Invalid);
+		assert_eq!(parse_request(&HttpMethod::Get, &format!("/0x01/{}", signature)), Request::Invalid);
+	}
+
+	#[test]
+	fn signature_is_redacted() {
+		let document = "0x0000000000000000000000000000000000000000000000000000000000000001";
+		let signature = "0xa199fb39e11eefb61c78a4074a53c0d4424600a3e74aad4fb9d93a26c30d067e1d4d29936de0c73f19827394a1dd049480a0d581aee7ae7546968da7d3d1c2fd01";
+
+		assert_eq!(redact_signature(&format!("/{}/{}/2", document, signature)), format!("/{}/<signature>/2", document));
+		assert_eq!(redact_signature(&format!("/{}/{}", document, signature)), format!("/{}/<signature>", document));
+		assert_eq!(redact_signature(&format!("/{}", document)), format!("/{}", document));
+	}
+}
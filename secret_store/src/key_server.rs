@@ -0,0 +1,529 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use ethkey::{Public, Secret, recover};
+use util::Bytes;
+use ethcrypto::ecies;
+use acl_storage::AclStorage;
+use key_storage::{KeyStorage, DocumentKeyShare};
+use key_server_cluster::{math, Cluster, MessageHandler, Message, NetCluster, NetClusterListener};
+use traits::KeyServer;
+use types::{Error, ClusterConfiguration, DocumentAddress, DocumentKey, DocumentEncryptedKey, RequestSignature, NodeId};
+
+/// Secret store key server implementation.
+pub struct KeyServerImpl {
+	core: Arc<KeyServerCore>,
+	_listener: NetClusterListener,
+}
+
+/// Key server logic, shared between the HTTP listener and the cluster listener.
+pub struct KeyServerCore {
+	cluster: Arc<Cluster>,
+	acl_storage: Arc<AclStorage>,
+	key_storage: Arc<KeyStorage>,
+}
+
+impl KeyServerImpl {
+	/// Create new key server and start listening for other cluster nodes.
+	pub fn new(config: &ClusterConfiguration, acl_storage: Arc<AclStorage>, key_storage: Arc<KeyStorage>) -> Result<Self, Error> {
+		let cluster = Arc::new(try!(NetCluster::new(config)));
+		let core = Arc::new(KeyServerCore::new(cluster, acl_storage, key_storage));
+		let listener = try!(NetClusterListener::start(config, core.clone()));
+
+		Ok(KeyServerImpl {
+			core: core,
+			_listener: listener,
+		})
+	}
+}
+
+impl KeyServer for KeyServerImpl {
+	fn generate_document_key(&self, signature: &RequestSignature, document: &DocumentAddress, threshold: usize) -> Result<DocumentEncryptedKey, Error> {
+		self.core.generate_document_key(signature, document, threshold)
+	}
+
+	fn document_key(&self, signature: &RequestSignature, document: &DocumentAddress) -> Result<DocumentEncryptedKey, Error> {
+		self.core.document_key(signature, document)
+	}
+}
+
+impl KeyServerCore {
+	pub fn new(cluster: Arc<Cluster>, acl_storage: Arc<AclStorage>, key_storage: Arc<KeyStorage>) -> Self {
+		KeyServerCore {
+			cluster: cluster,
+			acl_storage: acl_storage,
+			key_storage: key_storage,
+		}
+	}
+
+	/// Recovers requester public from signature and checks that it has access to the document.
+	fn check_access(&self, signature: &RequestSignature, document: &DocumentAddress) -> Result<Public, Error> {
+		let requester = try!(recover(signature, document).map_err(|_| Error::BadSignature));
+		match try!(self.acl_storage.check(&requester, document)) {
+			true => Ok(requester),
+			false => Err(Error::AccessDenied),
+		}
+	}
+
+	/// Generates random polynom and encrypts its value at every node number with the node public.
+	/// Only the public of the polynom free term is revealed.
+	fn generate_key_shares(&self, signature: &RequestSignature, document: &DocumentAddress, threshold: usize, nodes: &[NodeId]) -> Result<(Public, Vec<Bytes>), Error> {
+		try!(self.check_access(signature, document));
+		if threshold + 1 > nodes.len() {
+			return Err(Error::InvalidThreshold);
+		}
+		if !nodes.contains(self.cluster.node_id()) {
+			return Err(Error::Cluster("This node is not a key share holder".into()));
+		}
+
+		let polynom = try!(math::generate_random_polynom(threshold));
+		let mut shares = Vec::with_capacity(nodes.len());
+		for node in nodes {
+			let value = try!(math::compute_polynom(&polynom, &math::compute_node_number(node)));
+			shares.push(try!(ecies::encrypt(node, &[0; 0], &value)));
+		}
+		Ok((try!(math::compute_public(&polynom[0])), shares))
+	}
+
+	/// Stores key share of this node: sum of values of all nodes polynoms at its number.
+	fn store_key_share(&self, signature: &RequestSignature, document: &DocumentAddress, threshold: usize, nodes: Vec<NodeId>, shares: &[Bytes], common_point: Public, encrypted_point: Public) -> Result<(), Error> {
+		try!(self.check_access(signature, document));
+		if self.key_storage.contains(document) {
+			return Err(Error::DocumentAlreadyStored);
+		}
+		if threshold + 1 > nodes.len() || shares.len() != nodes.len() || !nodes.contains(self.cluster.node_id()) {
+			return Err(Error::Cluster("Invalid key share".into()));
+		}
+
+		let mut values = Vec::with_capacity(shares.len());
+		for share in shares {
+			let value = try!(self.cluster.decrypt(share));
+			if value.len() != 32 {
+				return Err(Error::Cluster("Invalid key share".into()));
+			}
+			values.push(Secret::from_slice(&value));
+		}
+
+		self.key_storage.insert(document.clone(), DocumentKeyShare {
+			threshold: threshold,
+			id_numbers: nodes.iter().map(|node| (node.clone(), math::compute_node_number(node))).collect(),
+			secret_share: try!(math::compute_secret_sum(values.iter())),
+			common_point: common_point,
+			encrypted_point: encrypted_point,
+		})
+	}
+
+	/// Removes key share, stored during failed key generation.
+	fn remove_key_share(&self, from: &NodeId, signature: &RequestSignature, document: &DocumentAddress) -> Result<(), Error> {
+		try!(self.check_access(signature, document));
+		let share = try!(self.key_storage.get(document));
+		if !share.id_numbers.contains_key(from) {
+			return Err(Error::AccessDenied);
+		}
+		self.key_storage.remove(document)
+	}
+
+	fn shadow_point(&self, from: &NodeId, signature: &RequestSignature, document: &DocumentAddress, participants: &[NodeId]) -> Result<Public, Error> {
+		try!(self.check_access(signature, document));
+		if !participants.contains(from) || !participants.contains(self.cluster.node_id()) {
+			return Err(Error::Cluster("Invalid decryption participants".into()));
+		}
+
+		let share = try!(self.key_storage.get(document));
+		compute_shadow_point(&share, self.cluster.node_id(), participants)
+	}
+
+	/// Sends request to other node, or processes it locally if `node` is this node.
+	fn request(&self, node: &NodeId, message: Message) -> Result<Message, Error> {
+		match node == self.cluster.node_id() {
+			true => Ok(self.process(node, message)),
+			false => self.cluster.send(node, message),
+		}
+	}
+
+	/// Removes key shares stored on given nodes during failed key generation.
+	fn rollback_key_shares(&self, signature: &RequestSignature, document: &DocumentAddress, nodes: &[NodeId]) {
+		for node in nodes {
+			let request = Message::RemoveKeyShare {
+				document: document.clone(),
+				signature: signature.clone(),
+			};
+			match self.request(node, request) {
+				Ok(Message::KeyShareRemoved) => (),
+				Ok(response) => warn!(target: "secretstore", "Failed to remove key share from {:?}: {}", node, unexpected_response(response)),
+				Err(e) => warn!(target: "secretstore", "Failed to remove key share from {:?}: {}", node, e),
+			}
+		}
+	}
+
+	/// Collects shadow points of all participants. Returns failed node along with the error.
+	fn shadow_points(&self, signature: &RequestSignature, document: &DocumentAddress, participants: &[NodeId]) -> Result<Vec<Public>, (NodeId, Error)> {
+		let mut shadow_points = Vec::with_capacity(participants.len());
+		for node in participants {
+			let request = Message::RequestShadowPoint {
+				document: document.clone(),
+				signature: signature.clone(),
+				participants: participants.to_vec(),
+			};
+			match self.request(node, request) {
+				Ok(Message::ShadowPoint(point)) => shadow_points.push(point),
+				Ok(response) => return Err((node.clone(), unexpected_response(response))),
+				Err(e) => return Err((node.clone(), e)),
+			}
+		}
+		Ok(shadow_points)
+	}
+}
+
+impl KeyServer for KeyServerCore {
+	fn generate_document_key(&self, signature: &RequestSignature, document: &DocumentAddress, threshold: usize) -> Result<DocumentEncryptedKey, Error> {
+		let requester = try!(self.check_access(signature, document));
+		let nodes = self.cluster.nodes();
+		if threshold + 1 > nodes.len() {
+			return Err(Error::InvalidThreshold);
+		}
+		if self.key_storage.contains(document) {
+			return Err(Error::DocumentAlreadyStored);
+		}
+
+		// every node contributes a random polynom, so that no node knows the joint secret
+		let mut public_shares = Vec::with_capacity(nodes.len());
+		let mut node_shares = vec![Vec::with_capacity(nodes.len()); nodes.len()];
+		for node in &nodes {
+			let request = Message::GenerateKeyShares {
+				document: document.clone(),
+				signature: signature.clone(),
+				threshold: threshold,
+				nodes: nodes.clone(),
+			};
+			match try!(self.request(node, request)) {
+				Message::KeySharesGenerated { ref public_share, ref shares } if shares.len() == nodes.len() => {
+					public_shares.push(public_share.clone());
+					for (node_share, share) in node_shares.iter_mut().zip(shares) {
+						node_share.push(share.clone());
+					}
+				},
+				response => return Err(unexpected_response(response)),
+			}
+		}
+
+		let joint_public = try!(math::compute_joint_public(public_shares.iter()));
+		let document_key = try!(math::generate_random_point());
+		let encrypted = try!(math::encrypt_secret(&document_key, &joint_public));
+
+		// key is either stored on all nodes, or removed from nodes which have already stored it
+		let mut stored = Vec::with_capacity(nodes.len());
+		for (node, shares) in nodes.iter().zip(node_shares) {
+			let request = Message::StoreKeyShare {
+				document: document.clone(),
+				signature: signature.clone(),
+				threshold: threshold,
+				nodes: nodes.clone(),
+				shares: shares,
+				common_point: encrypted.common_point.clone(),
+				encrypted_point: encrypted.encrypted_point.clone(),
+			};
+			let result = match self.request(node, request) {
+				Ok(Message::KeyShareStored) => Ok(()),
+				Ok(response) => Err(unexpected_response(response)),
+				Err(e) => Err(e),
+			};
+			if let Err(e) = result {
+				self.rollback_key_shares(signature, document, &stored);
+				return Err(e);
+			}
+			stored.push(node.clone());
+		}
+
+		encrypt_document_key(&requester, &document_key)
+	}
+
+	fn document_key(&self, signature: &RequestSignature, document: &DocumentAddress) -> Result<DocumentEncryptedKey, Error> {
+		let requester = try!(self.check_access(signature, document));
+		let share = try!(self.key_storage.get(document));
+
+		// nodes which fail to respond are replaced with other key share holders
+		let self_id = self.cluster.node_id().clone();
+		let mut candidates: Vec<NodeId> = share.id_numbers.keys().filter(|node| **node != self_id).cloned().collect();
+		loop {
+			if candidates.len() < share.threshold {
+				return Err(Error::Cluster("Not enough nodes to restore the document key".into()));
+			}
+
+			let mut participants = vec![self_id.clone()];
+			participants.extend(candidates.iter().take(share.threshold).cloned());
+			match self.shadow_points(signature, document, &participants) {
+				Ok(shadow_points) => {
+					let document_key = try!(math::decrypt_with_shadow_points(&share.encrypted_point, shadow_points.iter()));
+					return encrypt_document_key(&requester, &document_key);
+				},
+				Err((ref node, ref e)) if *node == self_id => return Err(e.clone()),
+				Err((node, e)) => {
+					warn!(target: "secretstore", "Node {:?} has failed to compute shadow point: {}", node, e);
+					candidates.retain(|candidate| *candidate != node);
+				},
+			}
+		}
+	}
+}
+
+impl MessageHandler for KeyServerCore {
+	fn process(&self, from: &NodeId, message: Message) -> Message {
+		let result = match message {
+			Message::GenerateKeyShares { document, signature, threshold, nodes } =>
+				self.generate_key_shares(&signature, &document, threshold, &nodes)
+					.map(|(public_share, shares)| Message::KeySharesGenerated { public_share: public_share, shares: shares }),
+			Message::StoreKeyShare { document, signature, threshold, nodes, shares, common_point, encrypted_point } =>
+				self.store_key_share(&signature, &document, threshold, nodes, &shares, common_point, encrypted_point).map(|_| Message::KeyShareStored),
+			Message::RemoveKeyShare { document, signature } =>
+				self.remove_key_share(from, &signature, &document).map(|_| Message::KeyShareRemoved),
+			Message::RequestShadowPoint { document, signature, participants } =>
+				self.shadow_point(from, &signature, &document, &participants).map(Message::ShadowPoint),
+			message => Err(Error::Cluster(format!("Unexpected request {:?}", message))),
+		};
+
+		result.unwrap_or_else(|e| {
+			warn!(target: "secretstore", "Request from {:?} has failed: {}", from, e);
+			Message::Error(e.to_string())
+		})
+	}
+}
+
+fn compute_shadow_point(share: &DocumentKeyShare, node: &NodeId, participants: &[NodeId]) -> Result<Public, Error> {
+	let number = try!(share.id_numbers.get(node).ok_or_else(|| Error::Internal("Node is not a key share holder".into())));
+	let mut other_numbers = Vec::with_capacity(participants.len());
+	for participant in participants.iter().filter(|participant| *participant != node) {
+		other_numbers.push(try!(share.id_numbers.get(participant).ok_or_else(|| Error::Cluster("Unknown decryption participant".into()))));
+	}
+
+	math::compute_node_shadow_point(number, &share.secret_share, other_numbers.into_iter(), &share.common_point)
+}
+
+fn encrypt_document_key(requester: &Public, document_key: &DocumentKey) -> Result<DocumentEncryptedKey, Error> {
+	Ok(try!(ecies::encrypt(requester, &[0; 0], document_key)))
+}
+
+fn unexpected_response(response: Message) -> Error {
+	match response {
+		Message::Error(e) => Error::Cluster(e),
+		response => Error::Cluster(format!("Unexpected response {:?}", response)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::collections::{BTreeMap, HashSet};
+	use util::{Bytes, RwLock};
+	use ethkey::{KeyPair, Random, Generator, sign};
+	use ethcrypto::ecies;
+	use acl_storage::tests::DummyAclStorage;
+	use key_storage::{KeyStorage, DocumentKeyShare};
+	use key_storage::tests::DummyKeyStorage;
+	use key_server_cluster::{Cluster, MessageHandler, Message};
+	use traits::KeyServer;
+	use types::{Error, NodeId, DocumentAddress};
+	use super::KeyServerCore;
+
+	/// Cluster, delivering messages directly to handlers of other nodes.
+	struct TestCluster {
+		key_pair: KeyPair,
+		handlers: Arc<RwLock<BTreeMap<NodeId, Arc<MessageHandler>>>>,
+		disconnected: Arc<RwLock<HashSet<NodeId>>>,
+	}
+
+	impl Cluster for TestCluster {
+		fn node_id(&self) -> &NodeId {
+			self.key_pair.public()
+		}
+
+		fn nodes(&self) -> Vec<NodeId> {
+			self.handlers.read().keys().cloned().collect()
+		}
+
+		fn send(&self, to: &NodeId, message: Message) -> Result<Message, Error> {
+			if self.disconnected.read().contains(to) {
+				return Err(Error::Cluster("Node is disconnected".into()));
+			}
+			let handler = try!(self.handlers.read().get(to).cloned().ok_or(Error::Cluster("Unknown node".into())));
+			Ok(handler.process(self.key_pair.public(), message))
+		}
+
+		fn decrypt(&self, data: &[u8]) -> Result<Bytes, Error> {
+			Ok(try!(ecies::decrypt(self.key_pair.secret(), &[0; 0], data)))
+		}
+	}
+
+	struct TestServers {
+		servers: Vec<Arc<KeyServerCore>>,
+		key_storages: Vec<Arc<DummyKeyStorage>>,
+		disconnected: Arc<RwLock<HashSet<NodeId>>>,
+	}
+
+	fn test_servers(count: usize, acl_storage: Arc<DummyAclStorage>) -> TestServers {
+		let handlers = Arc::new(RwLock::new(BTreeMap::new()));
+		let disconnected = Arc::new(RwLock::new(HashSet::new()));
+		let mut servers: Vec<_> = (0..count).map(|_| {
+			let cluster = Arc::new(TestCluster {
+				key_pair: Random.generate().unwrap(),
+				handlers: handlers.clone(),
+				disconnected: disconnected.clone(),
+			});
+			let key_storage = Arc::new(DummyKeyStorage::default());
+			let server = Arc::new(KeyServerCore::new(cluster.clone(), acl_storage.clone(), key_storage.clone()));
+			handlers.write().insert(cluster.node_id().clone(), server.clone() as Arc<MessageHandler>);
+			(cluster.node_id().clone(), server, key_storage)
+		}).collect();
+
+		// order servers the same way cluster nodes are ordered
+		servers.sort_by(|a, b| a.0.cmp(&b.0));
+		TestServers {
+			servers: servers.iter().map(|s| s.1.clone()).collect(),
+			key_storages: servers.iter().map(|s| s.2.clone()).collect(),
+			disconnected: disconnected,
+		}
+	}
+
+	fn key_servers(count: usize) -> (Vec<Arc<KeyServerCore>>, Arc<DummyAclStorage>) {
+		let acl_storage = Arc::new(DummyAclStorage::default());
+		(test_servers(count, acl_storage.clone()).servers, acl_storage)
+	}
+
+	fn decrypt(requester: &KeyPair, encrypted_key: &[u8]) -> Vec<u8> {
+		ecies::decrypt(requester.secret(), &[0; 0], encrypted_key).unwrap()
+	}
+
+	#[test]
+	fn document_key_generated_on_one_node_is_retrieved_on_others() {
+		let (servers, _) = key_servers(3);
+		let requester = Random.generate().unwrap();
+		let document = DocumentAddress::from(42);
+		let signature = sign(requester.secret(), &document).unwrap();
+
+		let generated = decrypt(&requester, &servers[0].generate_document_key(&signature, &document, 1).unwrap());
+		for server in &servers {
+			let retrieved = decrypt(&requester, &server.document_key(&signature, &document).unwrap());
+			assert_eq!(retrieved, generated);
+		}
+	}
+
+	#[test]
+	fn document_key_is_not_generated_twice() {
+		let (servers, _) = key_servers(3);
+		let requester = Random.generate().unwrap();
+		let document = DocumentAddress::from(42);
+		let signature = sign(requester.secret(), &document).unwrap();
+
+		assert!(servers[0].generate_document_key(&signature, &document, 1).is_ok());
+		assert_eq!(servers[1].generate_document_key(&signature, &document, 1), Err(Error::DocumentAlreadyStored));
+	}
+
+	#[test]
+	fn threshold_should_be_less_than_nodes_count() {
+		let (servers, _) = key_servers(3);
+		let requester = Random.generate().unwrap();
+		let document = DocumentAddress::from(42);
+		let signature = sign(requester.secret(), &document).unwrap();
+
+		assert_eq!(servers[0].generate_document_key(&signature, &document, 3), Err(Error::InvalidThreshold));
+	}
+
+	#[test]
+	fn document_key_is_not_retrieved_without_permission() {
+		let (servers, acl_storage) = key_servers(3);
+		let requester = Random.generate().unwrap();
+		let other = Random.generate().unwrap();
+		let document = DocumentAddress::from(42);
+		let signature = sign(requester.secret(), &document).unwrap();
+		let other_signature = sign(other.secret(), &document).unwrap();
+		acl_storage.prohibit(other.public().clone(), document.clone());
+
+		assert!(servers[0].generate_document_key(&signature, &document, 2).is_ok());
+		assert_eq!(servers[1].document_key(&other_signature, &document), Err(Error::AccessDenied));
+	}
+
+	#[test]
+	fn no_node_knows_the_joint_secret() {
+		let test = test_servers(3, Arc::new(DummyAclStorage::default()));
+		let requester = Random.generate().unwrap();
+		let document = DocumentAddress::from(42);
+		let signature = sign(requester.secret(), &document).unwrap();
+
+		assert!(test.servers[0].generate_document_key(&signature, &document, 1).is_ok());
+		let shares: Vec<_> = test.key_storages.iter().map(|storage| storage.get(&document).unwrap()).collect();
+		// shares are points of a polynom which is the sum of polynoms of all nodes
+		for share in &shares {
+			assert_eq!(share.threshold, 1);
+			assert_eq!(share.id_numbers.len(), 3);
+			assert_eq!(shares.iter().filter(|other| other.secret_share == share.secret_share).count(), 1);
+		}
+	}
+
+	#[test]
+	fn key_generation_is_rolled_back_when_a_node_fails_to_store_its_share() {
+		let test = test_servers(3, Arc::new(DummyAclStorage::default()));
+		let requester = Random.generate().unwrap();
+		let document = DocumentAddress::from(42);
+		let signature = sign(requester.secret(), &document).unwrap();
+
+		// the last node already has a key for the document
+		let existing = DocumentKeyShare {
+			threshold: 1,
+			id_numbers: BTreeMap::new(),
+			secret_share: Random.generate().unwrap().secret().clone(),
+			common_point: Random.generate().unwrap().public().clone(),
+			encrypted_point: Random.generate().unwrap().public().clone(),
+		};
+		test.key_storages[2].insert(document.clone(), existing.clone()).unwrap();
+
+		assert!(test.servers[0].generate_document_key(&signature, &document, 1).is_err());
+		assert!(!test.key_storages[0].contains(&document));
+		assert!(!test.key_storages[1].contains(&document));
+		assert_eq!(test.key_storages[2].get(&document), Ok(existing));
+	}
+
+	#[test]
+	fn key_generation_fails_when_a_node_is_disconnected() {
+		let test = test_servers(3, Arc::new(DummyAclStorage::default()));
+		let requester = Random.generate().unwrap();
+		let document = DocumentAddress::from(42);
+		let signature = sign(requester.secret(), &document).unwrap();
+		test.disconnected.write().insert(test.servers[1].cluster.node_id().clone());
+
+		assert!(test.servers[0].generate_document_key(&signature, &document, 1).is_err());
+		assert!(test.key_storages.iter().all(|storage| !storage.contains(&document)));
+	}
+
+	#[test]
+	fn document_key_is_retrieved_when_some_nodes_are_disconnected() {
+		let test = test_servers(5, Arc::new(DummyAclStorage::default()));
+		let requester = Random.generate().unwrap();
+		let document = DocumentAddress::from(42);
+		let signature = sign(requester.secret(), &document).unwrap();
+
+		let generated = decrypt(&requester, &test.servers[0].generate_document_key(&signature, &document, 2).unwrap());
+		test.disconnected.write().insert(test.servers[1].cluster.node_id().clone());
+		test.disconnected.write().insert(test.servers[2].cluster.node_id().clone());
+
+		let retrieved = decrypt(&requester, &test.servers[0].document_key(&signature, &document).unwrap());
+		assert_eq!(retrieved, generated);
+
+		// threshold + 1 nodes are required
+		test.disconnected.write().insert(test.servers[3].cluster.node_id().clone());
+		assert!(test.servers[0].document_key(&signature, &document).is_err());
+	}
+}
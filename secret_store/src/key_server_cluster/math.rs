@@ -0,0 +1,180 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Threshold encryption primitives. Document key (a point on the curve) is encrypted with
+//! the joint public of the cluster, whose secret is shared between nodes using Shamir's scheme.
+//! Every node contributes a random polynom, so the joint secret is never known to a single node.
+
+use ethkey::{Public, Secret, Random, Generator, math};
+use util::Hashable;
+use types::{Error, NodeId};
+
+/// Encryption result.
+#[derive(Debug)]
+pub struct EncryptedSecret {
+	/// Common encryption point.
+	pub common_point: Public,
+	/// Encrypted point.
+	pub encrypted_point: Public,
+}
+
+/// Generate random scalar.
+pub fn generate_random_scalar() -> Result<Secret, Error> {
+	Ok(try!(Random.generate()).secret().clone())
+}
+
+/// Generate random point.
+pub fn generate_random_point() -> Result<Public, Error> {
+	Ok(try!(Random.generate()).public().clone())
+}
+
+/// Generate random polynom of given degree.
+pub fn generate_random_polynom(threshold: usize) -> Result<Vec<Secret>, Error> {
+	let mut polynom = Vec::with_capacity(threshold + 1);
+	for _ in 0..threshold + 1 {
+		polynom.push(try!(generate_random_scalar()));
+	}
+	Ok(polynom)
+}
+
+/// Compute value of polynom at given point.
+pub fn compute_polynom(polynom: &[Secret], x: &Secret) -> Result<Secret, Error> {
+	let mut result = try!(polynom.last().cloned().ok_or(Error::InvalidThreshold));
+	for coeff in polynom.iter().rev().skip(1) {
+		try!(math::secret_mul(&mut result, x));
+		try!(math::secret_add(&mut result, coeff));
+	}
+	Ok(result)
+}
+
+/// Compute public corresponding to given secret.
+pub fn compute_public(secret: &Secret) -> Result<Public, Error> {
+	let mut public = math::generation_point();
+	try!(math::public_mul_secret(&mut public, secret));
+	Ok(public)
+}
+
+/// Compute sum of secrets. Key share of a node is the sum of values of all nodes polynoms at its number.
+pub fn compute_secret_sum<'a, I>(mut secrets: I) -> Result<Secret, Error> where I: Iterator<Item=&'a Secret> {
+	let mut sum = try!(secrets.next().cloned().ok_or(Error::InvalidThreshold));
+	for secret in secrets {
+		try!(math::secret_add(&mut sum, secret));
+	}
+	Ok(sum)
+}
+
+/// Compute joint public from publics of free terms of all nodes polynoms.
+pub fn compute_joint_public<'a, I>(mut public_shares: I) -> Result<Public, Error> where I: Iterator<Item=&'a Public> {
+	let mut joint_public = try!(public_shares.next().cloned().ok_or(Error::InvalidThreshold));
+	for public_share in public_shares {
+		try!(math::public_add(&mut joint_public, public_share));
+	}
+	Ok(joint_public)
+}
+
+/// Compute node number (x coordinate of its share) from node id.
+pub fn compute_node_number(node: &NodeId) -> Secret {
+	node.sha3()
+}
+
+/// Encrypt secret point with joint public.
+pub fn encrypt_secret(secret: &Public, joint_public: &Public) -> Result<EncryptedSecret, Error> {
+	let key = try!(generate_random_scalar());
+	let common_point = try!(compute_public(&key));
+	let mut encrypted_point = joint_public.clone();
+	try!(math::public_mul_secret(&mut encrypted_point, &key));
+	try!(math::public_add(&mut encrypted_point, secret));
+
+	Ok(EncryptedSecret {
+		common_point: common_point,
+		encrypted_point: encrypted_point,
+	})
+}
+
+/// Compute node shadow point: its share of the decryption, weighted with Lagrange coefficient
+/// over given set of other participating nodes.
+pub fn compute_node_shadow_point<'a, I>(node_number: &Secret, node_secret_share: &Secret, other_nodes_numbers: I, common_point: &Public) -> Result<Public, Error>
+	where I: Iterator<Item=&'a Secret> {
+	let mut coeff = node_secret_share.clone();
+	for other_number in other_nodes_numbers {
+		// coeff *= other / (other - node)
+		let mut denominator = other_number.clone();
+		try!(math::secret_sub(&mut denominator, node_number));
+		try!(math::secret_inv(&mut denominator));
+		try!(math::secret_mul(&mut coeff, other_number));
+		try!(math::secret_mul(&mut coeff, &denominator));
+	}
+
+	let mut shadow_point = common_point.clone();
+	try!(math::public_mul_secret(&mut shadow_point, &coeff));
+	Ok(shadow_point)
+}
+
+/// Decrypt secret point using shadow points of at least threshold + 1 nodes.
+pub fn decrypt_with_shadow_points<'a, I>(encrypted_point: &Public, mut shadow_points: I) -> Result<Public, Error>
+	where I: Iterator<Item=&'a Public> {
+	let mut shadows_sum = try!(shadow_points.next().cloned().ok_or(Error::InvalidThreshold));
+	for shadow_point in shadow_points {
+		try!(math::public_add(&mut shadows_sum, shadow_point));
+	}
+	try!(math::public_negate(&mut shadows_sum));
+
+	let mut decrypted_point = encrypted_point.clone();
+	try!(math::public_add(&mut decrypted_point, &shadows_sum));
+	Ok(decrypted_point)
+}
+
+#[cfg(test)]
+mod tests {
+	use ethkey::{Random, Generator};
+	use super::{generate_random_point, generate_random_polynom, compute_polynom, compute_public, compute_node_number,
+		compute_secret_sum, compute_joint_public, encrypt_secret, compute_node_shadow_point, decrypt_with_shadow_points};
+
+	fn run_key_sharing(threshold: usize, nodes: usize, participants: usize) {
+		let numbers: Vec<_> = (0..nodes).map(|_| compute_node_number(Random.generate().unwrap().public())).collect();
+		// every node generates its own polynom
+		let polynoms: Vec<_> = (0..nodes).map(|_| generate_random_polynom(threshold).unwrap()).collect();
+		let shares: Vec<_> = numbers.iter().map(|n| {
+			let values: Vec<_> = polynoms.iter().map(|polynom| compute_polynom(polynom, n).unwrap()).collect();
+			compute_secret_sum(values.iter()).unwrap()
+		}).collect();
+		let public_shares: Vec<_> = polynoms.iter().map(|polynom| compute_public(&polynom[0]).unwrap()).collect();
+		let joint_public = compute_joint_public(public_shares.iter()).unwrap();
+
+		let document_key = generate_random_point().unwrap();
+		let encrypted = encrypt_secret(&document_key, &joint_public).unwrap();
+
+		let shadows: Vec<_> = (0..participants).map(|i| {
+			let others = numbers.iter().take(participants).enumerate().filter(|&(j, _)| j != i).map(|(_, n)| n);
+			compute_node_shadow_point(&numbers[i], &shares[i], others, &encrypted.common_point).unwrap()
+		}).collect();
+		let decrypted = decrypt_with_shadow_points(&encrypted.encrypted_point, shadows.iter()).unwrap();
+
+		assert_eq!(decrypted == document_key, participants > threshold);
+	}
+
+	#[test]
+	fn document_key_is_restored_by_threshold_plus_one_nodes() {
+		run_key_sharing(1, 3, 2);
+		run_key_sharing(2, 5, 3);
+		run_key_sharing(2, 5, 5);
+	}
+
+	#[test]
+	fn document_key_is_not_restored_by_threshold_nodes() {
+		run_key_sharing(2, 5, 2);
+	}
+}
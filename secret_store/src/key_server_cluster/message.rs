@@ -0,0 +1,252 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use rlp::{RlpStream, UntrustedRlp, View, Stream, Encodable, Decodable, Decoder, DecoderError};
+use ethkey::Public;
+use util::Bytes;
+use types::{DocumentAddress, RequestSignature, NodeId};
+
+/// Message, exchanged between key server cluster nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+	/// Master node asks to generate a random polynom and share its values with all nodes.
+	GenerateKeyShares {
+		/// Document address.
+		document: DocumentAddress,
+		/// Requester signature of the document address.
+		signature: RequestSignature,
+		/// Decryption threshold.
+		threshold: usize,
+		/// Ids of all nodes, receiving key shares.
+		nodes: Vec<NodeId>,
+	},
+	/// Public of the polynom free term and polynom values for every node, encrypted with node public.
+	KeySharesGenerated {
+		/// Public of the polynom free term.
+		public_share: Public,
+		/// Polynom values in order of nodes in the request.
+		shares: Vec<Bytes>,
+	},
+	/// Master node asks to store key share of newly generated document key.
+	StoreKeyShare {
+		/// Document address.
+		document: DocumentAddress,
+		/// Requester signature of the document address.
+		signature: RequestSignature,
+		/// Decryption threshold.
+		threshold: usize,
+		/// Ids of all nodes, holding key shares.
+		nodes: Vec<NodeId>,
+		/// Polynom values for the receiving node from every node, encrypted with receiving node public.
+		shares: Vec<Bytes>,
+		/// Common (shared) encryption point.
+		common_point: Public,
+		/// Encrypted point.
+		encrypted_point: Public,
+	},
+	/// Key share has been stored.
+	KeyShareStored,
+	/// Master node asks to remove key share, stored during failed key generation.
+	RemoveKeyShare {
+		/// Document address.
+		document: DocumentAddress,
+		/// Requester signature of the document address.
+		signature: RequestSignature,
+	},
+	/// Key share has been removed.
+	KeyShareRemoved,
+	/// Master node asks to compute decryption shadow point.
+	RequestShadowPoint {
+		/// Document address.
+		document: DocumentAddress,
+		/// Requester signature of the document address.
+		signature: RequestSignature,
+		/// Ids of all nodes, participating in decryption.
+		participants: Vec<NodeId>,
+	},
+	/// Decryption shadow point of the responding node.
+	ShadowPoint(Public),
+	/// Request has failed on the responding node.
+	Error(String),
+}
+
+impl Encodable for Message {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		match *self {
+			Message::StoreKeyShare { ref document, ref signature, ref threshold, ref nodes, ref shares, ref common_point, ref encrypted_point } => {
+				s.begin_list(8);
+				s.append(&0u8);
+				s.append(document);
+				s.append(&signature.to_vec());
+				s.append(threshold);
+				s.append(nodes);
+				s.append(shares);
+				s.append(common_point);
+				s.append(encrypted_point);
+			},
+			Message::KeyShareStored => {
+				s.begin_list(1);
+				s.append(&1u8);
+			},
+			Message::RequestShadowPoint { ref document, ref signature, ref participants } => {
+				s.begin_list(4);
+				s.append(&2u8);
+				s.append(document);
+				s.append(&signature.to_vec());
+				s.append(participants);
+			},
+			Message::ShadowPoint(ref point) => {
+				s.begin_list(2);
+				s.append(&3u8);
+				s.append(point);
+			},
+			Message::Error(ref message) => {
+				s.begin_list(2);
+				s.append(&4u8);
+				s.append(message);
+			},
+			Message::GenerateKeyShares { ref document, ref signature, ref threshold, ref nodes } => {
+				s.begin_list(5);
+				s.append(&5u8);
+				s.append(document);
+				s.append(&signature.to_vec());
+				s.append(threshold);
+				s.append(nodes);
+			},
+			Message::KeySharesGenerated { ref public_share, ref shares } => {
+				s.begin_list(3);
+				s.append(&6u8);
+				s.append(public_share);
+				s.append(shares);
+			},
+			Message::RemoveKeyShare { ref document, ref signature } => {
+				s.begin_list(3);
+				s.append(&7u8);
+				s.append(document);
+				s.append(&signature.to_vec());
+			},
+			Message::KeyShareRemoved => {
+				s.begin_list(1);
+				s.append(&8u8);
+			},
+		}
+	}
+}
+
+impl Decodable for Message {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let message = match try!(d.val_at::<u8>(0)) {
+			0 => Message::StoreKeyShare {
+				document: try!(d.val_at(1)),
+				signature: try!(signature_at(d, 2)),
+				threshold: try!(d.val_at(3)),
+				nodes: try!(d.val_at(4)),
+				shares: try!(d.val_at(5)),
+				common_point: try!(d.val_at(6)),
+				encrypted_point: try!(d.val_at(7)),
+			},
+			1 => Message::KeyShareStored,
+			2 => Message::RequestShadowPoint {
+				document: try!(d.val_at(1)),
+				signature: try!(signature_at(d, 2)),
+				participants: try!(d.val_at(3)),
+			},
+			3 => Message::ShadowPoint(try!(d.val_at(1))),
+			4 => Message::Error(try!(d.val_at(1))),
+			5 => Message::GenerateKeyShares {
+				document: try!(d.val_at(1)),
+				signature: try!(signature_at(d, 2)),
+				threshold: try!(d.val_at(3)),
+				nodes: try!(d.val_at(4)),
+			},
+			6 => Message::KeySharesGenerated {
+				public_share: try!(d.val_at(1)),
+				shares: try!(d.val_at(2)),
+			},
+			7 => Message::RemoveKeyShare {
+				document: try!(d.val_at(1)),
+				signature: try!(signature_at(d, 2)),
+			},
+			8 => Message::KeyShareRemoved,
+			_ => return Err(DecoderError::Custom("Unknown message type")),
+		};
+		Ok(message)
+	}
+}
+
+fn signature_at(rlp: &UntrustedRlp, index: usize) -> Result<RequestSignature, DecoderError> {
+	let bytes: Vec<u8> = try!(rlp.val_at(index));
+	if bytes.len() != 65 {
+		return Err(DecoderError::RlpIncorrectListLen);
+	}
+	let mut signature = [0u8; 65];
+	signature.copy_from_slice(&bytes);
+	Ok(signature.into())
+}
+
+#[cfg(test)]
+mod tests {
+	use rlp::{self, UntrustedRlp, View};
+	use ethkey::{Random, Generator, sign};
+	use super::Message;
+
+	#[test]
+	fn messages_roundtrip() {
+		let key = Random.generate().unwrap();
+		let signature = sign(key.secret(), &1.into()).unwrap();
+		let messages = vec![
+			Message::GenerateKeyShares {
+				document: 1.into(),
+				signature: signature.clone(),
+				threshold: 1,
+				nodes: vec![key.public().clone()],
+			},
+			Message::KeySharesGenerated {
+				public_share: key.public().clone(),
+				shares: vec![vec![1, 2, 3], vec![4, 5]],
+			},
+			Message::StoreKeyShare {
+				document: 1.into(),
+				signature: signature.clone(),
+				threshold: 1,
+				nodes: vec![key.public().clone()],
+				shares: vec![vec![1, 2, 3]],
+				common_point: key.public().clone(),
+				encrypted_point: key.public().clone(),
+			},
+			Message::KeyShareStored,
+			Message::RemoveKeyShare {
+				document: 1.into(),
+				signature: signature.clone(),
+			},
+			Message::KeyShareRemoved,
+			Message::RequestShadowPoint {
+				document: 1.into(),
+				signature: signature,
+				participants: vec![key.public().clone()],
+			},
+			Message::ShadowPoint(key.public().clone()),
+			Message::Error("error".into()),
+		];
+
+		for message in messages {
+			let encoded = rlp::encode(&message);
+			let decoded: Message = UntrustedRlp::new(&encoded).as_val().unwrap();
+			assert_eq!(decoded, message);
+		}
+	}
+}
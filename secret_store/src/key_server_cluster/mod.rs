@@ -0,0 +1,45 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cluster of key servers, sharing document keys with threshold scheme.
+
+use util::Bytes;
+use types::{Error, NodeId};
+
+pub mod math;
+pub mod message;
+pub mod net;
+
+pub use self::message::Message;
+pub use self::net::{NetCluster, NetClusterListener};
+
+/// Handler of messages, received from other nodes.
+pub trait MessageHandler: Send + Sync {
+	/// Process message from other node and return the response.
+	fn process(&self, from: &NodeId, message: Message) -> Message;
+}
+
+/// Cluster of key server nodes.
+pub trait Cluster: Send + Sync {
+	/// Id of this node.
+	fn node_id(&self) -> &NodeId;
+	/// Ids of all nodes of the cluster (including this node).
+	fn nodes(&self) -> Vec<NodeId>;
+	/// Send message to other node and wait for its response.
+	fn send(&self, to: &NodeId, message: Message) -> Result<Message, Error>;
+	/// Decrypt data, encrypted with the public of this node.
+	fn decrypt(&self, data: &[u8]) -> Result<Bytes, Error>;
+}
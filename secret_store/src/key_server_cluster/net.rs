@@ -0,0 +1,215 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Key server cluster networking. Every request opens a connection to the target node,
+//! sends a single message and waits for a single response. Messages are encrypted with
+//! the receiver public and signed by the sender.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream, SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use std::collections::BTreeMap;
+use rlp::{self, RlpStream, UntrustedRlp, View, Stream};
+use ethkey::{KeyPair, sign, verify_public};
+use ethcrypto::ecies;
+use util::{Bytes, Hashable};
+use types::{Error, NodeId, NodeAddress, ClusterConfiguration};
+use key_server_cluster::{Cluster, MessageHandler};
+use key_server_cluster::message::Message;
+
+/// Maximal size of a single message.
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+/// Connection read/write timeout.
+const IO_TIMEOUT_SECS: u64 = 30;
+
+/// Cluster which communicates with other nodes over TCP.
+pub struct NetCluster {
+	self_key: Arc<KeyPair>,
+	nodes: BTreeMap<NodeId, NodeAddress>,
+}
+
+/// Listener of incoming connections from other nodes.
+pub struct NetClusterListener {
+	address: SocketAddr,
+	stopped: Arc<AtomicBool>,
+}
+
+impl NetCluster {
+	/// Create new cluster from configuration.
+	pub fn new(config: &ClusterConfiguration) -> Result<Self, Error> {
+		let self_key = try!(KeyPair::from_secret(config.self_secret.clone()));
+		if !config.nodes.contains_key(self_key.public()) {
+			return Err(Error::Cluster("This node is not listed in the cluster nodes".into()));
+		}
+
+		Ok(NetCluster {
+			self_key: Arc::new(self_key),
+			nodes: config.nodes.clone(),
+		})
+	}
+}
+
+impl Cluster for NetCluster {
+	fn node_id(&self) -> &NodeId {
+		self.self_key.public()
+	}
+
+	fn nodes(&self) -> Vec<NodeId> {
+		self.nodes.keys().cloned().collect()
+	}
+
+	fn send(&self, to: &NodeId, message: Message) -> Result<Message, Error> {
+		let address = try!(self.nodes.get(to).ok_or_else(|| Error::Cluster(format!("Unknown node {:?}", to))));
+		let socket_address = try!(resolve(address));
+		let mut stream = try!(TcpStream::connect(socket_address).map_err(as_cluster_error));
+		try!(set_timeouts(&stream));
+
+		try!(write_message(&mut stream, &self.self_key, to, &message));
+		let (from, response) = try!(read_message(&mut stream, &self.self_key, &self.nodes));
+		if &from != to {
+			return Err(Error::Cluster(format!("Response from unexpected node {:?}", from)));
+		}
+		Ok(response)
+	}
+	fn decrypt(&self, data: &[u8]) -> Result<Bytes, Error> {
+		Ok(try!(ecies::decrypt(self.self_key.secret(), &[0; 0], data)))
+	}
+}
+
+impl NetClusterListener {
+	/// Start listening for connections from other nodes.
+	pub fn start(config: &ClusterConfiguration, handler: Arc<MessageHandler>) -> Result<Self, Error> {
+		let self_key = Arc::new(try!(KeyPair::from_secret(config.self_secret.clone())));
+		let address = try!(resolve(&config.listener_address));
+		let listener = try!(TcpListener::bind(address).map_err(as_cluster_error));
+		let stopped = Arc::new(AtomicBool::new(false));
+		let nodes = Arc::new(config.nodes.clone());
+
+		let thread_stopped = stopped.clone();
+		try!(thread::Builder::new().name("secretstore-cluster".into()).spawn(move || {
+			for stream in listener.incoming() {
+				if thread_stopped.load(Ordering::Relaxed) {
+					break;
+				}
+
+				let stream = match stream {
+					Ok(stream) => stream,
+					Err(e) => {
+						warn!(target: "secretstore", "Error accepting cluster connection: {}", e);
+						continue;
+					},
+				};
+
+				let (self_key, nodes, handler) = (self_key.clone(), nodes.clone(), handler.clone());
+				let _ = thread::Builder::new().name("secretstore-connection".into()).spawn(move || {
+					if let Err(e) = serve_connection(stream, &self_key, &nodes, &*handler) {
+						warn!(target: "secretstore", "Error serving cluster connection: {}", e);
+					}
+				});
+			}
+		}).map_err(as_cluster_error));
+
+		Ok(NetClusterListener {
+			address: address,
+			stopped: stopped,
+		})
+	}
+}
+
+impl Drop for NetClusterListener {
+	fn drop(&mut self) {
+		self.stopped.store(true, Ordering::Relaxed);
+		// wake up the listening thread
+		let _ = TcpStream::connect(self.address);
+	}
+}
+
+fn serve_connection(mut stream: TcpStream, self_key: &KeyPair, nodes: &BTreeMap<NodeId, NodeAddress>, handler: &MessageHandler) -> Result<(), Error> {
+	try!(set_timeouts(&stream));
+	let (from, message) = try!(read_message(&mut stream, self_key, nodes));
+	trace!(target: "secretstore", "Received {:?} from {:?}", message, from);
+	let response = handler.process(&from, message);
+	write_message(&mut stream, self_key, &from, &response)
+}
+
+fn write_message(stream: &mut TcpStream, self_key: &KeyPair, to: &NodeId, message: &Message) -> Result<(), Error> {
+	let payload = rlp::encode(message);
+	let encrypted = try!(ecies::encrypt(to, &[0; 0], &payload));
+	let signature = try!(sign(self_key.secret(), &encrypted.sha3()));
+
+	let mut frame = RlpStream::new_list(3);
+	frame.append(self_key.public());
+	frame.append(&signature.to_vec());
+	frame.append(&encrypted);
+	let frame = frame.out();
+
+	let len = frame.len() as u32;
+	let header = [(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+	try!(stream.write_all(&header).map_err(as_cluster_error));
+	try!(stream.write_all(&frame).map_err(as_cluster_error));
+	stream.flush().map_err(as_cluster_error)
+}
+
+fn read_message(stream: &mut TcpStream, self_key: &KeyPair, nodes: &BTreeMap<NodeId, NodeAddress>) -> Result<(NodeId, Message), Error> {
+	let mut header = [0u8; 4];
+	try!(stream.read_exact(&mut header).map_err(as_cluster_error));
+	let len = ((header[0] as usize) << 24) | ((header[1] as usize) << 16) | ((header[2] as usize) << 8) | header[3] as usize;
+	if len > MAX_FRAME_SIZE {
+		return Err(Error::Cluster("Message is too large".into()));
+	}
+
+	let mut frame = vec![0u8; len];
+	try!(stream.read_exact(&mut frame).map_err(as_cluster_error));
+
+	let frame = UntrustedRlp::new(&frame);
+	let from: NodeId = try!(frame.val_at(0).map_err(as_cluster_error));
+	let signature: Vec<u8> = try!(frame.val_at(1).map_err(as_cluster_error));
+	let encrypted: Vec<u8> = try!(frame.val_at(2).map_err(as_cluster_error));
+
+	if !nodes.contains_key(&from) {
+		return Err(Error::Cluster(format!("Message from unknown node {:?}", from)));
+	}
+	if signature.len() != 65 {
+		return Err(Error::Cluster("Invalid message signature".into()));
+	}
+	let mut signature_bytes = [0u8; 65];
+	signature_bytes.copy_from_slice(&signature);
+	if !try!(verify_public(&from, &signature_bytes.into(), &encrypted.sha3())) {
+		return Err(Error::Cluster("Invalid message signature".into()));
+	}
+
+	let payload = try!(ecies::decrypt(self_key.secret(), &[0; 0], &encrypted));
+	let message = try!(UntrustedRlp::new(&payload).as_val().map_err(as_cluster_error));
+	Ok((from, message))
+}
+
+fn set_timeouts(stream: &TcpStream) -> Result<(), Error> {
+	let timeout = Some(Duration::from_secs(IO_TIMEOUT_SECS));
+	try!(stream.set_read_timeout(timeout).map_err(as_cluster_error));
+	stream.set_write_timeout(timeout).map_err(as_cluster_error)
+}
+
+fn resolve(address: &NodeAddress) -> Result<SocketAddr, Error> {
+	let mut addresses = try!((address.address.as_str(), address.port).to_socket_addrs().map_err(as_cluster_error));
+	addresses.next().ok_or_else(|| Error::Cluster(format!("Unable to resolve {}:{}", address.address, address.port)))
+}
+
+fn as_cluster_error<E: ::std::fmt::Debug>(e: E) -> Error {
+	Error::Cluster(format!("{:?}", e))
+}
@@ -0,0 +1,220 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+use std::collections::BTreeMap;
+use rlp::{self, RlpStream, UntrustedRlp, View, Stream, Encodable, Decodable, Decoder, DecoderError};
+use util::Database;
+use ethkey::{Secret, Public};
+use types::{Error, ServiceConfiguration, DocumentAddress, NodeId};
+
+/// Encrypted key share, stored by key storage on the single key server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentKeyShare {
+	/// Decryption threshold (at least threshold + 1 nodes are required to decrypt data).
+	pub threshold: usize,
+	/// Nodes ids numbers.
+	pub id_numbers: BTreeMap<NodeId, Secret>,
+	/// Node secret share.
+	pub secret_share: Secret,
+	/// Common (shared) encryption point.
+	pub common_point: Public,
+	/// Encrypted point.
+	pub encrypted_point: Public,
+}
+
+/// Document encryption keys storage.
+pub trait KeyStorage: Send + Sync {
+	/// Insert document encryption key.
+	fn insert(&self, document: DocumentAddress, key: DocumentKeyShare) -> Result<(), Error>;
+	/// Get document encryption key.
+	fn get(&self, document: &DocumentAddress) -> Result<DocumentKeyShare, Error>;
+	/// Check if storage contains document encryption key.
+	fn contains(&self, document: &DocumentAddress) -> bool;
+	/// Remove document encryption key.
+	fn remove(&self, document: &DocumentAddress) -> Result<(), Error>;
+}
+
+/// Persistent document encryption keys storage.
+pub struct PersistentKeyStorage {
+	db: Database,
+}
+
+impl PersistentKeyStorage {
+	/// Create new persistent document encryption keys storage.
+	pub fn new(config: &ServiceConfiguration) -> Result<Self, Error> {
+		let mut db_path = PathBuf::from(&config.data_path);
+		db_path.push("db");
+		let db_path = try!(db_path.to_str().ok_or(Error::Database("Invalid secretstore path".into())));
+
+		Ok(PersistentKeyStorage {
+			db: try!(Database::open_default(&db_path).map_err(Error::Database)),
+		})
+	}
+}
+
+impl KeyStorage for PersistentKeyStorage {
+	fn insert(&self, document: DocumentAddress, key: DocumentKeyShare) -> Result<(), Error> {
+		let key = rlp::encode(&key);
+		let mut batch = self.db.transaction();
+		batch.put(None, &document, &key);
+		self.db.write(batch).map_err(Error::Database)
+	}
+
+	fn get(&self, document: &DocumentAddress) -> Result<DocumentKeyShare, Error> {
+		let key = try!(try!(self.db.get(None, document).map_err(Error::Database)).ok_or(Error::DocumentNotFound));
+		UntrustedRlp::new(&key).as_val().map_err(|e| Error::Database(format!("{:?}", e)))
+	}
+
+	fn contains(&self, document: &DocumentAddress) -> bool {
+		self.db.get(None, document).map(|key| key.is_some()).unwrap_or(false)
+	}
+
+	fn remove(&self, document: &DocumentAddress) -> Result<(), Error> {
+		let mut batch = self.db.transaction();
+		batch.delete(None, document);
+		self.db.write(batch).map_err(Error::Database)
+	}
+}
+
+impl Encodable for DocumentKeyShare {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(5);
+		s.append(&self.threshold);
+		s.begin_list(self.id_numbers.len());
+		for (node, number) in &self.id_numbers {
+			s.begin_list(2);
+			s.append(node);
+			s.append(number);
+		}
+		s.append(&self.secret_share);
+		s.append(&self.common_point);
+		s.append(&self.encrypted_point);
+	}
+}
+
+impl Decodable for DocumentKeyShare {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let mut id_numbers = BTreeMap::new();
+		for item in try!(d.at(1)).iter() {
+			id_numbers.insert(try!(item.val_at(0)), try!(item.val_at(1)));
+		}
+
+		Ok(DocumentKeyShare {
+			threshold: try!(d.val_at(0)),
+			id_numbers: id_numbers,
+			secret_share: try!(d.val_at(2)),
+			common_point: try!(d.val_at(3)),
+			encrypted_point: try!(d.val_at(4)),
+		})
+	}
+}
+
+#[cfg(test)]
+pub mod tests {
+	use std::collections::HashMap;
+	use util::RwLock;
+	use devtools::RandomTempPath;
+	use ethkey::{Random, Generator};
+	use types::{Error, ServiceConfiguration, ClusterConfiguration, DocumentAddress, NodeAddress};
+	use super::{KeyStorage, PersistentKeyStorage, DocumentKeyShare};
+
+	/// In-memory document encryption keys storage.
+	#[derive(Default)]
+	pub struct DummyKeyStorage {
+		keys: RwLock<HashMap<DocumentAddress, DocumentKeyShare>>,
+	}
+
+	impl KeyStorage for DummyKeyStorage {
+		fn insert(&self, document: DocumentAddress, key: DocumentKeyShare) -> Result<(), Error> {
+			self.keys.write().insert(document, key);
+			Ok(())
+		}
+
+		fn get(&self, document: &DocumentAddress) -> Result<DocumentKeyShare, Error> {
+			self.keys.read().get(document).cloned().ok_or(Error::DocumentNotFound)
+		}
+
+		fn contains(&self, document: &DocumentAddress) -> bool {
+			self.keys.read().contains_key(document)
+		}
+
+		fn remove(&self, document: &DocumentAddress) -> Result<(), Error> {
+			self.keys.write().remove(document);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn persistent_key_storage() {
+		let path = RandomTempPath::create_dir();
+		let config = ServiceConfiguration {
+			listener_address: NodeAddress {
+				address: "0.0.0.0".into(),
+				port: 8082,
+			},
+			data_path: path.as_str().to_owned(),
+			cluster_config: ClusterConfiguration {
+				self_secret: Random.generate().unwrap().secret().clone(),
+				listener_address: NodeAddress {
+					address: "0.0.0.0".into(),
+					port: 8083,
+				},
+				nodes: Default::default(),
+			},
+		};
+
+		let key1 = DocumentAddress::from(1);
+		let value1 = DocumentKeyShare {
+			threshold: 100,
+			id_numbers: vec![
+				(Random.generate().unwrap().public().clone(), Random.generate().unwrap().secret().clone())
+			].into_iter().collect(),
+			secret_share: Random.generate().unwrap().secret().clone(),
+			common_point: Random.generate().unwrap().public().clone(),
+			encrypted_point: Random.generate().unwrap().public().clone(),
+		};
+		let key2 = DocumentAddress::from(2);
+		let value2 = DocumentKeyShare {
+			threshold: 200,
+			id_numbers: vec![
+				(Random.generate().unwrap().public().clone(), Random.generate().unwrap().secret().clone())
+			].into_iter().collect(),
+			secret_share: Random.generate().unwrap().secret().clone(),
+			common_point: Random.generate().unwrap().public().clone(),
+			encrypted_point: Random.generate().unwrap().public().clone(),
+		};
+		let key3 = DocumentAddress::from(3);
+
+		let key_storage = PersistentKeyStorage::new(&config).unwrap();
+		key_storage.insert(key1.clone(), value1.clone()).unwrap();
+		key_storage.insert(key2.clone(), value2.clone()).unwrap();
+		assert_eq!(key_storage.get(&key1), Ok(value1.clone()));
+		assert_eq!(key_storage.get(&key2), Ok(value2.clone()));
+		assert_eq!(key_storage.get(&key3), Err(Error::DocumentNotFound));
+		drop(key_storage);
+
+		let key_storage = PersistentKeyStorage::new(&config).unwrap();
+		assert_eq!(key_storage.get(&key1), Ok(value1));
+		assert_eq!(key_storage.get(&key2), Ok(value2.clone()));
+		assert!(!key_storage.contains(&key3));
+
+		key_storage.remove(&key1).unwrap();
+		assert!(!key_storage.contains(&key1));
+		assert_eq!(key_storage.get(&key2), Ok(value2));
+	}
+}
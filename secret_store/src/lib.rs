@@ -0,0 +1,55 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Secret Store: stores document keys, shared between the cluster of key servers,
+//! and hands them out to requesters permitted by the on-chain ACL checker contract.
+
+#[macro_use]
+extern crate log;
+extern crate rustc_serialize;
+extern crate hyper;
+extern crate ethabi;
+extern crate ethcore_util as util;
+extern crate ethcrypto;
+extern crate ethkey;
+extern crate rlp;
+
+#[cfg(test)]
+extern crate ethcore_devtools as devtools;
+
+mod acl_storage;
+mod http_listener;
+mod key_server;
+mod key_server_cluster;
+mod key_storage;
+mod traits;
+mod types;
+
+use std::sync::Arc;
+
+pub use types::{Error, NodeId, NodeAddress, ServiceConfiguration, ClusterConfiguration,
+	DocumentAddress, DocumentKey, DocumentEncryptedKey, RequestSignature};
+pub use traits::KeyServer;
+pub use acl_storage::ContractClient;
+
+/// Start new key server instance.
+pub fn start(client: Arc<ContractClient>, config: ServiceConfiguration) -> Result<Box<KeyServer>, Error> {
+	let acl_storage = Arc::new(acl_storage::OnChainAclStorage::new(client));
+	let key_storage = Arc::new(try!(key_storage::PersistentKeyStorage::new(&config)));
+	let key_server = try!(key_server::KeyServerImpl::new(&config.cluster_config, acl_storage, key_storage));
+	let listener = try!(http_listener::KeyServerHttpListener::start(&config.listener_address, key_server));
+	Ok(Box::new(listener))
+}
@@ -0,0 +1,113 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Secret Store types.
+
+use std::fmt;
+use std::collections::BTreeMap;
+use ethkey;
+use ethcrypto;
+use util::{H256, Bytes};
+
+/// Node id.
+pub type NodeId = ethkey::Public;
+/// Document address type.
+pub type DocumentAddress = H256;
+/// Document key (point on the curve).
+pub type DocumentKey = ethkey::Public;
+/// Document key, encrypted with requester public.
+pub type DocumentEncryptedKey = Bytes;
+/// Request signature type.
+pub type RequestSignature = ethkey::Signature;
+
+/// Secret store error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+	/// Request signature is invalid.
+	BadSignature,
+	/// Access to resource is denied.
+	AccessDenied,
+	/// Requested document is not found.
+	DocumentNotFound,
+	/// Key for the document has already been generated.
+	DocumentAlreadyStored,
+	/// Threshold is not less than number of nodes in the cluster.
+	InvalidThreshold,
+	/// Communication with other nodes has failed.
+	Cluster(String),
+	/// Key storage error.
+	Database(String),
+	/// Internal error.
+	Internal(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			Error::BadSignature => write!(f, "Bad signature"),
+			Error::AccessDenied => write!(f, "Access denied"),
+			Error::DocumentNotFound => write!(f, "Document not found"),
+			Error::DocumentAlreadyStored => write!(f, "Document key has already been generated"),
+			Error::InvalidThreshold => write!(f, "Threshold must be less than number of nodes"),
+			Error::Cluster(ref msg) => write!(f, "Cluster error: {}", msg),
+			Error::Database(ref msg) => write!(f, "Database error: {}", msg),
+			Error::Internal(ref msg) => write!(f, "Internal error: {}", msg),
+		}
+	}
+}
+
+impl From<ethkey::Error> for Error {
+	fn from(err: ethkey::Error) -> Self {
+		Error::Internal(err.to_string())
+	}
+}
+
+impl From<ethcrypto::Error> for Error {
+	fn from(err: ethcrypto::Error) -> Self {
+		Error::Internal(err.to_string())
+	}
+}
+
+/// Network address of a node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeAddress {
+	/// IP address or hostname.
+	pub address: String,
+	/// Port.
+	pub port: u16,
+}
+
+/// Secret store configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceConfiguration {
+	/// HTTP listener address.
+	pub listener_address: NodeAddress,
+	/// Data directory path for the key storage.
+	pub data_path: String,
+	/// Key server cluster configuration.
+	pub cluster_config: ClusterConfiguration,
+}
+
+/// Key server cluster configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterConfiguration {
+	/// Secret key of this node. Its public is used as node id.
+	pub self_secret: ethkey::Secret,
+	/// Address to listen for connections from other nodes.
+	pub listener_address: NodeAddress,
+	/// All nodes of the cluster (including this node).
+	pub nodes: BTreeMap<NodeId, NodeAddress>,
+}
@@ -22,6 +22,7 @@ use std::fs;
 use std::path::Path;
 use std::time;
 use util::{H256, Hashable};
+use util::path::restrict_permissions_owner;
 
 /// Providing current time in seconds
 pub trait TimeProvider {
@@ -83,9 +84,12 @@ impl AuthCodes<DefaultTimeProvider> {
 
 impl<T: TimeProvider> AuthCodes<T> {
 
-	/// Writes all `AuthCodes` to a disk.
-	pub fn to_file(&self, file: &Path) -> io::Result<()> {
-		let mut file = try!(fs::File::create(file));
+	/// Writes all `AuthCodes` to a disk. The file is readable only by its owner.
+	pub fn to_file(&self, path: &Path) -> io::Result<()> {
+		let mut file = try!(fs::File::create(path));
+		if let Err(e) = restrict_permissions_owner(path) {
+			warn!(target: "signer", "Failed to restrict permissions of {:?}: {}", path, e);
+		}
 		let content = self.codes.join("\n");
 		file.write_all(content.as_bytes())
 	}
@@ -216,6 +220,22 @@ mod tests {
 		assert_eq!(res2, false);
 	}
 
-}
+	#[cfg(unix)]
+	#[test]
+	fn should_write_codes_readable_only_by_owner() {
+		use std::fs;
+		use std::os::unix::fs::PermissionsExt;
+		use devtools::RandomTempPath;
 
+		// given
+		let path = RandomTempPath::new();
+		let codes = AuthCodes::new(vec!["23521352asdfasdfadf".into()], || 100);
 
+		// when
+		codes.to_file(path.as_path()).unwrap();
+
+		// then
+		let mode = fs::metadata(path.as_path()).unwrap().permissions().mode();
+		assert_eq!(mode & 0o777, 0o600);
+	}
+}
@@ -23,7 +23,9 @@ use std::path::PathBuf;
 use std::default::Default;
 use std::ops::Drop;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::net::SocketAddr;
+use std::time::Duration;
 use io::{PanicHandler, OnPanicListener, MayPanic};
 use jsonrpc_core::{IoHandler, IoDelegate};
 use rpc::{Extendable, ConfirmationsQueue};
@@ -54,6 +56,7 @@ pub struct ServerBuilder {
 	handler: Arc<IoHandler>,
 	authcodes_path: PathBuf,
 	skip_origin_validation: bool,
+	request_timeout: Option<Duration>,
 }
 
 impl Extendable for ServerBuilder {
@@ -70,6 +73,7 @@ impl ServerBuilder {
 			handler: Arc::new(IoHandler::new()),
 			authcodes_path: authcodes_path,
 			skip_origin_validation: false,
+			request_timeout: None,
 		}
 	}
 
@@ -80,17 +84,30 @@ impl ServerBuilder {
 		self
 	}
 
+	/// Requests which are not confirmed within `timeout` are rejected
+	/// and Signer UIs are notified that they expired. By default requests never expire.
+	pub fn request_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.request_timeout = timeout;
+		self
+	}
+
 	/// Starts a new `WebSocket` server in separate thread.
 	/// Returns a `Server` handle which closes the server when droped.
 	pub fn start(self, addr: SocketAddr) -> Result<Server, ServerError> {
+		self.queue.set_timeout(self.request_timeout);
 		Server::start(addr, self.handler, self.queue, self.authcodes_path, self.skip_origin_validation)
 	}
 }
 
 /// `WebSockets` server implementation.
+///
+/// Every change in the confirmations queue is pushed to connected Signer UIs
+/// as a `signer_requestEvent` notification.
 pub struct Server {
 	handle: Option<thread::JoinHandle<ws::WebSocket<session::Factory>>>,
 	broadcaster_handle: Option<thread::JoinHandle<()>>,
+	expiry_handle: Option<thread::JoinHandle<()>>,
+	finished: Arc<AtomicBool>,
 	queue: Arc<ConfirmationsQueue>,
 	panic_handler: Arc<PanicHandler>,
 	addr: SocketAddr,
@@ -146,9 +163,12 @@ impl Server {
 		let q = queue.clone();
 		let broadcaster_handle = thread::spawn(move || {
 			ph.catch_panic(move || {
-				q.start_listening(|_message| {
-					// TODO [ToDr] Some better structure here for messages.
-					broadcaster.send("new_message").unwrap();
+				q.start_listening(|message| {
+					if let Some(notification) = q.event_notification(&message) {
+						if let Err(e) = broadcaster.send(notification) {
+							warn!(target: "signer", "Error while broadcasting queue event: {:?}", e);
+						}
+					}
 				}).expect("It's the only place we are running start_listening. It shouldn't fail.");
 				let res = broadcaster.shutdown();
 
@@ -158,10 +178,29 @@ impl Server {
 			}).unwrap()
 		});
 
+		// Spawn a thread rejecting expired requests
+		let finished = Arc::new(AtomicBool::new(false));
+		let expiry_handle = match queue.timeout() {
+			Some(_) => {
+				let (ph, q, f) = (panic_handler.clone(), queue.clone(), finished.clone());
+				Some(thread::spawn(move || {
+					ph.catch_panic(move || {
+						while !f.load(Ordering::SeqCst) {
+							q.remove_expired();
+							thread::park_timeout(Duration::from_secs(1));
+						}
+					}).unwrap()
+				}))
+			},
+			None => None,
+		};
+
 		// Return a handle
 		Ok(Server {
 			handle: Some(handle),
 			broadcaster_handle: Some(broadcaster_handle),
+			expiry_handle: expiry_handle,
+			finished: finished,
 			queue: queue,
 			panic_handler: panic_handler,
 			addr: addr,
@@ -177,6 +216,11 @@ impl MayPanic for Server {
 
 impl Drop for Server {
 	fn drop(&mut self) {
+		self.finished.store(true, Ordering::SeqCst);
+		if let Some(handle) = self.expiry_handle.take() {
+			handle.thread().unpark();
+			handle.join().unwrap();
+		}
 		self.queue.finish();
 		self.broadcaster_handle.take().unwrap().join().unwrap();
 		self.handle.take().unwrap().join().unwrap();
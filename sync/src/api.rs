@@ -17,8 +17,9 @@
 use std::sync::Arc;
 use std::str;
 use network::{NetworkProtocolHandler, NetworkService, NetworkContext, PeerId,
-	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode, NetworkError};
-use util::{U256, H256};
+	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode, NatType as BasicNatType, NetworkError,
+	ConnectionFilter, ConnectionDirection};
+use util::{U256, H256, H512, Bytes};
 use io::{TimerToken};
 use ethcore::client::{BlockChainClient, ChainNotify};
 use ethcore::snapshot::SnapshotService;
@@ -30,6 +31,23 @@ use ipc::{BinaryConvertable, BinaryConvertError, IpcConfig};
 use std::str::FromStr;
 use parking_lot::RwLock;
 
+/// Method used for the initial sync of an empty database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMethod {
+	/// Restore a snapshot served by peers, if any.
+	Warp,
+	/// Download the state of a recent block node by node from any eth/63 peer.
+	Fast,
+	/// Import every block from genesis.
+	Full,
+}
+
+impl Default for SyncMethod {
+	fn default() -> Self {
+		SyncMethod::Warp
+	}
+}
+
 /// Sync configuration
 #[derive(Debug, Clone, Copy)]
 pub struct SyncConfig {
@@ -41,6 +59,16 @@ pub struct SyncConfig {
 	pub subprotocol_name: [u8; 3],
 	/// Fork block to check
 	pub fork_block: Option<(BlockNumber, H256)>,
+	/// Relay transactions while the initial sync is in progress.
+	/// Such transactions can't be validated against the latest state.
+	pub propagate_transactions_while_syncing: bool,
+	/// Announce new blocks to peers while the initial sync is in progress.
+	pub propagate_blocks_while_syncing: bool,
+	/// Initial sync method.
+	pub sync_method: SyncMethod,
+	/// The client runs in relay mode and keeps no state.
+	/// Blocks are always synced in full and transactions are relayed at all times.
+	pub relay: bool,
 }
 
 impl Default for SyncConfig {
@@ -50,6 +78,10 @@ impl Default for SyncConfig {
 			network_id: U256::from(1),
 			subprotocol_name: *b"eth",
 			fork_block: None,
+			propagate_transactions_while_syncing: false,
+			propagate_blocks_while_syncing: true,
+			sync_method: SyncMethod::default(),
+			relay: false,
 		}
 	}
 }
@@ -61,6 +93,40 @@ binary_fixed_size!(SyncStatus);
 pub trait SyncProvider: Send + Sync {
 	/// Get sync status
 	fn status(&self) -> SyncStatus;
+
+	/// Get peers information
+	fn peers(&self) -> Vec<PeerInfo>;
+}
+
+/// Peer connection information
+#[derive(Debug, Binary, Clone, PartialEq)]
+pub struct PeerInfo {
+	/// Public node id
+	pub id: Option<String>,
+	/// Node client ID
+	pub client_version: String,
+	/// Capabilities
+	pub capabilities: Vec<String>,
+	/// Remote endpoint address
+	pub remote_address: String,
+	/// Local endpoint address
+	pub local_address: String,
+	/// Ping delay in milliseconds
+	pub ping_ms: Option<u64>,
+	/// Number of packets received from the peer
+	pub packets_received: u64,
+	/// Number of packets sent to the peer
+	pub packets_sent: u64,
+	/// Number of packet bytes received from the peer
+	pub bytes_received: u64,
+	/// Number of packet bytes sent to the peer
+	pub bytes_sent: u64,
+	/// Ethereum protocol version
+	pub eth_version: u32,
+	/// Peer best block hash
+	pub eth_head: H256,
+	/// Peer total difficulty if known
+	pub eth_difficulty: Option<U256>,
 }
 
 /// Ethereum network protocol handler
@@ -77,7 +143,8 @@ impl EthSync {
 	/// Creates and register protocol with the network service
 	pub fn new(config: SyncConfig, chain: Arc<BlockChainClient>, snapshot_service: Arc<SnapshotService>, network_config: NetworkConfiguration) -> Result<Arc<EthSync>, NetworkError> {
 		let chain_sync = ChainSync::new(config, &*chain);
-		let service = try!(NetworkService::new(try!(network_config.into_basic())));
+		let filter = Arc::new(ChainConnectionFilter { chain: chain.clone() });
+		let service = try!(NetworkService::new(try!(network_config.into_basic()), Some(filter)));
 		let sync = Arc::new(EthSync{
 			network: service,
 			handler: Arc::new(SyncProtocolHandler { sync: RwLock::new(chain_sync), chain: chain, snapshot_service: snapshot_service }),
@@ -88,12 +155,31 @@ impl EthSync {
 	}
 }
 
+/// Allows only the connections permitted by the chain spec.
+struct ChainConnectionFilter {
+	chain: Arc<BlockChainClient>,
+}
+
+impl ConnectionFilter for ChainConnectionFilter {
+	fn connection_allowed(&self, own_id: &H512, connecting_id: &H512, _direction: ConnectionDirection) -> bool {
+		self.chain.node_allowed(own_id, connecting_id)
+	}
+}
+
 #[ipc(client_ident="SyncClient")]
 impl SyncProvider for EthSync {
 	/// Get sync status
 	fn status(&self) -> SyncStatus {
 		self.handler.sync.write().status()
 	}
+
+	/// Get peers information
+	fn peers(&self) -> Vec<PeerInfo> {
+		self.network.with_context_eval(self.subprotocol_name, |context| {
+			let sync_io = NetSyncIo::new(context, &*self.handler.chain, &*self.handler.snapshot_service);
+			self.handler.sync.read().peers(&sync_io)
+		}).unwrap_or(Vec::new())
+	}
 }
 
 struct SyncProtocolHandler {
@@ -152,13 +238,27 @@ impl ChainNotify for EthSync {
 
 	fn start(&self) {
 		self.network.start().unwrap_or_else(|e| warn!("Error starting network: {:?}", e));
-		self.network.register_protocol(self.handler.clone(), self.subprotocol_name, &[62u8, 63u8, 64u8])
+		self.network.register_protocol(self.handler.clone(), self.subprotocol_name, &[62u8, 63u8, 64u8, 65u8, 66u8])
 			.unwrap_or_else(|e| warn!("Error registering ethereum protocol: {:?}", e));
 	}
 
 	fn stop(&self) {
 		self.network.stop().unwrap_or_else(|e| warn!("Error stopping network: {:?}", e));
 	}
+
+	fn broadcast_private_transaction(&self, packet: Bytes) {
+		self.network.with_context(self.subprotocol_name, |context| {
+			let mut sync_io = NetSyncIo::new(context, &*self.handler.chain, &*self.handler.snapshot_service);
+			self.handler.sync.write().propagate_private_transaction(&mut sync_io, packet.clone());
+		});
+	}
+
+	fn broadcast_signed_private_transaction(&self, packet: Bytes) {
+		self.network.with_context(self.subprotocol_name, |context| {
+			let mut sync_io = NetSyncIo::new(context, &*self.handler.chain, &*self.handler.snapshot_service);
+			self.handler.sync.write().propagate_signed_private_transaction(&mut sync_io, packet.clone());
+		});
+	}
 }
 
 impl IpcConfig for ManageNetwork { }
@@ -180,6 +280,8 @@ pub trait ManageNetwork : Send + Sync {
 	fn stop_network(&self);
 	/// Query the current configuration of the network
 	fn network_config(&self) -> NetworkConfiguration;
+	/// Query the amount of data transferred over the network
+	fn network_traffic(&self) -> NetworkTraffic;
 }
 
 
@@ -216,6 +318,82 @@ impl ManageNetwork for EthSync {
 	fn network_config(&self) -> NetworkConfiguration {
 		NetworkConfiguration::from(self.network.config().clone())
 	}
+
+	fn network_traffic(&self) -> NetworkTraffic {
+		let stats = self.network.stats();
+		let mut protocols: Vec<_> = stats.protocols().into_iter()
+			.map(|(protocol, traffic)| ProtocolTrafficInfo {
+				protocol: String::from_utf8_lossy(&protocol).into_owned(),
+				bytes_received: traffic.recv as u64,
+				bytes_sent: traffic.send as u64,
+			})
+			.collect();
+		protocols.sort_by(|a, b| a.protocol.cmp(&b.protocol));
+		NetworkTraffic {
+			bytes_received: stats.recv() as u64,
+			bytes_sent: stats.send() as u64,
+			receive_rate: stats.recv_rate() as u64,
+			send_rate: stats.send_rate() as u64,
+			protocols: protocols,
+		}
+	}
+}
+
+/// Data transferred over a network subprotocol
+#[derive(Debug, Binary, Clone, PartialEq)]
+pub struct ProtocolTrafficInfo {
+	/// Subprotocol name
+	pub protocol: String,
+	/// Number of packet bytes received
+	pub bytes_received: u64,
+	/// Number of packet bytes sent
+	pub bytes_sent: u64,
+}
+
+/// Network traffic statistics
+#[derive(Debug, Binary, Clone, PartialEq)]
+pub struct NetworkTraffic {
+	/// Total number of bytes received
+	pub bytes_received: u64,
+	/// Total number of bytes sent
+	pub bytes_sent: u64,
+	/// Bytes received per second
+	pub receive_rate: u64,
+	/// Bytes sent per second
+	pub send_rate: u64,
+	/// Traffic per subprotocol
+	pub protocols: Vec<ProtocolTrafficInfo>,
+}
+
+#[derive(Binary, Debug, Clone, Copy, PartialEq, Eq)]
+/// NAT traversal method
+pub enum NatType {
+	/// Try UPnP first, then NAT-PMP.
+	Any,
+	/// Universal Plug and Play only.
+	UPnP,
+	/// NAT Port Mapping Protocol only.
+	NatPmp,
+}
+
+impl From<BasicNatType> for NatType {
+	fn from(other: BasicNatType) -> Self {
+		match other {
+			BasicNatType::Any => NatType::Any,
+			BasicNatType::UPnP => NatType::UPnP,
+			BasicNatType::NatPmp => NatType::NatPmp,
+		}
+	}
+}
+
+impl From<NatType> for BasicNatType {
+	fn from(other: NatType) -> Self {
+		match other {
+			NatType::Any => BasicNatType::Any,
+			NatType::UPnP => BasicNatType::UPnP,
+			NatType::NatPmp => BasicNatType::NatPmp,
+		}
+	}
 }
 
 #[derive(Binary, Debug, Clone, PartialEq, Eq)]
@@ -233,6 +411,8 @@ pub struct NetworkConfiguration {
 	pub udp_port: Option<u16>,
 	/// Enable NAT configuration
 	pub nat_enabled: bool,
+	/// NAT traversal method
+	pub nat_type: NatType,
 	/// Enable discovery
 	pub discovery_enabled: bool,
 	/// List of initial node addresses
@@ -247,6 +427,10 @@ pub struct NetworkConfiguration {
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub allow_non_reserved: bool,
+	/// Download soft limit in bytes per second
+	pub max_download: Option<u64>,
+	/// Upload soft limit in bytes per second
+	pub max_upload: Option<u64>,
 }
 
 impl NetworkConfiguration {
@@ -277,6 +461,7 @@ impl NetworkConfiguration {
 			public_address:  match self.public_address { None => None, Some(addr) => Some(try!(SocketAddr::from_str(&addr))) },
 			udp_port: self.udp_port,
 			nat_enabled: self.nat_enabled,
+			nat_type: self.nat_type.into(),
 			discovery_enabled: self.discovery_enabled,
 			boot_nodes: self.boot_nodes,
 			use_secret: self.use_secret,
@@ -284,6 +469,8 @@ impl NetworkConfiguration {
 			min_peers: self.min_peers,
 			reserved_nodes: self.reserved_nodes,
 			non_reserved_mode: if self.allow_non_reserved { NonReservedPeerMode::Accept } else { NonReservedPeerMode::Deny },
+			max_download: self.max_download.map(|v| v as usize),
+			max_upload: self.max_upload.map(|v| v as usize),
 		})
 	}
 }
@@ -297,6 +484,7 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
 			public_address: other.public_address.and_then(|addr| Some(format!("{}", addr))),
 			udp_port: other.udp_port,
 			nat_enabled: other.nat_enabled,
+			nat_type: other.nat_type.into(),
 			discovery_enabled: other.discovery_enabled,
 			boot_nodes: other.boot_nodes,
 			use_secret: other.use_secret,
@@ -304,6 +492,8 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
 			min_peers: other.min_peers,
 			reserved_nodes: other.reserved_nodes,
 			allow_non_reserved: match other.non_reserved_mode { NonReservedPeerMode::Accept => true, _ => false } ,
+			max_download: other.max_download.map(|v| v as u64),
+			max_upload: other.max_upload.map(|v| v as u64),
 		}
 	}
 }
@@ -25,6 +25,8 @@ known_heap_size!(0, HeaderId);
 struct SyncBlock {
 	header: Bytes,
 	body: Option<Bytes>,
+	/// Body identifier if the body has to be downloaded.
+	header_id: Option<HeaderId>,
 }
 
 impl HeapSizeOf for SyncBlock {
@@ -34,7 +36,7 @@ impl HeapSizeOf for SyncBlock {
 }
 
 /// Used to identify header by transactions and uncles hashes
-#[derive(Eq, PartialEq, Hash)]
+#[derive(Eq, PartialEq, Hash, Clone)]
 struct HeaderId {
 	transactions_root: H256,
 	uncles: H256
@@ -57,8 +59,10 @@ pub struct BlockCollection {
 	head: Option<H256>,
 	/// Set of block header hashes being downloaded
 	downloading_headers: HashSet<H256>,
-	/// Set of block bodies being downloaded identified by block hash.
-	downloading_bodies: HashSet<H256>,
+	/// Block bodies being downloaded identified by block hash, with the number of requests in flight.
+	downloading_bodies: HashMap<H256, usize>,
+	/// Subchain head the next subchain heads were last requested from.
+	extended_from: Option<H256>,
 }
 
 impl BlockCollection {
@@ -71,7 +75,8 @@ impl BlockCollection {
 			parents: HashMap::new(),
 			head: None,
 			downloading_headers: HashSet::new(),
-			downloading_bodies: HashSet::new(),
+			downloading_bodies: HashMap::new(),
+			extended_from: None,
 		}
 	}
 
@@ -84,6 +89,7 @@ impl BlockCollection {
 		self.head = None;
 		self.downloading_headers.clear();
 		self.downloading_bodies.clear();
+		self.extended_from = None;
 	}
 
 	/// Reset collection for a new sync round with given subchain block hashes.
@@ -92,6 +98,15 @@ impl BlockCollection {
 		self.heads = hashes;
 	}
 
+	/// Add subchain heads for the next part of the chain while the blocks downloaded so far are still being completed.
+	pub fn extend_heads(&mut self, hashes: Vec<H256>) {
+		for h in hashes {
+			if !self.blocks.contains_key(&h) && !self.heads.contains(&h) && self.head != Some(h) {
+				self.heads.push(h);
+			}
+		}
+	}
+
 	/// Insert a set of headers into collection and advance subchain head pointers.
 	pub fn insert_headers(&mut self, headers: Vec<Bytes>) {
 		for h in headers.into_iter() {
@@ -103,6 +118,8 @@ impl BlockCollection {
 	}
 
 	/// Insert a collection of block bodies for previously downloaded headers.
+	/// Bodies that have already been delivered by another peer, or whose blocks have
+	/// been drained in the meantime, are ignored and counted as inserted.
 	pub fn insert_bodies(&mut self, bodies: Vec<Bytes>) -> usize {
 		let mut inserted = 0;
 		for b in bodies.into_iter() {
@@ -117,7 +134,9 @@ impl BlockCollection {
 	}
 
 	/// Returns a set of block hashes that require a body download. The returned set is marked as being downloaded.
-	pub fn needed_bodies(&mut self, count: usize, _ignore_downloading: bool) -> Vec<H256> {
+	/// With `ignore_downloading` only the bodies next in line for import are returned, including the ones
+	/// already requested from other peers.
+	pub fn needed_bodies(&mut self, count: usize, ignore_downloading: bool) -> Vec<H256> {
 		if self.head.is_none() {
 			return Vec::new();
 		}
@@ -127,21 +146,24 @@ impl BlockCollection {
 			head = self.parents.get(&head.unwrap()).cloned();
 			if let Some(head) = head {
 				match self.blocks.get(&head) {
-					Some(block) if block.body.is_none() && !self.downloading_bodies.contains(&head) => {
-						self.downloading_bodies.insert(head.clone());
+					Some(block) if block.body.is_none() && (ignore_downloading || !self.downloading_bodies.contains_key(&head)) => {
+						*self.downloading_bodies.entry(head.clone()).or_insert(0) += 1;
 						needed_bodies.push(head.clone());
 					}
 					_ => (),
 				}
 			}
 		}
+		if ignore_downloading {
+			return needed_bodies;
+		}
 		for h in self.header_ids.values() {
 			if needed_bodies.len() >= count {
 				break;
 			}
-			if !self.downloading_bodies.contains(h) {
+			if !self.downloading_bodies.contains_key(h) && self.blocks.get(h).map_or(false, |b| b.body.is_none()) {
 				needed_bodies.push(h.clone());
-				self.downloading_bodies.insert(h.clone());
+				self.downloading_bodies.insert(h.clone(), 1);
 			}
 		}
 		needed_bodies
@@ -168,9 +190,40 @@ impl BlockCollection {
 		self.downloading_headers.remove(hash);
 	}
 
-	/// Unmark a block body as being downloaded.
+	/// Unmark a block body as being downloaded by a single request.
 	pub fn clear_body_download(&mut self, hash: &H256) {
-		self.downloading_bodies.remove(hash);
+		let done = match self.downloading_bodies.get_mut(hash) {
+			Some(requests) => {
+				*requests -= 1;
+				*requests == 0
+			},
+			None => false,
+		};
+		if done {
+			self.downloading_bodies.remove(hash);
+		}
+	}
+
+	/// Returns the head of the last subchain to request the next subchain heads from.
+	/// Each head is returned only once, the last subchain has to advance before it is extended again.
+	pub fn needed_heads(&mut self) -> Option<H256> {
+		match self.heads.last() {
+			Some(h) if self.extended_from.as_ref() != Some(h) => {
+				self.extended_from = Some(h.clone());
+				Some(h.clone())
+			},
+			_ => None,
+		}
+	}
+
+	/// Allow the head of the last subchain to be extended again after a failed request.
+	pub fn clear_heads_download(&mut self) {
+		self.extended_from = None;
+	}
+
+	/// Number of downloaded blocks not yet drained.
+	pub fn block_count(&self) -> usize {
+		self.blocks.len()
 	}
 
 	/// Get a valid chain of blocks ordered in descending order and ready for importing into blockchain.
@@ -208,7 +261,9 @@ impl BlockCollection {
 			}
 		}
 		for h in hashes {
-			self.blocks.remove(&h);
+			if let Some(header_id) = self.blocks.remove(&h).and_then(|b| b.header_id) {
+				self.header_ids.remove(&header_id);
+			}
 		}
 		trace!("Drained {} blocks, new head :{:?}", drained.len(), self.head);
 		drained
@@ -237,7 +292,7 @@ impl BlockCollection {
 
 	/// Check if given block hash is marked as being downloaded.
 	pub fn is_downloading(&self, hash: &H256) -> bool {
-		self.downloading_headers.contains(hash) || self.downloading_bodies.contains(hash)
+		self.downloading_headers.contains(hash) || self.downloading_bodies.contains_key(hash)
 	}
 
 	fn insert_body(&mut self, b: Bytes) -> Result<(), NetworkError> {
@@ -251,9 +306,12 @@ impl BlockCollection {
 		};
 		match self.header_ids.get(&header_id).cloned() {
 			Some(h) => {
-				self.header_ids.remove(&header_id);
 				self.downloading_bodies.remove(&h);
 				match self.blocks.get_mut(&h) {
+					Some(ref block) if block.body.is_some() => {
+						trace!(target: "sync", "Got duplicate body {}", h);
+						Ok(())
+					},
 					Some(ref mut block) => {
 						trace!(target: "sync", "Got body {}", h);
 						block.body = Some(body.as_raw().to_vec());
@@ -266,8 +324,9 @@ impl BlockCollection {
 				}
 			}
 			None => {
+				// bodies next in line are requested from several peers, the late ones arrive after the block is drained.
 				trace!(target: "sync", "Ignored unknown/stale block body");
-				Err(NetworkError::BadProtocol)
+				Ok(())
 			}
 		}
 	}
@@ -289,6 +348,7 @@ impl BlockCollection {
 		let mut block = SyncBlock {
 			header: header,
 			body: None,
+			header_id: None,
 		};
 		let header_id = HeaderId {
 			transactions_root: info.transactions_root().clone(),
@@ -302,7 +362,8 @@ impl BlockCollection {
 			block.body = Some(body_stream.out());
 		}
 		else {
-			self.header_ids.insert(header_id, hash.clone());
+			self.header_ids.insert(header_id.clone(), hash.clone());
+			block.header_id = Some(header_id);
 		}
 
 		self.parents.insert(info.parent_hash().clone(), hash.clone());
@@ -357,7 +418,8 @@ mod test {
 		bc.header_ids.is_empty() &&
 		bc.head.is_none() &&
 		bc.downloading_headers.is_empty() &&
-		bc.downloading_bodies.is_empty()
+		bc.downloading_bodies.is_empty() &&
+		bc.extended_from.is_none()
 	}
 
 	#[test]
@@ -466,5 +528,75 @@ mod test {
 		bc.insert_headers(headers[0..1].to_vec());
 		assert_eq!(bc.drain().len(), 2);
 	}
+
+	#[test]
+	fn extend_heads() {
+		let mut bc = BlockCollection::new();
+		let client = TestBlockChainClient::new();
+		let nblocks = 200;
+		client.add_blocks(nblocks, EachBlockWith::Nothing);
+		let blocks: Vec<_> = (0 .. nblocks).map(|i| (&client as &BlockChainClient).block(BlockID::Number(i as BlockNumber)).unwrap()).collect();
+		let headers: Vec<_> = blocks.iter().map(|b| Rlp::new(b).at(0).as_raw().to_vec()).collect();
+		let hashes: Vec<_> = headers.iter().map(|h| HeaderView::new(h).sha3()).collect();
+		let heads: Vec<_> = hashes.iter().enumerate().filter_map(|(i, h)| if i % 20 == 0 && i < 100 { Some(h.clone()) } else { None }).collect();
+		bc.reset_to(heads);
+
+		bc.insert_headers(headers[0..10].to_vec());
+		assert_eq!(&bc.drain()[..], &blocks[0..10]);
+		assert_eq!(Some(hashes[80]), bc.needed_heads());
+		assert_eq!(None, bc.needed_heads());
+
+		bc.extend_heads(vec![hashes[9], hashes[80], hashes[100], hashes[120]]);
+		assert_eq!(bc.heads, vec![hashes[9], hashes[20], hashes[40], hashes[60], hashes[80], hashes[100], hashes[120]]);
+		assert_eq!(Some(hashes[120]), bc.needed_heads());
+
+		bc.insert_headers(headers[80..125].to_vec());
+		assert_eq!(bc.heads, vec![hashes[9], hashes[20], hashes[40], hashes[60], hashes[124]]);
+		assert_eq!(Some(hashes[124]), bc.needed_heads());
+		assert_eq!(None, bc.needed_heads());
+
+		// a failed request allows another peer to look ahead from the same head
+		bc.clear_heads_download();
+		assert_eq!(Some(hashes[124]), bc.needed_heads());
+	}
+
+	#[test]
+	fn requests_bodies_next_in_line_again() {
+		let mut bc = BlockCollection::new();
+		let client = TestBlockChainClient::new();
+		let nblocks = 10;
+		client.add_blocks(nblocks, EachBlockWith::Uncle);
+		let blocks: Vec<_> = (0 .. nblocks).map(|i| (&client as &BlockChainClient).block(BlockID::Number(i as BlockNumber)).unwrap()).collect();
+		let headers: Vec<_> = blocks.iter().map(|b| Rlp::new(b).at(0).as_raw().to_vec()).collect();
+		let hashes: Vec<_> = headers.iter().map(|h| HeaderView::new(h).sha3()).collect();
+		let bodies: Vec<_> = blocks.iter().map(|b| {
+			let block = Rlp::new(b);
+			let mut body = RlpStream::new_list(2);
+			body.append_raw(block.at(1).as_raw(), 1);
+			body.append_raw(block.at(2).as_raw(), 1);
+			body.out()
+		}).collect();
+		bc.reset_to(vec![hashes[0].clone()]);
+		bc.insert_headers(headers.clone());
+		assert_eq!(bc.drain().len(), 1);
+
+		assert_eq!(bc.needed_bodies(3, false), hashes[1..4].to_vec());
+		assert_eq!(bc.needed_bodies(3, false), hashes[4..7].to_vec());
+		// bodies in flight are only requested again with `ignore_downloading`
+		assert_eq!(bc.needed_bodies(2, false), hashes[7..9].to_vec());
+		assert_eq!(bc.needed_bodies(2, true), hashes[1..3].to_vec());
+
+		bc.clear_body_download(&hashes[2]);
+		assert!(bc.is_downloading(&hashes[2]));
+		bc.clear_body_download(&hashes[2]);
+		assert!(!bc.is_downloading(&hashes[2]));
+
+		// the body delivered by the slower peer is not treated as invalid
+		assert_eq!(bc.insert_bodies(vec![bodies[1].clone()]), 1);
+		assert_eq!(bc.insert_bodies(vec![bodies[1].clone()]), 1);
+		assert_eq!(&bc.drain()[..], &blocks[1..2]);
+		assert_eq!(bc.insert_bodies(vec![bodies[1].clone()]), 1);
+		assert_eq!(bc.needed_bodies(2, true), hashes[2..4].to_vec());
+	}
 }
 
@@ -59,6 +59,13 @@
 ///  	Request block bodies for the hashes in the set.
 /// Else
 /// 	Find an element in S which is  not being downloaded by other peers. If found: Request M headers starting from the element.
+/// Else if H is smaller than the download ahead limit and the last element of S has not been extended yet:
+/// 	Request N/M headers with interval M+1 starting from the last element of S.
+/// Else
+/// 	Request bodies for up to 256 blocks following l which are not in B, even if they are being downloaded by other peers.
+///
+/// On `BlockHeaders(R)` for the N/M headers request:
+/// 	Add the headers from R which are not in H to S.
 ///
 /// On `BlockHeaders(R)`:
 /// If R is empty remove current peer from P and restart.
@@ -70,6 +77,8 @@
 /// 	Add bodies with a matching header in H to B.
 /// 	Go to `CollectBlocks`.
 ///
+/// Headers and bodies received while the block queue is full are added to H and B and collected once the queue has room.
+///
 /// `CollectBlocks`:
 /// Find a chain of blocks C in H starting from h where h’s parent equals to l. The chain ends with the first block which does not have a body in B.
 /// Add all blocks from the chain to the block queue. Remove them from H and B. Set l to the hash of the last block from C.
@@ -77,10 +86,27 @@
 /// If H is empty and S contains a single element set s to `ChainHead`.
 /// Restart.
 ///
+/// Workflow for fast sync (`StatePivot` and `StateData` states).
+/// Only used on an empty database with the fast sync method.
+/// Request 65 headers going back from a peer's best block. The oldest one is the pivot block candidate.
+/// Its total difficulty is the peer's one minus the difficulties of the blocks in between.
+/// Ask other peers for their header with the candidate's number. Once `PIVOT_PEERS` peers agree on it the
+/// candidate becomes the pivot block. If more peers return a different block than agree on the candidate,
+/// drop it and deactivate the peer which proposed it.
+/// Set s to `StateData` and download the pivot block body, its receipts and the state trie
+/// of the pivot block with `GetNodeData` from all peers. Nodes are passed on to the snapshot
+/// service for restoration as soon as their sub-trie is complete.
+/// If the state is no longer served, pick a new pivot block, keeping complete sub-tries.
+/// Once done, hand the pivot block to the snapshot service, set s to `SnapshotWaiting` and
+/// continue with the regular sync from the pivot block once the restoration is finished.
+///
 /// All other messages are ignored.
 /// Workflow for Idle state.
 /// On `NewBlock`:
 /// 	Import the block. If the block is unknown set s to `ChainHead` and restart.
+/// On `NewBlockHeader`:
+/// 	If the parent is known request the body from the announcing peer and import the block once it arrives.
+/// 	Otherwise set s to `ChainHead` and restart.
 /// On `NewHashes`:
 /// 	Set s to `ChainHead` and restart.
 ///
@@ -96,25 +122,49 @@ use ethcore::header::{BlockNumber, Header as BlockHeader};
 use ethcore::client::{BlockChainClient, BlockStatus, BlockID, BlockChainInfo, BlockImportError};
 use ethcore::error::*;
 use ethcore::block::Block;
-use ethcore::snapshot::{ManifestData, RestorationStatus};
+use ethcore::snapshot::{self, ManifestData, RestorationStatus};
 use sync_io::SyncIo;
 use time;
-use super::SyncConfig;
+use super::{SyncConfig, SyncMethod, PeerInfo as PeerInfoDigest};
 use blocks::BlockCollection;
 use snapshot::{Snapshot, ChunkType};
+use state_sync::StateSync;
 use rand::{thread_rng, Rng};
 
 known_heap_size!(0, PeerInfo);
 
 type PacketDecodeError = DecoderError;
 
+/// Bytes served to a peer within the current budget window.
+struct ServeBudget {
+	/// Start of the current window.
+	window_start: f64,
+	/// Bytes served since the window start.
+	served: usize,
+}
+
 const MAX_BODIES_TO_SEND: usize = 256;
 const MAX_HEADERS_TO_SEND: usize = 512;
 const MAX_NODE_DATA_TO_SEND: usize = 1024;
 const MAX_RECEIPTS_TO_SEND: usize = 1024;
 const MAX_RECEIPTS_HEADERS_TO_SEND: usize = 256;
+/// Soft limit on the size of a single response to a peer. Data is not added once the limit is exceeded.
+const PAYLOAD_SOFT_LIMIT: usize = 2 * 1024 * 1024;
+/// Response size soft limit used while the upload rate exceeds the configured limit.
+const LIMITED_PAYLOAD_SOFT_LIMIT: usize = 64 * 1024;
+/// Soft limit on the number of bytes served to a single peer within `SERVE_BUDGET_WINDOW`.
+const SERVE_BUDGET: usize = 16 * 1024 * 1024;
+/// Length of the serving budget window in seconds.
+const SERVE_BUDGET_WINDOW: f64 = 10f64;
 const MAX_HEADERS_TO_REQUEST: usize = 128;
 const MAX_BODIES_TO_REQUEST: usize = 128;
+const MAX_NODE_DATA_TO_REQUEST: usize = 384;
+/// Distance of the fast sync pivot block from a peer's best block.
+const PIVOT_DISTANCE: usize = 64;
+/// Number of peers that must agree on the fast sync pivot block, including the one which proposed it.
+const PIVOT_PEERS: usize = 3;
+/// Number of consecutive node data responses without any requested node after which a new pivot block is chosen.
+const MAX_EMPTY_NODE_DATA_RESPONSES: usize = 16;
 const MIN_PEERS_PROPAGATION: usize = 4;
 const MAX_PEERS_PROPAGATION: usize = 128;
 const MAX_PEER_LAG_PROPAGATION: BlockNumber = 20;
@@ -141,12 +191,25 @@ const GET_SNAPSHOT_MANIFEST_PACKET: u8 = 0x11;
 const SNAPSHOT_MANIFEST_PACKET: u8 = 0x12;
 const GET_SNAPSHOT_DATA_PACKET: u8 = 0x13;
 const SNAPSHOT_DATA_PACKET: u8 = 0x14;
+const NEW_BLOCK_HEADER_PACKET: u8 = 0x15;
+const PRIVATE_TRANSACTION_PACKET: u8 = 0x16;
+const SIGNED_PRIVATE_TRANSACTION_PACKET: u8 = 0x17;
+
+/// Lowest protocol version that supports snapshot sync.
+const SNAPSHOT_PROTOCOL_VERSION: u32 = 64;
+/// Lowest protocol version that supports header-first block announcements.
+const HEADER_FIRST_PROTOCOL_VERSION: u32 = 65;
+/// Lowest protocol version that supports private transactions.
+const PRIVATE_TRANSACTIONS_PROTOCOL_VERSION: u32 = 66;
 
 const HEADERS_TIMEOUT_SEC: f64 = 15f64;
 const BODIES_TIMEOUT_SEC: f64 = 5f64;
 const FORK_HEADER_TIMEOUT_SEC: f64 = 3f64;
 const SNAPSHOT_MANIFEST_TIMEOUT_SEC: f64 = 3f64;
 const SNAPSHOT_DATA_TIMEOUT_SEC: f64 = 10f64;
+const NEW_BLOCK_BODY_TIMEOUT_SEC: f64 = 3f64;
+const RECEIPTS_TIMEOUT_SEC: f64 = 10f64;
+const NODE_DATA_TIMEOUT_SEC: f64 = 10f64;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 /// Sync state
@@ -157,6 +220,10 @@ pub enum SyncState {
 	SnapshotData,
 	/// Waiting for snapshot restoration to complete
 	SnapshotWaiting,
+	/// Downloading fast sync pivot block headers
+	StatePivot,
+	/// Downloading pivot block state trie, body and receipts
+	StateData,
 	/// Downloading subchain heads
 	ChainHead,
 	/// Initial chain sync complete. Waiting for new packets
@@ -198,6 +265,12 @@ pub struct SyncStatus {
 	pub num_snapshot_chunks: usize,
 	/// Snapshot chunks downloaded
 	pub snapshot_chunks_done: usize,
+	/// State trie nodes downloaded
+	pub state_nodes_downloaded: usize,
+	/// State trie nodes requested but not yet written
+	pub state_nodes_pending: usize,
+	/// Estimated time to finish downloading the current sync stage, in seconds
+	pub estimated_time_remaining: Option<u64>,
 }
 
 impl SyncStatus {
@@ -226,6 +299,12 @@ enum PeerAsking {
 	Heads,
 	SnapshotManifest,
 	SnapshotData,
+	NewBlockBody,
+	PivotHeaders,
+	PivotConfirmation,
+	PivotBody,
+	PivotReceipts,
+	NodeData,
 }
 
 #[derive(Clone, Eq, PartialEq)]
@@ -261,6 +340,8 @@ struct PeerInfo {
 	asking_hash: Option<H256>,
 	/// Holds requested snapshot chunk hash if any.
 	asking_snapshot_data: Option<H256>,
+	/// Holds requested state trie node hashes if any.
+	asking_nodes: Vec<H256>,
 	/// Request timestamp
 	ask_time: f64,
 	/// Holds a set of transactions recently sent to this peer to avoid spamming.
@@ -275,6 +356,34 @@ struct PeerInfo {
 	snapshot_number: Option<BlockNumber>,
 }
 
+/// Fast sync pivot block.
+struct Pivot {
+	/// Block hash.
+	hash: H256,
+	/// Header RLP.
+	header: Bytes,
+	/// Total difficulty of the parent block.
+	parent_total_difficulty: U256,
+	/// Block RLP once the body is downloaded.
+	block: Option<Bytes>,
+	/// Receipts RLP once downloaded.
+	receipts: Option<Bytes>,
+	/// Peer which proposed the pivot block.
+	proposer: PeerId,
+	/// Peers which have the pivot block in their canonical chain.
+	confirmed_by: HashSet<PeerId>,
+	/// Peers which have a different block with the same number in their canonical chain.
+	disputed_by: HashSet<PeerId>,
+}
+
+/// Header of a freshly sealed block announced ahead of its body.
+struct AnnouncedHeader {
+	/// Block hash.
+	hash: H256,
+	/// Header RLP.
+	header: Bytes,
+}
+
 impl PeerInfo {
 	fn can_sync(&self) -> bool {
 		self.confirmation == ForkConfirmation::Confirmed && !self.expired
@@ -309,7 +418,7 @@ pub struct ChainSync {
 	/// Last propagated block number
 	last_sent_block_number: BlockNumber,
 	/// Max blocks to download ahead
-	_max_download_ahead_blocks: usize,
+	max_download_ahead_blocks: usize,
 	/// Number of blocks imported this round
 	imported_this_round: Option<usize>,
 	/// Block parents imported this round (hash, parent)
@@ -320,6 +429,26 @@ pub struct ChainSync {
 	fork_block: Option<(BlockNumber, H256)>,
 	/// Snapshot downloader.
 	snapshot: Snapshot,
+	/// Initial sync method.
+	sync_method: SyncMethod,
+	/// State trie downloader.
+	state_sync: StateSync,
+	/// Fast sync pivot block.
+	pivot: Option<Pivot>,
+	/// Consecutive node data responses without any requested node.
+	empty_node_data_responses: usize,
+	/// Announced headers whose bodies are being requested, by announcing peer.
+	announced_headers: HashMap<PeerId, AnnouncedHeader>,
+	/// Relay transactions during major sync.
+	propagate_transactions_while_syncing: bool,
+	/// Announce blocks during major sync.
+	propagate_blocks_while_syncing: bool,
+	/// Time and last imported block when block download started.
+	block_download_start: Option<(f64, BlockNumber)>,
+	/// Time snapshot chunk download started.
+	snapshot_download_start: Option<f64>,
+	/// Bytes served to each peer. Kept outside of the sync state lock so that requests can be served concurrently.
+	serve_budgets: Mutex<HashMap<PeerId, ServeBudget>>,
 }
 
 type RlpResponseResult = Result<Option<(PacketId, RlpStream)>, PacketDecodeError>;
@@ -341,11 +470,27 @@ impl ChainSync {
 			last_sent_block_number: 0,
 			imported_this_round: None,
 			round_parents: VecDeque::new(),
-			_max_download_ahead_blocks: max(MAX_HEADERS_TO_REQUEST, config.max_download_ahead_blocks),
+			max_download_ahead_blocks: max(MAX_HEADERS_TO_REQUEST, config.max_download_ahead_blocks),
 			network_id: config.network_id,
 			fork_block: config.fork_block,
 			snapshot: Snapshot::new(),
-		}
+			// warp and fast sync restore a state which a relaying node doesn't keep
+			sync_method: if config.relay { SyncMethod::Full } else { config.sync_method },
+			state_sync: StateSync::new(),
+			pivot: None,
+			empty_node_data_responses: 0,
+			announced_headers: HashMap::new(),
+			propagate_transactions_while_syncing: config.propagate_transactions_while_syncing || config.relay,
+			propagate_blocks_while_syncing: config.propagate_blocks_while_syncing,
+			block_download_start: None,
+			snapshot_download_start: None,
+			serve_budgets: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Returns true if the initial sync is in progress.
+	fn is_major_syncing(&self) -> bool {
+		self.state != SyncState::Idle && self.state != SyncState::NewBlocks
 	}
 
 	/// @returns Synchonization status
@@ -363,6 +508,9 @@ impl ChainSync {
 			num_active_peers: self.peers.values().filter(|p| p.is_allowed() && p.asking != PeerAsking::Nothing).count(),
 			num_snapshot_chunks: self.snapshot.total_chunks(),
 			snapshot_chunks_done: self.snapshot.done_chunks(),
+			state_nodes_downloaded: self.state_sync.downloaded(),
+			state_nodes_pending: self.state_sync.pending(),
+			estimated_time_remaining: self.estimate_time_remaining(),
 			mem_used:
 				self.blocks.heap_size()
 				+ self.peers.heap_size_of_children()
@@ -370,6 +518,47 @@ impl ChainSync {
 		}
 	}
 
+	/// Estimate the time left to finish block or snapshot download from the progress made so far.
+	fn estimate_time_remaining(&self) -> Option<u64> {
+		let now = time::precise_time_s();
+		match self.state {
+			SyncState::SnapshotData => self.snapshot_download_start.and_then(|start|
+				estimate_remaining(now - start, self.snapshot.done_chunks() as u64, self.snapshot.total_chunks() as u64)),
+			SyncState::ChainHead | SyncState::Blocks | SyncState::Waiting => match (self.block_download_start, self.highest_block) {
+				(Some((start, first)), Some(highest)) if self.last_imported_block > first && highest > first =>
+					estimate_remaining(now - start, self.last_imported_block - first, highest - first),
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+
+	/// Returns information on peers connections
+	pub fn peers(&self, io: &SyncIo) -> Vec<PeerInfoDigest> {
+		self.peers.iter()
+			.filter_map(|(&peer_id, ref peer_data)|
+				io.peer_session_info(peer_id).map(|session_info|
+					PeerInfoDigest {
+						id: session_info.id.map(|id| id.hex()),
+						client_version: session_info.client_version,
+						capabilities: session_info.peer_capabilities.into_iter()
+							.map(|c| format!("{}/{}", String::from_utf8_lossy(&c.protocol), c.version))
+							.collect(),
+						remote_address: session_info.remote_address,
+						local_address: session_info.local_address,
+						ping_ms: session_info.ping_ms,
+						packets_received: session_info.packets_received,
+						packets_sent: session_info.packets_sent,
+						bytes_received: session_info.bytes_received,
+						bytes_sent: session_info.bytes_sent,
+						eth_version: peer_data.protocol_version,
+						eth_head: peer_data.latest_hash,
+						eth_difficulty: peer_data.difficulty,
+					})
+			)
+			.collect()
+	}
+
 	/// Abort all sync activity
 	pub fn abort(&mut self, io: &mut SyncIo) {
 		self.restart(io);
@@ -381,12 +570,15 @@ impl ChainSync {
 	fn reset(&mut self, io: &mut SyncIo) {
 		self.blocks.clear();
 		self.snapshot.clear();
-		if self.state == SyncState::SnapshotData {
+		if self.state == SyncState::SnapshotData || self.state == SyncState::StatePivot || self.state == SyncState::StateData {
 			debug!(target:"sync", "Aborting snapshot restore");
 			io.snapshot_service().abort_restore();
 		}
+		self.state_sync.clear();
+		self.pivot = None;
 		for (_, ref mut p) in &mut self.peers {
 			p.asking_blocks.clear();
+			p.asking_nodes.clear();
 			p.asking_hash = None;
 			// mark any pending requests as expired
 			if p.asking != PeerAsking::Nothing && p.is_allowed() {
@@ -414,6 +606,10 @@ impl ChainSync {
 			trace!(target: "sync", "No more active peers");
 			if self.state == SyncState::ChainHead {
 				self.complete_sync(io);
+			} else if self.state == SyncState::StatePivot || self.state == SyncState::StateData {
+				// keep the downloaded state and try again with a new pivot block
+				self.active_peers = self.peers.keys().cloned().collect();
+				self.switch_pivot(io);
 			} else {
 				self.restart(io);
 			}
@@ -426,6 +622,24 @@ impl ChainSync {
 		self.state = SyncState::SnapshotManifest;
 	}
 
+	fn start_state_sync(&mut self, io: &mut SyncIo, peer_id: PeerId) {
+		trace!(target: "sync", "Starting state sync with {}", peer_id);
+		self.state_sync.clear();
+		self.pivot = None;
+		self.empty_node_data_responses = 0;
+		io.snapshot_service().begin_trie_restore();
+		self.request_pivot_headers(io, peer_id);
+	}
+
+	/// Pick a new pivot block, keeping the state downloaded so far.
+	fn switch_pivot(&mut self, io: &mut SyncIo) {
+		debug!(target: "sync", "State of pivot block is not available, switching pivot");
+		self.pivot = None;
+		self.empty_node_data_responses = 0;
+		self.state = SyncState::StatePivot;
+		self.continue_sync(io);
+	}
+
 	/// Restart sync after bad block has been detected. May end up re-downloading up to QUEUE_SIZE blocks
 	fn restart_on_bad_block(&mut self, io: &mut SyncIo) {
 		// Do not assume that the block queue/chain still has our last_imported_block
@@ -453,8 +667,9 @@ impl ChainSync {
 			expired: false,
 			confirmation: if self.fork_block.is_none() { ForkConfirmation::Confirmed } else { ForkConfirmation::Unconfirmed },
 			asking_snapshot_data: None,
-			snapshot_hash: if protocol_version == 64 { Some(try!(r.val_at(5))) } else { None },
-			snapshot_number: if protocol_version == 64 { Some(try!(r.val_at(6))) } else { None },
+			asking_nodes: Vec::new(),
+			snapshot_hash: if protocol_version >= SNAPSHOT_PROTOCOL_VERSION { Some(try!(r.val_at(5))) } else { None },
+			snapshot_number: if protocol_version >= SNAPSHOT_PROTOCOL_VERSION { Some(try!(r.val_at(6))) } else { None },
 		};
 
 		trace!(target: "sync", "New peer {} (protocol: {}, network: {:?}, difficulty: {:?}, latest:{}, genesis:{})", peer_id, peer.protocol_version, peer.network_id, peer.difficulty, peer.latest_hash, peer.genesis);
@@ -493,6 +708,12 @@ impl ChainSync {
 	#[cfg_attr(feature="dev", allow(cyclomatic_complexity))]
 	/// Called by peer once it has new block headers during sync
 	fn on_peer_block_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::PivotHeaders) {
+			return self.on_pivot_headers(io, peer_id, r);
+		}
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::PivotConfirmation) {
+			return self.on_pivot_confirmation(io, peer_id, r);
+		}
 		let confirmed = match self.peers.get_mut(&peer_id) {
 			Some(ref mut peer) if peer.asking == PeerAsking::ForkHeader => {
 				let item_count = r.item_count();
@@ -519,8 +740,11 @@ impl ChainSync {
 			return Ok(());
 		}
 
+		// subchain heads requested while downloading blocks extend the current round
+		let extending = (self.state == SyncState::Blocks || self.state == SyncState::Waiting)
+			&& self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::Heads);
 		self.clear_peer_download(peer_id);
-		let expected_asking = if self.state == SyncState::ChainHead { PeerAsking::Heads } else { PeerAsking::BlockHeaders };
+		let expected_asking = if self.state == SyncState::ChainHead || extending { PeerAsking::Heads } else { PeerAsking::BlockHeaders };
 		let expected_hash = self.peers.get(&peer_id).and_then(|p| p.asking_hash);
 		if !self.reset_peer_asking(peer_id, expected_asking) || expected_hash.is_none() {
 			trace!(target: "sync", "{}: Ignored unexpected headers", peer_id);
@@ -534,12 +758,7 @@ impl ChainSync {
 			self.continue_sync(io);
 			return Ok(());
 		}
-		if self.state == SyncState::Waiting {
-			trace!(target: "sync", "Ignored block headers while waiting");
-			self.continue_sync(io);
-			return Ok(());
-		}
-		if item_count == 0 && (self.state == SyncState::Blocks || self.state == SyncState::NewBlocks) {
+		if item_count == 0 && !extending && (self.state == SyncState::Blocks || self.state == SyncState::NewBlocks) {
 			self.deactivate_peer(io, peer_id); //TODO: is this too harsh?
 			self.continue_sync(io);
 			return Ok(());
@@ -593,12 +812,16 @@ impl ChainSync {
 			io.disable_peer(peer_id);
 		}
 
-		if headers.is_empty() {
+		if headers.is_empty() && !extending {
 			// Peer does not have any new subchain heads, deactivate it nd try with another
 			trace!(target: "sync", "{} Disabled for no data", peer_id);
 			io.disable_peer(peer_id);
 		}
 		match self.state {
+			SyncState::Blocks | SyncState::Waiting if extending => {
+				trace!(target: "sync", "Received {} more subchain heads", hashes.len());
+				self.blocks.extend_heads(hashes);
+			},
 			SyncState::ChainHead => {
 				if headers.is_empty() {
 					// peer is not on our chain
@@ -609,6 +832,9 @@ impl ChainSync {
 					// TODO: validate heads better. E.g. check that there is enough distance between blocks.
 					trace!(target: "sync", "Received {} subchain heads, proceeding to download", headers.len());
 					self.blocks.reset_to(hashes);
+					if self.block_download_start.is_none() {
+						self.block_download_start = Some((time::precise_time_s(), self.last_imported_block));
+					}
 					self.state = SyncState::Blocks;
 				}
 			},
@@ -629,6 +855,12 @@ impl ChainSync {
 
 	/// Called by peer once it has new block bodies
 	fn on_peer_block_bodies(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::NewBlockBody) {
+			return self.on_peer_new_block_body(io, peer_id, r);
+		}
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::PivotBody) {
+			return self.on_pivot_body(io, peer_id, r);
+		}
 		self.clear_peer_download(peer_id);
 		self.reset_peer_asking(peer_id, PeerAsking::BlockBodies);
 		let item_count = r.item_count();
@@ -639,9 +871,6 @@ impl ChainSync {
 		else if self.state != SyncState::Blocks && self.state != SyncState::NewBlocks && self.state != SyncState::Waiting {
 			trace!(target: "sync", "Ignored unexpected block bodies");
 		}
-		else if self.state == SyncState::Waiting {
-			trace!(target: "sync", "Ignored block bodies while waiting");
-		}
 		else
 		{
 			let mut bodies = Vec::with_capacity(item_count);
@@ -727,6 +956,125 @@ impl ChainSync {
 		Ok(())
 	}
 
+	/// Handles `NewBlockHeader` packet. Requests the body from the announcing peer if the block extends a known chain.
+	fn on_peer_new_block_header(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if !self.peers.get(&peer_id).map_or(false, |p| p.can_sync()) {
+			trace!(target: "sync", "Ignoring new block header from unconfirmed peer {}", peer_id);
+			return Ok(());
+		}
+		let header_rlp = try!(r.at(0));
+		let difficulty: U256 = try!(r.val_at(1));
+		let header: BlockHeader = try!(header_rlp.as_val());
+		let hash = header.hash();
+		trace!(target: "sync", "{} -> NewBlockHeader ({})", peer_id, hash);
+		if header.number() > self.highest_block.unwrap_or(0) {
+			self.highest_block = Some(header.number());
+		}
+		{
+			let peer = self.peers.get_mut(&peer_id).expect("peer existence checked above; qed");
+			peer.latest_hash = hash.clone();
+			peer.latest_number = Some(header.number());
+			if peer.difficulty.map_or(true, |pd| difficulty > pd) {
+				peer.difficulty = Some(difficulty);
+			}
+		}
+		if self.last_imported_block > header.number() && self.last_imported_block - header.number() > MAX_NEW_BLOCK_AGE {
+			trace!(target: "sync", "Ignored ancient new block header {:?}", hash);
+			io.disable_peer(peer_id);
+			return Ok(());
+		}
+		if self.state != SyncState::Idle {
+			trace!(target: "sync", "NewBlockHeader ignored while syncing");
+			return Ok(());
+		}
+		match io.chain().block_status(BlockID::Hash(hash.clone())) {
+			BlockStatus::InChain | BlockStatus::Queued => {
+				trace!(target: "sync", "New block header already known {:?}", hash);
+				return Ok(());
+			},
+			BlockStatus::Bad => {
+				debug!(target: "sync", "Bad new block header {:?}", hash);
+				io.disable_peer(peer_id);
+				return Ok(());
+			},
+			BlockStatus::Unknown => (),
+		}
+		match io.chain().block_status(BlockID::Hash(header.parent_hash().clone())) {
+			BlockStatus::InChain | BlockStatus::Queued => (),
+			_ => {
+				trace!(target: "sync", "Received header {:?} with no known parent. Peer needs syncing...", hash);
+				self.sync_peer(io, peer_id, true);
+				return Ok(());
+			}
+		}
+		if self.peers.get(&peer_id).map_or(true, |p| p.asking != PeerAsking::Nothing) {
+			trace!(target: "sync", "{}: Busy, not requesting announced body {:?}", peer_id, hash);
+			return Ok(());
+		}
+		trace!(target: "sync", "{} <- GetBlockBodies: announced block {:?}", peer_id, hash);
+		let mut rlp = RlpStream::new_list(1);
+		rlp.append(&hash);
+		self.announced_headers.insert(peer_id, AnnouncedHeader { hash: hash, header: header_rlp.as_raw().to_vec() });
+		self.send_request(io, peer_id, PeerAsking::NewBlockBody, GET_BLOCK_BODIES_PACKET, rlp.out());
+		Ok(())
+	}
+
+	/// Called by peer once it has sent the body for a previously announced header. Assembles and imports the block.
+	fn on_peer_new_block_body(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::NewBlockBody);
+		let announced = match self.announced_headers.remove(&peer_id) {
+			Some(announced) => announced,
+			None => {
+				trace!(target: "sync", "{}: Ignored unexpected announced block body", peer_id);
+				return Ok(());
+			}
+		};
+		trace!(target: "sync", "{} -> BlockBodies for announced block {:?}", peer_id, announced.hash);
+		if r.item_count() != 1 {
+			trace!(target: "sync", "{}: Announced block body not returned", peer_id);
+			self.sync_peer(io, peer_id, true);
+			return Ok(());
+		}
+		let header: BlockHeader = try!(UntrustedRlp::new(&announced.header).as_val());
+		let body = try!(r.at(0));
+		let transactions = try!(body.at(0));
+		let uncles = try!(body.at(1));
+		let tx_root = ordered_trie_root(transactions.iter().map(|r| r.as_raw().to_vec()));
+		if tx_root != *header.transactions_root() || uncles.as_raw().sha3() != *header.uncles_hash() {
+			debug!(target: "sync", "{}: Body does not match announced header {:?}", peer_id, announced.hash);
+			io.disable_peer(peer_id);
+			return Ok(());
+		}
+		let mut block = RlpStream::new_list(3);
+		block.append_raw(&announced.header, 1);
+		block.append_raw(transactions.as_raw(), 1);
+		block.append_raw(uncles.as_raw(), 1);
+		match io.chain().import_block(block.out()) {
+			Err(BlockImportError::Import(ImportError::AlreadyInChain)) => {
+				trace!(target: "sync", "Announced block already in chain {:?}", announced.hash);
+			},
+			Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {
+				trace!(target: "sync", "Announced block already queued {:?}", announced.hash);
+			},
+			Ok(_) => {
+				if header.number() == self.last_imported_block + 1 {
+					self.last_imported_block = header.number();
+					self.last_imported_hash = announced.hash.clone();
+				}
+				trace!(target: "sync", "Announced block queued {:?} ({})", announced.hash, header.number());
+			},
+			Err(BlockImportError::Block(BlockError::UnknownParent(p))) => {
+				trace!(target: "sync", "Announced block with unknown parent ({:?}) {:?}", p, announced.hash);
+				self.sync_peer(io, peer_id, true);
+			},
+			Err(e) => {
+				debug!(target: "sync", "Bad announced block {:?} : {:?}", announced.hash, e);
+				io.disable_peer(peer_id);
+			}
+		};
+		Ok(())
+	}
+
 	/// Handles `NewHashes` packet. Initiates headers download for any unknown hashes.
 	fn on_peer_new_hashes(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
 		if !self.peers.get(&peer_id).map_or(false, |p| p.can_sync()) {
@@ -817,6 +1165,7 @@ impl ChainSync {
 		};
 		self.snapshot.reset_to(&manifest, &manifest_rlp.as_raw().sha3());
 		io.snapshot_service().begin_restore(manifest);
+		self.snapshot_download_start = Some(time::precise_time_s());
 		self.state = SyncState::SnapshotData;
 
 		// give a task to the same peer first.
@@ -882,12 +1231,320 @@ impl ChainSync {
 		Ok(())
 	}
 
+	/// Called when fast sync pivot block headers are downloaded from a peer.
+	fn on_pivot_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		let (expected_hash, peer_difficulty) = self.peers.get(&peer_id).map_or((None, None), |p| (p.asking_hash, p.difficulty));
+		if !self.reset_peer_asking(peer_id, PeerAsking::PivotHeaders) || self.state != SyncState::StatePivot {
+			trace!(target: "sync", "{}: Ignored unexpected pivot headers", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+
+		let item_count = min(r.item_count(), PIVOT_DISTANCE + 1);
+		trace!(target: "sync", "{} -> PivotHeaders ({} entries)", peer_id, item_count);
+		// Headers go back from the peer's best block, the last one is the pivot.
+		// Subtracting the difficulties from the peer's total difficulty gives the one of the pivot's parent.
+		let mut total_difficulty = peer_difficulty.unwrap_or_else(U256::zero);
+		let mut expected = expected_hash;
+		let mut head_number = None;
+		let mut pivot = None;
+		for i in 0..item_count {
+			let header: BlockHeader = try!(r.val_at(i));
+			if expected != Some(header.hash()) || *header.difficulty() > total_difficulty {
+				trace!(target: "sync", "{}: Invalid pivot headers", peer_id);
+				io.disable_peer(peer_id);
+				self.continue_sync(io);
+				return Ok(());
+			}
+			total_difficulty = total_difficulty - *header.difficulty();
+			expected = Some(header.parent_hash().clone());
+			head_number = head_number.or(Some(header.number()));
+			if i == PIVOT_DISTANCE && header.number() > 0 {
+				pivot = Some((header, try!(r.at(i)).as_raw().to_vec()));
+			}
+		}
+
+		match pivot {
+			Some((header, header_rlp)) => {
+				trace!(target: "sync", "{}: Proposed pivot block #{} ({})", peer_id, header.number(), header.hash());
+				let mut confirmed_by = HashSet::new();
+				confirmed_by.insert(peer_id);
+				self.pivot = Some(Pivot {
+					hash: header.hash(),
+					header: header_rlp,
+					parent_total_difficulty: total_difficulty,
+					block: None,
+					receipts: None,
+					proposer: peer_id,
+					confirmed_by: confirmed_by,
+					disputed_by: HashSet::new(),
+				});
+				self.start_pivot_download();
+			},
+			None if head_number.map_or(false, |n| n <= PIVOT_DISTANCE as BlockNumber) => {
+				debug!(target: "sync", "Chain is too short for fast sync, falling back to full sync");
+				io.snapshot_service().abort_restore();
+				self.sync_method = SyncMethod::Full;
+				self.state_sync.clear();
+				self.state = SyncState::Idle;
+			},
+			None => {
+				trace!(target: "sync", "{}: Not enough pivot headers", peer_id);
+				self.deactivate_peer(io, peer_id);
+			},
+		}
+
+		// give a task to the same peer first.
+		self.sync_peer(io, peer_id, false);
+		// give tasks to other peers
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called when a peer returns its header with the number of the fast sync pivot block candidate.
+	fn on_pivot_confirmation(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		let expected_hash = self.peers.get(&peer_id).and_then(|p| p.asking_hash);
+		if !self.reset_peer_asking(peer_id, PeerAsking::PivotConfirmation) || self.state != SyncState::StatePivot {
+			trace!(target: "sync", "{}: Ignored unexpected pivot confirmation", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+
+		let candidate = match self.pivot {
+			Some(ref pivot) if expected_hash == Some(pivot.hash) => Some(pivot.proposer),
+			_ => None,
+		};
+		let hash = if r.item_count() == 1 { Some(try!(r.val_at::<BlockHeader>(0)).hash()) } else { None };
+		match (candidate, hash) {
+			(None, _) => trace!(target: "sync", "{}: Pivot block candidate has changed", peer_id),
+			(Some(_), None) => {
+				trace!(target: "sync", "{}: Pivot block candidate is not available", peer_id);
+				self.deactivate_peer(io, peer_id);
+			},
+			(Some(_), Some(hash)) if expected_hash == Some(hash) => {
+				trace!(target: "sync", "{}: Confirmed pivot block {}", peer_id, hash);
+				if let Some(ref mut pivot) = self.pivot {
+					pivot.confirmed_by.insert(peer_id);
+				}
+				self.start_pivot_download();
+			},
+			(Some(proposer), Some(hash)) => {
+				debug!(target: "sync", "{}: Disagrees on the pivot block proposed by {} ({})", peer_id, proposer, hash);
+				let rejected = match self.pivot {
+					Some(ref mut pivot) => {
+						pivot.disputed_by.insert(peer_id);
+						pivot.disputed_by.len() > pivot.confirmed_by.len()
+					},
+					None => false,
+				};
+				if rejected {
+					debug!(target: "sync", "Pivot block proposed by {} is disputed, choosing another one", proposer);
+					self.pivot = None;
+					self.deactivate_peer(io, proposer);
+				}
+			},
+		}
+
+		self.sync_peer(io, peer_id, false);
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Start downloading the state of the pivot block candidate once enough peers agree on it.
+	fn start_pivot_download(&mut self) {
+		let (number, hash, state_root) = match self.pivot {
+			Some(ref pivot) if pivot.confirmed_by.len() >= PIVOT_PEERS => {
+				let header = HeaderView::new(&pivot.header);
+				(header.number(), pivot.hash, header.state_root())
+			},
+			_ => return,
+		};
+		debug!(target: "sync", "Downloading state of pivot block #{} ({})", number, hash);
+		self.state_sync.reset_to(&state_root);
+		self.empty_node_data_responses = 0;
+		self.state = SyncState::StateData;
+	}
+
+	/// Called when the fast sync pivot block body is downloaded from a peer.
+	fn on_pivot_body(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::PivotBody);
+		if self.state != SyncState::StateData {
+			trace!(target: "sync", "{}: Ignored unexpected pivot body", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		trace!(target: "sync", "{} -> PivotBody ({} entries)", peer_id, r.item_count());
+
+		let block = match self.pivot {
+			Some(ref pivot) if r.item_count() == 1 => {
+				let body = try!(r.at(0));
+				let header = HeaderView::new(&pivot.header);
+				let transactions = try!(body.at(0));
+				let uncles = try!(body.at(1));
+				let transactions_root = ordered_trie_root(transactions.iter().map(|t| t.as_raw().to_vec()));
+				if transactions_root == header.transactions_root() && uncles.as_raw().sha3() == header.uncles_hash() {
+					let mut block = RlpStream::new_list(3);
+					block.append_raw(&pivot.header, 1).append_raw(transactions.as_raw(), 1).append_raw(uncles.as_raw(), 1);
+					Some(block.out())
+				} else {
+					None
+				}
+			},
+			_ => None,
+		};
+
+		match block {
+			Some(block) => {
+				if let Some(ref mut pivot) = self.pivot {
+					pivot.block = Some(block);
+				}
+				self.complete_state_sync(io);
+			},
+			None => {
+				trace!(target: "sync", "{}: No valid pivot body", peer_id);
+				self.deactivate_peer(io, peer_id);
+			},
+		}
+
+		self.sync_peer(io, peer_id, false);
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called when the fast sync pivot block receipts are downloaded from a peer.
+	fn on_pivot_receipts(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if !self.reset_peer_asking(peer_id, PeerAsking::PivotReceipts) || self.state != SyncState::StateData {
+			trace!(target: "sync", "{}: Ignored unexpected receipts", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		trace!(target: "sync", "{} -> PivotReceipts ({} entries)", peer_id, r.item_count());
+
+		let receipts = match self.pivot {
+			Some(ref pivot) if r.item_count() == 1 => {
+				let receipts = try!(r.at(0));
+				let receipts_root = ordered_trie_root(receipts.iter().map(|r| r.as_raw().to_vec()));
+				if receipts_root == HeaderView::new(&pivot.header).receipts_root() {
+					Some(receipts.as_raw().to_vec())
+				} else {
+					None
+				}
+			},
+			_ => None,
+		};
+
+		match receipts {
+			Some(receipts) => {
+				if let Some(ref mut pivot) = self.pivot {
+					pivot.receipts = Some(receipts);
+				}
+				self.complete_state_sync(io);
+			},
+			None => {
+				trace!(target: "sync", "{}: No valid pivot receipts", peer_id);
+				self.deactivate_peer(io, peer_id);
+			},
+		}
+
+		self.sync_peer(io, peer_id, false);
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called when state trie nodes are downloaded from a peer.
+	fn on_node_data(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		let requested = self.peers.get_mut(&peer_id).map_or_else(Vec::new, |p| replace(&mut p.asking_nodes, Vec::new()));
+		if !self.reset_peer_asking(peer_id, PeerAsking::NodeData) || self.state != SyncState::StateData {
+			trace!(target: "sync", "{}: Ignored unexpected node data", peer_id);
+			self.state_sync.clear_node_download(&requested);
+			self.continue_sync(io);
+			return Ok(());
+		}
+
+		// check service status
+		match io.snapshot_service().status() {
+			RestorationStatus::Inactive | RestorationStatus::Failed => {
+				trace!(target: "sync", "{}: State restoration aborted", peer_id);
+				self.state = SyncState::Idle;
+				self.state_sync.clear();
+				self.pivot = None;
+				self.continue_sync(io);
+				return Ok(());
+			},
+			RestorationStatus::Ongoing { .. } => {},
+		}
+
+		trace!(target: "sync", "{} -> NodeData ({} entries)", peer_id, r.item_count());
+		let nodes: Result<Vec<Bytes>, _> = r.iter().map(|n| n.as_val()).collect();
+		match nodes.and_then(|nodes| self.state_sync.insert_nodes(&requested, &nodes)) {
+			Ok(0) => self.empty_node_data_responses += 1,
+			Ok(_) => self.empty_node_data_responses = 0,
+			Err(e) => {
+				trace!(target: "sync", "{}: Bad node data: {:?}", peer_id, e);
+				self.state_sync.clear_node_download(&requested);
+				io.disable_peer(peer_id);
+			},
+		}
+
+		for (address_hash, nodes) in self.state_sync.drain() {
+			io.snapshot_service().restore_trie_nodes(address_hash, nodes);
+		}
+
+		if self.empty_node_data_responses > MAX_EMPTY_NODE_DATA_RESPONSES {
+			self.switch_pivot(io);
+			return Ok(());
+		}
+
+		self.complete_state_sync(io);
+		// give a task to the same peer first.
+		self.sync_peer(io, peer_id, false);
+		// give tasks to other peers
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Hand the pivot block over to the snapshot service once it has been downloaded along with its state.
+	fn complete_state_sync(&mut self, io: &mut SyncIo) {
+		if self.state != SyncState::StateData || !self.state_sync.is_complete() {
+			return;
+		}
+		let (manifest, chunk) = match self.pivot {
+			Some(Pivot { ref hash, ref header, block: Some(ref block), receipts: Some(ref receipts), ref parent_total_difficulty, .. }) => {
+				let (chunk_hash, chunk) = snapshot::single_block_chunk(block, receipts, *parent_total_difficulty);
+				let header = HeaderView::new(header);
+				let manifest = ManifestData {
+					state_hashes: Vec::new(),
+					block_hashes: vec![chunk_hash],
+					state_root: header.state_root(),
+					block_number: header.number(),
+					block_hash: hash.clone(),
+				};
+				(manifest, chunk)
+			},
+			_ => return,
+		};
+
+		debug!(target: "sync", "State download complete, {} nodes. Restoring block #{}", self.state_sync.downloaded(), manifest.block_number);
+		io.snapshot_service().complete_trie_restore(manifest, chunk);
+		self.state_sync.clear();
+		self.pivot = None;
+		// wait for the restoration to complete
+		self.state = SyncState::SnapshotWaiting;
+	}
+
 	/// Called by peer when it is disconnecting
 	pub fn on_peer_aborting(&mut self, io: &mut SyncIo, peer: PeerId) {
 		trace!(target: "sync", "== Disconnecting {}: {}", peer, io.peer_info(peer));
+		// requests may have been served before the peer finished its status handshake.
+		self.serve_budgets.lock().remove(&peer);
 		if self.peers.contains_key(&peer) {
 			debug!(target: "sync", "Disconnected {}", peer);
+			if self.peers[&peer].asking == PeerAsking::Heads && (self.state == SyncState::Blocks || self.state == SyncState::Waiting) {
+				// let another peer look ahead for the next subchain heads
+				self.blocks.clear_heads_download();
+			}
 			self.clear_peer_download(peer);
+			self.announced_headers.remove(&peer);
 			self.peers.remove(&peer);
 			self.active_peers.remove(&peer);
 			self.continue_sync(io);
@@ -917,6 +1574,7 @@ impl ChainSync {
 			}
 		}
 		if self.state != SyncState::Waiting && self.state != SyncState::SnapshotWaiting
+			&& self.state != SyncState::StatePivot && self.state != SyncState::StateData
 			&& !self.peers.values().any(|p| p.asking != PeerAsking::Nothing && p.can_sync()) {
 			self.complete_sync(io);
 		}
@@ -926,12 +1584,14 @@ impl ChainSync {
 	fn complete_sync(&mut self, io: &mut SyncIo) {
 		trace!(target: "sync", "Sync complete");
 		self.reset(io);
+		self.block_download_start = None;
+		self.snapshot_download_start = None;
 		self.state = SyncState::Idle;
 	}
 
 	/// Enter waiting state
-	fn pause_sync(&mut self) {
-		trace!(target: "sync", "Block queue full, pausing sync");
+	fn pause_sync(&mut self, reason: &str) {
+		trace!(target: "sync", "{}, pausing sync", reason);
 		self.state = SyncState::Waiting;
 	}
 
@@ -941,7 +1601,7 @@ impl ChainSync {
 			trace!(target: "sync", "Skipping deactivated peer");
 			return;
 		}
-		let (peer_latest, peer_difficulty, peer_snapshot_number, peer_snapshot_hash) = {
+		let (peer_latest, peer_difficulty, peer_snapshot_number, peer_snapshot_hash, peer_protocol_version) = {
 			let peer = self.peers.get_mut(&peer_id).unwrap();
 			if peer.asking != PeerAsking::Nothing || !peer.can_sync() {
 				return;
@@ -954,7 +1614,7 @@ impl ChainSync {
 				trace!(target: "sync", "Waiting for the snapshot restoration");
 				return;
 			}
-			(peer.latest_hash.clone(), peer.difficulty.clone(), peer.snapshot_number.as_ref().cloned(), peer.snapshot_hash.as_ref().cloned())
+			(peer.latest_hash.clone(), peer.difficulty.clone(), peer.snapshot_number.as_ref().cloned(), peer.snapshot_hash.as_ref().cloned(), peer.protocol_version)
 		};
 		let chain_info = io.chain().chain_info();
 		let td = chain_info.pending_total_difficulty;
@@ -964,8 +1624,10 @@ impl ChainSync {
 			match self.state {
 				SyncState::Idle => {
 					// check if we can start snapshot sync with this peer
-					if peer_snapshot_number.unwrap_or(0) > 0 && chain_info.best_block_number == 0 {
+					if self.sync_method == SyncMethod::Warp && peer_snapshot_number.unwrap_or(0) > 0 && chain_info.best_block_number == 0 {
 						self.start_snapshot_sync(io, peer_id);
+					} else if self.sync_method == SyncMethod::Fast && peer_protocol_version >= 63 && chain_info.best_block_number == 0 {
+						self.start_state_sync(io, peer_id);
 					} else {
 						if self.last_imported_block < chain_info.best_block_number {
 							self.last_imported_block = chain_info.best_block_number;
@@ -994,6 +1656,19 @@ impl ChainSync {
 						self.request_snapshot_data(io, peer_id);
 					}
 				},
+				SyncState::StatePivot => {
+					let candidate = self.pivot.as_ref().map(|p| (HeaderView::new(&p.header).number(), p.hash, p.confirmed_by.contains(&peer_id) || p.disputed_by.contains(&peer_id)));
+					match candidate {
+						Some((number, hash, false)) if peer_protocol_version >= 63 => self.request_pivot_confirmation(io, peer_id, number, &hash),
+						None if peer_protocol_version >= 63 && !self.peers.values().any(|p| p.asking == PeerAsking::PivotHeaders) => self.request_pivot_headers(io, peer_id),
+						_ => {},
+					}
+				},
+				SyncState::StateData => {
+					if peer_protocol_version >= 63 {
+						self.request_state_data(io, peer_id);
+					}
+				},
 				SyncState::SnapshotManifest | //already downloading from other peer
 					SyncState::Waiting | SyncState::SnapshotWaiting => ()
 			}
@@ -1032,10 +1707,17 @@ impl ChainSync {
 	}
 
 	/// Find some headers or blocks to download for a peer.
+	/// Once all subchains are being downloaded the next subchain heads are requested, so that header download,
+	/// body download and import keep going while the current round is completed. With nothing else left to do
+	/// the peer downloads bodies next in line for import along with the peers they have been requested from.
 	fn request_blocks(&mut self, io: &mut SyncIo, peer_id: PeerId, ignore_others: bool) {
 		self.clear_peer_download(peer_id);
 		if io.chain().queue_info().is_full() {
-			self.pause_sync();
+			self.pause_sync("Block queue full");
+			return;
+		}
+		if io.is_download_limited() {
+			self.pause_sync("Download rate limit exceeded");
 			return;
 		}
 
@@ -1051,6 +1733,25 @@ impl ChainSync {
 		if let Some((h, count)) = self.blocks.needed_headers(MAX_HEADERS_TO_REQUEST, ignore_others) {
 			replace(&mut self.peers.get_mut(&peer_id).unwrap().asking_blocks, vec![h.clone()]);
 			self.request_headers_by_hash(io, peer_id, &h, count, 0, false, PeerAsking::BlockHeaders);
+			return;
+		}
+
+		// look ahead for the subchains of the next round
+		if self.state == SyncState::Blocks && self.blocks.block_count() < self.max_download_ahead_blocks
+			&& !self.peers.values().any(|p| p.asking == PeerAsking::Heads) {
+			if let Some(h) = self.blocks.needed_heads() {
+				self.request_headers_by_hash(io, peer_id, &h, SUBCHAIN_SIZE, MAX_HEADERS_TO_REQUEST - 2, false, PeerAsking::Heads);
+				return;
+			}
+		}
+
+		if !ignore_others {
+			let needed_bodies = self.blocks.needed_bodies(MAX_BODIES_TO_REQUEST, true);
+			if !needed_bodies.is_empty() {
+				trace!(target: "sync", "{}: Requesting {} bodies downloaded by other peers", peer_id, needed_bodies.len());
+				replace(&mut self.peers.get_mut(&peer_id).unwrap().asking_blocks, needed_bodies.clone());
+				self.request_bodies(io, peer_id, needed_bodies);
+			}
 		}
 	}
 
@@ -1064,6 +1765,24 @@ impl ChainSync {
 		}
 	}
 
+	/// Find pivot block data or state trie nodes to download for a peer.
+	fn request_state_data(&mut self, io: &mut SyncIo, peer_id: PeerId) {
+		let (hash, need_block, need_receipts) = match self.pivot {
+			Some(ref pivot) => (pivot.hash.clone(), pivot.block.is_none(), pivot.receipts.is_none()),
+			None => return,
+		};
+		if need_block && !self.peers.values().any(|p| p.asking == PeerAsking::PivotBody) {
+			self.request_pivot_body(io, peer_id, &hash);
+		} else if need_receipts && !self.peers.values().any(|p| p.asking == PeerAsking::PivotReceipts) {
+			self.request_pivot_receipts(io, peer_id, &hash);
+		} else {
+			let hashes = self.state_sync.needed_nodes(MAX_NODE_DATA_TO_REQUEST);
+			if !hashes.is_empty() {
+				self.request_node_data(io, peer_id, hashes);
+			}
+		}
+	}
+
 	/// Clear all blocks/headers marked as being downloaded by a peer.
 	fn clear_peer_download(&mut self, peer_id: PeerId) {
 		let peer = self.peers.get_mut(&peer_id).unwrap();
@@ -1083,9 +1802,13 @@ impl ChainSync {
 					self.snapshot.clear_chunk_download(&hash);
 				}
 			},
+			PeerAsking::NodeData => {
+				self.state_sync.clear_node_download(&peer.asking_nodes);
+			},
 			_ => (),
 		}
 		peer.asking_blocks.clear();
+		peer.asking_nodes.clear();
 		peer.asking_snapshot_data = None;
 	}
 
@@ -1100,6 +1823,10 @@ impl ChainSync {
 
 	/// Checks if there are blocks fully downloaded that can be imported into the blockchain and does the import.
 	fn collect_blocks(&mut self, io: &mut SyncIo) {
+		if io.chain().queue_info().is_full() {
+			// downloaded blocks are kept until the block queue has room again
+			return;
+		}
 		let mut restart = false;
 		let mut imported = HashSet::new();
 		let blocks = self.blocks.drain();
@@ -1199,6 +1926,52 @@ impl ChainSync {
 		self.send_request(sync, peer_id, PeerAsking::SnapshotData, GET_SNAPSHOT_DATA_PACKET, rlp.out());
 	}
 
+	/// Request fast sync pivot block headers from a peer, going back from its best block.
+	fn request_pivot_headers(&mut self, sync: &mut SyncIo, peer_id: PeerId) {
+		let latest = self.peers.get(&peer_id)
+			.expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed")
+			.latest_hash.clone();
+		self.state = SyncState::StatePivot;
+		self.request_headers_by_hash(sync, peer_id, &latest, PIVOT_DISTANCE + 1, 0, true, PeerAsking::PivotHeaders);
+	}
+
+	/// Ask a peer for its canonical header with the number of the fast sync pivot block candidate.
+	fn request_pivot_confirmation(&mut self, sync: &mut SyncIo, peer_id: PeerId, number: BlockNumber, hash: &H256) {
+		self.request_headers_by_number(sync, peer_id, number, 1, 0, false, PeerAsking::PivotConfirmation);
+		self.peers.get_mut(&peer_id)
+			.expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed")
+			.asking_hash = Some(hash.clone());
+	}
+
+	/// Request fast sync pivot block body from a peer.
+	fn request_pivot_body(&mut self, sync: &mut SyncIo, peer_id: PeerId, hash: &H256) {
+		trace!(target: "sync", "{} <- GetBlockBodies: pivot block {}", peer_id, hash);
+		let mut rlp = RlpStream::new_list(1);
+		rlp.append(hash);
+		self.send_request(sync, peer_id, PeerAsking::PivotBody, GET_BLOCK_BODIES_PACKET, rlp.out());
+	}
+
+	/// Request fast sync pivot block receipts from a peer.
+	fn request_pivot_receipts(&mut self, sync: &mut SyncIo, peer_id: PeerId, hash: &H256) {
+		trace!(target: "sync", "{} <- GetReceipts: pivot block {}", peer_id, hash);
+		let mut rlp = RlpStream::new_list(1);
+		rlp.append(hash);
+		self.send_request(sync, peer_id, PeerAsking::PivotReceipts, GET_RECEIPTS_PACKET, rlp.out());
+	}
+
+	/// Request state trie nodes from a peer.
+	fn request_node_data(&mut self, sync: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>) {
+		trace!(target: "sync", "{} <- GetNodeData: {} entries", peer_id, hashes.len());
+		let mut rlp = RlpStream::new_list(hashes.len());
+		for h in &hashes {
+			rlp.append(h);
+		}
+		self.peers.get_mut(&peer_id)
+			.expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed")
+			.asking_nodes = hashes;
+		self.send_request(sync, peer_id, PeerAsking::NodeData, GET_NODE_DATA_PACKET, rlp.out());
+	}
+
 	/// Request block bodies from a peer
 	fn request_bodies(&mut self, sync: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>) {
 		let mut rlp = RlpStream::new_list(hashes.len());
@@ -1246,14 +2019,38 @@ impl ChainSync {
 		}
 	}
 
-	/// Called when peer sends us new transactions
-	fn on_peer_transactions(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+	/// Called when peer sends us a private transaction
+	fn on_private_transaction(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if !self.supports_private_transactions(peer_id) {
+			trace!(target: "sync", "{} Ignoring private transaction packet from peer without support", peer_id);
+			return Ok(());
+		}
+		trace!(target: "sync", "Received private transaction packet");
+		io.chain().queue_private_transaction(r.as_raw().to_vec());
+		Ok(())
+	}
+
+	/// Called when peer sends us a validator's signature of a private transaction
+	fn on_signed_private_transaction(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if !self.supports_private_transactions(peer_id) {
+			trace!(target: "sync", "{} Ignoring signed private transaction packet from peer without support", peer_id);
+			return Ok(());
+		}
+		trace!(target: "sync", "Received signed private transaction packet");
+		io.chain().queue_signed_private_transaction(r.as_raw().to_vec());
+		Ok(())
+	}
+
+	/// Called when peer sends us new transactions.
+	/// Transactions are queued without holding the sync lock so that they don't hold up block download.
+	fn on_peer_transactions(sync: &RwLock<ChainSync>, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
 		// accepting transactions once only fully synced
 		if !io.is_chain_queue_empty() {
 			return Ok(());
 		}
-		if !self.peers.get(&peer_id).map_or(false, |p| p.can_sync()) {
+		if !sync.read().peers.get(&peer_id).map_or(false, |p| p.can_sync()) {
 			trace!(target: "sync", "{} Ignoring transactions from unconfirmed/unknown peer", peer_id);
+			return Ok(());
 		}
 
 		let mut item_count = r.item_count();
@@ -1270,7 +2067,7 @@ impl ChainSync {
 
 	/// Send Status message
 	fn send_status(&mut self, io: &mut SyncIo, peer: PeerId) -> Result<(), NetworkError> {
-		let pv64 = io.eth_protocol_version(peer) >= 64;
+		let pv64 = io.eth_protocol_version(peer) as u32 >= SNAPSHOT_PROTOCOL_VERSION;
 		let mut packet = RlpStream::new_list(if pv64 { 7 } else { 5 });
 		let chain = io.chain().chain_info();
 		packet.append(&(io.eth_protocol_version(peer) as u32));
@@ -1288,6 +2085,11 @@ impl ChainSync {
 		io.respond(STATUS_PACKET, packet.out())
 	}
 
+	/// Response size soft limit. Reduced while the upload rate exceeds the configured limit.
+	fn payload_limit(io: &SyncIo) -> usize {
+		if io.is_upload_limited() { LIMITED_PAYLOAD_SOFT_LIMIT } else { PAYLOAD_SOFT_LIMIT }
+	}
+
 	/// Respond to GetBlockHeaders request
 	fn return_block_headers(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
 		// Packet layout:
@@ -1327,10 +2129,11 @@ impl ChainSync {
 			max(0, number)
 		};
 		let max_count = min(MAX_HEADERS_TO_SEND, max_headers);
+		let payload_limit = ChainSync::payload_limit(io);
 		let mut count = 0;
 		let mut data = Bytes::new();
 		let inc = (skip + 1) as BlockNumber;
-		while number <= last && count < max_count {
+		while number <= last && count < max_count && data.len() < payload_limit {
 			if let Some(mut hdr) = io.chain().block_header(BlockID::Number(number)) {
 				data.append(&mut hdr);
 				count += 1;
@@ -1360,6 +2163,7 @@ impl ChainSync {
 		}
 		trace!(target: "sync", "{} -> GetBlockBodies: {} entries", peer_id, count);
 		count = min(count, MAX_BODIES_TO_SEND);
+		let payload_limit = ChainSync::payload_limit(io);
 		let mut added = 0usize;
 		let mut data = Bytes::new();
 		for i in 0..count {
@@ -1367,6 +2171,9 @@ impl ChainSync {
 				data.append(&mut hdr);
 				added += 1;
 			}
+			if data.len() >= payload_limit {
+				break;
+			}
 		}
 		let mut rlp = RlpStream::new_list(added);
 		rlp.append_raw(&data, added);
@@ -1383,6 +2190,7 @@ impl ChainSync {
 			return Ok(None);
 		}
 		count = min(count, MAX_NODE_DATA_TO_SEND);
+		let payload_limit = ChainSync::payload_limit(io);
 		let mut added = 0usize;
 		let mut data = Bytes::new();
 		for i in 0..count {
@@ -1390,6 +2198,9 @@ impl ChainSync {
 				data.append(&mut hdr);
 				added += 1;
 			}
+			if data.len() >= payload_limit {
+				break;
+			}
 		}
 		trace!(target: "sync", "{} -> GetNodeData: return {} entries", peer_id, added);
 		let mut rlp = RlpStream::new_list(added);
@@ -1405,15 +2216,16 @@ impl ChainSync {
 			return Ok(None);
 		}
 		count = min(count, MAX_RECEIPTS_HEADERS_TO_SEND);
+		let payload_limit = ChainSync::payload_limit(io);
 		let mut added_headers = 0usize;
 		let mut added_receipts = 0usize;
 		let mut data = Bytes::new();
 		for i in 0..count {
 			if let Some(mut receipts_bytes) = io.chain().block_receipts(&try!(rlp.val_at::<H256>(i))) {
+				added_receipts += UntrustedRlp::new(&receipts_bytes).item_count();
 				data.append(&mut receipts_bytes);
-				added_receipts += receipts_bytes.len();
 				added_headers += 1;
-				if added_receipts > MAX_RECEIPTS_TO_SEND { break; }
+				if added_receipts > MAX_RECEIPTS_TO_SEND || data.len() >= payload_limit { break; }
 			}
 		}
 		let mut rlp_result = RlpStream::new_list(added_headers);
@@ -1477,23 +2289,75 @@ impl ChainSync {
 		}
 	}
 
+	/// Returns true if the peer has used up its serving budget for the current window.
+	fn serve_budget_exhausted(&self, peer: PeerId) -> bool {
+		let now = time::precise_time_s();
+		let mut budgets = self.serve_budgets.lock();
+		let budget = budgets.entry(peer).or_insert_with(|| ServeBudget { window_start: now, served: 0 });
+		if now - budget.window_start >= SERVE_BUDGET_WINDOW {
+			budget.window_start = now;
+			budget.served = 0;
+		}
+		budget.served >= SERVE_BUDGET
+	}
+
+	/// Charge served bytes to the peer's serving budget.
+	fn charge_serve_budget(&self, peer: PeerId, bytes: usize) {
+		if let Some(budget) = self.serve_budgets.lock().get_mut(&peer) {
+			budget.served += bytes;
+		}
+	}
+
+	/// Like `return_rlp`, but charges the response to the peer's serving budget and to the upload limit.
+	/// Once either is used up requests are left unanswered, so that the peer times out
+	/// and asks someone else rather than penalizing us for an empty response.
+	fn return_budgeted_rlp<FRlp, FError>(sync: &RwLock<ChainSync>, io: &mut SyncIo, rlp: &UntrustedRlp, peer: PeerId, rlp_func: FRlp, error_func: FError) -> Result<(), PacketDecodeError>
+		where FRlp : Fn(&SyncIo, &UntrustedRlp, PeerId) -> RlpResponseResult,
+			FError : FnOnce(NetworkError) -> String
+	{
+		if sync.read().serve_budget_exhausted(peer) {
+			trace!(target: "sync", "{}: Serving budget used up, ignoring request", peer);
+			return Ok(());
+		}
+		if let Some((packet_id, rlp_stream)) = try!(rlp_func(io, rlp, peer)) {
+			let data = rlp_stream.out();
+			if !io.reserve_upload(data.len()) {
+				trace!(target: "sync", "{}: Upload limit reached, ignoring request", peer);
+				return Ok(());
+			}
+			sync.read().charge_serve_budget(peer, data.len());
+			io.respond(packet_id, data).unwrap_or_else(
+				|e| debug!(target: "sync", "{:?}", error_func(e)));
+		}
+		Ok(())
+	}
+
 	/// Dispatch incoming requests and responses
 	pub fn dispatch_packet(sync: &RwLock<ChainSync>, io: &mut SyncIo, peer: PeerId, packet_id: u8, data: &[u8]) {
 		let rlp = UntrustedRlp::new(data);
 		let result = match packet_id {
-			GET_BLOCK_BODIES_PACKET => ChainSync::return_rlp(io, &rlp, peer,
+			GET_BLOCK_BODIES_PACKET | GET_RECEIPTS_PACKET if !io.chain().stores_bodies() => {
+				// only headers are kept; an empty response lets the peer ask someone else.
+				trace!(target: "sync", "{}: Block bodies and receipts are not stored, sending an empty response", peer);
+				let response = if packet_id == GET_BLOCK_BODIES_PACKET { BLOCK_BODIES_PACKET } else { RECEIPTS_PACKET };
+				io.respond(response, RlpStream::new_list(0).out()).unwrap_or_else(
+					|e| debug!(target: "sync", "Error sending empty response: {:?}", e));
+				Ok(())
+			},
+
+			GET_BLOCK_BODIES_PACKET => ChainSync::return_budgeted_rlp(sync, io, &rlp, peer,
 				ChainSync::return_block_bodies,
 				|e| format!("Error sending block bodies: {:?}", e)),
 
-			GET_BLOCK_HEADERS_PACKET => ChainSync::return_rlp(io, &rlp, peer,
+			GET_BLOCK_HEADERS_PACKET => ChainSync::return_budgeted_rlp(sync, io, &rlp, peer,
 				ChainSync::return_block_headers,
 				|e| format!("Error sending block headers: {:?}", e)),
 
-			GET_RECEIPTS_PACKET => ChainSync::return_rlp(io, &rlp, peer,
+			GET_RECEIPTS_PACKET => ChainSync::return_budgeted_rlp(sync, io, &rlp, peer,
 				ChainSync::return_receipts,
 				|e| format!("Error sending receipts: {:?}", e)),
 
-			GET_NODE_DATA_PACKET => ChainSync::return_rlp(io, &rlp, peer,
+			GET_NODE_DATA_PACKET => ChainSync::return_budgeted_rlp(sync, io, &rlp, peer,
 				ChainSync::return_node_data,
 				|e| format!("Error sending nodes: {:?}", e)),
 
@@ -1505,6 +2369,8 @@ impl ChainSync {
 				ChainSync::return_snapshot_data,
 				|e| format!("Error sending snapshot data: {:?}", e)),
 
+			TRANSACTIONS_PACKET => ChainSync::on_peer_transactions(sync, io, peer, &rlp),
+
 			_ => {
 				sync.write().on_packet(io, peer, packet_id, data);
 				Ok(())
@@ -1523,13 +2389,17 @@ impl ChainSync {
 		let rlp = UntrustedRlp::new(data);
 		let result = match packet_id {
 			STATUS_PACKET => self.on_peer_status(io, peer, &rlp),
-			TRANSACTIONS_PACKET => self.on_peer_transactions(io, peer, &rlp),
 			BLOCK_HEADERS_PACKET => self.on_peer_block_headers(io, peer, &rlp),
 			BLOCK_BODIES_PACKET => self.on_peer_block_bodies(io, peer, &rlp),
 			NEW_BLOCK_PACKET => self.on_peer_new_block(io, peer, &rlp),
 			NEW_BLOCK_HASHES_PACKET => self.on_peer_new_hashes(io, peer, &rlp),
+			NEW_BLOCK_HEADER_PACKET => self.on_peer_new_block_header(io, peer, &rlp),
 			SNAPSHOT_MANIFEST_PACKET => self.on_snapshot_manifest(io, peer, &rlp),
 			SNAPSHOT_DATA_PACKET => self.on_snapshot_data(io, peer, &rlp),
+			RECEIPTS_PACKET => self.on_pivot_receipts(io, peer, &rlp),
+			NODE_DATA_PACKET => self.on_node_data(io, peer, &rlp),
+			PRIVATE_TRANSACTION_PACKET => self.on_private_transaction(io, peer, &rlp),
+			SIGNED_PRIVATE_TRANSACTION_PACKET => self.on_signed_private_transaction(io, peer, &rlp),
 			_ => {
 				debug!(target: "sync", "Unknown packet {}", packet_id);
 				Ok(())
@@ -1552,6 +2422,11 @@ impl ChainSync {
 				PeerAsking::ForkHeader => (tick - peer.ask_time) > FORK_HEADER_TIMEOUT_SEC,
 				PeerAsking::SnapshotManifest => (tick - peer.ask_time) > SNAPSHOT_MANIFEST_TIMEOUT_SEC,
 				PeerAsking::SnapshotData => (tick - peer.ask_time) > SNAPSHOT_DATA_TIMEOUT_SEC,
+				PeerAsking::NewBlockBody => (tick - peer.ask_time) > NEW_BLOCK_BODY_TIMEOUT_SEC,
+				PeerAsking::PivotHeaders | PeerAsking::PivotConfirmation => (tick - peer.ask_time) > HEADERS_TIMEOUT_SEC,
+				PeerAsking::PivotBody => (tick - peer.ask_time) > BODIES_TIMEOUT_SEC,
+				PeerAsking::PivotReceipts => (tick - peer.ask_time) > RECEIPTS_TIMEOUT_SEC,
+				PeerAsking::NodeData => (tick - peer.ask_time) > NODE_DATA_TIMEOUT_SEC,
 			};
 			if timeout {
 				trace!(target:"sync", "Timeout {}", peer_id);
@@ -1565,8 +2440,9 @@ impl ChainSync {
 	}
 
 	fn check_resume(&mut self, io: &mut SyncIo) {
-		if self.state == SyncState::Waiting && !io.chain().queue_info().is_full() && self.state == SyncState::Waiting {
+		if self.state == SyncState::Waiting && !io.chain().queue_info().is_full() && !io.is_download_limited() {
 			self.state = SyncState::Blocks;
+			self.collect_blocks(io);
 			self.continue_sync(io);
 		} else if self.state == SyncState::SnapshotWaiting && io.snapshot_service().status() == RestorationStatus::Inactive {
 			self.state = SyncState::Idle;
@@ -1617,6 +2493,14 @@ impl ChainSync {
 		rlp_stream.out()
 	}
 
+	/// creates new block header announcement rlp for the given sealed block
+	fn create_new_block_header_rlp(chain: &BlockChainClient, hash: &H256) -> Bytes {
+		let mut rlp_stream = RlpStream::new_list(2);
+		rlp_stream.append_raw(&chain.block_header(BlockID::Hash(hash.clone())).expect("Block has just been sealed; qed"), 1);
+		rlp_stream.append(&chain.block_total_difficulty(BlockID::Hash(hash.clone())).expect("Block has just been sealed; qed."));
+		rlp_stream.out()
+	}
+
 	/// returns peer ids that have less blocks than our chain
 	fn get_lagging_peers(&mut self, chain_info: &BlockChainInfo, io: &SyncIo) -> Vec<(PeerId, BlockNumber)> {
 		let latest_hash = chain_info.best_block_hash;
@@ -1669,6 +2553,29 @@ impl ChainSync {
 		sent
 	}
 
+	/// announces sealed block headers ahead of the bodies to lagging peers that support it.
+	/// Returns the peers the headers were sent to.
+	fn propagate_sealed_headers(&mut self, chain_info: &BlockChainInfo, io: &mut SyncIo, sealed: &[H256], peers: &[(PeerId, BlockNumber)]) -> Vec<(PeerId, BlockNumber)> {
+		let header_peers: Vec<_> = peers.iter()
+			.filter(|&&(peer_id, _)| self.peers.get(&peer_id).map_or(false, |p| p.protocol_version >= HEADER_FIRST_PROTOCOL_VERSION))
+			.cloned()
+			.collect();
+		if header_peers.is_empty() {
+			return header_peers;
+		}
+		trace!(target: "sync", "Sending NewBlockHeaders to {:?}", header_peers);
+		let headers: Vec<_> = sealed.iter().map(|h| ChainSync::create_new_block_header_rlp(io.chain(), h)).collect();
+		for &(peer_id, _) in &header_peers {
+			for rlp in &headers {
+				self.send_packet(io, peer_id, NEW_BLOCK_HEADER_PACKET, rlp.clone());
+			}
+			let peer = self.peers.get_mut(&peer_id).unwrap();
+			peer.latest_hash = chain_info.best_block_hash.clone();
+			peer.latest_number = Some(chain_info.best_block_number);
+		}
+		header_peers
+	}
+
 	/// propagates new known hashes to all peers
 	fn propagate_new_hashes(&mut self, chain_info: &BlockChainInfo, io: &mut SyncIo, peers: &[(PeerId, BlockNumber)]) -> usize {
 		trace!(target: "sync", "Sending NewHashes to {:?}", peers);
@@ -1705,6 +2612,11 @@ impl ChainSync {
 			return 0;
 		}
 
+		if self.is_major_syncing() && !self.propagate_transactions_while_syncing {
+			trace!(target: "sync", "Skipping transaction propagation while syncing");
+			return 0;
+		}
+
 		let transactions = io.chain().pending_transactions();
 		if transactions.is_empty() {
 			return 0;
@@ -1762,9 +2674,32 @@ impl ChainSync {
 		sent
 	}
 
+	/// Send a private transaction to all peers supporting it.
+	pub fn propagate_private_transaction(&mut self, io: &mut SyncIo, packet: Bytes) {
+		self.send_to_private_transaction_peers(io, PRIVATE_TRANSACTION_PACKET, packet);
+	}
+
+	/// Send a validator's signature of a private transaction to all peers supporting it.
+	pub fn propagate_signed_private_transaction(&mut self, io: &mut SyncIo, packet: Bytes) {
+		self.send_to_private_transaction_peers(io, SIGNED_PRIVATE_TRANSACTION_PACKET, packet);
+	}
+
+	fn supports_private_transactions(&self, peer_id: PeerId) -> bool {
+		self.peers.get(&peer_id).map_or(false, |p| p.protocol_version >= PRIVATE_TRANSACTIONS_PROTOCOL_VERSION)
+	}
+
+	fn send_to_private_transaction_peers(&mut self, io: &mut SyncIo, packet_id: PacketId, packet: Bytes) {
+		let peers: Vec<PeerId> = self.peers.keys().cloned().filter(|p| self.supports_private_transactions(*p)).collect();
+		trace!(target: "sync", "Sending packet {} to {} peers", packet_id, peers.len());
+		for peer_id in peers {
+			self.send_packet(io, peer_id, packet_id, packet.clone());
+		}
+	}
+
 	fn propagate_latest_blocks(&mut self, io: &mut SyncIo, sealed: &[H256]) {
 		let chain_info = io.chain().chain_info();
-		if (((chain_info.best_block_number as i64) - (self.last_sent_block_number as i64)).abs() as BlockNumber) < MAX_PEER_LAG_PROPAGATION {
+		let may_propagate = !self.is_major_syncing() || self.propagate_blocks_while_syncing;
+		if may_propagate && (((chain_info.best_block_number as i64) - (self.last_sent_block_number as i64)).abs() as BlockNumber) < MAX_PEER_LAG_PROPAGATION {
 			let mut peers = self.get_lagging_peers(&chain_info, io);
 			if sealed.is_empty() {
 				let hashes = self.propagate_new_hashes(&chain_info, io, &peers);
@@ -1774,9 +2709,12 @@ impl ChainSync {
 					trace!(target: "sync", "Sent latest {} blocks and {} hashes to peers.", blocks, hashes);
 				}
 			} else {
+				// headers go out first so that capable peers can start fetching the bodies right away
+				let header_peers = self.propagate_sealed_headers(&chain_info, io, sealed, &peers);
+				peers.retain(|p| !header_peers.contains(p));
 				self.propagate_blocks(&chain_info, io, sealed, &peers);
 				self.propagate_new_hashes(&chain_info, io, &peers);
-				trace!(target: "sync", "Sent sealed block to all peers");
+				trace!(target: "sync", "Sent sealed block headers to {} peers and blocks to {} peers", header_peers.len(), peers.len());
 			};
 		}
 		self.propagate_new_transactions(io);
@@ -1803,6 +2741,14 @@ impl ChainSync {
 	}
 }
 
+/// Time in seconds to finish `total` items if `done` of them took `elapsed` seconds.
+fn estimate_remaining(elapsed: f64, done: u64, total: u64) -> Option<u64> {
+	if done == 0 || done >= total || elapsed <= 0f64 {
+		return None;
+	}
+	Some(((total - done) as f64 * elapsed / done as f64) as u64)
+}
+
 #[cfg(test)]
 mod tests {
 	use tests::helpers::*;
@@ -1841,6 +2787,13 @@ mod tests {
 		rlp.out()
 	}
 
+	#[test]
+	fn estimates_time_remaining() {
+		assert_eq!(super::estimate_remaining(10f64, 100, 400), Some(30));
+		assert_eq!(super::estimate_remaining(10f64, 0, 400), None);
+		assert_eq!(super::estimate_remaining(10f64, 400, 400), None);
+	}
+
 	fn get_dummy_hashes() -> Bytes {
 		let mut rlp = RlpStream::new_list(5);
 		for _ in 0..5 {
@@ -1898,6 +2851,29 @@ mod tests {
 		assert_eq!(1, io.queue.len());
 	}
 
+	#[test]
+	fn returns_empty_bodies_and_receipts_without_bodies() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(10, EachBlockWith::Nothing);
+		*client.stores_bodies.write() = false;
+		let mut queue = VecDeque::new();
+		let sync = RwLock::new(dummy_sync_with_peer(H256::new(), &client));
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, Some(0));
+
+		let mut request = RlpStream::new_list(1);
+		request.append(&io.chain.block_hash(BlockID::Number(5)).unwrap());
+		let request = request.out();
+
+		ChainSync::dispatch_packet(&sync, &mut io, 0, super::GET_BLOCK_BODIES_PACKET, &request);
+		ChainSync::dispatch_packet(&sync, &mut io, 0, super::GET_RECEIPTS_PACKET, &request);
+
+		assert_eq!(2, io.queue.len());
+		assert_eq!(io.queue[0].packet_id, super::BLOCK_BODIES_PACKET);
+		assert_eq!(io.queue[1].packet_id, super::RECEIPTS_PACKET);
+		assert!(io.queue.iter().all(|packet| UntrustedRlp::new(&packet.data).item_count() == 0));
+	}
+
 	#[test]
 	fn return_block_headers() {
 		use ethcore::views::HeaderView;
@@ -1993,6 +2969,142 @@ mod tests {
 		assert_eq!(1, io.queue.len());
 	}
 
+	#[test]
+	fn serving_budget_is_shared_across_responses() {
+		let mut client = TestBlockChainClient::new();
+		let mut queue = VecDeque::new();
+		let sync = RwLock::new(dummy_sync_with_peer(H256::new(), &client));
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, Some(0));
+
+		let mut node_list = RlpStream::new_list(1);
+		node_list.append(&H256::from("ffffffffffffffffffffffffffffffffffffffffffffaaaaaaaaaaaaaaaaaaaa"));
+		let node_request = node_list.out();
+
+		ChainSync::dispatch_packet(&sync, &mut io, 0usize, super::GET_NODE_DATA_PACKET, &node_request);
+		ChainSync::dispatch_packet(&sync, &mut io, 0usize, super::GET_NODE_DATA_PACKET, &node_request);
+		assert_eq!(2, io.queue.len());
+		assert_eq!(68, sync.read().serve_budgets.lock()[&0].served);
+
+		// use up the rest of the budget, further requests are not answered
+		sync.read().charge_serve_budget(0, SERVE_BUDGET);
+		ChainSync::dispatch_packet(&sync, &mut io, 0usize, super::GET_NODE_DATA_PACKET, &node_request);
+		assert_eq!(2, io.queue.len());
+
+		let mut receipt_list = RlpStream::new_list(1);
+		receipt_list.append(&H256::from("ff00000000000000000000000000000000000000000000000000000000000000"));
+		ChainSync::dispatch_packet(&sync, &mut io, 0usize, super::GET_RECEIPTS_PACKET, &receipt_list.out());
+		assert_eq!(2, io.queue.len());
+
+		// the budget is restored once the window ends
+		sync.read().serve_budgets.lock().get_mut(&0).unwrap().window_start -= SERVE_BUDGET_WINDOW;
+		ChainSync::dispatch_packet(&sync, &mut io, 0usize, super::GET_NODE_DATA_PACKET, &node_request);
+		let packet = io.queue.pop_back().unwrap();
+		assert_eq!(1, UntrustedRlp::new(&packet.data).item_count());
+		assert_eq!(34, sync.read().serve_budgets.lock()[&0].served);
+	}
+
+	#[test]
+	fn serving_budget_is_dropped_with_peer() {
+		let mut client = TestBlockChainClient::new();
+		let mut queue = VecDeque::new();
+		let mut sync = dummy_sync_with_peer(H256::new(), &client);
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		assert!(!sync.serve_budget_exhausted(0));
+		sync.charge_serve_budget(0, SERVE_BUDGET);
+		assert!(sync.serve_budget_exhausted(0));
+		sync.on_peer_aborting(&mut io, 0);
+		assert!(sync.serve_budgets.lock().is_empty());
+	}
+
+	#[test]
+	fn serving_budget_is_dropped_with_peer_before_status() {
+		let mut client = TestBlockChainClient::new();
+		let mut queue = VecDeque::new();
+		let mut sync = dummy_sync_with_peer(H256::new(), &client);
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		// peer 1 never completed the status handshake.
+		assert!(!sync.serve_budget_exhausted(1));
+		sync.on_peer_aborting(&mut io, 1);
+		assert!(sync.serve_budgets.lock().is_empty());
+	}
+
+	#[test]
+	fn serves_smaller_responses_while_upload_limited() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(600, EachBlockWith::Nothing);
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		let mut rlp = RlpStream::new_list(4);
+		rlp.append(&0usize);
+		rlp.append(&MAX_HEADERS_TO_SEND);
+		rlp.append(&0usize);
+		rlp.append(&0u32);
+		let request = rlp.out();
+
+		let response = ChainSync::return_block_headers(&io, &UntrustedRlp::new(&request), 0).unwrap().unwrap().1.out();
+		assert_eq!(MAX_HEADERS_TO_SEND, Rlp::new(&response).item_count());
+
+		io.upload_limited = true;
+		let response = ChainSync::return_block_headers(&io, &UntrustedRlp::new(&request), 0).unwrap().unwrap().1.out();
+		assert!(Rlp::new(&response).item_count() < MAX_HEADERS_TO_SEND);
+		assert!(response.len() < LIMITED_PAYLOAD_SOFT_LIMIT + 1024);
+	}
+
+	#[test]
+	fn leaves_requests_unanswered_above_upload_limit() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Nothing);
+		let mut queue = VecDeque::new();
+		let sync = RwLock::new(dummy_sync_with_peer(H256::new(), &client));
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, Some(0));
+
+		let mut rlp = RlpStream::new_list(4);
+		rlp.append(&0usize);
+		rlp.append(&10usize);
+		rlp.append(&0usize);
+		rlp.append(&0u32);
+		let request = rlp.out();
+
+		io.upload_allowance = Some(0);
+		ChainSync::dispatch_packet(&sync, &mut io, 0, super::GET_BLOCK_HEADERS_PACKET, &request);
+		assert!(io.queue.is_empty());
+
+		io.upload_allowance = Some(64 * 1024);
+		ChainSync::dispatch_packet(&sync, &mut io, 0, super::GET_BLOCK_HEADERS_PACKET, &request);
+		assert_eq!(1, io.queue.len());
+		assert!(io.upload_allowance.unwrap() < 64 * 1024);
+	}
+
+	#[test]
+	fn pauses_block_download_while_download_limited() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(5), &client);
+		sync.state = SyncState::Blocks;
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		io.download_limited = true;
+		sync.request_blocks(&mut io, 0, false);
+		assert_eq!(sync.state, SyncState::Waiting);
+		assert!(io.queue.is_empty());
+		sync.maintain_sync(&mut io);
+		assert_eq!(sync.state, SyncState::Waiting);
+
+		io.download_limited = false;
+		sync.maintain_sync(&mut io);
+		assert!(sync.state != SyncState::Waiting);
+	}
+
 	fn dummy_sync_with_peer(peer_latest_hash: H256, client: &BlockChainClient) -> ChainSync {
 		let mut sync = ChainSync::new(SyncConfig::default(), client);
 		sync.peers.insert(0,
@@ -2013,6 +3125,7 @@ mod tests {
 				snapshot_number: None,
 				snapshot_hash: None,
 				asking_snapshot_data: None,
+				asking_nodes: Vec::new(),
 			});
 		sync
 	}
@@ -2111,6 +3224,117 @@ mod tests {
 		assert_eq!(0x07, io.queue[0].packet_id);
 	}
 
+	#[test]
+	fn sends_sealed_block_header_to_capable_peer() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let mut queue = VecDeque::new();
+		let hash = client.block_hash(BlockID::Number(99)).unwrap();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(5), &client);
+		sync.peers.get_mut(&0).unwrap().protocol_version = 65;
+		let chain_info = client.chain_info();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+		let peers = sync.get_lagging_peers(&chain_info, &io);
+		let header_peers = sync.propagate_sealed_headers(&chain_info, &mut io, &[hash.clone()], &peers);
+
+		// 1 message should be send
+		assert_eq!(1, io.queue.len());
+		// 1 peer should be updated
+		assert_eq!(1, header_peers.len());
+		// NEW_BLOCK_HEADER_PACKET
+		assert_eq!(0x15, io.queue[0].packet_id);
+	}
+
+	#[test]
+	fn sends_private_transactions_to_capable_peers_only() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(10, EachBlockWith::Nothing);
+		let mut queue = VecDeque::new();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(5), &client);
+		sync.peers.get_mut(&0).unwrap().protocol_version = 65;
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		sync.propagate_private_transaction(&mut io, vec![0xc0]);
+		assert_eq!(0, io.queue.len());
+
+		sync.peers.get_mut(&0).unwrap().protocol_version = 66;
+		sync.propagate_private_transaction(&mut io, vec![0xc0]);
+		sync.propagate_signed_private_transaction(&mut io, vec![0xc0]);
+		assert_eq!(2, io.queue.len());
+		// PRIVATE_TRANSACTION_PACKET
+		assert_eq!(0x16, io.queue[0].packet_id);
+		// SIGNED_PRIVATE_TRANSACTION_PACKET
+		assert_eq!(0x17, io.queue[1].packet_id);
+	}
+
+	#[test]
+	fn negotiated_protocol_versions_agree() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(10, EachBlockWith::Nothing);
+		let best_hash = client.chain_info().best_block_hash;
+		let ss = TestSnapshotService::new();
+		for &ours in &[63u8, 64u8, 65u8, 66u8] {
+			for &theirs in &[63u8, 64u8, 65u8, 66u8] {
+				// the session keeps the highest version both sides support.
+				let negotiated = ::std::cmp::min(ours, theirs);
+				let mut queue = VecDeque::new();
+				let status = {
+					let mut sender = ChainSync::new(SyncConfig::default(), &client);
+					let mut io = TestIo::new(&mut client, &ss, &mut queue, Some(0));
+					io.protocol_version = negotiated;
+					sender.send_status(&mut io, 0).unwrap();
+					io.queue.pop_front().unwrap().data
+				};
+
+				let mut receiver = ChainSync::new(SyncConfig::default(), &client);
+				let mut io = TestIo::new(&mut client, &ss, &mut queue, Some(0));
+				io.protocol_version = negotiated;
+				receiver.on_peer_status(&mut io, 0, &UntrustedRlp::new(&status)).unwrap();
+				{
+					let peer = receiver.peers.get(&0).unwrap();
+					assert_eq!(peer.protocol_version, negotiated as u32);
+					assert_eq!(peer.snapshot_hash.is_some(), negotiated >= 64);
+				}
+
+				io.queue.clear();
+				let chain_info = io.chain().chain_info();
+				let header_peers = receiver.propagate_sealed_headers(&chain_info, &mut io, &[best_hash.clone()], &[(0, 0)]);
+				assert_eq!(header_peers.len(), if negotiated >= 65 { 1 } else { 0 });
+				assert!(io.queue.iter().all(|p| p.packet_id != 0x15 || negotiated >= 65));
+			}
+		}
+	}
+
+	#[test]
+	fn requests_body_for_announced_header() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(10, EachBlockWith::Uncle);
+
+		let block_data = get_dummy_block(11, client.chain_info().best_block_hash);
+		let header_data = {
+			let block = UntrustedRlp::new(&block_data);
+			let mut rlp = RlpStream::new_list(2);
+			rlp.append_raw(block.at(0).unwrap().as_raw(), 1);
+			rlp.append(&U256::from(1100));
+			rlp.out()
+		};
+
+		let mut queue = VecDeque::new();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(5), &client);
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		let result = sync.on_peer_new_block_header(&mut io, 0, &UntrustedRlp::new(&header_data));
+
+		assert!(result.is_ok());
+		// GET_BLOCK_BODIES_PACKET
+		assert_eq!(1, io.queue.len());
+		assert_eq!(0x05, io.queue[0].packet_id);
+		assert_eq!(PeerAsking::NewBlockBody, sync.peers[&0].asking);
+	}
+
 	#[test]
 	fn propagates_transactions() {
 		let mut client = TestBlockChainClient::new();
@@ -2133,6 +3357,38 @@ mod tests {
 		assert_eq!(0x02, io.queue[0].packet_id);
 	}
 
+	#[test]
+	fn does_not_propagate_transactions_while_syncing() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		client.insert_transaction_to_queue();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		sync.state = SyncState::Blocks;
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+		let peer_count = sync.propagate_new_transactions(&mut io);
+		assert_eq!(0, peer_count);
+		assert_eq!(0, io.queue.len());
+
+		sync.propagate_transactions_while_syncing = true;
+		let peer_count = sync.propagate_new_transactions(&mut io);
+		assert_eq!(1, peer_count);
+		assert_eq!(1, io.queue.len());
+	}
+
+	#[test]
+	fn relay_syncs_full_blocks_and_always_propagates_transactions() {
+		use ::SyncMethod;
+
+		let client = TestBlockChainClient::new();
+		let mut config = SyncConfig::default();
+		config.relay = true;
+		let sync = ChainSync::new(config, &client);
+		assert_eq!(sync.sync_method, SyncMethod::Full);
+		assert!(sync.propagate_transactions_while_syncing);
+	}
+
 	#[test]
 	fn propagates_transactions_again_after_new_block() {
 		let mut client = TestBlockChainClient::new();
@@ -2398,4 +3654,64 @@ mod tests {
 		assert_eq!(status.transactions_in_pending_queue, 0);
 		assert_eq!(status.transactions_in_future_queue, 0);
 	}
+
+	fn fast_sync_net(honest_peers: usize, dissenting_peers: usize) -> TestNet {
+		let mut net = TestNet::new(1 + honest_peers + dissenting_peers);
+		let mut config = SyncConfig::default();
+		config.sync_method = ::SyncMethod::Fast;
+		let sync = ChainSync::new(config, &net.peer(0).chain);
+		net.peer_mut(0).sync = RwLock::new(sync);
+		for i in 0..honest_peers {
+			net.peer_mut(1 + i).chain.add_blocks(100, EachBlockWith::Nothing);
+		}
+		for i in 0..dissenting_peers {
+			net.peer_mut(1 + honest_peers + i).chain.add_blocks(100, EachBlockWith::Uncle);
+		}
+		net
+	}
+
+	fn sync_until_pivot_is_agreed(net: &mut TestNet) {
+		for _ in 0..100 {
+			if net.peer(0).sync.read().state == super::SyncState::StateData {
+				return;
+			}
+			net.sync_steps(1);
+		}
+		panic!("no pivot block was agreed on");
+	}
+
+	#[test]
+	fn fast_sync_downloads_pivot_agreed_by_several_peers() {
+		let mut net = fast_sync_net(3, 0);
+		sync_until_pivot_is_agreed(&mut net);
+
+		let pivot_hash = (&net.peer(1).chain as &BlockChainClient).block_hash(BlockID::Number(100 - PIVOT_DISTANCE as BlockNumber)).unwrap();
+		let sync = net.peer(0).sync.read();
+		let pivot = sync.pivot.as_ref().unwrap();
+		assert_eq!(pivot.hash, pivot_hash);
+		assert_eq!(pivot.confirmed_by.len(), PIVOT_PEERS);
+		assert_eq!(sync.state_sync.root(), Some(::ethcore::views::HeaderView::new(&pivot.header).state_root()));
+	}
+
+	#[test]
+	fn fast_sync_waits_for_enough_peers_to_agree_on_pivot() {
+		let mut net = fast_sync_net(2, 0);
+		net.sync_steps(50);
+
+		let sync = net.peer(0).sync.read();
+		assert_eq!(sync.state, super::SyncState::StatePivot);
+		assert_eq!(sync.pivot.as_ref().unwrap().confirmed_by.len(), 2);
+	}
+
+	#[test]
+	fn fast_sync_ignores_pivot_of_dissenting_peer() {
+		let mut net = fast_sync_net(3, 1);
+		sync_until_pivot_is_agreed(&mut net);
+
+		let pivot_hash = (&net.peer(1).chain as &BlockChainClient).block_hash(BlockID::Number(100 - PIVOT_DISTANCE as BlockNumber)).unwrap();
+		let sync = net.peer(0).sync.read();
+		let pivot = sync.pivot.as_ref().unwrap();
+		assert_eq!(pivot.hash, pivot_hash);
+		assert!(!pivot.confirmed_by.contains(&4));
+	}
 }
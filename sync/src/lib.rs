@@ -50,6 +50,7 @@ mod chain;
 mod blocks;
 mod sync_io;
 mod snapshot;
+mod state_sync;
 
 #[cfg(test)]
 mod tests;
@@ -59,8 +60,8 @@ mod api {
 	include!(concat!(env!("OUT_DIR"), "/api.rs"));
 }
 
-pub use api::{EthSync, SyncProvider, SyncClient, NetworkManagerClient, ManageNetwork, SyncConfig,
-	ServiceConfiguration, NetworkConfiguration};
+pub use api::{EthSync, SyncProvider, SyncClient, NetworkManagerClient, ManageNetwork, SyncConfig, SyncMethod,
+	ServiceConfiguration, NetworkConfiguration, NatType, PeerInfo, NetworkTraffic, ProtocolTrafficInfo};
 pub use chain::{SyncStatus, SyncState};
 pub use network::{is_valid_node_url, NonReservedPeerMode, NetworkError};
 
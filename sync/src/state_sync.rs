@@ -0,0 +1,546 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State trie download scheduler.
+//!
+//! Downloads the state of a pivot block node by node with `GetNodeData`.
+//! Each request is identified by the key the node is stored under: the node hash for
+//! account trie nodes, and the node hash combined with the account address hash for
+//! storage trie nodes and code, the same way `AccountDB` does it.
+//! A node is only handed out for writing after all of its children have been,
+//! so anything written is a complete sub-trie. Those are remembered and are not
+//! requested again after switching to a different pivot block. Only the topmost
+//! complete sub-tries are remembered: once a node is written, its children are forgotten.
+
+use std::collections::{HashMap, HashSet, BinaryHeap};
+use util::{H256, Bytes, Hashable, SHA3_NULL_RLP, SHA3_EMPTY};
+use rlp::{UntrustedRlp, View, DecoderError};
+
+/// Kind of requested data.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum NodeKind {
+	/// Account trie node.
+	Account,
+	/// Storage trie node of an account.
+	Storage,
+	/// Contract code of an account.
+	Code,
+}
+
+/// Data referenced by a downloaded node.
+struct Child {
+	hash: H256,
+	kind: NodeKind,
+	owner: Option<H256>,
+	path: Vec<u8>,
+}
+
+/// A single node request.
+struct Request {
+	kind: NodeKind,
+	/// Address hash of the account owning a storage node or code.
+	owner: Option<H256>,
+	/// Key nibbles leading to an account trie node.
+	path: Vec<u8>,
+	depth: usize,
+	/// Node data once downloaded.
+	data: Option<Bytes>,
+	/// Number of children not yet written.
+	deps: usize,
+	/// Requests waiting for this one to be written.
+	parents: Vec<H256>,
+	/// Keys of the children, forgotten from `completed` once this is written.
+	children: Vec<H256>,
+}
+
+/// State download progress.
+pub struct StateSync {
+	/// State root being downloaded.
+	root: Option<H256>,
+	/// Outstanding requests by key.
+	requests: HashMap<H256, Request>,
+	/// Keys of outstanding requests by node hash. Several accounts may share a node.
+	keys: HashMap<H256, Vec<H256>>,
+	/// Node hashes to request, deepest first.
+	queue: BinaryHeap<(usize, H256)>,
+	/// Node hashes being downloaded.
+	downloading: HashSet<H256>,
+	/// Keys of complete sub-tries whose parents are not complete yet.
+	completed: HashSet<H256>,
+	/// Complete nodes to be written, by owning account.
+	ready: HashMap<Option<H256>, Vec<Bytes>>,
+	/// Number of nodes downloaded so far.
+	downloaded: usize,
+}
+
+impl StateSync {
+	/// Create a new instance.
+	pub fn new() -> StateSync {
+		StateSync {
+			root: None,
+			requests: HashMap::new(),
+			keys: HashMap::new(),
+			queue: BinaryHeap::new(),
+			downloading: HashSet::new(),
+			completed: HashSet::new(),
+			ready: HashMap::new(),
+			downloaded: 0,
+		}
+	}
+
+	/// Clear everything, including previously completed sub-tries.
+	pub fn clear(&mut self) {
+		self.root = None;
+		self.requests.clear();
+		self.keys.clear();
+		self.queue.clear();
+		self.downloading.clear();
+		self.completed.clear();
+		self.ready.clear();
+		self.downloaded = 0;
+	}
+
+	/// Start downloading a state root. Outstanding requests for any previous root are dropped,
+	/// but complete sub-tries are kept.
+	pub fn reset_to(&mut self, root: &H256) {
+		self.requests.clear();
+		self.keys.clear();
+		self.queue.clear();
+		self.downloading.clear();
+		self.root = Some(root.clone());
+		if *root == SHA3_NULL_RLP {
+			self.completed.insert(root.clone());
+			return;
+		}
+		self.schedule(Child { hash: root.clone(), kind: NodeKind::Account, owner: None, path: Vec::new() }, 0, None);
+	}
+
+	/// State root being downloaded.
+	pub fn root(&self) -> Option<H256> {
+		self.root
+	}
+
+	/// Find up to `count` nodes to download.
+	pub fn needed_nodes(&mut self, count: usize) -> Vec<H256> {
+		let mut hashes = Vec::new();
+		while hashes.len() < count {
+			match self.queue.pop() {
+				Some((_, hash)) => {
+					if self.keys.contains_key(&hash) && self.downloading.insert(hash.clone()) {
+						hashes.push(hash);
+					}
+				},
+				None => break,
+			}
+		}
+		hashes
+	}
+
+	/// Mark nodes as no longer being downloaded, so that they are requested again.
+	pub fn clear_node_download(&mut self, hashes: &[H256]) {
+		for hash in hashes {
+			if self.downloading.remove(hash) {
+				if let Some(depth) = self.keys.get(hash).and_then(|keys| keys.first()).and_then(|key| self.requests.get(key)).map(|r| r.depth) {
+					self.queue.push((depth, hash.clone()));
+				}
+			}
+		}
+	}
+
+	/// Insert downloaded nodes. `requested` are the hashes sent in the request;
+	/// those missing from the response are requested again.
+	/// Returns the number of nodes that were expected.
+	pub fn insert_nodes(&mut self, requested: &[H256], nodes: &[Bytes]) -> Result<usize, DecoderError> {
+		let mut inserted = 0;
+		let mut result = Ok(());
+		for node in nodes {
+			let hash = node.sha3();
+			if !self.downloading.contains(&hash) {
+				trace!(target: "sync", "Ignored unexpected node {}", hash);
+				continue;
+			}
+			if let Err(e) = self.process(&hash, node) {
+				result = Err(e);
+				break;
+			}
+			self.downloading.remove(&hash);
+			inserted += 1;
+		}
+		self.clear_node_download(requested);
+		self.downloaded += inserted;
+		result.map(|_| inserted)
+	}
+
+	/// Take the nodes that are ready to be written, grouped by owning account.
+	pub fn drain(&mut self) -> Vec<(Option<H256>, Vec<Bytes>)> {
+		self.ready.drain().collect()
+	}
+
+	/// Whether the whole state has been downloaded.
+	pub fn is_complete(&self) -> bool {
+		self.root.map_or(false, |root| self.completed.contains(&root))
+	}
+
+	/// Number of nodes downloaded so far.
+	pub fn downloaded(&self) -> usize {
+		self.downloaded
+	}
+
+	/// Number of nodes still to be downloaded or written.
+	pub fn pending(&self) -> usize {
+		self.requests.len()
+	}
+
+	fn schedule(&mut self, child: Child, depth: usize, parent: Option<H256>) -> bool {
+		let key = child.key();
+		if self.completed.contains(&key) {
+			return false;
+		}
+		if let Some(request) = self.requests.get_mut(&key) {
+			request.parents.extend(parent);
+			return true;
+		}
+		let keys = self.keys.entry(child.hash.clone()).or_insert_with(Vec::new);
+		if keys.is_empty() {
+			self.queue.push((depth, child.hash.clone()));
+		}
+		keys.push(key.clone());
+		self.requests.insert(key, Request {
+			kind: child.kind,
+			owner: child.owner,
+			path: child.path,
+			depth: depth,
+			data: None,
+			deps: 0,
+			parents: parent.into_iter().collect(),
+			children: Vec::new(),
+		});
+		true
+	}
+
+	fn process(&mut self, hash: &H256, data: &[u8]) -> Result<(), DecoderError> {
+		// decode for all requests first, so that nothing changes for an invalid node.
+		let mut decoded = Vec::new();
+		for key in self.keys.get(hash).cloned().unwrap_or_else(Vec::new) {
+			let (kind, owner, depth, children) = match self.requests.get(&key) {
+				Some(r) if r.kind == NodeKind::Code => (r.kind, r.owner.clone(), r.depth, Vec::new()),
+				Some(r) => (r.kind, r.owner.clone(), r.depth, try!(node_children(r.kind, &r.path, data))),
+				None => continue,
+			};
+			decoded.push((key, kind, owner, depth, children));
+		}
+		self.keys.remove(hash);
+
+		for (key, kind, owner, depth, children) in decoded {
+			let mut deps = 0;
+			let mut child_keys = Vec::with_capacity(children.len());
+			for mut child in children {
+				if kind == NodeKind::Storage {
+					// storage nodes belong to the account of their parent
+					child.owner = owner.clone();
+				}
+				child_keys.push(child.key());
+				if self.schedule(child, depth + 1, Some(key.clone())) {
+					deps += 1;
+				}
+			}
+			{
+				let request = self.requests.get_mut(&key).expect("request existence checked above; qed");
+				request.data = Some(data.to_vec());
+				request.deps = deps;
+				request.children = child_keys;
+			}
+			if deps == 0 {
+				self.commit(key);
+			}
+		}
+		Ok(())
+	}
+
+	fn commit(&mut self, key: H256) {
+		let mut to_commit = vec![key];
+		while let Some(key) = to_commit.pop() {
+			let request = match self.requests.remove(&key) {
+				Some(r) => r,
+				None => continue,
+			};
+			self.ready.entry(request.owner).or_insert_with(Vec::new)
+				.push(request.data.expect("only downloaded requests are committed; qed"));
+			// the sub-trie of this node covers the ones of its children
+			for child in &request.children {
+				self.completed.remove(child);
+			}
+			self.completed.insert(key);
+			for parent in request.parents {
+				if let Some(p) = self.requests.get_mut(&parent) {
+					p.deps -= 1;
+					if p.deps == 0 && p.data.is_some() {
+						to_commit.push(parent.clone());
+					}
+				}
+			}
+		}
+	}
+}
+
+impl Child {
+	/// Key of the request for this data.
+	fn key(&self) -> H256 {
+		match self.owner {
+			Some(ref owner) => combine_key(owner, &self.hash),
+			None => self.hash.clone(),
+		}
+	}
+}
+
+/// Combine a node hash with an account address hash into a database key, as `AccountDB` does.
+fn combine_key(address_hash: &H256, hash: &H256) -> H256 {
+	let mut key = hash.clone();
+	for (k, a) in key[12..].iter_mut().zip(&address_hash[12..]) {
+		*k ^= *a;
+	}
+	key
+}
+
+/// Collect the data referenced by a trie node.
+fn node_children(kind: NodeKind, path: &[u8], data: &[u8]) -> Result<Vec<Child>, DecoderError> {
+	let mut children = Vec::new();
+	try!(walk_node(kind, path, &UntrustedRlp::new(data), &mut children));
+	Ok(children)
+}
+
+fn walk_node(kind: NodeKind, path: &[u8], node: &UntrustedRlp, children: &mut Vec<Child>) -> Result<(), DecoderError> {
+	match node.item_count() {
+		// branch
+		17 => {
+			for i in 0..16 {
+				let mut child_path = path.to_vec();
+				child_path.push(i as u8);
+				try!(walk_reference(kind, child_path, &try!(node.at(i)), children));
+			}
+		},
+		// extension or leaf
+		2 => {
+			let (nibbles, is_leaf) = try!(decode_partial_key(try!(try!(node.at(0)).data())));
+			let mut child_path = path.to_vec();
+			child_path.extend(nibbles);
+			if is_leaf {
+				if kind == NodeKind::Account {
+					try!(walk_account(child_path, try!(try!(node.at(1)).data()), children));
+				}
+			} else {
+				try!(walk_reference(kind, child_path, &try!(node.at(1)), children));
+			}
+		},
+		0 if node.is_empty() => {},
+		_ => return Err(DecoderError::Custom("Invalid trie node")),
+	}
+	Ok(())
+}
+
+fn walk_reference(kind: NodeKind, path: Vec<u8>, reference: &UntrustedRlp, children: &mut Vec<Child>) -> Result<(), DecoderError> {
+	if reference.is_empty() {
+		return Ok(());
+	}
+	if reference.is_list() {
+		// node embedded into its parent
+		return walk_node(kind, &path, reference, children);
+	}
+	children.push(Child {
+		hash: try!(reference.as_val()),
+		kind: kind,
+		owner: None,
+		path: if kind == NodeKind::Account { path } else { Vec::new() },
+	});
+	Ok(())
+}
+
+fn walk_account(path: Vec<u8>, account: &[u8], children: &mut Vec<Child>) -> Result<(), DecoderError> {
+	if path.len() != 64 {
+		return Err(DecoderError::Custom("Invalid account key length"));
+	}
+	let mut address_hash = H256::new();
+	for (i, byte) in address_hash.iter_mut().enumerate() {
+		*byte = (path[i * 2] << 4) | path[i * 2 + 1];
+	}
+	let account = UntrustedRlp::new(account);
+	let storage_root: H256 = try!(account.val_at(2));
+	let code_hash: H256 = try!(account.val_at(3));
+	if storage_root != SHA3_NULL_RLP {
+		children.push(Child { hash: storage_root, kind: NodeKind::Storage, owner: Some(address_hash.clone()), path: Vec::new() });
+	}
+	if code_hash != SHA3_EMPTY {
+		children.push(Child { hash: code_hash, kind: NodeKind::Code, owner: Some(address_hash), path: Vec::new() });
+	}
+	Ok(())
+}
+
+/// Decode a hex-prefix encoded partial key into nibbles and the leaf flag.
+fn decode_partial_key(encoded: &[u8]) -> Result<(Vec<u8>, bool), DecoderError> {
+	if encoded.is_empty() {
+		return Err(DecoderError::Custom("Empty partial key"));
+	}
+	let is_leaf = encoded[0] & 0x20 != 0;
+	let mut nibbles = Vec::with_capacity(encoded.len() * 2);
+	if encoded[0] & 0x10 != 0 {
+		nibbles.push(encoded[0] & 0x0f);
+	}
+	for byte in &encoded[1..] {
+		nibbles.push(byte >> 4);
+		nibbles.push(byte & 0x0f);
+	}
+	Ok((nibbles, is_leaf))
+}
+
+#[cfg(test)]
+mod test {
+	use util::*;
+	use util::trie::{TrieDBMut, TrieMut};
+	use util::memorydb::MemoryDB;
+	use rlp::{RlpStream, Stream};
+	use super::*;
+
+	fn account_rlp(storage_root: &H256, code_hash: &H256) -> Bytes {
+		let mut stream = RlpStream::new_list(4);
+		stream.append(&U256::zero()).append(&U256::from(10)).append(storage_root).append(code_hash);
+		stream.out()
+	}
+
+	// builds a state where every account shares the same storage and code.
+	fn test_state(accounts: usize) -> (MemoryDB, H256) {
+		let mut db = MemoryDB::new();
+		let code_hash = db.insert(b"contract code");
+		let mut storage_root = H256::new();
+		{
+			let mut storage = TrieDBMut::new(&mut db, &mut storage_root);
+			for i in 0..100u64 {
+				storage.insert(&H256::from(i).sha3(), &[i as u8 + 1]).unwrap();
+			}
+		}
+		let mut root = H256::new();
+		{
+			let mut trie = TrieDBMut::new(&mut db, &mut root);
+			for i in 0..accounts as u64 {
+				trie.insert(&H256::from(i).sha3(), &account_rlp(&storage_root, &code_hash)).unwrap();
+			}
+		}
+		(db, root)
+	}
+
+	fn download(sync: &mut StateSync, db: &MemoryDB, batch: usize) -> Vec<(Option<H256>, Vec<Bytes>)> {
+		download_rounds(sync, db, batch, usize::max_value())
+	}
+
+	fn download_rounds(sync: &mut StateSync, db: &MemoryDB, batch: usize, rounds: usize) -> Vec<(Option<H256>, Vec<Bytes>)> {
+		let mut written = Vec::new();
+		for _ in 0..rounds {
+			let hashes = sync.needed_nodes(batch);
+			if hashes.is_empty() {
+				break;
+			}
+			let nodes: Vec<Bytes> = hashes.iter().map(|h| db.get(h).unwrap().to_vec()).collect();
+			assert_eq!(sync.insert_nodes(&hashes, &nodes).unwrap(), hashes.len());
+			written.extend(sync.drain());
+		}
+		written
+	}
+
+	#[test]
+	fn decodes_partial_keys() {
+		assert_eq!(decode_partial_key(&[0x20, 0x12]).unwrap(), (vec![1, 2], true));
+		assert_eq!(decode_partial_key(&[0x3a, 0x12]).unwrap(), (vec![0xa, 1, 2], true));
+		assert_eq!(decode_partial_key(&[0x00, 0x12]).unwrap(), (vec![1, 2], false));
+		assert_eq!(decode_partial_key(&[0x1a]).unwrap(), (vec![0xa], false));
+		assert!(decode_partial_key(&[]).is_err());
+	}
+
+	#[test]
+	fn downloads_state_with_storage_per_account() {
+		let (db, root) = test_state(50);
+		let mut sync = StateSync::new();
+		sync.reset_to(&root);
+		let written = download(&mut sync, &db, 16);
+		assert!(sync.is_complete());
+		assert_eq!(sync.pending(), 0);
+		// only the root is remembered once the whole state is written.
+		assert_eq!(sync.completed.len(), 1);
+
+		let state_nodes: usize = written.iter().filter(|&&(ref owner, _)| owner.is_none()).map(|&(_, ref n)| n.len()).sum();
+		let owners: HashSet<H256> = written.iter().filter_map(|&(ref owner, _)| owner.clone()).collect();
+		assert!(state_nodes > 0);
+		// every account gets its own copy of the shared storage and code.
+		assert_eq!(owners.len(), 50);
+		let code: usize = written.iter().map(|&(_, ref n)| n.iter().filter(|n| &n[..] == b"contract code").count()).sum();
+		assert_eq!(code, 50);
+	}
+
+	#[test]
+	fn requests_missing_nodes_again() {
+		let (db, root) = test_state(10);
+		let mut sync = StateSync::new();
+		sync.reset_to(&root);
+		let hashes = sync.needed_nodes(16);
+		assert_eq!(hashes, vec![root.clone()]);
+		assert!(sync.needed_nodes(16).is_empty());
+		assert_eq!(sync.insert_nodes(&hashes, &[]).unwrap(), 0);
+		assert_eq!(sync.needed_nodes(16), vec![root.clone()]);
+		sync.clear_node_download(&[root.clone()]);
+		download(&mut sync, &db, 4);
+		assert!(sync.is_complete());
+	}
+
+	#[test]
+	fn keeps_completed_subtries_on_pivot_switch() {
+		let (mut db, root) = test_state(20);
+		let count = |w: &Vec<(Option<H256>, Vec<Bytes>)>| w.iter().map(|&(_, ref n)| n.len()).sum::<usize>();
+		let mut full_sync = StateSync::new();
+		full_sync.reset_to(&root);
+		let full = count(&download(&mut full_sync, &db, 8));
+
+		// switch pivots half way through the download.
+		let mut sync = StateSync::new();
+		sync.reset_to(&root);
+		let first = download_rounds(&mut sync, &db, 8, full / 16);
+		assert!(!sync.is_complete());
+		assert!(!first.is_empty());
+
+		// a new pivot that differs in one account only.
+		let mut new_root = root.clone();
+		{
+			let mut trie = TrieDBMut::from_existing(&mut db, &mut new_root).unwrap();
+			trie.insert(&H256::from(1000).sha3(), &account_rlp(&SHA3_NULL_RLP, &SHA3_EMPTY)).unwrap();
+		}
+		sync.reset_to(&new_root);
+		assert!(!sync.is_complete());
+		let second = download(&mut sync, &db, 8);
+		assert!(sync.is_complete());
+
+		// only sub-tries on the path to the changed account are downloaded twice.
+		assert!(count(&first) + count(&second) < full + full / 4);
+		assert_eq!(sync.completed.len(), 1);
+	}
+
+	#[test]
+	fn rejects_invalid_nodes() {
+		let mut sync = StateSync::new();
+		let node = vec![0xc3, 0x01, 0x02, 0x03];
+		sync.reset_to(&node.sha3());
+		let hashes = sync.needed_nodes(1);
+		assert!(sync.insert_nodes(&hashes, &[node.clone()]).is_err());
+		// the node is requested again.
+		assert_eq!(sync.needed_nodes(1), hashes);
+	}
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use network::{NetworkContext, PeerId, PacketId, NetworkError};
+use network::{NetworkContext, PeerId, PacketId, NetworkError, SessionInfo};
 use ethcore::client::BlockChainClient;
 use ethcore::snapshot::SnapshotService;
 
@@ -38,6 +38,8 @@ pub trait SyncIo {
 	fn peer_info(&self, peer_id: PeerId) -> String {
 		peer_id.to_string()
 	}
+	/// Returns information on p2p session
+	fn peer_session_info(&self, peer_id: PeerId) -> Option<SessionInfo>;
 	/// Maximum mutuallt supported ETH protocol version
 	fn eth_protocol_version(&self, peer_id: PeerId) -> u8;
 	/// Returns if the chain block queue empty
@@ -46,6 +48,18 @@ pub trait SyncIo {
 	}
 	/// Check if the session is expired
 	fn is_expired(&self) -> bool;
+	/// Check if the download rate exceeds the configured soft limit
+	fn is_download_limited(&self) -> bool {
+		false
+	}
+	/// Check if the upload rate exceeds the configured soft limit
+	fn is_upload_limited(&self) -> bool {
+		false
+	}
+	/// Take `size` bytes from the upload allowance. Returns false if the upload limit does not allow sending them
+	fn reserve_upload(&mut self, _size: usize) -> bool {
+		true
+	}
 }
 
 /// Wraps `NetworkContext` and the blockchain client
@@ -95,10 +109,26 @@ impl<'s, 'h> SyncIo for NetSyncIo<'s, 'h> {
 		self.network.peer_info(peer_id)
 	}
 
+	fn peer_session_info(&self, peer_id: PeerId) -> Option<SessionInfo> {
+		self.network.session_info(peer_id)
+	}
+
 	fn is_expired(&self) -> bool {
 		self.network.is_expired()
 	}
 
+	fn is_download_limited(&self) -> bool {
+		self.network.is_download_limited()
+	}
+
+	fn is_upload_limited(&self) -> bool {
+		self.network.is_upload_limited()
+	}
+
+	fn reserve_upload(&mut self, size: usize) -> bool {
+		self.network.reserve_upload(size)
+	}
+
 	fn eth_protocol_version(&self, peer_id: PeerId) -> u8 {
 		self.network.protocol_version(peer_id, self.network.subprotocol_name()).unwrap_or(0)
 	}
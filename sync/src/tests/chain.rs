@@ -40,6 +40,34 @@ fn long_chain() {
 	assert_eq!(*net.peer(0).chain.blocks.read(), *net.peer(1).chain.blocks.read());
 }
 
+#[test]
+fn long_chain_from_many_peers() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(4);
+	net.peer_mut(1).chain.add_blocks(40000, EachBlockWith::Uncle);
+	net.peer_mut(2).chain.add_blocks(40000, EachBlockWith::Uncle);
+	net.peer_mut(3).chain.add_blocks(40000, EachBlockWith::Uncle);
+	net.sync();
+	assert!(net.peer(0).chain.block(BlockID::Number(40000)).is_some());
+	assert_eq!(*net.peer(0).chain.blocks.read(), *net.peer(1).chain.blocks.read());
+}
+
+#[test]
+fn many_peers_sync_in_fewer_steps() {
+	let mut single = TestNet::new(2);
+	single.peer_mut(1).chain.add_blocks(5000, EachBlockWith::Uncle);
+	let single_steps = single.sync();
+
+	let mut many = TestNet::new(4);
+	many.peer_mut(1).chain.add_blocks(5000, EachBlockWith::Uncle);
+	many.peer_mut(2).chain.add_blocks(5000, EachBlockWith::Uncle);
+	many.peer_mut(3).chain.add_blocks(5000, EachBlockWith::Uncle);
+	let many_steps = many.sync();
+
+	assert!(many.peer(0).chain.block(BlockID::Number(5000)).is_some());
+	assert!(many_steps < single_steps, "{} steps with three peers, {} with one", many_steps, single_steps);
+}
+
 #[test]
 fn status_after_sync() {
 	::env_logger::init().ok();
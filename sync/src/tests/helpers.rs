@@ -29,6 +29,10 @@ pub struct TestIo<'p> {
 	pub snapshot_service: &'p TestSnapshotService,
 	pub queue: &'p mut VecDeque<TestPacket>,
 	pub sender: Option<PeerId>,
+	pub protocol_version: u8,
+	pub download_limited: bool,
+	pub upload_limited: bool,
+	pub upload_allowance: Option<usize>,
 }
 
 impl<'p> TestIo<'p> {
@@ -37,7 +41,11 @@ impl<'p> TestIo<'p> {
 			chain: chain,
 			snapshot_service: ss,
 			queue: queue,
-			sender: sender
+			sender: sender,
+			protocol_version: 64,
+			download_limited: false,
+			upload_limited: false,
+			upload_allowance: None,
 		}
 	}
 }
@@ -53,6 +61,25 @@ impl<'p> SyncIo for TestIo<'p> {
 		false
 	}
 
+	fn is_download_limited(&self) -> bool {
+		self.download_limited
+	}
+
+	fn is_upload_limited(&self) -> bool {
+		self.upload_limited
+	}
+
+	fn reserve_upload(&mut self, size: usize) -> bool {
+		match self.upload_allowance {
+			Some(left) if left < size => false,
+			Some(left) => {
+				self.upload_allowance = Some(left - size);
+				true
+			},
+			None => true,
+		}
+	}
+
 	fn respond(&mut self, packet_id: PacketId, data: Vec<u8>) -> Result<(), NetworkError> {
 		self.queue.push_back(TestPacket {
 			data: data,
@@ -79,8 +106,12 @@ impl<'p> SyncIo for TestIo<'p> {
 		self.snapshot_service
 	}
 
+	fn peer_session_info(&self, _peer_id: PeerId) -> Option<SessionInfo> {
+		None
+	}
+
 	fn eth_protocol_version(&self, _peer: PeerId) -> u8 {
-		64
+		self.protocol_version
 	}
 }
 
@@ -27,6 +27,7 @@ pub struct TestSnapshotService {
 	restoration_manifest: Mutex<Option<ManifestData>>,
 	state_restoration_chunks: Mutex<HashMap<H256, Bytes>>,
 	block_restoration_chunks: Mutex<HashMap<H256, Bytes>>,
+	trie_restoration_nodes: Mutex<Option<Vec<(Option<H256>, Bytes)>>>,
 }
 
 impl TestSnapshotService {
@@ -37,6 +38,7 @@ impl TestSnapshotService {
 			restoration_manifest: Mutex::new(None),
 			state_restoration_chunks: Mutex::new(HashMap::new()),
 			block_restoration_chunks: Mutex::new(HashMap::new()),
+			trie_restoration_nodes: Mutex::new(None),
 		}
 	}
 
@@ -60,6 +62,7 @@ impl TestSnapshotService {
 			restoration_manifest: Mutex::new(None),
 			state_restoration_chunks: Mutex::new(HashMap::new()),
 			block_restoration_chunks: Mutex::new(HashMap::new()),
+			trie_restoration_nodes: Mutex::new(None),
 		}
 	}
 }
@@ -74,6 +77,9 @@ impl SnapshotService for TestSnapshotService {
 	}
 
 	fn status(&self) -> RestorationStatus {
+		if self.trie_restoration_nodes.lock().is_some() {
+			return RestorationStatus::Ongoing { state_chunks_done: 0, block_chunks_done: 0 };
+		}
 		match *self.restoration_manifest.lock() {
 			Some(ref manifest) if self.state_restoration_chunks.lock().len() == manifest.state_hashes.len() &&
 				self.block_restoration_chunks.lock().len() == manifest.block_hashes.len() => RestorationStatus::Inactive,
@@ -87,12 +93,14 @@ impl SnapshotService for TestSnapshotService {
 
 	fn begin_restore(&self, manifest: ManifestData) {
 		*self.restoration_manifest.lock() = Some(manifest);
+		*self.trie_restoration_nodes.lock() = None;
 		self.state_restoration_chunks.lock().clear();
 		self.block_restoration_chunks.lock().clear();
 	}
 
 	fn abort_restore(&self) {
 		*self.restoration_manifest.lock() = None;
+		*self.trie_restoration_nodes.lock() = None;
 		self.state_restoration_chunks.lock().clear();
 		self.block_restoration_chunks.lock().clear();
 	}
@@ -108,6 +116,26 @@ impl SnapshotService for TestSnapshotService {
 			self.block_restoration_chunks.lock().insert(hash, chunk);
 		}
 	}
+
+	fn begin_trie_restore(&self) {
+		*self.restoration_manifest.lock() = None;
+		*self.trie_restoration_nodes.lock() = Some(Vec::new());
+		self.state_restoration_chunks.lock().clear();
+		self.block_restoration_chunks.lock().clear();
+	}
+
+	fn restore_trie_nodes(&self, address_hash: Option<H256>, nodes: Vec<Bytes>) {
+		if let Some(ref mut restored) = *self.trie_restoration_nodes.lock() {
+			restored.extend(nodes.into_iter().map(|node| (address_hash.clone(), node)));
+		}
+	}
+
+	fn complete_trie_restore(&self, manifest: ManifestData, block_chunk: Bytes) {
+		if self.trie_restoration_nodes.lock().take().is_some() {
+			self.block_restoration_chunks.lock().insert(block_chunk.sha3(), block_chunk);
+			*self.restoration_manifest.lock() = Some(manifest);
+		}
+	}
 }
 
 #[test]
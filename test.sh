@@ -1,12 +1,12 @@
 #!/bin/sh
 # Running Parity Full Test Sute
 
-FEATURES="json-tests ipc"
+FEATURES="json-tests ipc secretstore"
 OPTIONS="--release"
 
 case $1 in
     --no-json)
-    FEATURES="ipc"
+    FEATURES="ipc secretstore"
     shift # past argument=value
     ;;
 	--no-release)
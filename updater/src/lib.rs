@@ -0,0 +1,34 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Release updater: checks a release manifest for new versions
+//! and downloads signed binaries to be used after a restart.
+
+#[macro_use]
+extern crate log;
+extern crate serde_json;
+extern crate ethcore_util as util;
+extern crate ethkey;
+extern crate fetch;
+
+#[cfg(test)]
+extern crate ethcore_devtools as devtools;
+
+mod release;
+mod updater;
+
+pub use release::{Release, Version, platform};
+pub use updater::{Updater, UpdaterConfig, UpdateFilter, Upgrade, Service, installed_binary};
@@ -0,0 +1,196 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Release manifest parsing and binary verification.
+//!
+//! The manifest is a JSON document describing the latest release:
+//!
+//! ```json
+//! {
+//!   "version": "1.4.1",
+//!   "critical": true,
+//!   "binaries": {
+//!     "linux-x86_64": { "url": "https://...", "hash": "0x...", "signature": "0x..." }
+//!   }
+//! }
+//! ```
+//!
+//! `hash` is the Keccak hash of the binary. `signature` is the release signer's signature of the whole release record,
+//! the Keccak hash of `"{version}:{critical}:{platform}:{hash}"`, so none of the fields can be swapped or altered.
+
+use std::fmt;
+use std::env::consts;
+use std::str::FromStr;
+use serde_json::{self, Value};
+use ethkey::{recover, public_to_address, Signature};
+use util::{Address, H256, Hashable};
+
+/// Version of a release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+	/// Major version.
+	pub major: u32,
+	/// Minor version.
+	pub minor: u32,
+	/// Patch version.
+	pub patch: u32,
+}
+
+impl FromStr for Version {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || format!("Invalid version: {}. Expected MAJOR.MINOR.PATCH.", s);
+		let mut parts = s.trim_left_matches('v').split('.');
+		let version = {
+			let mut next = || parts.next().and_then(|part| part.parse::<u32>().ok()).ok_or_else(|| invalid());
+			Version {
+				major: try!(next()),
+				minor: try!(next()),
+				patch: try!(next()),
+			}
+		};
+		if parts.next().is_some() {
+			return Err(invalid());
+		}
+		Ok(version)
+	}
+}
+
+impl fmt::Display for Version {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+	}
+}
+
+/// Release described by the manifest, with the binary for this platform.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+	/// Released version.
+	pub version: Version,
+	/// Whether the release contains consensus-critical fixes.
+	pub critical: bool,
+	/// Platform the binary was built for.
+	pub platform: String,
+	/// Where the binary can be downloaded from.
+	pub binary_url: String,
+	/// Keccak hash of the binary.
+	pub binary_hash: H256,
+	/// Signature of the release record by the release signer.
+	pub signature: Signature,
+}
+
+impl Release {
+	/// Parse the release manifest, picking the binary built for `platform`.
+	pub fn parse(manifest: &str, platform: &str) -> Result<Self, String> {
+		let manifest: Value = try!(serde_json::from_str(manifest).map_err(|e| format!("Invalid release manifest: {}", e)));
+		let field = |value: &Value, name: &str| value.find(name)
+			.and_then(Value::as_str)
+			.map(|s| s.trim_left_matches("0x").to_owned())
+			.ok_or_else(|| format!("Release manifest is missing {}.", name));
+
+		let version = try!(try!(field(&manifest, "version")).parse());
+		let critical = manifest.find("critical").and_then(Value::as_bool).unwrap_or(false);
+		let binary = try!(manifest.find("binaries").and_then(|b| b.find(platform))
+			.ok_or_else(|| format!("No binary released for {}.", platform)));
+		let hash = try!(field(binary, "hash"));
+		let signature = try!(field(binary, "signature"));
+
+		Ok(Release {
+			version: version,
+			critical: critical,
+			platform: platform.to_owned(),
+			binary_url: try!(field(binary, "url")),
+			binary_hash: try!(hash.parse().map_err(|_| format!("Invalid binary hash: {}", hash))),
+			signature: try!(signature.parse().map_err(|_| format!("Invalid binary signature: {}", signature))),
+		})
+	}
+
+	/// Hash of the release record, which is what the release signer signs.
+	pub fn record_hash(&self) -> H256 {
+		format!("{}:{}:{}:{:?}", self.version, self.critical, self.platform, self.binary_hash).sha3()
+	}
+
+	/// Check that the release record was signed by `signer` and that `binary` is the released one.
+	pub fn verify(&self, binary: &[u8], signer: &Address) -> Result<(), String> {
+		let public = try!(recover(&self.signature, &self.record_hash()).map_err(|e| format!("Invalid release signature: {}", e)));
+		let address = public_to_address(&public);
+		if &address != signer {
+			return Err(format!("Release signed by {:?} instead of {:?}.", address, signer));
+		}
+
+		let hash = binary.sha3();
+		if hash != self.binary_hash {
+			return Err(format!("Binary hash mismatch: expected {:?}, got {:?}.", self.binary_hash, hash));
+		}
+		Ok(())
+	}
+}
+
+/// Platform the binaries in the manifest are keyed by, e.g. `linux-x86_64`.
+pub fn platform() -> String {
+	format!("{}-{}", consts::OS, consts::ARCH)
+}
+
+#[cfg(test)]
+mod tests {
+	use ethkey::{sign, Generator, Random};
+	use util::Hashable;
+	use super::{Release, Version};
+
+	#[test]
+	fn should_parse_and_compare_versions() {
+		let version: Version = "1.4.10".parse().unwrap();
+		assert_eq!(version, Version { major: 1, minor: 4, patch: 10 });
+		assert_eq!("v1.4.10".parse::<Version>(), Ok(version));
+		assert!(version > "1.4.9".parse().unwrap());
+		assert!(version < "1.5.0".parse().unwrap());
+		assert!("1.4".parse::<Version>().is_err());
+		assert!("1.4.x".parse::<Version>().is_err());
+		assert!("1.4.1.2".parse::<Version>().is_err());
+	}
+
+	#[test]
+	fn should_parse_manifest_and_verify_binary() {
+		let binary = b"parity binary";
+		let keypair = Random.generate().unwrap();
+		let record = format!("1.4.1:true:linux-x86_64:{:?}", binary.sha3());
+		let signature = sign(keypair.secret(), &record.sha3()).unwrap();
+		let manifest = |critical: bool| format!(r#"{{
+			"version": "1.4.1",
+			"critical": {},
+			"binaries": {{
+				"linux-x86_64": {{ "url": "https://example.com/parity", "hash": "0x{:?}", "signature": "0x{}" }}
+			}}
+		}}"#, critical, binary.sha3(), signature);
+
+		let release = Release::parse(&manifest(true), "linux-x86_64").unwrap();
+		assert_eq!(release.version, "1.4.1".parse().unwrap());
+		assert!(release.critical);
+		assert_eq!(release.platform, "linux-x86_64");
+		assert_eq!(release.binary_url, "https://example.com/parity");
+		assert_eq!(release.record_hash(), record.sha3());
+		assert!(release.verify(binary, &keypair.address()).is_ok());
+		assert!(release.verify(b"other binary", &keypair.address()).is_err());
+		assert!(release.verify(binary, &Random.generate().unwrap().address()).is_err());
+
+		// the signature covers the whole record
+		let downgraded = Release::parse(&manifest(false), "linux-x86_64").unwrap();
+		assert!(downgraded.verify(binary, &keypair.address()).is_err());
+
+		assert!(Release::parse(&manifest(true), "windows-x86_64").is_err());
+	}
+}
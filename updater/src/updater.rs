@@ -0,0 +1,337 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Periodic release checks and installation of new binaries.
+
+use std::{fs, thread};
+use std::env::consts::EXE_SUFFIX;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+use fetch::{self, Fetch};
+use util::{Address, H256, Hashable, Mutex};
+use release::{Release, Version, platform};
+
+/// How often the release manifest is checked.
+const POLL_INTERVAL_SECS: u64 = 60 * 60;
+/// Maximal size of a downloaded binary.
+const MAX_BINARY_SIZE: usize = 128 * 1024 * 1024;
+/// File in the updates directory holding the version and hash of the last installed binary.
+const LATEST_FILE: &'static str = "latest";
+
+/// Which releases are installed automatically.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdateFilter {
+	/// Only notify about new releases.
+	None,
+	/// Install releases marked as critical.
+	Critical,
+	/// Install all releases.
+	All,
+}
+
+impl UpdateFilter {
+	/// Whether `release` should be installed.
+	pub fn installs(&self, release: &Release) -> bool {
+		match *self {
+			UpdateFilter::None => false,
+			UpdateFilter::Critical => release.critical,
+			UpdateFilter::All => true,
+		}
+	}
+}
+
+impl FromStr for UpdateFilter {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"none" => Ok(UpdateFilter::None),
+			"critical" => Ok(UpdateFilter::Critical),
+			"all" => Ok(UpdateFilter::All),
+			other => Err(format!("Invalid auto-update policy: {}", other)),
+		}
+	}
+}
+
+/// Updater configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdaterConfig {
+	/// Which releases are installed.
+	pub filter: UpdateFilter,
+	/// URL of the release manifest.
+	pub manifest_url: String,
+	/// Address releases must be signed by. Required if the filter installs anything.
+	pub signer: Option<Address>,
+	/// Directory the downloaded binaries are stored in.
+	pub updates_path: PathBuf,
+	/// Version of the running binary.
+	pub current_version: Version,
+}
+
+/// Release newer than the running binary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upgrade {
+	/// The release.
+	pub release: Release,
+	/// Whether the binary has been installed and will be used after a restart.
+	pub installed: bool,
+}
+
+/// Updater interface exposed to RPC.
+pub trait Service: Send + Sync {
+	/// Newest release found, if it's newer than the running binary.
+	fn upgrade_ready(&self) -> Option<Upgrade>;
+}
+
+/// Checks the release manifest and installs new binaries according to the update filter.
+pub struct Updater {
+	config: UpdaterConfig,
+	fetch: Mutex<fetch::Client>,
+	upgrade: Mutex<Option<Upgrade>>,
+}
+
+impl Updater {
+	/// Create a new updater. Call `start` to begin polling.
+	pub fn new(config: UpdaterConfig) -> Result<Self, String> {
+		if config.filter != UpdateFilter::None && config.signer.is_none() {
+			return Err("A release signer is required to install updates automatically.".into());
+		}
+
+		Ok(Updater {
+			config: config,
+			fetch: Mutex::new(fetch::Client::with_limit(Some(MAX_BINARY_SIZE))),
+			upgrade: Mutex::new(None),
+		})
+	}
+
+	/// Poll the release manifest in a background thread until the updater is dropped.
+	pub fn start(this: &Arc<Self>) -> Result<(), String> {
+		let updater = Arc::downgrade(this);
+		thread::Builder::new().name("updater".into()).spawn(move || Self::run(updater))
+			.map(|_| ())
+			.map_err(|e| format!("Error starting updater: {}", e))
+	}
+
+	fn run(updater: Weak<Self>) {
+		loop {
+			match updater.upgrade() {
+				Some(updater) => if let Err(e) = updater.poll() {
+					warn!(target: "updater", "Error checking for new releases: {}", e);
+				},
+				None => return,
+			}
+			thread::sleep(Duration::from_secs(POLL_INTERVAL_SECS));
+		}
+	}
+
+	/// Check for a new release, installing it if the filter allows.
+	pub fn poll(&self) -> Result<(), String> {
+		let manifest = try!(self.download(&self.config.manifest_url));
+		let manifest = try!(String::from_utf8(manifest).map_err(|_| "Release manifest is not valid UTF-8.".to_owned()));
+		let release = try!(Release::parse(&manifest, &platform()));
+
+		if release.version <= self.config.current_version {
+			trace!(target: "updater", "Running the latest release ({})", self.config.current_version);
+			return Ok(());
+		}
+		if self.upgrade.lock().as_ref().map_or(false, |upgrade| upgrade.release == release) {
+			return Ok(());
+		}
+
+		info!(target: "updater", "New release available: {}{}", release.version, if release.critical { " (critical)" } else { "" });
+		let installed = self.config.filter.installs(&release);
+		if installed {
+			try!(self.install(&release));
+			info!(target: "updater", "Release {} installed, restart to use it.", release.version);
+		}
+
+		*self.upgrade.lock() = Some(Upgrade {
+			release: release,
+			installed: installed,
+		});
+		Ok(())
+	}
+
+	fn install(&self, release: &Release) -> Result<(), String> {
+		let signer = try!(self.config.signer.ok_or_else(|| "No release signer configured.".to_owned()));
+		let binary = try!(self.download(&release.binary_url));
+		try!(release.verify(&binary, &signer));
+
+		let path = binary_path(&self.config.updates_path, &release.version);
+		try!(fs::create_dir_all(&self.config.updates_path).map_err(|e| format!("Error creating updates directory: {}", e)));
+		try!(write_file(&path, &binary, true).map_err(|e| format!("Error writing {}: {}", path.display(), e)));
+
+		let latest = self.config.updates_path.join(LATEST_FILE);
+		write_file(&latest, format!("{} {:?}", release.version, release.binary_hash).as_bytes(), false)
+			.map_err(|e| format!("Error writing {}: {}", latest.display(), e))
+	}
+
+	fn download(&self, url: &str) -> Result<Vec<u8>, String> {
+		let abort = Arc::new(AtomicBool::new(false));
+		let rx = try!(self.fetch.lock().request(url, abort, Box::new(|| {}))
+			.map_err(|e| format!("Error fetching {}: {:?}", url, e)));
+		let path = try!(try!(rx.recv().map_err(|_| format!("Fetching {} aborted.", url)))
+			.map_err(|e| format!("Error fetching {}: {:?}", url, e)));
+
+		let mut content = Vec::new();
+		let result = fs::File::open(&path).and_then(|mut file| file.read_to_end(&mut content));
+		let _ = fs::remove_file(&path);
+		try!(result.map_err(|e| format!("Error reading {}: {}", path.display(), e)));
+		Ok(content)
+	}
+}
+
+impl Service for Updater {
+	fn upgrade_ready(&self) -> Option<Upgrade> {
+		self.upgrade.lock().clone()
+	}
+}
+
+/// Binary installed by the updater which is newer than `current`, if any.
+/// Fails if the binary no longer matches the hash of the release it was installed from.
+pub fn installed_binary(updates_path: &Path, current: &Version) -> Result<Option<PathBuf>, String> {
+	let (version, hash) = match read_latest(updates_path) {
+		Some((version, _)) if version <= *current => return Ok(None),
+		Some(latest) => latest,
+		None => return Ok(None),
+	};
+
+	let path = binary_path(updates_path, &version);
+	let mut binary = Vec::new();
+	if fs::File::open(&path).and_then(|mut file| file.read_to_end(&mut binary)).is_err() {
+		return Ok(None);
+	}
+	if binary.sha3() != hash {
+		return Err(format!("{} does not match the hash of release {}.", path.display(), version));
+	}
+	Ok(Some(path))
+}
+
+/// Version and binary hash of the last installed release.
+fn read_latest(updates_path: &Path) -> Option<(Version, H256)> {
+	let mut latest = String::new();
+	if fs::File::open(updates_path.join(LATEST_FILE)).and_then(|mut file| file.read_to_string(&mut latest)).is_err() {
+		return None;
+	}
+
+	let mut fields = latest.split_whitespace();
+	let version = fields.next().and_then(|version| version.parse::<Version>().ok());
+	let hash = fields.next().and_then(|hash| hash.trim_left_matches("0x").parse::<H256>().ok());
+	match (version, hash) {
+		(Some(version), Some(hash)) => Some((version, hash)),
+		_ => None,
+	}
+}
+
+fn binary_path(updates_path: &Path, version: &Version) -> PathBuf {
+	updates_path.join(format!("parity-{}{}", version, EXE_SUFFIX))
+}
+
+/// Write the file next to `path` and rename it, so a crash never leaves a partially written file behind.
+fn write_file(path: &Path, content: &[u8], executable: bool) -> ::std::io::Result<()> {
+	let mut temp = path.to_path_buf().into_os_string();
+	temp.push(".tmp");
+	let temp = PathBuf::from(temp);
+
+	let write = || -> ::std::io::Result<()> {
+		let mut file = try!(fs::File::create(&temp));
+		try!(file.write_all(content));
+		try!(file.sync_all());
+		if executable {
+			try!(make_executable(&temp));
+		}
+		fs::rename(&temp, path)
+	};
+	write().map_err(|e| {
+		let _ = fs::remove_file(&temp);
+		e
+	})
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> ::std::io::Result<()> {
+	use std::os::unix::fs::PermissionsExt;
+	fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> ::std::io::Result<()> {
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::io::{Read, Write};
+	use devtools::RandomTempPath;
+	use util::Hashable;
+	use release::Version;
+	use super::{installed_binary, binary_path, write_file, UpdateFilter, LATEST_FILE};
+
+	#[test]
+	fn should_parse_update_filter() {
+		assert_eq!("none".parse(), Ok(UpdateFilter::None));
+		assert_eq!("critical".parse(), Ok(UpdateFilter::Critical));
+		assert_eq!("all".parse(), Ok(UpdateFilter::All));
+		assert!("some".parse::<UpdateFilter>().is_err());
+	}
+
+	#[test]
+	fn should_find_newer_installed_binary() {
+		let temp = RandomTempPath::create_dir();
+		let dir = temp.as_path().to_path_buf();
+		let installed: Version = "1.4.1".parse().unwrap();
+		let binary = b"parity binary";
+
+		assert_eq!(installed_binary(&dir, &"1.4.0".parse().unwrap()), Ok(None));
+		fs::File::create(dir.join(LATEST_FILE)).unwrap().write_all(format!("1.4.1 {:?}\n", binary.sha3()).as_bytes()).unwrap();
+		assert_eq!(installed_binary(&dir, &"1.4.0".parse().unwrap()), Ok(None));
+		fs::File::create(binary_path(&dir, &installed)).unwrap().write_all(binary).unwrap();
+
+		assert_eq!(installed_binary(&dir, &"1.4.0".parse().unwrap()), Ok(Some(binary_path(&dir, &installed))));
+		assert_eq!(installed_binary(&dir, &installed), Ok(None));
+	}
+
+	#[test]
+	fn should_refuse_modified_installed_binary() {
+		let temp = RandomTempPath::create_dir();
+		let dir = temp.as_path().to_path_buf();
+		let installed: Version = "1.4.1".parse().unwrap();
+
+		fs::File::create(dir.join(LATEST_FILE)).unwrap().write_all(format!("1.4.1 {:?}\n", b"parity binary".sha3()).as_bytes()).unwrap();
+		fs::File::create(binary_path(&dir, &installed)).unwrap().write_all(b"modified binary").unwrap();
+
+		assert!(installed_binary(&dir, &"1.4.0".parse().unwrap()).is_err());
+	}
+
+	#[test]
+	fn should_replace_files_atomically() {
+		let temp = RandomTempPath::create_dir();
+		let path = temp.as_path().join(LATEST_FILE);
+
+		write_file(&path, b"1.4.1", false).unwrap();
+		write_file(&path, b"1.4.2", false).unwrap();
+
+		let mut content = String::new();
+		fs::File::open(&path).unwrap().read_to_string(&mut content).unwrap();
+		assert_eq!(content, "1.4.2");
+		assert_eq!(fs::read_dir(temp.as_path()).unwrap().count(), 1);
+	}
+}
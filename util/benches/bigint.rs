@@ -85,6 +85,13 @@ fn u256_mul(b: &mut Bencher) {
 	});
 }
 
+#[bench]
+fn u256_mul_word(b: &mut Bencher) {
+	b.iter(|| {
+		let n = black_box(10000);
+		(1..n).fold(U256::zero(), |old, new| { old ^ (U256::from(black_box(new)) * U256::from(black_box(new))) })
+	});
+}
 
 #[bench]
 fn u256_full_mul(b: &mut Bencher) {
@@ -109,3 +116,29 @@ fn u128_mul(b: &mut Bencher) {
 	});
 }
 
+#[bench]
+fn u256_div(b: &mut Bencher) {
+	b.iter(|| {
+		let n = black_box(10000);
+		let max = black_box(U256::max_value());
+		(1..n).fold(U256::zero(), |old, new| { old ^ (max / U256([new, new, 0, 0])) })
+	});
+}
+
+#[bench]
+fn u256_div_small(b: &mut Bencher) {
+	b.iter(|| {
+		let n = black_box(10000);
+		let max = black_box(U256::max_value());
+		(1..n).fold(U256::zero(), |old, new| { old ^ (max / U256::from(new)) })
+	});
+}
+
+#[bench]
+fn u256_rem_word(b: &mut Bencher) {
+	b.iter(|| {
+		let n = black_box(10000);
+		(1..n).fold(U256::zero(), |old, new| { old ^ (U256::from(new * 7919) % U256::from(new)) })
+	});
+}
+
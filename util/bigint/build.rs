@@ -16,10 +16,16 @@
 
 extern crate rustc_version;
 
+use std::env;
 use rustc_version::{version_meta, Channel};
 
 fn main() {
+	// inline assembly arithmetic is opt-in with the `x64asm_arithmetic` feature and needs nightly.
+	let asm_requested = env::var_os("CARGO_FEATURE_X64ASM_ARITHMETIC").is_some()
+		&& env::var_os("CARGO_FEATURE_RUST_ARITHMETIC").is_none();
 	if let Channel::Nightly = version_meta().channel {
-		println!("cargo:rustc-cfg=asm_available");
+		if asm_requested {
+			println!("cargo:rustc-cfg=asm_available");
+		}
 	}
 }
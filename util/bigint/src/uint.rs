@@ -75,18 +75,33 @@ macro_rules! uint_overflowing_add_reg {
 		let $name(ref me) = $self_expr;
 		let $name(ref you) = $other;
 
-		let mut ret = [0u64; $n_words];
-		let mut carry = 0u64;
+		if you[1..].iter().all(|w| *w == 0) {
+			// adding a single word only has to propagate the carry.
+			let mut ret = *me;
+			let (res, mut carry) = me[0].overflowing_add(you[0]);
+			ret[0] = res;
+			let mut i = 1;
+			while carry && i < $n_words {
+				let (res, overflow) = ret[i].overflowing_add(1);
+				ret[i] = res;
+				carry = overflow;
+				i += 1;
+			}
+			($name(ret), carry)
+		} else {
+			let mut ret = [0u64; $n_words];
+			let mut carry = 0u64;
 
-		for i in 0..$n_words {
-			let (res1, overflow1) = me[i].overflowing_add(you[i]);
-			let (res2, overflow2) = res1.overflowing_add(carry);
+			for i in 0..$n_words {
+				let (res1, overflow1) = me[i].overflowing_add(you[i]);
+				let (res2, overflow2) = res1.overflowing_add(carry);
 
-			ret[i] = res2;
-			carry = overflow1 as u64 + overflow2 as u64;
-		}
+				ret[i] = res2;
+				carry = overflow1 as u64 + overflow2 as u64;
+			}
 
-		($name(ret), carry > 0)
+			($name(ret), carry > 0)
+		}
 	})
 }
 
@@ -178,19 +193,33 @@ macro_rules! uint_overflowing_sub_reg {
 		let $name(ref me) = $self_expr;
 		let $name(ref you) = $other;
 
-		let mut ret = [0u64; $n_words];
-		let mut carry = 0u64;
-
-		for i in 0..$n_words {
-			let (res1, overflow1) = me[i].overflowing_sub(you[i]);
-			let (res2, overflow2) = res1.overflowing_sub(carry);
+		if you[1..].iter().all(|w| *w == 0) {
+			// subtracting a single word only has to propagate the borrow.
+			let mut ret = *me;
+			let (res, mut borrow) = me[0].overflowing_sub(you[0]);
+			ret[0] = res;
+			let mut i = 1;
+			while borrow && i < $n_words {
+				let (res, overflow) = ret[i].overflowing_sub(1);
+				ret[i] = res;
+				borrow = overflow;
+				i += 1;
+			}
+			($name(ret), borrow)
+		} else {
+			let mut ret = [0u64; $n_words];
+			let mut carry = 0u64;
 
-			ret[i] = res2;
-			carry = overflow1 as u64 + overflow2 as u64;
-		}
+			for i in 0..$n_words {
+				let (res1, overflow1) = me[i].overflowing_sub(you[i]);
+				let (res2, overflow2) = res1.overflowing_sub(carry);
 
-		($name(ret), carry > 0)
+				ret[i] = res2;
+				carry = overflow1 as u64 + overflow2 as u64;
+			}
 
+			($name(ret), carry > 0)
+		}
 	})
 }
 
@@ -777,6 +806,12 @@ macro_rules! construct_uint {
 
 			#[inline(always)]
 			fn overflowing_mul(self, other: $name) -> ($name, bool) {
+				// operands fitting in a word are by far the most common, use a single multiplication if the product fits as well.
+				if self.bits() <= 64 && other.bits() <= 64 {
+					if let Some(result) = self.low_u64().checked_mul(other.low_u64()) {
+						return ($name::from(result), false);
+					}
+				}
 				uint_overflowing_mul!($name, $n_words, self, other)
 			}
 
@@ -794,6 +829,72 @@ macro_rules! construct_uint {
 		}
 
 		impl $name {
+			/// Division with remainder. Operands fitting in a word and small divisors,
+			/// which are by far the most common, avoid the bitwise long division.
+			fn div_mod(self, other: $name) -> ($name, $name) {
+				let my_bits = self.bits();
+				let your_bits = other.bits();
+
+				// Check for division by 0
+				assert!(your_bits != 0);
+
+				// Early return in case we are dividing by a larger number than us
+				if my_bits < your_bits {
+					return ($name::zero(), self);
+				}
+
+				if my_bits <= 64 {
+					let (me, you) = (self.low_u64(), other.low_u64());
+					return ($name::from(me / you), $name::from(me % you));
+				}
+
+				if your_bits <= 32 {
+					let (result, rem) = self.div_mod_u32(other.low_u64() as u32);
+					return (result, $name::from(rem));
+				}
+
+				let mut sub_copy = self;
+				let mut shift_copy = other;
+				let mut ret = [0u64; $n_words];
+
+				// Bitwise long division
+				let mut shift = my_bits - your_bits;
+				shift_copy = shift_copy << shift;
+				loop {
+					if sub_copy >= shift_copy {
+						ret[shift / 64] |= 1 << (shift % 64);
+						sub_copy = overflowing!(sub_copy.overflowing_sub(shift_copy));
+					}
+					shift_copy = shift_copy >> 1;
+					if shift == 0 { break; }
+					shift -= 1;
+				}
+
+				($name(ret), sub_copy)
+			}
+
+			/// Division with remainder by a 32-bit divisor, half a word at a time.
+			fn div_mod_u32(self, other: u32) -> ($name, u64) {
+				let $name(ref arr) = self;
+				let divisor = other as u64;
+				let mut ret = [0u64; $n_words];
+				let mut rem = 0u64;
+
+				for i in (0..$n_words).rev() {
+					let (upper, lower) = split(arr[i]);
+					// the remainder is smaller than the divisor, so this can't overflow
+					let part = (rem << 32) | upper;
+					let result_upper = part / divisor;
+					rem = part % divisor;
+					let part = (rem << 32) | lower;
+					let result_lower = part / divisor;
+					rem = part % divisor;
+					ret[i] = (result_upper << 32) | result_lower;
+				}
+
+				($name(ret), rem)
+			}
+
 			/// Multiplication by u32
 			#[allow(dead_code)] // not used when multiplied with inline assembly
 			fn mul_u32(self, other: u32) -> Self {
@@ -917,35 +1018,7 @@ macro_rules! construct_uint {
 			type Output = $name;
 
 			fn div(self, other: $name) -> $name {
-				let mut sub_copy = self;
-				let mut shift_copy = other;
-				let mut ret = [0u64; $n_words];
-
-				let my_bits = self.bits();
-				let your_bits = other.bits();
-
-				// Check for division by 0
-				assert!(your_bits != 0);
-
-				// Early return in case we are dividing by a larger number than us
-				if my_bits < your_bits {
-					return $name(ret);
-				}
-
-				// Bitwise long division
-				let mut shift = my_bits - your_bits;
-				shift_copy = shift_copy << shift;
-				loop {
-					if sub_copy >= shift_copy {
-						ret[shift / 64] |= 1 << (shift % 64);
-						sub_copy = overflowing!(sub_copy.overflowing_sub(shift_copy));
-					}
-					shift_copy = shift_copy >> 1;
-					if shift == 0 { break; }
-					shift -= 1;
-				}
-
-				$name(ret)
+				self.div_mod(other).0
 			}
 		}
 
@@ -953,8 +1026,7 @@ macro_rules! construct_uint {
 			type Output = $name;
 
 			fn rem(self, other: $name) -> $name {
-				let times = self / other;
-				self - (times * other)
+				self.div_mod(other).1
 			}
 		}
 
@@ -1761,6 +1833,16 @@ mod tests {
 			);
 	}
 
+	#[test]
+	pub fn uint_word_add_sub_propagate_carry() {
+		assert_eq!(U256([!0, !0, 0, 0]).overflowing_add(U256::from(1)), (U256([0, 0, 1, 0]), false));
+		assert_eq!(U256::max_value().overflowing_add(U256::from(2)), (U256::from(1), true));
+		assert_eq!(U256([0, 0, 1, 0]).overflowing_sub(U256::from(1)), (U256([!0, !0, 0, 0]), false));
+		assert_eq!(U256::from(1).overflowing_sub(U256::from(2)), (U256::max_value(), true));
+		assert_eq!(U512([!0, !0, !0, !0, !0, !0, !0, 0]).overflowing_add(U512::from(1)), (U512([0, 0, 0, 0, 0, 0, 0, 1]), false));
+		assert_eq!(U128([0, 1]).overflowing_sub(U128::from(1)), (U128([!0, 0]), false));
+	}
+
 	#[test]
 	#[should_panic]
 	pub fn uint256_sub_overflow_panic() {
@@ -1815,6 +1897,46 @@ mod tests {
 		assert_eq!(U256::from(10u64) % U256::from(3u64), U256::from(1u64));
 	}
 
+	#[test]
+	fn uint256_mul_word_paths() {
+		assert_eq!(U256::from(u64::max_value()) * U256::one(), U256::from(u64::max_value()));
+		assert_eq!(U256::from(0xffff_ffffu64) * U256::from(0xffff_ffffu64), U256::from(0xffff_fffe_0000_0001u64));
+		assert_eq!(U256::from(1u64 << 32) * U256::from(1u64 << 32), U256([0, 1, 0, 0]));
+		assert_eq!(U256::from(u64::max_value()) * U256::from(u64::max_value()), U256([1, u64::max_value() - 1, 0, 0]));
+		assert_eq!(U128::from(u64::max_value()).overflowing_mul(U128::from(2u64)), (U128([u64::max_value() - 1, 1]), false));
+	}
+
+	#[test]
+	fn uint256_div_mod_paths() {
+		let big = U256([0x1234_5678_9abc_def0, 0xffff_ffff_ffff_ffff, 0x8000_0000_0000_0001, 0x0fed_cba9_8765_4321]);
+		let divisors = [
+			U256::from(7u64),
+			U256::from(0xffff_ffffu64),
+			U256::from(0x1_0000_0000u64),
+			U256::from(u64::max_value()),
+			U256([0, 3, 0, 0]),
+			U256([5, 0, 0, 0x0fed_cba9_8765_4321]),
+			big,
+		];
+
+		for divisor in &divisors {
+			let (result, rem) = (big / *divisor, big % *divisor);
+			assert!(rem < *divisor);
+			assert_eq!(result * *divisor + rem, big);
+		}
+
+		assert_eq!(U256::from(u64::max_value()) / U256::from(10u64), U256::from(u64::max_value() / 10));
+		assert_eq!(U256::from(u64::max_value()) % U256::from(10u64), U256::from(u64::max_value() % 10));
+		assert_eq!(U256::from(5u64) / big, U256::zero());
+		assert_eq!(U256::from(5u64) % big, U256::from(5u64));
+	}
+
+	#[test]
+	#[should_panic]
+	fn uint256_div_by_zero() {
+		let _ = U256::from(10u64) / U256::zero();
+	}
+
 	#[test]
 	fn uint256_from_dec_str() {
 		assert_eq!(U256::from_dec_str("10").unwrap(), U256::from(10u64));
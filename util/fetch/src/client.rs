@@ -91,7 +91,8 @@ impl Default for Client {
 }
 
 impl Client {
-	fn with_limit(limit: Option<usize>) -> Self {
+	/// Create a client downloading at most `limit` bytes per request.
+	pub fn with_limit(limit: Option<usize>) -> Self {
 		Client {
 			http_client: hyper::Client::new().expect("Unable to initialize http client."),
 			https_client: https::Client::with_limit(limit).expect("Unable to initialize https client."),
@@ -0,0 +1,36 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Connection filter trait.
+
+use node_table::NodeId;
+
+/// Filtered connection direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionDirection {
+	/// Connection initiated by the remote node.
+	Inbound,
+	/// Connection initiated by this node.
+	Outbound,
+}
+
+/// Connection filter. Each connection is checked against the filter once the node id of the
+/// remote peer is known and before any protocol is started, and then periodically while it is
+/// open, so peers are disconnected when the filter stops allowing them.
+pub trait ConnectionFilter : Send + Sync {
+	/// Filter a connection. Returns `true` if the connection is allowed.
+	fn connection_allowed(&self, own_id: &NodeId, connecting_id: &NodeId, direction: ConnectionDirection) -> bool;
+}
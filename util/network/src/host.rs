@@ -31,14 +31,15 @@ use util::hash::*;
 use util::Hashable;
 use util::version;
 use rlp::*;
-use session::{Session, SessionData};
+use session::{Session, SessionData, SessionInfo};
 use error::*;
 use io::*;
-use {NetworkProtocolHandler, NonReservedPeerMode, PROTOCOL_VERSION};
+use {NetworkProtocolHandler, NonReservedPeerMode, NatType, PROTOCOL_VERSION};
 use node_table::*;
 use stats::NetworkStats;
 use discovery::{Discovery, TableUpdates, NodeEntry};
-use ip_utils::{map_external_address, select_public_address};
+use connection_filter::{ConnectionFilter, ConnectionDirection};
+use ip_utils::{map_external_address, renew_nat_pmp_mapping, select_public_address, NAT_PMP_LIFETIME_SEC};
 use util::path::restrict_permissions_owner;
 use parking_lot::{Mutex, RwLock};
 
@@ -64,6 +65,8 @@ pub struct NetworkConfiguration {
 	pub udp_port: Option<u16>,
 	/// Enable NAT configuration
 	pub nat_enabled: bool,
+	/// NAT traversal method to use when NAT configuration is enabled
+	pub nat_type: NatType,
 	/// Enable discovery
 	pub discovery_enabled: bool,
 	/// List of initial node addresses
@@ -78,6 +81,10 @@ pub struct NetworkConfiguration {
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub non_reserved_mode: NonReservedPeerMode,
+	/// Download soft limit in bytes per second
+	pub max_download: Option<usize>,
+	/// Upload soft limit in bytes per second
+	pub max_upload: Option<usize>,
 }
 
 impl Default for NetworkConfiguration {
@@ -96,6 +103,7 @@ impl NetworkConfiguration {
 			public_address: None,
 			udp_port: None,
 			nat_enabled: true,
+			nat_type: NatType::Any,
 			discovery_enabled: true,
 			boot_nodes: Vec::new(),
 			use_secret: None,
@@ -103,6 +111,8 @@ impl NetworkConfiguration {
 			max_peers: 50,
 			reserved_nodes: Vec::new(),
 			non_reserved_mode: NonReservedPeerMode::Accept,
+			max_download: None,
+			max_upload: None,
 		}
 	}
 
@@ -129,6 +139,7 @@ const DISCOVERY: usize = SYS_TIMER + 3;
 const DISCOVERY_REFRESH: usize = SYS_TIMER + 4;
 const DISCOVERY_ROUND: usize = SYS_TIMER + 5;
 const NODE_TABLE: usize = SYS_TIMER + 6;
+const NAT_RENEWAL: usize = SYS_TIMER + 7;
 const FIRST_SESSION: usize = 0;
 const LAST_SESSION: usize = FIRST_SESSION + MAX_SESSIONS - 1;
 const USER_TIMER: usize = LAST_SESSION + 256;
@@ -197,7 +208,8 @@ pub struct NetworkContext<'s> {
 	sessions: Arc<RwLock<Slab<SharedSession>>>,
 	session: Option<SharedSession>,
 	session_id: Option<StreamToken>,
-	_reserved_peers: &'s HashSet<NodeId>,
+	reserved_peers: &'s HashSet<NodeId>,
+	stats: &'s NetworkStats,
 }
 
 impl<'s> NetworkContext<'s> {
@@ -205,7 +217,7 @@ impl<'s> NetworkContext<'s> {
 	fn new(io: &'s IoContext<NetworkIoMessage>,
 		protocol: ProtocolId,
 		session: Option<SharedSession>, sessions: Arc<RwLock<Slab<SharedSession>>>,
-		reserved_peers: &'s HashSet<NodeId>) -> NetworkContext<'s> {
+		reserved_peers: &'s HashSet<NodeId>, stats: &'s NetworkStats) -> NetworkContext<'s> {
 		let id = session.as_ref().map(|s| s.lock().token());
 		NetworkContext {
 			io: io,
@@ -213,7 +225,8 @@ impl<'s> NetworkContext<'s> {
 			session_id: id,
 			session: session,
 			sessions: sessions,
-			_reserved_peers: reserved_peers,
+			reserved_peers: reserved_peers,
+			stats: stats,
 		}
 	}
 
@@ -247,18 +260,34 @@ impl<'s> NetworkContext<'s> {
 	}
 
 	/// Disable current protocol capability for given peer. If no capabilities left peer gets disconnected.
+	/// Reserved peers are never disabled.
 	pub fn disable_peer(&self, peer: PeerId) {
+		if self.is_reserved_peer(peer) {
+			trace!(target: "network", "Not disabling reserved peer {}", peer);
+			return;
+		}
 		//TODO: remove capability, disconnect if no capabilities left
 		self.io.message(NetworkIoMessage::DisablePeer(peer))
 			.unwrap_or_else(|e| warn!("Error sending network IO message: {:?}", e));
 	}
 
-	/// Disconnect peer. Reconnect can be attempted later.
+	/// Disconnect peer. Reconnect can be attempted later. Reserved peers are never disconnected.
 	pub fn disconnect_peer(&self, peer: PeerId) {
+		if self.is_reserved_peer(peer) {
+			trace!(target: "network", "Not disconnecting reserved peer {}", peer);
+			return;
+		}
 		self.io.message(NetworkIoMessage::Disconnect(peer))
 			.unwrap_or_else(|e| warn!("Error sending network IO message: {:?}", e));
 	}
 
+	/// Check if the given peer is a reserved node.
+	pub fn is_reserved_peer(&self, peer: PeerId) -> bool {
+		self.resolve_session(peer)
+			.and_then(|s| s.lock().id().cloned())
+			.map_or(false, |id| self.reserved_peers.contains(&id))
+	}
+
 	/// Check if the session is still active.
 	pub fn is_expired(&self) -> bool {
 		self.session.as_ref().map_or(false, |s| s.lock().expired())
@@ -283,6 +312,11 @@ impl<'s> NetworkContext<'s> {
 		"unknown".to_owned()
 	}
 
+	/// Returns information on p2p session
+	pub fn session_info(&self, peer: PeerId) -> Option<SessionInfo> {
+		self.resolve_session(peer).map(|s| s.lock().info.clone())
+	}
+
 	/// Returns max version for a given protocol.
 	pub fn protocol_version(&self, peer: PeerId, protocol: ProtocolId) -> Option<u8> {
 		let session = self.resolve_session(peer);
@@ -291,6 +325,21 @@ impl<'s> NetworkContext<'s> {
 
 	/// Returns this object's subprotocol name.
 	pub fn subprotocol_name(&self) -> ProtocolId { self.protocol }
+
+	/// Check if the download rate exceeds the configured soft limit.
+	pub fn is_download_limited(&self) -> bool {
+		self.stats.is_download_limited()
+	}
+
+	/// Check if the upload rate exceeds the configured soft limit.
+	pub fn is_upload_limited(&self) -> bool {
+		self.stats.is_upload_limited()
+	}
+
+	/// Take `size` bytes from the upload allowance. Returns false if sending them would exceed the upload limit.
+	pub fn reserve_upload(&self, size: usize) -> bool {
+		self.stats.reserve_upload(size)
+	}
 }
 
 /// Shared host information
@@ -353,11 +402,12 @@ pub struct Host {
 	reserved_nodes: RwLock<HashSet<NodeId>>,
 	num_sessions: AtomicUsize,
 	stopping: AtomicBool,
+	filter: Option<Arc<ConnectionFilter>>,
 }
 
 impl Host {
 	/// Create a new instance
-	pub fn new(config: NetworkConfiguration, stats: Arc<NetworkStats>) -> Result<Host, NetworkError> {
+	pub fn new(config: NetworkConfiguration, stats: Arc<NetworkStats>, filter: Option<Arc<ConnectionFilter>>) -> Result<Host, NetworkError> {
 		trace!(target: "host", "Creating new Host object");
 
 		let mut listen_address = match config.listen_address {
@@ -387,6 +437,7 @@ impl Host {
 
 		let boot_nodes = config.boot_nodes.clone();
 		let reserved_nodes = config.reserved_nodes.clone();
+		stats.set_limits(config.max_download, config.max_upload);
 
 		let mut host = Host {
 			info: RwLock::new(HostInfo {
@@ -410,6 +461,7 @@ impl Host {
 			reserved_nodes: RwLock::new(HashSet::new()),
 			num_sessions: AtomicUsize::new(0),
 			stopping: AtomicBool::new(false),
+			filter: filter,
 		};
 
 		for n in boot_nodes {
@@ -529,10 +581,18 @@ impl Host {
 			None => {
 				let public_address = select_public_address(local_endpoint.address.port());
 				let public_endpoint = NodeEndpoint { address: public_address, udp_port: local_endpoint.udp_port };
-				if self.info.read().config.nat_enabled {
-					match map_external_address(&local_endpoint) {
-						Some(endpoint) => {
-							info!("NAT mapped to external address {}", endpoint.address);
+				let (nat_enabled, nat_type) = {
+					let info = self.info.read();
+					(info.config.nat_enabled, info.config.nat_type)
+				};
+				if nat_enabled {
+					match map_external_address(&local_endpoint, nat_type) {
+						Some((endpoint, mapped_with)) => {
+							info!("NAT mapped to external address {} using {:?}", endpoint.address, mapped_with);
+							if mapped_with == NatType::NatPmp {
+								// NAT-PMP mappings expire and have to be refreshed
+								try!(io.register_timer(NAT_RENEWAL, NAT_PMP_LIFETIME_SEC as u64 * 500));
+							}
 							endpoint
 						},
 						None => public_endpoint
@@ -574,7 +634,9 @@ impl Host {
 	}
 
 	fn maintain_network(&self, io: &IoContext<NetworkIoMessage>) {
+		self.stats.update_rates();
 		self.keep_alive(io);
+		self.check_filter(io);
 		self.connect_peers(io);
 	}
 
@@ -597,11 +659,21 @@ impl Host {
 
 	fn keep_alive(&self, io: &IoContext<NetworkIoMessage>) {
 		let mut to_kill = Vec::new();
+		let mut timed_out = Vec::new();
 		for e in self.sessions.write().iter_mut() {
 			let mut s = e.lock();
 			if !s.keep_alive(io) {
 				s.disconnect(io, DisconnectReason::PingTimeout);
 				to_kill.push(s.token());
+				if let Some(id) = s.id() {
+					timed_out.push(id.clone());
+				}
+			}
+		}
+		{
+			let mut nodes = self.nodes.write();
+			for id in timed_out {
+				nodes.note_penalty(&id, Penalty::Timeout);
 			}
 		}
 		for p in to_kill {
@@ -610,6 +682,41 @@ impl Host {
 		}
 	}
 
+	/// Disconnect the peers which are no longer allowed by the connection filter.
+	/// The filter is called without holding the session locks.
+	fn check_filter(&self, io: &IoContext<NetworkIoMessage>) {
+		let filter = match self.filter {
+			Some(ref filter) => filter,
+			None => return,
+		};
+		let peers: Vec<_> = self.sessions.read().iter().filter_map(|e| {
+			let s = e.lock();
+			let direction = if s.info.originated { ConnectionDirection::Outbound } else { ConnectionDirection::Inbound };
+			if s.is_ready() {
+				s.info.id.clone().map(|id| (s.token(), id, direction))
+			} else {
+				None
+			}
+		}).collect();
+		if peers.is_empty() {
+			return;
+		}
+
+		let own_id = self.info.read().id().clone();
+		for (token, id, direction) in peers {
+			if filter.connection_allowed(&own_id, &id, direction) {
+				continue;
+			}
+			trace!(target: "network", "Connection to {:?} no longer allowed by the filter", id);
+			self.nodes.write().mark_as_useless(&id);
+			let session = self.sessions.read().get(token).cloned();
+			if let Some(session) = session {
+				session.lock().disconnect(io, DisconnectReason::UselessPeer);
+			}
+			self.kill_connection(token, io, true);
+		}
+	}
+
 	fn connect_peers(&self, io: &IoContext<NetworkIoMessage>) {
 		let (min_peers, mut pin) = {
 			let info = self.info.read();
@@ -770,7 +877,9 @@ impl Host {
 						if let NetworkError::Disconnect(DisconnectReason::IncompatibleProtocol) = e {
 							if let Some(id) = s.id() {
 								if !self.reserved_nodes.read().contains(id) {
-									self.nodes.write().mark_as_useless(id);
+									let mut nodes = self.nodes.write();
+									nodes.mark_as_useless(id);
+									nodes.note_penalty(id, Penalty::UselessPeer);
 								}
 							}
 						}
@@ -779,6 +888,16 @@ impl Host {
 					},
 					Ok(SessionData::Ready) => {
 						self.num_sessions.fetch_add(1, AtomicOrdering::SeqCst);
+						let id = s.id().expect("Ready session always has id").clone();
+						if let Some(ref filter) = self.filter {
+							let direction = if s.info.originated { ConnectionDirection::Outbound } else { ConnectionDirection::Inbound };
+							if !filter.connection_allowed(self.info.read().id(), &id, direction) {
+								trace!(target: "network", "Connection to {:?} not allowed by the filter", id);
+								self.nodes.write().mark_as_useless(&id);
+								s.disconnect(io, DisconnectReason::UselessPeer);
+								return;
+							}
+						}
 						if !s.info.originated {
 							let session_count = self.session_count();
 							let (max_peers, reserved_only) = {
@@ -833,17 +952,21 @@ impl Host {
 			let h = handlers.get(&p).unwrap().clone();
 			self.stats.inc_sessions();
 			let reserved = self.reserved_nodes.read();
-			h.connected(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved), &token);
+			h.connected(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved, &self.stats), &token);
 		}
 		for (p, packet_id, data) in packet_data {
 			let h = handlers.get(&p).unwrap().clone();
 			let reserved = self.reserved_nodes.read();
-			h.read(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved), &token, packet_id, &data[1..]);
+			h.read(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved, &self.stats), &token, packet_id, &data[1..]);
 		}
 	}
 
 	fn connection_timeout(&self, token: StreamToken, io: &IoContext<NetworkIoMessage>) {
 		trace!(target: "network", "Connection timeout: {}", token);
+		let id = { self.sessions.read().get(token).and_then(|s| s.lock().id().cloned()) };
+		if let Some(id) = id {
+			self.nodes.write().note_penalty(&id, Penalty::Timeout);
+		}
 		self.kill_connection(token, io, true)
 	}
 
@@ -880,7 +1003,7 @@ impl Host {
 		for p in to_disconnect {
 			let h = self.handlers.read().get(&p).unwrap().clone();
 			let reserved = self.reserved_nodes.read();
-			h.disconnected(&NetworkContext::new(io, p, expired_session.clone(), self.sessions.clone(), &reserved), &token);
+			h.disconnected(&NetworkContext::new(io, p, expired_session.clone(), self.sessions.clone(), &reserved, &self.stats), &token);
 		}
 		if deregister {
 			io.deregister_stream(token).unwrap_or_else(|e| debug!("Error deregistering stream: {:?}", e));
@@ -910,9 +1033,16 @@ impl Host {
 	pub fn with_context<F>(&self, protocol: ProtocolId, io: &IoContext<NetworkIoMessage>, action: F) where F: Fn(&NetworkContext) {
 		let reserved = { self.reserved_nodes.read() };
 
-		let context = NetworkContext::new(io, protocol, None, self.sessions.clone(), &reserved);
+		let context = NetworkContext::new(io, protocol, None, self.sessions.clone(), &reserved, &self.stats);
 		action(&context);
 	}
+
+	pub fn with_context_eval<F, T>(&self, protocol: ProtocolId, io: &IoContext<NetworkIoMessage>, action: F) -> T where F: Fn(&NetworkContext) -> T {
+		let reserved = { self.reserved_nodes.read() };
+
+		let context = NetworkContext::new(io, protocol, None, self.sessions.clone(), &reserved, &self.stats);
+		action(&context)
+	}
 }
 
 impl IoHandler<NetworkIoMessage> for Host {
@@ -981,14 +1111,27 @@ impl IoHandler<NetworkIoMessage> for Host {
 			},
 			NODE_TABLE => {
 				trace!(target: "network", "Refreshing node table");
-				self.nodes.write().clear_useless();
+				let mut nodes = self.nodes.write();
+				nodes.clear_useless();
+				nodes.decay_reputation();
+				nodes.save();
+			},
+			NAT_RENEWAL => {
+				let (local_endpoint, public_endpoint) = {
+					let info = self.info.read();
+					(info.local_endpoint.clone(), info.public_endpoint.clone())
+				};
+				if let Some(public_endpoint) = public_endpoint {
+					trace!(target: "network", "Renewing NAT-PMP port mapping");
+					renew_nat_pmp_mapping(&local_endpoint, &public_endpoint);
+				}
 			},
 			_ => match self.timers.read().get(&token).cloned() {
 				Some(timer) => match self.handlers.read().get(&timer.protocol).cloned() {
 					None => { warn!(target: "network", "No handler found for protocol: {:?}", timer.protocol) },
 					Some(h) => {
 						let reserved = self.reserved_nodes.read();
-						h.timeout(&NetworkContext::new(io, timer.protocol, None, self.sessions.clone(), &reserved), timer.token);
+						h.timeout(&NetworkContext::new(io, timer.protocol, None, self.sessions.clone(), &reserved, &self.stats), timer.token);
 					}
 				},
 				None => { warn!("Unknown timer token: {}", token); } // timer is not registerd through us
@@ -1008,8 +1151,9 @@ impl IoHandler<NetworkIoMessage> for Host {
 			} => {
 				let h = handler.clone();
 				let reserved = self.reserved_nodes.read();
-				h.initialize(&NetworkContext::new(io, *protocol, None, self.sessions.clone(), &reserved));
+				h.initialize(&NetworkContext::new(io, *protocol, None, self.sessions.clone(), &reserved, &self.stats));
 				self.handlers.write().insert(*protocol, h);
+				self.stats.register_protocol(*protocol);
 				let mut info = self.info.write();
 				for v in versions {
 					info.capabilities.push(CapabilityInfo { protocol: *protocol, version: *v, packet_count:0 });
@@ -1043,7 +1187,9 @@ impl IoHandler<NetworkIoMessage> for Host {
 				if let Some(session) = session {
 					session.lock().disconnect(io, DisconnectReason::DisconnectRequested);
 					if let Some(id) = session.lock().id() {
-						self.nodes.write().mark_as_useless(id)
+						let mut nodes = self.nodes.write();
+						nodes.mark_as_useless(id);
+						nodes.note_penalty(id, Penalty::BadBlock);
 					}
 				}
 				trace!(target: "network", "Disabling peer {}", peer);
@@ -1164,6 +1310,6 @@ fn host_client_url() {
 	let mut config = NetworkConfiguration::new();
 	let key = "6f7b0d801bc7b5ce7bbd930b84fd0369b3eb25d09be58d64ba811091046f3aa2".into();
 	config.use_secret = Some(key);
-	let host: Host = Host::new(config, Arc::new(NetworkStats::new())).unwrap();
+	let host: Host = Host::new(config, Arc::new(NetworkStats::new()), None).unwrap();
 	assert!(host.local_url().starts_with("enode://101b3ef5a4ea7a1c7928e24c4c75fd053c235d7b80c22ae5c03d145d0ac7396e2a4ffff9adee3133a7b05044a5cee08115fd65145e5165d646bde371010d803c@"));
 }
@@ -16,11 +16,23 @@
 
 // Based on original work by David Levy https://raw.githubusercontent.com/dlevy47/rust-interfaces
 
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, UdpSocket};
 use std::io;
 use igd::{PortMappingProtocol, search_gateway_from_timeout};
 use std::time::Duration;
 use node_table::{NodeEndpoint};
+use NatType;
+
+/// Lifetime of NAT-PMP port mappings in seconds.
+pub const NAT_PMP_LIFETIME_SEC: u32 = 7200;
+const NAT_PMP_PORT: u16 = 5351;
+/// Retransmissions are capped well below the RFC 6886 schedule so that an absent gateway
+/// delays startup by less than a second.
+const NAT_PMP_ATTEMPTS: usize = 2;
+const NAT_PMP_INITIAL_TIMEOUT_MS: u64 = 250;
+const NAT_PMP_OP_EXTERNAL_ADDRESS: u8 = 0;
+const NAT_PMP_OP_MAP_UDP: u8 = 1;
+const NAT_PMP_OP_MAP_TCP: u8 = 2;
 
 /// Socket address extension for rustc beta. To be replaces with now unstable API
 pub trait SocketAddrExt {
@@ -163,7 +175,29 @@ pub fn select_public_address(port: u16) -> SocketAddr {
 	SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), port))
 }
 
-pub fn map_external_address(local: &NodeEndpoint) -> Option<NodeEndpoint> {
+/// Map the local endpoint on the gateway using given NAT traversal method.
+/// Returns the external endpoint and the method that succeeded.
+pub fn map_external_address(local: &NodeEndpoint, nat_type: NatType) -> Option<(NodeEndpoint, NatType)> {
+	match nat_type {
+		NatType::UPnP => map_external_address_upnp(local).map(|e| (e, NatType::UPnP)),
+		NatType::NatPmp => map_external_address_pmp(local, None).map(|e| (e, NatType::NatPmp)),
+		NatType::Any => map_external_address_upnp(local).map(|e| (e, NatType::UPnP))
+			.or_else(|| map_external_address_pmp(local, None).map(|e| (e, NatType::NatPmp))),
+	}
+}
+
+/// Refresh NAT-PMP mapping for the previously mapped external endpoint.
+pub fn renew_nat_pmp_mapping(local: &NodeEndpoint, external: &NodeEndpoint) {
+	match map_external_address_pmp(local, Some(external)) {
+		Some(ref endpoint) if endpoint.address != external.address || endpoint.udp_port != external.udp_port => {
+			warn!("NAT-PMP gateway changed external endpoint to {}; peers may be unable to connect", endpoint.address);
+		},
+		Some(_) => {},
+		None => warn!("Error renewing NAT-PMP port mapping"),
+	}
+}
+
+fn map_external_address_upnp(local: &NodeEndpoint) -> Option<NodeEndpoint> {
 	if let SocketAddr::V4(ref local_addr) = local.address {
 		match search_gateway_from_timeout(local_addr.ip().clone(), Duration::new(5, 0)) {
 			Err(ref err) => debug!("Gateway search error: {}", err),
@@ -196,6 +230,127 @@ pub fn map_external_address(local: &NodeEndpoint) -> Option<NodeEndpoint> {
 	None
 }
 
+fn map_external_address_pmp(local: &NodeEndpoint, previous: Option<&NodeEndpoint>) -> Option<NodeEndpoint> {
+	let gateway = match default_gateway() {
+		Some(gateway) => gateway,
+		None => {
+			debug!("NAT-PMP skipped, default gateway is unknown");
+			return None;
+		}
+	};
+	let (tcp_port, udp_port) = previous.map_or((0, 0), |e| (e.address.port(), e.udp_port));
+	let result = nat_pmp_external_address(&gateway).and_then(|external_ip| {
+		let tcp_port = try!(nat_pmp_map_port(&gateway, NAT_PMP_OP_MAP_TCP, local.address.port(), tcp_port));
+		let udp_port = try!(nat_pmp_map_port(&gateway, NAT_PMP_OP_MAP_UDP, local.udp_port, udp_port));
+		Ok(NodeEndpoint { address: SocketAddr::V4(SocketAddrV4::new(external_ip, tcp_port)), udp_port: udp_port })
+	});
+	match result {
+		Ok(endpoint) => Some(endpoint),
+		Err(err) => {
+			debug!("NAT-PMP error: {}", err);
+			None
+		}
+	}
+}
+
+fn nat_pmp_external_address(gateway: &Ipv4Addr) -> io::Result<Ipv4Addr> {
+	let response = try!(nat_pmp_request(gateway, &[0, NAT_PMP_OP_EXTERNAL_ADDRESS], 12));
+	Ok(Ipv4Addr::new(response[8], response[9], response[10], response[11]))
+}
+
+fn nat_pmp_map_port(gateway: &Ipv4Addr, op: u8, internal_port: u16, external_port: u16) -> io::Result<u16> {
+	let request = [
+		0, op, 0, 0,
+		(internal_port >> 8) as u8, internal_port as u8,
+		(external_port >> 8) as u8, external_port as u8,
+		(NAT_PMP_LIFETIME_SEC >> 24) as u8, (NAT_PMP_LIFETIME_SEC >> 16) as u8, (NAT_PMP_LIFETIME_SEC >> 8) as u8, NAT_PMP_LIFETIME_SEC as u8,
+	];
+	let response = try!(nat_pmp_request(gateway, &request, 16));
+	Ok(((response[10] as u16) << 8) | response[11] as u16)
+}
+
+/// Send a NAT-PMP request to the gateway, retransmitting with doubling timeout as suggested by RFC 6886.
+fn nat_pmp_request(gateway: &Ipv4Addr, request: &[u8], response_len: usize) -> io::Result<Vec<u8>> {
+	let socket = try!(UdpSocket::bind("0.0.0.0:0"));
+	let gateway = SocketAddr::V4(SocketAddrV4::new(gateway.clone(), NAT_PMP_PORT));
+	let mut buf = [0u8; 16];
+	let mut timeout = NAT_PMP_INITIAL_TIMEOUT_MS;
+	for _ in 0..NAT_PMP_ATTEMPTS {
+		try!(socket.set_read_timeout(Some(Duration::from_millis(timeout))));
+		try!(socket.send_to(request, gateway));
+		match socket.recv_from(&mut buf) {
+			Ok((len, from)) if from == gateway && len >= response_len => {
+				return parse_nat_pmp_response(request[1], &buf[..len]);
+			},
+			Ok(_) => {},
+			Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {},
+			Err(e) => return Err(e),
+		}
+		timeout *= 2;
+	}
+	Err(io::Error::new(io::ErrorKind::TimedOut, "NAT-PMP gateway did not respond"))
+}
+
+fn parse_nat_pmp_response(op: u8, response: &[u8]) -> io::Result<Vec<u8>> {
+	if response.len() < 4 || response[0] != 0 || response[1] != op + 128 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "Unexpected NAT-PMP response"));
+	}
+	let result = ((response[2] as u16) << 8) | response[3] as u16;
+	if result != 0 {
+		return Err(io::Error::new(io::ErrorKind::Other, format!("NAT-PMP request rejected with result code {}", result)));
+	}
+	Ok(response.to_vec())
+}
+
+#[cfg(target_os = "linux")]
+fn default_gateway() -> Option<Ipv4Addr> {
+	use std::fs::File;
+	use std::io::Read;
+
+	let mut routes = String::new();
+	match File::open("/proc/net/route").and_then(|mut f| f.read_to_string(&mut routes)) {
+		Ok(_) => parse_default_gateway(&routes),
+		Err(_) => None,
+	}
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> Option<Ipv4Addr> {
+	None
+}
+
+/// Extract default gateway from the `/proc/net/route` table.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+	routes.lines().skip(1)
+		.map(|line| line.split_whitespace().collect::<Vec<_>>())
+		.filter(|fields| fields.len() > 2 && fields[1] == "00000000")
+		.filter_map(|fields| u32::from_str_radix(fields[2], 16).ok())
+		.find(|gateway| *gateway != 0)
+		// the table is in host byte order, which is little endian on all supported linux targets
+		.map(|g| Ipv4Addr::new(g as u8, (g >> 8) as u8, (g >> 16) as u8, (g >> 24) as u8))
+}
+
+#[test]
+fn can_parse_default_gateway() {
+	let routes = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+		wlan0\t0000A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0\n\
+		wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0\n";
+	assert_eq!(parse_default_gateway(routes), Some(Ipv4Addr::new(192, 168, 1, 1)));
+	assert_eq!(parse_default_gateway("Iface\tDestination\tGateway\n"), None);
+}
+
+#[test]
+fn can_parse_nat_pmp_response() {
+	let response = [0u8, 130, 0, 0, 0, 0, 0, 1, 0x76, 0x5f, 0x76, 0x5f, 0, 0, 0x1c, 0x20];
+	assert_eq!(parse_nat_pmp_response(NAT_PMP_OP_MAP_TCP, &response).unwrap(), response.to_vec());
+	// wrong opcode
+	assert!(parse_nat_pmp_response(NAT_PMP_OP_MAP_UDP, &response).is_err());
+	// not authorized
+	let rejected = [0u8, 130, 0, 2, 0, 0, 0, 1, 0x76, 0x5f, 0x76, 0x5f, 0, 0, 0, 0];
+	assert!(parse_nat_pmp_response(NAT_PMP_OP_MAP_TCP, &rejected).is_err());
+}
+
 #[test]
 fn can_select_public_address() {
 	let pub_address = select_public_address(40477);
@@ -206,7 +361,7 @@ fn can_select_public_address() {
 #[test]
 fn can_map_external_address_or_fail() {
 	let pub_address = select_public_address(40478);
-	let _ = map_external_address(&NodeEndpoint { address: pub_address, udp_port: 40478 });
+	let _ = map_external_address(&NodeEndpoint { address: pub_address, udp_port: 40478 }, NatType::Any);
 }
 
 #[test]
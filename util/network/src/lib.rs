@@ -44,7 +44,7 @@
 //! }
 //!
 //! fn main () {
-//! 	let mut service = NetworkService::new(NetworkConfiguration::new_local()).expect("Error creating network service");
+//! 	let mut service = NetworkService::new(NetworkConfiguration::new_local(), None).expect("Error creating network service");
 //! 	service.register_protocol(Arc::new(MyHandler), *b"myp", &[1u8]);
 //! 	service.start().expect("Error starting service");
 //!
@@ -87,6 +87,7 @@ mod error;
 mod node_table;
 mod stats;
 mod ip_utils;
+mod connection_filter;
 
 #[cfg(test)]
 mod tests;
@@ -98,7 +99,9 @@ pub use service::NetworkService;
 pub use host::NetworkIoMessage;
 pub use error::NetworkError;
 pub use host::NetworkConfiguration;
-pub use stats::NetworkStats;
+pub use stats::{NetworkStats, ProtocolTraffic};
+pub use session::{SessionInfo, PeerCapabilityInfo};
+pub use connection_filter::{ConnectionFilter, ConnectionDirection};
 
 use io::TimerToken;
 pub use node_table::is_valid_node_url;
@@ -121,6 +124,17 @@ pub trait NetworkProtocolHandler: Sync + Send {
 	fn timeout(&self, _io: &NetworkContext, _timer: TimerToken) {}
 }
 
+/// NAT traversal methods.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NatType {
+	/// Try UPnP first, then NAT-PMP.
+	Any,
+	/// Universal Plug and Play only.
+	UPnP,
+	/// NAT Port Mapping Protocol only.
+	NatPmp,
+}
+
 /// Non-reserved peer modes.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum NonReservedPeerMode {
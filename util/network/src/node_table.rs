@@ -18,6 +18,7 @@ use std::mem;
 use std::slice::from_raw_parts;
 use std::net::{SocketAddr, ToSocketAddrs, SocketAddrV4, SocketAddrV6, Ipv4Addr, Ipv6Addr};
 use std::hash::{Hash, Hasher};
+use std::cmp::Ordering;
 use std::str::{FromStr};
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
@@ -130,11 +131,36 @@ pub enum PeerType {
 	Optional
 }
 
+/// Nodes with reputation at or below this value are not dialed.
+const BAD_REPUTATION: i32 = -100;
+
+/// Reason for lowering node reputation.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Penalty {
+	/// Node has no compatible protocols.
+	UselessPeer,
+	/// Node did not respond in time.
+	Timeout,
+	/// Node was disabled by a protocol handler, e.g. for sending a bad block.
+	BadBlock,
+}
+
+impl Penalty {
+	fn value(&self) -> i32 {
+		match *self {
+			Penalty::UselessPeer => 100,
+			Penalty::Timeout => 20,
+			Penalty::BadBlock => 200,
+		}
+	}
+}
+
 pub struct Node {
 	pub id: NodeId,
 	pub endpoint: NodeEndpoint,
 	pub peer_type: PeerType,
 	pub failures: u32,
+	pub reputation: i32,
 	pub last_attempted: Option<Tm>,
 }
 
@@ -145,6 +171,7 @@ impl Node {
 			endpoint: endpoint,
 			peer_type: PeerType::Optional,
 			failures: 0,
+			reputation: 0,
 			last_attempted: None,
 		}
 	}
@@ -177,6 +204,7 @@ impl FromStr for Node {
 			peer_type: PeerType::Optional,
 			last_attempted: None,
 			failures: 0,
+			reputation: 0,
 		})
 	}
 }
@@ -212,17 +240,30 @@ impl NodeTable {
 
 	/// Add a node to table
 	pub fn add_node(&mut self, mut node: Node) {
-		// preserve failure counter
-		let failures = self.nodes.get(&node.id).map_or(0, |n| n.failures);
-		node.failures = failures;
+		// preserve failure counter and reputation
+		if let Some(existing) = self.nodes.get(&node.id) {
+			node.failures = existing.failures;
+			node.reputation = existing.reputation;
+		}
 		self.nodes.insert(node.id.clone(), node);
 	}
 
-	/// Returns node ids sorted by number of failures
+	/// Returns all nodes sorted by reputation and then by number of failures
+	fn ordered_nodes(&self) -> Vec<&Node> {
+		let mut refs: Vec<&Node> = self.nodes.values().collect();
+		refs.sort_by(|a, b| match b.reputation.cmp(&a.reputation) {
+			Ordering::Equal => a.failures.cmp(&b.failures),
+			ordering => ordering,
+		});
+		refs
+	}
+
+	/// Returns ids of nodes worth connecting to, sorted by reputation and then by number of failures
 	pub fn nodes(&self) -> Vec<NodeId> {
-		let mut refs: Vec<&Node> = self.nodes.values().filter(|n| !self.useless_nodes.contains(&n.id)).collect();
-		refs.sort_by(|a, b| a.failures.cmp(&b.failures));
-		refs.iter().map(|n| n.id.clone()).collect()
+		self.ordered_nodes().into_iter()
+			.filter(|n| !self.useless_nodes.contains(&n.id) && n.reputation > BAD_REPUTATION)
+			.map(|n| n.id.clone())
+			.collect()
 	}
 
 	/// Unordered list of all entries
@@ -256,6 +297,22 @@ impl NodeTable {
 		}
 	}
 
+	/// Lower node reputation
+	pub fn note_penalty(&mut self, id: &NodeId, penalty: Penalty) {
+		if let Some(node) = self.nodes.get_mut(id) {
+			node.reputation = node.reputation.saturating_sub(penalty.value());
+			trace!(target: "network", "Node {} penalized for {:?}, reputation {}", id, penalty, node.reputation);
+		}
+	}
+
+	/// Move reputation of all nodes towards neutral.
+	pub fn decay_reputation(&mut self) {
+		for node in self.nodes.values_mut() {
+			// at least one point per call, so small reputations reach neutral as well
+			node.reputation -= node.reputation / 4 + node.reputation.signum();
+		}
+	}
+
 	/// Mark as useless, no furter attempts to connect until next call to `clear_useless`.
 	pub fn mark_as_useless(&mut self, id: &NodeId) {
 		self.useless_nodes.insert(id.clone());
@@ -266,7 +323,8 @@ impl NodeTable {
 		self.useless_nodes.clear();
 	}
 
-	fn save(&self) {
+	/// Save the table to disk.
+	pub fn save(&self) {
 		if let Some(ref path) = self.path {
 			let mut path_buf = PathBuf::from(path);
 			if let Err(e) = fs::create_dir_all(path_buf.as_path()) {
@@ -277,10 +335,11 @@ impl NodeTable {
 			let mut json = String::new();
 			json.push_str("{\n");
 			json.push_str("\"nodes\": [\n");
-			let node_ids = self.nodes();
-			for i in 0 .. node_ids.len() {
-				let node = self.nodes.get(&node_ids[i]).unwrap();
-				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {} }}{}\n", node, node.failures, if i == node_ids.len() - 1 {""} else {","}))
+			// useless nodes are forgotten, they would be dialed again after restart
+			let nodes: Vec<_> = self.ordered_nodes().into_iter().filter(|n| !self.useless_nodes.contains(&n.id)).collect();
+			for i in 0 .. nodes.len() {
+				let node = nodes[i];
+				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {}, \"reputation\": {} }}{}\n", node, node.failures, node.reputation, if i == nodes.len() - 1 {""} else {","}))
 			}
 			json.push_str("]\n");
 			json.push_str("}");
@@ -331,6 +390,9 @@ impl NodeTable {
 							if let Some(failures) = n.get("failures").and_then(|f| f.as_u64()) {
 								node.failures = failures as u32;
 							}
+							if let Some(reputation) = n.get("reputation").and_then(|r| r.as_i64()) {
+								node.reputation = reputation as i32;
+							}
 							nodes.insert(node.id.clone(), node);
 						}
 					}
@@ -411,6 +473,52 @@ mod tests {
 		assert_eq!(r[2][..], id1[..]);
 	}
 
+	#[test]
+	fn table_reputation_order() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node3 = Node::from_str("enode://c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id3 = H512::from_str("c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+		table.add_node(node2);
+		table.add_node(node3);
+
+		table.note_failure(&id3);
+		table.note_penalty(&id1, Penalty::Timeout);
+		table.note_penalty(&id2, Penalty::BadBlock);
+
+		// penalty outweighs failures, badly behaving node is not dialed at all
+		let r = table.nodes();
+		assert_eq!(r.len(), 2);
+		assert_eq!(r[0][..], id3[..]);
+		assert_eq!(r[1][..], id1[..]);
+
+		// reputation recovers over time
+		for _ in 0..3 {
+			table.decay_reputation();
+		}
+		let r = table.nodes();
+		assert_eq!(r.len(), 3);
+		assert_eq!(r[2][..], id2[..]);
+	}
+
+	#[test]
+	fn table_small_reputation_decays() {
+		let node = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let mut table = NodeTable::new(None);
+		table.add_node(node);
+		table.note_penalty(&id, Penalty::Timeout);
+
+		for _ in 0..10 {
+			table.decay_reputation();
+		}
+		assert_eq!(table.get_mut(&id).unwrap().reputation, 0);
+	}
+
 	#[test]
 	fn table_save_load() {
 		let temp_path = RandomTempPath::create_dir();
@@ -426,10 +534,42 @@ mod tests {
 		}
 
 		{
-			let table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
 			let r = table.nodes();
 			assert_eq!(r[0][..], id1[..]);
 			assert_eq!(r[1][..], id2[..]);
+			table.note_penalty(&id1, Penalty::UselessPeer);
+			table.save();
+		}
+
+		{
+			let table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			let r = table.nodes();
+			assert_eq!(r.len(), 1);
+			assert_eq!(r[0][..], id2[..]);
+		}
+	}
+
+	#[test]
+	fn table_save_skips_useless_nodes() {
+		let temp_path = RandomTempPath::create_dir();
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		{
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			table.add_node(node1);
+			table.add_node(node2);
+			table.mark_as_useless(&id1);
+			table.save();
+		}
+
+		{
+			let table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			let r = table.nodes();
+			assert_eq!(r.len(), 1);
+			assert_eq!(r[0][..], id2[..]);
 		}
 	}
 }
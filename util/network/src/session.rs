@@ -46,6 +46,8 @@ pub struct Session {
 	ping_time_ns: u64,
 	pong_time_ns: Option<u64>,
 	state: State,
+	/// Shared network statistics
+	stats: Arc<NetworkStats>,
 }
 
 enum State {
@@ -72,6 +74,7 @@ pub enum SessionData {
 }
 
 /// Shared session information
+#[derive(Debug, Clone)]
 pub struct SessionInfo {
 	/// Peer public key
 	pub id: Option<NodeId>,
@@ -79,15 +82,29 @@ pub struct SessionInfo {
 	pub client_version: String,
 	/// Peer RLPx protocol version
 	pub protocol_version: u32,
-	/// Peer protocol capabilities
+	/// Session protocol capabilities
 	capabilities: Vec<SessionCapabilityInfo>,
+	/// Peer protocol capabilities
+	pub peer_capabilities: Vec<PeerCapabilityInfo>,
 	/// Peer ping delay in milliseconds
 	pub ping_ms: Option<u64>,
 	/// True if this session was originated by us.
 	pub originated: bool,
+	/// Remote endpoint address of the session
+	pub remote_address: String,
+	/// Local endpoint address of the session
+	pub local_address: String,
+	/// Number of packets received from the peer
+	pub packets_received: u64,
+	/// Number of packets sent to the peer
+	pub packets_sent: u64,
+	/// Number of packet bytes received from the peer
+	pub bytes_received: u64,
+	/// Number of packet bytes sent to the peer
+	pub bytes_sent: u64,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PeerCapabilityInfo {
 	pub protocol: ProtocolId,
 	pub version: u8,
@@ -109,7 +126,7 @@ impl Decodable for PeerCapabilityInfo {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct SessionCapabilityInfo {
 	pub protocol: [u8; 3],
 	pub version: u8,
@@ -133,7 +150,9 @@ impl Session {
 		nonce: &H256, stats: Arc<NetworkStats>, host: &HostInfo) -> Result<Session, NetworkError>
 		where Message: Send + Clone {
 		let originated = id.is_some();
-		let mut handshake = Handshake::new(token, id, socket, nonce, stats).expect("Can't create handshake");
+		let mut handshake = Handshake::new(token, id, socket, nonce, stats.clone()).expect("Can't create handshake");
+		let remote_address = handshake.connection.remote_addr_str();
+		let local_address = handshake.connection.local_addr_str();
 		try!(handshake.start(io, host, originated));
 		Ok(Session {
 			state: State::Handshake(handshake),
@@ -143,12 +162,20 @@ impl Session {
 				client_version: String::new(),
 				protocol_version: 0,
 				capabilities: Vec::new(),
+				peer_capabilities: Vec::new(),
 				ping_ms: None,
 				originated: originated,
+				remote_address: remote_address,
+				local_address: local_address,
+				packets_received: 0,
+				packets_sent: 0,
+				bytes_received: 0,
+				bytes_sent: 0,
 			},
 			ping_time_ns: 0,
 			pong_time_ns: None,
 			expired: false,
+			stats: stats,
 		})
 	}
 
@@ -297,6 +324,7 @@ impl Session {
 		let mut rlp = RlpStream::new();
 		rlp.append(&(pid as u32));
 		rlp.append_raw(data, 1);
+		self.stats.inc_protocol_send(protocol, rlp.as_raw().len());
 		self.send(io, rlp)
 	}
 
@@ -332,6 +360,8 @@ impl Session {
 		if packet_id != PACKET_HELLO && packet_id != PACKET_DISCONNECT && !self.had_hello {
 			return Err(From::from(NetworkError::BadProtocol));
 		}
+		self.info.packets_received += 1;
+		self.info.bytes_received += packet.data.len() as u64;
 		match packet_id {
 			PACKET_HELLO => {
 				let rlp = UntrustedRlp::new(&packet.data[1..]); //TODO: validate rlp expected size
@@ -370,6 +400,7 @@ impl Session {
 				// map to protocol
 				let protocol = self.info.capabilities[i].protocol;
 				let pid = packet_id - self.info.capabilities[i].id_offset;
+				self.stats.inc_protocol_recv(protocol, packet.data.len());
 				Ok(SessionData::Packet { data: packet.data, protocol: protocol, packet_id: pid } )
 			},
 			_ => {
@@ -433,6 +464,7 @@ impl Session {
 		trace!(target: "network", "Hello: {} v{} {} {:?}", client_version, protocol, id, caps);
 		self.info.client_version = client_version;
 		self.info.capabilities = caps;
+		self.info.peer_capabilities = peer_caps;
 		if self.info.capabilities.is_empty() {
 			trace!(target: "network", "No common capabilities with peer.");
 			return Err(From::from(self.disconnect(io, DisconnectReason::UselessPeer)));
@@ -482,7 +514,10 @@ impl Session {
 				warn!(target:"network", "Unexpected send request");
 			},
 			State::Session(ref mut s) => {
-				try!(s.send_packet(io, &rlp.out()))
+				let data = rlp.out();
+				try!(s.send_packet(io, &data));
+				self.info.packets_sent += 1;
+				self.info.bytes_sent += data.len() as u64;
 			},
 		}
 		Ok(())
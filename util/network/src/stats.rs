@@ -15,7 +15,27 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Network Statistics
+use std::collections::HashMap;
 use std::sync::atomic::*;
+use parking_lot::{Mutex, RwLock};
+use host::ProtocolId;
+use time;
+
+/// Bytes received and sent over a subprotocol.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolTraffic {
+	/// Bytes received
+	pub recv: usize,
+	/// Bytes sent
+	pub send: usize,
+}
+
+/// Packet byte counters of a subprotocol.
+#[derive(Default, Debug)]
+struct ProtocolCounters {
+	recv: AtomicUsize,
+	send: AtomicUsize,
+}
 
 /// Network statistics structure
 #[derive(Default, Debug)]
@@ -26,6 +46,20 @@ pub struct NetworkStats {
 	send: AtomicUsize,
 	/// Total number of sessions created
 	sessions: AtomicUsize,
+	/// Packet bytes received and sent per subprotocol. Only written to when a subprotocol is registered.
+	protocols: RwLock<HashMap<ProtocolId, ProtocolCounters>>,
+	/// Bytes received per second, measured over the last sampling interval
+	recv_rate: AtomicUsize,
+	/// Bytes sent per second, measured over the last sampling interval
+	send_rate: AtomicUsize,
+	/// Bytes received, bytes sent and time in nanoseconds of the last rate sample
+	last_sample: Mutex<(usize, usize, u64)>,
+	/// Download soft limit in bytes per second, 0 for no limit
+	max_download: AtomicUsize,
+	/// Upload soft limit in bytes per second, 0 for no limit
+	max_upload: AtomicUsize,
+	/// Bytes that may still be uploaded and time in nanoseconds the allowance was last refilled
+	upload_allowance: Mutex<(usize, u64)>,
 }
 
 impl NetworkStats {
@@ -47,6 +81,29 @@ impl NetworkStats {
 		self.sessions.fetch_add(1, Ordering::Relaxed);
 	}
 
+	/// Start counting packet bytes of a subprotocol.
+	pub fn register_protocol(&self, protocol: ProtocolId) {
+		self.protocols.write().entry(protocol).or_insert_with(Default::default);
+	}
+
+	fn with_protocol<F>(&self, protocol: ProtocolId, f: F) where F: Fn(&ProtocolCounters) {
+		if let Some(counters) = self.protocols.read().get(&protocol) {
+			f(counters);
+			return;
+		}
+		f(self.protocols.write().entry(protocol).or_insert_with(Default::default));
+	}
+
+	/// Increase packet bytes received over a subprotocol.
+	pub fn inc_protocol_recv(&self, protocol: ProtocolId, size: usize) {
+		self.with_protocol(protocol, |counters| { counters.recv.fetch_add(size, Ordering::Relaxed); });
+	}
+
+	/// Increase packet bytes sent over a subprotocol.
+	pub fn inc_protocol_send(&self, protocol: ProtocolId, size: usize) {
+		self.with_protocol(protocol, |counters| { counters.send.fetch_add(size, Ordering::Relaxed); });
+	}
+
 	/// Get bytes sent.
 	#[inline]
 	pub fn send(&self) -> usize {
@@ -65,12 +122,162 @@ impl NetworkStats {
 		self.sessions.load(Ordering::Relaxed)
 	}
 
+	/// Get packet bytes received and sent per subprotocol.
+	pub fn protocols(&self) -> HashMap<ProtocolId, ProtocolTraffic> {
+		self.protocols.read().iter().map(|(protocol, counters)| (*protocol, ProtocolTraffic {
+			recv: counters.recv.load(Ordering::Relaxed),
+			send: counters.send.load(Ordering::Relaxed),
+		})).collect()
+	}
+
+	/// Get bytes received per second.
+	#[inline]
+	pub fn recv_rate(&self) -> usize {
+		self.recv_rate.load(Ordering::Relaxed)
+	}
+
+	/// Get bytes sent per second.
+	#[inline]
+	pub fn send_rate(&self) -> usize {
+		self.send_rate.load(Ordering::Relaxed)
+	}
+
+	/// Measure transfer rates since the previous call.
+	pub fn update_rates(&self) {
+		let now = time::precise_time_ns();
+		let (recv, send) = (self.recv(), self.send());
+		let mut last = self.last_sample.lock();
+		let (last_recv, last_send, last_time) = *last;
+		if last_time != 0 && now > last_time {
+			let elapsed = now - last_time;
+			self.recv_rate.store(((recv - last_recv) as u64 * 1_000_000_000 / elapsed) as usize, Ordering::Relaxed);
+			self.send_rate.store(((send - last_send) as u64 * 1_000_000_000 / elapsed) as usize, Ordering::Relaxed);
+		}
+		*last = (recv, send, now);
+	}
+
+	/// Set download and upload soft limits in bytes per second.
+	pub fn set_limits(&self, max_download: Option<usize>, max_upload: Option<usize>) {
+		self.max_download.store(max_download.unwrap_or(0), Ordering::Relaxed);
+		self.max_upload.store(max_upload.unwrap_or(0), Ordering::Relaxed);
+	}
+
+	/// Check if the download rate exceeds the soft limit.
+	pub fn is_download_limited(&self) -> bool {
+		let max = self.max_download.load(Ordering::Relaxed);
+		max != 0 && self.recv_rate() > max
+	}
+
+	/// Check if the upload rate exceeds the soft limit.
+	pub fn is_upload_limited(&self) -> bool {
+		let max = self.max_upload.load(Ordering::Relaxed);
+		max != 0 && self.send_rate() > max
+	}
+
+	/// Take `size` bytes from the upload allowance, which refills at the upload limit rate
+	/// and holds at most one second worth of bytes. Returns false if there is not enough left.
+	/// A full allowance lets any packet through, so packets larger than the limit are not refused forever.
+	pub fn reserve_upload(&self, size: usize) -> bool {
+		let max = self.max_upload.load(Ordering::Relaxed);
+		if max == 0 {
+			return true;
+		}
+
+		let now = time::precise_time_ns();
+		let mut allowance = self.upload_allowance.lock();
+		if allowance.1 == 0 {
+			*allowance = (max, now);
+		}
+		// the refill time only moves on once at least a byte has been added, so frequent calls don't starve it.
+		let elapsed = ::std::cmp::min(now.saturating_sub(allowance.1), 1_000_000_000);
+		let refill = (elapsed * max as u64 / 1_000_000_000) as usize;
+		if refill > 0 {
+			*allowance = (::std::cmp::min(max, allowance.0 + refill), now);
+		}
+
+		if allowance.0 < size && allowance.0 < max {
+			return false;
+		}
+		allowance.0 = allowance.0.saturating_sub(size);
+		true
+	}
+
 	/// Create a new empty instance.
 	pub fn new() -> NetworkStats {
 		NetworkStats {
 			recv: AtomicUsize::new(0),
 			send: AtomicUsize::new(0),
 			sessions: AtomicUsize::new(0),
+			protocols: RwLock::new(HashMap::new()),
+			recv_rate: AtomicUsize::new(0),
+			send_rate: AtomicUsize::new(0),
+			last_sample: Mutex::new((0, 0, 0)),
+			max_download: AtomicUsize::new(0),
+			max_upload: AtomicUsize::new(0),
+			upload_allowance: Mutex::new((0, 0)),
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use time;
+
+	#[test]
+	fn counts_protocol_traffic() {
+		let stats = NetworkStats::new();
+		stats.register_protocol(*b"eth");
+		stats.inc_protocol_recv(*b"eth", 100);
+		stats.inc_protocol_send(*b"eth", 10);
+		stats.inc_protocol_send(*b"eth", 20);
+		stats.inc_protocol_recv(*b"par", 5);
+
+		let protocols = stats.protocols();
+		assert_eq!(protocols[b"eth"], ProtocolTraffic { recv: 100, send: 30 });
+		assert_eq!(protocols[b"par"], ProtocolTraffic { recv: 5, send: 0 });
+	}
+
+	#[test]
+	fn measures_rates_and_applies_limits() {
+		let stats = NetworkStats::new();
+		stats.set_limits(Some(1000), Some(1000));
+		stats.update_rates();
+		assert!(!stats.is_download_limited());
+		assert!(!stats.is_upload_limited());
+
+		// pretend the previous sample was taken a second ago
+		stats.last_sample.lock().2 = time::precise_time_ns() - 1_000_000_000;
+		stats.inc_recv(2000);
+		stats.inc_send(500);
+		stats.update_rates();
+		assert!(stats.recv_rate() > 1000 && stats.recv_rate() <= 2000);
+		assert!(stats.send_rate() <= 500);
+		assert!(stats.is_download_limited());
+		assert!(!stats.is_upload_limited());
+
+		stats.set_limits(None, None);
+		assert!(!stats.is_download_limited());
+	}
+
+	#[test]
+	fn limits_upload_bytes_per_second() {
+		let stats = NetworkStats::new();
+		assert!(stats.reserve_upload(1_000_000));
+
+		stats.set_limits(None, Some(1000));
+		assert!(stats.reserve_upload(600));
+		assert!(stats.reserve_upload(400));
+		assert!(!stats.reserve_upload(100));
+
+		// pretend half a second passed
+		stats.upload_allowance.lock().1 = time::precise_time_ns() - 500_000_000;
+		assert!(stats.reserve_upload(400));
+		assert!(!stats.reserve_upload(400));
+
+		// a full allowance lets an oversized packet through
+		stats.upload_allowance.lock().1 = time::precise_time_ns() - 2_000_000_000;
+		assert!(stats.reserve_upload(5000));
+		assert!(!stats.reserve_upload(1));
+	}
+}
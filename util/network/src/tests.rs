@@ -91,7 +91,7 @@ impl NetworkProtocolHandler for TestProtocol {
 
 #[test]
 fn net_service() {
-	let service = NetworkService::new(NetworkConfiguration::new_local()).expect("Error creating network service");
+	let service = NetworkService::new(NetworkConfiguration::new_local(), None).expect("Error creating network service");
 	service.start().unwrap();
 	service.register_protocol(Arc::new(TestProtocol::new(false)), *b"myp", &[1u8]).unwrap();
 }
@@ -103,13 +103,13 @@ fn net_connect() {
 	let mut config1 = NetworkConfiguration::new_local();
 	config1.use_secret = Some(key1.secret().clone());
 	config1.boot_nodes = vec![ ];
-	let mut service1 = NetworkService::new(config1).unwrap();
+	let mut service1 = NetworkService::new(config1, None).unwrap();
 	service1.start().unwrap();
 	let handler1 = TestProtocol::register(&mut service1, false);
 	let mut config2 = NetworkConfiguration::new_local();
 	info!("net_connect: local URL: {}", service1.local_url().unwrap());
 	config2.boot_nodes = vec![ service1.local_url().unwrap() ];
-	let mut service2 = NetworkService::new(config2).unwrap();
+	let mut service2 = NetworkService::new(config2, None).unwrap();
 	service2.start().unwrap();
 	let handler2 = TestProtocol::register(&mut service2, false);
 	while !handler1.got_packet() && !handler2.got_packet() && (service1.stats().sessions() == 0 || service2.stats().sessions() == 0) {
@@ -122,7 +122,7 @@ fn net_connect() {
 #[test]
 fn net_start_stop() {
 	let config = NetworkConfiguration::new_local();
-	let service = NetworkService::new(config).unwrap();
+	let service = NetworkService::new(config, None).unwrap();
 	service.start().unwrap();
 	service.stop().unwrap();
 	service.start().unwrap();
@@ -134,12 +134,12 @@ fn net_disconnect() {
 	let mut config1 = NetworkConfiguration::new_local();
 	config1.use_secret = Some(key1.secret().clone());
 	config1.boot_nodes = vec![ ];
-	let mut service1 = NetworkService::new(config1).unwrap();
+	let mut service1 = NetworkService::new(config1, None).unwrap();
 	service1.start().unwrap();
 	let handler1 = TestProtocol::register(&mut service1, false);
 	let mut config2 = NetworkConfiguration::new_local();
 	config2.boot_nodes = vec![ service1.local_url().unwrap() ];
-	let mut service2 = NetworkService::new(config2).unwrap();
+	let mut service2 = NetworkService::new(config2, None).unwrap();
 	service2.start().unwrap();
 	let handler2 = TestProtocol::register(&mut service2, true);
 	while !(handler1.got_disconnect() && handler2.got_disconnect()) {
@@ -152,10 +152,85 @@ fn net_disconnect() {
 #[test]
 fn net_timeout() {
 	let config = NetworkConfiguration::new_local();
-	let mut service = NetworkService::new(config).unwrap();
+	let mut service = NetworkService::new(config, None).unwrap();
 	service.start().unwrap();
 	let handler = TestProtocol::register(&mut service, false);
 	while !handler.got_timeout() {
 		thread::sleep(Duration::from_millis(50));
 	}
 }
+
+#[test]
+fn net_reserved_peer_is_not_disconnected() {
+	let key1 = Random.generate().unwrap();
+	let mut config1 = NetworkConfiguration::new_local();
+	config1.use_secret = Some(key1.secret().clone());
+	config1.boot_nodes = vec![ ];
+	let mut service1 = NetworkService::new(config1, None).unwrap();
+	service1.start().unwrap();
+	let handler1 = TestProtocol::register(&mut service1, false);
+	let mut config2 = NetworkConfiguration::new_local();
+	config2.reserved_nodes = vec![ service1.local_url().unwrap() ];
+	let mut service2 = NetworkService::new(config2, None).unwrap();
+	service2.start().unwrap();
+	// tries to drop every session on connect
+	let handler2 = TestProtocol::register(&mut service2, true);
+	while !handler2.got_packet() {
+		thread::sleep(Duration::from_millis(50));
+	}
+	thread::sleep(Duration::from_millis(200));
+	assert!(!handler1.got_disconnect());
+	assert!(!handler2.got_disconnect());
+}
+
+struct TestFilter {
+	allow: AtomicBool,
+}
+
+impl ConnectionFilter for TestFilter {
+	fn connection_allowed(&self, _own_id: &H512, _connecting_id: &H512, _direction: ConnectionDirection) -> bool {
+		self.allow.load(AtomicOrdering::Relaxed)
+	}
+}
+
+fn filtered_services(allow: bool) -> (Arc<TestFilter>, NetworkService, Arc<TestProtocol>, NetworkService, Arc<TestProtocol>) {
+	let filter = Arc::new(TestFilter { allow: AtomicBool::new(allow) });
+	let key1 = Random.generate().unwrap();
+	let mut config1 = NetworkConfiguration::new_local();
+	config1.use_secret = Some(key1.secret().clone());
+	config1.boot_nodes = vec![ ];
+	let mut service1 = NetworkService::new(config1, Some(filter.clone())).unwrap();
+	service1.start().unwrap();
+	let handler1 = TestProtocol::register(&mut service1, false);
+	let mut config2 = NetworkConfiguration::new_local();
+	config2.boot_nodes = vec![ service1.local_url().unwrap() ];
+	let mut service2 = NetworkService::new(config2, None).unwrap();
+	service2.start().unwrap();
+	let handler2 = TestProtocol::register(&mut service2, false);
+	(filter, service1, handler1, service2, handler2)
+}
+
+#[test]
+fn net_filter_refuses_connection() {
+	let (_filter, _service1, handler1, _service2, handler2) = filtered_services(false);
+	while !handler2.got_disconnect() {
+		thread::sleep(Duration::from_millis(50));
+	}
+	assert!(!handler1.got_packet());
+	assert!(!handler1.got_disconnect());
+}
+
+#[test]
+fn net_filter_disconnects_peers_no_longer_allowed() {
+	let (filter, _service1, handler1, _service2, handler2) = filtered_services(true);
+	while !(handler1.got_packet() && handler2.got_packet()) {
+		thread::sleep(Duration::from_millis(50));
+	}
+	assert!(!handler1.got_disconnect());
+	assert!(!handler2.got_disconnect());
+
+	filter.allow.store(false, AtomicOrdering::Relaxed);
+	while !(handler1.got_disconnect() && handler2.got_disconnect()) {
+		thread::sleep(Duration::from_millis(50));
+	}
+}
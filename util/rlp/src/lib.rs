@@ -34,6 +34,10 @@
 //! * You want to encode something in portions.
 //! * You encode a big set of data.
 //!
+//!### Use `RlpReader` when:
+//! * You want to decode a big list item by item without loading it into memory.
+//! * You read the data from a file or socket.
+//!
 //!### Use `Rlp` when:
 //! * You are working on trusted data (not corrupted).
 //! * You want to get view onto rlp-slice.
@@ -51,6 +55,7 @@ mod rlperrors;
 mod rlpin;
 mod untrusted_rlp;
 mod rlpstream;
+mod rlpreader;
 mod rlpcompression;
 mod commonrlps;
 mod bytes;
@@ -63,6 +68,7 @@ pub use self::rlptraits::{Decoder, Decodable, View, Stream, Encodable, Encoder,
 pub use self::untrusted_rlp::{UntrustedRlp, UntrustedRlpIterator, PayloadInfo, Prototype};
 pub use self::rlpin::{Rlp, RlpIterator};
 pub use self::rlpstream::RlpStream;
+pub use self::rlpreader::RlpReader;
 pub use self::rlpcompression::RlpType;
 
 extern crate ethcore_bigint as bigint;
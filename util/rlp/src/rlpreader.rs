@@ -0,0 +1,115 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{self, Read};
+use ::{UntrustedRlp, View, DecoderError, PayloadInfo};
+
+/// Incremental rlp decoder reading items one by one from a `Read`.
+///
+/// Only the item being decoded is held in memory, which makes it suitable for
+/// big lists like block bodies or snapshot chunks. Each item is returned as an
+/// `UntrustedRlp` borrowing from the reader's buffer.
+pub struct RlpReader<R> {
+	reader: R,
+	buffer: Vec<u8>,
+	// payload bytes left in the list entered with `begin_list`.
+	remaining: Option<usize>,
+}
+
+impl<R> RlpReader<R> where R: Read {
+	/// Create a new reader. Items are read from the top level until `begin_list` is called.
+	pub fn new(reader: R) -> Self {
+		RlpReader {
+			reader: reader,
+			buffer: Vec::new(),
+			remaining: None,
+		}
+	}
+
+	/// Enter the list at the current position, so that `next_item` returns its items.
+	/// Returns the length of the list's payload in bytes.
+	pub fn begin_list(&mut self) -> Result<usize, DecoderError> {
+		let info = match try!(self.read_header()) {
+			Some(info) => info,
+			None => return Err(DecoderError::RlpIsTooShort),
+		};
+		if self.buffer[0] < 0xc0 {
+			return Err(DecoderError::RlpExpectedToBeList);
+		}
+
+		self.remaining = Some(info.value_len);
+		Ok(info.value_len)
+	}
+
+	/// Read the next item. Returns `None` at the end of the entered list, or at the end
+	/// of the input if no list was entered.
+	pub fn next_item(&mut self) -> Result<Option<UntrustedRlp>, DecoderError> {
+		if self.remaining == Some(0) {
+			return Ok(None);
+		}
+
+		let info = match try!(self.read_header()) {
+			Some(info) => info,
+			None if self.remaining.is_none() => return Ok(None),
+			None => return Err(DecoderError::RlpIsTooShort),
+		};
+		self.remaining = try!(self.remaining.map_or(Ok(None), |remaining| match remaining.checked_sub(info.total()) {
+			Some(remaining) => Ok(Some(remaining)),
+			None => Err(DecoderError::RlpInconsistentLengthAndData),
+		}));
+
+		// single byte items are their own header.
+		let value_len = if info.header_len == 0 { 0 } else { info.value_len };
+		// the buffer grows with the data actually read, so a bogus length can't exhaust memory.
+		let read = try!((&mut self.reader).take(value_len as u64).read_to_end(&mut self.buffer).map_err(io_error));
+		if read < value_len {
+			return Err(DecoderError::RlpIsTooShort);
+		}
+		Ok(Some(UntrustedRlp::new(&self.buffer)))
+	}
+
+	/// Consume the reader, returning the underlying `Read`.
+	pub fn into_inner(self) -> R {
+		self.reader
+	}
+
+	/// Read the header of the next item into the cleared buffer. Returns `None` at the end of the input.
+	fn read_header(&mut self) -> Result<Option<PayloadInfo>, DecoderError> {
+		self.buffer.clear();
+		let mut prefix = [0u8; 1];
+		match self.reader.read(&mut prefix) {
+			Ok(0) => return Ok(None),
+			Ok(_) => self.buffer.push(prefix[0]),
+			Err(e) => return Err(io_error(e)),
+		}
+
+		let len_of_len = match prefix[0] {
+			l @ 0xb8...0xbf => l as usize - 0xb7,
+			l @ 0xf8...0xff => l as usize - 0xf7,
+			_ => 0,
+		};
+		self.buffer.resize(1 + len_of_len, 0);
+		try!(self.reader.read_exact(&mut self.buffer[1..]).map_err(io_error));
+		PayloadInfo::from(&self.buffer).map(Some)
+	}
+}
+
+fn io_error(e: io::Error) -> DecoderError {
+	match e.kind() {
+		io::ErrorKind::UnexpectedEof => DecoderError::RlpIsTooShort,
+		_ => DecoderError::Custom("I/O error while reading rlp"),
+	}
+}
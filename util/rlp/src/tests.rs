@@ -16,7 +16,7 @@
 
 use std::{fmt, cmp};
 use std::str::FromStr;
-use ::{Encodable, RlpDecodable, UntrustedRlp, RlpStream, View, Stream, DecoderError};
+use ::{Encodable, RlpDecodable, UntrustedRlp, RlpStream, RlpReader, View, Stream, DecoderError};
 use bigint::uint::U256;
 
 #[test]
@@ -408,3 +408,26 @@ fn test_rlp_list_length_overflow() {
 	let as_val: Result<String, DecoderError> = rlp.val_at(0);
 	assert_eq!(Err(DecoderError::RlpIsTooShort), as_val);
 }
+
+#[test]
+fn test_rlp_reader() {
+	let mut stream = RlpStream::new_list(3);
+	stream.append(&"cat");
+	stream.append(&5u8);
+	stream.append(&vec![7u8; 1000]);
+	let data = stream.out();
+
+	let mut reader = RlpReader::new(&data[..]);
+	assert_eq!(reader.begin_list(), Ok(data.len() - 3));
+	assert_eq!(reader.next_item().unwrap().unwrap().as_val::<String>(), Ok("cat".to_owned()));
+	assert_eq!(reader.next_item().unwrap().unwrap().as_val::<u8>(), Ok(5));
+	assert_eq!(reader.next_item().unwrap().unwrap().as_val::<Vec<u8>>(), Ok(vec![7u8; 1000]));
+	assert!(reader.next_item().unwrap().is_none());
+
+	let truncated = &data[..data.len() - 1];
+	let mut reader = RlpReader::new(truncated);
+	reader.begin_list().unwrap();
+	reader.next_item().unwrap();
+	reader.next_item().unwrap();
+	assert_eq!(reader.next_item().err(), Some(DecoderError::RlpIsTooShort));
+}
@@ -17,7 +17,9 @@
 //! `JournalDB` interface and implementation.
 
 use common::*;
-use kvdb::Database;
+use rlp::{RlpStream, Stream, UntrustedRlp, View};
+use hashdb::HashDB;
+use kvdb::{Database, DBTransaction};
 
 /// Export the journaldb module.
 pub mod traits;
@@ -68,7 +70,7 @@ impl FromStr for Algorithm {
 			"archive" => Ok(Algorithm::Archive),
 			"light" => Ok(Algorithm::EarlyMerge),
 			"fast" => Ok(Algorithm::OverlayRecent),
-			"basic" => Ok(Algorithm::RefCounted),
+			"basic" | "refcounted" => Ok(Algorithm::RefCounted),
 			e => Err(format!("Invalid algorithm: {}", e)),
 		}
 	}
@@ -125,13 +127,201 @@ pub fn new(backing: Arc<Database>, algorithm: Algorithm, col: Option<u32>) -> Bo
 	}
 }
 
+/// Check that the journal of a database written with `algorithm` is complete, can be decoded
+/// and that every node it refers to exists. Meant to be run after an unclean shutdown, before the journal is loaded.
+/// Returns the number of journal records checked.
+pub fn check_journal(backing: &Database, algorithm: Algorithm, col: Option<u32>) -> Result<usize, String> {
+	let report = try!(verify_journal(backing, algorithm, col));
+	match report.is_consistent() {
+		true => Ok(report.records),
+		false => Err(format!("{} journalled nodes are missing and {} deletions refer to unknown nodes",
+			report.missing.len(), report.dangling_deletions.len())),
+	}
+}
+
+/// Result of checking the journal against the nodes in the backing database.
+#[derive(Debug, Default, PartialEq)]
+pub struct JournalReport {
+	/// Number of journal records checked.
+	pub records: usize,
+	/// Nodes inserted by journalled commits which are missing or corrupt.
+	pub missing: Vec<H256>,
+	/// Nodes the journal is going to delete which don't exist.
+	pub dangling_deletions: Vec<H256>,
+}
+
+impl JournalReport {
+	/// Whether no problems were found.
+	pub fn is_consistent(&self) -> bool {
+		self.missing.is_empty() && self.dangling_deletions.is_empty()
+	}
+}
+
+/// Check that the journal can be decoded and that every node it refers to exists.
+pub fn verify_journal(backing: &Database, algorithm: Algorithm, col: Option<u32>) -> Result<JournalReport, String> {
+	let mut report = JournalReport::default();
+	if algorithm == Algorithm::Archive {
+		return Ok(report);
+	}
+
+	let records = try!(journal_records(backing, col));
+	report.records = records.len();
+
+	let mut inserted = HashSet::new();
+	let mut deleted = Vec::new();
+	for &(_, ref record) in &records {
+		let rlp = UntrustedRlp::new(record);
+		let inserts = try!(rlp.at(1).map_err(|e| format!("Corrupt journal record: {:?}", e)));
+		for insert in inserts.iter() {
+			let key = match algorithm {
+				// inserted values are kept in the journal until the era is canonicalized.
+				Algorithm::OverlayRecent => {
+					let key: H256 = try!(insert.val_at(0).map_err(|e| format!("Corrupt journal record: {:?}", e)));
+					let value: Bytes = try!(insert.val_at(1).map_err(|e| format!("Corrupt journal record: {:?}", e)));
+					if value.sha3() != key {
+						report.missing.push(key);
+					}
+					key
+				},
+				_ => {
+					let key: H256 = try!(insert.as_val().map_err(|e| format!("Corrupt journal record: {:?}", e)));
+					if try!(backing.get(col, &key)).is_none() {
+						report.missing.push(key);
+					}
+					key
+				},
+			};
+			inserted.insert(key);
+		}
+		deleted.extend(try!(rlp.val_at::<Vec<H256>>(2).map_err(|e| format!("Corrupt journal record: {:?}", e))));
+	}
+
+	for key in deleted {
+		if !inserted.contains(&key) && try!(backing.get(col, &key)).is_none() && !report.dangling_deletions.contains(&key) {
+			report.dangling_deletions.push(key);
+		}
+	}
+	Ok(report)
+}
+
+/// Restore `nodes` reported by `verify_journal`. Nodes inserted by journal records which keep
+/// their values are fixed in the records, all others are written into the database bypassing the journal.
+/// Returns the number of nodes and records written.
+pub fn repair_journal(backing: Arc<Database>, algorithm: Algorithm, col: Option<u32>, nodes: Vec<(H256, Bytes)>) -> Result<u32, UtilError> {
+	let mut nodes: HashMap<H256, Bytes> = nodes.into_iter().collect();
+	let mut batch = backing.transaction();
+	let mut ops = 0;
+	if algorithm == Algorithm::OverlayRecent {
+		ops += try!(repair_journal_values(&backing, col, &mut nodes, &mut batch));
+	}
+
+	let mut jdb = new(backing.clone(), algorithm, col);
+	for (key, value) in nodes {
+		jdb.emplace(key, value);
+	}
+	ops += try!(jdb.inject(&mut batch));
+	try!(backing.write(batch));
+	Ok(ops)
+}
+
+/// Rewrite journal records inserting any of `nodes` with the given values, removing these from `nodes`.
+/// Values kept in the journal are written to the database on canonicalization, so they have to be fixed in place.
+fn repair_journal_values(backing: &Database, col: Option<u32>, nodes: &mut HashMap<H256, Bytes>, batch: &mut DBTransaction) -> Result<u32, UtilError> {
+	let mut repaired = HashSet::new();
+	let mut records = 0;
+	for (key, record) in try!(journal_records(backing, col).map_err(UtilError::SimpleString)) {
+		let rlp = UntrustedRlp::new(&record);
+		let inserts = try!(rlp.at(1));
+		let mut changed = false;
+		let mut r = RlpStream::new_list(3);
+		r.append_raw(try!(rlp.at(0)).as_raw(), 1);
+		r.begin_list(inserts.item_count());
+		for insert in inserts.iter() {
+			let node: H256 = try!(insert.val_at(0));
+			match nodes.get(&node) {
+				Some(value) => {
+					r.begin_list(2);
+					r.append(&node);
+					r.append(value);
+					repaired.insert(node);
+					changed = true;
+				},
+				None => { r.append_raw(insert.as_raw(), 1); },
+			}
+		}
+		r.append_raw(try!(rlp.at(2)).as_raw(), 1);
+		if changed {
+			batch.put_vec(col, &key, r.out());
+			records += 1;
+		}
+	}
+	for node in repaired {
+		nodes.remove(&node);
+	}
+	Ok(records)
+}
+
+/// All journal records with their keys, most recent era first.
+fn journal_records(backing: &Database, col: Option<u32>) -> Result<Vec<(Bytes, Bytes)>, String> {
+	let latest_era = match try!(backing.get(col, &LATEST_ERA_KEY)) {
+		Some(val) => try!(UntrustedRlp::new(&val).as_val::<u64>().map_err(|e| format!("Corrupt latest era record: {:?}", e))),
+		None => return Ok(Vec::new()),
+	};
+
+	let mut records = Vec::new();
+	let mut era = latest_era;
+	loop {
+		let mut index = 0usize;
+		while let Some(record) = try!(backing.get(col, &journal_key(era, index))) {
+			{
+				let rlp = UntrustedRlp::new(&record);
+				let valid = rlp.item_count() == 3
+					&& rlp.val_at::<H256>(0).is_ok()
+					&& rlp.at(1).map(|r| r.is_list()).unwrap_or(false)
+					&& rlp.val_at::<Vec<H256>>(2).is_ok();
+				if !valid {
+					return Err(format!("Corrupt journal record {} of era {}", index, era));
+				}
+			}
+			records.push((journal_key(era, index), record.to_vec()));
+			index += 1;
+		}
+
+		if index == 0 {
+			// the latest era and its records are always written in the same batch
+			if era == latest_era {
+				return Err(format!("Missing journal records for the latest era {}", era));
+			}
+			break;
+		}
+		if era == 0 {
+			break;
+		}
+		era -= 1;
+	}
+	Ok(records)
+}
+
+fn journal_key(era: u64, index: usize) -> Bytes {
+	let mut r = RlpStream::new_list(3);
+	r.append(&era);
+	r.append(&index);
+	r.append(&&PADDING[..]);
+	r.drain().to_vec()
+}
+
 // all keys must be at least 12 bytes
 const DB_PREFIX_LEN : usize = 12;
 const LATEST_ERA_KEY : [u8; DB_PREFIX_LEN] = [ b'l', b'a', b's', b't', 0, 0, 0, 0, 0, 0, 0, 0 ];
+const PADDING : [u8; 10] = [ 0u8; 10 ];
 
 #[cfg(test)]
 mod tests {
-	use super::Algorithm;
+	use common::*;
+	use hashdb::HashDB;
+	use kvdb::Database;
+	use rlp::{RlpStream, Stream};
+	use super::{Algorithm, check_journal, verify_journal, repair_journal, journal_key, new};
 
 	#[test]
 	fn test_journal_algorithm_parsing() {
@@ -139,6 +329,7 @@ mod tests {
 		assert_eq!(Algorithm::EarlyMerge, "light".parse().unwrap());
 		assert_eq!(Algorithm::OverlayRecent, "fast".parse().unwrap());
 		assert_eq!(Algorithm::RefCounted, "basic".parse().unwrap());
+		assert_eq!(Algorithm::RefCounted, "refcounted".parse().unwrap());
 	}
 
 	#[test]
@@ -184,4 +375,113 @@ mod tests {
 		assert_eq!(overlayrecent, 1);
 		assert_eq!(refcounted, 1);
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn should_check_journal() {
+		let temp = ::devtools::RandomTempPath::new();
+		let backing = Arc::new(Database::open_default(temp.as_str()).unwrap());
+		let mut jdb = new(backing.clone(), Algorithm::OverlayRecent, None);
+		assert_eq!(check_journal(&backing, Algorithm::OverlayRecent, None), Ok(0));
+
+		jdb.insert(b"dog");
+		jdb.commit_batch(0, &b"0".sha3(), None).unwrap();
+		jdb.insert(b"cat");
+		jdb.commit_batch(1, &b"1".sha3(), None).unwrap();
+		assert_eq!(check_journal(&backing, Algorithm::OverlayRecent, None), Ok(2));
+
+		let mut batch = backing.transaction();
+		batch.put(None, &journal_key(1, 0), b"corrupt");
+		backing.write(batch).unwrap();
+		assert!(check_journal(&backing, Algorithm::OverlayRecent, None).is_err());
+
+		let mut batch = backing.transaction();
+		batch.delete(None, &journal_key(1, 0));
+		backing.write(batch).unwrap();
+		assert!(check_journal(&backing, Algorithm::OverlayRecent, None).is_err());
+	}
+
+	#[test]
+	fn should_check_journal_against_database() {
+		let temp = ::devtools::RandomTempPath::new();
+		let backing = Arc::new(Database::open_default(temp.as_str()).unwrap());
+		let mut jdb = new(backing.clone(), Algorithm::EarlyMerge, None);
+
+		let dog = jdb.insert(b"dog");
+		jdb.commit_batch(0, &b"0".sha3(), None).unwrap();
+		assert_eq!(check_journal(&backing, Algorithm::EarlyMerge, None), Ok(1));
+
+		// lost by an unclean shutdown
+		let mut batch = backing.transaction();
+		batch.delete(None, &dog);
+		backing.write(batch).unwrap();
+		assert!(check_journal(&backing, Algorithm::EarlyMerge, None).is_err());
+	}
+
+	#[test]
+	fn should_verify_and_repair_journal() {
+		let temp = ::devtools::RandomTempPath::new();
+		let backing = Arc::new(Database::open_default(temp.as_str()).unwrap());
+		let mut jdb = new(backing.clone(), Algorithm::EarlyMerge, None);
+
+		let dog = jdb.insert(b"dog");
+		jdb.inject_batch().unwrap();
+		jdb.remove(&dog);
+		let cat = jdb.insert(b"cat");
+		jdb.commit_batch(0, &b"0".sha3(), None).unwrap();
+
+		let report = verify_journal(&backing, Algorithm::EarlyMerge, None).unwrap();
+		assert_eq!(report.records, 1);
+		assert!(report.is_consistent());
+
+		let mut batch = backing.transaction();
+		batch.delete(None, &cat);
+		batch.delete(None, &dog);
+		backing.write(batch).unwrap();
+
+		let report = verify_journal(&backing, Algorithm::EarlyMerge, None).unwrap();
+		assert_eq!(report.missing, vec![cat]);
+		assert_eq!(report.dangling_deletions, vec![dog]);
+
+		repair_journal(backing.clone(), Algorithm::EarlyMerge, None, vec![(cat, b"cat".to_vec()), (dog, b"dog".to_vec())]).unwrap();
+		assert!(verify_journal(&backing, Algorithm::EarlyMerge, None).unwrap().is_consistent());
+	}
+
+	#[test]
+	fn should_verify_and_repair_overlay_recent_journal() {
+		let temp = ::devtools::RandomTempPath::new();
+		let backing = Arc::new(Database::open_default(temp.as_str()).unwrap());
+		let mut jdb = new(backing.clone(), Algorithm::OverlayRecent, None);
+
+		let dog = jdb.insert(b"dog");
+		jdb.inject_batch().unwrap();
+		jdb.remove(&dog);
+		let cat = jdb.insert(b"cat");
+		jdb.commit_batch(0, &b"0".sha3(), None).unwrap();
+
+		let report = verify_journal(&backing, Algorithm::OverlayRecent, None).unwrap();
+		assert_eq!(report.records, 1);
+		assert!(report.is_consistent());
+
+		// the journal keeps inserted values until canonicalization
+		let mut record = RlpStream::new_list(3);
+		record.append(&b"0".sha3());
+		record.begin_list(1).begin_list(2).append(&cat).append(&b"cow".to_vec());
+		record.append(&vec![dog]);
+		let mut batch = backing.transaction();
+		batch.put_vec(None, &journal_key(0, 0), record.out());
+		batch.delete(None, &dog);
+		backing.write(batch).unwrap();
+
+		let report = verify_journal(&backing, Algorithm::OverlayRecent, None).unwrap();
+		assert_eq!(report.missing, vec![cat]);
+		assert_eq!(report.dangling_deletions, vec![dog]);
+
+		repair_journal(backing.clone(), Algorithm::OverlayRecent, None, vec![(cat, b"cat".to_vec()), (dog, b"dog".to_vec())]).unwrap();
+		assert!(verify_journal(&backing, Algorithm::OverlayRecent, None).unwrap().is_consistent());
+
+		// the repaired value is written on canonicalization
+		let mut jdb = new(backing.clone(), Algorithm::OverlayRecent, None);
+		jdb.commit_batch(1, &b"1".sha3(), Some((0, b"0".sha3()))).unwrap();
+		assert_eq!(backing.get(None, &cat).unwrap().unwrap().to_vec(), b"cat".to_vec());
+	}
+}
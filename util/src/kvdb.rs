@@ -17,17 +17,22 @@
 //! Key-Value store abstraction with `RocksDB` backend.
 
 use std::io::ErrorKind;
+use std::env;
 use common::*;
 use elastic_array::*;
 use std::default::Default;
 use std::path::PathBuf;
 use rlp::{UntrustedRlp, RlpType, View, Compressible};
+use node_events::{self, NodeEventKind};
 use rocksdb::{DB, Writable, WriteBatch, WriteOptions, IteratorMode, DBIterator,
 	Options, DBCompactionStyle, BlockBasedOptions, Direction, Cache, Column};
 
 const DB_BACKGROUND_FLUSHES: i32 = 2;
 const DB_BACKGROUND_COMPACTIONS: i32 = 2;
 
+/// Default total size of write-ahead logs (in bytes) after which memtables get flushed.
+pub const DEFAULT_WAL_SIZE_LIMIT: u64 = 64 * 1024 * 1024;
+
 /// Write transaction. Batches a sequence of put/delete operations for efficiency.
 pub struct DBTransaction {
 	ops: Vec<DBOp>,
@@ -132,6 +137,45 @@ impl Default for CompactionProfile {
 }
 
 impl CompactionProfile {
+	/// Picks the profile suitable for the storage the database at `db_path` resides on.
+	/// Rotational disks get the hdd profile; anything undetectable gets the default one.
+	#[cfg(target_os = "linux")]
+	pub fn auto(db_path: &Path) -> CompactionProfile {
+		use std::os::unix::fs::MetadataExt;
+
+		// the database may not be created yet, so look at the closest existing parent.
+		let mut path = db_path.to_owned();
+		while fs::metadata(&path).is_err() {
+			if !path.pop() { return Self::default(); }
+		}
+		let dev = match fs::metadata(&path) {
+			Ok(metadata) => metadata.dev(),
+			Err(_) => return Self::default(),
+		};
+
+		let (major, minor) = dev_major_minor(dev);
+		// partitions keep the queue attributes on their parent device.
+		let rotational = [
+			format!("/sys/dev/block/{}:{}/queue/rotational", major, minor),
+			format!("/sys/dev/block/{}:{}/../queue/rotational", major, minor),
+		].iter().filter_map(|p| {
+			let mut s = String::new();
+			fs::File::open(p).and_then(|mut f| f.read_to_string(&mut s)).ok().map(|_| s)
+		}).next();
+
+		match rotational.as_ref().map(|s| s.trim()) {
+			Some("1") => Self::hdd(),
+			_ => Self::default(),
+		}
+	}
+
+	/// Picks the profile suitable for the storage the database at `db_path` resides on.
+	/// Detection is only supported on Linux, so this is always the default profile.
+	#[cfg(not(target_os = "linux"))]
+	pub fn auto(_db_path: &Path) -> CompactionProfile {
+		Self::default()
+	}
+
 	/// Slow hdd compaction profile
 	pub fn hdd() -> CompactionProfile {
 		CompactionProfile {
@@ -142,6 +186,14 @@ impl CompactionProfile {
 	}
 }
 
+/// Split a device number into its major and minor parts, the way glibc's `major` and `minor` do.
+#[cfg(target_os = "linux")]
+fn dev_major_minor(dev: u64) -> (u64, u64) {
+	let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & 0xffff_f000);
+	let minor = (dev & 0xff) | ((dev >> 12) & 0xffff_ff00);
+	(major, minor)
+}
+
 /// Database configuration
 #[derive(Clone)]
 pub struct DatabaseConfig {
@@ -155,6 +207,10 @@ pub struct DatabaseConfig {
 	pub columns: Option<u32>,
 	/// Should we keep WAL enabled?
 	pub wal: bool,
+	/// Total size of write-ahead logs (in bytes) after which memtables get flushed.
+	pub wal_size_limit: u64,
+	/// Size of a memtable (in bytes) before it is flushed to disk; RocksDB default if not set.
+	pub write_buffer_size: Option<u64>,
 }
 
 impl DatabaseConfig {
@@ -180,13 +236,15 @@ impl Default for DatabaseConfig {
 			compaction: CompactionProfile::default(),
 			columns: None,
 			wal: true,
+			wal_size_limit: DEFAULT_WAL_SIZE_LIMIT,
+			write_buffer_size: None,
 		}
 	}
 }
 
 /// Database iterator for flushed data only
 pub struct DatabaseIterator {
-	iter: DBIterator,
+	iter: Box<Iterator<Item=(Box<[u8]>, Box<[u8]>)>>,
 }
 
 impl<'a> Iterator for DatabaseIterator {
@@ -197,6 +255,27 @@ impl<'a> Iterator for DatabaseIterator {
 	}
 }
 
+/// Options of the column `col`.
+fn col_config(col: u32, config: &DatabaseConfig) -> Result<Options, String> {
+	// default cache size for columns not specified.
+	const DEFAULT_CACHE: usize = 2;
+
+	let mut opts = Options::new();
+	opts.set_compaction_style(DBCompactionStyle::DBUniversalCompaction);
+	opts.set_target_file_size_base(config.compaction.initial_file_size);
+	opts.set_target_file_size_multiplier(config.compaction.file_size_multiplier);
+	if let Some(write_buffer_size) = config.write_buffer_size {
+		try!(opts.set_parsed_options(&format!("write_buffer_size={}", write_buffer_size)));
+	}
+
+	let cache_size = config.cache_sizes.get(&Some(col)).cloned().unwrap_or(DEFAULT_CACHE);
+	let mut block_opts = BlockBasedOptions::new();
+	// all goes to read cache.
+	block_opts.set_cache(Cache::new(cache_size * 1024 * 1024));
+	opts.set_block_based_table_factory(&block_opts);
+	Ok(opts)
+}
+
 struct DBAndColumns {
 	db: DB,
 	cfs: Vec<Column>,
@@ -209,6 +288,10 @@ pub struct Database {
 	write_opts: WriteOptions,
 	overlay: RwLock<Vec<HashMap<ElasticArray32<u8>, KeyState>>>,
 	path: String,
+	// opened with `open_read_only`.
+	read_only: bool,
+	// everything is kept in the overlay, see `Database::in_memory`.
+	in_memory: bool,
 }
 
 impl Database {
@@ -219,16 +302,49 @@ impl Database {
 
 	/// Open database file. Creates if it does not exist.
 	pub fn open(config: &DatabaseConfig, path: &str) -> Result<Database, String> {
-		// default cache size for columns not specified.
-		const DEFAULT_CACHE: usize = 2;
+		Self::open_with(config, path, true)
+	}
+
+	/// Open existing database without ever modifying it: it is not created, repaired
+	/// or written to. Data written by other processes after opening is not visible.
+	pub fn open_read_only(config: &DatabaseConfig, path: &str) -> Result<Database, String> {
+		Self::open_with(config, path, false)
+	}
+
+	/// Create a database which keeps all data in memory and is never written to disk,
+	/// e.g. for one-off executions. The data is lost when the database is dropped.
+	pub fn in_memory(columns: Option<u32>) -> Database {
+		Database {
+			db: RwLock::new(None),
+			config: DatabaseConfig::with_columns(columns),
+			write_opts: WriteOptions::new(),
+			overlay: RwLock::new((0..(columns.unwrap_or(0) + 1)).map(|_| HashMap::new()).collect()),
+			path: String::new(),
+			read_only: false,
+			in_memory: true,
+		}
+	}
 
+	/// Returns true if the database was opened with `open_read_only`.
+	pub fn is_read_only(&self) -> bool {
+		self.read_only
+	}
+
+	fn open_with(config: &DatabaseConfig, path: &str, writable: bool) -> Result<Database, String> {
 		let mut opts = Options::new();
 		if let Some(rate_limit) = config.compaction.write_rate_limit {
 			try!(opts.set_parsed_options(&format!("rate_limiter_bytes_per_sec={}", rate_limit)));
 		}
-		try!(opts.set_parsed_options(&format!("max_total_wal_size={}", 64 * 1024 * 1024)));
+		try!(opts.set_parsed_options(&format!("max_total_wal_size={}", config.wal_size_limit)));
+		if let Some(write_buffer_size) = config.write_buffer_size {
+			try!(opts.set_parsed_options(&format!("write_buffer_size={}", write_buffer_size)));
+		}
 		opts.set_max_open_files(config.max_open_files);
-		opts.create_if_missing(true);
+		opts.create_if_missing(writable);
+		if !writable {
+			// keep the info log out of the database directory.
+			try!(opts.set_parsed_options(&format!("db_log_dir={}", env::temp_dir().display())));
+		}
 		opts.set_use_fsync(false);
 
 		opts.set_max_background_flushes(DB_BACKGROUND_FLUSHES);
@@ -242,22 +358,7 @@ impl Database {
 		let mut cf_options = Vec::with_capacity(config.columns.unwrap_or(0) as usize);
 
 		for col in 0 .. config.columns.unwrap_or(0) {
-			let mut opts = Options::new();
-			opts.set_compaction_style(DBCompactionStyle::DBUniversalCompaction);
-			opts.set_target_file_size_base(config.compaction.initial_file_size);
-			opts.set_target_file_size_multiplier(config.compaction.file_size_multiplier);
-
-			let col_opt = config.columns.map(|_| col);
-
-			{
-				let cache_size = config.cache_sizes.get(&col_opt).cloned().unwrap_or(DEFAULT_CACHE);
-				let mut block_opts = BlockBasedOptions::new();
-				// all goes to read cache.
-				block_opts.set_cache(Cache::new(cache_size * 1024 * 1024));
-				opts.set_block_based_table_factory(&block_opts);
-			}
-
-			cf_options.push(opts);
+			cf_options.push(try!(col_config(col, config)));
 		}
 
 		let mut write_opts = WriteOptions::new();
@@ -270,13 +371,18 @@ impl Database {
 			Some(columns) => {
 				let cfnames: Vec<_> = (0..columns).map(|c| format!("col{}", c)).collect();
 				let cfnames: Vec<&str> = cfnames.iter().map(|n| n as &str).collect();
-				match DB::open_cf(&opts, path, &cfnames, &cf_options) {
+				let opened = if writable {
+					DB::open_cf(&opts, path, &cfnames, &cf_options)
+				} else {
+					DB::open_cf_for_read_only(&opts, path, &cfnames, &cf_options)
+				};
+				match opened {
 					Ok(db) => {
 						cfs = cfnames.iter().map(|n| db.cf_handle(n).unwrap()).collect();
 						assert!(cfs.len() == columns as usize);
 						Ok(db)
 					}
-					Err(_) => {
+					Err(e) => if !writable { Err(e) } else {
 						// retry and create CFs
 						match DB::open_cf(&opts, path, &[], &[]) {
 							Ok(mut db) => {
@@ -288,14 +394,16 @@ impl Database {
 					}
 				}
 			},
-			None => DB::open(&opts, path)
+			None if writable => DB::open(&opts, path),
+			None => DB::open_for_read_only(&opts, path),
 		};
 		let db = match db {
 			Ok(db) => db,
-			Err(ref s) if s.starts_with("Corruption:") => {
+			Err(ref s) if writable && s.starts_with("Corruption:") => {
 				info!("{}", s);
 				info!("Attempting DB repair for {}", path);
 				try!(DB::repair(&opts, path));
+				node_events::record_event(NodeEventKind::DatabaseRepair, &format!("{} ({})", path, s));
 				try!(DB::open(&opts, path))
 			},
 			Err(s) => { return Err(s); }
@@ -307,9 +415,36 @@ impl Database {
 			write_opts: write_opts,
 			overlay: RwLock::new((0..(num_cols + 1)).map(|_| HashMap::new()).collect()),
 			path: path.to_owned(),
+			read_only: !writable,
+			in_memory: false,
 		})
 	}
 
+	/// Number of columns in the database.
+	pub fn num_columns(&self) -> u32 {
+		if self.in_memory {
+			return self.overlay.read().len() as u32 - 1;
+		}
+		self.db.read().as_ref().map_or(0, |db| db.cfs.len() as u32)
+	}
+
+	/// Append a new empty column to the database.
+	pub fn add_column(&self) -> Result<(), String> {
+		match *self.db.write() {
+			Some(DBAndColumns { ref mut db, ref mut cfs }) => {
+				let col = cfs.len() as u32;
+				cfs.push(try!(db.create_cf(&format!("col{}", col), &try!(col_config(col, &self.config)))));
+				self.overlay.write().push(HashMap::new());
+				Ok(())
+			},
+			None if self.in_memory => {
+				self.overlay.write().push(HashMap::new());
+				Ok(())
+			},
+			None => Err("Database is closed".to_owned()),
+		}
+	}
+
 	/// Creates new transaction for this database.
 	pub fn transaction(&self) -> DBTransaction {
 		DBTransaction::new(self)
@@ -344,6 +479,9 @@ impl Database {
 
 	/// Commit buffered changes to database.
 	pub fn flush(&self) -> Result<(), String> {
+		if self.is_read_only() {
+			return Err("Database is read-only".to_owned());
+		}
 		match *self.db.read() {
 			Some(DBAndColumns { ref db, ref cfs }) => {
 				let batch = WriteBatch::new();
@@ -380,6 +518,7 @@ impl Database {
 				}
 				db.write_opt(batch, &self.write_opts)
 			},
+			None if self.in_memory => Ok(()),
 			None => Err("Database is closed".to_owned())
 		}
 	}
@@ -387,6 +526,9 @@ impl Database {
 
 	/// Commit transaction to database.
 	pub fn write(&self, tr: DBTransaction) -> Result<(), String> {
+		if self.is_read_only() {
+			return Err("Database is read-only".to_owned());
+		}
 		match *self.db.read() {
 			Some(DBAndColumns { ref db, ref cfs }) => {
 				let batch = WriteBatch::new();
@@ -407,6 +549,10 @@ impl Database {
 				}
 				db.write_opt(batch, &self.write_opts)
 			},
+			None if self.in_memory => {
+				self.write_buffered(tr);
+				Ok(())
+			},
 			None => Err("Database is closed".to_owned())
 		}
 	}
@@ -426,7 +572,10 @@ impl Database {
 					},
 				}
 			},
-			None => Ok(None),
+			None => match self.overlay.read().get(Self::to_overlay_column(col)).and_then(|overlay| overlay.get(key)) {
+				Some(&KeyState::Insert(ref value)) | Some(&KeyState::InsertCompressed(ref value)) => Ok(Some(value.clone())),
+				_ => Ok(None),
+			},
 		}
 	}
 
@@ -443,7 +592,9 @@ impl Database {
 					_ => None
 				}
 			},
-			None => None,
+			None => self.overlay_iter(col)
+				.find(|&(ref k, _)| k.starts_with(prefix))
+				.map(|(_, v)| v),
 		}
 	}
 
@@ -452,13 +603,43 @@ impl Database {
 		//TODO: iterate over overlay
 		match *self.db.read() {
 			Some(DBAndColumns { ref db, ref cfs }) => {
-				col.map_or_else(|| DatabaseIterator { iter: db.iterator(IteratorMode::Start) },
-					|c| DatabaseIterator { iter: db.iterator_cf(cfs[c as usize], IteratorMode::Start).unwrap() })
+				let iter: DBIterator = col.map_or_else(|| db.iterator(IteratorMode::Start),
+					|c| db.iterator_cf(cfs[c as usize], IteratorMode::Start).unwrap());
+				DatabaseIterator { iter: Box::new(iter) }
 			},
+			None if self.in_memory => DatabaseIterator { iter: Box::new(self.overlay_iter(col)) },
 			None => panic!("Not supported yet") //TODO: return an empty iterator or change return type
 		}
 	}
 
+	/// Compact the whole key range of the column in place, dropping the data of deleted and overwritten keys.
+	pub fn compact(&self, col: Option<u32>) -> Result<(), String> {
+		try!(self.flush());
+		match *self.db.read() {
+			Some(DBAndColumns { ref db, ref cfs }) => {
+				match col {
+					Some(c) => db.compact_range_cf(cfs[c as usize], None, None),
+					None => db.compact_range(None, None),
+				}
+				Ok(())
+			},
+			None => Ok(()),
+		}
+	}
+
+	/// Values inserted in the overlay of the column, in key order.
+	fn overlay_iter(&self, col: Option<u32>) -> ::std::vec::IntoIter<(Box<[u8]>, Box<[u8]>)> {
+		let mut values: Vec<_> = self.overlay.read().get(Self::to_overlay_column(col))
+			.map_or_else(Vec::new, |overlay| overlay.iter()
+				.filter_map(|(key, state)| match *state {
+					KeyState::Insert(ref value) | KeyState::InsertCompressed(ref value) => Some((key.to_vec().into_boxed_slice(), value.clone().into_boxed_slice())),
+					KeyState::Delete => None,
+				})
+				.collect());
+		values.sort();
+		values.into_iter()
+	}
+
 	/// Close the database
 	fn close(&self) {
 		*self.db.write() = None;
@@ -467,6 +648,9 @@ impl Database {
 
 	/// Restore the database from a copy at given path.
 	pub fn restore(&self, new_db: &str) -> Result<(), UtilError> {
+		if self.is_read_only() || self.in_memory {
+			return Err(UtilError::SimpleString("Database can't be restored".into()));
+		}
 		self.close();
 
 		let mut backup_db = PathBuf::from(&self.path);
@@ -513,9 +697,22 @@ mod tests {
 	use devtools::*;
 	use std::str::FromStr;
 
+	#[test]
+	#[cfg(target_os = "linux")]
+	fn splits_device_numbers() {
+		use super::dev_major_minor;
+		assert_eq!(dev_major_minor(0x801), (8, 1));
+		assert_eq!(dev_major_minor(0x1231_0345), (259, 0x12345));
+		assert_eq!(dev_major_minor(0x1000_0000_0000), (0x1000, 0));
+		assert_eq!(dev_major_minor(0x1000_0000_0801), (0x1008, 1));
+	}
+
 	fn test_db(config: &DatabaseConfig) {
 		let path = RandomTempPath::create_dir();
-		let db = Database::open(config, path.as_path().to_str().unwrap()).unwrap();
+		check_db(&Database::open(config, path.as_path().to_str().unwrap()).unwrap());
+	}
+
+	fn check_db(db: &Database) {
 		let key1 = H256::from_str("02c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
 		let key2 = H256::from_str("03c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
 		let key3 = H256::from_str("01c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
@@ -566,10 +763,79 @@ mod tests {
 		assert_eq!(&*db.get(None, &key1).unwrap().unwrap(), b"horse");
 	}
 
+	#[test]
+	fn read_only_open_leaves_database_untouched() {
+		let temp = RandomTempPath::create_dir();
+		let path = temp.as_str();
+		let key = H256::from(1);
+		{
+			let db = Database::open_default(path).unwrap();
+			let mut batch = db.transaction();
+			batch.put(None, &key, b"cat");
+			db.write(batch).unwrap();
+		}
+		let files = |path: &str| {
+			let mut files: Vec<_> = fs::read_dir(path).unwrap()
+				.map(|e| { let e = e.unwrap(); (e.file_name(), e.metadata().unwrap().len()) })
+				.collect();
+			files.sort();
+			files
+		};
+		let before = files(path);
+
+		let db = Database::open_read_only(&DatabaseConfig::default(), path).unwrap();
+		assert_eq!(&*db.get(None, &key).unwrap().unwrap(), b"cat");
+		let mut batch = db.transaction();
+		batch.put(None, &key, b"dog");
+		assert!(db.write(batch).is_err());
+		drop(db);
+
+		assert_eq!(files(path), before);
+	}
+
+	#[test]
+	fn read_only_open_does_not_create() {
+		let path = RandomTempPath::new();
+		assert!(Database::open_read_only(&DatabaseConfig::default(), path.as_str()).is_err());
+		assert!(!path.as_path().exists());
+	}
+
+	#[test]
+	fn add_columns() {
+		let temp = RandomTempPath::create_dir();
+		let key = H256::from(1);
+		{
+			let db = Database::open(&DatabaseConfig::with_columns(Some(1)), temp.as_str()).unwrap();
+			assert_eq!(db.num_columns(), 1);
+			db.add_column().unwrap();
+			assert_eq!(db.num_columns(), 2);
+
+			let mut batch = db.transaction();
+			batch.put(Some(1), &key, b"cat");
+			db.write(batch).unwrap();
+		}
+
+		let db = Database::open(&DatabaseConfig::with_columns(Some(2)), temp.as_str()).unwrap();
+		assert_eq!(&*db.get(Some(1), &key).unwrap().unwrap(), b"cat");
+	}
+
 	#[test]
 	fn kvdb() {
 		let path = RandomTempPath::create_dir();
 		let _ = Database::open_default(path.as_path().to_str().unwrap()).unwrap();
 		test_db(&DatabaseConfig::default());
 	}
+
+	#[test]
+	fn in_memory_kvdb() {
+		check_db(&Database::in_memory(None));
+
+		let db = Database::in_memory(Some(2));
+		assert_eq!(db.num_columns(), 2);
+		let mut batch = db.transaction();
+		batch.put(Some(1), &H256::from(1), b"cat");
+		db.write(batch).unwrap();
+		assert_eq!(&*db.get(Some(1), &H256::from(1)).unwrap().unwrap(), b"cat");
+		assert!(db.get(Some(0), &H256::from(1)).unwrap().is_none());
+	}
 }
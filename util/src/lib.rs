@@ -104,6 +104,7 @@ extern crate parking_lot;
 extern crate ansi_term;
 extern crate tiny_keccak;
 extern crate rlp;
+extern crate backtrace;
 
 #[macro_use]
 extern crate heapsize;
@@ -140,6 +141,8 @@ pub mod nibbleslice;
 pub mod nibblevec;
 pub mod semantic_version;
 pub mod log;
+pub mod node_events;
+pub mod metrics;
 pub mod path;
 pub mod snappy;
 mod timer;
@@ -47,33 +47,59 @@ pub fn init_log() {
 
 const LOG_SIZE : usize = 128;
 
+/// Callback applying new level directives to the active logger.
+pub type LevelsHandler = Box<Fn(&str) -> Result<(), String> + Send + Sync>;
+
 /// Logger implementation that keeps up to `LOG_SIZE` log elements.
 pub struct RotatingLogger {
 	/// Defined logger levels
-	levels: String,
+	levels: RwLock<String>,
 	/// Logs array. Latest log is always at index 0
 	logs: RwLock<ArrayVec<[String; LOG_SIZE]>>,
+	/// Applies level changes made at runtime.
+	levels_handler: RwLock<Option<LevelsHandler>>,
 }
 
 impl RotatingLogger {
 
 	/// Creates new `RotatingLogger` with given levels.
-	/// It does not enforce levels - it's just read only.
+	/// It does not enforce levels - levels can only be changed once a handler is set.
 	pub fn new(levels: String) -> Self {
 		RotatingLogger {
-			levels: levels,
+			levels: RwLock::new(levels),
 			logs: RwLock::new(ArrayVec::<[_; LOG_SIZE]>::new()),
+			levels_handler: RwLock::new(None),
 		}
 	}
 
+	/// Set the handler used to apply level changes to the active logger.
+	pub fn set_levels_handler(&self, handler: LevelsHandler) {
+		*self.levels_handler.write() = Some(handler);
+	}
+
+	/// Apply additional `RUST_LOG`-style directives (e.g. `sync=trace`) on top of the current levels.
+	pub fn set_levels(&self, directives: &str) -> Result<(), String> {
+		match *self.levels_handler.read() {
+			Some(ref handler) => try!(handler(directives)),
+			None => return Err("Logger levels can't be changed at runtime".into()),
+		}
+
+		let mut levels = self.levels.write();
+		if !levels.is_empty() && !levels.ends_with(',') {
+			levels.push(',');
+		}
+		levels.push_str(directives);
+		Ok(())
+	}
+
 	/// Append new log entry
 	pub fn append(&self, log: String) {
 		self.logs.write().insert(0, log);
 	}
 
 	/// Return levels
-	pub fn levels(&self) -> &str {
-		&self.levels
+	pub fn levels(&self) -> String {
+		self.levels.read().clone()
 	}
 
 	/// Return logs
@@ -118,5 +144,23 @@ mod test {
 		assert_eq!(logs[1], "a".to_owned());
 		assert_eq!(logs.len(), 2);
 	}
+
+	#[test]
+	fn should_update_levels_through_handler() {
+		// given
+		let logger = logger();
+		assert!(logger.set_levels("sync=trace").is_err());
+
+		// when
+		logger.set_levels_handler(Box::new(|levels| match levels.contains('=') {
+			true => Ok(()),
+			false => Err("invalid".into()),
+		}));
+
+		// then
+		assert!(logger.set_levels("trace").is_err());
+		assert_eq!(logger.set_levels("sync=trace"), Ok(()));
+		assert_eq!(logger.levels(), "test,sync=trace");
+	}
 }
 
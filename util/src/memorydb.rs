@@ -23,6 +23,7 @@ use sha3::*;
 use hashdb::*;
 use heapsize::*;
 use std::mem;
+use parking_lot::Mutex;
 use std::collections::HashMap;
 
 const STATIC_NULL_RLP: (&'static [u8], i32) = (&[0x80; 1], 1);
@@ -71,17 +72,95 @@ use std::collections::hash_map::Entry;
 ///   assert!(!m.contains(&k));
 /// }
 /// ```
-#[derive(Default, Clone, PartialEq)]
 pub struct MemoryDB {
 	data: H256FastMap<(Bytes, i32)>,
+	// values of keys only known from a backing database, see `denote`.
+	denoted: Denoted,
 	aux: HashMap<Bytes, Bytes>,
 }
 
+/// Append-only store of values denoted while the database is shared.
+///
+/// Each value is stored once per key in its own allocation, which is neither moved
+/// nor freed until the store is borrowed mutably. Slices of stored values therefore
+/// stay valid for as long as the store is borrowed.
+#[derive(Default)]
+struct Denoted {
+	values: Mutex<H256FastMap<Box<[u8]>>>,
+}
+
+impl Denoted {
+	/// Value of `key`, if it has been denoted.
+	fn get(&self, key: &H256) -> Option<&[u8]> {
+		self.values.lock().get(key).map(|value| self.extend(value))
+	}
+
+	/// Value of `key`, storing `value` first if the key is not known yet.
+	fn get_or_insert(&self, key: &H256, value: Bytes) -> &[u8] {
+		let mut values = self.values.lock();
+		self.extend(values.entry(key.clone()).or_insert_with(|| value.into_boxed_slice()))
+	}
+
+	/// Extends the lifetime of a stored value to the borrow of the store.
+	fn extend<'a>(&'a self, value: &[u8]) -> &'a [u8] {
+		// values are only added while the store is shared: growing the map moves the boxes,
+		// not their contents, and nothing is removed without a mutable borrow.
+		unsafe { &*(value as *const [u8]) }
+	}
+
+	/// Takes all stored values. Doesn't lock as the store is borrowed mutably.
+	fn drain(&mut self) -> H256FastMap<Box<[u8]>> {
+		mem::replace(self.values.get_mut(), H256FastMap::default())
+	}
+
+	fn heap_size(&self) -> usize {
+		let values = self.values.lock();
+		values.capacity() * mem::size_of::<(H256, Box<[u8]>)>() + values.values().map(|value| value.len()).sum::<usize>()
+	}
+}
+
+impl Clone for Denoted {
+	fn clone(&self) -> Self {
+		Denoted {
+			values: Mutex::new(self.values.lock().clone()),
+		}
+	}
+}
+
+impl PartialEq for Denoted {
+	fn eq(&self, other: &Denoted) -> bool {
+		*self.values.lock() == *other.values.lock()
+	}
+}
+
+impl Default for MemoryDB {
+	fn default() -> Self {
+		MemoryDB::new()
+	}
+}
+
+impl Clone for MemoryDB {
+	fn clone(&self) -> Self {
+		MemoryDB {
+			data: self.data.clone(),
+			denoted: self.denoted.clone(),
+			aux: self.aux.clone(),
+		}
+	}
+}
+
+impl PartialEq for MemoryDB {
+	fn eq(&self, other: &MemoryDB) -> bool {
+		self.data == other.data && self.denoted == other.denoted && self.aux == other.aux
+	}
+}
+
 impl MemoryDB {
 	/// Create a new instance of the memory DB.
 	pub fn new() -> MemoryDB {
 		MemoryDB {
 			data: H256FastMap::default(),
+			denoted: Denoted::default(),
 			aux: HashMap::new(),
 		}
 	}
@@ -104,10 +183,12 @@ impl MemoryDB {
 	/// ```
 	pub fn clear(&mut self) {
 		self.data.clear();
+		self.denoted.drain();
 	}
 
 	/// Purge all zero-referenced data from the database.
 	pub fn purge(&mut self) {
+		self.denoted.drain();
 		let empties: Vec<_> = self.data.iter()
 			.filter(|&(_, &(_, rc))| rc == 0)
 			.map(|(k, _)| k.clone())
@@ -117,6 +198,7 @@ impl MemoryDB {
 
 	/// Return the internal map of hashes to data, clearing the current state.
 	pub fn drain(&mut self) -> H256FastMap<(Bytes, i32)> {
+		self.absorb_denoted();
 		mem::replace(&mut self.data, H256FastMap::default())
 	}
 
@@ -134,7 +216,10 @@ impl MemoryDB {
 		if key == &SHA3_NULL_RLP {
 			return Some(STATIC_NULL_RLP.clone());
 		}
-		self.data.get(key).map(|&(ref val, rc)| (&val[..], rc))
+		match self.data.get(key) {
+			Some(&(ref val, rc)) => Some((&val[..], rc)),
+			None => self.denoted.get(key).map(|val| (val, 0)),
+		}
 	}
 
 	/// Denote than an existing value has the given key. Used when a key gets removed without
@@ -142,18 +227,16 @@ impl MemoryDB {
 	///
 	/// May safely be called even if the key's value is known, in which case it will be a no-op.
 	pub fn denote(&self, key: &H256, value: Bytes) -> (&[u8], i32) {
-		if self.raw(key) == None {
-			unsafe {
-				let p = &self.data as *const H256FastMap<(Bytes, i32)> as *mut H256FastMap<(Bytes, i32)>;
-				(*p).insert(key.clone(), (value, 0));
-			}
+		match self.raw(key) {
+			Some(raw) => raw,
+			None => (self.denoted.get_or_insert(key, value), 0),
 		}
-		self.raw(key).unwrap()
 	}
 
 	/// Returns the size of allocated heap memory
 	pub fn mem_used(&self) -> usize {
 		self.data.heap_size_of_children()
+		+ self.denoted.heap_size()
 		+ self.aux.heap_size_of_children()
 	}
 
@@ -162,6 +245,7 @@ impl MemoryDB {
 		if key == &SHA3_NULL_RLP {
 			return;
 		}
+		self.absorb_denoted();
 		match self.data.entry(key.clone()) {
 			Entry::Occupied(mut entry) =>
 				if entry.get().1 == 1 {
@@ -177,10 +261,11 @@ impl MemoryDB {
 
 	/// Consolidate all the entries of `other` into `self`.
 	pub fn consolidate(&mut self, mut other: Self) {
+		self.absorb_denoted();
 		for (key, (value, rc)) in other.drain() {
 			match self.data.entry(key) {
 				Entry::Occupied(mut entry) => {
-					if entry.get().1 < 0 {
+					if entry.get().1 < 0 && entry.get().0.is_empty() {
 						entry.get_mut().0 = value;
 					}
 
@@ -192,6 +277,31 @@ impl MemoryDB {
 			}
 		}
 	}
+
+	/// Add a reference to `key`, storing `value` if it isn't known yet.
+	/// Values are identified by their hash, so a known value is never stored again.
+	fn insert_ref(&mut self, key: H256, value: &[u8]) {
+		self.absorb_denoted();
+		match self.data.entry(key) {
+			Entry::Occupied(mut entry) => {
+				let &mut (ref mut old_value, ref mut rc) = entry.get_mut();
+				if *rc <= 0 && old_value.is_empty() {
+					*old_value = value.to_vec();
+				}
+				*rc += 1;
+			},
+			Entry::Vacant(entry) => {
+				entry.insert((value.to_vec(), 1));
+			},
+		}
+	}
+
+	/// Move denoted values into the reference counted data before it is modified.
+	fn absorb_denoted(&mut self) {
+		for (key, value) in self.denoted.drain() {
+			self.data.entry(key).or_insert((value.into_vec(), 0));
+		}
+	}
 }
 
 static NULL_RLP_STATIC: [u8; 1] = [0x80; 1];
@@ -228,17 +338,7 @@ impl HashDB for MemoryDB {
 			return SHA3_NULL_RLP.clone();
 		}
 		let key = value.sha3();
-		if match self.data.get_mut(&key) {
-			Some(&mut (ref mut old_value, ref mut rc @ -0x80000000i32 ... 0)) => {
-				*old_value = value.into();
-				*rc += 1;
-				false
-			},
-			Some(&mut (_, ref mut x)) => { *x += 1; false } ,
-			None => true,
-		}{	// ... None falls through into...
-			self.data.insert(key.clone(), (value.into(), 1));
-		}
+		self.insert_ref(key.clone(), value);
 		key
 	}
 
@@ -246,18 +346,7 @@ impl HashDB for MemoryDB {
 		if value == &NULL_RLP {
 			return;
 		}
-
-		match self.data.get_mut(&key) {
-			Some(&mut (ref mut old_value, ref mut rc @ -0x80000000i32 ... 0)) => {
-				*old_value = value;
-				*rc += 1;
-				return;
-			},
-			Some(&mut (_, ref mut x)) => { *x += 1; return; } ,
-			None => {},
-		}
-		// ... None falls through into...
-		self.data.insert(key, (value, 1));
+		self.insert_ref(key, &value);
 	}
 
 	fn remove(&mut self, key: &H256) {
@@ -265,12 +354,13 @@ impl HashDB for MemoryDB {
 			return;
 		}
 
-		if match self.data.get_mut(key) {
-			Some(&mut (_, ref mut x)) => { *x -= 1; false }
-			None => true
-		}{	// ... None falls through into...
-			self.data.insert(key.clone(), (Bytes::new(), -1));
+		self.absorb_denoted();
+		match self.data.get_mut(key) {
+			Some(&mut (_, ref mut x)) => { *x -= 1; return; }
+			None => {},
 		}
+		// ... None falls through into...
+		self.data.insert(key.clone(), (Bytes::new(), -1));
 	}
 
 	fn insert_aux(&mut self, hash: Vec<u8>, value: Vec<u8>) {
@@ -345,4 +435,38 @@ fn consolidate() {
 
 	assert_eq!(overlay.get(&remove_key).unwrap(), &(b"doggo".to_vec(), 0));
 	assert_eq!(overlay.get(&insert_key).unwrap(), &(b"arf".to_vec(), 2));
-}
\ No newline at end of file
+}
+
+#[test]
+fn memorydb_stores_value_once() {
+	let mut m = MemoryDB::new();
+	let key = m.insert(b"dog");
+	m.insert(b"dog");
+	m.emplace(key, b"dog".to_vec());
+	assert_eq!(m.raw(&key).unwrap(), (&b"dog"[..], 3));
+
+	let other = {
+		let mut other = MemoryDB::new();
+		other.remove(&key);
+		other.insert(b"dog");
+		other.insert(b"dog");
+		other.insert(b"dog");
+		other.insert(b"dog");
+		other
+	};
+	assert!(m == other);
+}
+
+#[test]
+fn memorydb_denoted_values_are_kept() {
+	let mut m = MemoryDB::new();
+	let key = b"dog".sha3();
+	assert_eq!(m.denote(&key, b"dog".to_vec()), (&b"dog"[..], 0));
+	assert!(!m.contains(&key));
+
+	m.remove(&key);
+	assert_eq!(m.raw(&key).unwrap(), (&b"dog"[..], -1));
+	m.remove(&key);
+	m.insert(b"dog");
+	assert_eq!(m.drain().get(&key).unwrap(), &(b"dog".to_vec(), -1));
+}
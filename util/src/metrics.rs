@@ -0,0 +1,252 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Process-wide metrics registry.
+//!
+//! Counters, gauges and histograms are registered by name on first use and
+//! rendered in the Prometheus text exposition format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use parking_lot::{Mutex, RwLock};
+
+/// Default histogram buckets, in seconds.
+pub const DEFAULT_BUCKETS: &'static [f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+lazy_static! {
+	static ref REGISTRY: Registry = Registry::new();
+}
+
+/// Returns the process-wide registry.
+pub fn registry() -> &'static Registry {
+	&REGISTRY
+}
+
+/// Monotonically increasing value.
+#[derive(Default, Debug)]
+pub struct Counter(AtomicUsize);
+
+impl Counter {
+	/// Increase the counter by one.
+	pub fn inc(&self) {
+		self.inc_by(1);
+	}
+
+	/// Increase the counter by `n`.
+	pub fn inc_by(&self, n: usize) {
+		self.0.fetch_add(n, Ordering::Relaxed);
+	}
+
+	/// Current value.
+	pub fn get(&self) -> usize {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+/// Value that can go up and down.
+#[derive(Default, Debug)]
+pub struct Gauge(AtomicUsize);
+
+impl Gauge {
+	/// Set the current value.
+	pub fn set(&self, value: usize) {
+		self.0.store(value, Ordering::Relaxed);
+	}
+
+	/// Current value.
+	pub fn get(&self) -> usize {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+#[derive(Debug)]
+struct HistogramData {
+	/// Number of observations per bucket, not cumulative.
+	buckets: Vec<usize>,
+	sum: f64,
+	count: usize,
+}
+
+/// Distribution of observed values over fixed buckets.
+#[derive(Debug)]
+pub struct Histogram {
+	bounds: Vec<f64>,
+	data: Mutex<HistogramData>,
+}
+
+impl Histogram {
+	/// Create a histogram with the given upper bucket bounds, in ascending order.
+	pub fn new(bounds: &[f64]) -> Self {
+		Histogram {
+			bounds: bounds.to_vec(),
+			data: Mutex::new(HistogramData {
+				buckets: vec![0; bounds.len()],
+				sum: 0.0,
+				count: 0,
+			}),
+		}
+	}
+
+	/// Record a value.
+	pub fn observe(&self, value: f64) {
+		let mut data = self.data.lock();
+		if let Some(i) = self.bounds.iter().position(|b| value <= *b) {
+			data.buckets[i] += 1;
+		}
+		data.sum += value;
+		data.count += 1;
+	}
+
+	/// Record the time elapsed since `start`, in seconds.
+	pub fn observe_since(&self, start: Instant) {
+		let elapsed = start.elapsed();
+		self.observe(elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000f64);
+	}
+
+	/// Number of recorded values.
+	pub fn count(&self) -> usize {
+		self.data.lock().count
+	}
+}
+
+impl Default for Histogram {
+	fn default() -> Self {
+		Histogram::new(DEFAULT_BUCKETS)
+	}
+}
+
+#[derive(Debug, Clone)]
+enum Metric {
+	Counter(Arc<Counter>),
+	Gauge(Arc<Gauge>),
+	Histogram(Arc<Histogram>),
+}
+
+/// Collection of named metrics.
+#[derive(Default, Debug)]
+pub struct Registry {
+	metrics: RwLock<BTreeMap<String, (String, Metric)>>,
+}
+
+impl Registry {
+	/// Create an empty registry.
+	pub fn new() -> Self {
+		Registry::default()
+	}
+
+	fn register<F>(&self, name: &str, help: &str, create: F) -> Metric where F: FnOnce() -> Metric {
+		if let Some(&(_, ref metric)) = self.metrics.read().get(name) {
+			return metric.clone();
+		}
+		self.metrics.write().entry(name.to_owned()).or_insert_with(|| (help.to_owned(), create())).1.clone()
+	}
+
+	/// Get the counter registered under `name`, registering it if needed.
+	pub fn counter(&self, name: &str, help: &str) -> Arc<Counter> {
+		match self.register(name, help, || Metric::Counter(Default::default())) {
+			Metric::Counter(c) => c,
+			_ => panic!("Metric {} is not a counter", name),
+		}
+	}
+
+	/// Get the gauge registered under `name`, registering it if needed.
+	pub fn gauge(&self, name: &str, help: &str) -> Arc<Gauge> {
+		match self.register(name, help, || Metric::Gauge(Default::default())) {
+			Metric::Gauge(g) => g,
+			_ => panic!("Metric {} is not a gauge", name),
+		}
+	}
+
+	/// Get the histogram registered under `name`, registering it with default buckets if needed.
+	pub fn histogram(&self, name: &str, help: &str) -> Arc<Histogram> {
+		match self.register(name, help, || Metric::Histogram(Default::default())) {
+			Metric::Histogram(h) => h,
+			_ => panic!("Metric {} is not a histogram", name),
+		}
+	}
+
+	/// Render all metrics in the Prometheus text exposition format.
+	pub fn render(&self) -> String {
+		let mut out = String::new();
+		for (name, &(ref help, ref metric)) in self.metrics.read().iter() {
+			let _ = writeln!(out, "# HELP {} {}", name, help);
+			match *metric {
+				Metric::Counter(ref c) => {
+					let _ = writeln!(out, "# TYPE {} counter\n{} {}", name, name, c.get());
+				},
+				Metric::Gauge(ref g) => {
+					let _ = writeln!(out, "# TYPE {} gauge\n{} {}", name, name, g.get());
+				},
+				Metric::Histogram(ref h) => {
+					let _ = writeln!(out, "# TYPE {} histogram", name);
+					let data = h.data.lock();
+					let mut cumulative = 0;
+					for (bound, count) in h.bounds.iter().zip(data.buckets.iter()) {
+						cumulative += *count;
+						let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+					}
+					let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, data.count);
+					let _ = writeln!(out, "{}_sum {}\n{}_count {}", name, data.sum, name, data.count);
+				},
+			}
+		}
+		out
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Registry;
+
+	#[test]
+	fn should_return_registered_metric() {
+		let registry = Registry::new();
+		registry.counter("imported_total", "Imported blocks").inc_by(2);
+		registry.counter("imported_total", "Imported blocks").inc();
+		assert_eq!(registry.counter("imported_total", "").get(), 3);
+	}
+
+	#[test]
+	#[should_panic]
+	fn should_reject_metric_of_other_type() {
+		let registry = Registry::new();
+		registry.counter("peers", "Peers");
+		registry.gauge("peers", "Peers");
+	}
+
+	#[test]
+	fn should_render_text_format() {
+		let registry = Registry::new();
+		registry.counter("a_total", "A counter").inc();
+		registry.gauge("b", "A gauge").set(7);
+		let histogram = registry.histogram("c_seconds", "A histogram");
+		histogram.observe(0.003);
+		histogram.observe(0.2);
+		histogram.observe(20.0);
+
+		let rendered = registry.render();
+		assert!(rendered.starts_with("# HELP a_total A counter\n# TYPE a_total counter\na_total 1\n# HELP b A gauge\n# TYPE b gauge\nb 7\n"));
+		assert!(rendered.contains("c_seconds_bucket{le=\"0.001\"} 0\n"));
+		assert!(rendered.contains("c_seconds_bucket{le=\"0.005\"} 1\n"));
+		assert!(rendered.contains("c_seconds_bucket{le=\"0.25\"} 2\n"));
+		assert!(rendered.contains("c_seconds_bucket{le=\"10\"} 2\n"));
+		assert!(rendered.contains("c_seconds_bucket{le=\"+Inf\"} 3\n"));
+		assert!(rendered.ends_with("c_seconds_count 3\n"));
+	}
+}
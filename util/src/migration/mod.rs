@@ -19,12 +19,35 @@
 mod tests;
 
 use std::collections::BTreeMap;
-use std::fs;
+use std::fs::{self, File};
 use std::fmt;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use ::kvdb::{CompactionProfile, Database, DatabaseConfig, DBTransaction};
+use ::kvdb::{CompactionProfile, Database, DatabaseConfig, DBTransaction, DEFAULT_WAL_SIZE_LIMIT};
+
+/// Key in the default column under which the version of a migrated database is recorded.
+pub const VERSION_KEY: &'static [u8] = b"migration_version";
+
+/// Name of the file in the databases root recording the last completed migration step.
+const PROGRESS_FILE_NAME: &'static str = "migration_progress";
+
+/// Reads the version recorded in the database by a previous migration, if any.
+pub fn read_version(db: &Database) -> Result<Option<u32>, Error> {
+	match try!(db.get(None, VERSION_KEY).map_err(Error::Custom)) {
+		Some(ref v) if v.len() == 4 => Ok(Some(((v[0] as u32) << 24) | ((v[1] as u32) << 16) | ((v[2] as u32) << 8) | v[3] as u32)),
+		Some(_) => Err(Error::Custom("Malformed database version".into())),
+		None => Ok(None),
+	}
+}
+
+/// Records the given version in the database.
+pub fn write_version(db: &Database, version: u32) -> Result<(), Error> {
+	let mut transaction = db.transaction();
+	transaction.put(None, VERSION_KEY, &[(version >> 24) as u8, (version >> 16) as u8, (version >> 8) as u8, version as u8]);
+	db.write(transaction).map_err(Error::Custom)
+}
 
 /// Migration config.
 #[derive(Clone)]
@@ -127,12 +150,39 @@ pub trait Migration: 'static {
 	fn pre_columns(&self) -> Option<u32> { self.columns() }
 	/// Number of columns in database after the migration.
 	fn columns(&self) -> Option<u32>;
+	/// Whether the migration changes existing data. Migrations which only add columns
+	/// are performed in place, without copying the database.
+	fn alters_existing(&self) -> bool { true }
 	/// Version of the database after the migration.
 	fn version(&self) -> u32;
 	/// Migrate a source to a destination.
 	fn migrate(&mut self, source: Arc<Database>, config: &Config, destination: &mut Database, col: Option<u32>) -> Result<(), Error>;
 }
 
+/// A migration which only adds empty columns.
+pub struct ChangeColumns {
+	/// Number of columns before the migration.
+	pub pre_columns: Option<u32>,
+	/// Number of columns after the migration.
+	pub post_columns: Option<u32>,
+	/// Version of the database after the migration.
+	pub version: u32,
+}
+
+impl Migration for ChangeColumns {
+	fn pre_columns(&self) -> Option<u32> { self.pre_columns }
+
+	fn columns(&self) -> Option<u32> { self.post_columns }
+
+	fn alters_existing(&self) -> bool { false }
+
+	fn version(&self) -> u32 { self.version }
+
+	fn migrate(&mut self, _: Arc<Database>, _: &Config, _: &mut Database, _: Option<u32>) -> Result<(), Error> {
+		Ok(())
+	}
+}
+
 /// A simple migration over key-value pairs.
 pub trait SimpleMigration: 'static {
 	/// Number of columns in database after the migration.
@@ -151,8 +201,12 @@ impl<T: SimpleMigration> Migration for T {
 
 	fn migrate(&mut self, source: Arc<Database>, config: &Config, dest: &mut Database, col: Option<u32>) -> Result<(), Error> {
 		let mut batch = Batch::new(config, col);
+		let mut progress = Progress::default();
 
 		for (key, value) in source.iter(col) {
+			progress.tick();
+			// the version is recorded by the manager once the migration is done.
+			if col.is_none() && &*key == VERSION_KEY { continue; }
 			if let Some((key, value)) = self.simple_migrate(key.to_vec(), value.to_vec()) {
 				try!(batch.insert(key, value, dest));
 			}
@@ -169,12 +223,28 @@ fn database_path(path: &Path) -> PathBuf {
 	temp_path
 }
 
+#[derive(Clone, Copy)]
 enum TempIndex {
 	One,
 	Two,
 }
 
 impl TempIndex {
+	fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"temp_migration_1" => Some(TempIndex::One),
+			"temp_migration_2" => Some(TempIndex::Two),
+			_ => None,
+		}
+	}
+
+	fn name(&self) -> &'static str {
+		match *self {
+			TempIndex::One => "temp_migration_1",
+			TempIndex::Two => "temp_migration_2",
+		}
+	}
+
 	fn swap(&mut self) {
 		match *self {
 			TempIndex::One => *self = TempIndex::Two,
@@ -185,14 +255,39 @@ impl TempIndex {
 	// given the path to the old database, get the path of this one.
 	fn path(&self, db_root: &Path) -> PathBuf {
 		let mut buf = db_root.to_owned();
+		buf.push(self.name());
+		buf
+	}
+}
 
-		match *self {
-			TempIndex::One => buf.push("temp_migration_1"),
-			TempIndex::Two => buf.push("temp_migration_2"),
-		};
+/// Returns the path of the file recording migration progress.
+fn progress_file_path(db_root: &Path) -> PathBuf {
+	let mut buf = db_root.to_owned();
+	buf.push(PROGRESS_FILE_NAME);
+	buf
+}
 
-		buf
+/// Reads the last completed migration step: the version reached and the temporary database holding it.
+fn read_progress(db_root: &Path) -> Option<(u32, TempIndex)> {
+	let mut s = String::new();
+	if File::open(progress_file_path(db_root)).and_then(|mut f| f.read_to_string(&mut s)).is_err() {
+		return None;
 	}
+
+	let mut parts = s.trim().split(' ');
+	let version = parts.next().and_then(|v| v.parse().ok());
+	let index = parts.next().and_then(TempIndex::from_name);
+	match (version, index) {
+		(Some(version), Some(index)) => Some((version, index)),
+		_ => None,
+	}
+}
+
+/// Records a completed migration step, so that an interrupted migration can be resumed.
+fn write_progress(db_root: &Path, version: u32, index: &TempIndex) -> Result<(), Error> {
+	let mut file = try!(File::create(progress_file_path(db_root)));
+	try!(file.write_all(format!("{} {}", version, index.name()).as_bytes()));
+	Ok(())
 }
 
 /// Manages database migration.
@@ -225,13 +320,33 @@ impl Manager {
 
 	/// Performs migration in order, starting with a source path, migrating between two temporary databases,
 	/// and producing a path where the final migration lives.
+	///
+	/// Every completed step is recorded in the databases root, so an interrupted migration
+	/// is resumed from the last completed step instead of starting over.
 	pub fn execute(&mut self, old_path: &Path, version: u32) -> Result<PathBuf, Error> {
 		let config = self.config.clone();
-		let migrations = self.migrations_from(version);
-		trace!(target: "migration", "Total migrations to execute for version {}: {}", version, migrations.len());
-		if migrations.is_empty() { return Err(Error::MigrationImpossible) };
+		let db_root = database_path(old_path);
+
+		// resume from the last completed step, if it is still part of this migration.
+		let resume = read_progress(&db_root)
+			.and_then(|(v, idx)| self.migrations.iter().find(|m| m.version() == v && v > version).map(|m| (v, idx, m.columns())))
+			.and_then(|(v, idx, columns)| match fs::metadata(idx.path(&db_root)).is_ok() {
+				true => Some((v, idx, columns)),
+				false => None,
+			});
+		if resume.is_none() {
+			let _ = fs::remove_file(progress_file_path(&db_root));
+		}
 
-		let columns = migrations.iter().nth(0).and_then(|m| m.pre_columns());
+		let start_version = resume.as_ref().map_or(version, |r| r.0);
+		let migrations = self.migrations_from(start_version);
+		trace!(target: "migration", "Total migrations to execute for version {}: {}", start_version, migrations.len());
+		if migrations.is_empty() && resume.is_none() { return Err(Error::MigrationImpossible) };
+
+		let columns = match resume {
+			Some((_, _, columns)) => columns,
+			None => migrations.iter().nth(0).and_then(|m| m.pre_columns()),
+		};
 
 		trace!(target: "migration", "Expecting database to contain {:?} columns", columns);
 		let mut db_config = DatabaseConfig {
@@ -240,23 +355,67 @@ impl Manager {
 			compaction: config.compaction_profile,
 			columns: columns,
 			wal: true,
+			wal_size_limit: DEFAULT_WAL_SIZE_LIMIT,
+			write_buffer_size: None,
 		};
 
-		let db_root = database_path(old_path);
-		let mut temp_idx = TempIndex::One;
-		let mut temp_path = temp_idx.path(&db_root);
+		// start with the old db, or with the temporary db of the last completed step.
+		let (mut temp_idx, source_path) = match resume {
+			Some((v, mut idx, _)) => {
+				info!(target: "migration", "Resuming interrupted database migration from version {}", v);
+				let path = idx.path(&db_root);
+				idx.swap();
+				(idx, path)
+			},
+			None => (TempIndex::One, old_path.to_owned()),
+		};
+		let mut temp_path = source_path.clone();
+
+		let source_path_str = try!(source_path.to_str().ok_or(Error::MigrationImpossible));
+		let cur_db = match Database::open(&db_config, source_path_str) {
+			Ok(db) => db,
+			Err(e) => match migrations.first() {
+				// an interrupted in-place migration may have added the columns already.
+				Some(m) if !m.alters_existing() => {
+					db_config.columns = m.columns();
+					try!(Database::open(&db_config, source_path_str).map_err(|_| Error::Custom(e)))
+				},
+				_ => return Err(Error::Custom(e)),
+			},
+		};
+		let mut cur_db = Arc::new(cur_db);
 
-		// start with the old db.
-		let old_path_str = try!(old_path.to_str().ok_or(Error::MigrationImpossible));
-		let mut cur_db = Arc::new(try!(Database::open(&db_config, old_path_str).map_err(Error::Custom)));
+		let total = migrations.len();
+		for (i, migration) in migrations.into_iter().enumerate() {
+			info!(target: "migration", "Migrating database to version {} ({} of {})", migration.version(), i + 1, total);
+
+			if !migration.alters_existing() {
+				let columns = migration.columns().unwrap_or(0);
+				if columns < cur_db.num_columns() {
+					return Err(Error::MigrationImpossible);
+				}
+				while cur_db.num_columns() < columns {
+					try!(cur_db.add_column().map_err(Error::Custom));
+				}
+				db_config.columns = migration.columns();
+				try!(write_version(&cur_db, migration.version()));
+
+				// the original database is not a completed step to resume from.
+				if temp_path != old_path {
+					let mut cur_idx = temp_idx;
+					cur_idx.swap();
+					try!(write_progress(&db_root, migration.version(), &cur_idx));
+				}
+				continue;
+			}
 
-		for migration in migrations {
 			// Change number of columns in new db
 			let current_columns = db_config.columns;
 			db_config.columns = migration.columns();
 
-			// open the target temporary database.
+			// open the target temporary database, discarding any leftovers of an interrupted step.
 			temp_path = temp_idx.path(&db_root);
+			let _ = fs::remove_dir_all(&temp_path);
 			let temp_path_str = try!(temp_path.to_str().ok_or(Error::MigrationImpossible));
 			let mut new_db = try!(Database::open(&db_config, temp_path_str).map_err(Error::Custom));
 
@@ -271,6 +430,11 @@ impl Manager {
 					}
 				}
 			}
+
+			// record the version in the migrated db and the completed step in the progress file.
+			try!(write_version(&new_db, migration.version()));
+			try!(write_progress(&db_root, migration.version(), &temp_idx));
+
 			// next iteration, we will migrate from this db into the other temp.
 			cur_db = Arc::new(new_db);
 			temp_idx.swap();
@@ -278,6 +442,8 @@ impl Manager {
 			// remove the other temporary migration database.
 			let _ = fs::remove_dir_all(temp_idx.path(&db_root));
 		}
+
+		let _ = fs::remove_file(progress_file_path(&db_root));
 		Ok(temp_path)
 	}
 
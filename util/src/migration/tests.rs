@@ -19,8 +19,8 @@
 //! are performed in temp sub-directories.
 
 use common::*;
-use migration::{Batch, Config, Error, SimpleMigration, Migration, Manager};
-use kvdb::Database;
+use migration::{Batch, Config, Error, SimpleMigration, Migration, Manager, ChangeColumns, TempIndex, read_version, read_progress, write_progress};
+use kvdb::{Database, DatabaseConfig};
 
 use devtools::RandomTempPath;
 use std::path::PathBuf;
@@ -226,3 +226,62 @@ fn pre_columns() {
 	// short of the one before it.
 	manager.execute(&db_path, 0).unwrap();
 }
+
+#[test]
+fn records_version() {
+	let dir = RandomTempPath::create_dir();
+	let db_path = db_path(dir.as_path());
+	let mut manager = Manager::new(Config::default());
+	make_db(&db_path, map![vec![] => vec![], vec![1] => vec![1]]);
+
+	manager.add_migration(Migration0).unwrap();
+	manager.add_migration(Migration1).unwrap();
+	let end_path = manager.execute(&db_path, 0).unwrap();
+
+	let db = Database::open_default(end_path.to_str().unwrap()).unwrap();
+	assert_eq!(read_version(&db).unwrap(), Some(2));
+	// the version key itself is not migrated as a regular entry.
+	assert_eq!(db.iter(None).count(), 3);
+}
+
+#[test]
+fn adds_columns_in_place() {
+	let dir = RandomTempPath::create_dir();
+	let db_path = db_path(dir.as_path());
+	make_db(&db_path, map![vec![] => vec![], vec![1] => vec![1]]);
+
+	let mut manager = Manager::new(Config::default());
+	manager.add_migration(ChangeColumns { pre_columns: None, post_columns: Some(2), version: 1 }).unwrap();
+	let end_path = manager.execute(&db_path, 0).unwrap();
+	assert_eq!(end_path, db_path);
+
+	let db = Database::open(&DatabaseConfig::with_columns(Some(2)), end_path.to_str().unwrap()).unwrap();
+	assert_eq!(db.num_columns(), 2);
+	assert_eq!(read_version(&db).unwrap(), Some(1));
+	assert_eq!(&*db.get(None, &[1]).unwrap().unwrap(), &[1u8][..]);
+}
+
+#[test]
+fn resumes_interrupted_migration() {
+	let dir = RandomTempPath::create_dir();
+	let db_path = db_path(dir.as_path());
+	make_db(&db_path, map![vec![] => vec![], vec![1] => vec![1]]);
+
+	// perform the first step and pretend we were interrupted right after it.
+	let mut manager = Manager::new(Config::default());
+	manager.add_migration(Migration0).unwrap();
+	manager.execute(&db_path, 0).unwrap();
+	write_progress(dir.as_path(), 1, &TempIndex::One).unwrap();
+
+	// the original database is no longer needed to finish the migration.
+	::std::fs::remove_dir_all(&db_path).unwrap();
+
+	let mut manager = Manager::new(Config::default());
+	manager.add_migration(Migration0).unwrap();
+	manager.add_migration(Migration1).unwrap();
+	let end_path = manager.execute(&db_path, 0).unwrap();
+
+	let expected = map![vec![0x11] => vec![], vec![1, 0x11] => vec![]];
+	verify_migration(&end_path, expected);
+	assert!(read_progress(dir.as_path()).is_none());
+}
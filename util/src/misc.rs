@@ -43,6 +43,11 @@ pub fn version() -> String {
 	format!("Parity/v{}-unstable{}{}{}{}/{}-{}{}{}/rustc{}", env!("CARGO_PKG_VERSION"), sha3_dash, sha3, date_dash, commit_date, Target::arch(), Target::os(), env_dash, env, rustc_version())
 }
 
+/// Get the short version string for this software, e.g. `Parity/v1.4.0`.
+pub fn short_version() -> String {
+	format!("Parity/v{}", env!("CARGO_PKG_VERSION"))
+}
+
 /// Get the standard version data for this software.
 pub fn version_data() -> Bytes {
 	let mut s = RlpStream::new_list(4);
@@ -0,0 +1,286 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Persistent journal of node lifecycle events.
+//!
+//! Each event is stored as a single line `timestamp<TAB>kind<TAB>details`,
+//! with tabs, newlines and backslashes in details escaped.
+
+use std::{fs, io, panic, thread};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::fmt;
+use std::str::FromStr;
+use parking_lot::RwLock;
+use backtrace::Backtrace;
+use time;
+
+/// Maximal number of events kept in the journal. Older events are dropped.
+const MAX_EVENTS: usize = 1024;
+
+lazy_static! {
+	static ref JOURNAL: RwLock<Option<Arc<NodeEvents>>> = RwLock::new(None);
+}
+
+/// Kind of node lifecycle event.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NodeEventKind {
+	/// Node started.
+	Start,
+	/// Node shut down cleanly.
+	Shutdown,
+	/// Previous run ended without a clean shutdown.
+	UncleanShutdown,
+	/// Corrupted database was repaired.
+	DatabaseRepair,
+	/// Fatal error, including the backtrace.
+	Fatal,
+}
+
+impl fmt::Display for NodeEventKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let s = match *self {
+			NodeEventKind::Start => "start",
+			NodeEventKind::Shutdown => "shutdown",
+			NodeEventKind::UncleanShutdown => "unclean-shutdown",
+			NodeEventKind::DatabaseRepair => "database-repair",
+			NodeEventKind::Fatal => "fatal",
+		};
+		write!(f, "{}", s)
+	}
+}
+
+impl FromStr for NodeEventKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"start" => Ok(NodeEventKind::Start),
+			"shutdown" => Ok(NodeEventKind::Shutdown),
+			"unclean-shutdown" => Ok(NodeEventKind::UncleanShutdown),
+			"database-repair" => Ok(NodeEventKind::DatabaseRepair),
+			"fatal" => Ok(NodeEventKind::Fatal),
+			other => Err(format!("Invalid node event kind: {}", other)),
+		}
+	}
+}
+
+/// Single journal entry.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NodeEvent {
+	/// Unix timestamp (in seconds) when the event happened.
+	pub timestamp: u64,
+	/// Kind of the event.
+	pub kind: NodeEventKind,
+	/// Free-form details.
+	pub details: String,
+}
+
+impl NodeEvent {
+	fn to_line(&self) -> String {
+		let details = self.details.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n");
+		format!("{}\t{}\t{}\n", self.timestamp, self.kind, details)
+	}
+
+	fn from_line(line: &str) -> Option<Self> {
+		let mut parts = line.splitn(3, '\t');
+		let timestamp = match parts.next().and_then(|t| t.parse().ok()) {
+			Some(t) => t,
+			None => return None,
+		};
+		let kind = match parts.next().and_then(|k| k.parse().ok()) {
+			Some(k) => k,
+			None => return None,
+		};
+		Some(NodeEvent {
+			timestamp: timestamp,
+			kind: kind,
+			details: unescape(parts.next().unwrap_or("")),
+		})
+	}
+}
+
+fn unescape(s: &str) -> String {
+	let mut result = String::with_capacity(s.len());
+	let mut chars = s.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			result.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('t') => result.push('\t'),
+			Some('n') => result.push('\n'),
+			Some(other) => result.push(other),
+			None => result.push('\\'),
+		}
+	}
+	result
+}
+
+/// File-backed journal of node events.
+pub struct NodeEvents {
+	path: PathBuf,
+	events: RwLock<Vec<NodeEvent>>,
+}
+
+impl NodeEvents {
+	/// Opens the journal at given path, creating it if necessary.
+	///
+	/// If the previous run did not record a shutdown, an `UncleanShutdown` event is added.
+	pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+		let path = path.as_ref().to_path_buf();
+		let mut events = Vec::new();
+		match fs::File::open(&path) {
+			Ok(file) => {
+				for line in BufReader::new(file).lines() {
+					if let Some(event) = NodeEvent::from_line(&try!(line)) {
+						events.push(event);
+					}
+				}
+			},
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => {},
+			Err(e) => return Err(e),
+		}
+
+		let truncated = events.len() > MAX_EVENTS;
+		if truncated {
+			let excess = events.len() - MAX_EVENTS;
+			events.drain(..excess);
+			let mut file = try!(fs::File::create(&path));
+			for event in &events {
+				try!(file.write_all(event.to_line().as_bytes()));
+			}
+		}
+
+		let running = events.iter().rev()
+			.find(|e| e.kind == NodeEventKind::Start || e.kind == NodeEventKind::Shutdown || e.kind == NodeEventKind::UncleanShutdown)
+			.map_or(false, |e| e.kind == NodeEventKind::Start);
+
+		let journal = NodeEvents {
+			path: path,
+			events: RwLock::new(events),
+		};
+
+		if running {
+			try!(journal.record(NodeEventKind::UncleanShutdown, "Previous run did not shut down cleanly."));
+		}
+		Ok(journal)
+	}
+
+	/// Appends new event to the journal.
+	pub fn record(&self, kind: NodeEventKind, details: &str) -> io::Result<()> {
+		let event = NodeEvent {
+			timestamp: time::get_time().sec as u64,
+			kind: kind,
+			details: details.to_owned(),
+		};
+
+		let mut file = try!(fs::OpenOptions::new().create(true).append(true).open(&self.path));
+		try!(file.write_all(event.to_line().as_bytes()));
+
+		let mut events = self.events.write();
+		if events.len() == MAX_EVENTS {
+			events.remove(0);
+		}
+		events.push(event);
+		Ok(())
+	}
+
+	/// Returns all events, oldest first.
+	pub fn events(&self) -> Vec<NodeEvent> {
+		self.events.read().clone()
+	}
+}
+
+/// Sets the process-wide journal used by `record_event`.
+pub fn set_journal(journal: Arc<NodeEvents>) {
+	*JOURNAL.write() = Some(journal);
+}
+
+/// Returns the process-wide journal, if set.
+pub fn journal() -> Option<Arc<NodeEvents>> {
+	JOURNAL.read().clone()
+}
+
+/// Records an event in the process-wide journal. Does nothing if no journal was set.
+pub fn record_event(kind: NodeEventKind, details: &str) {
+	if let Some(journal) = journal() {
+		if let Err(e) = journal.record(kind, details) {
+			warn!("Could not write node event: {}", e);
+		}
+	}
+}
+
+/// Installs a panic hook recording every panic together with its backtrace as a `Fatal` event.
+/// The previously installed hook is still invoked.
+pub fn record_panics() {
+	let previous = panic::take_hook();
+	panic::set_hook(Box::new(move |info| {
+		let message = match info.payload().downcast_ref::<&'static str>() {
+			Some(s) => *s,
+			None => match info.payload().downcast_ref::<String>() {
+				Some(s) => &s[..],
+				None => "Box<Any>",
+			},
+		};
+		let location = info.location().map_or_else(String::new, |l| format!("{}:{}", l.file(), l.line()));
+		let details = format!("Thread '{}' panicked at '{}', {}\n{:?}",
+			thread::current().name().unwrap_or("<unnamed>"),
+			message,
+			location,
+			Backtrace::new());
+		record_event(NodeEventKind::Fatal, &details);
+		previous(info);
+	}));
+}
+
+#[cfg(test)]
+mod tests {
+	use devtools::RandomTempPath;
+	use super::{NodeEvents, NodeEventKind};
+
+	#[test]
+	fn should_persist_events() {
+		let path = RandomTempPath::new();
+		{
+			let journal = NodeEvents::open(path.as_path()).unwrap();
+			journal.record(NodeEventKind::Start, "").unwrap();
+			journal.record(NodeEventKind::Fatal, "line\twith\ntabs\\").unwrap();
+			journal.record(NodeEventKind::Shutdown, "").unwrap();
+		}
+
+		let journal = NodeEvents::open(path.as_path()).unwrap();
+		let events = journal.events();
+		assert_eq!(events.len(), 3);
+		assert_eq!(events[1].kind, NodeEventKind::Fatal);
+		assert_eq!(events[1].details, "line\twith\ntabs\\");
+	}
+
+	#[test]
+	fn should_detect_unclean_shutdown() {
+		let path = RandomTempPath::new();
+		{
+			let journal = NodeEvents::open(path.as_path()).unwrap();
+			journal.record(NodeEventKind::Start, "").unwrap();
+		}
+
+		let journal = NodeEvents::open(path.as_path()).unwrap();
+		let kinds: Vec<_> = journal.events().into_iter().map(|e| e.kind).collect();
+		assert_eq!(kinds, vec![NodeEventKind::Start, NodeEventKind::UncleanShutdown]);
+	}
+}
@@ -17,7 +17,8 @@
 use hash::H256;
 use sha3::Hashable;
 use hashdb::HashDB;
-use super::{TrieDB, Trie, TrieDBIterator, TrieItem, Recorder};
+use super::{TrieDB, Trie, TrieItem, Recorder};
+use super::sectriedb::SecTrieDBIterator;
 
 /// A `Trie` implementation which hashes keys and uses a generic `HashDB` backing database.
 /// Additionaly it stores inserted hash-key mappings for later retrieval.
@@ -43,11 +44,17 @@ impl<'db> FatDB<'db> {
 	pub fn db(&self) -> &HashDB {
 		self.raw.db()
 	}
+
+	/// Iterate over the items with their original keys, in the order of the key hashes.
+	/// Unlike `iter`, the returned iterator can `seek`.
+	pub fn iter_preimages(&self) -> super::Result<FatDBIterator> {
+		SecTrieDBIterator::new(&self.raw)
+	}
 }
 
 impl<'db> Trie for FatDB<'db> {
 	fn iter<'a>(&'a self) -> super::Result<Box<Iterator<Item = TrieItem> + 'a>> {
-		FatDBIterator::new(&self.raw).map(|iter| Box::new(iter) as Box<_>)
+		SecTrieDBIterator::new(&self.raw).map(|iter| Box::new(iter) as Box<_>)
 	}
 
 	fn root(&self) -> &H256 {
@@ -65,34 +72,8 @@ impl<'db> Trie for FatDB<'db> {
 	}
 }
 
-/// Itarator over inserted pairs of key values.
-pub struct FatDBIterator<'db> {
-	trie_iterator: TrieDBIterator<'db>,
-	trie: &'db TrieDB<'db>,
-}
-
-impl<'db> FatDBIterator<'db> {
-	/// Creates new iterator.
-	pub fn new(trie: &'db TrieDB) -> super::Result<Self> {
-		Ok(FatDBIterator {
-			trie_iterator: try!(TrieDBIterator::new(trie)),
-			trie: trie,
-		})
-	}
-}
-
-impl<'db> Iterator for FatDBIterator<'db> {
-	type Item = TrieItem<'db>;
-
-	fn next(&mut self) -> Option<Self::Item> {
-		self.trie_iterator.next()
-			.map(|res|
-				res.map(|(hash, value)| {
-					(self.trie.db().get_aux(&hash).expect("Missing fatdb hash"), value)
-				})
-			)
-	}
-}
+/// Iterator over inserted pairs of key values.
+pub type FatDBIterator<'db> = SecTrieDBIterator<'db>;
 
 #[test]
 fn fatdb_to_trie() {
@@ -109,3 +90,31 @@ fn fatdb_to_trie() {
 	assert_eq!(t.get(&[0x01u8, 0x23]).unwrap().unwrap(), &[0x01u8, 0x23]);
 	assert_eq!(t.iter().unwrap().map(Result::unwrap).collect::<Vec<_>>(), vec![(vec![0x01u8, 0x23], &[0x01u8, 0x23] as &[u8])]);
 }
+
+#[test]
+fn fatdb_iterator_seek() {
+	use memorydb::MemoryDB;
+	use sha3::Hashable;
+	use trie::{FatDBMut, TrieMut};
+
+	let mut memdb = MemoryDB::new();
+	let mut root = H256::default();
+	let keys: Vec<Vec<u8>> = (0u8..20).map(|i| vec![i; 20]).collect();
+	{
+		let mut t = FatDBMut::new(&mut memdb, &mut root);
+		for key in &keys {
+			t.insert(key, &[1]).unwrap();
+		}
+	}
+
+	// items come ordered by key hash
+	let mut sorted = keys.clone();
+	sorted.sort_by_key(|key| key.sha3());
+
+	let t = FatDB::new(&memdb, &root).unwrap();
+	assert_eq!(t.iter().unwrap().map(|x| x.unwrap().0).collect::<Vec<_>>(), sorted);
+
+	let mut iter = t.iter_preimages().unwrap();
+	iter.seek(&sorted[7]).unwrap();
+	assert_eq!(iter.map(|x| x.unwrap().0).collect::<Vec<_>>(), &sorted[7..]);
+}
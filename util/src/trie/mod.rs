@@ -36,6 +36,8 @@ pub mod sectriedb;
 pub mod sectriedbmut;
 /// Trie query recording.
 pub mod recorder;
+/// Trie lookup proofs.
+pub mod proof;
 
 
 mod fatdb;
@@ -45,10 +47,11 @@ pub use self::standardmap::{Alphabet, StandardMap, ValueMode};
 pub use self::triedbmut::TrieDBMut;
 pub use self::triedb::{TrieDB, TrieDBIterator};
 pub use self::sectriedbmut::SecTrieDBMut;
-pub use self::sectriedb::SecTrieDB;
+pub use self::sectriedb::{SecTrieDB, SecTrieDBIterator};
 pub use self::fatdb::{FatDB, FatDBIterator};
 pub use self::fatdbmut::FatDBMut;
 pub use self::recorder::Recorder;
+pub use self::proof::{verify_proof, ProofError};
 
 /// Trie Errors.
 ///
@@ -0,0 +1,212 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of trie lookup proofs.
+//!
+//! A proof is the RLP of every node visited while looking a key up, as produced by
+//! `TrieDB::get_with_proof`. It shows the value of the key, or its absence, to anyone knowing
+//! only the trie root.
+
+use std::fmt;
+use std::collections::HashMap;
+use hash::H256;
+use sha3::Hashable;
+use nibbleslice::NibbleSlice;
+use rlp::{UntrustedRlp, View, Prototype};
+use Bytes;
+use super::node::Node;
+
+/// Reasons for rejecting a proof.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ProofError {
+	/// The proof doesn't contain a node on the path to the key: either the root
+	/// or a node referenced by another node of the proof.
+	MissingNode(H256),
+	/// A node of the proof, or a node inlined in it, isn't a valid trie node.
+	InvalidNode(H256),
+}
+
+impl fmt::Display for ProofError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ProofError::MissingNode(ref hash) => write!(f, "Proof is missing node: {}", hash),
+			ProofError::InvalidNode(ref hash) => write!(f, "Proof contains invalid node: {}", hash),
+		}
+	}
+}
+
+/// Verify the proof of `key` against the trie `root`. Returns the value of the key,
+/// or `None` if the proof shows that the key isn't in the trie.
+///
+/// Keys of secure tries must be hashed before being passed here.
+pub fn verify_proof(root: &H256, key: &[u8], proof: &[Bytes]) -> Result<Option<Bytes>, ProofError> {
+	let nodes: HashMap<H256, &[u8]> = proof.iter().map(|node| (node.sha3(), &node[..])).collect();
+	let mut key = NibbleSlice::new(key);
+	let mut node = try!(nodes.get(root).cloned().ok_or(ProofError::MissingNode(*root)));
+
+	loop {
+		let child = match try!(decode_node(node).ok_or_else(|| ProofError::InvalidNode(node.sha3()))) {
+			Node::Leaf(ref slice, value) => return Ok(match key == *slice {
+				true => Some(value.to_vec()),
+				false => None,
+			}),
+			Node::Extension(ref slice, child) if key.starts_with(slice) => {
+				key = key.mid(slice.len());
+				child
+			},
+			Node::Branch(ref children, value) => match key.is_empty() {
+				true => return Ok(value.map(|value| value.to_vec())),
+				false => {
+					let child = children[key.at(0) as usize];
+					key = key.mid(1);
+					child
+				},
+			},
+			_ => return Ok(None),
+		};
+
+		// children are either referenced by hash or inlined
+		let rlp = UntrustedRlp::new(child);
+		node = match rlp.is_data() && rlp.size() == 32 {
+			true => {
+				let hash: H256 = try!(rlp.as_val().map_err(|_| ProofError::InvalidNode(child.sha3())));
+				try!(nodes.get(&hash).cloned().ok_or(ProofError::MissingNode(hash)))
+			},
+			false => child,
+		};
+	}
+}
+
+/// Decode a node which can't be trusted to be valid RLP.
+fn decode_node(data: &[u8]) -> Option<Node> {
+	let rlp = UntrustedRlp::new(data);
+	match rlp.prototype() {
+		Ok(Prototype::List(2)) => {
+			let partial = match rlp.at(0).and_then(|partial| partial.data()) {
+				Ok(partial) if !partial.is_empty() => partial,
+				_ => return None,
+			};
+			let item = match rlp.at(1) {
+				Ok(item) => item,
+				Err(_) => return None,
+			};
+			match NibbleSlice::from_encoded(partial) {
+				(slice, true) => item.data().ok().map(|value| Node::Leaf(slice, value)),
+				(slice, false) => Some(Node::Extension(slice, item.as_raw())),
+			}
+		},
+		Ok(Prototype::List(17)) => {
+			let mut children: [&[u8]; 16] = [&[], &[], &[], &[], &[], &[], &[], &[], &[], &[], &[], &[], &[], &[], &[], &[]];
+			for i in 0..16 {
+				children[i] = match rlp.at(i) {
+					Ok(child) => child.as_raw(),
+					Err(_) => return None,
+				};
+			}
+			match rlp.at(16).and_then(|value| value.data()) {
+				Ok(value) => Some(Node::Branch(children, if value.is_empty() { None } else { Some(value) })),
+				Err(_) => None,
+			}
+		},
+		Ok(Prototype::Data(0)) => Some(Node::Empty),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use hash::H256;
+	use memorydb::MemoryDB;
+	use sha3::Hashable;
+	use trie::{TrieDB, TrieDBMut, TrieMut, StandardMap, Alphabet, ValueMode};
+	use super::{verify_proof, ProofError};
+
+	fn random_trie(seed: &mut H256, count: usize, db: &mut MemoryDB) -> (H256, HashMap<Vec<u8>, Vec<u8>>) {
+		let entries: HashMap<_, _> = StandardMap {
+			alphabet: Alphabet::All,
+			min_key: 1,
+			journal_key: 31,
+			value_mode: ValueMode::Random,
+			count: count,
+		}.make_with(seed).into_iter().collect();
+
+		let mut root = H256::new();
+		{
+			let mut trie = TrieDBMut::new(db, &mut root);
+			for (key, value) in &entries {
+				trie.insert(key, value).unwrap();
+			}
+		}
+		(root, entries)
+	}
+
+	#[test]
+	fn should_verify_proofs_of_random_tries() {
+		let mut seed = H256::new();
+		for count in &[1, 2, 10, 100, 500] {
+			let mut db = MemoryDB::new();
+			let (root, entries) = random_trie(&mut seed, *count, &mut db);
+			let trie = TrieDB::new(&db, &root).unwrap();
+
+			for (key, value) in &entries {
+				let (found, proof) = trie.get_with_proof(key).unwrap();
+				assert_eq!(found, Some(&value[..]));
+				assert_eq!(verify_proof(&root, key, &proof), Ok(Some(value.clone())));
+			}
+
+			for _ in 0..100 {
+				seed = seed.sha3();
+				let key = &seed[..(seed[0] as usize % 32) + 1];
+				let (found, proof) = trie.get_with_proof(key).unwrap();
+				let expected = entries.get(key).cloned();
+				assert_eq!(found.map(|v| v.to_vec()), expected);
+				assert_eq!(verify_proof(&root, key, &proof), Ok(expected));
+			}
+		}
+	}
+
+	#[test]
+	fn should_reject_incomplete_or_altered_proofs() {
+		let mut seed = H256::new();
+		let mut db = MemoryDB::new();
+		let (root, entries) = random_trie(&mut seed, 100, &mut db);
+		let trie = TrieDB::new(&db, &root).unwrap();
+
+		for key in entries.keys() {
+			let (_, proof) = trie.get_with_proof(key).unwrap();
+
+			assert_eq!(verify_proof(&root, key, &[]), Err(ProofError::MissingNode(root)));
+			if proof.len() > 1 {
+				let missing = proof.last().unwrap().sha3();
+				assert_eq!(verify_proof(&root, key, &proof[..proof.len() - 1]), Err(ProofError::MissingNode(missing)));
+			}
+
+			let mut altered = proof.clone();
+			let last = altered.last_mut().unwrap();
+			let end = last.len() - 1;
+			last[end] ^= 1;
+			assert!(verify_proof(&root, key, &altered).is_err());
+		}
+	}
+
+	#[test]
+	fn should_reject_invalid_node() {
+		let node = vec![0xc2, 0x01];
+		let root = node.sha3();
+		assert_eq!(verify_proof(&root, b"key", &[node]), Err(ProofError::InvalidNode(root)));
+	}
+}
@@ -17,8 +17,8 @@
 use hash::H256;
 use sha3::Hashable;
 use hashdb::HashDB;
-use super::triedb::TrieDB;
-use super::{Trie, TrieItem, Recorder};
+use super::triedb::{TrieDB, TrieDBIterator};
+use super::{Trie, TrieItem, TrieError, Recorder};
 
 /// A `Trie` implementation which hashes keys and uses a generic `HashDB` backing database.
 ///
@@ -46,6 +46,49 @@ impl<'db> SecTrieDB<'db> {
 	pub fn raw_mut(&mut self) -> &mut TrieDB<'db> {
 		&mut self.raw
 	}
+
+	/// Iterate over the items with their original keys. Requires the key preimages
+	/// stored in the database by `FatDBMut`.
+	pub fn iter_preimages(&self) -> super::Result<SecTrieDBIterator> {
+		SecTrieDBIterator::new(&self.raw)
+	}
+}
+
+/// Iterator over the items of a secure trie, resolving the hashed keys to the original ones
+/// through the preimages stored in the database by `FatDBMut`.
+///
+/// Items come in the order of the trie, which is the order of the key hashes.
+pub struct SecTrieDBIterator<'db> {
+	trie_iterator: TrieDBIterator<'db>,
+	db: &'db HashDB,
+}
+
+impl<'db> SecTrieDBIterator<'db> {
+	/// Create a new iterator over the raw trie underlying a secure trie.
+	pub fn new(trie: &'db TrieDB) -> super::Result<Self> {
+		Ok(SecTrieDBIterator {
+			trie_iterator: try!(TrieDBIterator::new(trie)),
+			db: trie.db(),
+		})
+	}
+
+	/// Position the iterator on `key`, or on the item following it in the trie if there is no such key.
+	pub fn seek(&mut self, key: &[u8]) -> super::Result<()> {
+		self.trie_iterator.seek(&key.sha3())
+	}
+}
+
+impl<'db> Iterator for SecTrieDBIterator<'db> {
+	type Item = TrieItem<'db>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.trie_iterator.next().map(|item| item.and_then(|(hash, value)| {
+			match self.db.get_aux(&hash) {
+				Some(key) => Ok((key, value)),
+				None => Err(Box::new(TrieError::IncompleteDatabase(H256::from_slice(&hash)))),
+			}
+		}))
+	}
 }
 
 impl<'db> Trie for SecTrieDB<'db> {
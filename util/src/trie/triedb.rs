@@ -19,7 +19,7 @@ use hashdb::*;
 use nibbleslice::*;
 use rlp::*;
 use super::node::Node;
-use super::recorder::{Recorder, NoOp};
+use super::recorder::{Recorder, NoOp, BasicRecorder};
 use super::{Trie, TrieItem, TrieError};
 
 /// A `Trie` implementation using a generic `HashDB` backing database.
@@ -76,6 +76,15 @@ impl<'db> TrieDB<'db> {
 		self.db
 	}
 
+	/// Get the value of `key` along with the proof of it: the RLP of every node visited
+	/// by the lookup, root first. The proof also shows the absence of a key.
+	/// It can be checked against the trie root with `verify_proof`.
+	pub fn get_with_proof<'a, 'key>(&'a self, key: &'key [u8]) -> super::Result<(Option<&'a [u8]>, Vec<Bytes>)> where 'a: 'key {
+		let mut recorder = BasicRecorder::new();
+		let value = try!(self.get_recorded(key, &mut recorder));
+		Ok((value, recorder.drain().into_iter().map(|record| record.data).collect()))
+	}
+
 	/// Determine all the keys in the backing database that belong to the trie.
 	pub fn keys(&self) -> super::Result<Vec<H256>> {
 		let mut ret: Vec<H256> = Vec::new();
@@ -290,6 +299,66 @@ impl<'a> TrieDBIterator<'a> {
 		Ok(r)
 	}
 
+	/// Position the iterator so that the next item is the first one with a key
+	/// greater than or equal to `key`.
+	pub fn seek(&mut self, key: &[u8]) -> super::Result<()> {
+		self.trail.clear();
+		self.key_nibbles.clear();
+		let root = try!(self.db.root_data(&mut NoOp));
+		self.seek_descend(root, NibbleSlice::new(key))
+	}
+
+	/// Descend along `key`, leaving every node on the path in the state following
+	/// the items which sort before `key`.
+	fn seek_descend(&mut self, mut data: &'a [u8], mut key: NibbleSlice) -> super::Result<()> {
+		loop {
+			let node = try!(self.db.get_node(data, &mut NoOp, 0));
+			let (status, child) = match node {
+				Node::Leaf(ref slice, _) => match slice.iter().cmp(key.iter()) {
+					cmp::Ordering::Less => (Status::At, None),
+					_ => (Status::Entering, None),
+				},
+				Node::Extension(ref slice, child) => match key.starts_with(slice) {
+					true => (Status::At, Some((child, slice.len()))),
+					false => match slice.iter().cmp(key.iter()) {
+						cmp::Ordering::Less => (Status::At, None),
+						_ => (Status::Entering, None),
+					},
+				},
+				Node::Branch(ref children, _) => match key.is_empty() {
+					true => (Status::Entering, None),
+					false => {
+						let i = key.at(0) as usize;
+						let child = match children[i].len() > 0 {
+							true => Some((children[i], 1)),
+							false => None,
+						};
+						(Status::AtChild(i), child)
+					},
+				},
+				Node::Empty => return Ok(()),
+			};
+
+			match (&node, &status) {
+				(&Node::Leaf(ref slice, _), _) | (&Node::Extension(ref slice, _), _) => self.key_nibbles.extend(slice.iter()),
+				(&Node::Branch(..), &Status::AtChild(i)) => self.key_nibbles.push(i as u8),
+				_ => {},
+			}
+			self.trail.push(Crumb {
+				status: status,
+				node: node,
+			});
+
+			match child {
+				Some((child, skip)) => {
+					data = child;
+					key = key.mid(skip);
+				},
+				None => return Ok(()),
+			}
+		}
+	}
+
 	/// Descend into a payload.
 	fn descend(&mut self, d: &'a [u8]) -> super::Result<()> {
 		self.trail.push(Crumb {
@@ -403,3 +472,37 @@ fn iterator() {
 	assert_eq!(d.iter().map(|i|i.to_vec()).collect::<Vec<_>>(), t.iter().unwrap().map(|x| x.unwrap().0).collect::<Vec<_>>());
 	assert_eq!(d, t.iter().unwrap().map(|x| x.unwrap().1).collect::<Vec<_>>());
 }
+
+#[test]
+fn iterator_seek() {
+	use memorydb::*;
+	use super::TrieMut;
+	use super::triedbmut::*;
+
+	let d = vec![ &b"A"[..], &b"AA"[..], &b"AB"[..], &b"B"[..] ];
+
+	let mut memdb = MemoryDB::new();
+	let mut root = H256::new();
+	{
+		let mut t = TrieDBMut::new(&mut memdb, &mut root);
+		for x in &d {
+			t.insert(x, x).unwrap();
+		}
+	}
+
+	let t = TrieDB::new(&memdb, &root).unwrap();
+	let mut iter = TrieDBIterator::new(&t).unwrap();
+	let keys_from = |iter: &mut TrieDBIterator, key: &[u8]| {
+		iter.seek(key).unwrap();
+		iter.map(|x| x.unwrap().0).collect::<Vec<_>>()
+	};
+
+	assert_eq!(keys_from(&mut iter, b""), vec![b"A".to_vec(), b"AA".to_vec(), b"AB".to_vec(), b"B".to_vec()]);
+	assert_eq!(keys_from(&mut iter, b"A"), vec![b"A".to_vec(), b"AA".to_vec(), b"AB".to_vec(), b"B".to_vec()]);
+	assert_eq!(keys_from(&mut iter, b"AA"), vec![b"AA".to_vec(), b"AB".to_vec(), b"B".to_vec()]);
+	assert_eq!(keys_from(&mut iter, b"AAA"), vec![b"AB".to_vec(), b"B".to_vec()]);
+	assert_eq!(keys_from(&mut iter, b"AB"), vec![b"AB".to_vec(), b"B".to_vec()]);
+	assert_eq!(keys_from(&mut iter, b"AC"), vec![b"B".to_vec()]);
+	assert_eq!(keys_from(&mut iter, b"B"), vec![b"B".to_vec()]);
+	assert!(keys_from(&mut iter, b"C").is_empty());
+}
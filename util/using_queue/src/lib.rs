@@ -52,6 +52,12 @@ impl<T> UsingQueue<T> where T: Clone {
 		self.pending.as_ref().or(self.in_use.last())
 	}
 
+	/// Return a mutable reference to the item at the top of the queue (or `None` if the queue is empty);
+	/// it doesn't constitute noting that the item is used.
+	pub fn peek_last_mut(&mut self) -> Option<&mut T> {
+		self.pending.as_mut().or(self.in_use.last_mut())
+	}
+
 	/// Return a reference to the item at the top of the queue (or `None` if the queue is empty);
 	/// this constitutes using the item and will remain in the queue for at least another
 	/// `max_size` invocations of `push()`.
@@ -163,6 +169,16 @@ fn should_find_when_pushed_and_used_with_clone() {
 	assert!(q.clone_used_if(|i| i == &1).unwrap() == 1);
 }
 
+#[test]
+fn should_peek_last_mut_without_using() {
+	let mut q = UsingQueue::new(2);
+	q.push(1);
+	*q.peek_last_mut().unwrap() = 2;
+	assert!(q.clone_used_if(|i| i == &2).is_none());
+	q.use_last_ref();
+	assert!(q.clone_used_if(|i| i == &2).unwrap() == 2);
+}
+
 #[test]
 fn should_not_find_again_when_pushed_and_taken() {
 	let mut q = UsingQueue::new(2);